@@ -0,0 +1,130 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use ditto_cot::cot_events::{CotEvent, Point};
+use ditto_cot::ditto::{cot_event_from_ditto_document, cot_to_document};
+use ditto_cot::xml_utils::semantic_xml_eq;
+use libfuzzer_sys::fuzz_target;
+
+/// One `<tag attr="value"/>` detail child drawn from a small, always-valid
+/// vocabulary. Kept as a closed set (rather than arbitrary strings) so the
+/// assembled `<detail>` is guaranteed well-formed XML; picking the *same*
+/// tag more than once is how this target exercises duplicate detail
+/// children of the same tag (see `detail_parser`'s handling of repeats).
+#[derive(Debug, Arbitrary)]
+enum DetailChild {
+    Contact,
+    Group,
+    Remarks,
+}
+
+impl DetailChild {
+    fn to_xml(&self) -> &'static str {
+        match self {
+            DetailChild::Contact => "<contact callsign=\"ALPHA-1\"/>",
+            DetailChild::Group => "<__group name=\"Cyan\"/>",
+            DetailChild::Remarks => "<remarks>fuzz</remarks>",
+        }
+    }
+}
+
+/// A float that's occasionally pinned to one of the CoT "sentinel" values
+/// (e.g. the `999999.0`/`9999999.0` whole-number accuracy/stale sentinels)
+/// instead of always being a fresh arbitrary value, so `format_cot_float`'s
+/// whole-number-vs-fractional branches both get exercised.
+#[derive(Debug, Arbitrary)]
+enum SentinelBiasedFloat {
+    Sentinel,
+    BigSentinel,
+    Arbitrary(f64),
+}
+
+impl SentinelBiasedFloat {
+    fn resolve(self) -> f64 {
+        match self {
+            SentinelBiasedFloat::Sentinel => 999999.0,
+            SentinelBiasedFloat::BigSentinel => 9999999.0,
+            SentinelBiasedFloat::Arbitrary(v) => v,
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+struct ArbitraryCotEvent {
+    uid: String,
+    event_type: String,
+    lat: SentinelBiasedFloat,
+    lon: SentinelBiasedFloat,
+    hae: SentinelBiasedFloat,
+    ce: SentinelBiasedFloat,
+    le: SentinelBiasedFloat,
+    detail_children: Vec<DetailChild>,
+}
+
+fuzz_target!(|input: ArbitraryCotEvent| {
+    let point = Point {
+        lat: input.lat.resolve(),
+        lon: input.lon.resolve(),
+        hae: input.hae.resolve(),
+        ce: input.ce.resolve(),
+        le: input.le.resolve(),
+    };
+    if ![point.lat, point.lon, point.hae, point.ce, point.le]
+        .iter()
+        .all(|v| v.is_finite())
+    {
+        return;
+    }
+
+    // Empty `detail_children` covers the empty/missing `<detail>` case.
+    let detail = if input.detail_children.is_empty() {
+        String::new()
+    } else {
+        let mut d = String::from("<detail>");
+        for child in &input.detail_children {
+            d.push_str(child.to_xml());
+        }
+        d.push_str("</detail>");
+        d
+    };
+
+    let defaults = CotEvent::default();
+    let event = CotEvent {
+        version: "2.0".to_string(),
+        uid: input.uid,
+        event_type: input.event_type,
+        time: defaults.time,
+        start: defaults.start,
+        stale: defaults.stale,
+        how: "h-g-i-g-o".to_string(),
+        point,
+        detail,
+        tz_offset_secs: None,
+    };
+
+    let Ok(xml) = event.to_xml() else {
+        return;
+    };
+
+    // Invariant 1: XML round-trip is semantically stable.
+    let Ok(reparsed) = CotEvent::from_xml(&xml) else {
+        return;
+    };
+    let Ok(reparsed_xml) = reparsed.to_xml() else {
+        return;
+    };
+    assert!(
+        semantic_xml_eq(&xml, &reparsed_xml, false),
+        "XML round-trip changed meaning:\n  original: {xml}\n  reparsed: {reparsed_xml}"
+    );
+
+    // Invariant 2: the Ditto document round-trip agrees with the XML round-trip.
+    let doc = cot_to_document(&event, "fuzz-peer");
+    let from_ditto = cot_event_from_ditto_document(&doc);
+    let Ok(from_ditto_xml) = from_ditto.to_xml() else {
+        return;
+    };
+    assert!(
+        semantic_xml_eq(&xml, &from_ditto_xml, false),
+        "Ditto round-trip changed meaning:\n  original: {xml}\n  via Ditto: {from_ditto_xml}"
+    );
+});
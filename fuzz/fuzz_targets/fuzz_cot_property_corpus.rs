@@ -0,0 +1,38 @@
+#![no_main]
+use ditto_cot::cot_events::CotEvent;
+use ditto_cot::ditto::cot_to_document;
+use libfuzzer_sys::fuzz_target;
+
+/// Raw CoT XML text rather than a structured `Arbitrary` type: the seed
+/// corpus under `corpus/fuzz_cot_property_corpus/` is literal XML lifted
+/// from `fixtures::CoTTestFixtures` (the `COORDINATE_TEST_DATA` extremes and
+/// the `a-u-S` malformed-placement case from `cot_sensor_formats_test.rs`
+/// where a `<point>`/`<track>` placed *after* `</event>` still parses), so
+/// those regression cases are exercised byte-for-byte instead of only
+/// through `proptest`'s structured generators (see
+/// `rust/tests/property_roundtrip_test.rs`).
+fuzz_target!(|data: &str| {
+    // Invariant 1: parsing never panics on arbitrary bytes.
+    let Ok(event) = CotEvent::from_xml(data) else {
+        return;
+    };
+
+    // Invariant 2: xml -> CotEvent -> cot_to_document -> CotDocument never
+    // panics.
+    let _doc = cot_to_document(&event, "fuzz-property-peer");
+
+    // Invariant 3: serialize -> parse -> serialize reaches a fixed point.
+    let Ok(xml) = event.to_xml() else {
+        return;
+    };
+    let Ok(reparsed) = CotEvent::from_xml(&xml) else {
+        return;
+    };
+    let Ok(xml_fixed_point) = reparsed.to_xml() else {
+        return;
+    };
+    assert_eq!(
+        xml, xml_fixed_point,
+        "serialize->parse->serialize did not reach a fixed point:\n  first:  {xml}\n  second: {xml_fixed_point}"
+    );
+});
@@ -0,0 +1,159 @@
+#![no_main]
+use arbitrary::{Arbitrary, Unstructured};
+use ditto_cot::cot_events::{CotEvent, Point};
+use ditto_cot::detail_tree::{parse_detail_tree, write_detail_tree, DetailNode};
+use ditto_cot::ditto::{cot_event_from_ditto_document, cot_to_document};
+use libfuzzer_sys::fuzz_target;
+
+/// Caps how deep/wide [`arbitrary_detail_node`] will recurse, the same way
+/// [`detail_tree::MAX_DETAIL_DEPTH`](ditto_cot::detail_tree) caps parsing —
+/// unbounded recursion driven straight off fuzzer bytes can otherwise blow
+/// the stack before the invariant under test is ever reached.
+const MAX_DEPTH: u32 = 4;
+const MAX_CHILDREN: usize = 3;
+const MAX_ATTRS: usize = 3;
+
+/// A handful of tag/attribute names drawn from real CoT detail vocabulary,
+/// including two intentionally chosen to be prefixes of one another
+/// (`"link"`/`"linkAttr"`) so the fuzz target exercises tags whose names
+/// collide under naive prefix-based matching.
+const NAMES: &[&str] = &[
+    "contact", "__group", "link", "linkAttr", "remarks", "uid", "takv", "status",
+];
+
+fn arbitrary_name(u: &mut Unstructured) -> arbitrary::Result<String> {
+    let idx = u.int_in_range(0..=NAMES.len() - 1)?;
+    Ok(NAMES[idx].to_string())
+}
+
+/// Arbitrary text content, including the escape-worthy characters
+/// (`<`, `&`, `"`) a naive string-scanning reconstruction check would choke
+/// on but [`write_detail_tree`]/[`parse_detail_tree`] round-trip correctly.
+fn arbitrary_text(u: &mut Unstructured) -> arbitrary::Result<String> {
+    let base = String::arbitrary(u)?;
+    let decorated = match u.int_in_range(0..=3u8)? {
+        1 => format!("{base}<tag>&amp;"),
+        2 => format!("{base}&\"'"),
+        3 => format!("<![CDATA[{base}]]>"),
+        _ => base,
+    };
+    Ok(decorated)
+}
+
+fn arbitrary_detail_node(u: &mut Unstructured, depth: u32) -> arbitrary::Result<DetailNode> {
+    let name = arbitrary_name(u)?;
+    let attr_count = u.int_in_range(0..=MAX_ATTRS)?;
+    let mut attrs = Vec::with_capacity(attr_count);
+    for _ in 0..attr_count {
+        attrs.push((arbitrary_name(u)?, arbitrary_text(u)?));
+    }
+
+    let text = if bool::arbitrary(u)? {
+        Some(arbitrary_text(u)?)
+    } else {
+        None
+    };
+
+    let mut children = Vec::new();
+    if depth < MAX_DEPTH {
+        let child_count = u.int_in_range(0..=MAX_CHILDREN)?;
+        for _ in 0..child_count {
+            children.push(arbitrary_detail_node(u, depth + 1)?);
+        }
+    }
+
+    Ok(DetailNode {
+        name,
+        attrs,
+        text,
+        children,
+    })
+}
+
+/// Bounded forest of [`DetailNode`]s, including the empty-forest case (an
+/// empty `<detail/>`) and self-closing leaves (a node with no attrs, text,
+/// or children, which [`write_detail_tree`] emits as `<tag/>`).
+#[derive(Debug)]
+struct ArbitraryDetailForest(Vec<DetailNode>);
+
+impl<'a> Arbitrary<'a> for ArbitraryDetailForest {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let root_count = u.int_in_range(0..=MAX_CHILDREN)?;
+        let mut roots = Vec::with_capacity(root_count);
+        for _ in 0..root_count {
+            roots.push(arbitrary_detail_node(u, 0)?);
+        }
+        Ok(ArbitraryDetailForest(roots))
+    }
+}
+
+/// Recursively collects every element in a detail forest as a
+/// `(path, tag, attrs, text)` tuple, `path` being the ordinal-indexed chain
+/// of tags from the root (e.g. `"0/link"`, `"0/link/1/remarks"`) so that two
+/// elements with the same tag/attrs/text at different positions are still
+/// distinguished — catching a bug that drops or merges an element rather
+/// than one that only reorders equivalent siblings.
+type DetailTuple = (String, String, Vec<(String, String)>, Option<String>);
+
+fn collect_multiset(nodes: &[DetailNode], prefix: &str) -> Vec<DetailTuple> {
+    let mut out = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let path = format!("{prefix}{i}/{}", node.name);
+        let mut attrs = node.attrs.clone();
+        attrs.sort();
+        out.push((path.clone(), node.name.clone(), attrs, node.text.clone()));
+        out.extend(collect_multiset(&node.children, &format!("{path}/")));
+    }
+    out
+}
+
+fuzz_target!(|forest: ArbitraryDetailForest| {
+    let detail = if forest.0.is_empty() {
+        String::new()
+    } else {
+        write_detail_tree(&forest.0)
+    };
+
+    let event = CotEvent {
+        version: "2.0".to_string(),
+        uid: "fuzz-detail-uid".to_string(),
+        event_type: "a-f-G-U-C".to_string(),
+        time: CotEvent::default().time,
+        start: CotEvent::default().start,
+        stale: CotEvent::default().stale,
+        how: "h-g-i-g-o".to_string(),
+        point: Point {
+            lat: 1.0,
+            lon: 2.0,
+            hae: 3.0,
+            ce: 4.0,
+            le: 5.0,
+        },
+        detail,
+        tz_offset_secs: None,
+    };
+
+    let expected = parse_detail_tree(&format!("<detail>{}</detail>", event.detail));
+
+    let doc = cot_to_document(&event, "fuzz-detail-peer");
+    let from_ditto = cot_event_from_ditto_document(&doc);
+    let Ok(xml) = from_ditto.to_xml() else {
+        return;
+    };
+    let Ok(reparsed) = CotEvent::from_xml(&xml) else {
+        return;
+    };
+
+    let actual = parse_detail_tree(&format!("<detail>{}</detail>", reparsed.detail));
+
+    let mut expected_multiset = collect_multiset(&expected, "");
+    let mut actual_multiset = collect_multiset(&actual, "");
+    expected_multiset.sort();
+    actual_multiset.sort();
+
+    assert_eq!(
+        expected_multiset, actual_multiset,
+        "detail element (path, tag, attrs, text) multiset changed across round-trip:\n  \
+         before: {expected:#?}\n  after: {actual:#?}"
+    );
+});
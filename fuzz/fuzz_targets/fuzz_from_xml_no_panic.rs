@@ -0,0 +1,15 @@
+#![no_main]
+use ditto_cot::cot_events::CotEvent;
+use ditto_cot::xml_utils::minimize_xml;
+use libfuzzer_sys::fuzz_target;
+
+// Raw, unstructured bytes rather than a structured `Arbitrary` type: the only
+// invariant is "never panics", even on truncated tags, mismatched
+// quoting, or attribute values that aren't valid UTF-8 (which `minimize_xml`
+// and `CotEvent::from_xml` currently pull back in via `String::from_utf8_lossy`
+// rather than rejecting outright).
+fuzz_target!(|data: &[u8]| {
+    let xml = String::from_utf8_lossy(data);
+    let _ = CotEvent::from_xml(&xml);
+    let _ = minimize_xml(&xml);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use ditto_cot::detail_parser::parse_detail_section;
+
+// Raw, unstructured byte blobs: the only invariant is "never panics", even on
+// hostile/degenerate nesting (see MAX_DETAIL_DEPTH in detail_parser.rs).
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let _ = parse_detail_section(s);
+    }
+});
@@ -0,0 +1,76 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use ditto_cot::model::FlatCotEvent;
+use ditto_cot::xml_parser::parse_cot;
+use ditto_cot::xml_writer::to_cot_xml;
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashMap;
+
+/// A structurally-valid CoT event tree, generated directly from fuzzer bytes
+/// instead of free-form XML, so most inputs exercise the happy path rather
+/// than bailing out on malformed syntax.
+#[derive(Debug, Arbitrary)]
+struct ArbitraryCotEvent {
+    uid: String,
+    type_: String,
+    lat: f64,
+    lon: f64,
+    hae: f64,
+    ce: f64,
+    le: f64,
+    detail_extra: HashMap<String, String>,
+}
+
+fuzz_target!(|input: ArbitraryCotEvent| {
+    // Coordinates must be finite or the formatted XML round-trip is ill-defined.
+    if ![input.lat, input.lon, input.hae, input.ce, input.le]
+        .iter()
+        .all(|v| v.is_finite())
+    {
+        return;
+    }
+
+    let event = FlatCotEvent {
+        uid: input.uid,
+        type_: input.type_,
+        time: "2023-01-01T00:00:00Z".to_string(),
+        start: "2023-01-01T00:00:00Z".to_string(),
+        stale: "2023-01-01T00:00:00Z".to_string(),
+        how: "h-g-i-g-o".to_string(),
+        lat: input.lat,
+        lon: input.lon,
+        hae: input.hae,
+        ce: input.ce,
+        le: input.le,
+        callsign: None,
+        group_name: None,
+        detail_extra: input
+            .detail_extra
+            .into_iter()
+            .map(|(k, v)| (k, serde_json::Value::String(v)))
+            .collect(),
+    };
+
+    let xml = to_cot_xml(&event);
+    let Ok(reparsed) = parse_cot(&xml) else {
+        return;
+    };
+
+    // Round-trip idempotence: to_cot_xml -> parse_cot must reproduce the same
+    // uid, type_, coordinates. (`detail_extra` is checked key-by-key below
+    // rather than with a strict map equality, since repeated sibling tags are
+    // not yet guaranteed to survive a single `HashMap` entry.)
+    assert_eq!(reparsed.uid, event.uid);
+    assert_eq!(reparsed.type_, event.type_);
+    assert_eq!(reparsed.lat, event.lat);
+    assert_eq!(reparsed.lon, event.lon);
+    assert_eq!(reparsed.hae, event.hae);
+    assert_eq!(reparsed.ce, event.ce);
+    assert_eq!(reparsed.le, event.le);
+    for (k, v) in &event.detail_extra {
+        assert!(
+            reparsed.detail_extra.contains_key(k),
+            "dropped detail_extra key {k:?} (had {v:?}) across round-trip"
+        );
+    }
+});
@@ -0,0 +1,80 @@
+#![no_main]
+use arbitrary::{Arbitrary, Unstructured};
+use ditto_cot::ditto::r_field_flattening::{flatten_r_field, unflatten_r_field};
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A handful of path-segment names, reused across entries so the fuzzer
+/// exercises repeated/overlapping segments rather than only ever-unique
+/// random strings, the same bias `fuzz_detail_roundtrip`'s `NAMES` uses.
+const SEGMENTS: &[&str] = &["takv", "contact", "os", "link", "0", "1", "remarks", "__group"];
+
+fn arbitrary_segment(u: &mut Unstructured) -> arbitrary::Result<&'static str> {
+    let idx = u.int_in_range(0..=SEGMENTS.len() - 1)?;
+    Ok(SEGMENTS[idx])
+}
+
+fn arbitrary_leaf(u: &mut Unstructured) -> arbitrary::Result<Value> {
+    Ok(match u.int_in_range(0..=3u8)? {
+        0 => Value::String(String::arbitrary(u)?),
+        1 => Value::Number(serde_json::Number::from(i64::arbitrary(u)?)),
+        2 => Value::Bool(bool::arbitrary(u)?),
+        _ => Value::Null,
+    })
+}
+
+/// Is `candidate` a prefix (inclusive) of `existing`, or vice versa, when
+/// both are split on `_`? [`unflatten_r_field`]'s `insert_path` can't tell
+/// "r_a" apart from "r_a_b" without one clobbering the other's container
+/// type, so a well-formed flattened map never has one full key as a
+/// path-prefix of another.
+fn is_path_conflict(a: &str, b: &str) -> bool {
+    let a_tokens: Vec<&str> = a.split('_').collect();
+    let b_tokens: Vec<&str> = b.split('_').collect();
+    let len = a_tokens.len().min(b_tokens.len());
+    a_tokens[..len] == b_tokens[..len]
+}
+
+/// A flattened `r_*` map built from non-conflicting paths (see
+/// [`is_path_conflict`]), so [`flatten_r_field`]`(`[`unflatten_r_field`]`(m))`
+/// is expected to reproduce `m` exactly rather than only "doesn't panic".
+#[derive(Debug)]
+struct ArbitraryFlattenedRMap(HashMap<String, Value>);
+
+impl<'a> Arbitrary<'a> for ArbitraryFlattenedRMap {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut map = HashMap::new();
+        let entry_count = u.int_in_range(0..=6)?;
+        for _ in 0..entry_count {
+            let segment_count = u.int_in_range(1..=3)?;
+            let mut segments = Vec::with_capacity(segment_count);
+            for _ in 0..segment_count {
+                segments.push(arbitrary_segment(u)?);
+            }
+            let key = format!("r_{}", segments.join("_"));
+
+            if map.keys().any(|existing: &String| is_path_conflict(existing, &key)) {
+                continue;
+            }
+            map.insert(key, arbitrary_leaf(u)?);
+        }
+        Ok(ArbitraryFlattenedRMap(map))
+    }
+}
+
+fuzz_target!(|input: ArbitraryFlattenedRMap| {
+    let flattened = input.0;
+
+    // Invariant 1: unflattening never panics, regardless of mixed numeric
+    // ("array index") and non-numeric ("object key") path segments.
+    let r_map = unflatten_r_field(&flattened);
+
+    // Invariant 2: re-flattening the reconstructed r field reproduces the
+    // same flattened map the fuzzer started from.
+    let refattened = flatten_r_field(&r_map);
+    assert_eq!(
+        flattened, refattened,
+        "unflatten_r_field -> flatten_r_field did not reproduce the original flattened map"
+    );
+});
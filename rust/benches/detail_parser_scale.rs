@@ -0,0 +1,159 @@
+//! Scale benchmarks for the CRDT-optimized detail parser's hot paths.
+//!
+//! `get_next_available_index` rescans the whole map on every call, so
+//! building up a document one element at a time is quadratic; this suite
+//! reproduces that shape at realistic sizes instead of the tiny fixtures
+//! `crdt_detail_parser`'s own tests use, so a regression here (or in
+//! `parse_detail_section_with_stable_keys`/`convert_stable_keys_to_xml`)
+//! shows up before it reaches production-sized documents. Workload sizes
+//! (element counts, attribute sizes) are declared in
+//! `detail_parser_workload.json` rather than hard-coded, so tracking
+//! parser throughput over time doesn't require touching this file.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ditto_cot::crdt_detail_parser::{
+    convert_stable_keys_to_xml, get_next_available_index, parse_detail_section_with_stable_keys,
+    IndexAllocator,
+};
+use serde::Deserialize;
+
+const WORKLOAD_JSON: &str = include_str!("detail_parser_workload.json");
+
+#[derive(Debug, Deserialize)]
+struct WorkloadFile {
+    workloads: Vec<Workload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Workload {
+    name: String,
+    sensor_count: usize,
+    track_count: usize,
+    remarks_count: usize,
+    attrs_per_element: usize,
+    attr_value_len: usize,
+}
+
+fn workloads() -> Vec<Workload> {
+    serde_json::from_str::<WorkloadFile>(WORKLOAD_JSON)
+        .expect("detail_parser_workload.json must parse")
+        .workloads
+}
+
+/// Builds a synthetic `<detail>` section with `count` copies of `tag`, each
+/// carrying `attrs_per_element` attributes of `attr_value_len` characters.
+fn synthetic_elements(
+    tag: &str,
+    count: usize,
+    attrs_per_element: usize,
+    attr_value_len: usize,
+) -> String {
+    let mut xml = String::new();
+    for i in 0..count {
+        xml.push('<');
+        xml.push_str(tag);
+        for attr in 0..attrs_per_element {
+            let value = "v".repeat(attr_value_len);
+            xml.push_str(&format!(" attr{attr}=\"{value}-{i}\""));
+        }
+        xml.push_str("/>");
+    }
+    xml
+}
+
+fn synthetic_detail_xml(workload: &Workload) -> String {
+    let mut body = String::from("<detail>");
+    body.push_str(&synthetic_elements(
+        "sensor",
+        workload.sensor_count,
+        workload.attrs_per_element,
+        workload.attr_value_len,
+    ));
+    body.push_str(&synthetic_elements(
+        "track",
+        workload.track_count,
+        workload.attrs_per_element,
+        workload.attr_value_len,
+    ));
+    body.push_str(&synthetic_elements(
+        "remarks",
+        workload.remarks_count,
+        workload.attrs_per_element,
+        workload.attr_value_len,
+    ));
+    body.push_str("</detail>");
+    body
+}
+
+fn bench_parse_detail_section_with_stable_keys(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_detail_section_with_stable_keys");
+    for workload in workloads() {
+        let xml = synthetic_detail_xml(&workload);
+        group.bench_with_input(BenchmarkId::from_parameter(&workload.name), &xml, |b, xml| {
+            b.iter(|| parse_detail_section_with_stable_keys(xml, "bench-doc"));
+        });
+    }
+    group.finish();
+}
+
+fn bench_convert_stable_keys_to_xml(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convert_stable_keys_to_xml");
+    for workload in workloads() {
+        let xml = synthetic_detail_xml(&workload);
+        let detail_map = parse_detail_section_with_stable_keys(&xml, "bench-doc");
+        group.bench_with_input(
+            BenchmarkId::from_parameter(&workload.name),
+            &detail_map,
+            |b, detail_map| {
+                b.iter(|| convert_stable_keys_to_xml(detail_map));
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Allocating `sensor_count` indices one at a time via the free function is
+/// the quadratic path this benchmark exists to catch; `IndexAllocator` is
+/// the O(1)-per-call alternative it's compared against. The inserted keys
+/// don't need to match `get_next_available_index`'s real hash prefix —
+/// it scans every key in the map on every call regardless of whether any
+/// match, so a same-size growing map exercises the same per-call cost.
+fn bench_bulk_index_allocation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bulk_index_allocation");
+    for workload in workloads() {
+        group.bench_with_input(
+            BenchmarkId::new("get_next_available_index", &workload.name),
+            &workload.sensor_count,
+            |b, &sensor_count| {
+                b.iter(|| {
+                    let mut detail_map = std::collections::HashMap::new();
+                    for i in 0..sensor_count {
+                        let index = get_next_available_index(&detail_map, "bench-doc", "sensor");
+                        detail_map.insert(format!("sensor-{i}-{index}"), serde_json::Value::Null);
+                    }
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("index_allocator", &workload.name),
+            &workload.sensor_count,
+            |b, &sensor_count| {
+                b.iter(|| {
+                    let mut allocator = IndexAllocator::new();
+                    for _ in 0..sensor_count {
+                        allocator.next_index("bench-doc", "sensor");
+                    }
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse_detail_section_with_stable_keys,
+    bench_convert_stable_keys_to_xml,
+    bench_bulk_index_allocation
+);
+criterion_main!(benches);
@@ -0,0 +1,195 @@
+//! Workload-driven benchmark harness for the parsing/conversion pipeline.
+//!
+//! Unlike `benches/*.rs` (Criterion, run via `cargo bench`, tuned for
+//! micro-benchmark statistics on a fixed shape), this is a standalone binary
+//! that loads a named workload file from `workloads/` at runtime and reports
+//! wall-clock throughput/latency for each pipeline stage against that
+//! workload's sample corpus, so a workload can be swapped without touching
+//! (or recompiling against) a hard-coded fixture. Run as:
+//!
+//! ```text
+//! cargo run --example bench -- complex_detail
+//! cargo run --example bench -- stable_key_stress
+//! ```
+//!
+//! Each workload JSON file (see `workloads/complex_detail.json` for an
+//! example) names a corpus of CoT XML samples and the list of pipeline
+//! stages to measure against each one. The stages mirror the hot path a CoT
+//! event actually travels: `parse_cot` (XML -> `FlatCotEvent`),
+//! `parse_detail_section` (detail XML -> flattened map),
+//! `parse_detail_section_with_stable_keys` (detail XML -> CRDT-stable-keyed
+//! map), `cot_to_document` (`CotEvent` -> `CotDocument`), and `to_cbor`
+//! (`CotDocument` -> CBOR).
+//!
+//! This tree's schema/example_xml fixture directory (referenced by
+//! `tests/e2e_test.rs`) isn't present in this snapshot, so the `complex_detail`
+//! workload inlines an equivalent sample instead of loading it from there.
+
+use anyhow::{bail, Context, Result};
+use ditto_cot::{
+    cot_events::CotEvent, crdt_detail_parser::parse_detail_section_with_stable_keys,
+    detail_parser::parse_detail_section, ditto::cot_to_document, xml_parser::parse_cot,
+};
+use dittolive_ditto::store::query_builder::DittoDocument;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Deserialize)]
+struct Workload {
+    name: String,
+    #[allow(dead_code)]
+    description: String,
+    iterations: usize,
+    operations: Vec<String>,
+    samples: Vec<Sample>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Sample {
+    name: String,
+    xml: String,
+}
+
+/// One stage's timing samples for one corpus sample, reduced to the
+/// summary stats the report prints.
+struct StageStats {
+    operation: String,
+    sample: String,
+    ops_per_sec: f64,
+    p50: Duration,
+    p99: Duration,
+}
+
+fn percentile(sorted_durations: &[Duration], pct: f64) -> Duration {
+    let rank = ((sorted_durations.len() - 1) as f64 * pct).round() as usize;
+    sorted_durations[rank]
+}
+
+fn summarize(operation: &str, sample: &str, mut durations: Vec<Duration>) -> StageStats {
+    durations.sort();
+    let total: Duration = durations.iter().sum();
+    let ops_per_sec = if total.as_secs_f64() > 0.0 {
+        durations.len() as f64 / total.as_secs_f64()
+    } else {
+        f64::INFINITY
+    };
+    StageStats {
+        operation: operation.to_string(),
+        sample: sample.to_string(),
+        ops_per_sec,
+        p50: percentile(&durations, 0.50),
+        p99: percentile(&durations, 0.99),
+    }
+}
+
+/// Times `iterations` calls to `f`, discarding the result of each call (the
+/// pipeline stages all run for their side effect on elapsed time, not their
+/// return value) but propagating the first error encountered.
+fn time_operation<T>(
+    iterations: usize,
+    mut f: impl FnMut() -> Result<T>,
+) -> Result<Vec<Duration>> {
+    let mut durations = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        f()?;
+        durations.push(start.elapsed());
+    }
+    Ok(durations)
+}
+
+fn run_operation(
+    operation: &str,
+    xml: &str,
+    iterations: usize,
+) -> Result<Vec<Duration>> {
+    match operation {
+        "parse_cot" => time_operation(iterations, || Ok(parse_cot(xml)?)),
+        "parse_detail_section" => time_operation(iterations, || {
+            let detail_xml = detail_section_xml(xml)?;
+            Ok(parse_detail_section(&detail_xml))
+        }),
+        "parse_detail_section_with_stable_keys" => time_operation(iterations, || {
+            let detail_xml = detail_section_xml(xml)?;
+            Ok(parse_detail_section_with_stable_keys(
+                &detail_xml,
+                "bench-doc",
+            ))
+        }),
+        "cot_to_document" => time_operation(iterations, || {
+            let event = CotEvent::from_xml(xml)?;
+            Ok(cot_to_document(&event, "bench-peer"))
+        }),
+        "to_cbor" => time_operation(iterations, || {
+            let event = CotEvent::from_xml(xml)?;
+            let doc = cot_to_document(&event, "bench-peer");
+            DittoDocument::to_cbor(&doc).map_err(|e| anyhow::anyhow!("to_cbor failed: {e}"))
+        }),
+        other => bail!("unknown operation `{other}` (see the module docs for the supported set)"),
+    }
+}
+
+/// Pulls the raw `<detail>...</detail>` substring out of a full CoT XML
+/// document, for the two detail-only parsing stages.
+fn detail_section_xml(xml: &str) -> Result<String> {
+    let start = xml.find("<detail").context("no <detail> element in sample XML")?;
+    let end = xml[start..]
+        .find("</detail>")
+        .context("unterminated <detail> element in sample XML")?
+        + start
+        + "</detail>".len();
+    Ok(xml[start..end].to_string())
+}
+
+fn workloads_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("workloads")
+}
+
+fn load_workload(name: &str) -> Result<Workload> {
+    let path = workloads_dir().join(format!("{name}.json"));
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read workload file {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse workload file {}", path.display()))
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 2 {
+        bail!(
+            "usage: {} <workload-name>",
+            args.first().map_or("bench", |s| s.as_str())
+        );
+    }
+    let workload = load_workload(&args[1])?;
+
+    println!(
+        "workload: {} ({} iterations/stage)",
+        workload.name, workload.iterations
+    );
+    println!(
+        "{:<38} {:<20} {:>12} {:>12} {:>12}",
+        "operation", "sample", "ops/sec", "p50", "p99"
+    );
+
+    let mut stats = Vec::new();
+    for sample in &workload.samples {
+        for operation in &workload.operations {
+            let durations = run_operation(operation, &sample.xml, workload.iterations)
+                .with_context(|| format!("stage `{operation}` on sample `{}`", sample.name))?;
+            stats.push(summarize(operation, &sample.name, durations));
+        }
+    }
+
+    for stat in &stats {
+        println!(
+            "{:<38} {:<20} {:>12.1} {:>12?} {:>12?}",
+            stat.operation, stat.sample, stat.ops_per_sec, stat.p50, stat.p99
+        );
+    }
+
+    Ok(())
+}
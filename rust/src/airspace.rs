@@ -0,0 +1,430 @@
+//! OpenAir-style airspace/geofence import, producing CoT drawing events.
+//!
+//! OpenAir is the de facto plain-text format glider/paraglider tools
+//! exchange airspace files in: a stream of two-letter records (`AC` class,
+//! `AN` name, `AH`/`AL` ceiling/floor, `DP lat lon` polygon vertex, `V
+//! X=lat lon` center, `DC radius`/`DA radius,start,end` circle/arc) with no
+//! formal grammar body — every tool that emits it is a little different, so
+//! [`parse_openair`] is deliberately lenient about comments (`*`), blank
+//! lines, and whitespace. [`ShapeBuilder`] accumulates one airspace's
+//! vertices (expanding a `DC`/`DA` circle or arc into a point list at a
+//! configurable angular step) and emits a CoT `u-d-f` free-form polygon or
+//! `u-d-c-c` circle event, so operators can drop an existing airspace file
+//! onto a map as a tactical overlay the same way [`crate::cot_events::Route`]
+//! turns a list of legs into a `b-m-r` event.
+
+use crate::cot_events::{CotEvent, Point};
+use crate::detail_tree::{write_detail_tree, DetailNode};
+use crate::error::CotError;
+use chrono::Utc;
+
+/// Earth radius, taken as WGS84's semi-major axis, for expanding a `DC`/`DA`
+/// circle or arc into a vertex list. A spherical approximation is plenty accurate for airspace overlays,
+/// matching [`crate::cot_events::Route`]'s own haversine distance helper.
+const EARTH_RADIUS_METERS: f64 = 6_378_137.0;
+
+/// Meters per nautical mile, the unit OpenAir `DC`/`DA` radii are given in.
+const METERS_PER_NM: f64 = 1852.0;
+
+/// Default angular step, in degrees, between consecutive vertices
+/// [`ShapeBuilder::circle`]/[`ShapeBuilder::arc`] generate when the caller
+/// doesn't set [`ShapeBuilder::angular_step_deg`].
+const DEFAULT_ANGULAR_STEP_DEG: f64 = 10.0;
+
+/// Returns the point `distance_meters` from `from`, along initial bearing
+/// `bearing_deg` (clockwise from true north), via the spherical direct
+/// geodesic formula.
+fn destination_point(from: (f64, f64), bearing_deg: f64, distance_meters: f64) -> (f64, f64) {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let bearing = bearing_deg.to_radians();
+    let angular_distance = distance_meters / EARTH_RADIUS_METERS;
+
+    let lat2 = (lat1.sin() * angular_distance.cos()
+        + lat1.cos() * angular_distance.sin() * bearing.cos())
+    .asin();
+    let lon2 = lon1
+        + (bearing.sin() * angular_distance.sin() * lat1.cos())
+            .atan2(angular_distance.cos() - lat1.sin() * lat2.sin());
+
+    (lat2.to_degrees(), lon2.to_degrees())
+}
+
+/// Which CoT drawing event type [`ShapeBuilder::build`] emits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShapeKind {
+    /// `u-d-f`: a free-form polygon, for a `DP`-vertex or `DA`-arc airspace.
+    Polygon,
+    /// `u-d-c-c`: a circle, for a `DC`-only airspace.
+    Circle,
+}
+
+/// Accumulates one airspace boundary's vertices and metadata, and emits it
+/// as a CoT drawing event. See the [module docs](self) for the OpenAir
+/// commands each setter corresponds to.
+#[derive(Debug, Clone)]
+pub struct ShapeBuilder {
+    name: Option<String>,
+    ceiling: Option<String>,
+    floor: Option<String>,
+    vertices: Vec<Point>,
+    kind: ShapeKind,
+    angular_step_deg: f64,
+}
+
+impl Default for ShapeBuilder {
+    fn default() -> Self {
+        Self {
+            name: None,
+            ceiling: None,
+            floor: None,
+            vertices: Vec::new(),
+            kind: ShapeKind::Polygon,
+            angular_step_deg: DEFAULT_ANGULAR_STEP_DEG,
+        }
+    }
+}
+
+impl ShapeBuilder {
+    /// Creates an empty airspace boundary.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the angular step, in degrees, between consecutive vertices
+    /// [`Self::circle`]/[`Self::arc`] generate. Smaller steps produce a
+    /// smoother curve at the cost of more vertices.
+    pub fn angular_step_deg(mut self, step: f64) -> Self {
+        self.angular_step_deg = step;
+        self
+    }
+
+    /// Sets the airspace name (OpenAir `AN`).
+    pub fn name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the altitude band (OpenAir `AH`/`AL`), each as the record's raw
+    /// text (e.g. `"5000ft MSL"`, `"SFC"`) rather than a parsed altitude,
+    /// since OpenAir's altitude grammar includes flight levels and
+    /// AGL/MSL/SFC references this crate has no other use for.
+    pub fn altitude_band(mut self, ceiling: &str, floor: &str) -> Self {
+        self.ceiling = Some(ceiling.to_string());
+        self.floor = Some(floor.to_string());
+        self
+    }
+
+    /// Appends a polygon vertex (OpenAir `DP lat lon`).
+    pub fn vertex(mut self, lat: f64, lon: f64) -> Self {
+        self.vertices.push(Point::new(lat, lon, 0.0));
+        self.kind = ShapeKind::Polygon;
+        self
+    }
+
+    /// Expands a full circle of `radius_nm` nautical miles centered at
+    /// `(center_lat, center_lon)` into vertices (OpenAir `V X=lat lon`
+    /// followed by `DC radius`).
+    pub fn circle(mut self, center_lat: f64, center_lon: f64, radius_nm: f64) -> Self {
+        self.vertices = ring_vertices(
+            (center_lat, center_lon),
+            radius_nm * METERS_PER_NM,
+            0.0,
+            360.0,
+            self.angular_step_deg,
+        );
+        self.kind = ShapeKind::Circle;
+        self
+    }
+
+    /// Expands an arc of `radius_nm` nautical miles centered at
+    /// `(center_lat, center_lon)`, from `start_bearing_deg` to
+    /// `end_bearing_deg` clockwise, into vertices appended to the polygon
+    /// (OpenAir `V X=lat lon` followed by `DA radius,start,end`).
+    pub fn arc(
+        mut self,
+        center_lat: f64,
+        center_lon: f64,
+        radius_nm: f64,
+        start_bearing_deg: f64,
+        end_bearing_deg: f64,
+    ) -> Self {
+        self.vertices.extend(ring_vertices(
+            (center_lat, center_lon),
+            radius_nm * METERS_PER_NM,
+            start_bearing_deg,
+            end_bearing_deg,
+            self.angular_step_deg,
+        ));
+        self.kind = ShapeKind::Polygon;
+        self
+    }
+
+    /// Serializes the accumulated vertices and metadata into a
+    /// `<detail>...</detail>` string: a `<shape>` element carrying the name
+    /// and altitude band, wrapping a `<polyline>` of `<link point="lat,lon,
+    /// hae"/>` vertices.
+    fn to_detail_xml(&self) -> String {
+        let mut shape = DetailNode::new("shape");
+        if let Some(name) = &self.name {
+            shape.attrs.push(("name".to_string(), name.clone()));
+        }
+        if let Some(ceiling) = &self.ceiling {
+            shape.attrs.push(("ceiling".to_string(), ceiling.clone()));
+        }
+        if let Some(floor) = &self.floor {
+            shape.attrs.push(("floor".to_string(), floor.clone()));
+        }
+
+        let mut polyline = DetailNode::new("polyline");
+        polyline.attrs.push(("closed".to_string(), "true".to_string()));
+        for vertex in &self.vertices {
+            let mut link = DetailNode::new("link");
+            let point = format!("{},{},{}", vertex.lat, vertex.lon, vertex.hae);
+            link.attrs.push(("point".to_string(), point));
+            polyline.children.push(link);
+        }
+        shape.children.push(polyline);
+
+        format!("<detail>{}</detail>", write_detail_tree(&[shape]))
+    }
+
+    /// Builds the CoT drawing event: `u-d-c-c` for a [`Self::circle`] with
+    /// no additional vertices added afterward, `u-d-f` otherwise.
+    pub fn build(&self, uid: &str, stale_in: &str) -> Result<CotEvent, CotError> {
+        let now = Utc::now();
+        let stale = now + CotEvent::parse_relative_duration(stale_in)?;
+        let event_type = match self.kind {
+            ShapeKind::Polygon => "u-d-f",
+            ShapeKind::Circle => "u-d-c-c",
+        };
+        let point = self.vertices.first().cloned().unwrap_or_default();
+        Ok(CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: event_type.to_string(),
+            time: now,
+            start: now,
+            stale,
+            how: "h-g-i-g-o".to_string(),
+            point,
+            detail: self.to_detail_xml(),
+            tz_offset_secs: None,
+        })
+    }
+}
+
+/// Generates vertices every `angular_step_deg` degrees of bearing from
+/// `start_bearing_deg` to `end_bearing_deg` (clockwise), `radius_meters`
+/// from `center`, always including the end bearing exactly.
+fn ring_vertices(
+    center: (f64, f64),
+    radius_meters: f64,
+    start_bearing_deg: f64,
+    end_bearing_deg: f64,
+    angular_step_deg: f64,
+) -> Vec<Point> {
+    let step = angular_step_deg.abs().max(0.01);
+    let mut bearings = Vec::new();
+    let mut bearing = start_bearing_deg;
+    while bearing < end_bearing_deg {
+        bearings.push(bearing);
+        bearing += step;
+    }
+    bearings.push(end_bearing_deg);
+
+    bearings
+        .into_iter()
+        .map(|bearing| {
+            let (lat, lon) = destination_point(center, bearing, radius_meters);
+            Point::new(lat, lon, 0.0)
+        })
+        .collect()
+}
+
+/// Parses an OpenAir-format airspace file into one [`ShapeBuilder`] per
+/// airspace record, each started by an `AC` line and ended by the next `AC`
+/// line or end of input.
+///
+/// Lenient by necessity, since OpenAir is underspecified in the wild: blank
+/// lines and lines starting with `*` (comments) are skipped, and leading/
+/// trailing whitespace on every line is trimmed before matching a command.
+/// Unrecognized commands are ignored rather than rejected.
+pub fn parse_openair(input: &str) -> Vec<ShapeBuilder> {
+    let mut shapes = Vec::new();
+    let mut current: Option<ShapeBuilder> = None;
+    let mut pending_center: Option<(f64, f64)> = None;
+
+    for raw_line in input.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+        let Some((command, rest)) = line.split_once(char::is_whitespace) else {
+            continue;
+        };
+        let rest = rest.trim();
+
+        match command {
+            "AC" => {
+                if let Some(shape) = current.take() {
+                    shapes.push(shape);
+                }
+                current = Some(ShapeBuilder::new());
+                pending_center = None;
+            }
+            "AN" => {
+                current = current.map(|shape| shape.name(rest));
+            }
+            "AH" => {
+                current = current.map(|shape| {
+                    let floor = shape.floor.clone().unwrap_or_default();
+                    shape.altitude_band(rest, &floor)
+                });
+            }
+            "AL" => {
+                current = current.map(|shape| {
+                    let ceiling = shape.ceiling.clone().unwrap_or_default();
+                    shape.altitude_band(&ceiling, rest)
+                });
+            }
+            "DP" => {
+                if let Some((lat, lon)) = parse_lat_lon(rest) {
+                    current = current.map(|shape| shape.vertex(lat, lon));
+                }
+            }
+            "V" => {
+                if let Some(coords) = rest.strip_prefix("X=") {
+                    pending_center = parse_lat_lon(coords);
+                }
+            }
+            "DC" => {
+                if let (Ok(radius_nm), Some(center)) = (rest.parse::<f64>(), pending_center) {
+                    if let Some(shape) = current.take() {
+                        current = Some(shape.circle(center.0, center.1, radius_nm));
+                    }
+                }
+            }
+            "DA" => {
+                let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+                if let [radius_nm, start, end] = fields.as_slice() {
+                    if let (Ok(radius_nm), Ok(start), Ok(end), Some(center)) = (
+                        radius_nm.parse::<f64>(),
+                        start.parse::<f64>(),
+                        end.parse::<f64>(),
+                        pending_center,
+                    ) {
+                        if let Some(shape) = current.take() {
+                            current = Some(shape.arc(center.0, center.1, radius_nm, start, end));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(shape) = current.take() {
+        shapes.push(shape);
+    }
+    shapes
+}
+
+/// Parses an OpenAir `"lat lon"` pair (e.g. `"34:00:00 N 118:00:00 W"` style
+/// inputs are not supported — only plain decimal-degree pairs, the form
+/// every modern airspace tool now emits).
+fn parse_lat_lon(s: &str) -> Option<(f64, f64)> {
+    let mut parts = s.split_whitespace();
+    let lat = parts.next()?.parse::<f64>().ok()?;
+    let lon = parts.next()?.parse::<f64>().ok()?;
+    Some((lat, lon))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_openair_reads_a_polygon_airspace() {
+        let input = "\
+* comment line
+
+AC C
+AN Test Airspace
+AH 5000ft MSL
+AL SFC
+DP 34.0 -118.0
+DP 34.1 -118.0
+DP 34.1 -118.1
+";
+        let shapes = parse_openair(input);
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].name, Some("Test Airspace".to_string()));
+        assert_eq!(shapes[0].ceiling, Some("5000ft MSL".to_string()));
+        assert_eq!(shapes[0].floor, Some("SFC".to_string()));
+        assert_eq!(shapes[0].vertices.len(), 3);
+    }
+
+    #[test]
+    fn parse_openair_splits_on_each_ac_record() {
+        let input = "AC C\nAN First\nDP 1.0 1.0\nAC D\nAN Second\nDP 2.0 2.0\n";
+        let shapes = parse_openair(input);
+        assert_eq!(shapes.len(), 2);
+        assert_eq!(shapes[0].name, Some("First".to_string()));
+        assert_eq!(shapes[1].name, Some("Second".to_string()));
+    }
+
+    #[test]
+    fn parse_openair_expands_a_circle_record() {
+        let input = "AC C\nAN Circle Airspace\nV X=34.0 -118.0\nDC 5\n";
+        let shapes = parse_openair(input);
+        assert_eq!(shapes.len(), 1);
+        assert_eq!(shapes[0].kind, ShapeKind::Circle);
+        assert_eq!(shapes[0].vertices.len(), 37);
+    }
+
+    #[test]
+    fn circle_vertices_are_all_equidistant_from_the_center() {
+        let shape = ShapeBuilder::new()
+            .angular_step_deg(90.0)
+            .circle(34.0, -118.0, 5.0);
+        let center = Point::new(34.0, -118.0, 0.0);
+        for vertex in &shape.vertices {
+            let distance = haversine_distance_for_test(&center, vertex);
+            assert!((distance - 5.0 * METERS_PER_NM).abs() < 1.0);
+        }
+    }
+
+    fn haversine_distance_for_test(a: &Point, b: &Point) -> f64 {
+        let lat1 = a.lat.to_radians();
+        let lat2 = b.lat.to_radians();
+        let dlat = (b.lat - a.lat).to_radians();
+        let dlon = (b.lon - a.lon).to_radians();
+        let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
+    }
+
+    #[test]
+    fn build_emits_a_free_form_polygon_event_with_shape_detail() {
+        let shape = ShapeBuilder::new()
+            .name("Test Airspace")
+            .altitude_band("5000ft MSL", "SFC")
+            .vertex(34.0, -118.0)
+            .vertex(34.1, -118.0)
+            .vertex(34.1, -118.1);
+        let event = shape.build("AIRSPACE-1", "+1h").unwrap();
+
+        assert_eq!(event.uid, "AIRSPACE-1");
+        assert_eq!(event.event_type, "u-d-f");
+        assert!(event.detail.contains("<shape"));
+        assert!(event.detail.contains("name=\"Test Airspace\""));
+        assert!(event.detail.contains("ceiling=\"5000ft MSL\""));
+        assert!(event.detail.contains("<polyline closed=\"true\">"));
+        assert_eq!(event.detail.matches("<link").count(), 3);
+    }
+
+    #[test]
+    fn build_emits_a_circle_event_type_for_a_dc_only_record() {
+        let shape = ShapeBuilder::new().circle(34.0, -118.0, 5.0);
+        let event = shape.build("AIRSPACE-2", "+1h").unwrap();
+        assert_eq!(event.event_type, "u-d-c-c");
+    }
+}
@@ -0,0 +1,289 @@
+//! Pluggable wire-format codec for [`CotEvent`], mirroring
+//! [`crate::format`]'s `CotFormat` abstraction for `FlatCotEvent` one layer
+//! up, at the typed event itself rather than its flattened intermediate.
+//!
+//! [`CotEvent::to_xml`]/[`CotEvent::from_xml`] have always been the only way
+//! to serialize an event, but CoT traffic over Ditto's bandwidth-constrained
+//! mesh benefits from a binary representation instead of verbose XML (in
+//! the spirit of the `ilc` crate's Encode/Decode format abstraction).
+//! [`CotCodec`] is a small `encode`/`decode` trait with [`XmlCodec`],
+//! [`JsonCodec`], [`MsgPackCodec`], and [`ProtobufCodec`] implementations,
+//! and [`Format`] selects one at runtime so a relay can transcode between
+//! them.
+
+use crate::cot_events::{CotEvent, Point};
+use crate::error::CotError;
+use chrono::{DateTime, TimeZone, Utc};
+use prost::Message as _;
+
+/// Encodes and decodes a [`CotEvent`] to and from a particular wire format.
+pub trait CotCodec {
+    /// Serializes `event` to this codec's byte representation.
+    fn encode(&self, event: &CotEvent) -> Result<Vec<u8>, CotError>;
+    /// Deserializes a [`CotEvent`] previously written by [`Self::encode`].
+    fn decode(&self, bytes: &[u8]) -> Result<CotEvent, CotError>;
+}
+
+/// TAK CoT XML, via [`CotEvent::to_xml`] and [`CotEvent::from_xml`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XmlCodec;
+
+impl CotCodec for XmlCodec {
+    fn encode(&self, event: &CotEvent) -> Result<Vec<u8>, CotError> {
+        Ok(event.to_xml()?.into_bytes())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<CotEvent, CotError> {
+        let xml = std::str::from_utf8(bytes).map_err(|e| CotError::XmlError(e.to_string()))?;
+        CotEvent::from_xml(xml)
+    }
+}
+
+/// Plain JSON, via `CotEvent`'s `Serialize`/`Deserialize` impls.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl CotCodec for JsonCodec {
+    fn encode(&self, event: &CotEvent) -> Result<Vec<u8>, CotError> {
+        Ok(serde_json::to_vec(event)?)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<CotEvent, CotError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// MessagePack, matching [`ditto::msgpack`](crate::ditto::msgpack)'s
+/// `rmp_serde::to_vec_named`/`from_slice` convention.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgPackCodec;
+
+impl CotCodec for MsgPackCodec {
+    fn encode(&self, event: &CotEvent) -> Result<Vec<u8>, CotError> {
+        rmp_serde::to_vec_named(event).map_err(|e| CotError::MsgpackEncode(e.to_string()))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<CotEvent, CotError> {
+        rmp_serde::from_slice(bytes).map_err(|e| CotError::MsgpackDecode(e.to_string()))
+    }
+}
+
+/// Wire shape of [`Point`] under [`ProtobufCodec`]. A nested message rather
+/// than five sibling fields on [`ProtoCotEvent`] so an absent `point` (never
+/// produced by this codec, but tolerated on decode) is distinguishable from
+/// an all-zero one.
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoPoint {
+    #[prost(double, tag = "1")]
+    lat: f64,
+    #[prost(double, tag = "2")]
+    lon: f64,
+    #[prost(double, tag = "3")]
+    hae: f64,
+    #[prost(double, tag = "4")]
+    ce: f64,
+    #[prost(double, tag = "5")]
+    le: f64,
+}
+
+/// TAK Protocol Version 1 wire shape of a [`CotEvent`]. Timestamps are epoch
+/// milliseconds rather than the RFC 3339 strings XML/JSON use, matching what
+/// ATAK/TAK servers put on the wire; `detail` stays raw XML, since there's no
+/// structured `Detail` model yet for this codec to emit typed sub-messages
+/// into instead.
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoCotEvent {
+    #[prost(string, tag = "1")]
+    version: String,
+    #[prost(string, tag = "2")]
+    uid: String,
+    #[prost(string, tag = "3")]
+    event_type: String,
+    #[prost(int64, tag = "4")]
+    time_millis: i64,
+    #[prost(int64, tag = "5")]
+    start_millis: i64,
+    #[prost(int64, tag = "6")]
+    stale_millis: i64,
+    #[prost(string, tag = "7")]
+    how: String,
+    #[prost(message, optional, tag = "8")]
+    point: Option<ProtoPoint>,
+    #[prost(string, tag = "9")]
+    detail_xml: String,
+}
+
+/// TAK Protocol Version 1 Protobuf, the compact binary encoding TAK servers
+/// and ATAK clients negotiate for mesh and streaming transport in place of
+/// verbose XML. `detail` is carried as raw XML rather than decomposed into
+/// typed sub-messages, since [`CotEvent::detail`] itself is still an opaque
+/// string.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProtobufCodec;
+
+impl CotCodec for ProtobufCodec {
+    fn encode(&self, event: &CotEvent) -> Result<Vec<u8>, CotError> {
+        let proto = ProtoCotEvent {
+            version: event.version.clone(),
+            uid: event.uid.clone(),
+            event_type: event.event_type.clone(),
+            time_millis: event.time.timestamp_millis(),
+            start_millis: event.start.timestamp_millis(),
+            stale_millis: event.stale.timestamp_millis(),
+            how: event.how.clone(),
+            point: Some(ProtoPoint {
+                lat: event.point.lat,
+                lon: event.point.lon,
+                hae: event.point.hae,
+                ce: event.point.ce,
+                le: event.point.le,
+            }),
+            detail_xml: event.detail.clone(),
+        };
+        Ok(proto.encode_to_vec())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<CotEvent, CotError> {
+        let proto =
+            ProtoCotEvent::decode(bytes).map_err(|e| CotError::ProtoDecode(e.to_string()))?;
+        let point = proto.point.unwrap_or_default();
+        Ok(CotEvent {
+            version: proto.version,
+            uid: proto.uid,
+            event_type: proto.event_type,
+            time: millis_to_utc("time", proto.time_millis)?,
+            start: millis_to_utc("start", proto.start_millis)?,
+            stale: millis_to_utc("stale", proto.stale_millis)?,
+            how: proto.how,
+            point: Point {
+                lat: point.lat,
+                lon: point.lon,
+                hae: point.hae,
+                ce: point.ce,
+                le: point.le,
+            },
+            detail: proto.detail_xml,
+            tz_offset_secs: None,
+        })
+    }
+}
+
+fn millis_to_utc(field: &str, millis: i64) -> Result<DateTime<Utc>, CotError> {
+    Utc.timestamp_millis_opt(millis).single().ok_or_else(|| {
+        CotError::ProtoDecode(format!("'{field}' timestamp {millis} is out of range"))
+    })
+}
+
+/// Selects one of this module's [`CotCodec`] implementations at runtime, so
+/// a caller can round-trip the same [`CotEvent`] through any format, or a
+/// relay can transcode between them, without matching on a trait object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// TAK CoT XML ([`XmlCodec`]).
+    Xml,
+    /// Plain JSON ([`JsonCodec`]).
+    Json,
+    /// MessagePack ([`MsgPackCodec`]).
+    MsgPack,
+    /// TAK Protocol Version 1 Protobuf ([`ProtobufCodec`]).
+    Proto,
+}
+
+impl Format {
+    /// Serializes `event` to this format's byte representation.
+    pub fn encode(self, event: &CotEvent) -> Result<Vec<u8>, CotError> {
+        match self {
+            Format::Xml => XmlCodec.encode(event),
+            Format::Json => JsonCodec.encode(event),
+            Format::MsgPack => MsgPackCodec.encode(event),
+            Format::Proto => ProtobufCodec.encode(event),
+        }
+    }
+
+    /// Deserializes a [`CotEvent`] previously written by [`Self::encode`]
+    /// with the same format.
+    pub fn decode(self, bytes: &[u8]) -> Result<CotEvent, CotError> {
+        match self {
+            Format::Xml => XmlCodec.decode(bytes),
+            Format::Json => JsonCodec.decode(bytes),
+            Format::MsgPack => MsgPackCodec.decode(bytes),
+            Format::Proto => ProtobufCodec.decode(bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> CotEvent {
+        CotEvent::builder()
+            .uid("ANDROID-deadbeef")
+            .event_type("a-f-G-U-C")
+            .location(34.12345, -118.12345, 150.0)
+            .callsign("ALPHA-1")
+            .team("Cyan")
+            .build()
+    }
+
+    #[test]
+    fn xml_codec_round_trips() {
+        let original = event();
+        let bytes = XmlCodec.encode(&original).unwrap();
+        let decoded = XmlCodec.decode(&bytes).unwrap();
+        assert_eq!(decoded.uid, original.uid);
+        assert_eq!(decoded.point, original.point);
+    }
+
+    #[test]
+    fn json_codec_round_trips() {
+        let original = event();
+        let bytes = JsonCodec.encode(&original).unwrap();
+        let decoded = JsonCodec.decode(&bytes).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn msgpack_codec_round_trips() {
+        let original = event();
+        let bytes = MsgPackCodec.encode(&original).unwrap();
+        let decoded = MsgPackCodec.decode(&bytes).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn msgpack_decode_of_garbage_is_a_typed_error() {
+        let err = MsgPackCodec.decode(&[0xff, 0x00, 0x01]).unwrap_err();
+        assert!(matches!(err, CotError::MsgpackDecode(_)));
+    }
+
+    #[test]
+    fn protobuf_codec_round_trips() {
+        let original = event();
+        let bytes = ProtobufCodec.encode(&original).unwrap();
+        let decoded = ProtobufCodec.decode(&bytes).unwrap();
+        assert_eq!(decoded.uid, original.uid);
+        assert_eq!(decoded.event_type, original.event_type);
+        assert_eq!(decoded.point, original.point);
+        assert_eq!(decoded.time.timestamp_millis(), original.time.timestamp_millis());
+        assert_eq!(decoded.detail, original.detail);
+    }
+
+    #[test]
+    fn protobuf_decode_of_garbage_is_a_typed_error() {
+        let err = ProtobufCodec.decode(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap_err();
+        assert!(matches!(err, CotError::ProtoDecode(_)));
+    }
+
+    #[test]
+    fn format_transcodes_between_wire_representations() {
+        let original = event();
+        let json_bytes = Format::Json.encode(&original).unwrap();
+        let via_json = Format::Json.decode(&json_bytes).unwrap();
+
+        let msgpack_bytes = Format::MsgPack.encode(&via_json).unwrap();
+        let via_msgpack = Format::MsgPack.decode(&msgpack_bytes).unwrap();
+
+        assert_eq!(via_msgpack, original);
+    }
+}
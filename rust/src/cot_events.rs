@@ -3,13 +3,19 @@
 //! This module provides pre-defined templates for common CoT message types used in the TAK ecosystem.
 //! Each template includes the standard fields and can be customized as needed.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 
+use crate::detail_tree::{write_detail_tree, DetailNode};
 use crate::error::CotError;
+use crate::timestamp::CotTime;
 
 use crate::xml_utils::format_cot_float;
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use quick_xml::Writer;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
 use uuid::Uuid;
 
 /// Represents a Cursor on Target (CoT) event with all standard fields.
@@ -44,6 +50,19 @@ pub struct CotEvent {
 
     /// Raw XML for the <detail> element
     pub detail: String,
+
+    /// The producer's wall-clock UTC offset in seconds, captured from an
+    /// explicit offset (e.g. `+10:00`) on the source XML's `time`
+    /// attribute. `None` when the source used `Z` or the offset couldn't be
+    /// determined, in which case conversions treat this event as UTC, the
+    /// same as before this field existed.
+    ///
+    /// [`time`](Self::time)/[`start`](Self::start)/[`stale`](Self::stale)
+    /// stay normalized to UTC regardless — this field only records how to
+    /// re-render them in the producer's original offset for forensic
+    /// replay (see [`ditto::flat_cot_event_from_ditto`](crate::ditto::flat_cot_event_from_ditto)).
+    #[serde(default)]
+    pub tz_offset_secs: Option<i32>,
 }
 
 /// Represents a geographic point with elevation and accuracy information.
@@ -97,6 +116,137 @@ impl Point {
     }
 }
 
+/// Failure modes for [`PointBuilder::try_build`]'s geodetic validation.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+pub enum PointError {
+    /// `lat` fell outside the valid range `[-90, 90]`. Unlike longitude,
+    /// latitude has no periodic wraparound to normalize into range.
+    #[error("latitude {0} is outside the valid range [-90, 90]")]
+    InvalidLatitude(f64),
+
+    /// `lat`, `lon`, or `hae` was `NaN` or infinite.
+    #[error("'{field}' must be finite, got {value}")]
+    NonFinite {
+        /// Which field failed: `"lat"`, `"lon"`, or `"hae"`.
+        field: &'static str,
+        /// The offending value.
+        value: f64,
+    },
+}
+
+/// Horizontal/vertical accuracy for a [`Point`]'s `ce`/`le` fields: either a
+/// known value in meters, or [`Accuracy::Unknown`] for CoT's historical
+/// `999999.0` sentinel, so application code building or reading a `Point`
+/// stops comparing `ce`/`le` against that magic number directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Accuracy {
+    /// A known accuracy, in meters.
+    Meters(f64),
+    /// Accuracy is not known; builds as CoT's `999999.0` sentinel.
+    Unknown,
+}
+
+impl Accuracy {
+    /// CoT's historical "unknown accuracy" sentinel value.
+    const UNKNOWN_SENTINEL: f64 = 999_999.0;
+
+    /// The meter value this accuracy builds as: the wrapped value, or CoT's
+    /// `999999.0` sentinel for [`Self::Unknown`].
+    pub fn meters(self) -> f64 {
+        match self {
+            Accuracy::Meters(value) => value,
+            Accuracy::Unknown => Self::UNKNOWN_SENTINEL,
+        }
+    }
+
+    /// Reads a meter value back as [`Accuracy::Unknown`] if it matches CoT's
+    /// `999999.0` sentinel, or [`Accuracy::Meters`] otherwise.
+    pub fn from_meters(value: f64) -> Self {
+        if value == Self::UNKNOWN_SENTINEL {
+            Accuracy::Unknown
+        } else {
+            Accuracy::Meters(value)
+        }
+    }
+}
+
+/// A logarithmically-encoded precision in the style of RFC 1876 (the DNS LOC
+/// record)'s SIZE/HORIZ PRE/VERT PRE fields: a mantissa `1..=9` times
+/// `10^exponent` centimeters, packed into LOC's single base-and-exponent
+/// byte. Useful for producers that already carry accuracy in this form (or
+/// want its coarse, wire-compact rounding) instead of a free-floating meter
+/// value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocPrecision {
+    mantissa: u8,
+    exponent: u8,
+}
+
+impl LocPrecision {
+    /// Creates a precision of `mantissa * 10^exponent` centimeters.
+    /// `mantissa` is clamped to `1..=9` and `exponent` to `0..=9`, RFC
+    /// 1876's valid ranges for these fields.
+    pub fn new(mantissa: u8, exponent: u8) -> Self {
+        Self {
+            mantissa: mantissa.clamp(1, 9),
+            exponent: exponent.clamp(0, 9),
+        }
+    }
+
+    /// This precision in meters, for use as a [`Point`]'s `ce`/`le`.
+    pub fn meters(self) -> f64 {
+        (self.mantissa as f64) * 10f64.powi(self.exponent as i32) / 100.0
+    }
+
+    /// Approximates `meters` as the nearest representable [`LocPrecision`]:
+    /// the base-10 exponent of `meters` in centimeters, and the mantissa
+    /// that comes closest to it.
+    pub fn from_meters(meters: f64) -> Self {
+        let centimeters = (meters.abs() * 100.0).max(1.0);
+        let exponent = centimeters.log10().floor().clamp(0.0, 9.0);
+        let mantissa = (centimeters / 10f64.powi(exponent as i32)).round().clamp(1.0, 9.0);
+        Self::new(mantissa as u8, exponent as u8)
+    }
+
+    /// Packs this precision into RFC 1876's single base-and-exponent byte:
+    /// mantissa in the high nibble, exponent in the low nibble.
+    pub fn to_loc_byte(self) -> u8 {
+        (self.mantissa << 4) | self.exponent
+    }
+
+    /// Unpacks an RFC 1876 base-and-exponent byte into a precision.
+    pub fn from_loc_byte(byte: u8) -> Self {
+        Self::new(byte >> 4, byte & 0x0F)
+    }
+}
+
+/// Per-field record of which side won during a [`CotEvent::merge_with_provenance`],
+/// so a caller can detect a genuine conflict (both sides independently
+/// changed the same field) instead of silently trusting the merged result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldProvenance {
+    /// Timestamp that won for the event's scalar fields and `point`.
+    pub scalar_winner: DateTime<Utc>,
+    /// Winning timestamp per `detail` key present on either side.
+    pub detail_winners: HashMap<String, DateTime<Utc>>,
+    /// `detail` keys present on both sides with differing values — genuine
+    /// conflicts, where one side's edit was necessarily discarded.
+    pub conflicting_detail_keys: Vec<String>,
+}
+
+/// A recoverable issue [`CotEvent::from_xml_lenient`] papered over by
+/// substituting a default, instead of aborting the way [`CotEvent::from_xml`]
+/// would.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CotWarning {
+    /// The attribute this warning concerns (e.g. `"lat"`, `"stale"`).
+    pub field: String,
+    /// The raw text that failed to parse.
+    pub value: String,
+    /// Human-readable description of what was substituted and why.
+    pub message: String,
+}
+
 impl Default for CotEvent {
     fn default() -> Self {
         let now = Utc::now();
@@ -118,6 +268,7 @@ impl Default for CotEvent {
                 le: 999999.0,
             },
             detail: String::new(),
+            tz_offset_secs: None,
         }
     }
 }
@@ -155,8 +306,23 @@ impl CotEvent {
         &self.uid
     }
 
-    /// Converts the CotEvent to an XML string
+    /// Converts the CotEvent to an XML string, with `time`/`start`/`stale`
+    /// formatted at [`SecondsFormat::Secs`] precision. Use
+    /// [`Self::to_xml_with_precision`] for a different precision (e.g. the
+    /// milliseconds ATAK emits).
     pub fn to_xml(&self) -> Result<String, CotError> {
+        self.to_xml_with_precision(SecondsFormat::Secs)
+    }
+
+    /// Converts the CotEvent to an XML string, formatting `time`/`start`/
+    /// `stale` at the given `precision` with [`DateTime::to_rfc3339_opts`]
+    /// rather than plain [`DateTime::to_rfc3339`], which emits whatever
+    /// fractional-second precision the value happens to carry.
+    /// [`Self::to_xml`] is this function at [`SecondsFormat::Secs`]. A
+    /// stable, caller-chosen precision matters because some TAK parsers
+    /// reject variable-precision fractions; `Z` is always used instead of
+    /// `"+00:00"` regardless of `precision`, matching ATAK's own output.
+    pub fn to_xml_with_precision(&self, precision: SecondsFormat) -> Result<String, CotError> {
         // Pretty-print XML by manual string construction
         let lat = format_cot_float(self.point.lat);
         let lon = format_cot_float(self.point.lon);
@@ -165,32 +331,31 @@ impl CotEvent {
         let le = format_cot_float(self.point.le);
         let mut xml = String::new();
         xml.push_str("<event version=\"");
-        xml.push_str(self.version.as_str());
+        xml.push_str(&quick_xml::escape::escape(self.version.as_str()));
         xml.push('"');
         xml.push('\n');
         xml.push_str("              type=\"");
-        xml.push_str(self.event_type.as_str());
+        xml.push_str(&quick_xml::escape::escape(self.event_type.as_str()));
         xml.push('"');
         xml.push('\n');
         xml.push_str("              uid=\"");
-        xml.push_str(self.uid.as_str());
+        xml.push_str(&quick_xml::escape::escape(self.uid.as_str()));
         xml.push('"');
         xml.push('\n');
         xml.push_str("              time=\"");
-        // Format UTC timestamps with Z suffix instead of +00:00
-        xml.push_str(&self.time.to_rfc3339().replace("+00:00", "Z"));
+        xml.push_str(&self.time.to_rfc3339_opts(precision, true));
         xml.push('"');
         xml.push('\n');
         xml.push_str("              start=\"");
-        xml.push_str(&self.start.to_rfc3339().replace("+00:00", "Z"));
+        xml.push_str(&self.start.to_rfc3339_opts(precision, true));
         xml.push('"');
         xml.push('\n');
         xml.push_str("              stale=\"");
-        xml.push_str(&self.stale.to_rfc3339().replace("+00:00", "Z"));
+        xml.push_str(&self.stale.to_rfc3339_opts(precision, true));
         xml.push('"');
         xml.push('\n');
         xml.push_str("              how=\"");
-        xml.push_str(self.how.as_str());
+        xml.push_str(&quick_xml::escape::escape(self.how.as_str()));
         xml.push('"');
         xml.push('>');
         xml.push('\n');
@@ -230,15 +395,38 @@ impl CotEvent {
                     b"event" => {
                         // Parse event attributes
                         for attr in e.attributes() {
-                            let attr = attr?;
-                            let value = attr.unescape_value()?;
+                            let pos = reader.buffer_position();
+                            let attr = attr
+                                .map_err(|err| CotError::xml_parse_at(xml, pos, err.to_string()))?;
+                            let value = attr
+                                .unescape_value()
+                                .map_err(|err| CotError::xml_parse_at(xml, pos, err.to_string()))?;
                             match attr.key.0 {
                                 b"version" => event.version = value.into_owned(),
                                 b"uid" => event.uid = value.into_owned(),
                                 b"type" => event.event_type = value.into_owned(),
-                                b"time" => event.time = Self::parse_datetime(&value)?,
-                                b"start" => event.start = Self::parse_datetime(&value)?,
-                                b"stale" => event.stale = Self::parse_datetime(&value)?,
+                                b"time" => {
+                                    event.time = Self::parse_datetime(
+                                        "time",
+                                        &value,
+                                        crate::timestamp::DateBound::Floor,
+                                    )?;
+                                    event.tz_offset_secs = Self::parse_offset_secs(&value);
+                                }
+                                b"start" => {
+                                    event.start = Self::parse_datetime(
+                                        "start",
+                                        &value,
+                                        crate::timestamp::DateBound::Floor,
+                                    )?
+                                }
+                                b"stale" => {
+                                    event.stale = Self::parse_datetime(
+                                        "stale",
+                                        &value,
+                                        crate::timestamp::DateBound::Ceil,
+                                    )?
+                                }
                                 b"how" => event.how = value.into_owned(),
                                 _ => {}
                             }
@@ -248,8 +436,12 @@ impl CotEvent {
                         // Parse point attributes
                         log::trace!("Found point element");
                         for attr in e.attributes() {
-                            let attr = attr?;
-                            let value = attr.unescape_value()?;
+                            let pos = reader.buffer_position();
+                            let attr = attr
+                                .map_err(|err| CotError::xml_parse_at(xml, pos, err.to_string()))?;
+                            let value = attr
+                                .unescape_value()
+                                .map_err(|err| CotError::xml_parse_at(xml, pos, err.to_string()))?;
                             match attr.key.0 {
                                 b"lat" => {
                                     let lat_val: f64 =
@@ -347,8 +539,12 @@ impl CotEvent {
                     if name == "point" {
                         log::trace!("Found point element (Empty)");
                         for attr in e.attributes() {
-                            let attr = attr?;
-                            let value = attr.unescape_value()?;
+                            let pos = reader.buffer_position();
+                            let attr = attr
+                                .map_err(|err| CotError::xml_parse_at(xml, pos, err.to_string()))?;
+                            let value = attr
+                                .unescape_value()
+                                .map_err(|err| CotError::xml_parse_at(xml, pos, err.to_string()))?;
                             match attr.key.0 {
                                 b"lat" => {
                                     let lat_val: f64 =
@@ -433,7 +629,13 @@ impl CotEvent {
                     }
                 }
                 Ok(Event::Eof) => break,
-                Err(e) => return Err(CotError::XmlError(e.to_string())),
+                Err(e) => {
+                    return Err(CotError::xml_parse_at(
+                        xml,
+                        reader.buffer_position(),
+                        e.to_string(),
+                    ))
+                }
                 _ => {}
             }
         }
@@ -441,24 +643,306 @@ impl CotEvent {
         Ok(event)
     }
 
+    /// Parses a CoT XML string the way [`Self::from_xml`] does, but never
+    /// aborts on an unparseable `lat`/`lon`/`hae`/`ce`/`le` or
+    /// `time`/`start`/`stale` value: each one that fails to parse is
+    /// substituted with a sensible default (`0.0` for the numeric point
+    /// fields, `Utc::now()`-derived values for the timestamps) and recorded
+    /// as a [`CotWarning`] instead of short-circuiting the whole parse, in
+    /// the "be very lenient, real-world data is messy" spirit a hand-written
+    /// feed parser takes with malformed input it still wants to salvage
+    /// something from. A malformed XML document structure itself (as
+    /// opposed to a malformed attribute value within otherwise well-formed
+    /// XML) still ends the parse early, returning whatever fields were
+    /// filled in before the failure.
+    pub fn from_xml_lenient(xml: &str) -> (Self, Vec<CotWarning>) {
+        let mut reader = Reader::from_str(xml);
+        reader.trim_text(true);
+
+        let mut event = CotEvent::default();
+        let mut warnings = Vec::new();
+        let mut buf = Vec::new();
+
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.name().as_ref() == b"event" => {
+                    for attr in e.attributes().filter_map(Result::ok) {
+                        let Ok(value) = attr.unescape_value() else {
+                            continue;
+                        };
+                        match attr.key.0 {
+                            b"version" => event.version = value.into_owned(),
+                            b"uid" => event.uid = value.into_owned(),
+                            b"type" => event.event_type = value.into_owned(),
+                            b"time" => {
+                                event.time = Self::parse_datetime_lenient(
+                                    "time",
+                                    &value,
+                                    crate::timestamp::DateBound::Floor,
+                                    event.time,
+                                    &mut warnings,
+                                );
+                                event.tz_offset_secs = Self::parse_offset_secs(&value);
+                            }
+                            b"start" => {
+                                event.start = Self::parse_datetime_lenient(
+                                    "start",
+                                    &value,
+                                    crate::timestamp::DateBound::Floor,
+                                    event.start,
+                                    &mut warnings,
+                                )
+                            }
+                            b"stale" => {
+                                event.stale = Self::parse_datetime_lenient(
+                                    "stale",
+                                    &value,
+                                    crate::timestamp::DateBound::Ceil,
+                                    event.stale,
+                                    &mut warnings,
+                                )
+                            }
+                            b"how" => event.how = value.into_owned(),
+                            _ => {}
+                        }
+                    }
+                }
+                Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.name().as_ref() == b"point" => {
+                    for attr in e.attributes().filter_map(Result::ok) {
+                        let Ok(value) = attr.unescape_value() else {
+                            continue;
+                        };
+                        match attr.key.0 {
+                            b"lat" => {
+                                event.point.lat =
+                                    Self::parse_numeric_lenient("lat", &value, &mut warnings)
+                            }
+                            b"lon" => {
+                                event.point.lon =
+                                    Self::parse_numeric_lenient("lon", &value, &mut warnings)
+                            }
+                            b"hae" => {
+                                event.point.hae =
+                                    Self::parse_numeric_lenient("hae", &value, &mut warnings)
+                            }
+                            b"ce" => {
+                                event.point.ce =
+                                    Self::parse_numeric_lenient("ce", &value, &mut warnings)
+                            }
+                            b"le" => {
+                                event.point.le =
+                                    Self::parse_numeric_lenient("le", &value, &mut warnings)
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Ok(Event::Start(e)) if e.name().as_ref() == b"detail" => {
+                    let detail_start = reader.buffer_position() - e.name().0.len() - 2;
+                    let mut depth = 1;
+                    let mut detail_end = detail_start;
+
+                    loop {
+                        buf.clear();
+                        match reader.read_event_into(&mut buf) {
+                            Ok(Event::Start(ref e2)) if e2.name().as_ref() == b"detail" => {
+                                depth += 1
+                            }
+                            Ok(Event::End(ref e2)) if e2.name().as_ref() == b"detail" => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    detail_end = reader.buffer_position();
+                                    break;
+                                }
+                            }
+                            Ok(Event::Eof) | Err(_) => break,
+                            Ok(_) => {}
+                        }
+                    }
+                    let xml_bytes = xml.as_bytes();
+                    event.detail =
+                        String::from_utf8_lossy(&xml_bytes[detail_start..detail_end]).to_string();
+                }
+                Ok(Event::End(e)) if e.name().as_ref() == b"event" => break,
+                Ok(Event::Eof) => break,
+                Err(e) => {
+                    warnings.push(CotWarning {
+                        field: "xml".to_string(),
+                        value: String::new(),
+                        message: format!("stopped parsing at a malformed markup error: {e}"),
+                    });
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        (event, warnings)
+    }
+
+    /// Parses `value` as `f64`, recording a [`CotWarning`] and returning
+    /// `0.0` if it doesn't parse.
+    fn parse_numeric_lenient(field: &str, value: &str, warnings: &mut Vec<CotWarning>) -> f64 {
+        value.trim().parse::<f64>().unwrap_or_else(|_| {
+            warnings.push(CotWarning {
+                field: field.to_string(),
+                value: value.to_string(),
+                message: format!("'{value}' is not a number; defaulting '{field}' to 0.0"),
+            });
+            0.0
+        })
+    }
+
+    /// Parses `value` as a timestamp via [`Self::parse_datetime`], recording
+    /// a [`CotWarning`] and returning `default` if it doesn't parse.
+    fn parse_datetime_lenient(
+        field: &str,
+        value: &str,
+        bound: crate::timestamp::DateBound,
+        default: DateTime<Utc>,
+        warnings: &mut Vec<CotWarning>,
+    ) -> DateTime<Utc> {
+        Self::parse_datetime(field, value, bound).unwrap_or_else(|_| {
+            warnings.push(CotWarning {
+                field: field.to_string(),
+                value: value.to_string(),
+                message: format!("'{value}' is not a valid timestamp; defaulting '{field}'"),
+            });
+            default
+        })
+    }
+
     /// Helper function to parse ISO 8601 datetime strings
-    fn parse_datetime(s: &str) -> Result<DateTime<Utc>, CotError> {
-        // First try parsing as RFC 3339 format
-        DateTime::parse_from_rfc3339(s)
-            .map(|dt| dt.with_timezone(&Utc))
-            .or_else(|_| {
-                // Try with different formats if needed
-                DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.fZ")
-                    .or_else(|_| DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%SZ"))
-                    .map(|dt| dt.with_timezone(&Utc))
-            })
-            .map_err(|_| CotError::InvalidDateTime {
-                field: "datetime".to_string(),
-                value: s.to_string(),
-            })
+    ///
+    /// Tries [`Timestamp`](crate::timestamp::Timestamp) first, which accepts
+    /// a ` TAI`/` GPS` scale annotation (as carried by GPS/TAI-sourced
+    /// sensor and unmanned-system traffic) and a literal leap second, and
+    /// performs the leap-second-correct conversion to Unix microseconds.
+    /// Falls back to [`parse_flexible_timestamp`](crate::timestamp::parse_flexible_timestamp)
+    /// for the near-ISO-8601 variants and bare dates real producers send
+    /// that `Timestamp::parse` doesn't cover, padding a bare date per
+    /// `bound` depending on whether `field` is an opening bound
+    /// (`time`/`start`) or an open-ended one (`stale`).
+    ///
+    /// `field` is only used to label a [`CotError::InvalidDateTime`] if
+    /// every attempt fails.
+    fn parse_datetime(
+        field: &str,
+        s: &str,
+        bound: crate::timestamp::DateBound,
+    ) -> Result<DateTime<Utc>, CotError> {
+        let invalid = || CotError::InvalidDateTime {
+            field: field.to_string(),
+            value: s.to_string(),
+        };
+
+        if let Ok(ts) = crate::timestamp::Timestamp::parse(s) {
+            let micros = ts.to_unix_micros().map_err(|_| invalid())?;
+            return DateTime::<Utc>::from_timestamp_micros(micros as i64).ok_or_else(invalid);
+        }
+
+        let micros = crate::timestamp::parse_flexible_timestamp(s, bound).map_err(|_| invalid())?;
+        DateTime::<Utc>::from_timestamp_micros(micros).ok_or_else(invalid)
     }
 
-    /// Creates a new location update event
+    /// Extracts the explicit UTC offset (in seconds) from an RFC 3339
+    /// `time` attribute, e.g. `"+10:00"` in
+    /// `"2023-01-01T12:00:00+10:00"` yields `Some(36_000)`.
+    ///
+    /// Returns `None` for a `Z`/zero offset or any input
+    /// [`DateTime::parse_from_rfc3339`] can't parse (e.g. a bare `TAI`/`GPS`
+    /// scale reading handled separately by [`Self::parse_datetime`]), since
+    /// those carry no originating wall-clock offset to preserve.
+    fn parse_offset_secs(s: &str) -> Option<i32> {
+        let secs = DateTime::parse_from_rfc3339(s).ok()?.offset().local_minus_utc();
+        if secs == 0 {
+            None
+        } else {
+            Some(secs)
+        }
+    }
+
+    /// Parses a stale interval given as either an ISO 8601 duration
+    /// (`"PT5M"`, `"P1DT2H30M"`), a terse `<sign><num><unit>` expression
+    /// (`"+5m"`, `"+2h30m"`, `"-10s"`), or the literal `"now"`.
+    ///
+    /// Recognized units are `w`(eeks), `d`(ays), `h`(ours), `m`(inutes), and
+    /// `s`(econds); multiple `<num><unit>` groups sum together, the same way
+    /// the `reminder-bot` `natural_parser` sums a sign and repeated
+    /// `<number><unit>` groups into a single interval. `"now"` and a bare
+    /// ISO duration (no explicit sign) are always non-negative.
+    pub(crate) fn parse_relative_duration(s: &str) -> Result<chrono::Duration, CotError> {
+        let trimmed = s.trim();
+        if trimmed.eq_ignore_ascii_case("now") {
+            return Ok(chrono::Duration::zero());
+        }
+
+        if let Some(rest) = trimmed.strip_prefix('P') {
+            // The `T` date/time designator doesn't disambiguate anything for
+            // us here, since week/day/hour/minute/second units are already
+            // unambiguous on their own, so dropping it lets the same
+            // <num><unit> summation below handle both halves at once.
+            return Self::parse_unit_groups(&rest.replace('T', "")).ok_or_else(|| {
+                CotError::InvalidFormat(format!("Invalid ISO 8601 duration: {}", s))
+            });
+        }
+
+        let (sign, rest) = match trimmed.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+
+        Self::parse_unit_groups(rest)
+            .map(|duration| duration * sign)
+            .ok_or_else(|| CotError::InvalidFormat(format!("Invalid relative duration: {}", s)))
+    }
+
+    /// Parses zero or more `<digits><unit>` groups (e.g. `"2h30m"`) and sums
+    /// them, or returns `None` if `s` is empty, has trailing digits with no
+    /// unit, or contains an unrecognized unit.
+    fn parse_unit_groups(s: &str) -> Option<chrono::Duration> {
+        if s.is_empty() {
+            return None;
+        }
+        let mut total = chrono::Duration::zero();
+        let mut digits_start = 0;
+        let mut chars = s.char_indices().peekable();
+        while let Some(&(idx, ch)) = chars.peek() {
+            if ch.is_ascii_digit() {
+                chars.next();
+                continue;
+            }
+            let digits = &s[digits_start..idx];
+            if digits.is_empty() {
+                return None;
+            }
+            let count: i64 = digits.parse().ok()?;
+            total = total + Self::unit_duration(ch, count)?;
+            chars.next();
+            digits_start = idx + ch.len_utf8();
+        }
+        if digits_start != s.len() {
+            return None;
+        }
+        Some(total)
+    }
+
+    /// Maps a single unit letter (`w`/`d`/`h`/`m`/`s`, case-insensitive) to
+    /// the `chrono::Duration` it represents for `count` units.
+    fn unit_duration(unit: char, count: i64) -> Option<chrono::Duration> {
+        match unit.to_ascii_lowercase() {
+            'w' => Some(chrono::Duration::weeks(count)),
+            'd' => Some(chrono::Duration::days(count)),
+            'h' => Some(chrono::Duration::hours(count)),
+            'm' => Some(chrono::Duration::minutes(count)),
+            's' => Some(chrono::Duration::seconds(count)),
+            _ => None,
+        }
+    }
+
+    /// Creates a new location update event, staying stale for `stale_in`
+    /// (e.g. `"+5m"`, `"PT90S"`, `"now"` — see [`Self::parse_relative_duration`]).
     pub fn new_location_update(
         uid: &str,
         callsign: &str,
@@ -466,15 +950,17 @@ impl CotEvent {
         lat: f64,
         lon: f64,
         hae: f64,
-    ) -> Self {
+        stale_in: &str,
+    ) -> Result<Self, CotError> {
         let now = Utc::now();
-        Self {
+        let stale = now + Self::parse_relative_duration(stale_in)?;
+        Ok(Self {
             version: "2.0".to_string(),
             uid: uid.to_string(),
             event_type: "a-f-G-U-C".to_string(),
             time: now,
             start: now,
-            stale: now + chrono::Duration::minutes(5),
+            stale,
             how: "h-g-i-g-o".to_string(),
             point: Point {
                 lat,
@@ -484,52 +970,77 @@ impl CotEvent {
                 le: 10.0,
             },
             detail: format!("location update: callsign={}, team={}", callsign, team),
-        }
+        })
     }
 
-    /// Creates a new chat message event
+    /// Creates a new chat message event, staying stale for `stale_in` (see
+    /// [`Self::parse_relative_duration`]).
+    ///
+    /// Builds the full TAK GeoChat detail schema via [`GeoChat`] (the shape
+    /// [`ditto::transform_chat_event`](crate::ditto::transform_chat_event)
+    /// expects), rather than an ad hoc string, so the result round-trips
+    /// through the Ditto conversion path unchanged. Pass `recipient_uid` to
+    /// address one participant directly instead of broadcasting to the
+    /// whole room.
     pub fn new_chat_message(
         sender_uid: &str,
         sender_callsign: &str,
         message: &str,
         chatroom: &str,
-        _chat_group_uid: &str,
-    ) -> Self {
+        chat_group_uid: &str,
+        recipient_uid: Option<&str>,
+        stale_in: &str,
+    ) -> Result<Self, CotError> {
         let now = Utc::now();
+        let stale = now + Self::parse_relative_duration(stale_in)?;
+        let mut chat = GeoChat::new(
+            sender_uid,
+            sender_callsign,
+            chatroom,
+            chat_group_uid,
+            message,
+        );
+        if let Some(recipient_uid) = recipient_uid {
+            chat = chat.to_recipient(recipient_uid);
+        }
         let uid = format!("Chat-{}-", sender_uid);
-        Self {
+        Ok(Self {
             version: "2.0".to_string(),
             uid,
             event_type: "b-t-f".to_string(),
             time: now,
             start: now,
-            stale: now + chrono::Duration::minutes(5),
+            stale,
             how: "h-g-i-g-o".to_string(),
             point: Point::default(),
-            detail: format!(
-                "<detail>chat from={} room={} msg={}</detail>",
-                sender_callsign, chatroom, message
-            ),
-        }
+            detail: chat.to_detail_xml(now),
+        })
     }
 
-    /// Creates a new emergency event
+    /// Creates a new emergency alert event of `emergency_type`, staying
+    /// stale for `stale_in` (see [`Self::parse_relative_duration`]).
+    ///
+    /// Use [`Self::cancel_emergency`] to later close out the alert raised
+    /// here; see [`Self::is_emergency_cancellation`] for reading that
+    /// distinction back out on the receiving end.
     pub fn new_emergency(
         uid: &str,
-        _callsign: &str,
+        callsign: &str,
         lat: f64,
         lon: f64,
-        emergency_type: &str,
+        emergency_type: EmergencyType,
         message: &str,
-    ) -> Self {
+        stale_in: &str,
+    ) -> Result<Self, CotError> {
         let now = Utc::now();
-        Self {
+        let stale = now + Self::parse_relative_duration(stale_in)?;
+        Ok(Self {
             version: "2.0".to_string(),
             uid: uid.to_string(),
-            event_type: "b-a-o-can".to_string(),
+            event_type: emergency_type.cot_type().to_string(),
             time: now,
             start: now,
-            stale: now + chrono::Duration::minutes(5),
+            stale,
             how: "h-g-i-g-o".to_string(),
             point: Point {
                 lat,
@@ -538,11 +1049,673 @@ impl CotEvent {
                 ce: 10.0,
                 le: 10.0,
             },
-            detail: format!(
-                "<detail>emergency: type={} msg={}</detail>",
-                emergency_type, message
-            ),
+            detail: emergency_detail_xml(emergency_type, false, callsign, message),
+        })
+    }
+
+    /// Creates the [`EmergencyType::Cancel`] event that closes out a
+    /// previously-raised alert for the same `uid`, staying stale for
+    /// `stale_in` (see [`Self::parse_relative_duration`]).
+    pub fn cancel_emergency(uid: &str, callsign: &str, stale_in: &str) -> Result<Self, CotError> {
+        let now = Utc::now();
+        let stale = now + Self::parse_relative_duration(stale_in)?;
+        Ok(Self {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: EmergencyType::Cancel.cot_type().to_string(),
+            time: now,
+            start: now,
+            stale,
+            how: "h-g-i-g-o".to_string(),
+            point: Point::default(),
+            detail: emergency_detail_xml(EmergencyType::Cancel, true, callsign, ""),
+        })
+    }
+
+    /// Creates a new route event (`b-m-r`) from a [`Route`], staying stale
+    /// for `stale_in` (see [`Self::parse_relative_duration`]). The event's
+    /// `point` is the first waypoint of the route's first leg, falling back
+    /// to the origin if the route has no legs or points.
+    pub fn new_route(
+        uid: &str,
+        callsign: &str,
+        route: &Route,
+        stale_in: &str,
+    ) -> Result<Self, CotError> {
+        let now = Utc::now();
+        let stale = now + Self::parse_relative_duration(stale_in)?;
+        let point = route
+            .legs
+            .first()
+            .and_then(|leg| leg.points.first())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: "b-m-r".to_string(),
+            time: now,
+            start: now,
+            stale,
+            how: "h-g-i-g-o".to_string(),
+            point,
+            detail: route.to_detail_xml(callsign),
+            tz_offset_secs: None,
+        })
+    }
+
+    /// Creates a deletion/tombstone event (`t-x-d-d`) retracting the
+    /// previously-sent track `link_uid`, staying stale for `stale_in` (see
+    /// [`Self::parse_relative_duration`]). The `detail` carries a
+    /// `<link uid="{link_uid}" relation="p-p"/>` pointing at the retracted
+    /// track and a `<__forcedelete/>` marker, so a consumer can distinguish
+    /// this from an ordinary expiry.
+    pub fn new_deletion(uid: &str, link_uid: &str, stale_in: &str) -> Result<Self, CotError> {
+        let now = Utc::now();
+        let stale = now + Self::parse_relative_duration(stale_in)?;
+        let detail = DetailBuilder::new()
+            .element("link", &[("uid", link_uid), ("relation", "p-p")])
+            .element("__forcedelete", &[])
+            .to_xml();
+        Ok(Self {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: "t-x-d-d".to_string(),
+            time: now,
+            start: now,
+            stale,
+            how: "h-g-i-g-o".to_string(),
+            point: Point::default(),
+            detail,
+            tz_offset_secs: None,
+        })
+    }
+
+    /// Returns whether this event's `emergency` detail element has
+    /// `cancel="true"` — i.e. it closes out a previously-raised alert for
+    /// the same `uid` rather than raising a new one. A consumer tracking
+    /// emergencies by `uid` (e.g. with [`StaleTracker`](crate::stale::StaleTracker))
+    /// can use this to drive an alert -> acknowledged -> cancelled state
+    /// machine, with "acknowledged" as an application-level state between
+    /// the two this crate doesn't model.
+    pub fn is_emergency_cancellation(&self) -> bool {
+        crate::detail_parser::parse_detail_section(&self.detail)
+            .get("emergency")
+            .and_then(Value::as_object)
+            .and_then(|emergency| emergency.get("cancel"))
+            .and_then(Value::as_str)
+            .map(|cancel| cancel == "true")
+            .unwrap_or(false)
+    }
+
+    /// Reconciles two independently-edited copies of the same `uid` for
+    /// offline-sync convergence, the way two Ditto peers' updates to the
+    /// same event need to be merged once they reconnect.
+    ///
+    /// Scalar fields and `point` are last-writer-wins: the whole side with
+    /// the later `time` wins outright (ties favor `self`). `detail` merges
+    /// key-by-key instead — each key (as parsed by
+    /// [`detail_parser::parse_detail_section`](crate::detail_parser::parse_detail_section))
+    /// takes its value from whichever side has the later `time`, so
+    /// non-conflicting detail keys added by either side both survive rather
+    /// than one side's entire detail subtree clobbering the other's.
+    ///
+    /// See [`Self::merge_with_provenance`] for a variant that also reports
+    /// which side won each field.
+    pub fn merge(&self, other: &CotEvent) -> CotEvent {
+        self.merge_with_provenance(other).0
+    }
+
+    /// Like [`Self::merge`], but also returns a [`FieldProvenance`] record of
+    /// which side's timestamp won per field, so a caller can detect and log
+    /// genuine conflicts (the same `detail` key edited differently on both
+    /// sides) rather than silently discarding one side's change.
+    pub fn merge_with_provenance(&self, other: &CotEvent) -> (CotEvent, FieldProvenance) {
+        let scalars_from_self = self.time >= other.time;
+        let scalar_winner = if scalars_from_self {
+            self.time
+        } else {
+            other.time
+        };
+
+        let local_detail = crate::detail_parser::parse_detail_section(&self.detail);
+        let remote_detail = crate::detail_parser::parse_detail_section(&other.detail);
+
+        let mut merged_detail: HashMap<String, Value> = HashMap::new();
+        let mut detail_winners = HashMap::new();
+        let mut conflicting_detail_keys = Vec::new();
+
+        let keys: HashSet<&String> = local_detail.keys().chain(remote_detail.keys()).collect();
+        for key in keys {
+            match (local_detail.get(key), remote_detail.get(key)) {
+                (Some(l), None) => {
+                    merged_detail.insert(key.clone(), l.clone());
+                    detail_winners.insert(key.clone(), self.time);
+                }
+                (None, Some(r)) => {
+                    merged_detail.insert(key.clone(), r.clone());
+                    detail_winners.insert(key.clone(), other.time);
+                }
+                (Some(l), Some(r)) => {
+                    if l != r {
+                        conflicting_detail_keys.push(key.clone());
+                    }
+                    let (value, winner) = if scalars_from_self {
+                        (l, self.time)
+                    } else {
+                        (r, other.time)
+                    };
+                    merged_detail.insert(key.clone(), value.clone());
+                    detail_winners.insert(key.clone(), winner);
+                }
+                (None, None) => unreachable!("key drawn from the union of both maps"),
+            }
+        }
+        conflicting_detail_keys.sort();
+
+        let mut merged = if scalars_from_self {
+            self.clone()
+        } else {
+            other.clone()
+        };
+        merged.detail = detail_map_to_xml(&merged_detail);
+
+        (
+            merged,
+            FieldProvenance {
+                scalar_winner,
+                detail_winners,
+                conflicting_detail_keys,
+            },
+        )
+    }
+
+    /// Returns whether this event's `stale` time has passed as of `now`.
+    ///
+    /// See the [`stale`](crate::stale) module for a [`StaleTracker`](crate::stale::StaleTracker)
+    /// that polls a whole set of tracked events for this.
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now >= self.stale
+    }
+
+    /// Returns how much longer this event has before it goes stale, as of
+    /// `now`. Negative once [`Self::is_stale`] is true.
+    pub fn time_to_live(&self, now: DateTime<Utc>) -> chrono::Duration {
+        self.stale - now
+    }
+}
+
+/// Serializes a parsed detail map back into a full `<detail>...</detail>`
+/// element, reusing [`xml_writer`](crate::xml_writer)'s escaping and
+/// nested-element support so the result re-parses identically via
+/// [`detail_parser::parse_detail_section`](crate::detail_parser::parse_detail_section),
+/// which expects that wrapping `<detail>` tag.
+fn detail_map_to_xml(detail: &HashMap<String, Value>) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .write_event(Event::Start(quick_xml::events::BytesStart::new("detail")))
+        .expect("writing to an in-memory buffer never fails");
+    let mut keys: Vec<_> = detail.keys().collect();
+    keys.sort();
+    for key in keys {
+        crate::xml_writer::write_detail_value(&mut writer, key, &detail[key])
+            .expect("writing to an in-memory buffer never fails");
+    }
+    writer
+        .write_event(Event::End(quick_xml::events::BytesEnd::new("detail")))
+        .expect("writing to an in-memory buffer never fails");
+    String::from_utf8(writer.into_inner().into_inner()).expect("quick-xml only writes valid UTF-8")
+}
+
+/// A TAK GeoChat message: the `<__chat>`/`<chatgrp>`/`<link>`/`<remarks>`
+/// detail structure real TAK chat clients exchange, as opposed to a flat
+/// `chat`/`chatroom` placeholder.
+///
+/// Construct with [`Self::new`] and serialize with [`Self::to_detail_xml`]
+/// to build a [`CotEvent`] (see [`CotEvent::new_chat_message`]), or recover
+/// one from an incoming event's detail section with [`Self::from_event`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoChat {
+    /// uid of the sender.
+    pub sender_uid: String,
+    /// Display callsign of the sender.
+    pub sender_callsign: String,
+    /// Display name of the chat room (e.g. "All Chat Rooms").
+    pub chatroom: String,
+    /// Stable uid of the chat room/group (`chatgrp`'s `id` attribute),
+    /// distinct from `chatroom`'s display name.
+    pub chat_group_uid: String,
+    /// uids of every participant in the room, including the sender.
+    pub participants: Vec<String>,
+    /// uid of a specific recipient this message is addressed to, for a
+    /// direct message rather than a broadcast to the whole room.
+    pub recipient_uid: Option<String>,
+    /// Message text.
+    pub message: String,
+    /// Unique id for this chat message, used by TAK clients to dedupe
+    /// retransmissions.
+    pub message_id: String,
+}
+
+impl GeoChat {
+    /// Creates a message broadcast to `chatroom`/`chat_group_uid`'s whole
+    /// room, with `participants` defaulted to just the sender and the room.
+    /// Use [`Self::to_recipient`] to address one participant directly
+    /// instead.
+    pub fn new(
+        sender_uid: &str,
+        sender_callsign: &str,
+        chatroom: &str,
+        chat_group_uid: &str,
+        message: &str,
+    ) -> Self {
+        Self {
+            sender_uid: sender_uid.to_string(),
+            sender_callsign: sender_callsign.to_string(),
+            chatroom: chatroom.to_string(),
+            chat_group_uid: chat_group_uid.to_string(),
+            participants: vec![sender_uid.to_string(), chat_group_uid.to_string()],
+            recipient_uid: None,
+            message: message.to_string(),
+            message_id: Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// Addresses this message to a specific participant uid instead of
+    /// broadcasting to the whole room.
+    pub fn to_recipient(mut self, recipient_uid: &str) -> Self {
+        self.recipient_uid = Some(recipient_uid.to_string());
+        self
+    }
+
+    /// Serializes this message into a `<detail>...</detail>` element: a
+    /// `__chat` element (`chatroom`/`groupOwner`/`senderCallsign`/`id`/
+    /// `messageId`, with a nested `chatgrp` listing `participants` as
+    /// `uid0`, `uid1`, ...), a `link` to the sender uid, and a `remarks`
+    /// element carrying `message` with `source`/`to`/`time`. `to` is the
+    /// recipient uid if this message was addressed with
+    /// [`Self::to_recipient`], otherwise the room's `chat_group_uid`.
+    pub fn to_detail_xml(&self, time: DateTime<Utc>) -> String {
+        let mut chatgrp = serde_json::Map::new();
+        for (i, uid) in self.participants.iter().enumerate() {
+            chatgrp.insert(format!("uid{i}"), Value::String(uid.clone()));
+        }
+        chatgrp.insert("id".to_string(), Value::String(self.chat_group_uid.clone()));
+
+        let mut chat = serde_json::Map::new();
+        chat.insert("chatroom".to_string(), Value::String(self.chatroom.clone()));
+        chat.insert("groupOwner".to_string(), Value::String("false".to_string()));
+        chat.insert(
+            "senderCallsign".to_string(),
+            Value::String(self.sender_callsign.clone()),
+        );
+        chat.insert("id".to_string(), Value::String(self.chat_group_uid.clone()));
+        chat.insert(
+            "messageId".to_string(),
+            Value::String(self.message_id.clone()),
+        );
+        chat.insert("chatgrp".to_string(), Value::Object(chatgrp));
+
+        let mut link = serde_json::Map::new();
+        link.insert("uid".to_string(), Value::String(self.sender_uid.clone()));
+
+        let to = self
+            .recipient_uid
+            .clone()
+            .unwrap_or_else(|| self.chat_group_uid.clone());
+        let mut remarks = serde_json::Map::new();
+        remarks.insert("source".to_string(), Value::String(self.sender_uid.clone()));
+        remarks.insert("to".to_string(), Value::String(to));
+        remarks.insert(
+            "time".to_string(),
+            Value::String(CotTime::from(time).to_rfc3339_millis()),
+        );
+        remarks.insert("_text".to_string(), Value::String(self.message.clone()));
+
+        let mut detail = HashMap::new();
+        detail.insert("__chat".to_string(), Value::Object(chat));
+        detail.insert("link".to_string(), Value::Object(link));
+        detail.insert("remarks".to_string(), Value::Object(remarks));
+        detail_map_to_xml(&detail)
+    }
+
+    /// Parses the `<__chat>`/`<chatgrp>`/`<link>`/`<remarks>` structure back
+    /// out of `event`'s detail section, the inverse of [`Self::to_detail_xml`].
+    /// Returns `None` if `event`'s detail has no `__chat` element.
+    pub fn from_event(event: &CotEvent) -> Option<GeoChat> {
+        let detail = crate::detail_parser::parse_detail_section(&event.detail);
+        let chat = detail.get("__chat")?.as_object()?;
+
+        let chatroom = chat.get("chatroom")?.as_str()?.to_string();
+        let chat_group_uid = chat
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or(&chatroom)
+            .to_string();
+        let sender_callsign = chat
+            .get("senderCallsign")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let message_id = chat
+            .get("messageId")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let mut participants: Vec<String> = chat
+            .get("chatgrp")
+            .and_then(Value::as_object)
+            .map(|chatgrp| {
+                let mut uids: Vec<(usize, String)> = chatgrp
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        let idx: usize = key.strip_prefix("uid")?.parse().ok()?;
+                        Some((idx, value.as_str()?.to_string()))
+                    })
+                    .collect();
+                uids.sort_by_key(|(idx, _)| *idx);
+                uids.into_iter().map(|(_, uid)| uid).collect()
+            })
+            .unwrap_or_default();
+        if participants.is_empty() {
+            participants.push(chat_group_uid.clone());
+        }
+
+        let sender_uid = detail
+            .get("link")
+            .and_then(Value::as_object)
+            .and_then(|link| link.get("uid"))
+            .and_then(Value::as_str)
+            .unwrap_or(&event.uid)
+            .to_string();
+
+        let remarks = detail.get("remarks").and_then(Value::as_object);
+        let message = remarks
+            .and_then(|r| r.get("_text"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let recipient_uid = remarks
+            .and_then(|r| r.get("to"))
+            .and_then(Value::as_str)
+            .filter(|to| *to != chat_group_uid)
+            .map(str::to_string);
+
+        Some(GeoChat {
+            sender_uid,
+            sender_callsign,
+            chatroom,
+            chat_group_uid,
+            participants,
+            recipient_uid,
+            message,
+            message_id,
+        })
+    }
+}
+
+/// The specific kind of CoT emergency alert.
+///
+/// "Emergency" isn't one event type but a family: a general 911 alert, a
+/// "ring the bell" panic alert, a geo-fence breach, and the explicit
+/// cancellation that closes out any of the above — each with its own
+/// event-type code, the same way small event libraries grow a dedicated
+/// enum for connection/authorization state transitions rather than
+/// overloading one string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmergencyType {
+    /// `b-a-o-pan` — general 911/emergency alert.
+    Alert911,
+    /// `b-a-o-tbl` — "ring the bell" panic alert.
+    RingTheBell,
+    /// `b-a-g` — geo-fence breach.
+    GeoFenceBreach,
+    /// `b-a-o-can` — explicit cancellation of a previously raised alert.
+    Cancel,
+}
+
+impl EmergencyType {
+    /// The CoT event-type code for this emergency kind, used as
+    /// [`CotEvent::event_type`].
+    pub fn cot_type(self) -> &'static str {
+        match self {
+            EmergencyType::Alert911 => "b-a-o-pan",
+            EmergencyType::RingTheBell => "b-a-o-tbl",
+            EmergencyType::GeoFenceBreach => "b-a-g",
+            EmergencyType::Cancel => "b-a-o-can",
+        }
+    }
+
+    /// The human-readable label used as the `emergency` detail element's
+    /// `type` attribute.
+    pub fn label(self) -> &'static str {
+        match self {
+            EmergencyType::Alert911 => "911",
+            EmergencyType::RingTheBell => "Ring The Bell",
+            EmergencyType::GeoFenceBreach => "Geo-fence Breach",
+            EmergencyType::Cancel => "Cancel",
+        }
+    }
+}
+
+/// Builds the `<detail>...</detail>` element for [`CotEvent::new_emergency`]
+/// and [`CotEvent::cancel_emergency`]: an `emergency` element with `type`
+/// (`emergency_type`'s [`EmergencyType::label`]) and `cancel` attributes
+/// carrying `message` as text, alongside a `contact` element with the
+/// sender's callsign.
+fn emergency_detail_xml(
+    emergency_type: EmergencyType,
+    cancel: bool,
+    callsign: &str,
+    message: &str,
+) -> String {
+    let mut emergency = serde_json::Map::new();
+    emergency.insert(
+        "type".to_string(),
+        Value::String(emergency_type.label().to_string()),
+    );
+    emergency.insert("cancel".to_string(), Value::String(cancel.to_string()));
+    if !message.is_empty() {
+        emergency.insert("_text".to_string(), Value::String(message.to_string()));
+    }
+
+    let mut contact = serde_json::Map::new();
+    contact.insert("callsign".to_string(), Value::String(callsign.to_string()));
+
+    let mut detail = HashMap::new();
+    detail.insert("emergency".to_string(), Value::Object(emergency));
+    detail.insert("contact".to_string(), Value::Object(contact));
+    detail_map_to_xml(&detail)
+}
+
+/// Earth radius, for [`RouteLeg::distance_meters`]'s great-circle
+/// calculation. WGS84's semi-major axis is close enough for route-planning
+/// distances; full ellipsoidal distance isn't worth the complexity here.
+const EARTH_RADIUS_METERS: f64 = 6_378_137.0;
+
+/// Great-circle distance between two points, via the haversine formula.
+fn haversine_distance_meters(a: &Point, b: &Point) -> f64 {
+    let lat1 = a.lat.to_radians();
+    let lat2 = b.lat.to_radians();
+    let dlat = (b.lat - a.lat).to_radians();
+    let dlon = (b.lon - a.lon).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
+}
+
+/// One leg of a multi-leg [`Route`], modeled like a trip itinerary: its own
+/// start/end timestamps and an ordered list of waypoints, rather than a
+/// single start/end pair for the whole route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteLeg {
+    /// Display name for this leg (e.g. `"Leg 1"`).
+    pub name: String,
+    /// When this leg begins.
+    pub start: DateTime<Utc>,
+    /// When this leg ends.
+    pub end: DateTime<Utc>,
+    /// Ordered waypoints along this leg.
+    pub points: Vec<Point>,
+}
+
+impl RouteLeg {
+    /// Creates a new leg from `start` to `end` over `points`, in order.
+    pub fn new(name: &str, start: DateTime<Utc>, end: DateTime<Utc>, points: Vec<Point>) -> Self {
+        Self { name: name.to_string(), start, end, points }
+    }
+
+    /// How long this leg is scheduled to take.
+    pub fn duration(&self) -> chrono::Duration {
+        self.end - self.start
+    }
+
+    /// Total great-circle distance over this leg's waypoints, summed
+    /// consecutive-pair by consecutive-pair.
+    pub fn distance_meters(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|pair| haversine_distance_meters(&pair[0], &pair[1]))
+            .sum()
+    }
+}
+
+/// A TAK route (`b-m-r`): an ordered sequence of [`RouteLeg`]s, each with
+/// its own timing, serialized into the `<detail>` `<__routeinfo>`/`<link>`
+/// structure TAK clients render as a multi-waypoint route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// uid of the route itself (distinct from the [`CotEvent::uid`] it's
+    /// attached to, the way [`GeoChat::chat_group_uid`] is distinct from a
+    /// chat event's uid).
+    pub uid: String,
+    /// Display name for the route.
+    pub name: String,
+    /// Ordered legs making up the route.
+    pub legs: Vec<RouteLeg>,
+}
+
+impl Route {
+    /// Creates a new route from `legs`, in order.
+    pub fn new(uid: &str, name: &str, legs: Vec<RouteLeg>) -> Self {
+        Self { uid: uid.to_string(), name: name.to_string(), legs }
+    }
+
+    /// Total great-circle distance over every leg.
+    pub fn total_distance_meters(&self) -> f64 {
+        self.legs.iter().map(RouteLeg::distance_meters).sum()
+    }
+
+    /// Total scheduled duration over every leg.
+    pub fn total_duration(&self) -> chrono::Duration {
+        self.legs.iter().map(RouteLeg::duration).fold(chrono::Duration::zero(), |a, b| a + b)
+    }
+
+    /// Serializes this route into a `<detail>...</detail>` element: an
+    /// `__routeinfo` wrapping an `__route` (`name`/`contact` callsign),
+    /// with one `leg` child per [`RouteLeg`] (`name`/`start`/`end`
+    /// attributes) and one `link` grandchild per waypoint (a `point`
+    /// attribute holding `"lat,lon,hae"`).
+    pub fn to_detail_xml(&self, callsign: &str) -> String {
+        let mut route_node = DetailNode::new("__route");
+        route_node.attrs.push(("name".to_string(), self.name.clone()));
+        route_node.attrs.push(("uid".to_string(), self.uid.clone()));
+
+        let mut contact = DetailNode::new("contact");
+        contact.attrs.push(("callsign".to_string(), callsign.to_string()));
+        route_node.children.push(contact);
+
+        for (i, leg) in self.legs.iter().enumerate() {
+            let mut leg_node = DetailNode::new("leg");
+            leg_node.attrs.push(("name".to_string(), leg.name.clone()));
+            leg_node.attrs.push(("uid".to_string(), format!("{}-leg-{i}", self.uid)));
+            leg_node
+                .attrs
+                .push(("start".to_string(), CotTime::from(leg.start).to_rfc3339_millis()));
+            leg_node
+                .attrs
+                .push(("end".to_string(), CotTime::from(leg.end).to_rfc3339_millis()));
+
+            for point in &leg.points {
+                let mut link = DetailNode::new("link");
+                link.attrs.push((
+                    "point".to_string(),
+                    format!("{},{},{}", point.lat, point.lon, point.hae),
+                ));
+                leg_node.children.push(link);
+            }
+            route_node.children.push(leg_node);
+        }
+
+        let mut routeinfo = DetailNode::new("__routeinfo");
+        routeinfo.children.push(route_node);
+        format!("<detail>{}</detail>", write_detail_tree(&[routeinfo]))
+    }
+}
+
+/// A tree of named `<detail>` child elements, built up incrementally and
+/// serialized with [`write_detail_tree`]'s proper XML attribute/text
+/// escaping — the structured alternative to interpolating user strings
+/// directly into a `format!` template, where a callsign or message
+/// containing `"`, `<`, `&`, or `>` would corrupt the markup or let the
+/// value inject its own elements.
+///
+/// [`Self::parse`] reads an existing `<detail>...</detail>` string back into
+/// the tree, so callers can add elements incrementally with [`Self::element`]
+/// instead of overwriting the whole `detail` string.
+#[derive(Debug, Clone, Default)]
+pub struct DetailBuilder {
+    nodes: Vec<DetailNode>,
+}
+
+impl DetailBuilder {
+    /// Creates an empty detail tree.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses an existing `<detail>...</detail>` string into a tree whose
+    /// children can be added to with [`Self::element`].
+    pub fn parse(xml: &str) -> Self {
+        Self {
+            nodes: parse_detail_tree(xml),
+        }
+    }
+
+    /// Appends a child element with the given attributes, in order, and no
+    /// text content.
+    pub fn element<S: Into<String>>(mut self, name: S, attrs: &[(&str, &str)]) -> Self {
+        let mut node = DetailNode::new(name);
+        for (key, value) in attrs {
+            node.attrs.push((key.to_string(), value.to_string()));
         }
+        self.nodes.push(node);
+        self
+    }
+
+    /// Appends a child element with text content and no attributes.
+    pub fn element_with_text<S1: Into<String>, S2: Into<String>>(
+        mut self,
+        name: S1,
+        text: S2,
+    ) -> Self {
+        let mut node = DetailNode::new(name);
+        node.text = Some(text.into());
+        self.nodes.push(node);
+        self
+    }
+
+    /// Appends an already-built node, e.g. one with nested children.
+    pub fn node(mut self, node: DetailNode) -> Self {
+        self.nodes.push(node);
+        self
+    }
+
+    /// Serializes the tree to a full `<detail>...</detail>` string, escaping
+    /// every attribute value and text content.
+    pub fn to_xml(&self) -> String {
+        format!("<detail>{}</detail>", write_detail_tree(&self.nodes))
     }
 }
 
@@ -625,23 +1798,50 @@ impl CotEventBuilder {
         self
     }
 
-    /// Sets the detail XML content.
+    /// Sets the producer's wall-clock UTC offset in seconds, so Ditto
+    /// round-trips can re-render this event's timestamps in their
+    /// originating offset instead of `Z`. See
+    /// [`CotEvent::tz_offset_secs`].
+    pub fn tz_offset_secs(mut self, tz_offset_secs: i32) -> Self {
+        self.event.tz_offset_secs = Some(tz_offset_secs);
+        self
+    }
+
+    /// Sets the detail XML content verbatim, as an escape hatch for
+    /// pre-formed XML the caller already trusts. Prefer [`Self::callsign`]/
+    /// [`Self::callsign_and_team`]/[`Self::detail_element`], which go
+    /// through [`DetailBuilder`] and escape values that could otherwise
+    /// corrupt or inject into the markup.
     pub fn detail<S: Into<String>>(mut self, detail: S) -> Self {
         self.event.detail = detail.into();
         self
     }
 
+    /// Appends an element to the existing detail tree instead of overwriting
+    /// it, so e.g. a `<contact>` set by [`Self::callsign`] and a
+    /// `<__group>` added here both survive.
+    pub fn detail_element<S: Into<String>>(mut self, name: S, attrs: &[(&str, &str)]) -> Self {
+        self.event.detail = DetailBuilder::parse(&self.event.detail)
+            .element(name, attrs)
+            .to_xml();
+        self
+    }
+
     /// Convenience method to set callsign in detail section.
     pub fn callsign<S: Into<String>>(mut self, callsign: S) -> Self {
         let callsign = callsign.into();
-        self.event.detail = format!("<detail><contact callsign=\"{}\"/></detail>", callsign);
+        self.event.detail = DetailBuilder::new()
+            .element("contact", &[("callsign", &callsign)])
+            .to_xml();
         self
     }
 
     /// Convenience method to set team in detail section.
     pub fn team<S: Into<String>>(mut self, team: S) -> Self {
         let team = team.into();
-        self.event.detail = format!("<detail><__group name=\"{}\"/></detail>", team);
+        self.event.detail = DetailBuilder::new()
+            .element("__group", &[("name", &team)])
+            .to_xml();
         self
     }
 
@@ -653,10 +1853,10 @@ impl CotEventBuilder {
     ) -> Self {
         let callsign = callsign.into();
         let team = team.into();
-        self.event.detail = format!(
-            "<detail><contact callsign=\"{}\"/><__group name=\"{}\"/></detail>",
-            callsign, team
-        );
+        self.event.detail = DetailBuilder::new()
+            .element("contact", &[("callsign", &callsign)])
+            .element("__group", &[("name", &team)])
+            .to_xml();
         self
     }
 
@@ -734,10 +1934,71 @@ impl PointBuilder {
         self
     }
 
+    /// Sets the Circular Error from an [`Accuracy`], so `ce` can be set as
+    /// [`Accuracy::Unknown`] instead of the raw `999999.0` sentinel.
+    pub fn ce_accuracy(mut self, ce: Accuracy) -> Self {
+        self.point.ce = ce.meters();
+        self
+    }
+
+    /// Sets the Linear Error from an [`Accuracy`], so `le` can be set as
+    /// [`Accuracy::Unknown`] instead of the raw `999999.0` sentinel.
+    pub fn le_accuracy(mut self, le: Accuracy) -> Self {
+        self.point.le = le.meters();
+        self
+    }
+
+    /// Sets both `ce` and `le` from a single RFC 1876-style [`LocPrecision`],
+    /// mirroring how a LOC record's SIZE field applies one sphere-diameter
+    /// uncertainty to an entity as a whole, since [`Point`] has no separate
+    /// size field to hold it.
+    pub fn size(mut self, size: LocPrecision) -> Self {
+        self.point.ce = size.meters();
+        self.point.le = size.meters();
+        self
+    }
+
     /// Builds the final Point instance.
     pub fn build(self) -> Point {
         self.point
     }
+
+    /// Builds the Point, validating and normalizing it first:
+    /// - `lat`, `lon`, and `hae` must be finite.
+    /// - `lat` must fall in `[-90, 90]`; it is not wrapped, since latitude
+    ///   has no periodic wraparound.
+    /// - `lon` is normalized into `[-180, 180]` by wrapping around the
+    ///   antimeridian rather than being rejected out of range.
+    pub fn try_build(mut self) -> Result<Point, PointError> {
+        for (field, value) in [
+            ("lat", self.point.lat),
+            ("lon", self.point.lon),
+            ("hae", self.point.hae),
+        ] {
+            if !value.is_finite() {
+                return Err(PointError::NonFinite { field, value });
+            }
+        }
+        if !(-90.0..=90.0).contains(&self.point.lat) {
+            return Err(PointError::InvalidLatitude(self.point.lat));
+        }
+        self.point.lon = normalize_longitude(self.point.lon);
+        Ok(self.point)
+    }
+}
+
+/// Wraps `lon` into `[-180, 180]` by repeatedly crossing the antimeridian,
+/// rather than rejecting values produced by e.g. accumulated dead-reckoning
+/// offsets that happen to cross it.
+fn normalize_longitude(lon: f64) -> f64 {
+    let wrapped = (lon + 180.0).rem_euclid(360.0) - 180.0;
+    // `rem_euclid` can return exactly -180.0 for inputs like 180.0; CoT/TAK
+    // convention prefers +180 as the canonical antimeridian value.
+    if wrapped == -180.0 {
+        180.0
+    } else {
+        wrapped
+    }
 }
 
 impl Default for PointBuilder {
@@ -753,8 +2014,9 @@ mod tests {
     #[test]
     fn test_location_update_creation() {
         let event = CotEvent::new_location_update(
-            "USER-123", "ALPHA-1", "Cyan", 34.12345, -118.12345, 150.0,
-        );
+            "USER-123", "ALPHA-1", "Cyan", 34.12345, -118.12345, 150.0, "+5m",
+        )
+        .unwrap();
 
         assert_eq!(event.uid, "USER-123");
         assert_eq!(event.event_type, "a-f-G-U-C");
@@ -764,6 +2026,26 @@ mod tests {
         assert_eq!(event.detail, "location update: callsign=ALPHA-1, team=Cyan");
     }
 
+    #[test]
+    fn to_xml_escapes_special_characters_in_event_attributes() {
+        let mut event = CotEvent::new_location_update(
+            "USER&123\"<evil>", "ALPHA-1", "Cyan", 34.12345, -118.12345, 150.0, "+5m",
+        )
+        .unwrap();
+        event.event_type = "a-f-G-U-C\"&".to_string();
+        event.how = "h-g-i-g-o'".to_string();
+
+        let xml = event.to_xml().unwrap();
+        assert!(xml.contains("uid=\"USER&amp;123&quot;&lt;evil&gt;\""));
+        assert!(xml.contains("type=\"a-f-G-U-C&quot;&amp;\""));
+        assert!(xml.contains("how=\"h-g-i-g-o&apos;\""));
+
+        let reparsed = CotEvent::from_xml(&xml).expect("escaped XML must still be valid");
+        assert_eq!(reparsed.uid, "USER&123\"<evil>");
+        assert_eq!(reparsed.event_type, "a-f-G-U-C\"&");
+        assert_eq!(reparsed.how, "h-g-i-g-o'");
+    }
+
     #[test]
     fn test_chat_message_creation() {
         let event = CotEvent::new_chat_message(
@@ -772,19 +2054,71 @@ mod tests {
             "Test message",
             "All Chat Rooms",
             "All Chat Rooms",
-        );
+            None,
+            "+5m",
+        )
+        .unwrap();
 
         assert_eq!(event.uid, "Chat-USER-123-");
         assert_eq!(event.event_type, "b-t-f");
         assert_eq!(event.point.lat, 0.0);
         assert_eq!(event.point.lon, 0.0);
         assert_eq!(event.point.hae, 0.0);
+        assert!(event.detail.contains("<__chat"));
+        assert!(event.detail.contains("chatroom=\"All Chat Rooms\""));
+        assert!(event.detail.contains("senderCallsign=\"ALPHA-1\""));
+        assert!(event.detail.contains("<chatgrp"));
+        assert!(event.detail.contains("<link uid=\"USER-123\"/>"));
+        assert!(event.detail.contains("Test message"));
+
+        let chat = GeoChat::from_event(&event).unwrap();
+        assert_eq!(chat.sender_uid, "USER-123");
+        assert_eq!(chat.sender_callsign, "ALPHA-1");
+        assert_eq!(chat.chatroom, "All Chat Rooms");
+        assert_eq!(chat.chat_group_uid, "All Chat Rooms");
+        assert_eq!(chat.message, "Test message");
+        assert_eq!(chat.recipient_uid, None);
         assert_eq!(
-            event.detail,
-            "<detail>chat from=ALPHA-1 room=All Chat Rooms msg=Test message</detail>"
+            chat.participants,
+            vec!["USER-123".to_string(), "All Chat Rooms".to_string()]
         );
     }
 
+    #[test]
+    fn test_new_chat_message_addresses_a_specific_recipient() {
+        let event = CotEvent::new_chat_message(
+            "USER-123",
+            "ALPHA-1",
+            "Private message",
+            "All Chat Rooms",
+            "All Chat Rooms",
+            Some("USER-456"),
+            "+5m",
+        )
+        .unwrap();
+
+        let chat = GeoChat::from_event(&event).unwrap();
+        assert_eq!(chat.recipient_uid, Some("USER-456".to_string()));
+        assert_eq!(chat.message, "Private message");
+    }
+
+    #[test]
+    fn geo_chat_round_trips_through_to_detail_xml_and_from_event() {
+        let chat = GeoChat::new("USER-1", "ALPHA-1", "Ops", "ops-room", "hello there")
+            .to_recipient("USER-2");
+        let mut event = CotEvent::default();
+        event.detail = chat.to_detail_xml(Utc::now());
+
+        let parsed = GeoChat::from_event(&event).unwrap();
+        assert_eq!(parsed.sender_uid, chat.sender_uid);
+        assert_eq!(parsed.sender_callsign, chat.sender_callsign);
+        assert_eq!(parsed.chatroom, chat.chatroom);
+        assert_eq!(parsed.chat_group_uid, chat.chat_group_uid);
+        assert_eq!(parsed.message, chat.message);
+        assert_eq!(parsed.message_id, chat.message_id);
+        assert_eq!(parsed.recipient_uid, chat.recipient_uid);
+    }
+
     #[test]
     fn test_emergency_creation() {
         let event = CotEvent::new_emergency(
@@ -792,16 +2126,339 @@ mod tests {
             "ALPHA-1",
             34.12345,
             -118.12345,
-            "Emergency-911",
+            EmergencyType::Alert911,
             "Need immediate assistance!",
-        );
+            "+5m",
+        )
+        .unwrap();
+
+        assert_eq!(event.uid, "USER-123");
+        assert_eq!(event.event_type, "b-a-o-pan");
+        assert!(event.detail.contains("<emergency"));
+        assert!(event.detail.contains("type=\"911\""));
+        assert!(event.detail.contains("cancel=\"false\""));
+        assert!(event.detail.contains("Need immediate assistance!"));
+        assert!(event.detail.contains("<contact callsign=\"ALPHA-1\"/>"));
+        assert!(!event.is_emergency_cancellation());
+    }
+
+    #[test]
+    fn test_cancel_emergency_produces_the_cancel_event_type() {
+        let event = CotEvent::cancel_emergency("USER-123", "ALPHA-1", "+5m").unwrap();
 
         assert_eq!(event.uid, "USER-123");
         assert_eq!(event.event_type, "b-a-o-can");
+        assert!(event.detail.contains("cancel=\"true\""));
+        assert!(event.is_emergency_cancellation());
+    }
+
+    #[test]
+    fn test_route_leg_distance_sums_consecutive_waypoint_pairs() {
+        let now = Utc::now();
+        let leg = RouteLeg::new(
+            "Leg 1",
+            now,
+            now + chrono::Duration::minutes(10),
+            vec![
+                Point::new(34.0, -118.0, 0.0),
+                Point::new(34.1, -118.0, 0.0),
+                Point::new(34.2, -118.0, 0.0),
+            ],
+        );
+        let one_leg_distance = haversine_distance_meters(&leg.points[0], &leg.points[1]);
+        assert!((leg.distance_meters() - one_leg_distance * 2.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_route_total_distance_sums_every_leg() {
+        let now = Utc::now();
+        let leg_a = RouteLeg::new(
+            "Leg 1",
+            now,
+            now + chrono::Duration::minutes(10),
+            vec![Point::new(34.0, -118.0, 0.0), Point::new(34.1, -118.0, 0.0)],
+        );
+        let leg_b = RouteLeg::new(
+            "Leg 2",
+            leg_a.end,
+            leg_a.end + chrono::Duration::minutes(10),
+            vec![Point::new(34.1, -118.0, 0.0), Point::new(34.2, -118.0, 0.0)],
+        );
+        let route = Route::new("ROUTE-1", "Patrol Route", vec![leg_a.clone(), leg_b.clone()]);
+        assert!(
+            (route.total_distance_meters() - (leg_a.distance_meters() + leg_b.distance_meters()))
+                .abs()
+                < 1e-6
+        );
+        assert_eq!(route.total_duration(), chrono::Duration::minutes(20));
+    }
+
+    #[test]
+    fn test_new_route_builds_a_b_m_r_event_with_routeinfo_detail() {
+        let now = Utc::now();
+        let leg = RouteLeg::new(
+            "Leg 1",
+            now,
+            now + chrono::Duration::minutes(10),
+            vec![Point::new(34.0, -118.0, 0.0), Point::new(34.1, -118.0, 0.0)],
+        );
+        let route = Route::new("ROUTE-1", "Patrol Route", vec![leg]);
+        let event = CotEvent::new_route("USER-123", "ALPHA-1", &route, "+1h").unwrap();
+
+        assert_eq!(event.uid, "USER-123");
+        assert_eq!(event.event_type, "b-m-r");
+        assert_eq!(event.point, Point::new(34.0, -118.0, 0.0));
+        assert!(event.detail.contains("<__routeinfo>"));
+        assert!(event.detail.contains("<__route"));
+        assert!(event.detail.contains("name=\"Patrol Route\""));
+        assert!(event.detail.contains("<leg"));
+        assert!(event.detail.contains("<link point=\"34.1,-118,0\"/>"));
+    }
+
+    #[test]
+    fn test_new_deletion_builds_a_tombstone_event() {
+        let event = CotEvent::new_deletion("USER-123", "TRACK-456", "+1h").unwrap();
+
+        assert_eq!(event.uid, "USER-123");
+        assert_eq!(event.event_type, "t-x-d-d");
+        assert!(event.detail.contains("<link uid=\"TRACK-456\" relation=\"p-p\"/>"));
+        assert!(event.detail.contains("<__forcedelete/>"));
+    }
+
+    #[test]
+    fn test_parse_relative_duration_accepts_now() {
         assert_eq!(
-            event.detail,
-            "<detail>emergency: type=Emergency-911 msg=Need immediate assistance!</detail>"
+            CotEvent::parse_relative_duration("now").unwrap(),
+            chrono::Duration::zero()
+        );
+        assert_eq!(
+            CotEvent::parse_relative_duration("NOW").unwrap(),
+            chrono::Duration::zero()
+        );
+    }
+
+    #[test]
+    fn test_parse_relative_duration_sums_repeated_unit_groups() {
+        assert_eq!(
+            CotEvent::parse_relative_duration("+5m").unwrap(),
+            chrono::Duration::minutes(5)
+        );
+        assert_eq!(
+            CotEvent::parse_relative_duration("+2h30m").unwrap(),
+            chrono::Duration::hours(2) + chrono::Duration::minutes(30)
+        );
+        assert_eq!(
+            CotEvent::parse_relative_duration("-10s").unwrap(),
+            chrono::Duration::seconds(-10)
+        );
+        assert_eq!(
+            CotEvent::parse_relative_duration("1w2d").unwrap(),
+            chrono::Duration::weeks(1) + chrono::Duration::days(2)
+        );
+    }
+
+    #[test]
+    fn test_parse_relative_duration_accepts_iso8601_durations() {
+        assert_eq!(
+            CotEvent::parse_relative_duration("PT5M").unwrap(),
+            chrono::Duration::minutes(5)
+        );
+        assert_eq!(
+            CotEvent::parse_relative_duration("P1DT2H30M").unwrap(),
+            chrono::Duration::days(1) + chrono::Duration::hours(2) + chrono::Duration::minutes(30)
+        );
+    }
+
+    #[test]
+    fn test_parse_relative_duration_rejects_unrecognized_input() {
+        assert!(matches!(
+            CotEvent::parse_relative_duration("tomorrow"),
+            Err(CotError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            CotEvent::parse_relative_duration("+5"),
+            Err(CotError::InvalidFormat(_))
+        ));
+        assert!(matches!(
+            CotEvent::parse_relative_duration("+5x"),
+            Err(CotError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_datetime_falls_back_to_flexible_formats() {
+        use crate::timestamp::DateBound;
+
+        let missing_seconds =
+            CotEvent::parse_datetime("time", "2024-01-15T10:30+00:00", DateBound::Floor).unwrap();
+        assert_eq!(missing_seconds.to_rfc3339(), "2024-01-15T10:30:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_datetime_pads_a_bare_date_per_bound() {
+        use crate::timestamp::DateBound;
+
+        let floored = CotEvent::parse_datetime("start", "2024-01-15", DateBound::Floor).unwrap();
+        let ceiled = CotEvent::parse_datetime("stale", "2024-01-15", DateBound::Ceil).unwrap();
+
+        assert_eq!(floored.to_rfc3339(), "2024-01-15T00:00:00+00:00");
+        assert_eq!(ceiled.to_rfc3339(), "2024-01-15T23:59:59+00:00");
+    }
+
+    #[test]
+    fn test_parse_datetime_reports_the_failing_field_name() {
+        use crate::timestamp::DateBound;
+
+        let err = CotEvent::parse_datetime("stale", "not a timestamp", DateBound::Ceil).unwrap_err();
+        assert!(matches!(
+            err,
+            CotError::InvalidDateTime { field, .. } if field == "stale"
+        ));
+    }
+
+    #[test]
+    fn test_parse_datetime_accepts_millisecond_microsecond_and_no_fraction_forms() {
+        use crate::timestamp::DateBound;
+
+        let no_fraction =
+            CotEvent::parse_datetime("time", "2024-01-15T10:30:00Z", DateBound::Floor).unwrap();
+        let millis =
+            CotEvent::parse_datetime("time", "2024-01-15T10:30:00.500Z", DateBound::Floor)
+                .unwrap();
+        let micros =
+            CotEvent::parse_datetime("time", "2024-01-15T10:30:00.500000Z", DateBound::Floor)
+                .unwrap();
+
+        assert_eq!(millis, micros);
+        assert!(millis > no_fraction);
+    }
+
+    #[test]
+    fn test_to_xml_defaults_to_whole_second_precision_with_a_z_suffix() {
+        let event = CotEvent::builder()
+            .uid("ALPHA-1")
+            .event_type("a-f-G-U-C")
+            .location(34.0, -118.0, 0.0)
+            .timing(
+                DateTime::parse_from_rfc3339("2024-01-15T10:30:00.500Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+                Utc::now(),
+                Utc::now(),
+            )
+            .build();
+
+        let xml = event.to_xml().unwrap();
+        assert!(xml.contains("time=\"2024-01-15T10:30:00Z\""));
+    }
+
+    #[test]
+    fn test_to_xml_with_precision_forces_the_requested_fractional_digits() {
+        let event = CotEvent::builder()
+            .uid("ALPHA-1")
+            .event_type("a-f-G-U-C")
+            .location(34.0, -118.0, 0.0)
+            .timing(
+                DateTime::parse_from_rfc3339("2024-01-15T10:30:00.5Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+                Utc::now(),
+                Utc::now(),
+            )
+            .build();
+
+        let xml = event.to_xml_with_precision(SecondsFormat::Millis).unwrap();
+        assert!(xml.contains("time=\"2024-01-15T10:30:00.500Z\""));
+    }
+
+    #[test]
+    fn test_from_xml_accepts_a_bare_date_stale_attribute() {
+        let xml = r#"<event version="2.0" uid="TEST-1" type="a-f-G-U-C" time="2024-01-15T10:30:00Z" start="2024-01-15T10:30:00Z" stale="2024-01-16" how="h-g-i-g-o"><point lat="0" lon="0" hae="0" ce="0" le="0"/></event>"#;
+        let event = CotEvent::from_xml(xml).unwrap();
+        assert_eq!(event.stale.to_rfc3339(), "2024-01-16T23:59:59+00:00");
+    }
+
+    #[test]
+    fn test_from_xml_lenient_substitutes_defaults_for_bad_numeric_point_fields() {
+        let xml = concat!(
+            r#"<event version="2.0" uid="TEST-1" type="a-f-G-U-C" time="2024-01-15T10:30:00Z" "#,
+            r#"start="2024-01-15T10:30:00Z" stale="2024-01-15T10:35:00Z" how="h-g-i-g-o">"#,
+            r#"<point lat="not-a-number" lon="-118.0" hae="0" ce="0" le="0"/></event>"#,
+        );
+        let (event, warnings) = CotEvent::from_xml_lenient(xml);
+        assert_eq!(event.point.lat, 0.0);
+        assert_eq!(event.point.lon, -118.0);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "lat");
+    }
+
+    #[test]
+    fn test_from_xml_lenient_substitutes_a_default_for_a_bad_stale_timestamp() {
+        let xml = concat!(
+            r#"<event version="2.0" uid="TEST-1" type="a-f-G-U-C" time="2024-01-15T10:30:00Z" "#,
+            r#"start="2024-01-15T10:30:00Z" stale="not-a-timestamp" how="h-g-i-g-o">"#,
+            r#"<point lat="0" lon="0" hae="0" ce="0" le="0"/></event>"#,
+        );
+        let (event, warnings) = CotEvent::from_xml_lenient(xml);
+        assert!(event.stale > event.time);
+        assert!(warnings.iter().any(|w| w.field == "stale"));
+    }
+
+    #[test]
+    fn test_from_xml_lenient_returns_no_warnings_for_well_formed_input() {
+        let xml = concat!(
+            r#"<event version="2.0" uid="TEST-1" type="a-f-G-U-C" time="2024-01-15T10:30:00Z" "#,
+            r#"start="2024-01-15T10:30:00Z" stale="2024-01-15T10:35:00Z" how="h-g-i-g-o">"#,
+            r#"<point lat="34.0" lon="-118.0" hae="0" ce="0" le="0"/><detail/></event>"#,
         );
+        let (event, warnings) = CotEvent::from_xml_lenient(xml);
+        assert!(warnings.is_empty());
+        assert_eq!(event.uid, "TEST-1");
+        assert_eq!(event.point.lat, 34.0);
+    }
+
+    #[test]
+    fn test_from_xml_reports_the_line_and_column_of_malformed_markup() {
+        let xml = "<event version=\"2.0\" uid=\"TEST-1\">\n    <point lat=\"0\" lon=\"0\"></wrong>\n</event>";
+        let err = CotEvent::from_xml(xml).unwrap_err();
+        match err {
+            CotError::XmlParse {
+                line,
+                column,
+                context,
+                ..
+            } => {
+                assert_eq!(line, 2);
+                assert!(column > 1);
+                assert!(context.contains('^'));
+            }
+            other => panic!("expected CotError::XmlParse, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_from_xml_reports_position_for_a_malformed_attribute_value() {
+        let xml = concat!(
+            r#"<event version="2.0" uid="TEST&bad;1" type="a-f-G" time="2024-01-15T10:30:00Z" "#,
+            r#"start="2024-01-15T10:30:00Z" stale="2024-01-15T10:35:00Z" how="h-g-i-g-o">"#,
+            r#"<point lat="0" lon="0" hae="0" ce="0" le="0"/></event>"#,
+        );
+        let err = CotEvent::from_xml(xml).unwrap_err();
+        match err {
+            CotError::XmlParse { line, context, .. } => {
+                assert_eq!(line, 1);
+                assert!(context.contains('^'));
+            }
+            other => panic!("expected CotError::XmlParse, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_new_location_update_rejects_an_invalid_stale_interval() {
+        let result = CotEvent::new_location_update(
+            "USER-123", "ALPHA-1", "Cyan", 34.12345, -118.12345, 150.0, "whenever",
+        );
+        assert!(matches!(result, Err(CotError::InvalidFormat(_))));
     }
 
     #[test]
@@ -866,6 +2523,48 @@ mod tests {
         assert_eq!(event.detail, "<detail><custom field=\"value\"/></detail>");
     }
 
+    #[test]
+    fn test_callsign_and_team_escapes_xml_special_characters() {
+        let event = CotEvent::builder()
+            .uid("TEST-ESCAPE")
+            .callsign_and_team("BR<A>VO", "Bl&ue")
+            .build();
+
+        assert!(!event.detail.contains("BR<A>VO"));
+        let detail = event.detail_struct();
+        assert_eq!(
+            detail.contact.unwrap().callsign,
+            Some("BR<A>VO".to_string())
+        );
+        assert_eq!(detail.group.unwrap().name, Some("Bl&ue".to_string()));
+    }
+
+    #[test]
+    fn test_detail_element_appends_instead_of_overwriting() {
+        let event = CotEvent::builder()
+            .uid("TEST-INCREMENTAL")
+            .callsign("ALPHA-1")
+            .detail_element("status", &[("battery", "80")])
+            .build();
+
+        let detail = event.detail_struct();
+        assert_eq!(detail.contact.unwrap().callsign, Some("ALPHA-1".to_string()));
+        assert_eq!(detail.status.unwrap().battery, Some("80".to_string()));
+    }
+
+    #[test]
+    fn test_detail_builder_parses_existing_detail_back_into_the_tree() {
+        let xml = "<detail><contact callsign=\"ALPHA-1\"/></detail>";
+        let rebuilt = DetailBuilder::parse(xml)
+            .element("__group", &[("name", "Cyan")])
+            .to_xml();
+
+        assert_eq!(
+            rebuilt,
+            "<detail><contact callsign=\"ALPHA-1\"/><__group name=\"Cyan\"/></detail>"
+        );
+    }
+
     #[test]
     fn test_point_builder() {
         let point = Point::builder()
@@ -913,4 +2612,137 @@ mod tests {
         assert_eq!(point2.ce, 5.0);
         assert_eq!(point2.le, 10.0);
     }
+
+    #[test]
+    fn test_try_build_accepts_valid_coordinates() {
+        let point = Point::builder()
+            .coordinates(34.0526, -118.2437, 100.0)
+            .try_build()
+            .unwrap();
+        assert_eq!(point.lat, 34.0526);
+        assert_eq!(point.lon, -118.2437);
+    }
+
+    #[test]
+    fn test_try_build_rejects_out_of_range_latitude() {
+        let err = Point::builder()
+            .coordinates(91.0, 0.0, 0.0)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(err, PointError::InvalidLatitude(91.0));
+    }
+
+    #[test]
+    fn test_try_build_rejects_non_finite_values() {
+        let err = Point::builder()
+            .coordinates(f64::NAN, 0.0, 0.0)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            PointError::NonFinite {
+                field: "lat",
+                value: f64::NAN,
+            }
+        );
+    }
+
+    #[test]
+    fn test_try_build_normalizes_longitude_wraparound() {
+        let point = Point::builder()
+            .coordinates(0.0, 190.0, 0.0)
+            .try_build()
+            .unwrap();
+        assert_eq!(point.lon, -170.0);
+
+        let antimeridian = Point::builder()
+            .coordinates(0.0, 180.0, 0.0)
+            .try_build()
+            .unwrap();
+        assert_eq!(antimeridian.lon, 180.0);
+    }
+
+    #[test]
+    fn test_accuracy_unknown_maps_to_the_sentinel() {
+        let point = Point::builder()
+            .coordinates(0.0, 0.0, 0.0)
+            .ce_accuracy(Accuracy::Unknown)
+            .le_accuracy(Accuracy::Meters(5.0))
+            .build();
+        assert_eq!(point.ce, 999999.0);
+        assert_eq!(point.le, 5.0);
+        assert_eq!(Accuracy::from_meters(point.ce), Accuracy::Unknown);
+        assert_eq!(Accuracy::from_meters(point.le), Accuracy::Meters(5.0));
+    }
+
+    #[test]
+    fn test_loc_precision_round_trips_through_meters_and_byte() {
+        let precision = LocPrecision::new(3, 2);
+        assert_eq!(precision.meters(), 3.0);
+        assert_eq!(LocPrecision::from_meters(3.0), precision);
+        assert_eq!(LocPrecision::from_loc_byte(precision.to_loc_byte()), precision);
+    }
+
+    #[test]
+    fn test_point_builder_size_sets_ce_and_le_together() {
+        let point = Point::builder()
+            .coordinates(0.0, 0.0, 0.0)
+            .size(LocPrecision::new(1, 0))
+            .build();
+        assert_eq!(point.ce, 0.01);
+        assert_eq!(point.le, 0.01);
+    }
+
+    #[test]
+    fn test_merge_takes_scalar_fields_from_the_newer_side() {
+        let older = CotEvent::builder()
+            .uid("USER-1")
+            .event_type("a-f-G-U-C")
+            .location(1.0, 2.0, 3.0)
+            .timing(Utc::now(), Utc::now(), Utc::now())
+            .build();
+        let mut newer = older.clone();
+        newer.time = older.time + chrono::Duration::seconds(60);
+        newer.event_type = "a-f-G-U-T".to_string();
+        newer.point = Point::new(9.0, 9.0, 9.0);
+
+        let merged = older.merge(&newer);
+        assert_eq!(merged.event_type, "a-f-G-U-T");
+        assert_eq!(merged.point, newer.point);
+    }
+
+    #[test]
+    fn test_merge_keeps_non_conflicting_detail_keys_from_both_sides() {
+        let mut local = CotEvent::builder().uid("USER-1").build();
+        local.detail = r#"<detail><contact callsign="ALPHA-1"/></detail>"#.to_string();
+
+        let mut remote = local.clone();
+        remote.time = local.time + chrono::Duration::seconds(60);
+        remote.detail = r#"<detail><status readiness="true"/></detail>"#.to_string();
+
+        let merged = local.merge(&remote);
+        let detail = crate::detail_parser::parse_detail_section(&merged.detail);
+        assert_eq!(detail["contact"]["callsign"], "ALPHA-1");
+        assert_eq!(detail["status"]["readiness"], "true");
+    }
+
+    #[test]
+    fn test_merge_with_provenance_reports_a_conflicting_detail_key() {
+        let mut local = CotEvent::builder().uid("USER-1").build();
+        local.detail = r#"<detail><status battery="50"/></detail>"#.to_string();
+
+        let mut remote = local.clone();
+        remote.time = local.time + chrono::Duration::seconds(60);
+        remote.detail = r#"<detail><status battery="90"/></detail>"#.to_string();
+
+        let (merged, provenance) = local.merge_with_provenance(&remote);
+        let detail = crate::detail_parser::parse_detail_section(&merged.detail);
+        assert_eq!(detail["status"]["battery"], "90");
+        assert_eq!(
+            provenance.conflicting_detail_keys,
+            vec!["status".to_string()]
+        );
+        assert_eq!(provenance.detail_winners["status"], remote.time);
+        assert_eq!(provenance.scalar_winner, remote.time);
+    }
 }
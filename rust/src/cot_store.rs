@@ -0,0 +1,288 @@
+//! Embedded persistence/outbox layer for [`CotEvent`]s, so a relay can
+//! durably queue mesh traffic across a restart instead of keeping it only
+//! in an in-memory [`StaleTracker`](crate::stale::StaleTracker).
+//!
+//! Gated behind the `cot-store` Cargo feature (off by default, since most
+//! consumers only need the in-memory tracker). [`CotStore`] wraps a `sled`
+//! embedded key-value database, the same way a number of projects have
+//! moved event/reminder storage onto `sled` to get durable, queryable state
+//! without a server-backed database dependency. Events are deduplicated by
+//! `uid`, keeping the version with the newest `time` — the same
+//! last-writer-wins rule [`StaleTracker::ingest`](crate::stale::StaleTracker::ingest)
+//! and [`CotEvent::merge`] use. Two secondary indexes are maintained
+//! alongside the primary table: one keyed by event-type prefix, so
+//! [`CotStore::latest_by_type`] can answer "give me every current track of
+//! this kind" without a full scan, and one keyed by `stale` time, so
+//! [`CotStore::evict_stale`] can drop expired records cheaply. An outbox
+//! table tracks events not yet acknowledged by a peer; [`CotStore::drain_outbox`]
+//! hands back and acks the current batch, so a caller can replay it after a
+//! reconnect simply by not calling `drain_outbox` until the peer is back.
+#![cfg(feature = "cot-store")]
+
+use crate::cot_events::CotEvent;
+use crate::error::CotError;
+use chrono::{DateTime, Utc};
+
+/// An embedded, durable store of [`CotEvent`]s backed by `sled`.
+///
+/// Cloning a `CotStore` is cheap and shares the same underlying database,
+/// mirroring `sled::Db`'s own `Clone` semantics.
+#[derive(Clone)]
+pub struct CotStore {
+    events: sled::Tree,
+    by_type: sled::Tree,
+    by_stale: sled::Tree,
+    outbox: sled::Tree,
+}
+
+impl CotStore {
+    /// Opens (creating if needed) a `CotStore` at `path` on disk.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, CotError> {
+        let db = sled::open(path)?;
+        Self::from_db(&db)
+    }
+
+    /// Opens a `CotStore` backed by an already-open `sled::Db`, so a caller
+    /// that shares one database across several tables can reuse it here.
+    pub fn from_db(db: &sled::Db) -> Result<Self, CotError> {
+        Ok(Self {
+            events: db.open_tree("cot_events")?,
+            by_type: db.open_tree("cot_events_by_type")?,
+            by_stale: db.open_tree("cot_events_by_stale")?,
+            outbox: db.open_tree("cot_events_outbox")?,
+        })
+    }
+
+    /// Inserts `event`, keeping it only if it's newer than any previously
+    /// stored version of the same `uid` — the same rule
+    /// [`StaleTracker::ingest`](crate::stale::StaleTracker::ingest) uses.
+    /// A newly-inserted or updated event is added to the outbox.
+    pub fn insert(&self, event: &CotEvent) -> Result<(), CotError> {
+        if let Some(existing) = self.get(&event.uid)? {
+            if existing.time >= event.time {
+                return Ok(());
+            }
+            self.by_type
+                .remove(Self::type_key(&existing.event_type, &existing.uid))?;
+            self.by_stale
+                .remove(Self::stale_key(existing.stale, &existing.uid))?;
+        }
+
+        let bytes = serde_json::to_vec(event)?;
+        self.events.insert(event.uid.as_str(), bytes)?;
+        self.by_type
+            .insert(Self::type_key(&event.event_type, &event.uid), &[])?;
+        self.by_stale
+            .insert(Self::stale_key(event.stale, &event.uid), &[])?;
+        self.outbox.insert(event.uid.as_str(), &[])?;
+        Ok(())
+    }
+
+    /// Returns the currently stored event for `uid`, if any.
+    pub fn get(&self, uid: &str) -> Result<Option<CotEvent>, CotError> {
+        match self.events.get(uid)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns every currently stored event whose `event_type` starts with
+    /// `prefix`, via the event-type index rather than a full table scan.
+    pub fn latest_by_type(&self, prefix: &str) -> Result<Vec<CotEvent>, CotError> {
+        let mut events = Vec::new();
+        for entry in self.by_type.scan_prefix(prefix.as_bytes()) {
+            let (key, _) = entry?;
+            let uid = Self::uid_from_type_key(&key, prefix);
+            if let Some(event) = self.get(uid)? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Removes and returns every event still in the outbox (inserted or
+    /// updated since the last `drain_outbox` call), acknowledging them in
+    /// the process. Inserting a drained event again does not put it back in
+    /// the outbox: [`Self::insert`] ignores an event whose `time` is not
+    /// newer than the stored version, so only a newer version is re-queued.
+    pub fn drain_outbox(&self) -> Result<Vec<CotEvent>, CotError> {
+        let mut events = Vec::new();
+        for entry in self.outbox.iter() {
+            let (key, _) = entry?;
+            self.outbox.remove(&key)?;
+            if let Some(event) = self.get(std::str::from_utf8(&key).unwrap_or_default())? {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Evicts every stored event whose `stale` time is at or before `now`,
+    /// via the stale-time index, and returns the uids removed.
+    pub fn evict_stale(&self, now: DateTime<Utc>) -> Result<Vec<String>, CotError> {
+        let upper = Self::stale_key(now, "\u{10FFFF}");
+        let mut evicted = Vec::new();
+        for entry in self.by_stale.range(..=upper) {
+            let (key, _) = entry?;
+            let uid = Self::uid_from_stale_key(&key).to_string();
+            if let Some(event) = self.get(&uid)? {
+                self.events.remove(uid.as_str())?;
+                self.by_type
+                    .remove(Self::type_key(&event.event_type, &uid))?;
+                self.outbox.remove(uid.as_str())?;
+            }
+            self.by_stale.remove(&key)?;
+            evicted.push(uid);
+        }
+        Ok(evicted)
+    }
+
+    /// Returns the number of events currently stored.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns whether the store currently holds no events.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn type_key(event_type: &str, uid: &str) -> Vec<u8> {
+        let mut key = event_type.as_bytes().to_vec();
+        key.push(0);
+        key.extend_from_slice(uid.as_bytes());
+        key
+    }
+
+    fn uid_from_type_key(key: &[u8], prefix: &str) -> &str {
+        let rest = &key[prefix.len()..];
+        let rest = rest.iter().position(|&b| b == 0).map_or(rest, |i| &rest[i + 1..]);
+        std::str::from_utf8(rest).unwrap_or_default()
+    }
+
+    fn stale_key(stale: DateTime<Utc>, uid: &str) -> Vec<u8> {
+        let mut key = stale.timestamp_micros().to_be_bytes().to_vec();
+        key.push(0);
+        key.extend_from_slice(uid.as_bytes());
+        key
+    }
+
+    fn uid_from_stale_key(key: &[u8]) -> &str {
+        std::str::from_utf8(&key[9..]).unwrap_or_default()
+    }
+}
+
+impl From<sled::Error> for CotError {
+    fn from(err: sled::Error) -> Self {
+        CotError::Store(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn event_with(uid: &str, event_type: &str, time: DateTime<Utc>, stale: DateTime<Utc>) -> CotEvent {
+        let mut event = CotEvent::default();
+        event.uid = uid.to_string();
+        event.event_type = event_type.to_string();
+        event.time = time;
+        event.start = time;
+        event.stale = stale;
+        event
+    }
+
+    fn open_temp_store() -> CotStore {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        CotStore::from_db(&db).unwrap()
+    }
+
+    #[test]
+    fn insert_then_get_round_trips_an_event() {
+        let store = open_temp_store();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let event = event_with("UID-1", "a-f-G-U-C", now, now + Duration::minutes(5));
+
+        store.insert(&event).unwrap();
+
+        assert_eq!(store.get("UID-1").unwrap().unwrap().event_type, "a-f-G-U-C");
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn insert_ignores_an_older_update_arriving_after_a_newer_one() {
+        let store = open_temp_store();
+        let older = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let newer = Utc.with_ymd_and_hms(2024, 1, 1, 12, 5, 0).unwrap();
+
+        store
+            .insert(&event_with("UID-1", "a-f-G-U-C", newer, newer + Duration::minutes(1)))
+            .unwrap();
+        store
+            .insert(&event_with("UID-1", "a-f-G-U-C", older, older + Duration::minutes(1)))
+            .unwrap();
+
+        assert_eq!(store.get("UID-1").unwrap().unwrap().time, newer);
+    }
+
+    #[test]
+    fn latest_by_type_filters_via_the_event_type_prefix_index() {
+        let store = open_temp_store();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        store
+            .insert(&event_with("UNIT-1", "a-f-G-U-C", now, now + Duration::minutes(5)))
+            .unwrap();
+        store
+            .insert(&event_with("CHAT-1", "b-t-f", now, now + Duration::minutes(5)))
+            .unwrap();
+
+        let ground_units = store.latest_by_type("a-f-G").unwrap();
+
+        assert_eq!(ground_units.len(), 1);
+        assert_eq!(ground_units[0].uid, "UNIT-1");
+    }
+
+    #[test]
+    fn drain_outbox_returns_pending_events_only_once() {
+        let store = open_temp_store();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        store
+            .insert(&event_with("UID-1", "a-f-G-U-C", now, now + Duration::minutes(5)))
+            .unwrap();
+
+        let first_drain = store.drain_outbox().unwrap();
+        let second_drain = store.drain_outbox().unwrap();
+
+        assert_eq!(first_drain.len(), 1);
+        assert_eq!(first_drain[0].uid, "UID-1");
+        assert!(second_drain.is_empty());
+        assert!(store.get("UID-1").unwrap().is_some());
+    }
+
+    #[test]
+    fn evict_stale_removes_expired_events_and_keeps_live_ones() {
+        let store = open_temp_store();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        store
+            .insert(&event_with(
+                "DROPPED",
+                "a-f-G-U-C",
+                now - Duration::hours(1),
+                now - Duration::minutes(1),
+            ))
+            .unwrap();
+        store
+            .insert(&event_with("LIVE", "a-f-G-U-C", now, now + Duration::minutes(5)))
+            .unwrap();
+
+        let evicted = store.evict_stale(now).unwrap();
+
+        assert_eq!(evicted, vec!["DROPPED".to_string()]);
+        assert!(store.get("DROPPED").unwrap().is_none());
+        assert!(store.get("LIVE").unwrap().is_some());
+        assert_eq!(store.len(), 1);
+    }
+}
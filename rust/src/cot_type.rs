@@ -0,0 +1,376 @@
+//! Structured parser for the dash-delimited MIL-STD-2525/CoT event-type
+//! taxonomy (e.g. `"a-f-G-U-C"`, `"b-t-f"`, `"b-a-o-can"`).
+//!
+//! [`CotEvent::event_type`](crate::cot_events::CotEvent::event_type) is a
+//! plain `String`, so filtering or routing on it today means substring
+//! hacks against an opaque value. [`CotType`] instead decomposes that
+//! string into its positional fields — top-level [`Category`], and for
+//! atoms the [`Affiliation`] and battle [`Dimension`] — the same way small
+//! event crates promote stringly-typed event names into parsed enums for
+//! safe dispatch, while keeping [`CotType::matches_prefix`] and [`Display`]
+//! for callers that still want to treat the type as a dash-separated path.
+
+use std::fmt;
+
+/// Top-level CoT type category: the first dash-delimited token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    /// `a` — atom: a physical object such as a unit, vehicle, or sensor.
+    Atom,
+    /// `b` — bit: an event, such as a chat message or emergency alert.
+    Event,
+    /// `t` — tasking: an order or request directed at a unit.
+    Tasking,
+    /// `r` — reply: a response to a tasking request.
+    Reply,
+    /// `c` — capability: a system capability advertisement.
+    Capability,
+    /// Any other top-level token this taxonomy doesn't define.
+    Other(char),
+}
+
+impl Category {
+    fn from_char(c: char) -> Self {
+        match c {
+            'a' => Category::Atom,
+            'b' => Category::Event,
+            't' => Category::Tasking,
+            'r' => Category::Reply,
+            'c' => Category::Capability,
+            other => Category::Other(other),
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Category::Atom => 'a',
+            Category::Event => 'b',
+            Category::Tasking => 't',
+            Category::Reply => 'r',
+            Category::Capability => 'c',
+            Category::Other(c) => c,
+        }
+    }
+}
+
+/// Affiliation of an atom (the second token of an `a-...` type).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affiliation {
+    /// `f` — friend
+    Friend,
+    /// `h` — hostile
+    Hostile,
+    /// `n` — neutral
+    Neutral,
+    /// `u` — unknown
+    Unknown,
+    /// `p` — pending
+    Pending,
+    /// `a` — assumed friend
+    AssumedFriend,
+    /// `s` — suspect
+    Suspect,
+    /// `j` — joker
+    Joker,
+    /// `k` — faker
+    Faker,
+    /// `o` — none
+    None,
+    /// Any other affiliation token this taxonomy doesn't define.
+    Other(char),
+}
+
+impl Affiliation {
+    fn from_char(c: char) -> Self {
+        match c {
+            'f' => Affiliation::Friend,
+            'h' => Affiliation::Hostile,
+            'n' => Affiliation::Neutral,
+            'u' => Affiliation::Unknown,
+            'p' => Affiliation::Pending,
+            'a' => Affiliation::AssumedFriend,
+            's' => Affiliation::Suspect,
+            'j' => Affiliation::Joker,
+            'k' => Affiliation::Faker,
+            'o' => Affiliation::None,
+            other => Affiliation::Other(other),
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Affiliation::Friend => 'f',
+            Affiliation::Hostile => 'h',
+            Affiliation::Neutral => 'n',
+            Affiliation::Unknown => 'u',
+            Affiliation::Pending => 'p',
+            Affiliation::AssumedFriend => 'a',
+            Affiliation::Suspect => 's',
+            Affiliation::Joker => 'j',
+            Affiliation::Faker => 'k',
+            Affiliation::None => 'o',
+            Affiliation::Other(c) => c,
+        }
+    }
+}
+
+/// Battle dimension of an atom (the third token of an `a-...` type).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    /// `P` — space
+    Space,
+    /// `A` — air
+    Air,
+    /// `G` — ground
+    Ground,
+    /// `S` — surface/sea
+    SurfaceSea,
+    /// `U` — subsurface
+    Subsurface,
+    /// `F` — special operations forces
+    Sof,
+    /// Any other dimension token this taxonomy doesn't define.
+    Other(char),
+}
+
+impl Dimension {
+    fn from_char(c: char) -> Self {
+        match c {
+            'P' => Dimension::Space,
+            'A' => Dimension::Air,
+            'G' => Dimension::Ground,
+            'S' => Dimension::SurfaceSea,
+            'U' => Dimension::Subsurface,
+            'F' => Dimension::Sof,
+            other => Dimension::Other(other),
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Dimension::Space => 'P',
+            Dimension::Air => 'A',
+            Dimension::Ground => 'G',
+            Dimension::SurfaceSea => 'S',
+            Dimension::Subsurface => 'U',
+            Dimension::Sof => 'F',
+            Dimension::Other(c) => c,
+        }
+    }
+}
+
+/// Failure modes for [`CotType::parse`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CotTypeError {
+    /// The input was an empty string.
+    #[error("CoT type string is empty")]
+    Empty,
+    /// The leading category token wasn't exactly one character.
+    #[error("top-level category token must be exactly one character, got {0:?}")]
+    InvalidCategory(String),
+    /// An atom's affiliation token wasn't exactly one character.
+    #[error("atom affiliation token must be exactly one character, got {0:?}")]
+    InvalidAffiliation(String),
+    /// An atom's battle dimension token wasn't exactly one character.
+    #[error("atom battle dimension token must be exactly one character, got {0:?}")]
+    InvalidDimension(String),
+}
+
+/// A parsed, structured CoT event type, e.g. `"a-f-G-U-C"` decomposed into
+/// [`Category::Atom`] + [`Affiliation::Friend`] + [`Dimension::Ground`] +
+/// subtype refinements `["U", "C"]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CotType {
+    category: Category,
+    affiliation: Option<Affiliation>,
+    dimension: Option<Dimension>,
+    subtype: Vec<String>,
+}
+
+impl CotType {
+    /// Parses a dash-delimited CoT type string into its positional fields.
+    ///
+    /// Affiliation and battle dimension are only populated for
+    /// [`Category::Atom`] types that have at least three tokens; everything
+    /// after the fields a category consumes is kept verbatim as `subtype`,
+    /// so [`Display`] always round-trips back to the original string.
+    pub fn parse(s: &str) -> Result<CotType, CotTypeError> {
+        if s.is_empty() {
+            return Err(CotTypeError::Empty);
+        }
+
+        let tokens: Vec<&str> = s.split('-').collect();
+        let category_token = tokens[0];
+        if category_token.chars().count() != 1 {
+            return Err(CotTypeError::InvalidCategory(category_token.to_string()));
+        }
+        let category = Category::from_char(category_token.chars().next().unwrap());
+
+        let (affiliation, dimension, subtype_start) =
+            if category == Category::Atom && tokens.len() >= 3 {
+                let affiliation_token = tokens[1];
+                if affiliation_token.chars().count() != 1 {
+                    return Err(CotTypeError::InvalidAffiliation(
+                        affiliation_token.to_string(),
+                    ));
+                }
+                let dimension_token = tokens[2];
+                if dimension_token.chars().count() != 1 {
+                    return Err(CotTypeError::InvalidDimension(dimension_token.to_string()));
+                }
+                (
+                    Some(Affiliation::from_char(
+                        affiliation_token.chars().next().unwrap(),
+                    )),
+                    Some(Dimension::from_char(
+                        dimension_token.chars().next().unwrap(),
+                    )),
+                    3,
+                )
+            } else {
+                (None, None, 1)
+            };
+
+        let subtype = tokens[subtype_start..]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        Ok(CotType {
+            category,
+            affiliation,
+            dimension,
+            subtype,
+        })
+    }
+
+    /// The top-level category.
+    pub fn category(&self) -> Category {
+        self.category
+    }
+
+    /// The atom's affiliation, or `None` for non-atom types and atoms with
+    /// fewer than three dash-delimited tokens.
+    pub fn affiliation(&self) -> Option<Affiliation> {
+        self.affiliation
+    }
+
+    /// The atom's battle dimension, or `None` for non-atom types and atoms
+    /// with fewer than three dash-delimited tokens.
+    pub fn dimension(&self) -> Option<Dimension> {
+        self.dimension
+    }
+
+    /// The free-form subtype refinement tokens trailing the fields the
+    /// category consumed, in order (e.g. `["U", "C"]` for `"a-f-G-U-C"`).
+    pub fn subtype(&self) -> &[String] {
+        &self.subtype
+    }
+
+    /// Returns `true` if this is an [`Category::Atom`] (physical object)
+    /// type.
+    pub fn is_atom(&self) -> bool {
+        self.category == Category::Atom
+    }
+
+    /// Returns `true` if `prefix`, treated as a dash-delimited path, is a
+    /// prefix of this type's own path — so `"a-f"` matches `"a-f-G-U-C"`.
+    pub fn matches_prefix(&self, prefix: &str) -> bool {
+        let own = self.to_string();
+        let own_tokens: Vec<&str> = own.split('-').collect();
+        let prefix_tokens: Vec<&str> = prefix.split('-').collect();
+        prefix_tokens.len() <= own_tokens.len()
+            && prefix_tokens
+                .iter()
+                .zip(own_tokens.iter())
+                .all(|(p, o)| p == o)
+    }
+}
+
+impl fmt::Display for CotType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tokens = vec![self.category.to_char().to_string()];
+        if let Some(affiliation) = self.affiliation {
+            tokens.push(affiliation.to_char().to_string());
+        }
+        if let Some(dimension) = self.dimension {
+            tokens.push(dimension.to_char().to_string());
+        }
+        tokens.extend(self.subtype.iter().cloned());
+        write!(f, "{}", tokens.join("-"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_full_military_ground_unit_type() {
+        let cot_type = CotType::parse("a-f-G-U-C").unwrap();
+        assert!(cot_type.is_atom());
+        assert_eq!(cot_type.category(), Category::Atom);
+        assert_eq!(cot_type.affiliation(), Some(Affiliation::Friend));
+        assert_eq!(cot_type.dimension(), Some(Dimension::Ground));
+        assert_eq!(cot_type.subtype(), &["U".to_string(), "C".to_string()]);
+    }
+
+    #[test]
+    fn parses_a_bit_event_type_with_no_affiliation_or_dimension() {
+        let cot_type = CotType::parse("b-t-f").unwrap();
+        assert!(!cot_type.is_atom());
+        assert_eq!(cot_type.category(), Category::Event);
+        assert_eq!(cot_type.affiliation(), None);
+        assert_eq!(cot_type.dimension(), None);
+        assert_eq!(cot_type.subtype(), &["t".to_string(), "f".to_string()]);
+    }
+
+    #[test]
+    fn parses_an_emergency_type() {
+        let cot_type = CotType::parse("b-a-o-can").unwrap();
+        assert_eq!(cot_type.category(), Category::Event);
+        assert_eq!(
+            cot_type.subtype(),
+            &["a".to_string(), "o".to_string(), "can".to_string()]
+        );
+    }
+
+    #[test]
+    fn display_round_trips_the_original_string() {
+        for raw in ["a-f-G-U-C", "b-t-f", "b-a-o-can", "a-u-S", "t-k"] {
+            assert_eq!(CotType::parse(raw).unwrap().to_string(), raw);
+        }
+    }
+
+    #[test]
+    fn matches_prefix_treats_the_dash_path_as_a_tree() {
+        let cot_type = CotType::parse("a-f-G-U-C").unwrap();
+        assert!(cot_type.matches_prefix("a-f"));
+        assert!(cot_type.matches_prefix("a-f-G"));
+        assert!(cot_type.matches_prefix("a-f-G-U-C"));
+        assert!(!cot_type.matches_prefix("a-h"));
+        assert!(!cot_type.matches_prefix("a-f-G-U-C-X"));
+    }
+
+    #[test]
+    fn rejects_an_empty_string() {
+        assert_eq!(CotType::parse(""), Err(CotTypeError::Empty));
+    }
+
+    #[test]
+    fn rejects_a_multi_character_category_token() {
+        assert!(matches!(
+            CotType::parse("atom-f-G"),
+            Err(CotTypeError::InvalidCategory(_))
+        ));
+    }
+
+    #[test]
+    fn an_atom_with_too_few_tokens_has_no_affiliation_or_dimension() {
+        let cot_type = CotType::parse("a-f").unwrap();
+        assert_eq!(cot_type.affiliation(), None);
+        assert_eq!(cot_type.dimension(), None);
+        assert_eq!(cot_type.subtype(), &["f".to_string()]);
+        assert_eq!(cot_type.to_string(), "a-f");
+    }
+}
@@ -7,14 +7,386 @@
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::collections::hash_map::DefaultHasher;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 
 const TAG_METADATA: &str = "_tag";
 // Removed redundant metadata: _docId and _elementIndex are already encoded in the key
 const KEY_SEPARATOR: &str = "_";
+/// Marks a coalesced entry's first absorbed index; see [`enhance_with_run_metadata`].
+const RUN_START_METADATA: &str = "_run_start";
+/// Marks a coalesced entry's run length; see [`enhance_with_run_metadata`].
+const RUN_LEN_METADATA: &str = "_run_len";
+
+/// Failure modes for the `try_*` parse/reconstruct functions in this module.
+///
+/// The plain (non-`try_`) functions swallow all of these behind `_ => {}`
+/// match arms and `unwrap_or_default()`, which is convenient for a caller
+/// that just wants *a* map back, but for a protocol crate feeding a CRDT
+/// store it means corrupt CoT silently produces a partial map that
+/// round-trips to the wrong XML. The `try_*` functions report these instead
+/// of masking them; the plain functions log a warning and fall back to the
+/// lenient best-effort parse.
+#[derive(Debug, thiserror::Error, Clone, PartialEq)]
+pub enum DetailParseError {
+    /// `quick_xml` failed to read the next event.
+    #[error("XML read error at byte offset {offset}: {source}")]
+    XmlRead {
+        /// Byte offset into the input where the read failed.
+        offset: u64,
+        /// The underlying `quick_xml` error, stringified.
+        source: String,
+    },
+
+    /// An element's start tag was never matched by a closing tag before EOF.
+    #[error("element <{tag}> opened at byte offset {offset} was never closed")]
+    UnterminatedElement {
+        /// The unclosed element's tag name.
+        tag: String,
+        /// Byte offset of the element's opening tag.
+        offset: u64,
+    },
+
+    /// An element's text content contained an entity `quick_xml` couldn't unescape.
+    #[error("failed to unescape XML text at byte offset {offset}: {source}")]
+    UnescapeFailed {
+        /// Byte offset of the offending text node.
+        offset: u64,
+        /// The underlying unescape error, stringified.
+        source: String,
+    },
+
+    /// The input ended before a closing `</detail>` was found.
+    #[error("reached end of input at byte offset {offset} before a closing </detail>")]
+    UnexpectedEof {
+        /// Byte offset where EOF was hit.
+        offset: u64,
+    },
+
+    /// [`convert_stable_keys_to_xml`]'s stable-key branch found a key shaped
+    /// like a stable key, but its value carries no [`TAG_METADATA`] to
+    /// reconstruct the original element name from.
+    #[error("stable key `{key}` has no `_tag` metadata to reconstruct its element name")]
+    MissingTagMetadata {
+        /// The stable key missing its `_tag` metadata.
+        key: String,
+    },
+}
+
+/// Wire-format version tag mixed into every stable key's hash input. A future
+/// change to [`canonical_key_bytes`]'s layout should bump this so old and new
+/// peers compute visibly different keys instead of silently colliding.
+const STABLE_KEY_SALT: &[u8] = b"ditto_cot:stable_key:v1";
+
+/// A deterministic, byte-stable 64-bit hash family for [`generate_stable_key`].
+///
+/// `generate_stable_key` used to hash with
+/// `std::collections::hash_map::DefaultHasher`, whose SipHash output carries
+/// no cross-version or cross-platform stability guarantee — two peers in a
+/// CRDT swarm on different Rust toolchains could compute different keys for
+/// the same `(document_id, element_name, index)` triple, so their duplicate
+/// `sensor`/`track` elements would silently fail to converge. Every
+/// `StableKeyHasher` implementation must instead be a fixed, documented
+/// algorithm with no environment-dependent keying, so the same bytes always
+/// produce the same digest regardless of Rust version, platform, or
+/// standard-library update.
+///
+/// All peers in a swarm must agree on one implementation; mixing hash
+/// families across peers reintroduces the same non-convergence
+/// `DefaultHasher` had.
+pub trait StableKeyHasher: Send + Sync {
+    /// Hashes `canonical_bytes` — the [`canonical_key_bytes`] layout:
+    /// `document_id` UTF-8 bytes, a `0x00` separator, `element_name` UTF-8
+    /// bytes, then [`STABLE_KEY_SALT`] — to a fixed 64-bit digest.
+    fn hash64(&self, canonical_bytes: &[u8]) -> u64;
+}
+
+/// The default [`StableKeyHasher`]: 64-bit FNV-1a.
+///
+/// Chosen over re-deriving SipHash-with-a-fixed-key because FNV-1a is a
+/// public-domain algorithm defined purely in terms of a fixed offset basis,
+/// a fixed prime, and elementary XOR/multiply steps — no per-process keying
+/// of any kind — so an independent implementation in any language reproduces
+/// the same digest for the same bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fnv1aStableKeyHasher;
+
+/// FNV-1a's 64-bit offset basis, fixed by the algorithm's specification.
+const FNV_OFFSET_BASIS_64: u64 = 0xcbf2_9ce4_8422_2325;
+/// FNV-1a's 64-bit prime, fixed by the algorithm's specification.
+const FNV_PRIME_64: u64 = 0x0000_0100_0000_01b3;
+
+impl StableKeyHasher for Fnv1aStableKeyHasher {
+    fn hash64(&self, canonical_bytes: &[u8]) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS_64;
+        for &byte in canonical_bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME_64);
+        }
+        hash
+    }
+}
+
+/// The canonical byte layout hashed into every stable key: `document_id`'s
+/// UTF-8 bytes, a `0x00` separator, `element_name`'s UTF-8 bytes, then
+/// [`STABLE_KEY_SALT`]. This layout is part of the wire format — any
+/// implementation computing a stable key for the same
+/// `(document_id, element_name)` pair, in any language, must hash exactly
+/// these bytes (with a [`StableKeyHasher`] the whole swarm agrees on) to
+/// converge with this crate's keys.
+fn canonical_key_bytes(document_id: &str, element_name: &str) -> Vec<u8> {
+    let mut bytes =
+        Vec::with_capacity(document_id.len() + 1 + element_name.len() + STABLE_KEY_SALT.len());
+    bytes.extend_from_slice(document_id.as_bytes());
+    bytes.push(0x00);
+    bytes.extend_from_slice(element_name.as_bytes());
+    bytes.extend_from_slice(STABLE_KEY_SALT);
+    bytes
+}
+
+/// Salt appended when hashing for [`sha256_stable_key`], fixed as part of
+/// that function's wire-format contract. Distinct from [`STABLE_KEY_SALT`],
+/// which only [`canonical_key_bytes`]/[`StableKeyHasher`] implementations use.
+const SHA256_STABLE_KEY_SALT: &str = "stable_key_salt";
+
+/// Generates a stable key the same shape as [`generate_stable_key`], but
+/// with the hash basis fully specified as a cross-language wire-format
+/// contract rather than delegated to a [`StableKeyHasher`] impl: SHA-256
+/// over the exact UTF-8 string `{document_id}{element_name}{salt}` (no
+/// separators — see [`SHA256_STABLE_KEY_SALT`] for `salt`), truncated to its
+/// first 8 bytes, read big-endian, then [`URL_SAFE_NO_PAD`]-base64-encoded
+/// and joined to `index` with [`KEY_SEPARATOR`].
+///
+/// [`Fnv1aStableKeyHasher`] already gives every stable key a deterministic,
+/// public-algorithm digest, but leaves the *exact input bytes*
+/// ([`canonical_key_bytes`]'s `0x00`-separated layout) as this crate's
+/// internal implementation detail. This function instead locks the full
+/// input string and truncation rule as the contract itself, so an
+/// independent Java/Kotlin/Swift implementation can reproduce this exact
+/// key — not just "a" deterministic key — without reading this crate's
+/// source.
+pub fn sha256_stable_key(document_id: &str, element_name: &str, index: u32) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(document_id.as_bytes());
+    hasher.update(element_name.as_bytes());
+    hasher.update(SHA256_STABLE_KEY_SALT.as_bytes());
+    let digest = hasher.finalize();
+
+    let mut first_eight = [0u8; 8];
+    first_eight.copy_from_slice(&digest[..8]);
+    let b64_hash = URL_SAFE_NO_PAD.encode(first_eight);
+
+    format!("{b64_hash}{KEY_SEPARATOR}{index}")
+}
+
+/// How a stable key's hash digest is rendered into (and recovered from) the
+/// `String` used as a [`HashMap`] key.
+///
+/// [`generate_stable_key`] used to hard-code URL-safe base64 and assume any
+/// `{prefix}{KEY_SEPARATOR}{index}`-shaped key was a stable key, which
+/// misroutes a real single-occurrence tag that merely happens to contain an
+/// underscore followed by digits (e.g. a tag literally named `my_5`). A
+/// `KeyCodec` is the wire-format authority instead: [`KeyCodec::decode`]
+/// round-trips only a string [`KeyCodec::encode`] could have produced, so
+/// [`try_convert_stable_keys_to_xml`] can tell the two apart instead of
+/// guessing from the key's shape.
+///
+/// All peers in a swarm must agree on one implementation; mixing codecs
+/// across peers means one peer's stable keys look like plain element names
+/// to another.
+pub trait KeyCodec: Send + Sync {
+    /// Renders a digest's raw bytes and an occurrence index into a stable
+    /// key string.
+    fn encode(&self, hash_bytes: &[u8], index: u32) -> String;
+
+    /// Recovers a digest's raw bytes and occurrence index from a key
+    /// previously produced by [`KeyCodec::encode`], or `None` if `key`
+    /// isn't shaped like one this codec could have produced.
+    fn decode(&self, key: &str) -> Option<(Vec<u8>, u32)>;
+}
+
+/// The default [`KeyCodec`]: URL-safe, unpadded base64 for the hash digest,
+/// joined to the index with [`KEY_SEPARATOR`]. This is the locked wire
+/// format every existing stable key in the wild already uses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Base64KeyCodec;
+
+impl KeyCodec for Base64KeyCodec {
+    fn encode(&self, hash_bytes: &[u8], index: u32) -> String {
+        format!(
+            "{}{}{}",
+            URL_SAFE_NO_PAD.encode(hash_bytes),
+            KEY_SEPARATOR,
+            index
+        )
+    }
+
+    fn decode(&self, key: &str) -> Option<(Vec<u8>, u32)> {
+        let (prefix, index_str) = key.rsplit_once(KEY_SEPARATOR)?;
+        let index = index_str.parse::<u32>().ok()?;
+        let hash_bytes = URL_SAFE_NO_PAD.decode(prefix).ok()?;
+        Some((hash_bytes, index))
+    }
+}
+
+/// Base58 alphabet (Bitcoin's), used by [`Base58KeyCodec`]: no `0`/`O`/`I`/`l`,
+/// so a hand-copied key can't be misread between them.
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// Base62 alphabet used by [`Base62KeyCodec`]: all 10 digits plus upper- and
+/// lower-case letters, for the shortest alphanumeric-only encoding.
+const BASE62_ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// A shorter, human-friendlier [`KeyCodec`] than [`Base64KeyCodec`]: base58,
+/// Bitcoin's alphabet, with no characters that are visually ambiguous in a
+/// monospace font (`0`/`O`, `I`/`l`) and nothing that needs URL-escaping.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Base58KeyCodec;
+
+impl KeyCodec for Base58KeyCodec {
+    fn encode(&self, hash_bytes: &[u8], index: u32) -> String {
+        format!(
+            "{}{}{}",
+            encode_base_n(hash_bytes, BASE58_ALPHABET),
+            KEY_SEPARATOR,
+            index
+        )
+    }
+
+    fn decode(&self, key: &str) -> Option<(Vec<u8>, u32)> {
+        let (prefix, index_str) = key.rsplit_once(KEY_SEPARATOR)?;
+        let index = index_str.parse::<u32>().ok()?;
+        let hash_bytes = decode_base_n(prefix, BASE58_ALPHABET)?;
+        Some((hash_bytes, index))
+    }
+}
+
+/// A shorter, human-friendlier [`KeyCodec`] than [`Base64KeyCodec`]: base62,
+/// alphanumeric only, so a key can be pasted anywhere a bare identifier is
+/// accepted without escaping.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Base62KeyCodec;
+
+impl KeyCodec for Base62KeyCodec {
+    fn encode(&self, hash_bytes: &[u8], index: u32) -> String {
+        format!(
+            "{}{}{}",
+            encode_base_n(hash_bytes, BASE62_ALPHABET),
+            KEY_SEPARATOR,
+            index
+        )
+    }
+
+    fn decode(&self, key: &str) -> Option<(Vec<u8>, u32)> {
+        let (prefix, index_str) = key.rsplit_once(KEY_SEPARATOR)?;
+        let index = index_str.parse::<u32>().ok()?;
+        let hash_bytes = decode_base_n(prefix, BASE62_ALPHABET)?;
+        Some((hash_bytes, index))
+    }
+}
+
+/// Renders `bytes` as a big-endian number in the base of `alphabet`, the
+/// shared implementation behind [`Base58KeyCodec`] and [`Base62KeyCodec`].
+/// Leading zero bytes are preserved as leading `alphabet[0]` characters
+/// (the standard base58-style convention), so [`decode_base_n`] can recover
+/// the exact original byte length.
+fn encode_base_n(bytes: &[u8], alphabet: &[u8]) -> String {
+    let base = alphabet.len() as u32;
+    let leading_zeros = bytes.iter().take_while(|&&b| b == 0).count();
+
+    let mut digits: Vec<u8> = vec![0];
+    for &byte in bytes {
+        let mut carry = u32::from(byte);
+        for digit in digits.iter_mut() {
+            let value = u32::from(*digit) * 256 + carry;
+            *digit = (value % base) as u8;
+            carry = value / base;
+        }
+        while carry > 0 {
+            digits.push((carry % base) as u8);
+            carry /= base;
+        }
+    }
+
+    let body: String = if bytes.iter().all(|&b| b == 0) {
+        String::new()
+    } else {
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| alphabet[digit as usize] as char)
+            .collect()
+    };
+
+    format!(
+        "{}{}",
+        (alphabet[0] as char).to_string().repeat(leading_zeros),
+        body
+    )
+}
+
+/// Inverse of [`encode_base_n`]: recovers the original bytes from a string
+/// it produced, or `None` if `key` contains a character outside `alphabet`.
+fn decode_base_n(key: &str, alphabet: &[u8]) -> Option<Vec<u8>> {
+    if key.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let base = alphabet.len() as u32;
+    let zero_char = alphabet[0] as char;
+    let leading_zeros = key.chars().take_while(|&c| c == zero_char).count();
+
+    let mut bytes: Vec<u8> = vec![0];
+    for ch in key.chars() {
+        let digit = alphabet.iter().position(|&a| a as char == ch)? as u32;
+        let mut carry = digit;
+        for byte in bytes.iter_mut() {
+            let value = u32::from(*byte) * base + carry;
+            *byte = (value % 256) as u8;
+            carry = value / 256;
+        }
+        while carry > 0 {
+            bytes.push((carry % 256) as u8);
+            carry /= 256;
+        }
+    }
+
+    let mut result = vec![0u8; leading_zeros];
+    if !(bytes.len() == 1 && bytes[0] == 0 && leading_zeros > 0) {
+        result.extend(bytes.iter().rev());
+    }
+    Some(result)
+}
+
+/// Bundles the [`StableKeyHasher`] and [`KeyCodec`] a call site wants to use
+/// together, so a caller that wants both explicit doesn't have to thread two
+/// separate trait objects through every stable-key function.
+///
+/// `KeyScheme::default()` reproduces the crate's original, locked wire
+/// format: [`Fnv1aStableKeyHasher`] over [`Base64KeyCodec`].
+pub struct KeyScheme<'a> {
+    /// The hash algorithm used to digest `(document_id, element_name)`.
+    pub hasher: &'a dyn StableKeyHasher,
+    /// The encoding used to render that digest into a stable key string.
+    pub codec: &'a dyn KeyCodec,
+}
+
+impl<'a> KeyScheme<'a> {
+    /// Builds a scheme from an explicit hasher and codec.
+    pub fn new(hasher: &'a dyn StableKeyHasher, codec: &'a dyn KeyCodec) -> Self {
+        Self { hasher, codec }
+    }
+}
+
+impl Default for KeyScheme<'static> {
+    fn default() -> Self {
+        Self {
+            hasher: &Fnv1aStableKeyHasher,
+            codec: &Base64KeyCodec,
+        }
+    }
+}
 
 /// Parses the <detail> section with CRDT-optimized stable keys for duplicate elements.
 ///
@@ -65,12 +437,154 @@ const KEY_SEPARATOR: &str = "_";
 pub fn parse_detail_section_with_stable_keys(
     detail_xml: &str,
     document_id: &str,
+) -> HashMap<String, Value> {
+    match try_parse_detail_section_with_stable_keys(detail_xml, document_id) {
+        Ok(map) => map,
+        Err(err) => {
+            log::warn!(
+                "parse_detail_section_with_stable_keys: {err}; falling back to a lenient \
+                 best-effort parse that may silently drop the malformed data"
+            );
+            parse_detail_section_with_stable_keys_and_hasher(
+                detail_xml,
+                document_id,
+                &Fnv1aStableKeyHasher,
+            )
+        }
+    }
+}
+
+/// Like [`parse_detail_section_with_stable_keys`], but reporting malformed
+/// input instead of silently producing a partial map.
+///
+/// # Errors
+/// Returns [`DetailParseError`] if `detail_xml` doesn't parse as well-formed
+/// XML, an element or `<detail>` itself is never closed, or an entity
+/// reference fails to unescape.
+pub fn try_parse_detail_section_with_stable_keys(
+    detail_xml: &str,
+    document_id: &str,
+) -> Result<HashMap<String, Value>, DetailParseError> {
+    try_parse_detail_section_with_stable_keys_and_hasher(
+        detail_xml,
+        document_id,
+        &Fnv1aStableKeyHasher,
+    )
+}
+
+/// Like [`try_parse_detail_section_with_stable_keys`], but hashing stable
+/// keys with an explicitly chosen [`StableKeyHasher`] instead of the default
+/// [`Fnv1aStableKeyHasher`].
+pub fn try_parse_detail_section_with_stable_keys_and_hasher(
+    detail_xml: &str,
+    document_id: &str,
+    hasher: &dyn StableKeyHasher,
+) -> Result<HashMap<String, Value>, DetailParseError> {
+    let element_counts = try_count_element_occurrences(detail_xml)?;
+    try_parse_with_stable_keys(
+        detail_xml,
+        document_id,
+        &element_counts,
+        hasher,
+        &Base64KeyCodec,
+    )
+}
+
+/// Like [`try_parse_detail_section_with_stable_keys`], but hashing and
+/// encoding stable keys with an explicitly chosen [`KeyScheme`] instead of
+/// the default [`Fnv1aStableKeyHasher`]/[`Base64KeyCodec`] pair.
+pub fn try_parse_detail_section_with_stable_keys_with_scheme(
+    detail_xml: &str,
+    document_id: &str,
+    scheme: &KeyScheme,
+) -> Result<HashMap<String, Value>, DetailParseError> {
+    let element_counts = try_count_element_occurrences(detail_xml)?;
+    try_parse_with_stable_keys(
+        detail_xml,
+        document_id,
+        &element_counts,
+        scheme.hasher,
+        scheme.codec,
+    )
+}
+
+/// Like [`parse_detail_section_with_stable_keys`], but hashing stable keys
+/// with an explicitly chosen [`StableKeyHasher`] instead of the default
+/// [`Fnv1aStableKeyHasher`]. Every peer syncing the same documents must be
+/// configured with the same hasher, or their stable keys for the same
+/// duplicate elements won't match.
+pub fn parse_detail_section_with_stable_keys_and_hasher(
+    detail_xml: &str,
+    document_id: &str,
+    hasher: &dyn StableKeyHasher,
 ) -> HashMap<String, Value> {
     // First pass: count occurrences of each element type
     let element_counts = count_element_occurrences(detail_xml);
 
     // Second pass: parse with appropriate key generation
-    parse_with_stable_keys(detail_xml, document_id, &element_counts)
+    parse_with_stable_keys(
+        detail_xml,
+        document_id,
+        &element_counts,
+        hasher,
+        &Base64KeyCodec,
+    )
+}
+
+/// Like [`parse_detail_section_with_stable_keys`], but hashing and encoding
+/// stable keys with an explicitly chosen [`KeyScheme`] instead of the
+/// default [`Fnv1aStableKeyHasher`]/[`Base64KeyCodec`] pair. Every peer
+/// syncing the same documents must be configured with the same scheme, or
+/// their stable keys for the same duplicate elements won't match.
+pub fn parse_detail_section_with_stable_keys_with_scheme(
+    detail_xml: &str,
+    document_id: &str,
+    scheme: &KeyScheme,
+) -> HashMap<String, Value> {
+    let element_counts = count_element_occurrences(detail_xml);
+    parse_with_stable_keys(
+        detail_xml,
+        document_id,
+        &element_counts,
+        scheme.hasher,
+        scheme.codec,
+    )
+}
+
+/// Like [`parse_detail_section_with_stable_keys`], but coalescing runs of
+/// consecutive, value-identical occurrences of the same duplicate element
+/// into a single compact entry instead of one stable key per occurrence.
+///
+/// CoT detail sections often carry long sequences of near-identical
+/// repeated children — track points, waypoint lists, repeated `sensor`
+/// readings — and keying each occurrence independently bloats the CRDT
+/// document with near-duplicate entries. A run of 2 or more consecutive
+/// occurrences with the same value is instead stored once, tagged with how
+/// many occurrences it stands for; [`convert_stable_keys_to_xml`] expands it
+/// back into that many repeated elements. A non-repeating (run length 1)
+/// occurrence is unaffected and keeps its own stable key, same as
+/// [`parse_detail_section_with_stable_keys`].
+pub fn parse_detail_section_with_stable_keys_coalesced(
+    detail_xml: &str,
+    document_id: &str,
+) -> HashMap<String, Value> {
+    parse_detail_section_with_stable_keys_coalesced_and_hasher(
+        detail_xml,
+        document_id,
+        &Fnv1aStableKeyHasher,
+    )
+}
+
+/// Like [`parse_detail_section_with_stable_keys_coalesced`], but hashing
+/// stable keys with an explicitly chosen [`StableKeyHasher`] instead of the
+/// default [`Fnv1aStableKeyHasher`].
+pub fn parse_detail_section_with_stable_keys_coalesced_and_hasher(
+    detail_xml: &str,
+    document_id: &str,
+    hasher: &dyn StableKeyHasher,
+) -> HashMap<String, Value> {
+    let element_counts = count_element_occurrences(detail_xml);
+    parse_with_stable_keys_coalesced(detail_xml, document_id, &element_counts, hasher)
 }
 
 /// Counts occurrences of each element type in the detail section.
@@ -121,6 +635,8 @@ fn parse_with_stable_keys(
     detail_xml: &str,
     document_id: &str,
     element_counts: &HashMap<String, u32>,
+    hasher: &dyn StableKeyHasher,
+    codec: &dyn KeyCodec,
 ) -> HashMap<String, Value> {
     let mut reader = Reader::from_str(detail_xml);
     reader.trim_text(true);
@@ -144,7 +660,13 @@ fn parse_with_stable_keys(
                     if *count > 1 {
                         // Generate stable key for duplicate
                         let index = element_indices.entry(tag.clone()).or_insert(0);
-                        let stable_key = generate_stable_key(document_id, &tag, *index);
+                        let stable_key = generate_stable_key_with_codec(
+                            document_id,
+                            &tag,
+                            *index,
+                            hasher,
+                            codec,
+                        );
                         let enhanced_value =
                             enhance_with_metadata(value, &tag, document_id, *index);
                         result.insert(stable_key, enhanced_value);
@@ -169,7 +691,13 @@ fn parse_with_stable_keys(
                     let count = element_counts.get(&tag).unwrap_or(&0);
                     if *count > 1 {
                         let index = element_indices.entry(tag.clone()).or_insert(0);
-                        let stable_key = generate_stable_key(document_id, &tag, *index);
+                        let stable_key = generate_stable_key_with_codec(
+                            document_id,
+                            &tag,
+                            *index,
+                            hasher,
+                            codec,
+                        );
                         let enhanced_value =
                             enhance_with_metadata(value, &tag, document_id, *index);
                         result.insert(stable_key, enhanced_value);
@@ -193,6 +721,141 @@ fn parse_with_stable_keys(
     result
 }
 
+/// One maximal run of consecutive, value-identical occurrences of the same
+/// duplicate element tag, in parse order. Built by [`build_runs`].
+#[derive(Debug, Clone, PartialEq)]
+struct ElementRun {
+    /// Index (within its tag group) of the run's first occurrence.
+    start_index: u32,
+    /// How many consecutive occurrences share `value`.
+    len: u32,
+    /// The value every occurrence in the run parsed to.
+    value: Value,
+}
+
+/// Coalesces a tag group's occurrences, in index order, into runs of
+/// consecutive identical values.
+///
+/// A run-builder state machine: holds a "current run" of
+/// `(canonical_value, start_index, len)` and, for each next occurrence,
+/// either extends the current run (its value matches) or emits it and
+/// starts a new one. `occurrences` must already be in index order.
+fn build_runs(occurrences: Vec<(u32, Value)>) -> Vec<ElementRun> {
+    let mut runs = Vec::new();
+    let mut current: Option<ElementRun> = None;
+
+    for (index, value) in occurrences {
+        current = match current.take() {
+            Some(mut run) if run.value == value => {
+                run.len += 1;
+                Some(run)
+            }
+            Some(run) => {
+                runs.push(run);
+                Some(ElementRun {
+                    start_index: index,
+                    len: 1,
+                    value,
+                })
+            }
+            None => Some(ElementRun {
+                start_index: index,
+                len: 1,
+                value,
+            }),
+        };
+    }
+    if let Some(run) = current {
+        runs.push(run);
+    }
+
+    runs
+}
+
+/// Coalescing counterpart of [`parse_with_stable_keys`]: collects every
+/// duplicate tag's occurrences in order, then emits one entry per
+/// [`ElementRun`] — a run of length 1 gets a plain stable key, same as
+/// [`parse_with_stable_keys`]; a run of length 2 or more gets a single
+/// compact entry carrying [`RUN_START_METADATA`] and [`RUN_LEN_METADATA`].
+fn parse_with_stable_keys_coalesced(
+    detail_xml: &str,
+    document_id: &str,
+    element_counts: &HashMap<String, u32>,
+    hasher: &dyn StableKeyHasher,
+) -> HashMap<String, Value> {
+    let mut reader = Reader::from_str(detail_xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut result = HashMap::new();
+    let mut duplicates: HashMap<String, Vec<Value>> = HashMap::new();
+    let mut in_detail = false;
+
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if !in_detail && tag == "detail" {
+                    in_detail = true;
+                } else if in_detail {
+                    let mut child_buf = Vec::new();
+                    let value = parse_element(&mut reader, e, &mut child_buf);
+                    if *element_counts.get(&tag).unwrap_or(&0) > 1 {
+                        duplicates.entry(tag).or_default().push(value);
+                    } else {
+                        result.insert(tag, value);
+                    }
+                }
+            }
+            Ok(Event::Empty(ref e)) => {
+                if in_detail {
+                    let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    let mut map = Map::new();
+                    for attr in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        map.insert(key, Value::String(val));
+                    }
+                    let value = Value::Object(map);
+                    if *element_counts.get(&tag).unwrap_or(&0) > 1 {
+                        duplicates.entry(tag).or_default().push(value);
+                    } else {
+                        result.insert(tag, value);
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if in_detail && tag == "detail" {
+                    break;
+                }
+            }
+            Ok(Event::Eof) => break,
+            _ => {}
+        }
+    }
+
+    for (tag, values) in duplicates {
+        let occurrences: Vec<(u32, Value)> = values
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (index as u32, value))
+            .collect();
+
+        for run in build_runs(occurrences) {
+            let stable_key = generate_stable_key(document_id, &tag, run.start_index, hasher);
+            let enhanced = if run.len >= 2 {
+                enhance_with_run_metadata(run.value, &tag, run.start_index, run.len)
+            } else {
+                enhance_with_metadata(run.value, &tag, document_id, run.start_index)
+            };
+            result.insert(stable_key, enhanced);
+        }
+    }
+
+    result
+}
+
 /// Parse a single XML element into a Value.
 fn parse_element<R: std::io::BufRead>(
     reader: &mut Reader<R>,
@@ -281,75 +944,432 @@ fn skip_element<R: std::io::BufRead>(
     }
 }
 
-/// Generate a stable key for duplicate elements using Base64 hash format.
-/// Format: base64(hash(document_id + element_name))_index
-fn generate_stable_key(document_id: &str, element_name: &str, index: u32) -> String {
-    let mut hasher = DefaultHasher::new();
-    format!("{}{}{}", document_id, element_name, "stable_key_salt").hash(&mut hasher);
-    let hash = hasher.finish();
-
-    // Convert hash to bytes and encode as base64
-    let hash_bytes = hash.to_be_bytes();
-    let b64_hash = URL_SAFE_NO_PAD.encode(hash_bytes);
-
-    format!("{}{}{}", b64_hash, KEY_SEPARATOR, index)
-}
+/// Strict counterpart of [`count_element_occurrences`]: reports a
+/// [`DetailParseError`] instead of stopping silently at the first
+/// unreadable event or truncated element.
+fn try_count_element_occurrences(
+    detail_xml: &str,
+) -> Result<HashMap<String, u32>, DetailParseError> {
+    let mut reader = Reader::from_str(detail_xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut counts = HashMap::new();
+    let mut in_detail = false;
 
-/// Enhance a value with minimal metadata for reconstruction.
-/// Only stores the tag name - document ID and index are encoded in the key.
-fn enhance_with_metadata(value: Value, tag: &str, _doc_id: &str, _element_index: u32) -> Value {
-    match value {
-        Value::Object(mut map) => {
-            map.insert(TAG_METADATA.to_string(), Value::String(tag.to_string()));
-            Value::Object(map)
-        }
-        Value::String(text) => {
-            let mut map = Map::new();
-            map.insert(TAG_METADATA.to_string(), Value::String(tag.to_string()));
-            map.insert("_text".to_string(), Value::String(text));
-            Value::Object(map)
-        }
-        other => {
-            // For other types, wrap in object with metadata
-            let mut map = Map::new();
-            map.insert(TAG_METADATA.to_string(), Value::String(tag.to_string()));
-            map.insert("_value".to_string(), other);
-            Value::Object(map)
+    loop {
+        buf.clear();
+        let offset = reader.buffer_position();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if !in_detail && tag == "detail" {
+                    in_detail = true;
+                } else if in_detail {
+                    *counts.entry(tag).or_insert(0) += 1;
+                    let element_name = e.name().as_ref().to_vec();
+                    let mut skip_buf = Vec::new();
+                    try_skip_element(&mut reader, &element_name, &mut skip_buf)?;
+                }
+            }
+            Ok(Event::Empty(ref e)) => {
+                if in_detail {
+                    let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    *counts.entry(tag).or_insert(0) += 1;
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if in_detail && tag == "detail" {
+                    return Ok(counts);
+                }
+            }
+            Ok(Event::Eof) => return Err(DetailParseError::UnexpectedEof { offset }),
+            Ok(_) => {}
+            Err(e) => {
+                return Err(DetailParseError::XmlRead {
+                    offset,
+                    source: e.to_string(),
+                })
+            }
         }
     }
 }
 
-/// Convert a stable key map back to XML.
-///
-/// This function reconstructs XML from a HashMap that may contain stable keys,
-/// grouping duplicate elements by their original tag names and preserving
-/// the relative order within each group.
-///
-/// # Arguments
-/// * `detail_map` - HashMap with CRDT-optimized keys
-///
+/// Strict counterpart of [`parse_with_stable_keys`]: reports a
+/// [`DetailParseError`] instead of returning whatever partial map it had
+/// accumulated when the XML turned out to be malformed.
+fn try_parse_with_stable_keys(
+    detail_xml: &str,
+    document_id: &str,
+    element_counts: &HashMap<String, u32>,
+    hasher: &dyn StableKeyHasher,
+    codec: &dyn KeyCodec,
+) -> Result<HashMap<String, Value>, DetailParseError> {
+    let mut reader = Reader::from_str(detail_xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut result = HashMap::new();
+    let mut element_indices: HashMap<String, u32> = HashMap::new();
+    let mut in_detail = false;
+
+    loop {
+        buf.clear();
+        let offset = reader.buffer_position();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if !in_detail && tag == "detail" {
+                    in_detail = true;
+                } else if in_detail {
+                    let mut child_buf = Vec::new();
+                    let value = try_parse_element(&mut reader, e, &mut child_buf)?;
+
+                    let count = element_counts.get(&tag).unwrap_or(&0);
+                    if *count > 1 {
+                        let index = element_indices.entry(tag.clone()).or_insert(0);
+                        let stable_key = generate_stable_key_with_codec(
+                            document_id,
+                            &tag,
+                            *index,
+                            hasher,
+                            codec,
+                        );
+                        let enhanced_value =
+                            enhance_with_metadata(value, &tag, document_id, *index);
+                        result.insert(stable_key, enhanced_value);
+                        *index += 1;
+                    } else {
+                        result.insert(tag, value);
+                    }
+                }
+            }
+            Ok(Event::Empty(ref e)) => {
+                if in_detail {
+                    let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    let mut map = Map::new();
+                    for attr in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let val = String::from_utf8_lossy(&attr.value).to_string();
+                        map.insert(key, Value::String(val));
+                    }
+                    let value = Value::Object(map);
+
+                    let count = element_counts.get(&tag).unwrap_or(&0);
+                    if *count > 1 {
+                        let index = element_indices.entry(tag.clone()).or_insert(0);
+                        let stable_key = generate_stable_key_with_codec(
+                            document_id,
+                            &tag,
+                            *index,
+                            hasher,
+                            codec,
+                        );
+                        let enhanced_value =
+                            enhance_with_metadata(value, &tag, document_id, *index);
+                        result.insert(stable_key, enhanced_value);
+                        *index += 1;
+                    } else {
+                        result.insert(tag, value);
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if in_detail && tag == "detail" {
+                    return Ok(result);
+                }
+            }
+            Ok(Event::Eof) => return Err(DetailParseError::UnexpectedEof { offset }),
+            Ok(_) => {}
+            Err(e) => {
+                return Err(DetailParseError::XmlRead {
+                    offset,
+                    source: e.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// Strict counterpart of [`parse_element`]: reports a [`DetailParseError`]
+/// instead of treating an unescapable entity as empty text or a truncated
+/// element as simply having no more children.
+fn try_parse_element<R: std::io::BufRead>(
+    reader: &mut Reader<R>,
+    start: &BytesStart,
+    buf: &mut Vec<u8>,
+) -> Result<Value, DetailParseError> {
+    let open_tag = String::from_utf8_lossy(start.name().as_ref()).to_string();
+    let start_offset = reader.buffer_position();
+    let mut map = Map::new();
+
+    for attr in start.attributes().flatten() {
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let val = String::from_utf8_lossy(&attr.value).to_string();
+        map.insert(key, Value::String(val));
+    }
+
+    let mut text_content = None;
+    loop {
+        buf.clear();
+        let offset = reader.buffer_position();
+        match reader.read_event_into(buf) {
+            Ok(Event::Start(e)) => {
+                let child_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let mut child_buf = Vec::new();
+                let child_val = try_parse_element(reader, &e, &mut child_buf)?;
+                map.insert(child_tag, child_val);
+            }
+            Ok(Event::Empty(e)) => {
+                let child_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let mut child_map = Map::new();
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                    let val = String::from_utf8_lossy(&attr.value).to_string();
+                    child_map.insert(key, Value::String(val));
+                }
+                map.insert(child_tag, Value::Object(child_map));
+            }
+            Ok(Event::Text(t)) => {
+                let text = t
+                    .unescape()
+                    .map_err(|e| DetailParseError::UnescapeFailed {
+                        offset,
+                        source: e.to_string(),
+                    })?
+                    .to_string();
+                if !text.trim().is_empty() {
+                    text_content = Some(text);
+                }
+            }
+            Ok(Event::End(e)) if e.name() == start.name() => break,
+            Ok(Event::Eof) => {
+                return Err(DetailParseError::UnterminatedElement {
+                    tag: open_tag,
+                    offset: start_offset,
+                })
+            }
+            Ok(_) => {}
+            Err(e) => {
+                return Err(DetailParseError::XmlRead {
+                    offset,
+                    source: e.to_string(),
+                })
+            }
+        }
+    }
+
+    Ok(if map.is_empty() {
+        text_content
+            .map(Value::String)
+            .unwrap_or(Value::Object(map))
+    } else {
+        if let Some(text) = text_content {
+            map.insert("_text".to_string(), Value::String(text));
+        }
+        Value::Object(map)
+    })
+}
+
+/// Strict counterpart of [`skip_element`]: reports an
+/// [`DetailParseError::UnterminatedElement`] instead of silently stopping at
+/// EOF when the element it's skipping is never closed.
+fn try_skip_element<R: std::io::BufRead>(
+    reader: &mut Reader<R>,
+    element_name: &[u8],
+    buf: &mut Vec<u8>,
+) -> Result<(), DetailParseError> {
+    let open_tag = String::from_utf8_lossy(element_name).to_string();
+    let start_offset = reader.buffer_position();
+    let mut depth = 1;
+    loop {
+        buf.clear();
+        let offset = reader.buffer_position();
+        match reader.read_event_into(buf) {
+            Ok(Event::Start(e)) if e.name().as_ref() == element_name => {
+                depth += 1;
+            }
+            Ok(Event::End(e)) if e.name().as_ref() == element_name => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+            Ok(Event::Eof) => {
+                return Err(DetailParseError::UnterminatedElement {
+                    tag: open_tag,
+                    offset: start_offset,
+                })
+            }
+            Ok(_) => {}
+            Err(e) => {
+                return Err(DetailParseError::XmlRead {
+                    offset,
+                    source: e.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// Generate a stable key for duplicate elements using Base64 hash format.
+/// Format: `base64(hasher.hash64(canonical_key_bytes(document_id, element_name)))_index`.
+fn generate_stable_key(
+    document_id: &str,
+    element_name: &str,
+    index: u32,
+    hasher: &dyn StableKeyHasher,
+) -> String {
+    generate_stable_key_with_codec(document_id, element_name, index, hasher, &Base64KeyCodec)
+}
+
+/// Like [`generate_stable_key`], but rendering the digest with an explicitly
+/// chosen [`KeyCodec`] instead of the default [`Base64KeyCodec`].
+fn generate_stable_key_with_codec(
+    document_id: &str,
+    element_name: &str,
+    index: u32,
+    hasher: &dyn StableKeyHasher,
+    codec: &dyn KeyCodec,
+) -> String {
+    let hash = hasher.hash64(&canonical_key_bytes(document_id, element_name));
+    codec.encode(&hash.to_be_bytes(), index)
+}
+
+/// Enhance a value with minimal metadata for reconstruction.
+/// Only stores the tag name - document ID and index are encoded in the key.
+fn enhance_with_metadata(value: Value, tag: &str, _doc_id: &str, _element_index: u32) -> Value {
+    match value {
+        Value::Object(mut map) => {
+            map.insert(TAG_METADATA.to_string(), Value::String(tag.to_string()));
+            Value::Object(map)
+        }
+        Value::String(text) => {
+            let mut map = Map::new();
+            map.insert(TAG_METADATA.to_string(), Value::String(tag.to_string()));
+            map.insert("_text".to_string(), Value::String(text));
+            Value::Object(map)
+        }
+        other => {
+            // For other types, wrap in object with metadata
+            let mut map = Map::new();
+            map.insert(TAG_METADATA.to_string(), Value::String(tag.to_string()));
+            map.insert("_value".to_string(), other);
+            Value::Object(map)
+        }
+    }
+}
+
+/// Like [`enhance_with_metadata`], but additionally tagging the value as a
+/// coalesced run of `run_len` consecutive identical occurrences starting at
+/// `run_start` within its tag group, so [`convert_stable_keys_to_xml`] can
+/// expand it back into `run_len` repeated elements instead of one.
+fn enhance_with_run_metadata(value: Value, tag: &str, run_start: u32, run_len: u32) -> Value {
+    let mut enhanced = enhance_with_metadata(value, tag, "", 0);
+    if let Value::Object(ref mut map) = enhanced {
+        map.insert(RUN_START_METADATA.to_string(), Value::from(run_start));
+        map.insert(RUN_LEN_METADATA.to_string(), Value::from(run_len));
+    }
+    enhanced
+}
+
+/// Convert a stable key map back to XML.
+///
+/// This function reconstructs XML from a HashMap that may contain stable keys,
+/// grouping duplicate elements by their original tag names and preserving
+/// the relative order within each group.
+///
+/// A stable key whose value has lost its `_tag` metadata (e.g. a CRDT merge
+/// that dropped it) can't be reconstructed; this lenient entry point skips
+/// such entries rather than failing the whole reconstruction. Use
+/// [`try_convert_stable_keys_to_xml`] to be told about them instead.
+///
+/// # Arguments
+/// * `detail_map` - HashMap with CRDT-optimized keys
+///
 /// # Returns
 /// XML string representing the reconstructed detail section
 pub fn convert_stable_keys_to_xml(detail_map: &HashMap<String, Value>) -> String {
+    match try_convert_stable_keys_to_xml(detail_map) {
+        Ok(xml) => xml,
+        Err(err) => {
+            log::warn!(
+                "convert_stable_keys_to_xml: {err}; falling back to a lenient reconstruction \
+                 that skips any key it can't reconstruct"
+            );
+            convert_stable_keys_to_xml_lenient(detail_map, &Base64KeyCodec)
+        }
+    }
+}
+
+/// Like [`convert_stable_keys_to_xml`], but recognizing stable keys encoded
+/// with an explicitly chosen [`KeyCodec`] instead of the default
+/// [`Base64KeyCodec`] — must be the same codec `detail_map`'s stable keys
+/// were generated with.
+pub fn convert_stable_keys_to_xml_with_scheme(
+    detail_map: &HashMap<String, Value>,
+    scheme: &KeyScheme,
+) -> String {
+    match try_convert_stable_keys_to_xml_with_codec(detail_map, scheme.codec) {
+        Ok(xml) => xml,
+        Err(err) => {
+            log::warn!(
+                "convert_stable_keys_to_xml_with_scheme: {err}; falling back to a lenient \
+                 reconstruction that skips any key it can't reconstruct"
+            );
+            convert_stable_keys_to_xml_lenient(detail_map, scheme.codec)
+        }
+    }
+}
+
+/// Like [`convert_stable_keys_to_xml`], but reporting a
+/// [`DetailParseError::MissingTagMetadata`] instead of silently skipping a
+/// stable key whose value has lost the `_tag` metadata needed to recover its
+/// original element name.
+///
+/// # Errors
+/// Returns [`DetailParseError::MissingTagMetadata`] if any key in
+/// `detail_map` is shaped like a stable key but its value isn't an object
+/// carrying a string `_tag`.
+pub fn try_convert_stable_keys_to_xml(
+    detail_map: &HashMap<String, Value>,
+) -> Result<String, DetailParseError> {
+    try_convert_stable_keys_to_xml_with_codec(detail_map, &Base64KeyCodec)
+}
+
+/// Like [`try_convert_stable_keys_to_xml`], but recognizing stable keys
+/// encoded with an explicitly chosen [`KeyCodec`] instead of the default
+/// [`Base64KeyCodec`].
+///
+/// # Errors
+/// Returns [`DetailParseError::MissingTagMetadata`] if any key in
+/// `detail_map` is shaped like a stable key but its value isn't an object
+/// carrying a string `_tag`.
+fn try_convert_stable_keys_to_xml_with_codec(
+    detail_map: &HashMap<String, Value>,
+    codec: &dyn KeyCodec,
+) -> Result<String, DetailParseError> {
     let mut xml = String::from("<detail>");
 
     // Separate direct elements from stable key elements
     let mut direct_elements = Vec::new();
-    let mut stable_elements: HashMap<String, Vec<(u32, Value)>> = HashMap::new();
+    let mut stable_elements: HashMap<String, Vec<(u32, Value, u32)>> = HashMap::new();
 
     for (key, value) in detail_map {
-        if is_stable_key(key) {
-            if let Some(index) = parse_stable_key(key) {
-                // Extract tag name from metadata
-                if let Value::Object(obj) = value {
-                    if let Some(Value::String(tag)) = obj.get(TAG_METADATA) {
-                        stable_elements
-                            .entry(tag.clone())
-                            .or_default()
-                            .push((index, value.clone()));
-                    }
-                }
-            }
+        if let Some((_, index)) = codec.decode(key) {
+            // Extract tag name from metadata
+            let tag = match value {
+                Value::Object(obj) => match obj.get(TAG_METADATA) {
+                    Some(Value::String(tag)) => tag.clone(),
+                    _ => return Err(DetailParseError::MissingTagMetadata { key: key.clone() }),
+                },
+                _ => return Err(DetailParseError::MissingTagMetadata { key: key.clone() }),
+            };
+            let repeat = run_repeat_count(value);
+            stable_elements
+                .entry(tag)
+                .or_default()
+                .push((index, value.clone(), repeat));
         } else {
             direct_elements.push((key.clone(), value.clone()));
         }
@@ -360,33 +1380,79 @@ pub fn convert_stable_keys_to_xml(detail_map: &HashMap<String, Value>) -> String
         xml.push_str(&value_to_xml_element(&tag, &value, false));
     }
 
-    // Add stable key elements, sorted by index within each group
+    // Add stable key elements, sorted by index within each group; a
+    // coalesced run's single value is expanded back into `repeat` elements.
     for (tag, mut elements) in stable_elements {
-        elements.sort_by_key(|(index, _)| *index);
-        for (_, value) in elements {
-            xml.push_str(&value_to_xml_element(&tag, &value, true));
+        elements.sort_by_key(|(index, _, _)| *index);
+        for (_, value, repeat) in elements {
+            let element = value_to_xml_element(&tag, &value, true);
+            for _ in 0..repeat {
+                xml.push_str(&element);
+            }
         }
     }
 
     xml.push_str("</detail>");
-    xml
+    Ok(xml)
 }
 
-/// Check if a key is a stable key (base64 hash format with index).
-fn is_stable_key(key: &str) -> bool {
-    let parts: Vec<&str> = key.split(KEY_SEPARATOR).collect();
-    parts.len() == 2 && parts.last().unwrap().parse::<u32>().is_ok()
+/// How many consecutive elements a stable-key entry's value stands for: the
+/// [`RUN_LEN_METADATA`] set by [`enhance_with_run_metadata`] for a coalesced
+/// run, or 1 for a plain, non-coalesced entry.
+fn run_repeat_count(value: &Value) -> u32 {
+    value
+        .as_object()
+        .and_then(|obj| obj.get(RUN_LEN_METADATA))
+        .and_then(Value::as_u64)
+        .map(|len| len.max(1) as u32)
+        .unwrap_or(1)
 }
 
-/// Parse a stable key to extract index (tag name comes from metadata).
-fn parse_stable_key(key: &str) -> Option<u32> {
-    let parts: Vec<&str> = key.split(KEY_SEPARATOR).collect();
-    if parts.len() == 2 {
-        if let Ok(index) = parts.last().unwrap().parse::<u32>() {
-            return Some(index);
+/// The pre-[`DetailParseError`] reconstruction behavior: skips any stable
+/// key whose value has lost its `_tag` metadata instead of reporting it.
+/// Kept as [`convert_stable_keys_to_xml`]'s fallback for a malformed map.
+fn convert_stable_keys_to_xml_lenient(
+    detail_map: &HashMap<String, Value>,
+    codec: &dyn KeyCodec,
+) -> String {
+    let mut xml = String::from("<detail>");
+
+    let mut direct_elements = Vec::new();
+    let mut stable_elements: HashMap<String, Vec<(u32, Value, u32)>> = HashMap::new();
+
+    for (key, value) in detail_map {
+        if let Some((_, index)) = codec.decode(key) {
+            if let Value::Object(obj) = value {
+                if let Some(Value::String(tag)) = obj.get(TAG_METADATA) {
+                    let repeat = run_repeat_count(value);
+                    stable_elements.entry(tag.clone()).or_default().push((
+                        index,
+                        value.clone(),
+                        repeat,
+                    ));
+                }
+            }
+        } else {
+            direct_elements.push((key.clone(), value.clone()));
+        }
+    }
+
+    for (tag, value) in direct_elements {
+        xml.push_str(&value_to_xml_element(&tag, &value, false));
+    }
+
+    for (tag, mut elements) in stable_elements {
+        elements.sort_by_key(|(index, _, _)| *index);
+        for (_, value, repeat) in elements {
+            let element = value_to_xml_element(&tag, &value, true);
+            for _ in 0..repeat {
+                xml.push_str(&element);
+            }
         }
     }
-    None
+
+    xml.push_str("</detail>");
+    xml
 }
 
 /// Convert a Value to an XML element, optionally removing metadata.
@@ -453,11 +1519,27 @@ pub fn get_next_available_index(
     detail_map: &HashMap<String, Value>,
     document_id: &str,
     element_name: &str,
+) -> u32 {
+    get_next_available_index_with_hasher(
+        detail_map,
+        document_id,
+        element_name,
+        &Fnv1aStableKeyHasher,
+    )
+}
+
+/// Like [`get_next_available_index`], but matching stable keys produced by an
+/// explicitly chosen [`StableKeyHasher`] instead of the default
+/// [`Fnv1aStableKeyHasher`] — must be the same hasher [`generate_stable_key`]
+/// was called with when those keys were created.
+pub fn get_next_available_index_with_hasher(
+    detail_map: &HashMap<String, Value>,
+    document_id: &str,
+    element_name: &str,
+    hasher: &dyn StableKeyHasher,
 ) -> u32 {
     // Generate the expected hash for this document_id + element_name combination
-    let mut hasher = DefaultHasher::new();
-    format!("{}{}{}", document_id, element_name, "stable_key_salt").hash(&mut hasher);
-    let hash = hasher.finish();
+    let hash = hasher.hash64(&canonical_key_bytes(document_id, element_name));
     let hash_bytes = hash.to_be_bytes();
     let b64_hash = URL_SAFE_NO_PAD.encode(hash_bytes);
 
@@ -484,28 +1566,749 @@ pub fn get_next_available_index(
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// A per-`(docId, tag)` high-water-mark counter for stable-key occurrence
+/// indices.
+///
+/// [`get_next_available_index`] rescans every key in the map on each call,
+/// so inserting N elements of the same tag one at a time is O(n²). An
+/// `IndexAllocator` instead keeps a running counter per hash prefix, seeded
+/// once from whatever keys already exist, so each subsequent
+/// [`IndexAllocator::next_index`] call is O(1). It agrees with
+/// [`get_next_available_index`]'s "next after the highest existing index,
+/// including gaps" semantics for the indices it was seeded with, but (unlike
+/// that free function) also accounts for indices it itself has already
+/// handed out, so gaps aren't reused across a single allocator's lifetime.
+#[derive(Debug, Clone, Default)]
+pub struct IndexAllocator {
+    next_by_prefix: HashMap<String, u32>,
+}
 
-    #[test]
-    fn test_parse_simple_detail() {
-        let detail = r#"<detail><status operational="true"/></detail>"#;
-        let result = parse_detail_section_with_stable_keys(detail, "test-doc");
+impl IndexAllocator {
+    /// An allocator with no prior state: every `(docId, tag)` pair starts at
+    /// index 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        assert_eq!(result.len(), 1);
-        assert!(result.contains_key("status"));
+    /// Seeds an allocator from every stable key already present in
+    /// `detail_map`, so the first [`IndexAllocator::next_index`] call for an
+    /// already-populated `(docId, tag)` pair matches what
+    /// [`get_next_available_index`] would have returned. Decoding a key's
+    /// hash prefix needs no hasher — only minting a *new* prefix in
+    /// [`IndexAllocator::next_index`] does.
+    pub fn from_existing(detail_map: &HashMap<String, Value>) -> Self {
+        let mut next_by_prefix: HashMap<String, u32> = HashMap::new();
+        for key in detail_map.keys() {
+            let Some((prefix, index_str)) = key.rsplit_once(KEY_SEPARATOR) else {
+                continue;
+            };
+            let Ok(index) = index_str.parse::<u32>() else {
+                continue;
+            };
+            let next = next_by_prefix.entry(prefix.to_string()).or_insert(0);
+            *next = (*next).max(index + 1);
+        }
+        Self { next_by_prefix }
+    }
 
-        let status = result.get("status").unwrap();
-        assert_eq!(status["operational"], Value::String("true".to_string()));
+    /// Returns the next available index for `(document_id, tag)`, then
+    /// advances this allocator's high-water mark so a later call for the
+    /// same pair returns the following index. Uses the default
+    /// [`Fnv1aStableKeyHasher`]; see [`IndexAllocator::next_index_with_hasher`]
+    /// for a pluggable-hasher variant.
+    pub fn next_index(&mut self, document_id: &str, tag: &str) -> u32 {
+        self.next_index_with_hasher(document_id, tag, &Fnv1aStableKeyHasher)
     }
 
-    #[test]
-    fn test_parse_duplicate_elements() {
-        let detail = r#"<detail>
-            <sensor type="optical" id="sensor-1"/>
-            <sensor type="thermal" id="sensor-2"/>
-            <sensor type="radar" id="sensor-3"/>
+    /// Like [`IndexAllocator::next_index`], but hashing with an explicitly
+    /// chosen [`StableKeyHasher`] — must be the same hasher used to seed
+    /// this allocator's existing keys (via [`generate_stable_key`] or
+    /// similar), or the prefixes won't line up.
+    pub fn next_index_with_hasher(
+        &mut self,
+        document_id: &str,
+        tag: &str,
+        hasher: &dyn StableKeyHasher,
+    ) -> u32 {
+        let hash = hasher.hash64(&canonical_key_bytes(document_id, tag));
+        let prefix = URL_SAFE_NO_PAD.encode(hash.to_be_bytes());
+        let next = self.next_by_prefix.entry(prefix).or_insert(0);
+        let index = *next;
+        *next += 1;
+        index
+    }
+}
+
+/// An inverted index over a stable-key detail map's tags and attribute
+/// values, built by [`build_detail_index`] and queried with [`query_detail`].
+///
+/// Finding "every `sensor` with `type=thermal`" today means iterating
+/// `detail_map.values()` and filtering on [`TAG_METADATA`] and the `type`
+/// attribute by hand — fine for a handful of elements, linear for a large
+/// document. `DetailIndex` is deliberately not kept incrementally in sync
+/// with the map it was built from; rebuild it on demand after a merge or
+/// delta apply so it's always consistent with whatever the map currently
+/// holds, rather than tracking invalidation.
+#[derive(Debug, Clone, Default)]
+pub struct DetailIndex {
+    by_tag: HashMap<String, Vec<String>>,
+    by_attr: HashMap<(String, String), Vec<String>>,
+}
+
+/// Builds a [`DetailIndex`] over `detail_map`'s stable keys: one entry per
+/// tag (from [`TAG_METADATA`]) and one per `(attribute name, attribute
+/// value)` pair, for every string-valued, non-underscore-prefixed attribute.
+/// Metadata keys (`_tag`, `_run_start`, ...) are never indexed as attributes.
+pub fn build_detail_index(detail_map: &HashMap<String, Value>) -> DetailIndex {
+    let mut by_tag: HashMap<String, Vec<String>> = HashMap::new();
+    let mut by_attr: HashMap<(String, String), Vec<String>> = HashMap::new();
+
+    for (key, value) in detail_map {
+        let Value::Object(obj) = value else { continue };
+
+        if let Some(Value::String(tag)) = obj.get(TAG_METADATA) {
+            by_tag.entry(tag.clone()).or_default().push(key.clone());
+        }
+
+        for (attr_name, attr_value) in obj {
+            if attr_name.starts_with('_') {
+                continue;
+            }
+            if let Value::String(attr_value) = attr_value {
+                by_attr
+                    .entry((attr_name.clone(), attr_value.clone()))
+                    .or_default()
+                    .push(key.clone());
+            }
+        }
+    }
+
+    DetailIndex { by_tag, by_attr }
+}
+
+/// Looks up stable keys in `index` matching `tag` and/or `attr`, intersecting
+/// when both are given. `(None, None)` returns no keys — a caller wanting
+/// every key already has the map to iterate directly.
+pub fn query_detail<'a>(
+    index: &'a DetailIndex,
+    tag: Option<&str>,
+    attr: Option<(&str, &str)>,
+) -> Vec<&'a str> {
+    let by_tag = tag.map(|t| index.by_tag.get(t).map_or(&[][..], Vec::as_slice));
+    let by_attr = attr.map(|(name, value)| {
+        index
+            .by_attr
+            .get(&(name.to_string(), value.to_string()))
+            .map_or(&[][..], Vec::as_slice)
+    });
+
+    match (by_tag, by_attr) {
+        (Some(tag_keys), Some(attr_keys)) => {
+            let attr_set: std::collections::HashSet<&str> =
+                attr_keys.iter().map(String::as_str).collect();
+            tag_keys
+                .iter()
+                .map(String::as_str)
+                .filter(|key| attr_set.contains(key))
+                .collect()
+        }
+        (Some(tag_keys), None) => tag_keys.iter().map(String::as_str).collect(),
+        (None, Some(attr_keys)) => attr_keys.iter().map(String::as_str).collect(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/// Per-key change tracked by a [`DetailDelta`]: which attributes inside a
+/// stable-key entry's `Value::Object` were added, removed, or changed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldChanges {
+    /// Attribute names newly present, with their values.
+    pub added: HashMap<String, Value>,
+    /// Attribute names present in the old value but dropped from the new one.
+    pub removed: Vec<String>,
+    /// Attribute names present on both sides with a different value, mapped
+    /// to `(old_value, new_value)`.
+    pub changed: HashMap<String, (Value, Value)>,
+}
+
+/// What changed between two versions of a stable-key detail map, as
+/// produced by [`diff_detail_maps`].
+///
+/// Diffing keys on the stable key itself — rather than positionally — means
+/// two peers exchanging only a `DetailDelta` converge without shipping the
+/// whole detail section: a stable key already encodes which logical element
+/// it is, independent of parse order, so an element unchanged by either peer
+/// simply doesn't appear in either delta.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DetailDelta {
+    /// Stable keys present in the new map but absent from the old one, with
+    /// their values.
+    pub added: HashMap<String, Value>,
+    /// Stable keys present in the old map but absent from the new one.
+    pub removed: Vec<String>,
+    /// Stable keys present in both maps with a different value, with the
+    /// field-level breakdown of what changed.
+    pub changed: HashMap<String, FieldChanges>,
+}
+
+/// Computes what changed between two versions of a stable-key detail map.
+///
+/// A key present on both sides with an unchanged value doesn't appear in the
+/// result at all. A key present on both sides with a changed value is
+/// reported field-by-field via [`FieldChanges`] rather than as an opaque
+/// whole-value replacement, so [`merge_detail_maps`] can resolve a conflict
+/// on one attribute (e.g. `sensor.type`) without clobbering a concurrent,
+/// non-conflicting edit to a sibling attribute (e.g. `sensor.id`).
+pub fn diff_detail_maps(old: &HashMap<String, Value>, new: &HashMap<String, Value>) -> DetailDelta {
+    let mut delta = DetailDelta::default();
+
+    for (key, new_value) in new {
+        match old.get(key) {
+            None => {
+                delta.added.insert(key.clone(), new_value.clone());
+            }
+            Some(old_value) if old_value != new_value => {
+                delta
+                    .changed
+                    .insert(key.clone(), diff_fields(old_value, new_value));
+            }
+            _ => {}
+        }
+    }
+
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            delta.removed.push(key.clone());
+        }
+    }
+
+    delta
+}
+
+/// Diffs two stable-key entries' values field-by-field. A value that isn't a
+/// JSON object (the `_text`/`_value`-wrapped scalar cases `enhance_with_metadata`
+/// produces) is treated as a single `_value` field, so a scalar change still
+/// round-trips through [`FieldChanges`].
+fn diff_fields(old: &Value, new: &Value) -> FieldChanges {
+    if !old.is_object() || !new.is_object() {
+        let mut changed = HashMap::new();
+        if old != new {
+            changed.insert("_value".to_string(), (old.clone(), new.clone()));
+        }
+        return FieldChanges {
+            changed,
+            ..Default::default()
+        };
+    }
+
+    let empty = Map::new();
+    let old_obj = old.as_object().unwrap_or(&empty);
+    let new_obj = new.as_object().unwrap_or(&empty);
+
+    let mut changes = FieldChanges::default();
+
+    for (field, new_val) in new_obj {
+        match old_obj.get(field) {
+            None => {
+                changes.added.insert(field.clone(), new_val.clone());
+            }
+            Some(old_val) if old_val != new_val => {
+                changes
+                    .changed
+                    .insert(field.clone(), (old_val.clone(), new_val.clone()));
+            }
+            _ => {}
+        }
+    }
+    for field in old_obj.keys() {
+        if !new_obj.contains_key(field) {
+            changes.removed.push(field.clone());
+        }
+    }
+
+    changes
+}
+
+/// Applies a [`DetailDelta`] computed against `map` to `map`, reproducing
+/// the `new` map [`diff_detail_maps`] computed it from.
+pub fn apply_delta(map: &HashMap<String, Value>, delta: &DetailDelta) -> HashMap<String, Value> {
+    let mut result = map.clone();
+
+    for key in &delta.removed {
+        result.remove(key);
+    }
+    for (key, value) in &delta.added {
+        result.insert(key.clone(), value.clone());
+    }
+    for (key, changes) in &delta.changed {
+        let entry = result
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        apply_field_changes(entry, changes);
+    }
+
+    result
+}
+
+/// Applies a [`FieldChanges`] in place to a stable-key entry's value.
+fn apply_field_changes(value: &mut Value, changes: &FieldChanges) {
+    let Value::Object(obj) = value else { return };
+
+    for (field, field_value) in &changes.added {
+        obj.insert(field.clone(), field_value.clone());
+    }
+    for (field, (_, new_val)) in &changes.changed {
+        obj.insert(field.clone(), new_val.clone());
+    }
+    for field in &changes.removed {
+        obj.remove(field);
+    }
+}
+
+/// Alias for [`diff_detail_maps`] under the name a transport-layer caller
+/// shipping change-sets instead of whole maps would look for first; see
+/// [`DetailDelta`] for the patch shape this computes.
+pub fn compute_detail_delta(
+    old: &HashMap<String, Value>,
+    new: &HashMap<String, Value>,
+) -> DetailDelta {
+    diff_detail_maps(old, new)
+}
+
+/// Alias for [`apply_delta`] under the name that pairs with
+/// [`compute_detail_delta`].
+pub fn apply_detail_delta(
+    map: &HashMap<String, Value>,
+    delta: &DetailDelta,
+) -> HashMap<String, Value> {
+    apply_delta(map, delta)
+}
+
+/// Picks a winner for a key- or field-level conflict in [`merge_detail_maps`],
+/// so the merge isn't hard-wired to one conflict-resolution strategy.
+pub trait ConflictPolicy {
+    /// Returns `true` if `remote`'s value should win over `local`'s for
+    /// stable key `key` (and, for a field-level conflict, attribute `field`).
+    fn remote_wins(&self, key: &str, field: Option<&str>, local: &Value, remote: &Value) -> bool;
+}
+
+/// The default [`ConflictPolicy`]: last-write-wins, favoring whichever side
+/// the caller designates as the more recently written one (`remote`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoteWins;
+
+impl ConflictPolicy for RemoteWins {
+    fn remote_wins(
+        &self,
+        _key: &str,
+        _field: Option<&str>,
+        _local: &Value,
+        _remote: &Value,
+    ) -> bool {
+        true
+    }
+}
+
+/// Three-way merges two concurrently-edited versions of a stable-key detail
+/// map against their common `base`, resolving conflicts with the default
+/// [`RemoteWins`] policy.
+///
+/// A key or field touched by only one side is carried through unchanged; a
+/// key or field touched by both sides to the same value is an agreement, not
+/// a conflict. Only a key or field both sides changed to *different* values
+/// goes through the conflict policy.
+pub fn merge_detail_maps(
+    base: &HashMap<String, Value>,
+    local: &HashMap<String, Value>,
+    remote: &HashMap<String, Value>,
+) -> HashMap<String, Value> {
+    merge_detail_maps_with_policy(base, local, remote, &RemoteWins)
+}
+
+/// Like [`merge_detail_maps`], but resolving conflicts with an explicitly
+/// chosen [`ConflictPolicy`] instead of the default [`RemoteWins`].
+pub fn merge_detail_maps_with_policy(
+    base: &HashMap<String, Value>,
+    local: &HashMap<String, Value>,
+    remote: &HashMap<String, Value>,
+    policy: &dyn ConflictPolicy,
+) -> HashMap<String, Value> {
+    let local_delta = diff_detail_maps(base, local);
+    let remote_delta = diff_detail_maps(base, remote);
+
+    let mut merged = apply_delta(base, &local_delta);
+
+    for key in &remote_delta.removed {
+        let locally_touched =
+            local_delta.added.contains_key(key) || local_delta.changed.contains_key(key);
+        let remote_should_win = !locally_touched
+            || merged.get(key).map_or(true, |local_value| {
+                policy.remote_wins(key, None, local_value, &Value::Null)
+            });
+        if remote_should_win {
+            merged.remove(key);
+        }
+    }
+
+    for (key, remote_value) in &remote_delta.added {
+        match local_delta.added.get(key) {
+            Some(local_value) if local_value != remote_value => {
+                if policy.remote_wins(key, None, local_value, remote_value) {
+                    merged.insert(key.clone(), remote_value.clone());
+                }
+            }
+            None => {
+                merged.insert(key.clone(), remote_value.clone());
+            }
+            _ => {}
+        }
+    }
+
+    for (key, remote_changes) in &remote_delta.changed {
+        let Some(Value::Object(obj)) = merged.get_mut(key) else {
+            continue;
+        };
+        let local_changes = local_delta.changed.get(key);
+
+        for (field, remote_field_value) in &remote_changes.added {
+            match local_changes.and_then(|c| c.added.get(field)) {
+                Some(local_field_value) if local_field_value != remote_field_value => {
+                    if policy.remote_wins(key, Some(field), local_field_value, remote_field_value) {
+                        obj.insert(field.clone(), remote_field_value.clone());
+                    }
+                }
+                _ => {
+                    obj.insert(field.clone(), remote_field_value.clone());
+                }
+            }
+        }
+
+        for (field, (_, remote_new)) in &remote_changes.changed {
+            match local_changes.and_then(|c| c.changed.get(field)) {
+                Some((_, local_new)) if local_new != remote_new => {
+                    if policy.remote_wins(key, Some(field), local_new, remote_new) {
+                        obj.insert(field.clone(), remote_new.clone());
+                    }
+                }
+                _ => {
+                    obj.insert(field.clone(), remote_new.clone());
+                }
+            }
+        }
+
+        for field in &remote_changes.removed {
+            let locally_touched = local_changes
+                .map(|c| c.added.contains_key(field) || c.changed.contains_key(field))
+                .unwrap_or(false);
+            if !locally_touched {
+                obj.remove(field);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Reserved key under which each element's [`CausalStamp`] is stored; see
+/// [`DetailCrdtState`].
+const STAMP_METADATA: &str = "_stamp";
+/// Reserved key under which each element's per-attribute [`CausalStamp`]s
+/// are stored, keyed by attribute name; see [`DetailCrdtState`].
+const ATTR_STAMPS_METADATA: &str = "_attr_stamps";
+
+/// A replica-scoped logical clock value: a Lamport counter paired with the
+/// id of the replica that wrote it. Ordered lexicographically, counter
+/// first, so two replicas independently comparing the same pair of stamps
+/// always agree on which one is "newer" — no wall-clock or common ancestor
+/// required.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CausalStamp {
+    /// The writing replica's Lamport counter at the time of the write.
+    pub counter: u64,
+    /// The id of the replica that made the write.
+    pub replica_id: String,
+}
+
+impl CausalStamp {
+    /// Builds a stamp from a counter and replica id.
+    pub fn new(counter: u64, replica_id: impl Into<String>) -> Self {
+        Self {
+            counter,
+            replica_id: replica_id.into(),
+        }
+    }
+}
+
+/// Reads the element-level [`CausalStamp`] [`DetailCrdtState::upsert`]
+/// attached to `value` under [`STAMP_METADATA`], or `None` if `value` isn't
+/// a stamped CRDT element.
+fn element_stamp(value: &Value) -> Option<CausalStamp> {
+    value
+        .as_object()?
+        .get(STAMP_METADATA)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+}
+
+/// Reads attribute `attr`'s own [`CausalStamp`] from `value`'s
+/// [`ATTR_STAMPS_METADATA`], falling back to the element-level stamp if the
+/// attribute has none of its own (e.g. a value merged in from a pre-CRDT
+/// stable-key map that was never attribute-stamped).
+fn attr_stamp(value: &Value, attr: &str) -> Option<CausalStamp> {
+    let per_attr = value
+        .as_object()
+        .and_then(|obj| obj.get(ATTR_STAMPS_METADATA))
+        .and_then(|v| v.as_object())
+        .and_then(|stamps| stamps.get(attr))
+        .and_then(|v| serde_json::from_value(v.clone()).ok());
+    per_attr.or_else(|| element_stamp(value))
+}
+
+/// A state-based (no common-ancestor) CRDT view over a stable-key detail
+/// map: an OR-Set of element keys — present iff their stamp beats any
+/// tombstone for that key — layered with per-attribute last-writer-wins
+/// registers, so [`merge_crdt_states`] gives two replicas a single
+/// deterministic merge result regardless of what either one merged from
+/// before. Contrast with [`merge_detail_maps`], which needs a shared `base`
+/// and resolves whole-key/whole-field conflicts rather than tracking
+/// removals as tombstones — so a concurrent remove-and-re-add can't be told
+/// apart from "never removed" there, which is exactly the resurrection bug
+/// this type exists to avoid.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DetailCrdtState {
+    /// Live elements, each stamped with [`STAMP_METADATA`] and
+    /// [`ATTR_STAMPS_METADATA`] by [`DetailCrdtState::upsert`].
+    pub elements: HashMap<String, Value>,
+    /// Removed keys, with the stamp at which they were removed. A key only
+    /// reappears once a concurrent re-add's stamp beats its tombstone.
+    pub tombstones: HashMap<String, CausalStamp>,
+}
+
+impl DetailCrdtState {
+    /// An empty state with no live elements and no tombstones.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts or overwrites `key`'s element with `tag` and `attrs`,
+    /// stamping the element and every one of its attributes with `stamp`,
+    /// and clearing any tombstone for `key` (a fresh write un-removes it).
+    pub fn upsert(&mut self, key: &str, tag: &str, attrs: Map<String, Value>, stamp: CausalStamp) {
+        let stamp_value = serde_json::to_value(&stamp).unwrap_or(Value::Null);
+        let attr_stamps: Map<String, Value> = attrs
+            .keys()
+            .map(|attr| (attr.clone(), stamp_value.clone()))
+            .collect();
+
+        let mut obj = attrs;
+        obj.insert(TAG_METADATA.to_string(), Value::String(tag.to_string()));
+        obj.insert(STAMP_METADATA.to_string(), stamp_value);
+        obj.insert(ATTR_STAMPS_METADATA.to_string(), Value::Object(attr_stamps));
+
+        self.elements.insert(key.to_string(), Value::Object(obj));
+        self.tombstones.remove(key);
+    }
+
+    /// Removes `key`, recording `stamp` as the tombstone a concurrent re-add
+    /// must beat to resurrect it. A tombstone newer than any existing one
+    /// for `key` replaces it; an older one is ignored.
+    pub fn remove(&mut self, key: &str, stamp: CausalStamp) {
+        self.elements.remove(key);
+        self.tombstones
+            .entry(key.to_string())
+            .and_modify(|existing| {
+                if stamp > *existing {
+                    *existing = stamp.clone();
+                }
+            })
+            .or_insert(stamp);
+    }
+}
+
+/// Merges two independently-evolved [`DetailCrdtState`]s with no shared
+/// `base`, converging deterministically on every replica that computes it.
+///
+/// Per key: present in one side but not the other (and not tombstoned past
+/// its stamp) survives as-is; present in both, alive on both sides, merges
+/// attribute-by-attribute via [`attr_stamp`], keeping whichever side's
+/// stamp is lexicographically greater; tombstoned on either side with a
+/// stamp beating the other side's live stamp stays removed. Tombstones
+/// themselves union, keeping the newer stamp per key where both sides
+/// recorded a removal.
+pub fn merge_crdt_states(local: &DetailCrdtState, remote: &DetailCrdtState) -> DetailCrdtState {
+    let mut tombstones = local.tombstones.clone();
+    for (key, remote_stamp) in &remote.tombstones {
+        tombstones
+            .entry(key.clone())
+            .and_modify(|existing| {
+                if remote_stamp > existing {
+                    *existing = remote_stamp.clone();
+                }
+            })
+            .or_insert_with(|| remote_stamp.clone());
+    }
+
+    let keys: std::collections::HashSet<&String> = local
+        .elements
+        .keys()
+        .chain(remote.elements.keys())
+        .collect();
+
+    let mut elements = HashMap::new();
+    for key in keys {
+        let local_value = local.elements.get(key);
+        let remote_value = remote.elements.get(key);
+        let tombstone = tombstones.get(key);
+
+        let local_alive = local_value
+            .and_then(element_stamp)
+            .is_some_and(|stamp| tombstone.map_or(true, |t| stamp > *t));
+        let remote_alive = remote_value
+            .and_then(element_stamp)
+            .is_some_and(|stamp| tombstone.map_or(true, |t| stamp > *t));
+
+        match (local_alive, remote_alive) {
+            (false, false) => {}
+            (true, false) => {
+                elements.insert(key.clone(), local_value.unwrap().clone());
+            }
+            (false, true) => {
+                elements.insert(key.clone(), remote_value.unwrap().clone());
+            }
+            (true, true) => {
+                let merged = merge_crdt_element(local_value.unwrap(), remote_value.unwrap());
+                elements.insert(key.clone(), merged);
+            }
+        }
+    }
+
+    DetailCrdtState {
+        elements,
+        tombstones,
+    }
+}
+
+/// Merges two live, stamped versions of the same element attribute-by-
+/// attribute: for each attribute present on either side, keeps the value
+/// whose [`attr_stamp`] is greater, with ties (equal stamps, as when only
+/// one side ever touched the attribute) kept from `local` for determinism.
+/// `_tag` is kept as-is (both sides must agree — it names the same logical
+/// element); the merged element's own [`STAMP_METADATA`] becomes whichever
+/// side's element-level stamp is greater.
+fn merge_crdt_element(local: &Value, remote: &Value) -> Value {
+    let (Some(local_obj), Some(remote_obj)) = (local.as_object(), remote.as_object()) else {
+        return if element_stamp(remote) > element_stamp(local) {
+            remote.clone()
+        } else {
+            local.clone()
+        };
+    };
+
+    let attrs: std::collections::HashSet<&String> = local_obj
+        .keys()
+        .chain(remote_obj.keys())
+        .filter(|k| !k.starts_with('_'))
+        .collect();
+
+    let mut merged = Map::new();
+    if let Some(tag) = local_obj.get(TAG_METADATA).or_else(|| remote_obj.get(TAG_METADATA)) {
+        merged.insert(TAG_METADATA.to_string(), tag.clone());
+    }
+
+    let mut attr_stamps = Map::new();
+    for attr in attrs {
+        let local_val = local_obj.get(attr);
+        let remote_val = remote_obj.get(attr);
+        let local_stamp = local_val.and_then(|_| attr_stamp(local, attr));
+        let remote_stamp = remote_val.and_then(|_| attr_stamp(remote, attr));
+
+        let (winner, winner_stamp) = match (local_val, remote_val) {
+            (Some(lv), Some(rv)) => {
+                if remote_stamp > local_stamp {
+                    (rv, remote_stamp)
+                } else {
+                    (lv, local_stamp)
+                }
+            }
+            (Some(lv), None) => (lv, local_stamp),
+            (None, Some(rv)) => (rv, remote_stamp),
+            (None, None) => continue,
+        };
+
+        merged.insert(attr.clone(), winner.clone());
+        if let Some(stamp) = winner_stamp {
+            if let Ok(stamp_value) = serde_json::to_value(&stamp) {
+                attr_stamps.insert(attr.clone(), stamp_value);
+            }
+        }
+    }
+    merged.insert(ATTR_STAMPS_METADATA.to_string(), Value::Object(attr_stamps));
+
+    let local_stamp = element_stamp(local);
+    let remote_stamp = element_stamp(remote);
+    let element_winner_stamp = if remote_stamp > local_stamp {
+        remote_stamp
+    } else {
+        local_stamp
+    };
+    if let Some(stamp) = element_winner_stamp {
+        if let Ok(stamp_value) = serde_json::to_value(&stamp) {
+            merged.insert(STAMP_METADATA.to_string(), stamp_value);
+        }
+    }
+
+    Value::Object(merged)
+}
+
+/// Mints a stable key for a newly-added CRDT element that's collision-safe
+/// even when two replicas concurrently add an element of the same `tag`:
+/// unlike [`get_next_available_index`], whose plain numeric index two
+/// replicas can independently assign to two different elements, this folds
+/// `replica_id` into the key itself so concurrent additions never collide —
+/// at the cost of the key no longer being purely sequential.
+pub fn next_crdt_key(
+    state: &DetailCrdtState,
+    document_id: &str,
+    tag: &str,
+    replica_id: &str,
+    hasher: &dyn StableKeyHasher,
+) -> String {
+    // Keys minted here carry a `_{replica_id}` suffix after the index, so
+    // read the index as the first segment after the hash prefix.
+    let hash = hasher.hash64(&canonical_key_bytes(document_id, tag));
+    let key_prefix = format!("{}{}", URL_SAFE_NO_PAD.encode(hash.to_be_bytes()), KEY_SEPARATOR);
+    let base_index = state
+        .elements
+        .keys()
+        .filter_map(|key| key.strip_prefix(&key_prefix))
+        .filter_map(|rest| rest.split(KEY_SEPARATOR).next()?.parse::<u32>().ok())
+        .max()
+        .map_or(0, |max| max + 1);
+    let stable_key = generate_stable_key(document_id, tag, base_index, hasher);
+    format!("{stable_key}{KEY_SEPARATOR}{replica_id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_detail() {
+        let detail = r#"<detail><status operational="true"/></detail>"#;
+        let result = parse_detail_section_with_stable_keys(detail, "test-doc");
+
+        assert_eq!(result.len(), 1);
+        assert!(result.contains_key("status"));
+
+        let status = result.get("status").unwrap();
+        assert_eq!(status["operational"], Value::String("true".to_string()));
+    }
+
+    #[test]
+    fn test_parse_duplicate_elements() {
+        let detail = r#"<detail>
+            <sensor type="optical" id="sensor-1"/>
+            <sensor type="thermal" id="sensor-2"/>
+            <sensor type="radar" id="sensor-3"/>
             <status operational="true"/>
         </detail>"#;
 
@@ -565,12 +2368,10 @@ mod tests {
     fn test_get_next_available_index() {
         let mut detail_map = HashMap::new();
 
-        // Generate expected hash for sensor elements
-        let mut hasher = DefaultHasher::new();
-        format!("{}{}{}", "test-doc", "sensor", "stable_key_salt").hash(&mut hasher);
-        let hash = hasher.finish();
-        let hash_bytes = hash.to_be_bytes();
-        let b64_hash = URL_SAFE_NO_PAD.encode(hash_bytes);
+        // Generate expected hash for sensor elements using the same default
+        // hasher and canonical byte layout generate_stable_key uses.
+        let hash = Fnv1aStableKeyHasher.hash64(&canonical_key_bytes("test-doc", "sensor"));
+        let b64_hash = URL_SAFE_NO_PAD.encode(hash.to_be_bytes());
 
         detail_map.insert(format!("{}_0", b64_hash), Value::Null);
         detail_map.insert(format!("{}_2", b64_hash), Value::Null);
@@ -581,4 +2382,589 @@ mod tests {
         let next_contact = get_next_available_index(&detail_map, "test-doc", "contact");
         assert_eq!(next_contact, 0); // No existing contacts
     }
+
+    #[test]
+    fn index_allocator_seeded_from_existing_matches_get_next_available_index() {
+        let hash = Fnv1aStableKeyHasher.hash64(&canonical_key_bytes("test-doc", "sensor"));
+        let b64_hash = URL_SAFE_NO_PAD.encode(hash.to_be_bytes());
+
+        let mut detail_map = HashMap::new();
+        detail_map.insert(format!("{}_0", b64_hash), Value::Null);
+        detail_map.insert(format!("{}_2", b64_hash), Value::Null);
+
+        let mut allocator = IndexAllocator::from_existing(&detail_map);
+        assert_eq!(allocator.next_index("test-doc", "sensor"), 3);
+        assert_eq!(allocator.next_index("test-doc", "contact"), 0);
+    }
+
+    #[test]
+    fn index_allocator_advances_its_own_high_water_mark_across_calls() {
+        let mut allocator = IndexAllocator::new();
+        assert_eq!(allocator.next_index("test-doc", "sensor"), 0);
+        assert_eq!(allocator.next_index("test-doc", "sensor"), 1);
+        assert_eq!(allocator.next_index("test-doc", "sensor"), 2);
+        // A different tag gets its own independent counter.
+        assert_eq!(allocator.next_index("test-doc", "contact"), 0);
+    }
+
+    #[test]
+    fn query_detail_by_tag_and_attr_intersect() {
+        let mut detail_map = HashMap::new();
+        detail_map.insert(
+            "sensor_0".to_string(),
+            serde_json::json!({"_tag": "sensor", "type": "optical"}),
+        );
+        detail_map.insert(
+            "sensor_1".to_string(),
+            serde_json::json!({"_tag": "sensor", "type": "thermal"}),
+        );
+        detail_map.insert(
+            "contact_0".to_string(),
+            serde_json::json!({"_tag": "contact", "type": "thermal"}),
+        );
+
+        let index = build_detail_index(&detail_map);
+
+        let mut sensors = query_detail(&index, Some("sensor"), None);
+        sensors.sort_unstable();
+        assert_eq!(sensors, vec!["sensor_0", "sensor_1"]);
+
+        let mut thermal = query_detail(&index, None, Some(("type", "thermal")));
+        thermal.sort_unstable();
+        assert_eq!(thermal, vec!["contact_0", "sensor_1"]);
+
+        let thermal_sensors = query_detail(&index, Some("sensor"), Some(("type", "thermal")));
+        assert_eq!(thermal_sensors, vec!["sensor_1"]);
+
+        assert!(query_detail(&index, None, None).is_empty());
+    }
+
+    #[test]
+    fn detail_index_skips_metadata_keys_as_attributes() {
+        let mut detail_map = HashMap::new();
+        detail_map.insert(
+            "sensor_0".to_string(),
+            serde_json::json!({"_tag": "sensor", "_run_len": 3, "type": "optical"}),
+        );
+
+        let index = build_detail_index(&detail_map);
+
+        assert!(query_detail(&index, None, Some(("_tag", "sensor"))).is_empty());
+        assert_eq!(
+            query_detail(&index, None, Some(("type", "optical"))),
+            vec!["sensor_0"]
+        );
+    }
+
+    /// Locks the wire format: a hard-coded `(document_id, element_name,
+    /// index)` input must always produce this exact key, so a future change
+    /// to the canonical byte layout or hash family is caught here rather
+    /// than silently breaking cross-peer convergence.
+    #[test]
+    fn generate_stable_key_matches_locked_format_constant() {
+        let key = generate_stable_key("test-doc", "sensor", 0, &Fnv1aStableKeyHasher);
+        assert_eq!(key, "IdRgI3AYHRk_0");
+    }
+
+    /// Locks `sha256_stable_key`'s wire format: a hard-coded input must
+    /// always produce this exact key, so a from-scratch Java/Kotlin/Swift
+    /// implementation hashing the same `{docId}{tag}{salt}` string has a
+    /// known-good value to check itself against.
+    #[test]
+    fn sha256_stable_key_matches_locked_format_constant() {
+        let key = sha256_stable_key("test-doc", "sensor", 0);
+        assert_eq!(key, "c0Vk8nWQuw8_0");
+    }
+
+    #[test]
+    fn sha256_stable_key_is_deterministic_across_calls() {
+        assert_eq!(
+            sha256_stable_key("test-doc", "sensor", 3),
+            sha256_stable_key("test-doc", "sensor", 3)
+        );
+    }
+
+    #[test]
+    fn fnv1a_hash64_is_deterministic_across_calls() {
+        let bytes = canonical_key_bytes("test-doc", "sensor");
+        assert_eq!(
+            Fnv1aStableKeyHasher.hash64(&bytes),
+            Fnv1aStableKeyHasher.hash64(&bytes)
+        );
+    }
+
+    #[test]
+    fn try_parse_succeeds_on_well_formed_detail() {
+        let detail = r#"<detail><status operational="true"/></detail>"#;
+        let result = try_parse_detail_section_with_stable_keys(detail, "test-doc").unwrap();
+        assert!(result.contains_key("status"));
+    }
+
+    #[test]
+    fn try_parse_reports_an_unclosed_element() {
+        let detail = r#"<detail><sensor type="optical">"#;
+        let err = try_parse_detail_section_with_stable_keys(detail, "test-doc").unwrap_err();
+        assert!(matches!(
+            err,
+            DetailParseError::UnterminatedElement { ref tag, .. } if tag == "sensor"
+        ));
+    }
+
+    #[test]
+    fn try_parse_reports_eof_before_closing_detail_tag() {
+        let detail = r#"<detail><status operational="true"/>"#;
+        let err = try_parse_detail_section_with_stable_keys(detail, "test-doc").unwrap_err();
+        assert!(matches!(err, DetailParseError::UnexpectedEof { .. }));
+    }
+
+    #[test]
+    fn try_parse_reports_an_unescapable_entity() {
+        let detail = "<detail><status>bad &notanentity; value</status></detail>";
+        let err = try_parse_detail_section_with_stable_keys(detail, "test-doc").unwrap_err();
+        assert!(matches!(err, DetailParseError::UnescapeFailed { .. }));
+    }
+
+    #[test]
+    fn infallible_parse_falls_back_to_lenient_best_effort_on_malformed_input() {
+        let detail = r#"<detail><sensor type="optical">"#;
+        // The strict parser would reject this; the infallible wrapper still
+        // returns a map rather than panicking or propagating the error.
+        let result = parse_detail_section_with_stable_keys(detail, "test-doc");
+        assert_eq!(result.len(), 1);
+        assert_eq!(result["sensor"]["type"], Value::String("optical".to_string()));
+    }
+
+    #[test]
+    fn try_convert_reports_a_stable_key_missing_tag_metadata() {
+        let mut detail_map = HashMap::new();
+        detail_map.insert(
+            "IdRgI3AYHRk_0".to_string(),
+            Value::Object(Map::new()), // no `_tag` metadata
+        );
+        let err = try_convert_stable_keys_to_xml(&detail_map).unwrap_err();
+        assert!(matches!(
+            err,
+            DetailParseError::MissingTagMetadata { ref key } if key == "IdRgI3AYHRk_0"
+        ));
+    }
+
+    #[test]
+    fn infallible_convert_falls_back_to_lenient_reconstruction_on_missing_tag_metadata() {
+        let mut detail_map = HashMap::new();
+        detail_map.insert("IdRgI3AYHRk_0".to_string(), Value::Object(Map::new()));
+        // The lenient fallback simply omits the unreconstructable entry.
+        assert_eq!(convert_stable_keys_to_xml(&detail_map), "<detail></detail>");
+    }
+
+    #[test]
+    fn build_runs_coalesces_consecutive_identical_values_only() {
+        let a = Value::String("a".to_string());
+        let b = Value::String("b".to_string());
+        let occurrences = vec![
+            (0, a.clone()),
+            (1, a.clone()),
+            (2, a.clone()),
+            (3, b.clone()),
+            (4, a.clone()),
+        ];
+
+        let runs = build_runs(occurrences);
+
+        assert_eq!(
+            runs,
+            vec![
+                ElementRun {
+                    start_index: 0,
+                    len: 3,
+                    value: a.clone()
+                },
+                ElementRun {
+                    start_index: 3,
+                    len: 1,
+                    value: b
+                },
+                ElementRun {
+                    start_index: 4,
+                    len: 1,
+                    value: a
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn coalesced_parse_collapses_a_repeated_run_into_one_entry() {
+        let detail = r#"<detail>
+            <track lat="1.0" lon="2.0"/>
+            <track lat="1.0" lon="2.0"/>
+            <track lat="1.0" lon="2.0"/>
+            <status operational="true"/>
+        </detail>"#;
+
+        let result = parse_detail_section_with_stable_keys_coalesced(detail, "test-doc");
+
+        // status (single) + one coalesced track run = 2 entries, not 4.
+        assert_eq!(result.len(), 2);
+
+        let run = result
+            .values()
+            .find(|v| v.get(TAG_METADATA) == Some(&Value::String("track".to_string())))
+            .unwrap();
+        assert_eq!(run[RUN_START_METADATA], Value::from(0u32));
+        assert_eq!(run[RUN_LEN_METADATA], Value::from(3u32));
+    }
+
+    #[test]
+    fn coalesced_parse_keeps_non_repeating_duplicates_as_individual_entries() {
+        let detail = r#"<detail>
+            <sensor type="optical"/>
+            <sensor type="thermal"/>
+        </detail>"#;
+
+        let result = parse_detail_section_with_stable_keys_coalesced(detail, "test-doc");
+
+        assert_eq!(result.len(), 2);
+        assert!(result.values().all(|v| v.get(RUN_LEN_METADATA).is_none()));
+    }
+
+    #[test]
+    fn coalesced_round_trip_expands_back_to_byte_identical_xml() {
+        let detail = r#"<detail><track lat="1.0" lon="2.0"/><track lat="1.0" lon="2.0"/><track lat="1.0" lon="2.0"/></detail>"#;
+
+        let plain = parse_detail_section_with_stable_keys(detail, "test-doc");
+        let plain_xml = convert_stable_keys_to_xml(&plain);
+
+        let coalesced = parse_detail_section_with_stable_keys_coalesced(detail, "test-doc");
+        let coalesced_xml = convert_stable_keys_to_xml(&coalesced);
+
+        assert_eq!(coalesced_xml, plain_xml);
+    }
+
+    fn sensor(id: &str) -> Value {
+        serde_json::json!({ "_tag": "sensor", "id": id, "type": "optical" })
+    }
+
+    #[test]
+    fn diff_detects_added_removed_and_changed_keys() {
+        let mut old = HashMap::new();
+        old.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "true"}),
+        );
+        old.insert("sensor_0".to_string(), sensor("sensor-1"));
+
+        let mut new = HashMap::new();
+        new.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "false"}),
+        );
+        new.insert("sensor_1".to_string(), sensor("sensor-2"));
+
+        let delta = diff_detail_maps(&old, &new);
+
+        assert_eq!(delta.removed, vec!["sensor_0".to_string()]);
+        assert!(delta.added.contains_key("sensor_1"));
+        let status_changes = delta.changed.get("status").unwrap();
+        assert_eq!(
+            status_changes.changed.get("operational"),
+            Some(&(
+                Value::String("true".to_string()),
+                Value::String("false".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn apply_delta_reconstructs_the_new_map() {
+        let mut old = HashMap::new();
+        old.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "true"}),
+        );
+
+        let mut new = HashMap::new();
+        new.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "false", "battery": "90"}),
+        );
+        new.insert("sensor_0".to_string(), sensor("sensor-1"));
+
+        let delta = diff_detail_maps(&old, &new);
+        let reconstructed = apply_delta(&old, &delta);
+
+        assert_eq!(reconstructed, new);
+    }
+
+    #[test]
+    fn apply_detail_delta_round_trips_through_compute_detail_delta() {
+        let mut old = HashMap::new();
+        old.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "true"}),
+        );
+        old.insert("sensor_0".to_string(), sensor("sensor-1"));
+
+        let mut new = HashMap::new();
+        new.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "false", "battery": "90"}),
+        );
+        new.insert("sensor_1".to_string(), sensor("sensor-2"));
+
+        let delta = compute_detail_delta(&old, &new);
+        let reconstructed = apply_detail_delta(&old, &delta);
+
+        assert_eq!(reconstructed, new);
+    }
+
+    #[test]
+    fn merge_carries_through_non_conflicting_edits_from_both_sides() {
+        let mut base = HashMap::new();
+        base.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "true"}),
+        );
+
+        let mut local = base.clone();
+        local.insert("sensor_0".to_string(), sensor("sensor-1"));
+
+        let mut remote = base.clone();
+        remote.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "true", "battery": "90"}),
+        );
+
+        let merged = merge_detail_maps(&base, &local, &remote);
+
+        assert_eq!(merged.get("sensor_0"), Some(&sensor("sensor-1")));
+        assert_eq!(
+            merged.get("status").unwrap()["battery"],
+            Value::String("90".to_string())
+        );
+    }
+
+    #[test]
+    fn merge_default_policy_prefers_remote_on_a_field_conflict() {
+        let mut base = HashMap::new();
+        base.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "true"}),
+        );
+
+        let mut local = base.clone();
+        local.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "false"}),
+        );
+
+        let mut remote = base.clone();
+        remote.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "maybe"}),
+        );
+
+        let merged = merge_detail_maps(&base, &local, &remote);
+        assert_eq!(
+            merged["status"]["operational"],
+            Value::String("maybe".to_string())
+        );
+    }
+
+    struct LocalWins;
+    impl ConflictPolicy for LocalWins {
+        fn remote_wins(
+            &self,
+            _key: &str,
+            _field: Option<&str>,
+            _local: &Value,
+            _remote: &Value,
+        ) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn merge_honors_a_pluggable_conflict_policy() {
+        let mut base = HashMap::new();
+        base.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "true"}),
+        );
+
+        let mut local = base.clone();
+        local.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "false"}),
+        );
+
+        let mut remote = base.clone();
+        remote.insert(
+            "status".to_string(),
+            serde_json::json!({"operational": "maybe"}),
+        );
+
+        let merged = merge_detail_maps_with_policy(&base, &local, &remote, &LocalWins);
+        assert_eq!(
+            merged["status"]["operational"],
+            Value::String("false".to_string())
+        );
+    }
+
+    #[test]
+    fn base58_and_base62_codecs_round_trip_arbitrary_hash_bytes() {
+        for hash in [0u64, 1, 42, u64::MAX, 0x00ff_00ff_00ff_00ff] {
+            let hash_bytes = hash.to_be_bytes();
+            for codec in [&Base58KeyCodec as &dyn KeyCodec, &Base62KeyCodec] {
+                let key = codec.encode(&hash_bytes, 7);
+                let (decoded_bytes, decoded_index) = codec.decode(&key).unwrap();
+                assert_eq!(decoded_bytes, hash_bytes);
+                assert_eq!(decoded_index, 7);
+            }
+        }
+    }
+
+    #[test]
+    fn base64_codec_decode_rejects_a_plain_tag_shaped_like_a_stable_key() {
+        // "my_5" looks exactly like the old split('_')-plus-"is the tail a
+        // u32" heuristic's idea of a stable key, but "my" isn't valid unpadded
+        // URL-safe base64 (its last symbol leaves non-zero trailing bits), so
+        // the codec refuses it.
+        assert!(Base64KeyCodec.decode("my_5").is_none());
+    }
+
+    #[test]
+    fn convert_no_longer_misroutes_a_single_occurrence_tag_containing_an_underscore() {
+        // A real, single-occurrence element literally named "my_5" must
+        // round-trip as a direct key, not be misclassified as a stable key
+        // and dropped for lacking `_tag` metadata.
+        let mut detail_map = HashMap::new();
+        detail_map.insert("my_5".to_string(), serde_json::json!({"value": "true"}));
+
+        let xml = try_convert_stable_keys_to_xml(&detail_map).unwrap();
+        assert!(xml.contains("<my_5"));
+    }
+
+    #[test]
+    fn parse_and_convert_round_trip_with_a_non_default_key_scheme() {
+        let detail = r#"<detail>
+            <sensor type="optical" id="sensor-1"/>
+            <sensor type="thermal" id="sensor-2"/>
+        </detail>"#;
+
+        let scheme = KeyScheme::new(&Fnv1aStableKeyHasher, &Base62KeyCodec);
+        let parsed = parse_detail_section_with_stable_keys_with_scheme(detail, "test-doc", &scheme);
+
+        let stable_keys: Vec<&String> = parsed
+            .keys()
+            .filter(|key| Base62KeyCodec.decode(key).is_some())
+            .collect();
+        assert_eq!(stable_keys.len(), 2);
+
+        let xml = convert_stable_keys_to_xml_with_scheme(&parsed, &scheme);
+        assert_eq!(xml.matches("<sensor").count(), 2);
+    }
+
+    fn sensor_attrs(attrs: &[(&str, &str)]) -> Map<String, Value> {
+        attrs
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn crdt_merge_does_not_resurrect_a_concurrently_removed_element() {
+        let mut state = DetailCrdtState::new();
+        state.upsert(
+            "contact_0",
+            "contact",
+            sensor_attrs(&[("id", "contact-1")]),
+            CausalStamp::new(1, "node-a"),
+        );
+
+        let local = state.clone();
+        let mut remote = state.clone();
+
+        // Remote removes contact_0 with a later stamp than its original write.
+        remote.remove("contact_0", CausalStamp::new(2, "node-b"));
+
+        // Local never touches contact_0 again (an unsound remove+insert merge
+        // would otherwise resurrect it just because `local` still has it).
+        let merged = merge_crdt_states(&local, &remote);
+
+        assert!(!merged.elements.contains_key("contact_0"));
+        assert_eq!(
+            merged.tombstones.get("contact_0"),
+            Some(&CausalStamp::new(2, "node-b"))
+        );
+
+        // And the reverse merge direction agrees.
+        let merged_reverse = merge_crdt_states(&remote, &local);
+        assert!(!merged_reverse.elements.contains_key("contact_0"));
+    }
+
+    #[test]
+    fn crdt_merge_keeps_non_conflicting_concurrent_edits_from_both_sides() {
+        let mut base = DetailCrdtState::new();
+        base.upsert(
+            "sensor_1",
+            "sensor",
+            sensor_attrs(&[("id", "sensor-2"), ("type", "optical")]),
+            CausalStamp::new(1, "node-a"),
+        );
+
+        let mut local = base.clone();
+        local.upsert(
+            "sensor_1",
+            "sensor",
+            sensor_attrs(&[("id", "sensor-2"), ("type", "optical"), ("zoom", "20x")]),
+            CausalStamp::new(2, "node-a"),
+        );
+
+        let mut remote = base.clone();
+        remote.upsert(
+            "sensor_1",
+            "sensor",
+            sensor_attrs(&[("id", "sensor-2"), ("type", "thermal")]),
+            CausalStamp::new(2, "node-b"),
+        );
+
+        let merged = merge_crdt_states(&local, &remote);
+        let merged_sensor = merged.elements.get("sensor_1").unwrap();
+
+        // `node-b`'s stamp (2, "node-b") beats `node-a`'s (2, "node-a")
+        // lexicographically, so `type` takes remote's edit...
+        assert_eq!(merged_sensor["type"], Value::String("thermal".to_string()));
+        // ...but `zoom`, only ever touched locally, survives regardless.
+        assert_eq!(merged_sensor["zoom"], Value::String("20x".to_string()));
+    }
+
+    #[test]
+    fn crdt_merge_is_commutative() {
+        let mut a = DetailCrdtState::new();
+        a.upsert(
+            "sensor_0",
+            "sensor",
+            sensor_attrs(&[("id", "sensor-1")]),
+            CausalStamp::new(1, "node-a"),
+        );
+        let mut b = DetailCrdtState::new();
+        b.upsert(
+            "sensor_0",
+            "sensor",
+            sensor_attrs(&[("id", "sensor-1"), ("type", "radar")]),
+            CausalStamp::new(2, "node-b"),
+        );
+
+        let a_then_b = merge_crdt_states(&a, &b);
+        let b_then_a = merge_crdt_states(&b, &a);
+        assert_eq!(a_then_b, b_then_a);
+    }
+
+    #[test]
+    fn next_crdt_key_folds_in_the_replica_id_so_concurrent_adds_never_collide() {
+        let state = DetailCrdtState::new();
+        let key_a = next_crdt_key(&state, "test-doc", "track", "node-a", &Fnv1aStableKeyHasher);
+        let key_b = next_crdt_key(&state, "test-doc", "track", "node-b", &Fnv1aStableKeyHasher);
+        assert_ne!(key_a, key_b);
+    }
 }
@@ -0,0 +1,243 @@
+//! CRDT-style merge for concurrently-edited CoT `detail` maps.
+//!
+//! `detail_parser::parse_detail_section` hands back a plain
+//! `IndexMap<String, Value>`; on its own that gives no way to reconcile two
+//! copies of the same event that were edited on different peers without
+//! falling back to whole-document last-writer-wins. This module attaches a
+//! per-key causal token to each top-level detail entry and provides a
+//! [`merge`] function that keeps the most-recent entry per key (recursing
+//! into nested objects), so independently-edited sub-elements on each peer
+//! both survive instead of one whole subtree clobbering the other.
+
+use crate::model::FlatCotEvent;
+use indexmap::IndexMap;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A causal stamp attached to a detail entry: a Lamport counter plus the id
+/// of the peer that produced it. Higher `counter` wins; ties are broken by
+/// `peer_id` so the result is deterministic regardless of merge order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct CausalToken {
+    /// Monotonically increasing counter, incremented by the peer on every edit.
+    pub counter: u64,
+    /// Identifier of the peer that produced this value.
+    pub peer_id: String,
+}
+
+impl CausalToken {
+    /// Creates a new causal token.
+    pub fn new(counter: u64, peer_id: impl Into<String>) -> Self {
+        Self {
+            counter,
+            peer_id: peer_id.into(),
+        }
+    }
+}
+
+impl PartialOrd for CausalToken {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CausalToken {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.counter
+            .cmp(&other.counter)
+            .then_with(|| self.peer_id.cmp(&other.peer_id))
+    }
+}
+
+/// Per-top-level-key causal tokens for a `detail_extra` map, keyed the same
+/// way as the map it describes.
+pub type TokenMap = HashMap<String, CausalToken>;
+
+/// Stamps every top-level key of `detail_extra` with a fresh [`CausalToken`]
+/// for `peer_id` at `counter`, for use when an event is first parsed or
+/// created locally (before any merge has happened).
+pub fn tag_with_tokens(
+    detail_extra: &IndexMap<String, Value>,
+    peer_id: &str,
+    counter: u64,
+) -> TokenMap {
+    detail_extra
+        .keys()
+        .map(|k| (k.clone(), CausalToken::new(counter, peer_id)))
+        .collect()
+}
+
+/// Merges two `detail_extra` maps that were edited concurrently on different
+/// peers, using the companion [`TokenMap`]s to decide a winner per key.
+///
+/// Takes the union of keys. For a key present on both sides, the entry with
+/// the higher [`CausalToken`] wins outright *unless* both values are JSON
+/// objects, in which case the merge recurses per-field so independently
+/// edited sub-elements (e.g. `contact.callsign` vs `status.readiness`) both
+/// survive; the winning side's token is kept as the merged key's token.
+pub fn merge(
+    local: &IndexMap<String, Value>,
+    local_tokens: &TokenMap,
+    remote: &IndexMap<String, Value>,
+    remote_tokens: &TokenMap,
+) -> (IndexMap<String, Value>, TokenMap) {
+    let mut merged = IndexMap::new();
+    let mut merged_tokens = TokenMap::new();
+
+    let keys: std::collections::HashSet<&String> = local.keys().chain(remote.keys()).collect();
+
+    for key in keys {
+        match (local.get(key), remote.get(key)) {
+            (Some(l), None) => {
+                merged.insert(key.clone(), l.clone());
+                if let Some(t) = local_tokens.get(key) {
+                    merged_tokens.insert(key.clone(), t.clone());
+                }
+            }
+            (None, Some(r)) => {
+                merged.insert(key.clone(), r.clone());
+                if let Some(t) = remote_tokens.get(key) {
+                    merged_tokens.insert(key.clone(), t.clone());
+                }
+            }
+            (Some(l), Some(r)) => {
+                let l_token = local_tokens.get(key);
+                let r_token = remote_tokens.get(key);
+
+                let (value, token) = match (l.as_object(), r.as_object()) {
+                    (Some(l_obj), Some(r_obj)) => {
+                        // Both sides edited sub-fields of the same element: recurse
+                        // so independently-edited attributes both survive.
+                        let l_map: IndexMap<String, Value> =
+                            l_obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                        let r_map: IndexMap<String, Value> =
+                            r_obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                        let winner_token = pick_token(l_token, r_token);
+                        let (merged_obj, _) = merge(
+                            &l_map,
+                            &single_entry_tokens(&l_map, l_token),
+                            &r_map,
+                            &single_entry_tokens(&r_map, r_token),
+                        );
+                        (
+                            Value::Object(merged_obj.into_iter().collect()),
+                            winner_token,
+                        )
+                    }
+                    _ => {
+                        if l_token >= r_token {
+                            (l.clone(), l_token.cloned())
+                        } else {
+                            (r.clone(), r_token.cloned())
+                        }
+                    }
+                };
+
+                merged.insert(key.clone(), value);
+                if let Some(t) = token {
+                    merged_tokens.insert(key.clone(), t);
+                }
+            }
+            (None, None) => unreachable!("key drawn from the union of both maps"),
+        }
+    }
+
+    (merged, merged_tokens)
+}
+
+/// Merges two concurrently-edited copies of the same event's `detail_extra`
+/// map, returning the merged `FlatCotEvent` (identical to `local` except for
+/// `detail_extra`) alongside the merged [`TokenMap`].
+///
+/// Core scalar fields (`time`, `lat`/`lon`, ...) are taken from `local`
+/// as-is: CoT already has its own whole-event staleness/versioning policy for
+/// those, so this function's job is specifically reconciling the `detail`
+/// CRDT payload that Ditto syncs at field granularity.
+pub fn merge_events(
+    local: &FlatCotEvent,
+    local_tokens: &TokenMap,
+    remote: &FlatCotEvent,
+    remote_tokens: &TokenMap,
+) -> (FlatCotEvent, TokenMap) {
+    let (detail_extra, tokens) = merge(
+        &local.detail_extra,
+        local_tokens,
+        &remote.detail_extra,
+        remote_tokens,
+    );
+    let mut merged = local.clone();
+    merged.detail_extra = detail_extra;
+    (merged, tokens)
+}
+
+/// Builds a per-field token map where every field inherits the same
+/// element-level token, so a recursive merge of an object's fields can reuse
+/// the same winner-selection logic as the top level.
+fn single_entry_tokens(map: &IndexMap<String, Value>, token: Option<&CausalToken>) -> TokenMap {
+    match token {
+        Some(t) => map.keys().map(|k| (k.clone(), t.clone())).collect(),
+        None => TokenMap::new(),
+    }
+}
+
+fn pick_token(l: Option<&CausalToken>, r: Option<&CausalToken>) -> Option<CausalToken> {
+    match (l, r) {
+        (Some(l), Some(r)) => Some(if l >= r { l.clone() } else { r.clone() }),
+        (Some(l), None) => Some(l.clone()),
+        (None, Some(r)) => Some(r.clone()),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn higher_counter_wins_for_scalar_keys() {
+        let mut local = IndexMap::new();
+        local.insert("how".to_string(), json!("h-g-i-g-o"));
+        let local_tokens = TokenMap::from([("how".to_string(), CausalToken::new(1, "peer-a"))]);
+
+        let mut remote = IndexMap::new();
+        remote.insert("how".to_string(), json!("m-g"));
+        let remote_tokens = TokenMap::from([("how".to_string(), CausalToken::new(2, "peer-b"))]);
+
+        let (merged, tokens) = merge(&local, &local_tokens, &remote, &remote_tokens);
+        assert_eq!(merged["how"], json!("m-g"));
+        assert_eq!(tokens["how"].peer_id, "peer-b");
+    }
+
+    #[test]
+    fn independent_sub_elements_both_survive() {
+        let mut local = IndexMap::new();
+        local.insert("contact".to_string(), json!({"callsign": "ALPHA-1"}));
+        let local_tokens =
+            TokenMap::from([("contact".to_string(), CausalToken::new(1, "peer-a"))]);
+
+        let mut remote = IndexMap::new();
+        remote.insert("contact".to_string(), json!({"endpoint": "udp:1.2.3.4"}));
+        let remote_tokens =
+            TokenMap::from([("contact".to_string(), CausalToken::new(1, "peer-b"))]);
+
+        let (merged, _) = merge(&local, &local_tokens, &remote, &remote_tokens);
+        assert_eq!(merged["contact"]["callsign"], json!("ALPHA-1"));
+        assert_eq!(merged["contact"]["endpoint"], json!("udp:1.2.3.4"));
+    }
+
+    #[test]
+    fn union_of_keys_present_on_only_one_side() {
+        let mut local = IndexMap::new();
+        local.insert("takv".to_string(), json!({"platform": "ATAK"}));
+        let local_tokens = TokenMap::from([("takv".to_string(), CausalToken::new(1, "peer-a"))]);
+
+        let remote = IndexMap::new();
+        let remote_tokens = TokenMap::new();
+
+        let (merged, _) = merge(&local, &local_tokens, &remote, &remote_tokens);
+        assert_eq!(merged.len(), 1);
+        assert!(merged.contains_key("takv"));
+    }
+}
@@ -0,0 +1,323 @@
+//! Typed view over a `<detail>` section's common sub-elements, as an
+//! alternative to [`CotEvent::detail`]'s raw XML string.
+//!
+//! `detail` has always been an opaque string that the template constructors
+//! (`new_chat_message`, `new_emergency`, `new_location_update`) hand-format
+//! with `format!`, and [`CotEvent::from_xml`] never parses back into
+//! structure — callers wanting `contact`/`__group`/`status` fields have had
+//! to go through
+//! [`detail_parser::parse_detail_section`](crate::detail_parser::parse_detail_section)'s
+//! flattened map themselves. [`Detail`] instead holds the commonly-used
+//! sub-elements as typed fields (`contact`, `__group`, `status`, `takv`,
+//! `track`, `remarks`, `link`), with a catch-all `other` so anything this
+//! type doesn't model yet still round-trips losslessly.
+//!
+//! [`Detail::parse`]/[`Detail::to_xml`] are built directly on
+//! [`detail_tree::{parse_detail_tree, write_detail_tree}`](crate::detail_tree)
+//! rather than a second hand-rolled XML walk, so attribute/child order is
+//! preserved the same way the rest of this crate already guarantees it.
+
+use crate::cot_events::CotEvent;
+use crate::detail_tree::{parse_detail_tree, write_detail_tree, DetailNode};
+
+/// `<contact>`: callsign and, for a non-human endpoint, its network address.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Contact {
+    /// The `callsign` attribute.
+    pub callsign: Option<String>,
+    /// The `endpoint` attribute (e.g. `"*:-1:stcp"` for a TAK server).
+    pub endpoint: Option<String>,
+}
+
+/// `<__group>`: team affiliation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Group {
+    /// The `name` attribute (e.g. `"Cyan"`).
+    pub name: Option<String>,
+    /// The `role` attribute (e.g. `"Team Member"`).
+    pub role: Option<String>,
+}
+
+/// `<status>`: device battery level.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Status {
+    /// The `battery` attribute, as TAK puts it on the wire (a string, not a
+    /// parsed number — the schema never promises it's numeric).
+    pub battery: Option<String>,
+}
+
+/// `<takv>`: the originating TAK client's identity.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Takv {
+    /// The `device` attribute.
+    pub device: Option<String>,
+    /// The `platform` attribute (e.g. `"ATAK-CIV"`).
+    pub platform: Option<String>,
+    /// The `os` attribute.
+    pub os: Option<String>,
+    /// The `version` attribute.
+    pub version: Option<String>,
+}
+
+/// `<track>`: heading and speed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Track {
+    /// The `speed` attribute, in meters/second.
+    pub speed: Option<String>,
+    /// The `course` attribute, in degrees true.
+    pub course: Option<String>,
+}
+
+/// `<remarks>`: free-text remarks, with its optional `source`/`time` attrs
+/// and text content.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Remarks {
+    /// The element's text content.
+    pub text: Option<String>,
+    /// The `source` attribute (often the author's callsign).
+    pub source: Option<String>,
+    /// The `time` attribute.
+    pub time: Option<String>,
+}
+
+/// One `<link>` relation to another entity. A `<detail>` section can carry
+/// several, so [`Detail`] keeps them in a `Vec`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Link {
+    /// The `relation` attribute (e.g. `"p-p"` for a parent-point link).
+    pub relation: Option<String>,
+    /// The `uid` attribute of the linked entity.
+    pub uid: Option<String>,
+    /// The `type` attribute (the linked entity's CoT event type).
+    pub link_type: Option<String>,
+    /// The `point` attribute, as a `"lat,lon,hae"` string.
+    pub point: Option<String>,
+}
+
+/// Typed view over a `<detail>` section's common sub-elements, with every
+/// other child element preserved losslessly in [`Self::other`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Detail {
+    /// The `<contact>` child, if present.
+    pub contact: Option<Contact>,
+    /// The `<__group>` child, if present.
+    pub group: Option<Group>,
+    /// The `<status>` child, if present.
+    pub status: Option<Status>,
+    /// The `<takv>` child, if present.
+    pub takv: Option<Takv>,
+    /// The `<track>` child, if present.
+    pub track: Option<Track>,
+    /// The `<remarks>` child, if present.
+    pub remarks: Option<Remarks>,
+    /// Every `<link>` child, in document order.
+    pub links: Vec<Link>,
+    /// Every child element not recognized above, in document order.
+    pub other: Vec<DetailNode>,
+}
+
+impl Detail {
+    /// Parses a `<detail>...</detail>` XML string into its typed view.
+    /// Unrecognized children are kept in [`Self::other`] rather than
+    /// dropped.
+    pub fn parse(xml: &str) -> Self {
+        let mut detail = Detail::default();
+        for node in parse_detail_tree(xml) {
+            match node.name.as_str() {
+                "contact" => {
+                    detail.contact = Some(Contact {
+                        callsign: node.attr("callsign").map(str::to_string),
+                        endpoint: node.attr("endpoint").map(str::to_string),
+                    });
+                }
+                "__group" => {
+                    detail.group = Some(Group {
+                        name: node.attr("name").map(str::to_string),
+                        role: node.attr("role").map(str::to_string),
+                    });
+                }
+                "status" => {
+                    detail.status = Some(Status {
+                        battery: node.attr("battery").map(str::to_string),
+                    });
+                }
+                "takv" => {
+                    detail.takv = Some(Takv {
+                        device: node.attr("device").map(str::to_string),
+                        platform: node.attr("platform").map(str::to_string),
+                        os: node.attr("os").map(str::to_string),
+                        version: node.attr("version").map(str::to_string),
+                    });
+                }
+                "track" => {
+                    detail.track = Some(Track {
+                        speed: node.attr("speed").map(str::to_string),
+                        course: node.attr("course").map(str::to_string),
+                    });
+                }
+                "remarks" => {
+                    detail.remarks = Some(Remarks {
+                        text: node.text.clone(),
+                        source: node.attr("source").map(str::to_string),
+                        time: node.attr("time").map(str::to_string),
+                    });
+                }
+                "link" => {
+                    detail.links.push(Link {
+                        relation: node.attr("relation").map(str::to_string),
+                        uid: node.attr("uid").map(str::to_string),
+                        link_type: node.attr("type").map(str::to_string),
+                        point: node.attr("point").map(str::to_string),
+                    });
+                }
+                _ => detail.other.push(node),
+            }
+        }
+        detail
+    }
+
+    /// Serializes back to a full `<detail>...</detail>` XML string: `contact`,
+    /// `__group`, `status`, `takv`, `track`, `remarks`, then `links`, each
+    /// only if present, followed by [`Self::other`] in its preserved order.
+    pub fn to_xml(&self) -> String {
+        let mut nodes = Vec::new();
+
+        if let Some(contact) = &self.contact {
+            let mut node = DetailNode::new("contact");
+            push_attr(&mut node, "callsign", &contact.callsign);
+            push_attr(&mut node, "endpoint", &contact.endpoint);
+            nodes.push(node);
+        }
+        if let Some(group) = &self.group {
+            let mut node = DetailNode::new("__group");
+            push_attr(&mut node, "name", &group.name);
+            push_attr(&mut node, "role", &group.role);
+            nodes.push(node);
+        }
+        if let Some(status) = &self.status {
+            let mut node = DetailNode::new("status");
+            push_attr(&mut node, "battery", &status.battery);
+            nodes.push(node);
+        }
+        if let Some(takv) = &self.takv {
+            let mut node = DetailNode::new("takv");
+            push_attr(&mut node, "device", &takv.device);
+            push_attr(&mut node, "platform", &takv.platform);
+            push_attr(&mut node, "os", &takv.os);
+            push_attr(&mut node, "version", &takv.version);
+            nodes.push(node);
+        }
+        if let Some(track) = &self.track {
+            let mut node = DetailNode::new("track");
+            push_attr(&mut node, "speed", &track.speed);
+            push_attr(&mut node, "course", &track.course);
+            nodes.push(node);
+        }
+        if let Some(remarks) = &self.remarks {
+            let mut node = DetailNode::new("remarks");
+            push_attr(&mut node, "source", &remarks.source);
+            push_attr(&mut node, "time", &remarks.time);
+            node.text = remarks.text.clone();
+            nodes.push(node);
+        }
+        for link in &self.links {
+            let mut node = DetailNode::new("link");
+            push_attr(&mut node, "relation", &link.relation);
+            push_attr(&mut node, "uid", &link.uid);
+            push_attr(&mut node, "type", &link.link_type);
+            push_attr(&mut node, "point", &link.point);
+            nodes.push(node);
+        }
+        nodes.extend(self.other.iter().cloned());
+
+        format!("<detail>{}</detail>", write_detail_tree(&nodes))
+    }
+}
+
+fn push_attr(node: &mut DetailNode, key: &str, value: &Option<String>) {
+    if let Some(value) = value {
+        node.attrs.push((key.to_string(), value.clone()));
+    }
+}
+
+impl CotEvent {
+    /// Parses [`Self::detail`] into its typed [`Detail`] view. Unrecognized
+    /// children come back in [`Detail::other`] rather than being dropped.
+    pub fn detail_struct(&self) -> Detail {
+        Detail::parse(&self.detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_contact_and_group() {
+        let xml = concat!(
+            r#"<detail><contact callsign="ALPHA-1" endpoint="*:-1:stcp"/>"#,
+            r#"<__group name="Cyan" role="Team Member"/></detail>"#,
+        );
+        let detail = Detail::parse(xml);
+        assert_eq!(
+            detail.contact,
+            Some(Contact {
+                callsign: Some("ALPHA-1".to_string()),
+                endpoint: Some("*:-1:stcp".to_string()),
+            })
+        );
+        assert_eq!(
+            detail.group,
+            Some(Group {
+                name: Some("Cyan".to_string()),
+                role: Some("Team Member".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_collects_repeated_link_children() {
+        let detail = Detail::parse(
+            r#"<detail><link relation="p-p" uid="A"/><link relation="p-p" uid="B"/></detail>"#,
+        );
+        assert_eq!(detail.links.len(), 2);
+        assert_eq!(detail.links[0].uid, Some("A".to_string()));
+        assert_eq!(detail.links[1].uid, Some("B".to_string()));
+    }
+
+    #[test]
+    fn parse_keeps_remarks_text() {
+        let xml = r#"<detail><remarks source="ALPHA-1">all clear</remarks></detail>"#;
+        let detail = Detail::parse(xml);
+        let remarks = detail.remarks.unwrap();
+        assert_eq!(remarks.text, Some("all clear".to_string()));
+        assert_eq!(remarks.source, Some("ALPHA-1".to_string()));
+    }
+
+    #[test]
+    fn parse_preserves_unrecognized_children_in_other() {
+        let detail = Detail::parse(r#"<detail><fileshare name="photo.jpg"/></detail>"#);
+        assert!(detail.contact.is_none());
+        assert_eq!(detail.other.len(), 1);
+        assert_eq!(detail.other[0].name, "fileshare");
+    }
+
+    #[test]
+    fn to_xml_round_trips_through_parse() {
+        let xml = concat!(
+            r#"<detail><contact callsign="ALPHA-1"/><status battery="80"/>"#,
+            r#"<remarks>all clear</remarks></detail>"#,
+        );
+        let detail = Detail::parse(xml);
+        let reparsed = Detail::parse(&detail.to_xml());
+        assert_eq!(detail, reparsed);
+    }
+
+    #[test]
+    fn cot_event_detail_struct_reads_back_the_event_detail_field() {
+        let mut event = CotEvent::default();
+        event.detail = r#"<detail><contact callsign="BRAVO-2"/></detail>"#.to_string();
+        let detail = event.detail_struct();
+        assert_eq!(detail.contact.unwrap().callsign, Some("BRAVO-2".to_string()));
+    }
+}
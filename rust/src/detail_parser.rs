@@ -4,26 +4,132 @@
 //! extracting structured information like callsign, group name, and additional
 //! key-value pairs.
 
+use crate::error::CotError;
+use indexmap::IndexMap;
 use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// Maximum nesting depth `parse_element` will recurse into before treating an
+/// element as a leaf. Bounds stack growth against hostile/degenerate `<detail>`
+/// trees (e.g. thousands of nested elements) instead of risking a stack overflow.
+const MAX_DETAIL_DEPTH: usize = 64;
+
+/// Options controlling [`parse_detail_section_typed`]'s scalar type coercion.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Attribute/element names that must always stay `Value::String`, even
+    /// when they look numeric or boolean (e.g. `uid`, `code`).
+    pub coercion_opt_out: HashSet<String>,
+}
+
+/// Attempts to coerce a raw XML scalar string into a typed `serde_json::Value`.
+///
+/// Coercion is tried in a fixed order: a JSON number (parsed directly as a
+/// `serde_json::Number` rather than going through `i64`/`f64` separately, so
+/// a high-precision coordinate like `"150.1319535"` or an epoch-micros
+/// timestamp keeps its exact textual digits instead of an `f64` round trip
+/// distorting them — byte-for-byte exact once this crate's `serde_json`
+/// dependency has the `arbitrary_precision` feature enabled, and otherwise no
+/// worse than the old `i64`-then-`f64` fallback), then boolean (`true`/`false`
+/// only), then an RFC 3339 datetime normalized back to a `Value::String`;
+/// anything else (or any key in `opt_out`) is left as a plain string.
+///
+/// Leading-zero strings (`"007"`) and values with an explicit leading `+`
+/// (`"+1"`) are deliberately never coerced to numbers: CoT UIDs and codes
+/// frequently look numeric but must round-trip as strings.
+pub(crate) fn coerce_scalar(key: &str, raw: &str, opts: &ParseOptions) -> Value {
+    if opts.coercion_opt_out.contains(key) {
+        return Value::String(raw.to_string());
+    }
+
+    let looks_like_guarded_numeral = raw.len() > 1
+        && (raw.starts_with('+')
+            || (raw.starts_with('0') && raw.as_bytes()[1].is_ascii_digit()));
+
+    if !looks_like_guarded_numeral {
+        // `serde_json::Number`'s own JSON-number grammar already rejects the
+        // `"inf"`/`"infinity"`/`"nan"` spellings Rust's `f64::from_str`
+        // otherwise accepts, so there's no separate `is_finite()` guard needed.
+        if let Ok(n) = raw.parse::<serde_json::Number>() {
+            return Value::Number(n);
+        }
+        match raw {
+            "true" => return Value::Bool(true),
+            "false" => return Value::Bool(false),
+            _ => {}
+        }
+        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(raw) {
+            return Value::String(dt.with_timezone(&chrono::Utc).to_rfc3339());
+        }
+    }
+
+    Value::String(raw.to_string())
+}
+
+/// Inserts `value` under `key`, but if `key` is already present (a repeated
+/// sibling element, e.g. a second `<link>`), turns the entry into a
+/// `Value::Array` instead of overwriting it, appending to the array if a
+/// third, fourth, ... sibling follows. Preserves document order: an
+/// [`IndexMap`] entry keeps its original position on update, so a repeated
+/// tag doesn't jump to the end of the map the way a remove-then-reinsert
+/// would.
+pub(crate) fn insert_or_append(map: &mut IndexMap<String, Value>, key: String, value: Value) {
+    match map.entry(key) {
+        indexmap::map::Entry::Vacant(entry) => {
+            entry.insert(value);
+        }
+        indexmap::map::Entry::Occupied(mut entry) => match entry.get_mut() {
+            Value::Array(items) => items.push(value),
+            existing => {
+                let previous = std::mem::replace(existing, Value::Null);
+                *existing = Value::Array(vec![previous, value]);
+            }
+        },
+    }
+}
+
+/// Same as [`insert_or_append`], but for the `serde_json::Map` used while
+/// building a nested element's children.
+pub(crate) fn insert_or_append_in_map(
+    map: &mut serde_json::Map<String, Value>,
+    key: String,
+    value: Value,
+) {
+    match map.remove(&key) {
+        None => {
+            map.insert(key, value);
+        }
+        Some(Value::Array(mut items)) => {
+            items.push(value);
+            map.insert(key, Value::Array(items));
+        }
+        Some(existing) => {
+            map.insert(key, Value::Array(vec![existing, value]));
+        }
+    }
+}
 
 /// Parses the <detail> section of a CoT message as a generic XML-to-map transformation.
 ///
-/// This function converts all attributes and text content into a HashMap<String, Value>,
-/// preserving the structure and content of <detail> without any special-case logic.
+/// This function converts all attributes and text content into an
+/// `IndexMap<String, Value>`, preserving the structure and content of
+/// <detail> without any special-case logic.
 ///
 /// # Arguments
 /// * `detail_xml` - A string slice containing the XML content of the detail section
 ///
 /// # Returns
-/// A HashMap<String, Value> representing all attributes and text content in <detail>.
+/// An `IndexMap<String, Value>` representing all attributes and text content
+/// in <detail>, in the order each top-level tag first appeared. Repeated
+/// sibling elements (e.g. several `<link>` children) are preserved in
+/// document order as a `Value::Array` under their shared tag, rather than the
+/// last one silently overwriting the rest.
 ///
 /// # Example
 /// ```
 /// use ditto_cot::detail_parser::parse_detail_section;
-/// use std::collections::HashMap;
 /// use serde_json::Value;
 ///
 /// let detail = r#"<contact callsign="TEST-123"/><__group name="Blue"/><status readiness="true"/>"#;
@@ -32,81 +138,170 @@ use std::collections::HashMap;
 /// assert_eq!(extras.get("__group").unwrap()["name"], Value::String("Blue".to_string()));
 /// assert_eq!(extras.get("status").unwrap()["readiness"], Value::String("true".to_string()));
 /// ```
-pub fn parse_detail_section(detail_xml: &str) -> HashMap<String, Value> {
-    use serde_json::{Map, Value};
+pub fn parse_detail_section(detail_xml: &str) -> IndexMap<String, Value> {
+    parse_detail_section_with(detail_xml, None)
+}
 
-    let mut reader = Reader::from_str(detail_xml);
-    reader.trim_text(true);
-    let mut buf = Vec::new();
-    let mut extras = HashMap::new();
-
-    fn parse_element<R: std::io::BufRead>(
-        reader: &mut Reader<R>,
-        start: &BytesStart,
-        buf: &mut Vec<u8>,
-    ) -> Value {
-        let _tag = String::from_utf8_lossy(start.name().as_ref()).to_string();
-        let mut map = Map::new();
-        // Parse attributes
-        for attr_result in start.attributes() {
-            if let Ok(attr) = attr_result {
-                let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                let val = String::from_utf8_lossy(&attr.value).to_string();
-                map.insert(key, Value::String(val));
-            }
-        }
-        // Parse children
-        let mut text_content = None;
+/// Like [`parse_detail_section`], but opportunistically coerces scalar
+/// attribute and text values into `i64`/`f64`/`bool`/normalized-datetime
+/// `Value`s instead of leaving everything as `Value::String`.
+///
+/// See [`coerce_scalar`] for the coercion order and edge cases, and
+/// [`ParseOptions::coercion_opt_out`] to exempt specific keys (e.g. `uid`).
+///
+/// # Example
+/// ```
+/// use ditto_cot::detail_parser::{parse_detail_section_typed, ParseOptions};
+/// use serde_json::Value;
+///
+/// let detail = r#"<status readiness="true" battery="87.5"/>"#;
+/// let extras = parse_detail_section_typed(detail, &ParseOptions::default());
+/// assert_eq!(extras.get("status").unwrap()["readiness"], Value::Bool(true));
+/// assert_eq!(extras.get("status").unwrap()["battery"], Value::from(87.5));
+/// ```
+pub fn parse_detail_section_typed(
+    detail_xml: &str,
+    opts: &ParseOptions,
+) -> IndexMap<String, Value> {
+    parse_detail_section_with(detail_xml, Some(opts))
+}
+
+fn scalar(key: &str, raw: &str, opts: Option<&ParseOptions>) -> Value {
+    match opts {
+        Some(opts) => coerce_scalar(key, raw, opts),
+        None => Value::String(raw.to_string()),
+    }
+}
+
+fn parse_attrs(
+    start: &BytesStart,
+    opts: Option<&ParseOptions>,
+) -> Result<serde_json::Map<String, Value>, CotError> {
+    let mut map = serde_json::Map::new();
+    for attr_result in start.attributes() {
+        let attr = attr_result?;
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let val = attr.unescape_value()?.to_string();
+        let coerced = scalar(&key, &val, opts);
+        map.insert(key, coerced);
+    }
+    Ok(map)
+}
+
+fn parse_element<R: std::io::BufRead>(
+    reader: &mut Reader<R>,
+    start: &BytesStart,
+    buf: &mut Vec<u8>,
+    depth: usize,
+    opts: Option<&ParseOptions>,
+    source: &str,
+) -> Result<Value, CotError> {
+    let mut map = parse_attrs(start, opts)?;
+
+    // Beyond the depth cap, skip to this element's matching end tag without
+    // recursing further so arbitrarily deep nesting can't blow the stack.
+    if depth >= MAX_DETAIL_DEPTH {
+        let mut skip_depth = 1u32;
         loop {
             buf.clear();
             match reader.read_event_into(buf) {
-                Ok(Event::Start(e)) => {
-                    let child_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                    let mut child_buf = Vec::new();
-                    let child_val = parse_element(reader, &e, &mut child_buf);
-                    map.insert(child_tag, child_val);
-                }
-                Ok(Event::Empty(e)) => {
-                    let child_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                    let mut child_map = Map::new();
-                    for attr_result in e.attributes() {
-                        if let Ok(attr) = attr_result {
-                            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                            let val = String::from_utf8_lossy(&attr.value).to_string();
-                            child_map.insert(key, Value::String(val));
-                        }
-                    }
-                    map.insert(child_tag, Value::Object(child_map));
-                }
-                Ok(Event::Text(t)) => {
-                    let text = t.unescape().unwrap_or_default().to_string();
-                    if !text.is_empty() {
-                        text_content = Some(text);
-                    }
-                }
+                Ok(Event::Start(e)) if e.name() == start.name() => skip_depth += 1,
                 Ok(Event::End(e)) if e.name() == start.name() => {
-                    break;
+                    skip_depth -= 1;
+                    if skip_depth == 0 {
+                        break;
+                    }
                 }
                 Ok(Event::Eof) => break,
+                Err(e) => {
+                    return Err(CotError::xml_parse_at(
+                        source,
+                        reader.buffer_position(),
+                        e.to_string(),
+                    ))
+                }
                 _ => {}
             }
         }
-        // If there was only text content and no attributes/children, return as string
-        if map.is_empty() {
-            if let Some(text) = text_content {
-                Value::String(text)
-            } else {
-                Value::Object(map)
+        return Ok(Value::Object(map));
+    }
+
+    // Parse children
+    let mut text_content = None;
+    loop {
+        buf.clear();
+        match reader.read_event_into(buf) {
+            Ok(Event::Start(e)) => {
+                let child_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let mut child_buf = Vec::new();
+                let child_val =
+                    parse_element(reader, &e, &mut child_buf, depth + 1, opts, source)?;
+                insert_or_append_in_map(&mut map, child_tag, child_val);
             }
-        } else {
-            if let Some(text) = text_content {
-                map.insert("_text".to_string(), Value::String(text));
+            Ok(Event::Empty(e)) => {
+                let child_tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let attrs = Value::Object(parse_attrs(&e, opts)?);
+                insert_or_append_in_map(&mut map, child_tag, attrs);
+            }
+            Ok(Event::Text(t)) => {
+                let text = t.unescape()?.to_string();
+                if !text.is_empty() {
+                    text_content = Some(text);
+                }
+            }
+            Ok(Event::End(e)) if e.name() == start.name() => {
+                break;
             }
-            Value::Object(map)
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(CotError::xml_parse_at(source, reader.buffer_position(), e.to_string()))
+            }
+            _ => {}
+        }
+    }
+    // If there was only text content and no attributes/children, return as string
+    if map.is_empty() {
+        if let Some(text) = text_content {
+            Ok(scalar("_text", &text, opts))
+        } else {
+            Ok(Value::Object(map))
+        }
+    } else {
+        if let Some(text) = text_content {
+            map.insert("_text".to_string(), scalar("_text", &text, opts));
         }
+        Ok(Value::Object(map))
     }
+}
+
+/// Like [`parse_detail_section`]/[`parse_detail_section_typed`], but surfaces
+/// the first parse failure (malformed markup, an unterminated tag, an invalid
+/// attribute) as a position-aware [`CotError::XmlParse`] instead of silently
+/// dropping it. Use this over the infallible functions when ingesting CoT
+/// from an untrusted or unreliable source that should be rejected or
+/// quarantined rather than accepted as "just an empty detail section".
+pub fn try_parse_detail_section(detail_xml: &str) -> Result<IndexMap<String, Value>, CotError> {
+    try_parse_detail_section_with(detail_xml, None)
+}
+
+/// [`try_parse_detail_section`] with [`coerce_scalar`] typing, mirroring
+/// [`parse_detail_section_typed`]'s relationship to [`parse_detail_section`].
+pub fn try_parse_detail_section_typed(
+    detail_xml: &str,
+    opts: &ParseOptions,
+) -> Result<IndexMap<String, Value>, CotError> {
+    try_parse_detail_section_with(detail_xml, Some(opts))
+}
+
+fn try_parse_detail_section_with(
+    detail_xml: &str,
+    opts: Option<&ParseOptions>,
+) -> Result<IndexMap<String, Value>, CotError> {
+    let mut reader = Reader::from_str(detail_xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut extras = IndexMap::new();
 
-    // Main event loop
     let mut in_root = false;
     loop {
         buf.clear();
@@ -117,22 +312,72 @@ pub fn parse_detail_section(detail_xml: &str) -> HashMap<String, Value> {
                     in_root = true;
                 } else if in_root {
                     let mut child_buf = Vec::new();
-                    let val = parse_element(&mut reader, e, &mut child_buf);
-                    extras.insert(tag, val);
+                    let val = parse_element(&mut reader, e, &mut child_buf, 0, opts, detail_xml)?;
+                    insert_or_append(&mut extras, tag, val);
                 }
             }
             Ok(Event::Empty(ref e)) => {
                 let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
-                let mut map = Map::new();
-                for attr_result in e.attributes() {
-                    if let Ok(attr) = attr_result {
-                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                        let val = String::from_utf8_lossy(&attr.value).to_string();
-                        map.insert(key, Value::String(val));
+                insert_or_append(&mut extras, tag, Value::Object(parse_attrs(e, opts)?));
+            }
+            Ok(Event::End(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if in_root && tag == "detail" {
+                    break;
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(CotError::xml_parse_at(
+                    detail_xml,
+                    reader.buffer_position(),
+                    e.to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(extras)
+}
+
+/// Infallible wrapper over [`try_parse_detail_section_with`]'s per-element
+/// parsing: a malformed element is logged and skipped rather than aborting
+/// the whole detail section, so callers that can't handle a `Result` still
+/// get every well-formed sibling rather than nothing at all.
+fn parse_detail_section_with(
+    detail_xml: &str,
+    opts: Option<&ParseOptions>,
+) -> IndexMap<String, Value> {
+    let mut reader = Reader::from_str(detail_xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut extras = IndexMap::new();
+
+    let mut in_root = false;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if !in_root && tag == "detail" {
+                    in_root = true;
+                } else if in_root {
+                    let mut child_buf = Vec::new();
+                    match parse_element(&mut reader, e, &mut child_buf, 0, opts, detail_xml) {
+                        Ok(val) => insert_or_append(&mut extras, tag, val),
+                        Err(err) => {
+                            log::warn!("parse_detail_section: skipping malformed <{tag}>: {err}")
+                        }
                     }
                 }
-                if in_root {
-                    extras.insert(tag, Value::Object(map));
+            }
+            Ok(Event::Empty(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                match parse_attrs(e, opts) {
+                    Ok(attrs) => insert_or_append(&mut extras, tag, Value::Object(attrs)),
+                    Err(err) => {
+                        log::warn!("parse_detail_section: skipping malformed <{tag}>: {err}")
+                    }
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -142,6 +387,12 @@ pub fn parse_detail_section(detail_xml: &str) -> HashMap<String, Value> {
                 }
             }
             Ok(Event::Eof) => break,
+            Err(e) => {
+                let err =
+                    CotError::xml_parse_at(detail_xml, reader.buffer_position(), e.to_string());
+                log::warn!("parse_detail_section: {err}; returning partial result");
+                break;
+            }
             _ => {}
         }
     }
@@ -0,0 +1,236 @@
+//! A small selector/query layer over the `HashMap<String, Value>` trees
+//! produced by [`crate::detail_parser::parse_detail_section`].
+//!
+//! Walking nested `serde_json::Map`s by hand to ask something like "give me
+//! the callsign of every `contact` whose `status.readiness` is true" is
+//! tedious and easy to get wrong. [`DetailQuery`] lets callers express that
+//! as a tag path plus an optional predicate, and run it over either a single
+//! parsed detail map or a batch of them.
+
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A predicate evaluated against the `Value` a [`DetailQuery`] path resolves to.
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    /// The path resolves to some value at all.
+    Exists,
+    /// The resolved value equals the given one exactly.
+    Equals(Value),
+    /// The resolved value does not equal the given one.
+    NotEquals(Value),
+    /// The resolved value is numeric and greater than the given threshold.
+    GreaterThan(f64),
+    /// The resolved value is numeric and less than the given threshold.
+    LessThan(f64),
+    /// The resolved value is a string starting with the given prefix.
+    StartsWith(String),
+    /// The resolved value is a string containing the given substring.
+    Contains(String),
+    /// The resolved value is numeric and falls within `[min, max]`
+    /// inclusive; either bound may be absent for an open range.
+    InRange(Option<f64>, Option<f64>),
+}
+
+impl Predicate {
+    fn eval(&self, value: Option<&Value>) -> bool {
+        match self {
+            Predicate::Exists => value.is_some(),
+            Predicate::Equals(expected) => value == Some(expected),
+            Predicate::NotEquals(expected) => value != Some(expected),
+            Predicate::GreaterThan(threshold) => {
+                value.and_then(Value::as_f64).is_some_and(|n| n > *threshold)
+            }
+            Predicate::LessThan(threshold) => {
+                value.and_then(Value::as_f64).is_some_and(|n| n < *threshold)
+            }
+            Predicate::StartsWith(prefix) => value
+                .and_then(Value::as_str)
+                .is_some_and(|s| s.starts_with(prefix.as_str())),
+            Predicate::Contains(needle) => value
+                .and_then(Value::as_str)
+                .is_some_and(|s| s.contains(needle.as_str())),
+            Predicate::InRange(min, max) => value.and_then(Value::as_f64).is_some_and(|n| {
+                min.map_or(true, |min| n >= min) && max.map_or(true, |max| n <= max)
+            }),
+        }
+    }
+}
+
+/// A dot-separated tag path into a parsed detail tree (e.g. `contact.callsign`
+/// or `status.readiness`), with an optional predicate evaluated against the
+/// value the path resolves to.
+#[derive(Debug, Clone)]
+pub struct DetailQuery {
+    path: Vec<String>,
+    predicate: Option<Predicate>,
+}
+
+impl DetailQuery {
+    /// Starts a query over the given dot-separated tag path
+    /// (e.g. `DetailQuery::path("status.readiness")`).
+    pub fn path(path: &str) -> Self {
+        Self {
+            path: path.split('.').map(str::to_string).collect(),
+            predicate: None,
+        }
+    }
+
+    /// Requires the path to resolve to some value.
+    pub fn exists(mut self) -> Self {
+        self.predicate = Some(Predicate::Exists);
+        self
+    }
+
+    /// Requires the path to resolve to exactly the given value.
+    pub fn equals(mut self, value: impl Into<Value>) -> Self {
+        self.predicate = Some(Predicate::Equals(value.into()));
+        self
+    }
+
+    /// Requires the path to resolve to anything other than the given value.
+    pub fn not_equals(mut self, value: impl Into<Value>) -> Self {
+        self.predicate = Some(Predicate::NotEquals(value.into()));
+        self
+    }
+
+    /// Requires the path to resolve to a number greater than `threshold`.
+    pub fn greater_than(mut self, threshold: f64) -> Self {
+        self.predicate = Some(Predicate::GreaterThan(threshold));
+        self
+    }
+
+    /// Requires the path to resolve to a number less than `threshold`.
+    pub fn less_than(mut self, threshold: f64) -> Self {
+        self.predicate = Some(Predicate::LessThan(threshold));
+        self
+    }
+
+    /// Requires the path to resolve to a string starting with `prefix`.
+    pub fn starts_with(mut self, prefix: impl Into<String>) -> Self {
+        self.predicate = Some(Predicate::StartsWith(prefix.into()));
+        self
+    }
+
+    /// Requires the path to resolve to a string containing `needle`.
+    pub fn contains(mut self, needle: impl Into<String>) -> Self {
+        self.predicate = Some(Predicate::Contains(needle.into()));
+        self
+    }
+
+    /// Requires the path to resolve to a number within `[min, max]`
+    /// inclusive. Either bound may be `None` for an open range.
+    pub fn in_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.predicate = Some(Predicate::InRange(min, max));
+        self
+    }
+
+    /// Resolves this query's path against a parsed detail tree, walking
+    /// nested objects one segment at a time.
+    pub fn get<'a>(&self, detail: &'a HashMap<String, Value>) -> Option<&'a Value> {
+        let (first, rest) = self.path.split_first()?;
+        let mut current = detail.get(first)?;
+        for segment in rest {
+            current = current.get(segment)?;
+        }
+        Some(current)
+    }
+
+    /// Returns whether this query's predicate (or just presence, if none was
+    /// set) holds against the given detail tree.
+    pub fn matches(&self, detail: &HashMap<String, Value>) -> bool {
+        let resolved = self.get(detail);
+        match &self.predicate {
+            Some(predicate) => predicate.eval(resolved),
+            None => resolved.is_some(),
+        }
+    }
+}
+
+/// Filters a batch of parsed detail trees down to the ones matching `query`.
+pub fn filter<'a>(
+    details: impl IntoIterator<Item = &'a HashMap<String, Value>>,
+    query: &DetailQuery,
+) -> Vec<&'a HashMap<String, Value>> {
+    details.into_iter().filter(|d| query.matches(d)).collect()
+}
+
+/// Runs `filter_query` over a batch of detail trees, then extracts
+/// `extract_query`'s value from each match, dropping trees where extraction
+/// fails. Covers the common "give me X of every element matching Y" shape.
+pub fn select<'a>(
+    details: impl IntoIterator<Item = &'a HashMap<String, Value>>,
+    filter_query: &DetailQuery,
+    extract_query: &DetailQuery,
+) -> Vec<&'a Value> {
+    details
+        .into_iter()
+        .filter(|d| filter_query.matches(d))
+        .filter_map(|d| extract_query.get(d))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detail_parser::parse_detail_section;
+
+    #[test]
+    fn exists_and_equals_on_nested_path() {
+        let detail = parse_detail_section(
+            r#"<contact callsign="ALPHA-1"/><status readiness="true"/>"#,
+        );
+        assert!(DetailQuery::path("contact.callsign").exists().matches(&detail));
+        assert!(DetailQuery::path("status.readiness")
+            .equals("true")
+            .matches(&detail));
+        assert!(!DetailQuery::path("status.readiness")
+            .equals("false")
+            .matches(&detail));
+        assert!(!DetailQuery::path("missing.thing").exists().matches(&detail));
+    }
+
+    #[test]
+    fn starts_with_and_contains_match_substrings() {
+        let detail = parse_detail_section(r#"<takv device="ATAK-CIV"/>"#);
+        assert!(DetailQuery::path("takv.device")
+            .starts_with("ATAK")
+            .matches(&detail));
+        assert!(!DetailQuery::path("takv.device")
+            .starts_with("WINTAK")
+            .matches(&detail));
+        assert!(DetailQuery::path("takv.device")
+            .contains("CIV")
+            .matches(&detail));
+    }
+
+    #[test]
+    fn in_range_bounds_a_numeric_field() {
+        let detail = parse_detail_section(r#"<status battery="15"/>"#);
+        assert!(DetailQuery::path("status.battery")
+            .in_range(None, Some(20.0))
+            .matches(&detail));
+        assert!(!DetailQuery::path("status.battery")
+            .in_range(Some(20.0), None)
+            .matches(&detail));
+    }
+
+    #[test]
+    fn select_callsign_where_readiness_true() {
+        let ready = parse_detail_section(
+            r#"<contact callsign="ALPHA-1"/><status readiness="true"/>"#,
+        );
+        let not_ready = parse_detail_section(
+            r#"<contact callsign="BRAVO-2"/><status readiness="false"/>"#,
+        );
+        let batch = vec![ready, not_ready];
+
+        let callsigns = select(
+            batch.iter(),
+            &DetailQuery::path("status.readiness").equals("true"),
+            &DetailQuery::path("contact.callsign"),
+        );
+
+        assert_eq!(callsigns, vec![&Value::String("ALPHA-1".to_string())]);
+    }
+}
@@ -0,0 +1,748 @@
+//! Lossless, order-preserving tree model for a CoT `<detail>` section.
+//!
+//! [`detail_parser::parse_detail_section`](crate::detail_parser::parse_detail_section)
+//! flattens `<detail>` into a `HashMap<String, Value>`: convenient for
+//! queries and CRDT merge, but a `HashMap` has no concept of attribute or
+//! sibling order, so round-tripping through it can reorder an element's
+//! attributes or a repeated tag's instances even though the *content* comes
+//! through intact. [`DetailNode`] instead keeps attributes as an ordered
+//! `Vec<(String, String)>` and children as an ordered `Vec<DetailNode>` — the
+//! same "iterate attributes in source order, validate/escape each one"
+//! approach `hls_m3u8` takes with its `AttributePairs` iterator and
+//! `QuotedString` type, adapted here to quick-xml's attribute/writer API
+//! instead of a bespoke parser — so [`parse_detail_tree`]/[`write_detail_tree`]
+//! reproduce a subtree (e.g. `<__group>`, a list of `<link>` siblings, or a
+//! nested `<fileshare>`) byte-for-byte up to attribute quoting.
+//!
+//! [`as_flat_map`] projects a parsed tree down into the same
+//! `HashMap<String, Value>` shape [`detail_parser::parse_detail_section`]
+//! produces, so existing consumers (the CRDT [`crdt_merge`](crate::crdt_merge)
+//! merge, [`detail_query`](crate::detail_query)'s selector API) keep working
+//! unchanged off of that flattened convenience view, with [`DetailNode`]
+//! available alongside it wherever exact order matters.
+//!
+//! [`flatten_detail_tree`]/[`unflatten_detail_tree`] go one step further than
+//! [`as_flat_map`]: rather than folding same-named siblings into a
+//! `Value::Array` (ambiguous once nesting is involved — a `<link>` that
+//! itself contains nested same-named children can't always be told apart
+//! from a sibling `<link>`), every element's position in its parent's
+//! `children` list becomes an explicit ordinal segment in its flattened key
+//! (`r_0_link_uid`, `r_1_link_uid` for two `<link>` siblings), so two
+//! elements can never collide onto the same flattened key regardless of
+//! depth or repetition. [`crate::ditto::r_field_flattening`] remains the
+//! wire format Ditto documents actually store and CRDT-merge fields
+//! against; this ordinal scheme is for callers — like a future fuzz target
+//! proving the XML round trip never drops an element — that need a
+//! guaranteed-lossless flat representation of a whole detail tree rather
+//! than the tag-keyed convenience view.
+
+use crate::detail_parser::{insert_or_append, insert_or_append_in_map};
+use crate::ditto::r_field_flattening::{escape_segment, unescape_segment};
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// Bounds recursion depth the same way
+/// [`detail_parser`](crate::detail_parser)'s `MAX_DETAIL_DEPTH` does, for the
+/// same reason: an adversarial or malformed `<detail>` section shouldn't be
+/// able to blow the stack.
+const MAX_DETAIL_DEPTH: usize = 64;
+
+/// One piece of an element's direct content, in the order it appeared:
+/// either a child element or a run of text.
+///
+/// [`DetailNode::text`]/[`DetailNode::children`] collapse an element down to
+/// "its one text run" plus "its children", which is all real CoT `<detail>`
+/// content needs — but genuinely interleaved mixed content
+/// (`<a>before<b/>after</a>`, two separate text runs around a child) would
+/// silently lose the second run under that model. [`DetailNode::content`]
+/// keeps every run in order instead, so [`parse_detail_tree`]/
+/// [`write_detail_tree`] stay idempotent even for that case.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    /// A child element.
+    Element(DetailNode),
+    /// A run of text between, before, or after child elements.
+    Text(String),
+}
+
+/// One element of a parsed `<detail>` subtree: its tag name, its attributes
+/// in document order, its direct text content (if any), and its child
+/// elements in document order.
+///
+/// A repeated sibling tag (e.g. several `<link>` children) is simply several
+/// entries in the parent's `children` list — unlike the flattened
+/// `HashMap<String, Value>` view, no `Value::Array` promotion is needed.
+#[derive(Debug, Clone, Default)]
+pub struct DetailNode {
+    /// This element's tag name, e.g. `"contact"` or `"__group"`.
+    pub name: String,
+    /// This element's attributes, in the order they appeared in the source.
+    pub attrs: Vec<(String, String)>,
+    /// This element's direct text content, if it had any non-empty text. For
+    /// an element with more than one separate text run (see [`Content`]),
+    /// this is only the last one — read [`Self::content`] instead when exact
+    /// interleaving matters.
+    pub text: Option<String>,
+    /// This element's child elements, in document order.
+    pub children: Vec<DetailNode>,
+    /// This element's full direct content (text runs and child elements)
+    /// in document order, exactly as parsed. Empty for a node built directly
+    /// via [`DetailNode::new`] rather than [`parse_detail_tree`] — in that
+    /// case [`write_detail_tree`] falls back to [`Self::text`] followed by
+    /// [`Self::children`].
+    pub content: Vec<Content>,
+}
+
+impl PartialEq for DetailNode {
+    /// Compares by `name`/`attrs`/`text`/`children` only, ignoring
+    /// `content`: the two stay in sync for anything [`parse_detail_tree`]
+    /// produces, but a caller reconstructing a node field-by-field (e.g.
+    /// [`unflatten_detail_tree`]) never populates `content`, and that
+    /// shouldn't make an otherwise-identical node compare unequal.
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.attrs == other.attrs
+            && self.text == other.text
+            && self.children == other.children
+    }
+}
+
+impl DetailNode {
+    /// Creates an empty node with the given tag name and no attributes, text,
+    /// or children.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            attrs: Vec::new(),
+            text: None,
+            children: Vec::new(),
+            content: Vec::new(),
+        }
+    }
+
+    /// Returns the value of this element's first attribute named `key`, if any.
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Iterates over this element's direct children named `name`, in document order.
+    pub fn children_named<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a DetailNode> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+/// Parses a `<detail>...</detail>` XML string into its direct child elements,
+/// preserving attribute and sibling order exactly.
+///
+/// Unlike [`parse_detail_section`](crate::detail_parser::parse_detail_section),
+/// which folds same-named siblings into a `Value::Array`, this returns every
+/// top-level child as its own [`DetailNode`] in document order.
+pub fn parse_detail_tree(detail_xml: &str) -> Vec<DetailNode> {
+    let mut reader = Reader::from_str(detail_xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut nodes = Vec::new();
+    let mut in_root = false;
+
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if !in_root && tag == "detail" {
+                    in_root = true;
+                } else if in_root {
+                    nodes.push(parse_node(&mut reader, e, 0));
+                }
+            }
+            Ok(Event::Empty(ref e)) => {
+                if in_root {
+                    nodes.push(empty_node(e));
+                }
+            }
+            Ok(Event::End(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if in_root && tag == "detail" {
+                    break;
+                }
+            }
+            Ok(Event::Eof) => break,
+            _ => {}
+        }
+    }
+
+    nodes
+}
+
+fn parse_attrs(start: &BytesStart) -> Vec<(String, String)> {
+    start
+        .attributes()
+        .filter_map(Result::ok)
+        .map(|attr| {
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+            let value = attr.unescape_value().unwrap_or_default().to_string();
+            (key, value)
+        })
+        .collect()
+}
+
+fn empty_node(start: &BytesStart) -> DetailNode {
+    DetailNode {
+        name: String::from_utf8_lossy(start.name().as_ref()).to_string(),
+        attrs: parse_attrs(start),
+        text: None,
+        children: Vec::new(),
+        content: Vec::new(),
+    }
+}
+
+fn parse_node<R: std::io::BufRead>(
+    reader: &mut Reader<R>,
+    start: &BytesStart,
+    depth: usize,
+) -> DetailNode {
+    let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+    let attrs = parse_attrs(start);
+
+    // Beyond the depth cap, skip to this element's matching end tag without
+    // recursing further so arbitrarily deep nesting can't blow the stack.
+    if depth >= MAX_DETAIL_DEPTH {
+        let mut buf = Vec::new();
+        let mut skip_depth = 1u32;
+        loop {
+            buf.clear();
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) if e.name() == start.name() => skip_depth += 1,
+                Ok(Event::End(e)) if e.name() == start.name() => {
+                    skip_depth -= 1;
+                    if skip_depth == 0 {
+                        break;
+                    }
+                }
+                Ok(Event::Eof) => break,
+                _ => {}
+            }
+        }
+        return DetailNode {
+            name,
+            attrs,
+            text: None,
+            children: Vec::new(),
+            content: Vec::new(),
+        };
+    }
+
+    let mut text = None;
+    let mut children = Vec::new();
+    let mut content = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let child = parse_node(reader, &e, depth + 1);
+                content.push(Content::Element(child.clone()));
+                children.push(child);
+            }
+            Ok(Event::Empty(e)) => {
+                let child = empty_node(&e);
+                content.push(Content::Element(child.clone()));
+                children.push(child);
+            }
+            Ok(Event::Text(t)) => {
+                let run = t.unescape().unwrap_or_default().to_string();
+                if !run.is_empty() {
+                    text = Some(run.clone());
+                    content.push(Content::Text(run));
+                }
+            }
+            Ok(Event::End(e)) if e.name() == start.name() => break,
+            Ok(Event::Eof) => break,
+            _ => {}
+        }
+    }
+
+    DetailNode {
+        name,
+        attrs,
+        text,
+        children,
+        content,
+    }
+}
+
+/// Serializes `nodes` back to the XML that would appear directly inside
+/// `<detail>...</detail>`, with attributes and children in the order they're
+/// stored, escaped the same way [`xml_writer`](crate::xml_writer) escapes
+/// CoT XML.
+pub fn write_detail_tree(nodes: &[DetailNode]) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    for node in nodes {
+        write_node(&mut writer, node).expect("writing to an in-memory buffer never fails");
+    }
+    String::from_utf8(writer.into_inner().into_inner()).expect("quick-xml only writes valid UTF-8")
+}
+
+fn write_node<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    node: &DetailNode,
+) -> quick_xml::Result<()> {
+    let mut start = BytesStart::new(node.name.as_str());
+    for (key, value) in &node.attrs {
+        start.push_attribute((key.as_str(), value.as_str()));
+    }
+
+    if node.text.is_none() && node.children.is_empty() && node.content.is_empty() {
+        return writer.write_event(Event::Empty(start));
+    }
+
+    writer.write_event(Event::Start(start))?;
+    if node.content.is_empty() {
+        if let Some(text) = &node.text {
+            writer.write_event(Event::Text(BytesText::new(text)))?;
+        }
+        for child in &node.children {
+            write_node(writer, child)?;
+        }
+    } else {
+        for piece in &node.content {
+            match piece {
+                Content::Text(run) => writer.write_event(Event::Text(BytesText::new(run)))?,
+                Content::Element(child) => write_node(writer, child)?,
+            }
+        }
+    }
+    writer.write_event(Event::End(quick_xml::events::BytesEnd::new(
+        node.name.as_str(),
+    )))
+}
+
+/// Projects a parsed detail tree down into the same `HashMap<String, Value>`
+/// shape [`parse_detail_section`](crate::detail_parser::parse_detail_section)
+/// produces — repeated sibling tags fold into a `Value::Array`, in document
+/// order — for callers (e.g. [`crdt_merge`](crate::crdt_merge),
+/// [`detail_query`](crate::detail_query)) that only need the flattened view.
+pub fn as_flat_map(nodes: &[DetailNode]) -> HashMap<String, Value> {
+    let mut extras = HashMap::new();
+    for node in nodes {
+        insert_or_append(&mut extras, node.name.clone(), node_to_value(node));
+    }
+    extras
+}
+
+fn node_to_value(node: &DetailNode) -> Value {
+    let mut map = serde_json::Map::new();
+    for (key, value) in &node.attrs {
+        map.insert(key.clone(), Value::String(value.clone()));
+    }
+    for child in &node.children {
+        insert_or_append_in_map(&mut map, child.name.clone(), node_to_value(child));
+    }
+    if let Some(text) = &node.text {
+        if map.is_empty() {
+            return Value::String(text.clone());
+        }
+        map.insert("_text".to_string(), Value::String(text.clone()));
+    }
+    Value::Object(map)
+}
+
+/// Flattens a detail tree into a `HashMap<String, Value>` keyed by
+/// ordinal-tagged paths (`r_0_link_uid`, `r_1_link_uid`, ...) so every
+/// element's position among its siblings is baked into its key — no two
+/// elements can ever collide onto the same key, unlike [`as_flat_map`]'s
+/// tag-keyed array folding. See the module docs for why this exists
+/// alongside [`as_flat_map`] rather than replacing it.
+///
+/// A completely empty element (no attributes, no text, no children) would
+/// otherwise vanish with nothing left to flatten, so it gets a bare
+/// `Value::Null` entry at its own ordinal path as a presence marker.
+/// Attribute order within an element is not preserved by this scheme —
+/// only element/child order and repetition are.
+pub fn flatten_detail_tree(nodes: &[DetailNode]) -> HashMap<String, Value> {
+    let mut out = HashMap::new();
+    for (ordinal, node) in nodes.iter().enumerate() {
+        let prefix = format!("r_{ordinal}_{}", escape_segment(&node.name));
+        flatten_node_into(&prefix, node, &mut out);
+    }
+    out
+}
+
+fn flatten_node_into(prefix: &str, node: &DetailNode, out: &mut HashMap<String, Value>) {
+    if node.attrs.is_empty() && node.text.is_none() && node.children.is_empty() {
+        out.insert(prefix.to_string(), Value::Null);
+        return;
+    }
+    for (key, value) in &node.attrs {
+        out.insert(format!("{prefix}_{}", escape_segment(key)), Value::String(value.clone()));
+    }
+    if let Some(text) = &node.text {
+        out.insert(format!("{prefix}__text"), Value::String(text.clone()));
+    }
+    for (ordinal, child) in node.children.iter().enumerate() {
+        let child_prefix = format!("{prefix}_{ordinal}_{}", escape_segment(&child.name));
+        flatten_node_into(&child_prefix, child, out);
+    }
+}
+
+/// Inverse of [`flatten_detail_tree`]: reconstructs the detail tree from its
+/// ordinal-tagged flat map. Keys that don't match the `r_<ordinal>_<name>...`
+/// shape are ignored rather than rejected, since a caller may be unflattening
+/// a map that also carries unrelated fields.
+pub fn unflatten_detail_tree(flattened: &HashMap<String, Value>) -> Vec<DetailNode> {
+    let mut roots: Vec<DetailNode> = Vec::new();
+    for (key, value) in flattened {
+        let Some(suffix) = key.strip_prefix("r_") else {
+            continue;
+        };
+        let tokens: Vec<&str> = suffix.split('_').collect();
+        insert_leaf(&mut roots, &tokens, value);
+    }
+    roots
+}
+
+/// Walks `tokens` (alternating ordinal, escaped-name, then either a child's
+/// ordinal/name pair, an `_text` sentinel, or an escaped attribute name) one
+/// level at a time, growing `nodes` as needed and recursing into `children`
+/// for anything past the element's own attrs/text.
+fn insert_leaf(nodes: &mut Vec<DetailNode>, tokens: &[&str], value: &Value) {
+    let Some((ordinal_tok, rest)) = tokens.split_first() else {
+        return;
+    };
+    let Ok(ordinal) = ordinal_tok.parse::<usize>() else {
+        return;
+    };
+    let Some((name_tok, rest)) = rest.split_first() else {
+        return;
+    };
+
+    while nodes.len() <= ordinal {
+        nodes.push(DetailNode::default());
+    }
+    let node = &mut nodes[ordinal];
+    if node.name.is_empty() {
+        node.name = unescape_segment(name_tok);
+    }
+
+    match rest {
+        [] => {
+            // Presence marker for a fully empty element: nothing further to set.
+        }
+        // `{prefix}__text` splits on `_` into an empty token then `text`.
+        ["", "text"] => {
+            if let Some(s) = value.as_str() {
+                node.text = Some(s.to_string());
+            }
+        }
+        [attr_tok] if attr_tok.parse::<usize>().is_err() => {
+            if let Some(s) = value.as_str() {
+                node.attrs.push((unescape_segment(attr_tok), s.to_string()));
+            }
+        }
+        _ => insert_leaf(&mut node.children, rest, value),
+    }
+}
+
+/// Splits a possibly-prefixed tag or attribute name like `"usericon"` or
+/// `"tak:usericon"` into its `(prefix, local_name)` parts. [`parse_detail_tree`]
+/// already keeps a prefixed name intact as one opaque string (quick-xml's
+/// basic, non-namespace-aware `Reader` never splits on the colon), so two
+/// vendors' differently-prefixed tags never collide in [`DetailNode::name`] —
+/// this only matters once you need to compare against a *resolved* namespace
+/// URI rather than the raw prefix text, since two documents can alias the
+/// same prefix to different URIs (or the same URI to different prefixes).
+pub fn split_qname(name: &str) -> (Option<&str>, &str) {
+    match name.split_once(':') {
+        Some((prefix, local)) => (Some(prefix), local),
+        None => (None, name),
+    }
+}
+
+/// Finds every element reachable from `nodes` (searched recursively, in
+/// document order) whose namespace resolves to `namespace_uri`, tracking
+/// `xmlns`/`xmlns:prefix` declarations as it descends so a prefix introduced
+/// on an ancestor still applies to its descendants, the same scoping rule
+/// `xmlns` declarations follow in real XML.
+///
+/// `xmlns`/`xmlns:prefix` attributes themselves already round-trip
+/// byte-for-byte through [`parse_detail_tree`]/[`write_detail_tree`] since
+/// they're ordinary attributes to the parser — this just adds the resolution
+/// step CoT extension schemas need on top, without requiring a namespace-aware
+/// parser.
+pub fn find_by_namespace<'a>(nodes: &'a [DetailNode], namespace_uri: &str) -> Vec<&'a DetailNode> {
+    let mut matches = Vec::new();
+    let scope = HashMap::new();
+    for node in nodes {
+        find_by_namespace_in(node, namespace_uri, &scope, &mut matches);
+    }
+    matches
+}
+
+fn find_by_namespace_in<'a>(
+    node: &'a DetailNode,
+    namespace_uri: &str,
+    parent_scope: &HashMap<String, String>,
+    matches: &mut Vec<&'a DetailNode>,
+) {
+    let mut scope = parent_scope.clone();
+    for (key, value) in &node.attrs {
+        if key == "xmlns" {
+            scope.insert(String::new(), value.clone());
+        } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+            scope.insert(prefix.to_string(), value.clone());
+        }
+    }
+
+    let (prefix, _local) = split_qname(&node.name);
+    if scope.get(prefix.unwrap_or("")).map(String::as_str) == Some(namespace_uri) {
+        matches.push(node);
+    }
+
+    for child in &node.children {
+        find_by_namespace_in(child, namespace_uri, &scope, matches);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flat_attributes_in_document_order() {
+        let nodes = parse_detail_tree(r#"<detail><contact zulu="1" alpha="2"/></detail>"#);
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].name, "contact");
+        assert_eq!(
+            nodes[0].attrs,
+            vec![
+                ("zulu".to_string(), "1".to_string()),
+                ("alpha".to_string(), "2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn preserves_repeated_sibling_elements_as_separate_children() {
+        let nodes =
+            parse_detail_tree(r#"<detail><link uid="PARENT-1"/><link uid="PARENT-2"/></detail>"#);
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].attr("uid"), Some("PARENT-1"));
+        assert_eq!(nodes[1].attr("uid"), Some("PARENT-2"));
+    }
+
+    #[test]
+    fn preserves_mixed_attribute_text_and_children() {
+        let nodes = parse_detail_tree(
+            r#"<detail><fileshare name="map.kmz"><remarks>shared</remarks></fileshare></detail>"#,
+        );
+        assert_eq!(nodes.len(), 1);
+        let fileshare = &nodes[0];
+        assert_eq!(fileshare.attr("name"), Some("map.kmz"));
+        assert_eq!(fileshare.children.len(), 1);
+        assert_eq!(fileshare.children[0].name, "remarks");
+        assert_eq!(fileshare.children[0].text.as_deref(), Some("shared"));
+    }
+
+    #[test]
+    fn round_trips_exactly_through_write_detail_tree() {
+        let xml = r#"<detail><__group name="Blue" role="Team Lead"/><link uid="A"/><link uid="B"/></detail>"#;
+        let nodes = parse_detail_tree(xml);
+        let written = write_detail_tree(&nodes);
+        let reparsed = parse_detail_tree(&format!("<detail>{written}</detail>"));
+        assert_eq!(nodes, reparsed);
+    }
+
+    #[test]
+    fn escapes_special_characters_when_writing() {
+        let mut node = DetailNode::new("contact");
+        node.attrs
+            .push(("callsign".to_string(), "A&B <evil>".to_string()));
+        let written = write_detail_tree(&[node]);
+        assert!(written.contains("A&amp;B &lt;evil&gt;"));
+    }
+
+    #[test]
+    fn as_flat_map_folds_repeated_siblings_into_an_array() {
+        let nodes =
+            parse_detail_tree(r#"<detail><link uid="PARENT-1"/><link uid="PARENT-2"/></detail>"#);
+        let flat = as_flat_map(&nodes);
+        let links = flat.get("link").unwrap().as_array().unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0]["uid"], "PARENT-1");
+        assert_eq!(links[1]["uid"], "PARENT-2");
+    }
+
+    #[test]
+    fn as_flat_map_matches_parse_detail_section_for_simple_trees() {
+        let xml = r#"<contact callsign="ALPHA-1"/><status readiness="true"/>"#;
+        let nodes = parse_detail_tree(&format!("<detail>{xml}</detail>"));
+        let via_tree = as_flat_map(&nodes);
+        let via_flat = crate::detail_parser::parse_detail_section(xml);
+        assert_eq!(via_tree, via_flat);
+    }
+
+    #[test]
+    fn flatten_detail_tree_tags_repeated_siblings_with_distinct_ordinals() {
+        let nodes =
+            parse_detail_tree(r#"<detail><link uid="PARENT-1"/><link uid="PARENT-2"/></detail>"#);
+        let flat = flatten_detail_tree(&nodes);
+        assert_eq!(flat.get("r_0_link_uid"), Some(&Value::String("PARENT-1".to_string())));
+        assert_eq!(flat.get("r_1_link_uid"), Some(&Value::String("PARENT-2".to_string())));
+    }
+
+    #[test]
+    fn flatten_detail_tree_tags_nested_repeated_siblings_distinctly() {
+        let xml = r#"<detail><fileshare><link uid="A"/><link uid="B"/></fileshare></detail>"#;
+        let nodes = parse_detail_tree(xml);
+        let flat = flatten_detail_tree(&nodes);
+        assert_eq!(
+            flat.get("r_0_fileshare_0_link_uid"),
+            Some(&Value::String("A".to_string()))
+        );
+        assert_eq!(
+            flat.get("r_0_fileshare_1_link_uid"),
+            Some(&Value::String("B".to_string()))
+        );
+    }
+
+    #[test]
+    fn flatten_detail_tree_marks_fully_empty_elements_with_null() {
+        let nodes = parse_detail_tree(r#"<detail><archive/></detail>"#);
+        let flat = flatten_detail_tree(&nodes);
+        assert_eq!(flat.get("r_0_archive"), Some(&Value::Null));
+    }
+
+    #[test]
+    fn flatten_detail_tree_captures_attrs_and_text_together() {
+        let xml =
+            r#"<detail><fileshare name="map.kmz"><remarks>shared</remarks></fileshare></detail>"#;
+        let nodes = parse_detail_tree(xml);
+        let flat = flatten_detail_tree(&nodes);
+        assert_eq!(
+            flat.get("r_0_fileshare_name"),
+            Some(&Value::String("map.kmz".to_string()))
+        );
+        assert_eq!(
+            flat.get("r_0_fileshare_0_remarks__text"),
+            Some(&Value::String("shared".to_string()))
+        );
+    }
+
+    #[test]
+    fn unflatten_detail_tree_inverts_flatten_detail_tree() {
+        let xml = r#"<detail><__group name="Blue" role="Team Lead"/><link uid="A"/>
+            <link uid="B"/><archive/></detail>"#;
+        let nodes = parse_detail_tree(xml);
+        let flat = flatten_detail_tree(&nodes);
+        let mut reconstructed = unflatten_detail_tree(&flat);
+        reconstructed.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut expected = nodes.clone();
+        expected.sort_by(|a, b| a.name.cmp(&b.name));
+        // Attribute order isn't preserved by this scheme, so sort each
+        // node's attrs before comparing instead of relying on Vec equality.
+        let normalize = |mut ns: Vec<DetailNode>| {
+            for n in &mut ns {
+                n.attrs.sort();
+            }
+            ns
+        };
+        assert_eq!(normalize(reconstructed), normalize(expected));
+    }
+
+    #[test]
+    fn flatten_unflatten_write_round_trips_a_full_detail_section() {
+        let xml = r#"<detail><fileshare name="map.kmz"><remarks>shared</remarks></fileshare>
+            <link uid="A"/><link uid="B"/></detail>"#;
+        let nodes = parse_detail_tree(xml);
+        let flat = flatten_detail_tree(&nodes);
+        let reconstructed = unflatten_detail_tree(&flat);
+        let written = write_detail_tree(&reconstructed);
+        let reparsed = parse_detail_tree(&format!("<detail>{written}</detail>"));
+        assert_eq!(nodes, reparsed);
+    }
+
+    #[test]
+    fn write_detail_tree_preserves_interleaved_text_runs_around_a_child() {
+        let xml = r#"<detail><note>before<link uid="A"/>after</note></detail>"#;
+        let nodes = parse_detail_tree(xml);
+        let note = &nodes[0];
+        assert_eq!(
+            note.content,
+            vec![
+                Content::Text("before".to_string()),
+                Content::Element(DetailNode {
+                    name: "link".to_string(),
+                    attrs: vec![("uid".to_string(), "A".to_string())],
+                    text: None,
+                    children: Vec::new(),
+                    content: Vec::new(),
+                }),
+                Content::Text("after".to_string()),
+            ]
+        );
+        // DetailNode's PartialEq ignores `content`, so `text` alone (the last
+        // run seen) can't tell the two runs apart - only `content` can.
+        assert_eq!(note.text.as_deref(), Some("after"));
+
+        let written = write_detail_tree(&nodes);
+        let inner = xml.trim_start_matches("<detail>").trim_end_matches("</detail>");
+        assert_eq!(written, inner);
+        let reparsed = parse_detail_tree(&format!("<detail>{written}</detail>"));
+        assert_eq!(reparsed[0].content, note.content);
+    }
+
+    #[test]
+    fn split_qname_separates_prefix_from_local_name() {
+        assert_eq!(split_qname("tak:usericon"), (Some("tak"), "usericon"));
+        assert_eq!(split_qname("contact"), (None, "contact"));
+    }
+
+    #[test]
+    fn find_by_namespace_resolves_a_prefix_declared_on_the_element_itself() {
+        let xml = r#"<detail><tak:usericon xmlns:tak="urn:tak" iconsetpath="x"/></detail>"#;
+        let nodes = parse_detail_tree(xml);
+
+        let found = find_by_namespace(&nodes, "urn:tak");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "tak:usericon");
+    }
+
+    #[test]
+    fn find_by_namespace_inherits_a_prefix_declared_on_an_ancestor() {
+        let xml = r#"<detail><ext xmlns:tak="urn:tak"><tak:usericon/></ext></detail>"#;
+        let nodes = parse_detail_tree(xml);
+
+        let found = find_by_namespace(&nodes, "urn:tak");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "tak:usericon");
+    }
+
+    #[test]
+    fn find_by_namespace_distinguishes_the_same_prefix_aliased_to_different_uris() {
+        let xml = concat!(
+            r#"<detail><a xmlns:ext="urn:vendor-one"><ext:widget/></a>"#,
+            r#"<b xmlns:ext="urn:vendor-two"><ext:widget/></b></detail>"#,
+        );
+        let nodes = parse_detail_tree(xml);
+
+        let vendor_one = find_by_namespace(&nodes, "urn:vendor-one");
+        assert_eq!(vendor_one.len(), 1);
+        let vendor_two = find_by_namespace(&nodes, "urn:vendor-two");
+        assert_eq!(vendor_two.len(), 1);
+        assert!(!std::ptr::eq(vendor_one[0], vendor_two[0]));
+    }
+
+    #[test]
+    fn find_by_namespace_round_trips_the_xmlns_declaration_unchanged() {
+        let xml = r#"<detail><tak:usericon xmlns:tak="urn:tak"/></detail>"#;
+        let nodes = parse_detail_tree(xml);
+        let written = write_detail_tree(&nodes);
+        assert_eq!(written, r#"<tak:usericon xmlns:tak="urn:tak"/>"#);
+    }
+}
@@ -0,0 +1,99 @@
+//! Apache Avro binary encoding for a flattened Ditto document, as another
+//! compact wire form alongside [`msgpack`](super::msgpack).
+//!
+//! The ask this module answers wants a `.avsc` generated at build time from
+//! the Ditto JSON schemas, the same way `build.rs` generates `schema.rs`
+//! (see [`validation`](super::validation)'s header for that gap) — but
+//! those schema fixtures aren't checked into this tree, so there's nothing
+//! for a build-time step to read. A per-variant Avro `record` schema would
+//! also need `Api`/`Chat`/`File`/`Generic`/`MapItem`'s exact generated field
+//! lists, which live only in the unchecked-in `schema.rs`.
+//!
+//! What's achievable without either: every variant's
+//! [`to_flattened_json`](super::CotDocument::to_flattened_json) form is
+//! already a flat map of short-key scalars (string/number/boolean, with the
+//! `r` detail map itself flattened to scalar `r_*` keys by
+//! [`r_field_flattening`](super::r_field_flattening)) — a shape one static
+//! Avro `map` schema describes exactly, with no per-variant knowledge
+//! needed. [`FLATTENED_DOCUMENT_SCHEMA`] is that schema, and
+//! [`flattened_document_to_avro`]/[`flattened_document_from_avro`] are its
+//! encode/decode pair.
+
+use apache_avro::Schema;
+use serde_json::Value;
+
+use crate::error::CotError;
+
+/// Avro schema for a flattened Ditto document: a map from short field key to
+/// one of the scalar types [`r_field_flattening`](super::r_field_flattening)
+/// ever produces. Unlike MessagePack's self-describing binary form, Avro's
+/// raw (non-container) encoding carries no schema, so this same schema must
+/// also be supplied to [`flattened_document_from_avro`].
+pub const FLATTENED_DOCUMENT_SCHEMA: &str = r#"{
+    "type": "map",
+    "values": ["null", "boolean", "long", "double", "string"]
+}"#;
+
+fn schema() -> Schema {
+    Schema::parse_str(FLATTENED_DOCUMENT_SCHEMA).expect("FLATTENED_DOCUMENT_SCHEMA is valid Avro")
+}
+
+/// Encodes a flattened Ditto document (as produced by
+/// [`cot_to_flattened_document`](super::to_ditto::cot_to_flattened_document))
+/// as raw Avro bytes.
+pub fn flattened_document_to_avro(document: &Value) -> Result<Vec<u8>, CotError> {
+    let schema = schema();
+    let avro_value =
+        apache_avro::to_value(document).map_err(|e| CotError::AvroEncode(e.to_string()))?;
+    let resolved = avro_value
+        .resolve(&schema)
+        .map_err(|e| CotError::AvroEncode(e.to_string()))?;
+    apache_avro::to_avro_datum(&schema, resolved).map_err(|e| CotError::AvroEncode(e.to_string()))
+}
+
+/// Decodes a flattened Ditto document previously written by
+/// [`flattened_document_to_avro`].
+pub fn flattened_document_from_avro(bytes: &[u8]) -> Result<Value, CotError> {
+    let schema = schema();
+    let mut cursor = std::io::Cursor::new(bytes);
+    let avro_value = apache_avro::from_avro_datum(&schema, &mut cursor, None)
+        .map_err(|e| CotError::AvroDecode(e.to_string()))?;
+    apache_avro::from_value::<Value>(&avro_value).map_err(|e| CotError::AvroDecode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::cot_to_flattened_document;
+
+    fn event() -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "uid-1".to_string(),
+            event_type: "a-f-G-U-C".to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::new(34.0, -118.0, 100.0),
+            detail: r#"<detail><contact callsign="ALPHA-1"/></detail>"#.to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn flattened_document_round_trips_through_avro() {
+        let original = cot_to_flattened_document(&event(), "peer");
+        let bytes = flattened_document_to_avro(&original).unwrap();
+        let decoded = flattened_document_from_avro(&bytes).unwrap();
+
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn decoding_garbage_bytes_is_a_typed_error() {
+        let err = flattened_document_from_avro(&[0xff, 0xff, 0xff]).unwrap_err();
+        assert!(matches!(err, CotError::AvroDecode(_)));
+    }
+}
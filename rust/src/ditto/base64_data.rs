@@ -0,0 +1,237 @@
+//! A byte payload that serializes as URL-safe no-pad base64 but tolerates
+//! several other base64 flavors on the way in.
+//!
+//! The ask this module answers names a `FileDocument` struct flattening
+//! `CommonFields` — neither exists in this tree (this crate's file-carrying
+//! type is [`CotDocument::File`](super::CotDocument::File), a `File` struct
+//! generated from the Ditto JSON schemas, not a hand-rolled
+//! `#[serde(flatten)]` composition) — and a `data_encoding::Encoding` list,
+//! which isn't a dependency here; this crate already depends on `base64`
+//! (see [`attachment`](super::attachment) and
+//! [`encryption`](super::encryption)) and uses it for exactly this kind of
+//! payload encoding, so [`Base64Data`] tries a list of `base64` engines
+//! instead of `data_encoding` ones. What's delivered is the generically
+//! useful piece the request actually wants — a newtype that always emits
+//! URL-safe no-pad base64 but decodes whatever flavor a heterogeneous peer
+//! sent — available to any `r`-map detail field via
+//! [`CotDocument::base64_field`]/[`CotDocument::set_base64_field`] rather
+//! than a field on a struct that doesn't exist.
+
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ditto::{ApiRValue, ChatRValue, CotDocument, FileRValue, GenericRValue, MapItemRValue};
+use std::collections::HashMap;
+
+/// A byte payload with a base64 wire form. Always serializes with
+/// [`URL_SAFE_NO_PAD`]; deserializes by trying [`URL_SAFE_NO_PAD`],
+/// [`URL_SAFE`], [`STANDARD`], then [`STANDARD_NO_PAD`] in order, accepting
+/// the first one that decodes successfully.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Base64Data(pub Vec<u8>);
+
+impl Base64Data {
+    /// Decodes `encoded` by trying each accepted base64 flavor in turn,
+    /// failing only if none of them can make sense of it.
+    pub fn decode(encoded: &str) -> Result<Self, base64::DecodeError> {
+        let engines = [URL_SAFE_NO_PAD, URL_SAFE, STANDARD, STANDARD_NO_PAD];
+        let mut last_err = None;
+        for engine in engines {
+            match engine.decode(encoded) {
+                Ok(bytes) => return Ok(Self(bytes)),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.expect("engines is non-empty"))
+    }
+}
+
+impl std::fmt::Display for Base64Data {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", URL_SAFE_NO_PAD.encode(&self.0))
+    }
+}
+
+impl Serialize for Base64Data {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&URL_SAFE_NO_PAD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Base64Data {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Base64Data::decode(&s).map_err(D::Error::custom)
+    }
+}
+
+impl schemars::JsonSchema for Base64Data {
+    fn schema_name() -> String {
+        "Base64Data".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let mut schema = schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            ..Default::default()
+        };
+        schema
+            .extensions
+            .insert("contentEncoding".to_string(), serde_json::json!("base64"));
+        schema.into()
+    }
+}
+
+impl CotDocument {
+    /// Reads detail field `field` as decoded [`Base64Data`], tolerating
+    /// whatever base64 flavor the value was written in. Returns `None` for
+    /// a missing field, a non-string value, or a string none of the
+    /// accepted encodings can decode.
+    pub fn base64_field(&self, field: &str) -> Option<Base64Data> {
+        fn read<T>(
+            r: &HashMap<String, T>,
+            field: &str,
+            as_str: impl Fn(&T) -> Option<&str>,
+        ) -> Option<Base64Data> {
+            Base64Data::decode(as_str(r.get(field)?)?).ok()
+        }
+
+        match self {
+            CotDocument::Api(d) => read(&d.r, field, |v| match v {
+                ApiRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::Chat(d) => read(&d.r, field, |v| match v {
+                ChatRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::File(d) => read(&d.r, field, |v| match v {
+                FileRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::Generic(d) => read(&d.r, field, |v| match v {
+                GenericRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::MapItem(d) => read(&d.r, field, |v| match v {
+                MapItemRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::Unknown(u) => {
+                let raw = u.raw.as_object()?.get("r")?.as_object()?.get(field)?.as_str()?;
+                Base64Data::decode(raw).ok()
+            }
+        }
+    }
+
+    /// Writes `data` into detail field `field` as URL-safe no-pad base64,
+    /// replacing whatever was there before.
+    pub fn set_base64_field(&mut self, field: &str, data: &Base64Data) {
+        let encoded = data.to_string();
+
+        match self {
+            CotDocument::Api(d) => {
+                d.r.insert(field.to_string(), ApiRValue::String(encoded));
+            }
+            CotDocument::Chat(d) => {
+                d.r.insert(field.to_string(), ChatRValue::String(encoded));
+            }
+            CotDocument::File(d) => {
+                d.r.insert(field.to_string(), FileRValue::String(encoded));
+            }
+            CotDocument::Generic(d) => {
+                d.r.insert(field.to_string(), GenericRValue::String(encoded));
+            }
+            CotDocument::MapItem(d) => {
+                d.r.insert(field.to_string(), MapItemRValue::String(encoded));
+            }
+            CotDocument::Unknown(u) => {
+                let r = u
+                    .raw
+                    .as_object_mut()
+                    .and_then(|obj| obj.get_mut("r"))
+                    .and_then(serde_json::Value::as_object_mut);
+                if let Some(r) = r {
+                    r.insert(field.to_string(), serde_json::Value::String(encoded));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::MapItem;
+
+    fn map_item(r: HashMap<String, MapItemRValue>) -> CotDocument {
+        CotDocument::MapItem(MapItem {
+            id: "UID-1".to_string(),
+            a: "peer-a".to_string(),
+            b: 0.0,
+            c: None,
+            d: "UID-1".to_string(),
+            d_c: 0,
+            d_r: false,
+            d_v: 1,
+            source: None,
+            e: "ALPHA-1".to_string(),
+            f: None,
+            g: "2.0".to_string(),
+            h: None,
+            i: Some(10.0),
+            j: Some(35.0),
+            k: Some(5.0),
+            l: Some(-118.0),
+            n: Some(0.0),
+            o: Some(0.0),
+            p: "h-g-i-g-o".to_string(),
+            q: String::new(),
+            r,
+            s: String::new(),
+            t: String::new(),
+            u: String::new(),
+            v: String::new(),
+            w: "a-f-G-U-C".to_string(),
+        })
+    }
+
+    #[test]
+    fn always_serializes_as_url_safe_no_pad() {
+        let data = Base64Data(vec![0xfb, 0xff, 0xfe]);
+        assert_eq!(serde_json::to_string(&data).unwrap(), "\"-__-\"");
+    }
+
+    #[test]
+    fn decode_accepts_standard_padded_base64() {
+        let data = Base64Data::decode("+/7/").unwrap();
+        assert_eq!(data.0, vec![0xfb, 0xfe, 0xff]);
+    }
+
+    #[test]
+    fn decode_accepts_url_safe_no_pad_base64() {
+        let data = Base64Data::decode("-__-").unwrap();
+        assert_eq!(data.0, vec![0xfb, 0xff, 0xfe]);
+    }
+
+    #[test]
+    fn decode_rejects_invalid_base64() {
+        assert!(Base64Data::decode("not valid base64!!").is_err());
+    }
+
+    #[test]
+    fn base64_field_round_trips_through_a_document() {
+        let mut doc = map_item(HashMap::new());
+        let data = Base64Data(b"hello world".to_vec());
+        doc.set_base64_field("payload", &data);
+        assert_eq!(doc.base64_field("payload"), Some(data));
+    }
+
+    #[test]
+    fn base64_field_is_none_when_absent() {
+        let doc = map_item(HashMap::new());
+        assert_eq!(doc.base64_field("payload"), None);
+    }
+}
@@ -0,0 +1,190 @@
+//! Parallel batch conversion between [`CotEvent`] and [`CotDocument`] for
+//! high-volume ingest.
+//!
+//! [`try_cot_to_document`]/[`cot_event_from_ditto_document`] convert one
+//! event at a time, which bottlenecks a gateway replaying thousands of
+//! cached situational-awareness messages on reconnect. [`convert_many`] and
+//! [`convert_many_to_events`] fan that work out over a bounded
+//! `crossbeam_channel` work queue serviced by a fixed pool of worker
+//! threads: each worker pulls the next item's index, converts it
+//! independently of every other item, and sends its result back tagged with
+//! that index, so results can be reassembled in input order regardless of
+//! which worker finished first or how long any single conversion took. A
+//! conversion failure is returned inline in that item's slot — it neither
+//! aborts the batch nor drops the item — so one malformed event in a
+//! thousand-event backlog doesn't cost the other 999.
+//!
+//! There is no bound on *when* a given worker reports a result beyond the
+//! order it pulls work in, but the bounded queue caps how far ahead of the
+//! slowest worker the feeder can get, so memory use stays proportional to
+//! `worker_count` rather than the whole batch.
+
+use std::thread;
+
+use crossbeam_channel::bounded;
+
+use crate::cot_events::CotEvent;
+use crate::ditto::from_ditto::cot_event_from_ditto_document;
+use crate::ditto::to_ditto::{try_cot_to_document, CotDocument};
+use crate::error::CotConversionError;
+
+/// Worker count [`convert_many`]/[`convert_many_to_events`] use when the
+/// caller doesn't need to tune it. Conversion is CPU-bound pure computation
+/// with no I/O to overlap, so there's little benefit past a handful of
+/// threads for the batch sizes this is meant for (thousands, not millions).
+const DEFAULT_WORKER_COUNT: usize = 4;
+
+/// Runs `convert` over every item in `items` across `worker_count` threads,
+/// returning results in input order. See the module documentation for the
+/// queueing and ordering guarantees.
+fn run_batch<T, R>(items: &[T], worker_count: usize, convert: impl Fn(&T) -> R + Sync) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    if items.is_empty() {
+        return Vec::new();
+    }
+    let worker_count = worker_count.clamp(1, items.len());
+    let (work_tx, work_rx) = bounded::<usize>(worker_count * 2);
+    let (result_tx, result_rx) = bounded::<(usize, R)>(worker_count * 2);
+
+    thread::scope(|scope| {
+        for _ in 0..worker_count {
+            let work_rx = work_rx.clone();
+            let result_tx = result_tx.clone();
+            let convert = &convert;
+            scope.spawn(move || {
+                for index in work_rx {
+                    if result_tx.send((index, convert(&items[index]))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(result_tx);
+        drop(work_rx);
+
+        scope.spawn(move || {
+            for index in 0..items.len() {
+                if work_tx.send(index).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut results: Vec<Option<R>> = (0..items.len()).map(|_| None).collect();
+        for (index, result) in result_rx {
+            results[index] = Some(result);
+        }
+        results
+            .into_iter()
+            .map(|result| result.expect("every index is produced by exactly one worker"))
+            .collect()
+    })
+}
+
+/// Converts every event in `events` to a [`CotDocument`] in parallel,
+/// preserving input order and reporting each item's conversion failure
+/// inline rather than aborting the batch. Uses [`DEFAULT_WORKER_COUNT`]
+/// worker threads; see [`convert_many_with_workers`] to tune that.
+pub fn convert_many(
+    events: &[CotEvent],
+    peer_key: &str,
+) -> Vec<Result<CotDocument, CotConversionError>> {
+    convert_many_with_workers(events, peer_key, DEFAULT_WORKER_COUNT)
+}
+
+/// Like [`convert_many`], but with an explicit worker count (clamped to at
+/// least 1 and at most `events.len()`).
+pub fn convert_many_with_workers(
+    events: &[CotEvent],
+    peer_key: &str,
+    worker_count: usize,
+) -> Vec<Result<CotDocument, CotConversionError>> {
+    run_batch(events, worker_count, |event| {
+        try_cot_to_document(event, peer_key)
+    })
+}
+
+/// The inverse of [`convert_many`]: converts every [`CotDocument`] in `docs`
+/// back to a [`CotEvent`] in parallel, preserving input order.
+/// [`cot_event_from_ditto_document`] is a best-effort, infallible mapping,
+/// so unlike [`convert_many`] there's no per-item `Result` to isolate — a
+/// document this crate can't map faithfully degrades silently the same way
+/// [`cot_event_from_ditto_document`] already does for a single document.
+pub fn convert_many_to_events(docs: &[CotDocument]) -> Vec<CotEvent> {
+    convert_many_to_events_with_workers(docs, DEFAULT_WORKER_COUNT)
+}
+
+/// Like [`convert_many_to_events`], but with an explicit worker count
+/// (clamped to at least 1 and at most `docs.len()`).
+pub fn convert_many_to_events_with_workers(
+    docs: &[CotDocument],
+    worker_count: usize,
+) -> Vec<CotEvent> {
+    run_batch(docs, worker_count, cot_event_from_ditto_document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEventBuilder;
+    use crate::ditto::CommonDocumentFields;
+
+    fn sample_event(uid: &str) -> CotEvent {
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type("a-f-G-U-C")
+            .location(1.0, 2.0, 3.0)
+            .build()
+    }
+
+    #[test]
+    fn results_preserve_input_order() {
+        let events: Vec<CotEvent> =
+            (0..20).map(|i| sample_event(&format!("UID-{i}"))).collect();
+
+        let docs = convert_many(&events, "peer-a");
+        assert_eq!(docs.len(), 20);
+        for (i, doc) in docs.iter().enumerate() {
+            let doc = doc.as_ref().expect("generated event must convert");
+            assert_eq!(doc.common_id(), Some(format!("UID-{i}").as_str()));
+        }
+    }
+
+    #[test]
+    fn a_single_invalid_item_does_not_abort_the_rest() {
+        let mut events: Vec<CotEvent> = (0..5).map(|i| sample_event(&format!("UID-{i}"))).collect();
+        events[2].point.lat = f64::NAN;
+
+        let results = convert_many(&events, "peer-a");
+        assert_eq!(results.len(), 5);
+        assert!(results[2].is_err());
+        for i in [0, 1, 3, 4] {
+            assert!(results[i].is_ok());
+        }
+    }
+
+    #[test]
+    fn round_trips_back_to_events_preserving_order() {
+        let events: Vec<CotEvent> =
+            (0..10).map(|i| sample_event(&format!("UID-{i}"))).collect();
+        let docs: Vec<CotDocument> = convert_many(&events, "peer-a")
+            .into_iter()
+            .map(|result| result.unwrap())
+            .collect();
+
+        let round_tripped = convert_many_to_events(&docs);
+        assert_eq!(round_tripped.len(), 10);
+        for (i, event) in round_tripped.iter().enumerate() {
+            assert_eq!(event.uid, format!("UID-{i}"));
+        }
+    }
+
+    #[test]
+    fn an_empty_batch_yields_an_empty_result() {
+        let events: Vec<CotEvent> = Vec::new();
+        assert!(convert_many(&events, "peer-a").is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+//! BSON binary encoding for [`CotDocument`], matching the wire representation
+//! Ditto itself persists and syncs, as an alternative to the lossy JSON
+//! intermediate for documents carrying binary detail payloads.
+//!
+//! [`CotDocument::to_bson`]/[`CotDocument::from_bson`] go through the same
+//! `serde::Serialize`/`Deserialize` impls JSON and [`msgpack`](super::msgpack)
+//! already use, so the single-letter field keys and `_id` document key carry
+//! over unchanged. Coordinate fields land as BSON doubles rather than ints
+//! automatically — they're already typed `f64` on the generated schema
+//! structs, so the BSON serializer never has a reason to pick an integer
+//! representation, integral or not.
+//!
+//! `from_bson`'s `w`-field dispatch mirrors
+//! [`CotDocument::from_json_str`], which
+//! [`observer_json_to_cot_document`](super::sdk_conversion::observer_json_to_cot_document)
+//! already relies on for the JSON path.
+//!
+//! Binary detail payloads are a separate problem. An inline binary element
+//! (see [`attachment`](super::attachment)'s `DittoAttachmentToken::inline`
+//! shape: an `Object` carrying `filename`/`mime`/`size`/`data`) stores its
+//! payload as a base64 `data` string, because `XxxRValue` has no `Binary`
+//! variant to carry raw bytes — the same generated-schema gap
+//! [`attachment`](super::attachment), [`or_set`](super::or_set), and
+//! [`text_crdt`](super::text_crdt) ran into. BSON actually has a binary
+//! type, so `to_bson` rewrites any `data` field shaped that way into a real
+//! `Binary` (subtype 0) after serializing, and `from_bson` reverses it
+//! before dispatching, so the payload round-trips as actual bytes instead of
+//! a base64 string without needing a schema change.
+
+use bson::spec::BinarySubtype;
+use bson::{Binary, Bson, Document};
+
+use super::base64_data::Base64Data;
+use super::to_ditto::{CotDocument, UnknownDocument};
+use super::{Api, Chat, Generic, MapItem};
+use crate::error::CotError;
+
+impl CotDocument {
+    /// Encodes this document as BSON bytes, the wire form Ditto itself
+    /// stores and syncs.
+    pub fn to_bson(&self) -> Result<Vec<u8>, CotError> {
+        let mut doc = bson::to_document(self).map_err(|e| CotError::BsonEncode(e.to_string()))?;
+        inline_data_to_binary(&mut doc);
+        bson::to_vec(&doc).map_err(|e| CotError::BsonEncode(e.to_string()))
+    }
+
+    /// Decodes a [`CotDocument`] previously written by
+    /// [`CotDocument::to_bson`], dispatching on the `w` field exactly like
+    /// [`CotDocument::from_json_str`].
+    pub fn from_bson(bytes: &[u8]) -> Result<Self, CotError> {
+        let mut doc: Document =
+            bson::from_slice(bytes).map_err(|e| CotError::BsonDecode(e.to_string()))?;
+        inline_data_to_base64(&mut doc);
+
+        let doc_type = match doc.get_str("w") {
+            Ok(w) => w.to_string(),
+            Err(_) => {
+                return Ok(CotDocument::Unknown(UnknownDocument {
+                    raw: document_to_json(doc)?,
+                }))
+            }
+        };
+
+        if doc_type.contains("a-u-r-loc-g")
+            || doc_type.contains("a-f-G-U-C")
+            || doc_type.contains("a-f-G-U")
+            || doc_type.contains("a-f-G-U-I")
+            || doc_type.contains("a-f-G-U-T")
+            || doc_type.contains("a-f-S-C-U")
+            || doc_type.contains("a-f-A-M-F-Q")
+            || doc_type.contains("a-u-S")
+            || doc_type.contains("a-u-A")
+            || doc_type.contains("a-u-G")
+        {
+            let map_item: MapItem =
+                bson::from_document(doc).map_err(|e| CotError::BsonDecode(e.to_string()))?;
+            Ok(CotDocument::MapItem(map_item))
+        } else if doc_type.contains("b-t-f") || doc_type.contains("chat") {
+            let chat: Chat =
+                bson::from_document(doc).map_err(|e| CotError::BsonDecode(e.to_string()))?;
+            Ok(CotDocument::Chat(chat))
+        } else if doc_type == "a-u-emergency-g" {
+            let api: Api =
+                bson::from_document(doc).map_err(|e| CotError::BsonDecode(e.to_string()))?;
+            Ok(CotDocument::Api(api))
+        } else if let Ok(generic) = bson::from_document::<Generic>(doc.clone()) {
+            Ok(CotDocument::Generic(generic))
+        } else {
+            Ok(CotDocument::Unknown(UnknownDocument {
+                raw: document_to_json(doc)?,
+            }))
+        }
+    }
+}
+
+/// Converts a decoded BSON document to the `serde_json::Value` shape
+/// [`UnknownDocument::raw`] expects, for a document `from_bson` couldn't
+/// match to a known variant.
+fn document_to_json(doc: Document) -> Result<serde_json::Value, CotError> {
+    serde_json::to_value(doc).map_err(|e| CotError::BsonDecode(e.to_string()))
+}
+
+/// Rewrites any `r`-map entry shaped like
+/// [`DittoAttachmentToken::inline`](super::attachment)'s output — an
+/// `Object` with a base64 `data` string — into a real BSON `Binary`
+/// (subtype 0), so the payload is carried as actual bytes on the wire
+/// instead of a base64 string.
+fn inline_data_to_binary(doc: &mut Document) {
+    let Some(Bson::Document(r)) = doc.get_mut("r") else {
+        return;
+    };
+    for value in r.values_mut() {
+        let Bson::Document(obj) = value else { continue };
+        let Some(Bson::String(data)) = obj.get("data") else {
+            continue;
+        };
+        if let Ok(decoded) = Base64Data::decode(data) {
+            obj.insert(
+                "data",
+                Bson::Binary(Binary {
+                    subtype: BinarySubtype::Generic,
+                    bytes: decoded.0,
+                }),
+            );
+        }
+    }
+}
+
+/// Inverse of [`inline_data_to_binary`], run before dispatch so the decoded
+/// document still matches the `data`-as-base64-string shape the generated
+/// `XxxRValue::Object` schema types expect.
+fn inline_data_to_base64(doc: &mut Document) {
+    let Some(Bson::Document(r)) = doc.get_mut("r") else {
+        return;
+    };
+    for value in r.values_mut() {
+        let Bson::Document(obj) = value else { continue };
+        let Some(Bson::Binary(binary)) = obj.get("data") else {
+            continue;
+        };
+        let encoded = Base64Data(binary.bytes.clone()).to_string();
+        obj.insert("data", Bson::String(encoded));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::cot_to_document;
+    use crate::ditto::MapItemRValue;
+    use serde_json::Value;
+
+    fn event(event_type: &str, detail: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "uid-1".to_string(),
+            event_type: event_type.to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::new(34.0, -118.0, 100.0),
+            detail: detail.to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn map_item_round_trips_through_bson() {
+        let original = cot_to_document(
+            &event(
+                "a-f-G-U-C",
+                r#"<detail><contact callsign="ALPHA-1"/></detail>"#,
+            ),
+            "peer",
+        );
+        let bytes = original.to_bson().unwrap();
+        let decoded = CotDocument::from_bson(&bytes).unwrap();
+
+        assert_eq!(original.to_flattened_json(), decoded.to_flattened_json());
+    }
+
+    #[test]
+    fn chat_round_trips_through_bson() {
+        let original = cot_to_document(
+            &event(
+                "b-t-f",
+                r#"<detail><__chat chatroom="ops" senderCallsign="ALPHA"><chatgrp uid0="uid-1" uid1="ops"/></__chat><remarks>hello</remarks></detail>"#,
+            ),
+            "peer",
+        );
+        assert!(matches!(original, CotDocument::Chat(_)));
+
+        let bytes = original.to_bson().unwrap();
+        let decoded = CotDocument::from_bson(&bytes).unwrap();
+
+        assert_eq!(original.to_flattened_json(), decoded.to_flattened_json());
+    }
+
+    #[test]
+    fn binary_detail_extra_round_trips_as_bson_binary_not_base64() {
+        let mut original = cot_to_document(
+            &event(
+                "a-f-G-U-C",
+                r#"<detail><contact callsign="ALPHA-1"/></detail>"#,
+            ),
+            "peer",
+        );
+        let payload = Base64Data(b"\x89PNG\r\n\x1a\n".to_vec()).to_string();
+        if let CotDocument::MapItem(ref mut map_item) = original {
+            let mut image = serde_json::Map::new();
+            image.insert("mime".to_string(), Value::String("image/png".to_string()));
+            image.insert("data".to_string(), Value::String(payload.clone()));
+            map_item.r.insert("image".to_string(), MapItemRValue::Object(image));
+        } else {
+            panic!("expected a MapItem document");
+        }
+
+        let encoded = original.to_bson().unwrap();
+        let mut bson_doc: Document = bson::from_slice(&encoded).unwrap();
+        let r = bson_doc.get_document_mut("r").unwrap();
+        let image = r.get_document("image").unwrap();
+        assert!(matches!(image.get("data"), Some(Bson::Binary(_))));
+
+        let bytes = original.to_bson().unwrap();
+        let decoded = CotDocument::from_bson(&bytes).unwrap();
+        assert_eq!(original.to_flattened_json(), decoded.to_flattened_json());
+
+        if let CotDocument::MapItem(map_item) = decoded {
+            match map_item.r.get("image") {
+                Some(MapItemRValue::Object(obj)) => {
+                    assert_eq!(obj.get("data"), Some(&Value::String(payload)));
+                }
+                other => panic!("expected an Object r-value, got {other:?}"),
+            }
+        } else {
+            panic!("expected a MapItem document");
+        }
+    }
+
+    #[test]
+    fn decoding_garbage_bytes_is_a_typed_error() {
+        let err = CotDocument::from_bson(&[0xff, 0x00, 0x01]).unwrap_err();
+        assert!(matches!(err, CotError::BsonDecode(_)));
+    }
+}
@@ -0,0 +1,167 @@
+//! A [`CotDocument`] wrapper that memoizes its flattened JSON
+//! materialization, so repeated [`DittoDocument`] accessor calls on the
+//! same document don't each re-run `serde_json::to_value` from scratch.
+//!
+//! [`DittoDocument`]'s `get`/`to_cbor`/`typed` impl for a bare
+//! [`CotDocument`] (see [`dql_support`](super::dql_support)) materializes
+//! the whole document to [`serde_json::Value`] on every call — fine for a
+//! one-off accessor, but wasteful when several paths are resolved against
+//! the same document, e.g. evaluating a
+//! [`CotFilter`](super::cot_filter::CotFilter) that touches multiple
+//! fields, or reading `to_cbor` and `get` back to back. [`CachedCotDocument`]
+//! materializes the JSON once, lazily, on first access, and reuses it for
+//! every subsequent call; replacing the wrapped document via
+//! [`CachedCotDocument::set`] clears the cache so a stale value never
+//! leaks through.
+
+use std::sync::OnceLock;
+
+use dittolive_ditto::error::{DittoError, ErrorKind};
+use dittolive_ditto::prelude::CborValue;
+use dittolive_ditto::store::query_builder::{DittoDocument, DocumentId};
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+use super::dql_support::{json_to_cbor, resolve_document_path};
+use crate::ditto::CotDocument;
+
+/// Wraps a [`CotDocument`] with a memoized flattened-JSON materialization,
+/// so [`DittoDocument::get`]/`to_cbor`/`typed` reuse the same
+/// [`serde_json::Value`] instead of re-serializing the document on every
+/// call. See the module docs for when this pays off; [`DittoDocument::id`]
+/// is unaffected since it never touches the flattened JSON.
+#[derive(Debug)]
+pub struct CachedCotDocument {
+    document: CotDocument,
+    json: OnceLock<JsonValue>,
+}
+
+impl CachedCotDocument {
+    /// Wraps `document`; materialization is deferred until the first
+    /// accessor call.
+    pub fn new(document: CotDocument) -> Self {
+        Self {
+            document,
+            json: OnceLock::new(),
+        }
+    }
+
+    /// Replaces the wrapped document and clears the memoized JSON value, so
+    /// the next accessor call re-materializes from the new document instead
+    /// of returning stale data.
+    pub fn set(&mut self, document: CotDocument) {
+        self.document = document;
+        self.json = OnceLock::new();
+    }
+
+    /// The wrapped document.
+    pub fn document(&self) -> &CotDocument {
+        &self.document
+    }
+
+    fn json(&self) -> &JsonValue {
+        self.json.get_or_init(|| self.document.to_flattened_json())
+    }
+}
+
+impl DittoDocument for CachedCotDocument {
+    fn id(&self) -> DocumentId {
+        DittoDocument::id(&self.document)
+    }
+
+    fn to_cbor(&self) -> Result<CborValue, DittoError> {
+        json_to_cbor(self.json().clone()).map_err(|_| DittoError::from(ErrorKind::InvalidInput))
+    }
+
+    fn get<V: DeserializeOwned>(&self, path: &str) -> Result<V, DittoError> {
+        let value = resolve_document_path(&self.document, self.json(), path)?;
+        serde_json::from_value(value).map_err(|_| DittoError::from(ErrorKind::InvalidInput))
+    }
+
+    fn typed<T: DeserializeOwned>(&self) -> Result<T, DittoError> {
+        serde_json::from_value(self.json().clone())
+            .map_err(|_| DittoError::from(ErrorKind::InvalidInput))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::schema::{MapItem, MapItemRValue};
+    use std::collections::HashMap;
+
+    fn map_item(r: HashMap<String, MapItemRValue>) -> CotDocument {
+        CotDocument::MapItem(MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: None,
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: 2,
+            e: "Test Item".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: None,
+            k: None,
+            l: None,
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r,
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        })
+    }
+
+    #[test]
+    fn get_and_to_cbor_reuse_the_same_materialized_json() {
+        let mut r = HashMap::new();
+        r.insert("speed".to_string(), MapItemRValue::Number(12.5));
+        let cached = CachedCotDocument::new(map_item(r));
+
+        let speed: f64 = DittoDocument::get(&cached, "detail.speed").unwrap();
+        assert_eq!(speed, 12.5);
+        assert!(DittoDocument::to_cbor(&cached).is_ok());
+        // Both calls above resolved through the same cached `json()` value;
+        // a third call must still see it rather than panicking on a stale
+        // or uninitialized cache.
+        let id: String = DittoDocument::get(&cached, "id").unwrap();
+        assert_eq!(id, "test-id-123");
+    }
+
+    #[test]
+    fn set_clears_the_cache_so_stale_values_are_not_returned() {
+        let mut cached = CachedCotDocument::new(map_item(HashMap::new()));
+        let before: String = DittoDocument::get(&cached, "id").unwrap();
+        assert_eq!(before, "test-id-123");
+
+        let mut r = HashMap::new();
+        r.insert("note".to_string(), MapItemRValue::String("b".to_string()));
+        let mut new_doc = map_item(r);
+        if let CotDocument::MapItem(ref mut item) = new_doc {
+            item.id = "test-id-456".to_string();
+        }
+        cached.set(new_doc);
+
+        let after: String = DittoDocument::get(&cached, "id").unwrap();
+        assert_eq!(after, "test-id-456");
+        let note: String = DittoDocument::get(&cached, "detail.note").unwrap();
+        assert_eq!(note, "b");
+    }
+
+    #[test]
+    fn document_returns_the_wrapped_value() {
+        let doc = map_item(HashMap::new());
+        let cached = CachedCotDocument::new(doc.clone());
+        assert!(matches!(cached.document(), CotDocument::MapItem(_)));
+    }
+}
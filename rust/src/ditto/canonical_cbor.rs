@@ -0,0 +1,215 @@
+//! Deterministic (RFC 8949 §4.2.1 "Core Deterministic Encoding") CBOR byte
+//! encoding, for content-addressing and deduping documents by their CBOR
+//! representation.
+//!
+//! [`dql_support`](super::dql_support)'s `to_cbor` builds a
+//! `BTreeMap<CborValue, CborValue>`, which on encode sorts map keys by
+//! `CborValue`'s own [`Ord`] rather than the canonical rule — so two
+//! semantically identical documents can serialize to different bytes
+//! depending on field order or key type. [`to_canonical_cbor_bytes`]
+//! re-encodes a [`CborValue`] by hand, enforcing:
+//! - every integer in its shortest form (CBOR's normal additional-info
+//!   encoding already does this; this just picks it explicitly rather than
+//!   trusting a general-purpose serializer to)
+//! - `u64` magnitudes above `i64::MAX` still encode as an unsigned major
+//!   type 0 integer rather than erroring or falling back to a float
+//! - map keys sorted by their own *encoded bytes*, bytewise lexicographic —
+//!   not by value — computed by canonically encoding each key first, then
+//!   sorting the resulting `(key_bytes, value_bytes)` pairs before
+//!   concatenating
+//! - definite-length encoding for every array and map (never the
+//!   indefinite-length/streaming form)
+//! - floats in the shortest IEEE-754 width that round-trips exactly,
+//!   checking `f32` before falling back to `f64` (half-precision `f16` is
+//!   out of scope: this crate has no dependency that represents it)
+
+use dittolive_ditto::prelude::CborValue;
+
+/// Encodes `value` as deterministic CBOR bytes. See the module docs for the
+/// canonical rules this enforces.
+pub fn to_canonical_cbor_bytes(value: &CborValue) -> Vec<u8> {
+    match value {
+        CborValue::Null => vec![0xf6],
+        CborValue::Bool(false) => vec![0xf4],
+        CborValue::Bool(true) => vec![0xf5],
+        CborValue::Integer(i) => encode_integer(*i),
+        CborValue::Float(f) => encode_float(*f),
+        CborValue::Text(s) => encode_head(3, s.len() as u64)
+            .into_iter()
+            .chain(s.bytes())
+            .collect(),
+        CborValue::Bytes(b) => encode_head(2, b.len() as u64)
+            .into_iter()
+            .chain(b.iter().copied())
+            .collect(),
+        CborValue::Array(items) => {
+            let mut out = encode_head(4, items.len() as u64);
+            for item in items {
+                out.extend(to_canonical_cbor_bytes(item));
+            }
+            out
+        }
+        CborValue::Map(map) => {
+            let mut pairs: Vec<(Vec<u8>, Vec<u8>)> = map
+                .iter()
+                .map(|(k, v)| (to_canonical_cbor_bytes(k), to_canonical_cbor_bytes(v)))
+                .collect();
+            pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let mut out = encode_head(5, pairs.len() as u64);
+            for (key_bytes, value_bytes) in pairs {
+                out.extend(key_bytes);
+                out.extend(value_bytes);
+            }
+            out
+        }
+        // Anything else (e.g. a CBOR tag) this crate never produces itself;
+        // encode as the CBOR "undefined" simple value rather than panicking
+        // on input from outside this module.
+        _ => vec![0xf7],
+    }
+}
+
+/// Encodes a major type and its length/value argument with CBOR's shortest
+/// additional-info form: the value itself if `< 24`, else the smallest of
+/// `u8`/`u16`/`u32`/`u64` that holds it.
+fn encode_head(major_type: u8, value: u64) -> Vec<u8> {
+    let prefix = major_type << 5;
+    if value < 24 {
+        vec![prefix | value as u8]
+    } else if let Ok(v) = u8::try_from(value) {
+        vec![prefix | 24, v]
+    } else if let Ok(v) = u16::try_from(value) {
+        let mut out = vec![prefix | 25];
+        out.extend(v.to_be_bytes());
+        out
+    } else if let Ok(v) = u32::try_from(value) {
+        let mut out = vec![prefix | 26];
+        out.extend(v.to_be_bytes());
+        out
+    } else {
+        let mut out = vec![prefix | 27];
+        out.extend(value.to_be_bytes());
+        out
+    }
+}
+
+/// Encodes an integer as major type 0 (unsigned) for `i >= 0` or major type
+/// 1 (negative, stored as `-1 - n`) for `i < 0`. `i128` covers `u64`
+/// magnitudes that overflow `i64`, so a large unsigned value (e.g. a `u64`
+/// above `i64::MAX`) still takes the unsigned major type rather than being
+/// forced through a lossy float.
+fn encode_integer(i: i128) -> Vec<u8> {
+    if i >= 0 {
+        encode_head(0, i as u64)
+    } else {
+        encode_head(1, (-1 - i) as u64)
+    }
+}
+
+/// Encodes `f` in the shortest IEEE-754 width that round-trips exactly:
+/// `f32` if the value survives the narrowing and widening, otherwise the
+/// full `f64`. See the module docs for why `f16` isn't attempted.
+fn encode_float(f: f64) -> Vec<u8> {
+    let narrowed = f as f32;
+    if f.is_nan() || (narrowed as f64).to_bits() == f.to_bits() {
+        let mut out = vec![0xfa];
+        out.extend(narrowed.to_be_bytes());
+        out
+    } else {
+        let mut out = vec![0xfb];
+        out.extend(f.to_be_bytes());
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn small_integers_encode_as_a_single_byte() {
+        assert_eq!(to_canonical_cbor_bytes(&CborValue::Integer(0)), vec![0x00]);
+        assert_eq!(to_canonical_cbor_bytes(&CborValue::Integer(23)), vec![0x17]);
+    }
+
+    #[test]
+    fn integers_pick_the_shortest_additional_info_width() {
+        assert_eq!(
+            to_canonical_cbor_bytes(&CborValue::Integer(24)),
+            vec![0x18, 24]
+        );
+        assert_eq!(
+            to_canonical_cbor_bytes(&CborValue::Integer(256)),
+            vec![0x19, 0x01, 0x00]
+        );
+    }
+
+    #[test]
+    fn u64_above_i64_max_encodes_as_unsigned_major_type_zero() {
+        let value = u64::MAX as i128;
+        let bytes = to_canonical_cbor_bytes(&CborValue::Integer(value));
+        assert_eq!(bytes[0] >> 5, 0);
+        assert_eq!(&bytes[1..], &u64::MAX.to_be_bytes());
+    }
+
+    #[test]
+    fn negative_integers_use_major_type_one() {
+        let bytes = to_canonical_cbor_bytes(&CborValue::Integer(-1));
+        assert_eq!(bytes[0] >> 5, 1);
+        assert_eq!(bytes, vec![0x20]);
+    }
+
+    #[test]
+    fn floats_prefer_f32_when_it_round_trips() {
+        let bytes = to_canonical_cbor_bytes(&CborValue::Float(1.5));
+        assert_eq!(bytes[0], 0xfa);
+        assert_eq!(bytes.len(), 5);
+    }
+
+    #[test]
+    fn floats_fall_back_to_f64_when_narrowing_loses_precision() {
+        let value = 1.0 / 3.0;
+        let bytes = to_canonical_cbor_bytes(&CborValue::Float(value));
+        assert_eq!(bytes[0], 0xfb);
+        assert_eq!(bytes.len(), 9);
+    }
+
+    #[test]
+    fn map_keys_sort_by_encoded_bytes_not_by_value_order() {
+        let mut map = BTreeMap::new();
+        map.insert(CborValue::Text("bb".to_string()), CborValue::Integer(1));
+        map.insert(CborValue::Text("a".to_string()), CborValue::Integer(2));
+        let encoded = to_canonical_cbor_bytes(&CborValue::Map(map));
+
+        let mut expected = encode_head(5, 2);
+        expected.extend(to_canonical_cbor_bytes(&CborValue::Text("a".to_string())));
+        expected.extend(to_canonical_cbor_bytes(&CborValue::Integer(2)));
+        expected.extend(to_canonical_cbor_bytes(&CborValue::Text("bb".to_string())));
+        expected.extend(to_canonical_cbor_bytes(&CborValue::Integer(1)));
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn identical_documents_with_different_field_order_produce_identical_bytes() {
+        let mut a = BTreeMap::new();
+        a.insert(CborValue::Text("x".to_string()), CborValue::Integer(1));
+        a.insert(CborValue::Text("y".to_string()), CborValue::Integer(2));
+
+        let mut b = BTreeMap::new();
+        b.insert(CborValue::Text("y".to_string()), CborValue::Integer(2));
+        b.insert(CborValue::Text("x".to_string()), CborValue::Integer(1));
+
+        assert_eq!(
+            to_canonical_cbor_bytes(&CborValue::Map(a)),
+            to_canonical_cbor_bytes(&CborValue::Map(b))
+        );
+    }
+
+    #[test]
+    fn arrays_use_definite_length_encoding() {
+        let array = CborValue::Array(vec![CborValue::Integer(1), CborValue::Integer(2)]);
+        let bytes = to_canonical_cbor_bytes(&array);
+        assert_eq!(bytes, vec![0x82, 0x01, 0x02]);
+    }
+}
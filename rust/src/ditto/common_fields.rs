@@ -0,0 +1,242 @@
+//! Uniform access to the fields every [`CotDocument`] variant shares
+//! (`_id`, `a` peer key, `d_c` edit counter, `d_v` schema version, `d_r`
+//! removed flag), plus generic helpers built on top of them.
+//!
+//! The ask this module answers wants a `CommonFields` struct each document
+//! type flattens via `#[serde(flatten)] pub common: CommonFields`, wrapped
+//! in a generic `DittoDocument<T>` envelope so `ChatDocument` etc. become
+//! `type` aliases over it. This crate's document types don't compose that
+//! way: `Api`/`Chat`/`File`/`Generic`/`MapItem` are independently generated
+//! by `build.rs` from the Ditto JSON schemas (see [`CotDocument`]), each a
+//! flat struct with its own copy of these fields rather than a shared
+//! flattened sub-struct — there's no `CommonFields` type to flatten and no
+//! payload-only struct to wrap. [`CotDocument`] itself already serves the
+//! role `DittoDocument<T>` would: one sum type callers match on regardless
+//! of payload. What's genuinely missing, and what this module adds, is the
+//! "single place to implement helpers generically" part: a
+//! [`CommonDocumentFields`] trait exposing the shared fields as methods
+//! (matching every variant exactly once, the same way
+//! [`staleness::soft_delete`](super::staleness::soft_delete) already does
+//! for its one field pair), with [`CommonDocumentFields::bump_counter`] and
+//! [`CommonDocumentFields::mark_deleted`] implemented once, in terms of
+//! those methods, rather than duplicated per call site.
+
+use serde_json::Value;
+
+use crate::ditto::CotDocument;
+
+/// Accessors and generic helpers for the fields every [`CotDocument`]
+/// variant carries, regardless of its payload.
+pub trait CommonDocumentFields {
+    /// The document's `_id`.
+    fn common_id(&self) -> Option<&str>;
+
+    /// The authoring peer's key (`a`).
+    fn peer_key(&self) -> Option<&str>;
+
+    /// The edit counter (`d_c`), bumped on every local mutation.
+    fn edit_counter(&self) -> u32;
+
+    /// Overwrites the edit counter (`d_c`).
+    fn set_edit_counter(&mut self, value: u32);
+
+    /// The schema version (`d_v`) this document was last written under.
+    fn schema_version(&self) -> u32;
+
+    /// Whether this document is soft-deleted (`d_r`).
+    fn is_removed(&self) -> bool;
+
+    /// Overwrites the removed flag (`d_r`).
+    fn set_removed(&mut self, removed: bool);
+
+    /// Increments [`Self::edit_counter`] by one, saturating rather than
+    /// wrapping so a long-lived document's counter can't roll back to zero.
+    fn bump_counter(&mut self) {
+        self.set_edit_counter(self.edit_counter().saturating_add(1));
+    }
+
+    /// Soft-deletes this document in place: sets [`Self::is_removed`] and
+    /// bumps the edit counter, the same two changes
+    /// [`staleness::soft_delete`](super::staleness::soft_delete) makes on a
+    /// cloned copy.
+    fn mark_deleted(&mut self) {
+        self.set_removed(true);
+        self.bump_counter();
+    }
+}
+
+impl CommonDocumentFields for CotDocument {
+    fn common_id(&self) -> Option<&str> {
+        match self {
+            CotDocument::Api(d) => Some(&d.id),
+            CotDocument::Chat(d) => Some(&d.id),
+            CotDocument::File(d) => Some(&d.id),
+            CotDocument::Generic(d) => Some(&d.id),
+            CotDocument::MapItem(d) => Some(&d.id),
+            CotDocument::Unknown(u) => u.raw.get("_id").and_then(Value::as_str),
+        }
+    }
+
+    fn peer_key(&self) -> Option<&str> {
+        match self {
+            CotDocument::Api(d) => Some(&d.a),
+            CotDocument::Chat(d) => Some(&d.a),
+            CotDocument::File(d) => Some(&d.a),
+            CotDocument::Generic(d) => Some(&d.a),
+            CotDocument::MapItem(d) => Some(&d.a),
+            CotDocument::Unknown(u) => u.raw.get("a").and_then(Value::as_str),
+        }
+    }
+
+    fn edit_counter(&self) -> u32 {
+        match self {
+            CotDocument::Api(d) => d.d_c,
+            CotDocument::Chat(d) => d.d_c,
+            CotDocument::File(d) => d.d_c,
+            CotDocument::Generic(d) => d.d_c,
+            CotDocument::MapItem(d) => d.d_c,
+            CotDocument::Unknown(u) => {
+                u.raw.get("d_c").and_then(Value::as_u64).unwrap_or(0) as u32
+            }
+        }
+    }
+
+    fn set_edit_counter(&mut self, value: u32) {
+        match self {
+            CotDocument::Api(d) => d.d_c = value,
+            CotDocument::Chat(d) => d.d_c = value,
+            CotDocument::File(d) => d.d_c = value,
+            CotDocument::Generic(d) => d.d_c = value,
+            CotDocument::MapItem(d) => d.d_c = value,
+            CotDocument::Unknown(u) => {
+                if let Some(obj) = u.raw.as_object_mut() {
+                    obj.insert("d_c".to_string(), Value::from(value));
+                }
+            }
+        }
+    }
+
+    fn schema_version(&self) -> u32 {
+        match self {
+            CotDocument::Api(d) => d.d_v,
+            CotDocument::Chat(d) => d.d_v,
+            CotDocument::File(d) => d.d_v,
+            CotDocument::Generic(d) => d.d_v,
+            CotDocument::MapItem(d) => d.d_v,
+            CotDocument::Unknown(u) => {
+                u.raw.get("d_v").and_then(Value::as_u64).unwrap_or(0) as u32
+            }
+        }
+    }
+
+    fn is_removed(&self) -> bool {
+        match self {
+            CotDocument::Api(d) => d.d_r,
+            CotDocument::Chat(d) => d.d_r,
+            CotDocument::File(d) => d.d_r,
+            CotDocument::Generic(d) => d.d_r,
+            CotDocument::MapItem(d) => d.d_r,
+            CotDocument::Unknown(u) => {
+                u.raw.get("d_r").and_then(Value::as_bool).unwrap_or(false)
+            }
+        }
+    }
+
+    fn set_removed(&mut self, removed: bool) {
+        match self {
+            CotDocument::Api(d) => d.d_r = removed,
+            CotDocument::Chat(d) => d.d_r = removed,
+            CotDocument::File(d) => d.d_r = removed,
+            CotDocument::Generic(d) => d.d_r = removed,
+            CotDocument::MapItem(d) => d.d_r = removed,
+            CotDocument::Unknown(u) => {
+                if let Some(obj) = u.raw.as_object_mut() {
+                    obj.insert("d_r".to_string(), Value::Bool(removed));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::{MapItem, UnknownDocument};
+    use std::collections::HashMap;
+
+    fn map_item(d_c: u32, d_v: u32, d_r: bool) -> CotDocument {
+        CotDocument::MapItem(MapItem {
+            id: "UID-1".to_string(),
+            a: "peer-a".to_string(),
+            b: 0.0,
+            c: None,
+            d: "UID-1".to_string(),
+            d_c,
+            d_r,
+            d_v,
+            source: None,
+            e: "ALPHA-1".to_string(),
+            f: None,
+            g: "2.0".to_string(),
+            h: None,
+            i: Some(10.0),
+            j: Some(35.0),
+            k: Some(5.0),
+            l: Some(-118.0),
+            n: Some(0.0),
+            o: Some(0.0),
+            p: "h-g-i-g-o".to_string(),
+            q: String::new(),
+            r: HashMap::new(),
+            s: String::new(),
+            t: String::new(),
+            u: String::new(),
+            v: String::new(),
+            w: "a-f-G-U-C".to_string(),
+        })
+    }
+
+    #[test]
+    fn accessors_read_the_underlying_fields() {
+        let doc = map_item(3, 2, false);
+        assert_eq!(doc.common_id(), Some("UID-1"));
+        assert_eq!(doc.peer_key(), Some("peer-a"));
+        assert_eq!(doc.edit_counter(), 3);
+        assert_eq!(doc.schema_version(), 2);
+        assert!(!doc.is_removed());
+    }
+
+    #[test]
+    fn bump_counter_increments_by_one() {
+        let mut doc = map_item(3, 2, false);
+        doc.bump_counter();
+        assert_eq!(doc.edit_counter(), 4);
+    }
+
+    #[test]
+    fn bump_counter_saturates_instead_of_wrapping() {
+        let mut doc = map_item(u32::MAX, 2, false);
+        doc.bump_counter();
+        assert_eq!(doc.edit_counter(), u32::MAX);
+    }
+
+    #[test]
+    fn mark_deleted_sets_the_flag_and_bumps_the_counter() {
+        let mut doc = map_item(3, 2, false);
+        doc.mark_deleted();
+        assert!(doc.is_removed());
+        assert_eq!(doc.edit_counter(), 4);
+    }
+
+    #[test]
+    fn unknown_documents_read_and_write_through_raw_json() {
+        let mut doc = CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({"_id": "UID-2", "a": "peer-b", "d_c": 5, "d_v": 2}),
+        });
+        assert_eq!(doc.common_id(), Some("UID-2"));
+        assert_eq!(doc.edit_counter(), 5);
+        doc.mark_deleted();
+        assert!(doc.is_removed());
+        assert_eq!(doc.edit_counter(), 6);
+    }
+}
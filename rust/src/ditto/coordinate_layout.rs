@@ -0,0 +1,226 @@
+//! Explicit, overridable coordinate field mapping for the flattened-JSON
+//! inverse conversion, driven by the same event-type tables
+//! [`transformer`](super::transformer) uses to pick a [`CotDocument`]
+//! variant on the forward path.
+//!
+//! [`cot_event_from_flattened_json`](super::from_ditto::cot_event_from_flattened_json)
+//! used to decide which short keys hold lat/lon/hae by hand-rolled
+//! `is_map_item`/`is_file` substring checks against a duplicated, incomplete
+//! copy of [`transformer`](super::transformer)'s match patterns — missing
+//! `a-f-S-C-U` and `a-f-A-M-F-Q`, for instance, so those MapItem subtypes
+//! silently fell through to the "other documents" coordinate mapping with no
+//! error. [`classify_event_type`] replaces that duplicated list with the
+//! exact [`transformer`](super::transformer) constants, so the two paths
+//! can't drift apart again, and [`CoordinateLayoutRegistry`] makes the
+//! resulting field mapping an explicit, inspectable, overridable table
+//! instead of inline `if`/`else` branches.
+
+use std::collections::HashMap;
+
+use crate::ditto::transformer::{
+    CHAT_EVENT_TYPE_MARKERS, EMERGENCY_EVENT_TYPE, FILE_EVENT_TYPE_MARKERS,
+    LOCATION_EVENT_TYPE_MARKERS,
+};
+
+/// Which [`CotDocument`](super::CotDocument) variant an event type dispatches
+/// to on the forward ([`transformer`](super::transformer)) path. Mirrors
+/// [`CotDocument`](super::CotDocument)'s variants except
+/// [`CotDocument::Unknown`](super::CotDocument::Unknown), which has no event
+/// type to classify.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DocumentKind {
+    /// Matches [`EMERGENCY_EVENT_TYPE`](super::transformer::EMERGENCY_EVENT_TYPE).
+    Api,
+    /// Matches one of [`CHAT_EVENT_TYPE_MARKERS`](super::transformer::CHAT_EVENT_TYPE_MARKERS).
+    Chat,
+    /// Matches one of
+    /// [`LOCATION_EVENT_TYPE_MARKERS`](super::transformer::LOCATION_EVENT_TYPE_MARKERS).
+    MapItem,
+    /// Matches one of [`FILE_EVENT_TYPE_MARKERS`](super::transformer::FILE_EVENT_TYPE_MARKERS).
+    File,
+    /// Matches none of the above — the same catch-all
+    /// [`transformer::GenericTransformer`](super::transformer) always
+    /// accepts.
+    Generic,
+}
+
+/// Classifies `event_type` the same way
+/// [`TransformerRegistry::with_builtins`](super::transformer::TransformerRegistry::with_builtins)'s
+/// first-match-wins dispatch does, so a flattened document's coordinate
+/// layout can never disagree with which [`CotDocument`](super::CotDocument)
+/// variant the forward conversion would have produced for the same event
+/// type. Always returns a [`DocumentKind`] — [`DocumentKind::Generic`] is
+/// itself a meaningful classification, not an error case; see
+/// [`CoordinateLayoutRegistry::layout_for`] for where "no layout registered
+/// for this kind" is actually surfaced.
+pub fn classify_event_type(event_type: &str) -> DocumentKind {
+    if event_type == EMERGENCY_EVENT_TYPE {
+        DocumentKind::Api
+    } else if CHAT_EVENT_TYPE_MARKERS.iter().any(|marker| event_type.contains(marker)) {
+        DocumentKind::Chat
+    } else if LOCATION_EVENT_TYPE_MARKERS.iter().any(|marker| event_type.contains(marker)) {
+        DocumentKind::MapItem
+    } else if FILE_EVENT_TYPE_MARKERS.iter().any(|marker| event_type.contains(marker)) {
+        DocumentKind::File
+    } else {
+        DocumentKind::Generic
+    }
+}
+
+/// Where a flattened document's CE (circular error) value is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CeSource {
+    /// A top-level flattened field, by short key (e.g. `"h"`).
+    TopLevelField(&'static str),
+    /// A key inside the unflattened `r` detail map (e.g. `"_ce"`).
+    DetailField(&'static str),
+}
+
+/// Which short keys hold a [`DocumentKind`]'s lat/lon/hae/ce fields on the
+/// flattened document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoordinateLayout {
+    /// Top-level flattened field holding latitude.
+    pub lat_field: &'static str,
+    /// Top-level flattened field holding longitude.
+    pub lon_field: &'static str,
+    /// Top-level flattened field holding height above the ellipsoid.
+    pub hae_field: &'static str,
+    /// Where CE is read from.
+    pub ce_source: CeSource,
+}
+
+/// A registered, overridable map from [`DocumentKind`] to [`CoordinateLayout`].
+///
+/// [`Self::with_builtins`] reproduces the mapping
+/// [`cot_event_from_flattened_json`](super::from_ditto::cot_event_from_flattened_json)
+/// used before this module existed: `j`/`l`/`i` for [`DocumentKind::MapItem`],
+/// `h`/`i`/`j` for everything else, with [`DocumentKind::File`]'s CE read
+/// from the `_ce` detail field instead of the `h` top-level field every other
+/// kind uses.
+pub struct CoordinateLayoutRegistry {
+    layouts: HashMap<DocumentKind, CoordinateLayout>,
+}
+
+impl Default for CoordinateLayoutRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl CoordinateLayoutRegistry {
+    /// Creates an empty registry with no layouts registered.
+    pub fn new() -> Self {
+        Self {
+            layouts: HashMap::new(),
+        }
+    }
+
+    /// Creates a registry pre-loaded with the built-in layout for every
+    /// [`DocumentKind`].
+    pub fn with_builtins() -> Self {
+        let other = CoordinateLayout {
+            lat_field: "h",
+            lon_field: "i",
+            hae_field: "j",
+            ce_source: CeSource::TopLevelField("h"),
+        };
+
+        let mut registry = Self::new();
+        registry.register(DocumentKind::Api, other);
+        registry.register(DocumentKind::Chat, other);
+        registry.register(
+            DocumentKind::MapItem,
+            CoordinateLayout {
+                lat_field: "j",
+                lon_field: "l",
+                hae_field: "i",
+                ce_source: CeSource::TopLevelField("h"),
+            },
+        );
+        registry.register(
+            DocumentKind::File,
+            CoordinateLayout {
+                ce_source: CeSource::DetailField("_ce"),
+                ..other
+            },
+        );
+        registry.register(DocumentKind::Generic, other);
+        registry
+    }
+
+    /// Registers (or overrides) the layout for `kind`.
+    pub fn register(&mut self, kind: DocumentKind, layout: CoordinateLayout) {
+        self.layouts.insert(kind, layout);
+    }
+
+    /// Looks up the registered layout for `kind`, if any. `None` is the
+    /// "unknown layout" signal a caller should treat as a hard failure
+    /// rather than guessing a default mapping — with [`Self::with_builtins`]
+    /// every [`DocumentKind`] is registered, so this only fires for a
+    /// registry a caller built with [`Self::new`] and didn't finish
+    /// populating.
+    pub fn layout_for(&self, kind: DocumentKind) -> Option<&CoordinateLayout> {
+        self.layouts.get(&kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_known_event_types() {
+        assert_eq!(classify_event_type("a-u-emergency-g"), DocumentKind::Api);
+        assert_eq!(classify_event_type("b-t-f"), DocumentKind::Chat);
+        assert_eq!(classify_event_type("a-f-G-U-C"), DocumentKind::MapItem);
+        assert_eq!(classify_event_type("a-f-S-C-U"), DocumentKind::MapItem);
+        assert_eq!(classify_event_type("a-f-A-M-F-Q"), DocumentKind::MapItem);
+        assert_eq!(classify_event_type("b-f-t-file"), DocumentKind::File);
+        assert_eq!(classify_event_type("x-custom-y"), DocumentKind::Generic);
+    }
+
+    #[test]
+    fn with_builtins_registers_every_kind() {
+        let registry = CoordinateLayoutRegistry::with_builtins();
+        for kind in [
+            DocumentKind::Api,
+            DocumentKind::Chat,
+            DocumentKind::MapItem,
+            DocumentKind::File,
+            DocumentKind::Generic,
+        ] {
+            assert!(registry.layout_for(kind).is_some());
+        }
+    }
+
+    #[test]
+    fn file_layout_reads_ce_from_the_detail_field() {
+        let registry = CoordinateLayoutRegistry::with_builtins();
+        let layout = registry.layout_for(DocumentKind::File).unwrap();
+        assert_eq!(layout.ce_source, CeSource::DetailField("_ce"));
+        assert_eq!(layout.lat_field, "h");
+    }
+
+    #[test]
+    fn empty_registry_reports_an_unknown_layout() {
+        let registry = CoordinateLayoutRegistry::new();
+        assert_eq!(registry.layout_for(DocumentKind::MapItem), None);
+    }
+
+    #[test]
+    fn a_caller_can_override_a_builtin_layout() {
+        let mut registry = CoordinateLayoutRegistry::with_builtins();
+        registry.register(
+            DocumentKind::Generic,
+            CoordinateLayout {
+                lat_field: "custom_lat",
+                lon_field: "custom_lon",
+                hae_field: "custom_hae",
+                ce_source: CeSource::TopLevelField("custom_ce"),
+            },
+        );
+        let layout = registry.layout_for(DocumentKind::Generic).unwrap();
+        assert_eq!(layout.lat_field, "custom_lat");
+    }
+}
@@ -0,0 +1,583 @@
+//! DQL-style `WHERE`-clause filter evaluator for an already-materialized
+//! [`CotDocument`], for callers filtering a batch already held in memory or
+//! mirroring a live subscription's predicate locally — as opposed to
+//! [`Filter`](super::filter::Filter), which *compiles* a typed AST to a DQL
+//! string for a live Ditto query.
+//!
+//! [`CotFilter::parse`] parses a small boolean expression grammar:
+//! - Comparisons: `field = value`, `!=`, `>`, `>=`, `<`, `<=`
+//! - `field IN [value, value, ...]`
+//! - `EXISTS field` / `NOT EXISTS field`
+//! - `AND`, `OR`, `NOT`, with parenthesized grouping and the usual
+//!   precedence (`NOT` binds tightest, then `AND`, then `OR`)
+//! - String, number, boolean, and `null` literals
+//!
+//! [`CotFilter::matches`] evaluates the parsed AST against a document by
+//! reading each field through [`DittoDocument::get`]'s dotted-path
+//! resolution — the same path syntax (`detail.contact.callsign`, `r.speed`)
+//! every other accessor in this crate already uses, so a field path that
+//! works in one place works here too. A path that doesn't resolve makes a
+//! comparison or `IN` clause false, but makes `NOT EXISTS` true — mirroring
+//! SQL `NULL`-handling rather than treating a missing field as an error.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use dittolive_ditto::store::query_builder::DittoDocument;
+use serde_json::Value as JsonValue;
+
+use crate::ditto::CotDocument;
+
+/// A comparison operator in a [`CotFilter::Condition`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `=`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+}
+
+/// A literal on the right-hand side of a comparison or inside an `IN` list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    /// A string literal.
+    Str(String),
+    /// A numeric literal.
+    Number(f64),
+    /// A boolean literal.
+    Bool(bool),
+    /// `null`.
+    Null,
+}
+
+impl Literal {
+    fn as_json(&self) -> JsonValue {
+        match self {
+            Literal::Str(s) => JsonValue::String(s.clone()),
+            Literal::Number(n) => serde_json::Number::from_f64(*n)
+                .map(JsonValue::Number)
+                .unwrap_or(JsonValue::Null),
+            Literal::Bool(b) => JsonValue::Bool(*b),
+            Literal::Null => JsonValue::Null,
+        }
+    }
+
+    fn compare(&self, op: CompareOp, actual: &JsonValue) -> bool {
+        match op {
+            CompareOp::Eq => *actual == self.as_json(),
+            CompareOp::Ne => *actual != self.as_json(),
+            CompareOp::Gt | CompareOp::Ge | CompareOp::Lt | CompareOp::Le => {
+                let Some(ordering) = self.partial_cmp_json(actual) else {
+                    return false;
+                };
+                match op {
+                    CompareOp::Gt => ordering.is_gt(),
+                    CompareOp::Ge => ordering.is_ge(),
+                    CompareOp::Lt => ordering.is_lt(),
+                    CompareOp::Le => ordering.is_le(),
+                    CompareOp::Eq | CompareOp::Ne => unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// Orders `actual` against this literal: numbers compare numerically
+    /// (even across int/float JSON representations), strings lexically.
+    /// Any other pairing (a string against a number, a bool, etc.) has no
+    /// ordering.
+    fn partial_cmp_json(&self, actual: &JsonValue) -> Option<std::cmp::Ordering> {
+        match self {
+            Literal::Number(b) => actual.as_f64()?.partial_cmp(b),
+            Literal::Str(b) => actual.as_str()?.partial_cmp(b.as_str()),
+            Literal::Bool(_) | Literal::Null => None,
+        }
+    }
+}
+
+/// A parsed filter expression, built directly or via [`CotFilter::parse`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CotFilter {
+    /// `path <op> value`
+    Condition {
+        /// A [`DittoDocument::get`]-compatible dotted field path.
+        path: String,
+        /// The comparison operator.
+        op: CompareOp,
+        /// The value to compare against.
+        value: Literal,
+    },
+    /// `path IN [values...]`
+    In {
+        /// A [`DittoDocument::get`]-compatible dotted field path.
+        path: String,
+        /// The candidate values; matches if the field equals any of them.
+        values: Vec<Literal>,
+    },
+    /// `EXISTS path`
+    Exists {
+        /// A [`DittoDocument::get`]-compatible dotted field path.
+        path: String,
+    },
+    /// `(left AND right)`
+    And(Box<CotFilter>, Box<CotFilter>),
+    /// `(left OR right)`
+    Or(Box<CotFilter>, Box<CotFilter>),
+    /// `NOT (inner)`
+    Not(Box<CotFilter>),
+}
+
+/// Error returned by [`CotFilter::parse`] when the input isn't a
+/// well-formed filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CotFilterParseError {
+    /// The input (or a parenthesized group within it) was empty.
+    Empty,
+    /// A token was expected but not found, or didn't match what the grammar
+    /// allows at that position.
+    UnexpectedToken(String),
+    /// The input ended mid-expression (e.g. an unclosed `(` or `[`).
+    UnexpectedEnd,
+}
+
+impl fmt::Display for CotFilterParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CotFilterParseError::Empty => write!(f, "filter expression is empty"),
+            CotFilterParseError::UnexpectedToken(t) => write!(f, "unexpected token: {t:?}"),
+            CotFilterParseError::UnexpectedEnd => write!(f, "unexpected end of filter expression"),
+        }
+    }
+}
+
+impl std::error::Error for CotFilterParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Path(String),
+    Literal(Literal),
+    Op(CompareOp),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    And,
+    Or,
+    Not,
+    In,
+    Exists,
+}
+
+struct Tokenizer<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>, CotFilterParseError> {
+        let mut tokens = Vec::new();
+        while let Some(&(start, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else if c == '(' {
+                self.chars.next();
+                tokens.push(Token::LParen);
+            } else if c == ')' {
+                self.chars.next();
+                tokens.push(Token::RParen);
+            } else if c == '[' {
+                self.chars.next();
+                tokens.push(Token::LBracket);
+            } else if c == ']' {
+                self.chars.next();
+                tokens.push(Token::RBracket);
+            } else if c == ',' {
+                self.chars.next();
+                tokens.push(Token::Comma);
+            } else if c == '\'' || c == '"' {
+                tokens.push(Token::Literal(Literal::Str(self.read_quoted(c)?)));
+            } else if c == '>' || c == '<' || c == '=' || c == '!' {
+                tokens.push(Token::Op(self.read_operator()?));
+            } else if c.is_ascii_digit() || (c == '-' && self.peek_is_digit_after_minus()) {
+                tokens.push(Token::Literal(self.read_number(start)));
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                tokens.push(self.read_word(start));
+            } else {
+                return Err(CotFilterParseError::UnexpectedToken(c.to_string()));
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn peek_is_digit_after_minus(&self) -> bool {
+        let mut ahead = self.chars.clone();
+        ahead.next();
+        ahead.peek().is_some_and(|(_, c)| c.is_ascii_digit())
+    }
+
+    fn read_quoted(&mut self, quote: char) -> Result<String, CotFilterParseError> {
+        self.chars.next();
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(out),
+                Some((_, c)) => out.push(c),
+                None => return Err(CotFilterParseError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn read_operator(&mut self) -> Result<CompareOp, CotFilterParseError> {
+        let (_, first) = self.chars.next().expect("peeked");
+        let second = self.chars.peek().map(|&(_, c)| c);
+        match (first, second) {
+            ('>', Some('=')) => {
+                self.chars.next();
+                Ok(CompareOp::Ge)
+            }
+            ('<', Some('=')) => {
+                self.chars.next();
+                Ok(CompareOp::Le)
+            }
+            ('!', Some('=')) => {
+                self.chars.next();
+                Ok(CompareOp::Ne)
+            }
+            ('>', _) => Ok(CompareOp::Gt),
+            ('<', _) => Ok(CompareOp::Lt),
+            ('=', _) => Ok(CompareOp::Eq),
+            _ => Err(CotFilterParseError::UnexpectedToken(first.to_string())),
+        }
+    }
+
+    fn read_number(&mut self, start: usize) -> Literal {
+        let mut end = start;
+        if self.input[start..].starts_with('-') {
+            self.chars.next();
+            end += 1;
+        }
+        while let Some(&(idx, c)) = self.chars.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                self.chars.next();
+                end = idx + c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        let text = &self.input[start..end];
+        Literal::Number(text.parse().unwrap_or(0.0))
+    }
+
+    fn read_word(&mut self, start: usize) -> Token {
+        let mut end = start;
+        while let Some(&(idx, c)) = self.chars.peek() {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                self.chars.next();
+                end = idx + c.len_utf8();
+            } else {
+                break;
+            }
+        }
+        let word = &self.input[start..end];
+        match word.to_ascii_uppercase().as_str() {
+            "AND" => Token::And,
+            "OR" => Token::Or,
+            "NOT" => Token::Not,
+            "IN" => Token::In,
+            "EXISTS" => Token::Exists,
+            "TRUE" => Token::Literal(Literal::Bool(true)),
+            "FALSE" => Token::Literal(Literal::Bool(false)),
+            "NULL" => Token::Literal(Literal::Null),
+            _ => Token::Path(word.to_string()),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), CotFilterParseError> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            Some(t) => Err(CotFilterParseError::UnexpectedToken(format!("{t:?}"))),
+            None => Err(CotFilterParseError::UnexpectedEnd),
+        }
+    }
+
+    // Grammar, loosest-to-tightest binding: or_expr -> and_expr (OR and_expr)*
+    fn parse_or(&mut self) -> Result<CotFilter, CotFilterParseError> {
+        let mut left = self.parse_and()?;
+        while matches!(self.peek(), Some(Token::Or)) {
+            self.next();
+            let right = self.parse_and()?;
+            left = CotFilter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    // and_expr -> not_expr (AND not_expr)*
+    fn parse_and(&mut self) -> Result<CotFilter, CotFilterParseError> {
+        let mut left = self.parse_not()?;
+        while matches!(self.peek(), Some(Token::And)) {
+            self.next();
+            let right = self.parse_not()?;
+            left = CotFilter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    // not_expr -> NOT not_expr | atom
+    fn parse_not(&mut self) -> Result<CotFilter, CotFilterParseError> {
+        if matches!(self.peek(), Some(Token::Not)) {
+            self.next();
+            let inner = self.parse_not()?;
+            return Ok(CotFilter::Not(Box::new(inner)));
+        }
+        self.parse_atom()
+    }
+
+    // atom -> '(' or_expr ')' | EXISTS path | path IN '[' literal,... ']' | path op literal
+    fn parse_atom(&mut self) -> Result<CotFilter, CotFilterParseError> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let inner = self.parse_or()?;
+                self.expect(&Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Exists) => {
+                let path = self.expect_path()?;
+                Ok(CotFilter::Exists { path })
+            }
+            Some(Token::Path(path)) => {
+                let path = path.clone();
+                match self.next() {
+                    Some(Token::In) => {
+                        self.expect(&Token::LBracket)?;
+                        let values = self.parse_literal_list()?;
+                        self.expect(&Token::RBracket)?;
+                        Ok(CotFilter::In { path, values })
+                    }
+                    Some(Token::Op(op)) => {
+                        let op = *op;
+                        let value = self.expect_literal()?;
+                        Ok(CotFilter::Condition { path, op, value })
+                    }
+                    Some(other) => Err(CotFilterParseError::UnexpectedToken(format!("{other:?}"))),
+                    None => Err(CotFilterParseError::UnexpectedEnd),
+                }
+            }
+            Some(other) => Err(CotFilterParseError::UnexpectedToken(format!("{other:?}"))),
+            None => Err(CotFilterParseError::UnexpectedEnd),
+        }
+    }
+
+    fn parse_literal_list(&mut self) -> Result<Vec<Literal>, CotFilterParseError> {
+        let mut values = vec![self.expect_literal()?];
+        while matches!(self.peek(), Some(Token::Comma)) {
+            self.next();
+            values.push(self.expect_literal()?);
+        }
+        Ok(values)
+    }
+
+    fn expect_path(&mut self) -> Result<String, CotFilterParseError> {
+        match self.next() {
+            Some(Token::Path(path)) => Ok(path.clone()),
+            Some(other) => Err(CotFilterParseError::UnexpectedToken(format!("{other:?}"))),
+            None => Err(CotFilterParseError::UnexpectedEnd),
+        }
+    }
+
+    fn expect_literal(&mut self) -> Result<Literal, CotFilterParseError> {
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(value.clone()),
+            Some(other) => Err(CotFilterParseError::UnexpectedToken(format!("{other:?}"))),
+            None => Err(CotFilterParseError::UnexpectedEnd),
+        }
+    }
+}
+
+impl CotFilter {
+    /// Parses a filter expression. See the module docs for the grammar.
+    pub fn parse(input: &str) -> Result<Self, CotFilterParseError> {
+        let tokens = Tokenizer::new(input).tokenize()?;
+        if tokens.is_empty() {
+            return Err(CotFilterParseError::Empty);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let filter = parser.parse_or()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(CotFilterParseError::UnexpectedToken(format!(
+                "{:?}",
+                parser.tokens[parser.pos]
+            )));
+        }
+        Ok(filter)
+    }
+
+    /// Evaluates this filter against `document`, resolving field paths via
+    /// [`DittoDocument::get`]. A path that doesn't resolve makes a
+    /// comparison or `IN` clause false, and [`CotFilter::Exists`] false
+    /// (so `NOT EXISTS` is true for it), mirroring SQL `NULL`-handling
+    /// rather than erroring.
+    pub fn matches(&self, document: &CotDocument) -> bool {
+        match self {
+            CotFilter::Condition { path, op, value } => {
+                resolve(document, path).is_some_and(|actual| value.compare(*op, &actual))
+            }
+            CotFilter::In { path, values } => resolve(document, path)
+                .is_some_and(|actual| values.iter().any(|v| v.as_json() == actual)),
+            CotFilter::Exists { path } => resolve(document, path).is_some(),
+            CotFilter::And(left, right) => left.matches(document) && right.matches(document),
+            CotFilter::Or(left, right) => left.matches(document) || right.matches(document),
+            CotFilter::Not(inner) => !inner.matches(document),
+        }
+    }
+}
+
+/// Resolves `path` against `document` via [`DittoDocument::get`]. Values
+/// from `r`'s `MapItemRValue` map serialize as plain JSON scalars/arrays/
+/// objects (the enum is untagged), so no separate unwrapping step is needed
+/// beyond the ordinary JSON comparisons [`Literal::compare`] already does.
+fn resolve(document: &CotDocument, path: &str) -> Option<JsonValue> {
+    DittoDocument::get::<JsonValue>(document, path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn r_value(value: JsonValue) -> crate::ditto::schema::MapItemRValue {
+        use crate::ditto::schema::MapItemRValue;
+        match value {
+            JsonValue::String(s) => MapItemRValue::String(s),
+            JsonValue::Number(n) => MapItemRValue::Number(n.as_f64().unwrap_or(0.0)),
+            JsonValue::Bool(b) => MapItemRValue::Boolean(b),
+            JsonValue::Object(o) => MapItemRValue::Object(o),
+            JsonValue::Array(a) => MapItemRValue::Array(a),
+            JsonValue::Null => MapItemRValue::Null,
+        }
+    }
+
+    fn map_item(r: HashMap<String, JsonValue>) -> CotDocument {
+        use crate::ditto::schema::MapItem;
+        CotDocument::MapItem(MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: Some("Title".to_string()),
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: 2,
+            e: "Test Item".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: None,
+            k: None,
+            l: None,
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r: r.into_iter().map(|(k, v)| (k, r_value(v))).collect(),
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        })
+    }
+
+    #[test]
+    fn eq_condition_matches_string_field() {
+        let filter = CotFilter::parse("_id = 'test-id-123'").unwrap();
+        let doc = map_item(HashMap::new());
+        assert!(filter.matches(&doc));
+    }
+
+    #[test]
+    fn numeric_comparison_reads_an_r_field() {
+        let mut r = HashMap::new();
+        r.insert("speed".to_string(), JsonValue::from(12.5));
+        let doc = map_item(r);
+        assert!(CotFilter::parse("detail.speed > 10").unwrap().matches(&doc));
+        assert!(!CotFilter::parse("detail.speed > 20").unwrap().matches(&doc));
+    }
+
+    #[test]
+    fn in_clause_matches_any_listed_value() {
+        let mut r = HashMap::new();
+        r.insert("status".to_string(), JsonValue::from("green"));
+        let doc = map_item(r);
+        let filter = CotFilter::parse("detail.status IN ['red', 'green', 'blue']").unwrap();
+        assert!(filter.matches(&doc));
+    }
+
+    #[test]
+    fn exists_and_not_exists_handle_missing_paths() {
+        let doc = map_item(HashMap::new());
+        assert!(!CotFilter::parse("EXISTS detail.missing").unwrap().matches(&doc));
+        assert!(CotFilter::parse("NOT EXISTS detail.missing").unwrap().matches(&doc));
+    }
+
+    #[test]
+    fn and_or_not_with_parens_compose() {
+        let mut r = HashMap::new();
+        r.insert("speed".to_string(), JsonValue::from(5.0));
+        let doc = map_item(r);
+        let filter =
+            CotFilter::parse("(detail.speed > 10 OR detail.speed < 8) AND NOT (_id = 'x')")
+                .unwrap();
+        assert!(filter.matches(&doc));
+    }
+
+    #[test]
+    fn string_comparisons_order_lexicographically() {
+        let mut r = HashMap::new();
+        r.insert("callsign".to_string(), JsonValue::from("BRAVO"));
+        let doc = map_item(r);
+        assert!(CotFilter::parse("detail.callsign > 'ALPHA'").unwrap().matches(&doc));
+        assert!(!CotFilter::parse("detail.callsign > 'CHARLIE'").unwrap().matches(&doc));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_input() {
+        assert_eq!(CotFilter::parse(""), Err(CotFilterParseError::Empty));
+        assert!(CotFilter::parse("detail.speed >").is_err());
+        assert!(CotFilter::parse("detail.speed > 10 AND").is_err());
+    }
+}
@@ -0,0 +1,563 @@
+//! MeiliSearch-style query/filter/facet/highlight pipeline over collections
+//! of flattened CoT documents (the `serde_json::Value` maps
+//! `cot_to_flattened_document` produces).
+//!
+//! [`FilterExpr`] is a typed filter AST, built directly or parsed from a
+//! small expression syntax (`contact.callsign = "PINKY" AND __group.name =
+//! "Blue"`, `point.lat > 10.0`, `type = "b-m-p-*"`). Field resolution:
+//!  - `"type"` reads the flattened document's `w` field.
+//!  - `"point.lat"` / `"point.lon"` / `"point.hae"` are resolved through
+//!    [`CoordinateLayoutRegistry`](super::coordinate_layout::CoordinateLayoutRegistry),
+//!    since the actual flattened key for a coordinate varies by
+//!    [`DocumentKind`](super::coordinate_layout::DocumentKind) (see
+//!    [`coordinate_layout`](super::coordinate_layout)).
+//!  - Any other dotted path (`contact.callsign`, `__group.name`) is resolved
+//!    against the document's unflattened `r` detail map, the same tree
+//!    [`DetailQuery`](crate::detail_query::DetailQuery) walks.
+//!
+//! [`FilterExpr::parse`] supports `AND`/`OR` joining clauses left to right,
+//! with `OR` binding loosest (`a AND b OR c AND d` parses as
+//! `(a AND b) OR (c AND d)`) — there is no parenthesized grouping, a scope
+//! boundary documented rather than worked around. A string value ending in
+//! `*` used with `=` becomes a prefix match instead of an equality check,
+//! covering the `type = "b-m-p-*"` case.
+//!
+//! [`CotQuery`] wraps a [`FilterExpr`] with the rest of a search request:
+//! [`DetailProjection`](super::projection::DetailProjection) as the
+//! `attributes_to_retrieve` projection (reusing the existing detail-root
+//! allow-list rather than inventing a second projection mechanism), a facet
+//! field, `limit`/`offset` pagination, and an optional [`Highlight`] that
+//! wraps a matched free-text term in the document's reconstructed XML with
+//! configurable pre/post tags.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::ditto::coordinate_layout::{classify_event_type, CoordinateLayoutRegistry};
+use crate::ditto::from_ditto::cot_event_from_flattened_json;
+use crate::ditto::projection::{prune_flattened_document, DetailProjection};
+use crate::ditto::r_field_flattening::unflatten_document_r_field;
+
+/// A comparison operator in a [`FilterExpr::Compare`] clause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparator {
+    Eq,
+    NotEq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Comparator {
+    fn eval(self, actual: Option<&Value>, expected: &Value) -> bool {
+        match self {
+            Comparator::Eq => actual == Some(expected),
+            Comparator::NotEq => actual != Some(expected),
+            Comparator::Gt => numeric_cmp(actual, expected).is_some_and(|(a, b)| a > b),
+            Comparator::Gte => numeric_cmp(actual, expected).is_some_and(|(a, b)| a >= b),
+            Comparator::Lt => numeric_cmp(actual, expected).is_some_and(|(a, b)| a < b),
+            Comparator::Lte => numeric_cmp(actual, expected).is_some_and(|(a, b)| a <= b),
+        }
+    }
+}
+
+fn numeric_cmp(actual: Option<&Value>, expected: &Value) -> Option<(f64, f64)> {
+    Some((actual?.as_f64()?, expected.as_f64()?))
+}
+
+/// A typed filter expression over a flattened CoT document. See the module
+/// docs for field resolution rules and [`FilterExpr::parse`]'s syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    /// `field <comparator> value`
+    Compare {
+        field: String,
+        comparator: Comparator,
+        value: Value,
+    },
+    /// `field` starts with `prefix` (from a `field = "prefix*"` clause).
+    Prefix { field: String, prefix: String },
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+}
+
+/// Error returned by [`FilterExpr::parse`] when the input isn't a clause (or
+/// `AND`/`OR`-joined chain of clauses) this parser understands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterParseError {
+    Empty,
+    InvalidClause(String),
+    InvalidValue(String),
+}
+
+impl std::fmt::Display for FilterParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterParseError::Empty => write!(f, "filter expression is empty"),
+            FilterParseError::InvalidClause(s) => write!(f, "invalid filter clause: {s:?}"),
+            FilterParseError::InvalidValue(s) => write!(f, "invalid filter value: {s:?}"),
+        }
+    }
+}
+
+impl std::error::Error for FilterParseError {}
+
+const OPERATORS: &[(&str, Comparator)] = &[
+    (">=", Comparator::Gte),
+    ("<=", Comparator::Lte),
+    ("!=", Comparator::NotEq),
+    ("=", Comparator::Eq),
+    (">", Comparator::Gt),
+    ("<", Comparator::Lt),
+];
+
+fn parse_value(raw: &str) -> Result<Value, FilterParseError> {
+    if let Some(inner) = raw.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Ok(Value::String(inner.to_string()))
+    } else if raw == "true" || raw == "false" {
+        Ok(Value::Bool(raw == "true"))
+    } else if let Ok(n) = raw.parse::<f64>() {
+        Ok(Value::from(n))
+    } else {
+        Err(FilterParseError::InvalidValue(raw.to_string()))
+    }
+}
+
+fn parse_clause(clause: &str) -> Result<FilterExpr, FilterParseError> {
+    for &(token, comparator) in OPERATORS {
+        let Some(idx) = clause.find(token) else {
+            continue;
+        };
+        let field = clause[..idx].trim().to_string();
+        let raw_value = clause[idx + token.len()..].trim();
+        if field.is_empty() {
+            return Err(FilterParseError::InvalidClause(clause.to_string()));
+        }
+        let value = parse_value(raw_value)?;
+        if comparator == Comparator::Eq {
+            if let Value::String(s) = &value {
+                if let Some(prefix) = s.strip_suffix('*') {
+                    return Ok(FilterExpr::Prefix {
+                        field,
+                        prefix: prefix.to_string(),
+                    });
+                }
+            }
+        }
+        return Ok(FilterExpr::Compare {
+            field,
+            comparator,
+            value,
+        });
+    }
+    Err(FilterParseError::InvalidClause(clause.to_string()))
+}
+
+impl FilterExpr {
+    /// Parses a MeiliSearch-style filter expression. See the module docs for
+    /// the supported operators and `AND`/`OR` precedence.
+    pub fn parse(input: &str) -> Result<Self, FilterParseError> {
+        let mut or_expr: Option<FilterExpr> = None;
+        for or_clause in input.split(" OR ") {
+            let mut and_expr: Option<FilterExpr> = None;
+            for clause in or_clause.split(" AND ") {
+                let parsed = parse_clause(clause.trim())?;
+                and_expr = Some(match and_expr {
+                    Some(existing) => FilterExpr::And(Box::new(existing), Box::new(parsed)),
+                    None => parsed,
+                });
+            }
+            let and_expr = and_expr.ok_or(FilterParseError::Empty)?;
+            or_expr = Some(match or_expr {
+                Some(existing) => FilterExpr::Or(Box::new(existing), Box::new(and_expr)),
+                None => and_expr,
+            });
+        }
+        or_expr.ok_or(FilterParseError::Empty)
+    }
+
+    fn eval(&self, flattened: &Map<String, Value>, r_map: &HashMap<String, Value>) -> bool {
+        match self {
+            FilterExpr::Compare {
+                field,
+                comparator,
+                value,
+            } => comparator.eval(resolve_field(field, flattened, r_map).as_ref(), value),
+            FilterExpr::Prefix { field, prefix } => resolve_field(field, flattened, r_map)
+                .as_ref()
+                .and_then(Value::as_str)
+                .is_some_and(|s| s.starts_with(prefix.as_str())),
+            FilterExpr::And(left, right) => {
+                left.eval(flattened, r_map) && right.eval(flattened, r_map)
+            }
+            FilterExpr::Or(left, right) => {
+                left.eval(flattened, r_map) || right.eval(flattened, r_map)
+            }
+        }
+    }
+}
+
+/// Resolves a filter field name to its value in a flattened document, per
+/// the rules in the module docs.
+fn resolve_field(
+    field: &str,
+    flattened: &Map<String, Value>,
+    r_map: &HashMap<String, Value>,
+) -> Option<Value> {
+    match field {
+        "type" => flattened.get("w").cloned(),
+        "point.lat" | "point.lon" | "point.hae" => {
+            let event_type = flattened.get("w").and_then(Value::as_str).unwrap_or("");
+            let kind = classify_event_type(event_type);
+            let layout = CoordinateLayoutRegistry::with_builtins().layout_for(kind).copied()?;
+            let key = match field {
+                "point.lat" => layout.lat_field,
+                "point.lon" => layout.lon_field,
+                _ => layout.hae_field,
+            };
+            flattened.get(key).cloned()
+        }
+        path => {
+            let mut segments = path.split('.');
+            let mut current = r_map.get(segments.next()?)?;
+            for segment in segments {
+                current = current.get(segment)?;
+            }
+            Some(current.clone())
+        }
+    }
+}
+
+/// Wraps a matched free-text term in a document's reconstructed XML with
+/// configurable pre/post tags, the way a search result snippet highlights a
+/// match inside `<remarks>` or a string attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub term: String,
+    pub pre_tag: String,
+    pub post_tag: String,
+}
+
+impl Highlight {
+    /// Creates a highlight config wrapping every occurrence of `term` in
+    /// `pre_tag`/`post_tag` (e.g. `<em>`/`</em>`).
+    pub fn new(
+        term: impl Into<String>,
+        pre_tag: impl Into<String>,
+        post_tag: impl Into<String>,
+    ) -> Self {
+        Self {
+            term: term.into(),
+            pre_tag: pre_tag.into(),
+            post_tag: post_tag.into(),
+        }
+    }
+
+    /// Reconstructs `flattened`'s XML and wraps every occurrence of
+    /// [`Self::term`] in it, returning `None` if the document has no `_id`
+    /// or the term doesn't appear anywhere in the reconstructed XML.
+    fn apply(&self, flattened: &Value) -> Option<(String, String)> {
+        if self.term.is_empty() {
+            return None;
+        }
+        let id = flattened.get("_id")?.as_str()?.to_string();
+        let event = cot_event_from_flattened_json(flattened);
+        let xml = event.to_xml().ok()?;
+        if !xml.contains(self.term.as_str()) {
+            return None;
+        }
+        let wrapped = format!("{}{}{}", self.pre_tag, self.term, self.post_tag);
+        Some((id, xml.replace(self.term.as_str(), &wrapped)))
+    }
+}
+
+/// The result of running a [`CotQuery`]: the paginated, projected page of
+/// matching documents, the total match count before pagination, facet
+/// counts (if [`CotQuery::facet`] was set), and highlighted XML per matched
+/// document id (if [`CotQuery::highlight`] was set and found a match).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryResponse {
+    pub hits: Vec<Value>,
+    pub total_matched: usize,
+    pub facet_counts: Option<HashMap<String, usize>>,
+    pub highlights: HashMap<String, String>,
+}
+
+/// A query over a collection of flattened CoT documents: an optional
+/// [`FilterExpr`], an `attributes_to_retrieve` projection, a facet field,
+/// `limit`/`offset` pagination, and an optional [`Highlight`].
+#[derive(Debug, Clone, Default)]
+pub struct CotQuery {
+    pub filter: Option<FilterExpr>,
+    pub attributes_to_retrieve: Option<DetailProjection>,
+    pub facet: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+    pub highlight: Option<Highlight>,
+}
+
+impl CotQuery {
+    /// Creates an unfiltered, unpaginated query matching every document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter(mut self, filter: FilterExpr) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn attributes_to_retrieve(mut self, projection: DetailProjection) -> Self {
+        self.attributes_to_retrieve = Some(projection);
+        self
+    }
+
+    pub fn facet(mut self, field: impl Into<String>) -> Self {
+        self.facet = Some(field.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn highlight(mut self, highlight: Highlight) -> Self {
+        self.highlight = Some(highlight);
+        self
+    }
+
+    /// Returns whether `flattened` satisfies this query's filter (or `true`
+    /// if there is none).
+    pub fn matches(&self, flattened: &Value) -> bool {
+        let Some(map) = flattened.as_object() else {
+            return false;
+        };
+        let Some(filter) = &self.filter else {
+            return true;
+        };
+        let mut document_map: HashMap<String, Value> = map.clone().into_iter().collect();
+        let r_map = unflatten_document_r_field(&mut document_map);
+        filter.eval(map, &r_map)
+    }
+
+    /// Runs this query against `documents`, applying the filter, facet
+    /// counting, pagination, projection, and highlighting in that order.
+    pub fn run<'a>(&self, documents: impl IntoIterator<Item = &'a Value>) -> QueryResponse {
+        let matched: Vec<&Value> = documents.into_iter().filter(|d| self.matches(d)).collect();
+        let total_matched = matched.len();
+
+        let facet_counts = self.facet.as_ref().map(|field| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for doc in &matched {
+                let Some(map) = doc.as_object() else { continue };
+                let mut document_map: HashMap<String, Value> = map.clone().into_iter().collect();
+                let r_map = unflatten_document_r_field(&mut document_map);
+                if let Some(value) = resolve_field(field, map, &r_map) {
+                    let key = value
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| value.to_string());
+                    *counts.entry(key).or_insert(0) += 1;
+                }
+            }
+            counts
+        });
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let mut hits = Vec::new();
+        let mut highlights = HashMap::new();
+        for doc in matched.into_iter().skip(self.offset).take(limit) {
+            if let Some(highlight) = &self.highlight {
+                if let Some((id, xml)) = highlight.apply(doc) {
+                    highlights.insert(id, xml);
+                }
+            }
+            let mut hit = doc.clone();
+            if let Some(projection) = &self.attributes_to_retrieve {
+                prune_flattened_document(&mut hit, projection);
+            }
+            hits.push(hit);
+        }
+
+        QueryResponse {
+            hits,
+            total_matched,
+            facet_counts,
+            highlights,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::cot_to_flattened_document;
+
+    fn event(uid: &str, event_type: &str, detail: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: event_type.to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point {
+                lat: 35.0,
+                lon: -118.0,
+                hae: 10.0,
+                ce: 5.0,
+                le: 5.0,
+            },
+            detail: detail.to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    fn documents() -> Vec<Value> {
+        vec![
+            cot_to_flattened_document(
+                &event(
+                    "unit-1",
+                    "a-f-G-U-C",
+                    r#"<detail><contact callsign="PINKY"/><__group name="Blue"/>
+                        <remarks>all clear</remarks></detail>"#,
+                ),
+                "peer",
+            ),
+            cot_to_flattened_document(
+                &event(
+                    "unit-2",
+                    "a-f-G-U-C",
+                    r#"<detail><contact callsign="BRAIN"/><__group name="Red"/></detail>"#,
+                ),
+                "peer",
+            ),
+        ]
+    }
+
+    #[test]
+    fn parse_builds_an_and_chain() {
+        let expr =
+            FilterExpr::parse(r#"contact.callsign = "PINKY" AND __group.name = "Blue""#).unwrap();
+        assert!(matches!(expr, FilterExpr::And(_, _)));
+    }
+
+    #[test]
+    fn parse_converts_trailing_star_to_a_prefix_match() {
+        let expr = FilterExpr::parse(r#"type = "b-m-p-*""#).unwrap();
+        assert_eq!(
+            expr,
+            FilterExpr::Prefix {
+                field: "type".to_string(),
+                prefix: "b-m-p-".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_rejects_a_clause_with_no_operator() {
+        assert!(FilterExpr::parse("contact.callsign PINKY").is_err());
+    }
+
+    #[test]
+    fn detail_path_filter_matches_a_nested_attribute() {
+        let docs = documents();
+        let filter = FilterExpr::parse(r#"contact.callsign = "PINKY""#).unwrap();
+        let query = CotQuery::new().filter(filter);
+        let response = query.run(&docs);
+        assert_eq!(response.total_matched, 1);
+        assert_eq!(response.hits[0]["_id"], Value::String("unit-1".to_string()));
+    }
+
+    #[test]
+    fn and_filter_requires_both_clauses() {
+        let docs = documents();
+        let query = CotQuery::new().filter(
+            FilterExpr::parse(r#"contact.callsign = "PINKY" AND __group.name = "Blue""#).unwrap(),
+        );
+        assert_eq!(query.run(&docs).total_matched, 1);
+
+        let query = CotQuery::new().filter(
+            FilterExpr::parse(r#"contact.callsign = "PINKY" AND __group.name = "Red""#).unwrap(),
+        );
+        assert_eq!(query.run(&docs).total_matched, 0);
+    }
+
+    #[test]
+    fn type_prefix_filter_matches_event_type() {
+        let docs = documents();
+        let query = CotQuery::new().filter(FilterExpr::parse(r#"type = "a-f-*""#).unwrap());
+        assert_eq!(query.run(&docs).total_matched, 2);
+    }
+
+    #[test]
+    fn point_lat_filter_resolves_through_the_coordinate_layout() {
+        let docs = documents();
+        let query = CotQuery::new().filter(FilterExpr::parse("point.lat > 30.0").unwrap());
+        assert_eq!(query.run(&docs).total_matched, 2);
+
+        let query = CotQuery::new().filter(FilterExpr::parse("point.lat > 40.0").unwrap());
+        assert_eq!(query.run(&docs).total_matched, 0);
+    }
+
+    #[test]
+    fn facet_counts_distinct_values_of_a_detail_field() {
+        let docs = documents();
+        let query = CotQuery::new().facet("__group.name");
+        let response = query.run(&docs);
+        let counts = response.facet_counts.unwrap();
+        assert_eq!(counts.get("Blue"), Some(&1));
+        assert_eq!(counts.get("Red"), Some(&1));
+    }
+
+    #[test]
+    fn limit_and_offset_paginate_matches() {
+        let docs = documents();
+        let query = CotQuery::new().limit(1).offset(1);
+        let response = query.run(&docs);
+        assert_eq!(response.total_matched, 2);
+        assert_eq!(response.hits.len(), 1);
+    }
+
+    #[test]
+    fn attributes_to_retrieve_prunes_non_allow_listed_detail() {
+        let docs = documents();
+        let query = CotQuery::new()
+            .filter(FilterExpr::parse(r#"contact.callsign = "PINKY""#).unwrap())
+            .attributes_to_retrieve(DetailProjection::new(["contact"]));
+        let response = query.run(&docs);
+        assert!(response.hits[0].get("r_contact_callsign").is_some());
+        assert!(response.hits[0].get("r_%5F%5Fgroup_name").is_none());
+    }
+
+    #[test]
+    fn highlight_wraps_a_matched_term_in_the_reconstructed_xml() {
+        let docs = documents();
+        let query = CotQuery::new()
+            .filter(FilterExpr::parse(r#"contact.callsign = "PINKY""#).unwrap())
+            .highlight(Highlight::new("all clear", "<em>", "</em>"));
+        let response = query.run(&docs);
+        let xml = response.highlights.get("unit-1").unwrap();
+        assert!(xml.contains("<em>all clear</em>"));
+    }
+
+    #[test]
+    fn highlight_finds_nothing_for_an_absent_term() {
+        let docs = documents();
+        let query = CotQuery::new()
+            .filter(FilterExpr::parse(r#"contact.callsign = "PINKY""#).unwrap())
+            .highlight(Highlight::new("nonexistent", "<em>", "</em>"));
+        let response = query.run(&docs);
+        assert!(response.highlights.is_empty());
+    }
+}
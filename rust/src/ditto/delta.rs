@@ -0,0 +1,204 @@
+//! Compact delta encoding between two revisions of a [`CotDocument`], for
+//! bandwidth-constrained (DDIL — denied, disrupted, intermittent, limited)
+//! links where shipping a whole `MapItem` per position update is wasteful.
+//!
+//! [`CotDelta`] mirrors the replication-message shape aquadoggo ships over
+//! the wire: rather than the whole document, it carries only the top-level
+//! fields that changed, the `r` detail entries that were added or modified,
+//! and an explicit list of `r` keys that were removed (so a deletion isn't
+//! indistinguishable from "never sent"). [`CotDocument::diff`] produces one
+//! from two revisions; [`CotDocument::apply_delta`] replays it. Applying the
+//! same delta to the same base document twice is a no-op the second time
+//! (every change is an overwrite, not an increment), so a transport that
+//! redelivers a delta after a dropped ack can't double-apply it.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::ditto::sync_dag::{doc_id, version_fields};
+use crate::ditto::CotDocument;
+use crate::error::DeltaError;
+
+/// A compact description of how one [`CotDocument`] revision differs from
+/// another, produced by [`CotDocument::diff`] and replayed by
+/// [`CotDocument::apply_delta`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CotDelta {
+    /// The id of the document this delta applies to.
+    pub id: String,
+    /// The new revision's `d_v`, carried along so an out-of-order delivery
+    /// can be recognized rather than blindly applied.
+    pub d_v: u32,
+    /// Top-level fields (other than `r`) whose value differs between the old
+    /// and new revision, keyed by their JSON field name.
+    pub changed_fields: Map<String, Value>,
+    /// `r` detail entries present in the new revision that are new or
+    /// changed relative to the old one.
+    pub changed_r_entries: Map<String, Value>,
+    /// `r` detail keys present in the old revision but absent from the new
+    /// one — tracked explicitly so a removal round-trips instead of looking
+    /// identical to "this key was never touched".
+    pub removed_r_keys: Vec<String>,
+}
+
+/// The object view [`CotDocument::diff`] and [`CotDocument::apply_delta`]
+/// both work against: the document's own JSON representation (i.e.
+/// [`CotDocument::to_flattened_json`], despite the name, is a plain
+/// `serde_json::to_value` of the typed struct — `r` stays a nested object
+/// rather than being flattened into `r_*` keys).
+fn as_object(doc: &CotDocument) -> Result<Map<String, Value>, DeltaError> {
+    match doc.to_flattened_json() {
+        Value::Object(obj) => Ok(obj),
+        _ => Err(DeltaError::NotAnObject),
+    }
+}
+
+fn r_map_of(doc_json: &Map<String, Value>) -> Map<String, Value> {
+    doc_json.get("r").and_then(Value::as_object).cloned().unwrap_or_default()
+}
+
+impl CotDocument {
+    /// Computes the [`CotDelta`] that turns `old` into `new`. The two
+    /// documents are expected to share the same `id`; [`Self::apply_delta`]
+    /// is what enforces that, not this function.
+    pub fn diff(old: &CotDocument, new: &CotDocument) -> CotDelta {
+        let old_obj = as_object(old).unwrap_or_default();
+        let new_obj = as_object(new).unwrap_or_default();
+
+        let mut changed_fields = Map::new();
+        for (key, new_value) in &new_obj {
+            if key == "r" {
+                continue;
+            }
+            if old_obj.get(key) != Some(new_value) {
+                changed_fields.insert(key.clone(), new_value.clone());
+            }
+        }
+
+        let (old_r, new_r) = (r_map_of(&old_obj), r_map_of(&new_obj));
+
+        let mut changed_r_entries = Map::new();
+        for (key, new_value) in &new_r {
+            if old_r.get(key) != Some(new_value) {
+                changed_r_entries.insert(key.clone(), new_value.clone());
+            }
+        }
+
+        let removed_r_keys =
+            old_r.keys().filter(|key| !new_r.contains_key(*key)).cloned().collect();
+
+        let (d_v, ..) = version_fields(new);
+        CotDelta { id: doc_id(new), d_v, changed_fields, changed_r_entries, removed_r_keys }
+    }
+
+    /// Applies `delta` to `self`, returning the patched document. `self`
+    /// must be the delta's own `id`; this is idempotent and
+    /// application-order-independent since every change it carries is an
+    /// overwrite rather than an increment.
+    pub fn apply_delta(&self, delta: &CotDelta) -> Result<CotDocument, DeltaError> {
+        let self_id = doc_id(self);
+        if self_id != delta.id {
+            return Err(DeltaError::IdMismatch {
+                delta_id: delta.id.clone(),
+                doc_id: self_id,
+            });
+        }
+
+        let mut patched = as_object(self)?;
+        for (key, value) in &delta.changed_fields {
+            patched.insert(key.clone(), value.clone());
+        }
+
+        let mut r = r_map_of(&patched);
+        for key in &delta.removed_r_keys {
+            r.remove(key);
+        }
+        for (key, value) in &delta.changed_r_entries {
+            r.insert(key.clone(), value.clone());
+        }
+        patched.insert("r".to_string(), Value::Object(r));
+
+        serde_json::from_value(Value::Object(patched))
+            .map_err(|e| DeltaError::Deserialize(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::cot_to_document;
+
+    fn event(uid: &str, lat: f64) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: "a-f-G-U-C".to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point { lat, ..Default::default() },
+            detail: "<detail><contact callsign=\"ALPHA-1\"/></detail>".to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn diff_captures_only_the_fields_that_actually_changed() {
+        let old = cot_to_document(&event("uid-1", 35.0), "peer-a");
+        let new = cot_to_document(&event("uid-1", 36.0), "peer-a");
+
+        let delta = CotDocument::diff(&old, &new);
+        assert!(delta.changed_fields.contains_key("j")); // latitude
+        assert!(!delta.changed_fields.contains_key("g")); // version field, unchanged
+    }
+
+    #[test]
+    fn apply_delta_reproduces_the_new_revision() {
+        let old = cot_to_document(&event("uid-1", 35.0), "peer-a");
+        let new = cot_to_document(&event("uid-1", 36.0), "peer-a");
+
+        let delta = CotDocument::diff(&old, &new);
+        let patched = old.apply_delta(&delta).unwrap();
+        assert_eq!(patched.to_flattened_json(), new.to_flattened_json());
+    }
+
+    #[test]
+    fn apply_delta_is_idempotent() {
+        let old = cot_to_document(&event("uid-1", 35.0), "peer-a");
+        let new = cot_to_document(&event("uid-1", 36.0), "peer-a");
+
+        let delta = CotDocument::diff(&old, &new);
+        let patched_once = old.apply_delta(&delta).unwrap();
+        let patched_twice = patched_once.apply_delta(&delta).unwrap();
+        assert_eq!(patched_once.to_flattened_json(), patched_twice.to_flattened_json());
+    }
+
+    #[test]
+    fn removed_r_keys_are_dropped_rather_than_left_stale() {
+        let old = cot_to_document(&event("uid-1", 35.0), "peer-a");
+        let CotDocument::MapItem(mut new_item) = cot_to_document(&event("uid-1", 35.0), "peer-a")
+        else {
+            unreachable!()
+        };
+        new_item.r.clear();
+        let new = CotDocument::MapItem(new_item);
+
+        let delta = CotDocument::diff(&old, &new);
+        assert!(!delta.removed_r_keys.is_empty());
+
+        let patched = old.apply_delta(&delta).unwrap();
+        let CotDocument::MapItem(patched_item) = patched else { unreachable!() };
+        assert!(patched_item.r.is_empty());
+    }
+
+    #[test]
+    fn mismatched_ids_are_rejected() {
+        let old = cot_to_document(&event("uid-1", 35.0), "peer-a");
+        let new = cot_to_document(&event("uid-2", 35.0), "peer-a");
+
+        let delta = CotDocument::diff(&old, &new);
+        assert!(matches!(old.apply_delta(&delta), Err(DeltaError::IdMismatch { .. })));
+    }
+}
@@ -0,0 +1,249 @@
+//! Ergonomic, error-reporting accessors over detail `r` field maps.
+//!
+//! Every document type's `r` field is a `HashMap<String, XxxRValue>` (e.g.
+//! `MapItemRValue`), where `XxxRValue` is a codegen'd, `#[serde(untagged)]`
+//! enum of `String`/`Number`/`Boolean`/`Null`/`Array`/`Object` variants.
+//! Pulling a value like `contact.callsign` or `emergency` out of one of
+//! these maps otherwise means hand-matching every variant at every call
+//! site and panicking (or silently defaulting) on the wrong shape.
+//! [`DetailAccessor`] replaces that with `get_str`/`get_f64`/`get_bool`/
+//! `get_array`/`get_object`, each returning a [`CotError`] that names the
+//! key and what went wrong, plus [`DetailAccessor::get_nested`] for a
+//! dotted path like `"contact.endpoint"`.
+//!
+//! Implemented once for every `XxxRValue` map via [`AsRValueRef`], which
+//! exposes a borrowed view of an enum's current variant — rather than once
+//! per document type — since every RValue enum this crate generates has
+//! the same shape.
+
+use crate::error::CotError;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// A borrowed view of an `XxxRValue` enum's current variant, letting
+/// [`DetailAccessor`] be implemented generically over every RValue type
+/// instead of once per document type.
+enum RValueRef<'a> {
+    Str(&'a str),
+    Number(f64),
+    Boolean(bool),
+    Null,
+    Array(&'a [Value]),
+    Object(&'a Map<String, Value>),
+}
+
+/// Exposes a borrowed [`RValueRef`] view of an RValue enum's current
+/// variant. Implemented for every `XxxRValue` type the schema codegen
+/// produces (`ApiRValue`, `ChatRValue`, `FileRValue`, `GenericRValue`,
+/// `MapItemRValue`), since they're structurally identical.
+trait AsRValueRef {
+    fn as_rvalue_ref(&self) -> RValueRef<'_>;
+}
+
+macro_rules! impl_as_rvalue_ref {
+    ($ty:ty) => {
+        impl AsRValueRef for $ty {
+            fn as_rvalue_ref(&self) -> RValueRef<'_> {
+                match self {
+                    <$ty>::String(s) => RValueRef::Str(s),
+                    <$ty>::Number(n) => RValueRef::Number(*n),
+                    <$ty>::Boolean(b) => RValueRef::Boolean(*b),
+                    <$ty>::Null => RValueRef::Null,
+                    <$ty>::Array(a) => RValueRef::Array(a),
+                    <$ty>::Object(o) => RValueRef::Object(o),
+                }
+            }
+        }
+    };
+}
+
+impl_as_rvalue_ref!(crate::ditto::schema::ApiRValue);
+impl_as_rvalue_ref!(crate::ditto::schema::ChatRValue);
+impl_as_rvalue_ref!(crate::ditto::schema::FileRValue);
+impl_as_rvalue_ref!(crate::ditto::schema::GenericRValue);
+impl_as_rvalue_ref!(crate::ditto::schema::MapItemRValue);
+
+/// Returns the name of the variant `value` actually holds, for error
+/// messages naming what was found instead of what was expected.
+fn variant_name(value: &RValueRef<'_>) -> &'static str {
+    match value {
+        RValueRef::Str(_) => "string",
+        RValueRef::Number(_) => "number",
+        RValueRef::Boolean(_) => "boolean",
+        RValueRef::Null => "null",
+        RValueRef::Array(_) => "array",
+        RValueRef::Object(_) => "object",
+    }
+}
+
+fn type_mismatch(key: &str, expected: &str, actual: &RValueRef<'_>) -> CotError {
+    CotError::InvalidFormat(format!(
+        "detail key '{key}' is a {}, not a {expected}",
+        variant_name(actual)
+    ))
+}
+
+/// Typed, error-reporting accessors over a detail map's `r` field
+/// (`HashMap<String, XxxRValue>`).
+pub trait DetailAccessor {
+    /// Returns the string at `key`.
+    fn get_str(&self, key: &str) -> Result<&str, CotError>;
+    /// Returns the number at `key`.
+    fn get_f64(&self, key: &str) -> Result<f64, CotError>;
+    /// Returns the boolean at `key`.
+    fn get_bool(&self, key: &str) -> Result<bool, CotError>;
+    /// Returns the array at `key`.
+    fn get_array(&self, key: &str) -> Result<&[Value], CotError>;
+    /// Returns the object at `key`.
+    fn get_object(&self, key: &str) -> Result<&Map<String, Value>, CotError>;
+    /// Resolves a dotted path (e.g. `"contact.endpoint"`) against this map,
+    /// hopping into nested objects one segment at a time. The first segment
+    /// is looked up in this map; every segment after that navigates the
+    /// plain JSON `serde_json::Value` tree beneath it. Returns an owned
+    /// clone, since the path may descend past the first hop's borrow.
+    fn get_nested(&self, path: &str) -> Result<Value, CotError>;
+}
+
+impl<T: AsRValueRef> DetailAccessor for HashMap<String, T> {
+    fn get_str(&self, key: &str) -> Result<&str, CotError> {
+        match self.get(key) {
+            None => Err(CotError::MissingField(key.to_string())),
+            Some(v) => match v.as_rvalue_ref() {
+                RValueRef::Str(s) => Ok(s),
+                other => Err(type_mismatch(key, "string", &other)),
+            },
+        }
+    }
+
+    fn get_f64(&self, key: &str) -> Result<f64, CotError> {
+        match self.get(key) {
+            None => Err(CotError::MissingField(key.to_string())),
+            Some(v) => match v.as_rvalue_ref() {
+                RValueRef::Number(n) => Ok(n),
+                other => Err(type_mismatch(key, "number", &other)),
+            },
+        }
+    }
+
+    fn get_bool(&self, key: &str) -> Result<bool, CotError> {
+        match self.get(key) {
+            None => Err(CotError::MissingField(key.to_string())),
+            Some(v) => match v.as_rvalue_ref() {
+                RValueRef::Boolean(b) => Ok(b),
+                other => Err(type_mismatch(key, "boolean", &other)),
+            },
+        }
+    }
+
+    fn get_array(&self, key: &str) -> Result<&[Value], CotError> {
+        match self.get(key) {
+            None => Err(CotError::MissingField(key.to_string())),
+            Some(v) => match v.as_rvalue_ref() {
+                RValueRef::Array(a) => Ok(a),
+                other => Err(type_mismatch(key, "array", &other)),
+            },
+        }
+    }
+
+    fn get_object(&self, key: &str) -> Result<&Map<String, Value>, CotError> {
+        match self.get(key) {
+            None => Err(CotError::MissingField(key.to_string())),
+            Some(v) => match v.as_rvalue_ref() {
+                RValueRef::Object(o) => Ok(o),
+                other => Err(type_mismatch(key, "object", &other)),
+            },
+        }
+    }
+
+    fn get_nested(&self, path: &str) -> Result<Value, CotError> {
+        let mut segments = path.split('.');
+        let first = segments
+            .next()
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| CotError::InvalidFormat(format!("empty detail path: '{path}'")))?;
+
+        let top = self
+            .get(first)
+            .ok_or_else(|| CotError::MissingField(first.to_string()))?;
+        let mut current: Value = match top.as_rvalue_ref() {
+            RValueRef::Str(s) => Value::String(s.to_string()),
+            RValueRef::Number(n) => serde_json::Number::from_f64(n)
+                .map(Value::Number)
+                .unwrap_or(Value::Null),
+            RValueRef::Boolean(b) => Value::Bool(b),
+            RValueRef::Null => Value::Null,
+            RValueRef::Array(a) => Value::Array(a.to_vec()),
+            RValueRef::Object(o) => Value::Object(o.clone()),
+        };
+
+        for segment in segments {
+            current = current
+                .get(segment)
+                .cloned()
+                .ok_or_else(|| CotError::MissingField(format!("{path} (at '{segment}')")))?;
+        }
+
+        Ok(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::schema::MapItemRValue;
+
+    fn map(pairs: Vec<(&str, MapItemRValue)>) -> HashMap<String, MapItemRValue> {
+        pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+    }
+
+    #[test]
+    fn get_str_returns_the_string() {
+        let r = map(vec![(
+            "callsign",
+            MapItemRValue::String("ALPHA-1".to_string()),
+        )]);
+        assert_eq!(r.get_str("callsign").unwrap(), "ALPHA-1");
+    }
+
+    #[test]
+    fn get_str_on_wrong_type_names_the_actual_type() {
+        let r = map(vec![("battery", MapItemRValue::Number(42.0))]);
+        let err = r.get_str("battery").unwrap_err();
+        assert!(err.to_string().contains("number"));
+    }
+
+    #[test]
+    fn get_str_on_missing_key_names_the_key() {
+        let r: HashMap<String, MapItemRValue> = HashMap::new();
+        let err = r.get_str("missing").unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn get_nested_descends_through_an_object() {
+        let mut contact = serde_json::Map::new();
+        contact.insert(
+            "endpoint".to_string(),
+            Value::String("239.1.1.1:6969".to_string()),
+        );
+        let r = map(vec![("contact", MapItemRValue::Object(contact))]);
+        let endpoint = r.get_nested("contact.endpoint").unwrap();
+        assert_eq!(endpoint, Value::String("239.1.1.1:6969".to_string()));
+    }
+
+    #[test]
+    fn get_nested_single_segment_returns_the_top_level_value() {
+        let r = map(vec![("emergency", MapItemRValue::Boolean(true))]);
+        assert_eq!(r.get_nested("emergency").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn get_nested_missing_intermediate_segment_names_it() {
+        let r = map(vec![(
+            "contact",
+            MapItemRValue::Object(serde_json::Map::new()),
+        )]);
+        let err = r.get_nested("contact.endpoint").unwrap_err();
+        assert!(err.to_string().contains("endpoint"));
+    }
+}
@@ -0,0 +1,235 @@
+//! In-memory structured index over a [`CotDocument`]'s unflattened `r` map.
+//!
+//! [`dql_support`](super::dql_support) lets Ditto's query engine filter on a
+//! document's top-level fields, but nothing indexes the rich detail captured
+//! under `r` (`contact.callsign`, `takv.device`, `status.battery`,
+//! `track.course`, ...) the way a real query engine would.
+//! [`crate::detail_query`] already answers "does this one detail tree match
+//! this path/predicate" — [`DetailIndex`] adds the other half: a keyed,
+//! updatable collection of document ids to run that query against, ingesting
+//! each document's `r` map reconstructed from its flattened form via
+//! [`unflatten_document_r_field`](super::r_field_flattening::unflatten_document_r_field)
+//! so a caller working with already-flattened Ditto rows doesn't have to
+//! hand-parse `r_*` keys. This turns the flattened representation into
+//! something a tactical dashboard can actually search ("show all ATAK-CIV
+//! units with battery < 20 inside this box").
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::detail_query::DetailQuery;
+use crate::ditto::r_field_flattening::unflatten_document_r_field;
+use crate::ditto::CotDocument;
+
+/// Reconstructs `doc`'s `r` map as a nested tree, the way it looked before
+/// [`flatten_document_r_field`](super::r_field_flattening::flatten_document_r_field)
+/// ever ran — i.e. exactly the shape [`DetailQuery`] expects to walk.
+fn nested_r_map(doc: &CotDocument) -> HashMap<String, Value> {
+    let mut document_map: HashMap<String, Value> = match doc.to_flattened_json() {
+        Value::Object(obj) => obj.into_iter().collect(),
+        _ => HashMap::new(),
+    };
+    unflatten_document_r_field(&mut document_map)
+}
+
+/// An in-memory index over documents' unflattened `r` maps, keyed by
+/// document id.
+#[derive(Debug, Clone, Default)]
+pub struct DetailIndex {
+    documents: HashMap<String, HashMap<String, Value>>,
+}
+
+impl DetailIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ingests (or replaces) `doc`'s entry in the index under `doc_id`.
+    pub fn upsert(&mut self, doc_id: impl Into<String>, doc: &CotDocument) {
+        self.documents.insert(doc_id.into(), nested_r_map(doc));
+    }
+
+    /// Removes `doc_id`'s entry from the index, if present.
+    pub fn remove(&mut self, doc_id: &str) {
+        self.documents.remove(doc_id);
+    }
+
+    /// Returns the number of documents currently indexed.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns whether the index has no documents.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Returns ids of indexed documents matching `query`, sorted for
+    /// deterministic output.
+    pub fn query(&self, query: &DetailQuery) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .documents
+            .iter()
+            .filter(|(_, detail)| query.matches(detail))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Returns ids of documents whose values at `lat_path`/`lon_path` both
+    /// fall within the inclusive box `[min_lat, max_lat] x [min_lon, max_lon]`.
+    ///
+    /// Equivalent to intersecting [`Self::query`] on two
+    /// [`DetailQuery::in_range`] queries, one per axis.
+    pub fn query_bounding_box(
+        &self,
+        lat_path: &str,
+        lon_path: &str,
+        min_lat: f64,
+        max_lat: f64,
+        min_lon: f64,
+        max_lon: f64,
+    ) -> Vec<String> {
+        let lat_query = DetailQuery::path(lat_path).in_range(Some(min_lat), Some(max_lat));
+        let lon_query = DetailQuery::path(lon_path).in_range(Some(min_lon), Some(max_lon));
+        self.query(&lat_query)
+            .into_iter()
+            .filter(|id| {
+                self.documents
+                    .get(id)
+                    .is_some_and(|detail| lon_query.matches(detail))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::cot_to_document;
+
+    fn event(uid: &str, detail: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: "a-f-G-U-C".to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::default(),
+            detail: detail.to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    fn index_with_units() -> DetailIndex {
+        let mut index = DetailIndex::new();
+        index.upsert(
+            "unit-1",
+            &cot_to_document(
+                &event(
+                    "unit-1",
+                    r#"<detail>
+                        <contact callsign="GRAY KNIGHT"/>
+                        <takv device="ATAK-CIV"/>
+                        <status battery="15"/>
+                        <track course="90.0" speed="2.0"/>
+                    </detail>"#,
+                ),
+                "peer",
+            ),
+        );
+        index.upsert(
+            "unit-2",
+            &cot_to_document(
+                &event(
+                    "unit-2",
+                    r#"<detail>
+                        <contact callsign="RED FALCON"/>
+                        <takv device="ATAK-CIV"/>
+                        <status battery="80"/>
+                        <track course="180.0" speed="1.0"/>
+                    </detail>"#,
+                ),
+                "peer",
+            ),
+        );
+        index
+    }
+
+    #[test]
+    fn query_equals_matches_exact_callsign() {
+        let index = index_with_units();
+        let ids = index.query(&DetailQuery::path("contact.callsign").equals("GRAY KNIGHT"));
+        assert_eq!(ids, vec!["unit-1".to_string()]);
+    }
+
+    #[test]
+    fn query_starts_with_matches_device_family() {
+        let index = index_with_units();
+        let ids = index.query(&DetailQuery::path("takv.device").starts_with("ATAK"));
+        assert_eq!(ids, vec!["unit-1".to_string(), "unit-2".to_string()]);
+    }
+
+    #[test]
+    fn query_contains_matches_substring() {
+        let index = index_with_units();
+        let ids = index.query(&DetailQuery::path("contact.callsign").contains("FALCON"));
+        assert_eq!(ids, vec!["unit-2".to_string()]);
+    }
+
+    #[test]
+    fn query_in_range_filters_low_battery_units() {
+        let index = index_with_units();
+        let ids = index.query(&DetailQuery::path("status.battery").in_range(None, Some(20.0)));
+        assert_eq!(ids, vec!["unit-1".to_string()]);
+    }
+
+    #[test]
+    fn query_bounding_box_filters_by_course_and_speed_as_stand_in_axes() {
+        // `track.course`/`track.speed` stand in for two independent numeric
+        // axes here since this fixture has no real lat/lon detail fields;
+        // `query_bounding_box` only cares that both paths resolve to numbers.
+        let index = index_with_units();
+        let ids = index.query_bounding_box("track.course", "track.speed", 0.0, 100.0, 0.0, 3.0);
+        assert_eq!(ids, vec!["unit-1".to_string()]);
+    }
+
+    #[test]
+    fn removed_document_no_longer_matches() {
+        let mut index = index_with_units();
+        index.remove("unit-1");
+        let ids = index.query(&DetailQuery::path("takv.device").starts_with("ATAK"));
+        assert_eq!(ids, vec!["unit-2".to_string()]);
+    }
+
+    #[test]
+    fn upsert_replaces_a_prior_entry_for_the_same_id() {
+        let mut index = DetailIndex::new();
+        index.upsert(
+            "unit-1",
+            &cot_to_document(
+                &event("unit-1", r#"<detail><status battery="80"/></detail>"#),
+                "peer",
+            ),
+        );
+        index.upsert(
+            "unit-1",
+            &cot_to_document(
+                &event("unit-1", r#"<detail><status battery="15"/></detail>"#),
+                "peer",
+            ),
+        );
+
+        assert_eq!(index.len(), 1);
+        assert_eq!(
+            index.query(&DetailQuery::path("status.battery").in_range(None, Some(20.0))),
+            vec!["unit-1".to_string()]
+        );
+    }
+}
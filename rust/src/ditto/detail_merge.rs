@@ -0,0 +1,341 @@
+//! Element-level conflict-free merge for the flattened `r_*` keyspace.
+//!
+//! [`r_field_flattening`](crate::ditto::r_field_flattening) encodes each
+//! detail element attribute as its own `r_<element>_<attr>` key so Ditto can
+//! MERGE documents field-by-field, but two peers concurrently editing the
+//! *same* element previously had no defined outcome beyond whichever
+//! document happened to be read back. [`merge_flattened`] resolves that:
+//! given two flattened documents for the same `_id`, it groups the `r_*`
+//! keyspace back into logical elements, applies last-writer-wins per
+//! attribute (keyed on the whole document's `b` update timestamp, since
+//! individual attributes don't carry their own), and keeps attributes present
+//! on only one side instead of letting the other side's absence delete them.
+//!
+//! Repeated/positional elements (multiple same-named detail children, e.g.
+//! several `<link>`s) are expected to be flattened with a stable ordinal
+//! segment in the key (`r_link_0_uid`, `r_link_1_uid`, ...), which this
+//! module's prefix grouping already treats as distinct elements — so two
+//! peers editing `link_0` and `link_1` independently merge cleanly without
+//! either clobbering the other.
+//!
+//! [`merge_flattened`] breaks ties on a whole-document `b` timestamp, which
+//! is coarser than it needs to be: two edits made by the same peer in the
+//! same sync round share one `b`, so a tie falls back to "local wins" rather
+//! than anything causal. [`merge_flattened_with_stamps`] instead attaches a
+//! [`CausalStamp`] — a `(lamport_counter, peer_id)` pair — to each `r_*` key
+//! in a sibling metadata map, and merges by taking the field value with the
+//! higher counter (peer_id breaking ties). Because "higher stamp wins" is a
+//! pure max, the merge of stamped `r_*` keys is commutative, associative,
+//! and idempotent: the same pair of documents merge to the same `r_*` values
+//! regardless of order, and merging a document with itself is a no-op. Core
+//! (non-`r_*`) fields are taken from `local` when present, so they do depend
+//! on argument order.
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// The result of merging two flattened documents for the same `_id`.
+#[derive(Debug, Clone)]
+pub struct MergedDoc {
+    /// The merged flattened document.
+    pub document: Value,
+    /// Count of `r_*` attributes present on both sides with conflicting
+    /// values, where the newer document's value won.
+    pub conflicts_resolved: usize,
+}
+
+/// Merges two flattened documents for the same logical CoT event.
+///
+/// Core (non-`r_*`) fields are taken entirely from whichever document has the
+/// higher `b` (update timestamp); ties favor `local`. Within `r_*`, an
+/// attribute present on both sides takes the value from that same newer
+/// document, but an attribute present on only one side is preserved rather
+/// than dropped, so independently-edited elements on each peer both survive.
+pub fn merge_flattened(local: &Value, remote: &Value) -> MergedDoc {
+    let (Some(local_obj), Some(remote_obj)) = (local.as_object(), remote.as_object()) else {
+        // Nothing sensible to merge structurally; keep local as-is.
+        return MergedDoc {
+            document: local.clone(),
+            conflicts_resolved: 0,
+        };
+    };
+
+    let local_b = local_obj.get("b").and_then(Value::as_f64).unwrap_or(0.0);
+    let remote_b = remote_obj.get("b").and_then(Value::as_f64).unwrap_or(0.0);
+
+    let (newer, older) = if local_b >= remote_b {
+        (local_obj, remote_obj)
+    } else {
+        (remote_obj, local_obj)
+    };
+
+    let mut merged: Map<String, Value> = newer.clone();
+    let mut conflicts_resolved = 0;
+
+    for (key, older_value) in older {
+        if !key.starts_with("r_") {
+            // Core fields (`_id`, `w`, `n`, `o`, ...) are whole-document
+            // concerns already decided by picking the newer side above.
+            continue;
+        }
+
+        match merged.get(key) {
+            None => {
+                merged.insert(key.clone(), older_value.clone());
+            }
+            Some(newer_value) if newer_value != older_value => {
+                conflicts_resolved += 1;
+            }
+            _ => {}
+        }
+    }
+
+    MergedDoc {
+        document: Value::Object(merged),
+        conflicts_resolved,
+    }
+}
+
+/// Key under which [`merge_flattened_with_stamps`] stores the per-`r_*`-key
+/// causal stamps, as a sibling map alongside the document's own fields.
+pub const STAMPS_KEY: &str = "_r_stamps";
+
+/// A causal write stamp for a single `r_*` key: a Lamport counter plus the id
+/// of the peer that wrote it, used to break ties between equal counters.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub struct CausalStamp {
+    /// Logical clock value for this write; higher always wins a merge.
+    pub lamport_counter: u64,
+    /// Id of the peer that made this write, used only to break a counter tie.
+    pub peer_id: String,
+}
+
+/// Merges two flattened documents using per-field [`CausalStamp`]s rather
+/// than a single whole-document timestamp.
+///
+/// For each `r_*` key present on either side, the value with the higher
+/// stamp wins; a key present on only one side is carried through unchanged.
+/// Non-`r_*` (core) fields are taken from `local` when present, falling back
+/// to `remote`, since they aren't covered by per-field stamps. The resulting
+/// document carries a merged [`STAMPS_KEY`] map so a further merge against a
+/// third document still has accurate per-field provenance.
+pub fn merge_flattened_with_stamps(local: &Value, remote: &Value) -> Value {
+    let empty = Map::new();
+    let local_obj = local.as_object().unwrap_or(&empty);
+    let remote_obj = remote.as_object().unwrap_or(&empty);
+
+    let local_stamps = read_stamps(local_obj);
+    let remote_stamps = read_stamps(remote_obj);
+
+    let mut merged = local_obj.clone();
+    let mut stamps = local_stamps.clone();
+
+    for (key, remote_value) in remote_obj {
+        if key == STAMPS_KEY {
+            continue;
+        }
+        if !key.starts_with("r_") {
+            merged.entry(key.clone()).or_insert_with(|| remote_value.clone());
+            continue;
+        }
+
+        let remote_stamp = remote_stamps.get(key).cloned().unwrap_or_default();
+        let local_stamp = local_stamps.get(key).cloned();
+
+        let remote_wins = match &local_stamp {
+            None => true,
+            Some(local_stamp) => remote_stamp > *local_stamp,
+        };
+
+        if !merged.contains_key(key) || remote_wins {
+            merged.insert(key.clone(), remote_value.clone());
+            stamps.insert(key.clone(), remote_stamp);
+        }
+    }
+
+    merged.insert(STAMPS_KEY.to_string(), stamps_to_value(&stamps));
+    Value::Object(merged)
+}
+
+/// Merges two flattened documents by [`merge_flattened_with_stamps`] and
+/// reconstructs the combined result as a [`CotEvent`](crate::cot_events::CotEvent),
+/// so e.g. peer A's updated `track.course` and peer B's updated
+/// `status.battery` both appear in the regenerated detail XML.
+pub fn merge_cot_events_with_stamps(
+    local_flattened: &Value,
+    remote_flattened: &Value,
+) -> crate::cot_events::CotEvent {
+    let merged = merge_flattened_with_stamps(local_flattened, remote_flattened);
+    crate::ditto::from_ditto::cot_event_from_flattened_json(&merged)
+}
+
+impl Default for CausalStamp {
+    fn default() -> Self {
+        Self {
+            lamport_counter: 0,
+            peer_id: String::new(),
+        }
+    }
+}
+
+fn read_stamps(obj: &Map<String, Value>) -> HashMap<String, CausalStamp> {
+    obj.get(STAMPS_KEY)
+        .and_then(Value::as_object)
+        .map(|stamps_obj| {
+            stamps_obj
+                .iter()
+                .filter_map(|(k, v)| {
+                    serde_json::from_value::<CausalStamp>(v.clone())
+                        .ok()
+                        .map(|stamp| (k.clone(), stamp))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn stamps_to_value(stamps: &HashMap<String, CausalStamp>) -> Value {
+    let map: Map<String, Value> = stamps
+        .iter()
+        .map(|(k, v)| (k.clone(), serde_json::to_value(v).expect("CausalStamp always serializes")))
+        .collect();
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn independently_edited_elements_both_survive() {
+        let local = json!({
+            "_id": "uid-1", "b": 100.0,
+            "r_contact_callsign": "ALPHA-1",
+        });
+        let remote = json!({
+            "_id": "uid-1", "b": 100.0,
+            "r_status_readiness": true,
+        });
+
+        let merged = merge_flattened(&local, &remote);
+        assert_eq!(merged.document["r_contact_callsign"], json!("ALPHA-1"));
+        assert_eq!(merged.document["r_status_readiness"], json!(true));
+        assert_eq!(merged.conflicts_resolved, 0);
+    }
+
+    #[test]
+    fn conflicting_attribute_takes_newer_documents_value() {
+        let local = json!({
+            "_id": "uid-1", "b": 100.0,
+            "r_status_battery": 50,
+        });
+        let remote = json!({
+            "_id": "uid-1", "b": 200.0,
+            "r_status_battery": 90,
+        });
+
+        let merged = merge_flattened(&local, &remote);
+        assert_eq!(merged.document["r_status_battery"], json!(90));
+        assert_eq!(merged.conflicts_resolved, 1);
+    }
+
+    #[test]
+    fn positional_elements_matched_by_stable_ordinal_merge_independently() {
+        let local = json!({
+            "_id": "uid-1", "b": 100.0,
+            "r_link_0_uid": "PARENT-1",
+        });
+        let remote = json!({
+            "_id": "uid-1", "b": 150.0,
+            "r_link_1_uid": "PARENT-2",
+        });
+
+        let merged = merge_flattened(&local, &remote);
+        assert_eq!(merged.document["r_link_0_uid"], json!("PARENT-1"));
+        assert_eq!(merged.document["r_link_1_uid"], json!("PARENT-2"));
+    }
+
+    #[test]
+    fn core_fields_come_from_the_newer_document() {
+        let local = json!({ "_id": "uid-1", "b": 100.0, "w": "a-f-G-U-C" });
+        let remote = json!({ "_id": "uid-1", "b": 200.0, "w": "a-f-G-U-T" });
+
+        let merged = merge_flattened(&local, &remote);
+        assert_eq!(merged.document["w"], json!("a-f-G-U-T"));
+    }
+
+    fn stamped(fields: Value, stamps: &[(&str, u64, &str)]) -> Value {
+        let mut obj = fields.as_object().unwrap().clone();
+        let stamps_obj: Map<String, Value> = stamps
+            .iter()
+            .map(|(key, counter, peer)| {
+                (
+                    key.to_string(),
+                    json!({ "lamport_counter": counter, "peer_id": peer }),
+                )
+            })
+            .collect();
+        obj.insert(STAMPS_KEY.to_string(), Value::Object(stamps_obj));
+        Value::Object(obj)
+    }
+
+    #[test]
+    fn higher_counter_wins_regardless_of_side() {
+        let local = stamped(
+            json!({ "_id": "uid-1", "r_track_course": 10 }),
+            &[("r_track_course", 1, "peer-a")],
+        );
+        let remote = stamped(
+            json!({ "_id": "uid-1", "r_track_course": 90 }),
+            &[("r_track_course", 5, "peer-b")],
+        );
+
+        let merged = merge_flattened_with_stamps(&local, &remote);
+        assert_eq!(merged["r_track_course"], json!(90));
+    }
+
+    #[test]
+    fn disjoint_fields_from_both_peers_both_survive() {
+        let local = stamped(
+            json!({ "_id": "uid-1", "r_track_course": 10 }),
+            &[("r_track_course", 1, "peer-a")],
+        );
+        let remote = stamped(
+            json!({ "_id": "uid-1", "r_status_battery": 40 }),
+            &[("r_status_battery", 1, "peer-b")],
+        );
+
+        let merged = merge_flattened_with_stamps(&local, &remote);
+        assert_eq!(merged["r_track_course"], json!(10));
+        assert_eq!(merged["r_status_battery"], json!(40));
+    }
+
+    #[test]
+    fn merge_is_commutative() {
+        let local = stamped(
+            json!({ "_id": "uid-1", "r_track_course": 10 }),
+            &[("r_track_course", 3, "peer-a")],
+        );
+        let remote = stamped(
+            json!({ "_id": "uid-1", "r_track_course": 90 }),
+            &[("r_track_course", 3, "peer-b")],
+        );
+
+        let a_then_b = merge_flattened_with_stamps(&local, &remote);
+        let b_then_a = merge_flattened_with_stamps(&remote, &local);
+        assert_eq!(a_then_b["r_track_course"], b_then_a["r_track_course"]);
+    }
+
+    #[test]
+    fn merge_is_idempotent() {
+        let doc = stamped(
+            json!({ "_id": "uid-1", "r_track_course": 10 }),
+            &[("r_track_course", 3, "peer-a")],
+        );
+
+        let merged_once = merge_flattened_with_stamps(&doc, &doc);
+        let merged_twice = merge_flattened_with_stamps(&merged_once, &doc);
+        assert_eq!(merged_once["r_track_course"], merged_twice["r_track_course"]);
+    }
+}
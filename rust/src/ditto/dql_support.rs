@@ -3,6 +3,8 @@
 //! This module implements the Ditto DQL `DittoDocument` trait for our `CotDocument` enum,
 //! allowing it to be used directly with Ditto's query interface.
 
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use crate::ditto::CotDocument;
 use dittolive_ditto::error::{DittoError, ErrorKind};
 use dittolive_ditto::prelude::*;
@@ -11,14 +13,71 @@ use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
 use std::collections::BTreeMap;
 
+/// Resolves `path` against an already-materialized `json_value` for
+/// `document`, special-casing `"id"`/`"_id"` (stored differently in our
+/// model than in the flattened JSON) and rewriting a `detail.`-prefixed (or
+/// bare `detail`) path onto `r` before navigating — this lets callers
+/// address CoT substructures the way they think of them (e.g.
+/// `detail.track.speed`) without knowing the underlying schema letter.
+/// Shared by [`DittoDocument::get`] and
+/// [`CachedCotDocument`](super::cached_document::CachedCotDocument), so both
+/// the re-serializing and memoized accessors agree on path semantics.
+pub(crate) fn resolve_document_path(
+    document: &CotDocument,
+    json_value: &JsonValue,
+    path: &str,
+) -> Result<JsonValue, DittoError> {
+    match path {
+        "id" | "_id" => {
+            // Special case for ID, which is stored differently in our model vs Ditto
+            match document {
+                CotDocument::Api(api) => serde_json::to_value(&api.id),
+                CotDocument::Chat(chat) => serde_json::to_value(&chat.id),
+                CotDocument::File(file) => serde_json::to_value(&file.id),
+                CotDocument::Generic(generic) => serde_json::to_value(&generic.id),
+                CotDocument::MapItem(map_item) => serde_json::to_value(&map_item.id),
+                CotDocument::Unknown(unknown) => serde_json::to_value(unknown.id()),
+            }
+            .map_err(|_| DittoError::from(ErrorKind::NonExtant))
+        }
+        _ => {
+            let path = path
+                .strip_prefix("detail.")
+                .map(|rest| format!("r.{rest}"))
+                .unwrap_or_else(|| {
+                    if path == "detail" {
+                        "r".to_string()
+                    } else {
+                        path.to_string()
+                    }
+                });
+
+            // For other paths, navigate the JSON structure, supporting
+            // `[index]` and `[*]` suffixes on a segment (see
+            // `resolve_path_segments`).
+            let segments = parse_path_segments(&path)
+                .map_err(|_| DittoError::from(ErrorKind::InvalidInput))?;
+            resolve_path_segments(json_value, &segments)
+                .ok_or_else(|| DittoError::from(ErrorKind::NonExtant))
+        }
+    }
+}
+
 // Helper function to convert JSON values to CBOR values
-fn json_to_cbor(json: JsonValue) -> Result<CborValue, String> {
+pub(crate) fn json_to_cbor(json: JsonValue) -> Result<CborValue, String> {
     match json {
         JsonValue::Null => Ok(CborValue::Null),
         JsonValue::Bool(b) => Ok(CborValue::Bool(b)),
         JsonValue::Number(n) => {
             if let Some(i) = n.as_i64() {
                 Ok(CborValue::Integer(i as i128))
+            } else if let Some(u) = n.as_u64() {
+                // Above `i64::MAX` but still a whole number: `as_i64` fails
+                // here, so without this branch a value like `u64::MAX`
+                // would either lose precision through `as_f64` or (with the
+                // `arbitrary_precision` feature) fail outright instead of
+                // round-tripping as the exact integer it is.
+                Ok(CborValue::Integer(u as i128))
             } else if let Some(f) = n.as_f64() {
                 Ok(CborValue::Float(f))
             } else {
@@ -45,6 +104,136 @@ fn json_to_cbor(json: JsonValue) -> Result<CborValue, String> {
     }
 }
 
+/// One dotted segment of a [`DittoDocument::get`] path, e.g. `links`,
+/// `links[0]`, or `links[*]`.
+struct PathSegment {
+    /// The key to look up in the current object, e.g. `"links"`.
+    key: String,
+    /// The `[index]` or `[*]` suffix on this segment, if any.
+    index: Option<PathIndex>,
+}
+
+enum PathIndex {
+    /// `[N]`: a specific array element.
+    At(usize),
+    /// `[*]`: every array element, collected into a JSON array.
+    Wildcard,
+}
+
+/// Splits a `.`-joined path into [`PathSegment`]s, parsing a trailing
+/// `[index]`/`[*]` off each one.
+fn parse_path_segments(path: &str) -> Result<Vec<PathSegment>, ()> {
+    path.split('.')
+        .map(|raw| {
+            let Some(bracket_start) = raw.find('[') else {
+                return Ok(PathSegment {
+                    key: raw.to_string(),
+                    index: None,
+                });
+            };
+            let key = raw[..bracket_start].to_string();
+            let inside = raw[bracket_start + 1..].strip_suffix(']').ok_or(())?;
+            let index = if inside == "*" {
+                PathIndex::Wildcard
+            } else {
+                PathIndex::At(inside.parse::<usize>().map_err(|_| ())?)
+            };
+            Ok(PathSegment {
+                key,
+                index: Some(index),
+            })
+        })
+        .collect()
+}
+
+/// Walks `segments` against `current`. A `[*]` segment fans out over every
+/// remaining segment for each array element and collects the (present)
+/// results back into a JSON array; a missing key, an out-of-range `[N]`, or
+/// a `[*]`/`[N]` applied to a non-array all resolve to `None` rather than
+/// erroring, so the caller can turn "not found" into [`ErrorKind::NonExtant`]
+/// uniformly.
+fn resolve_path_segments(current: &JsonValue, segments: &[PathSegment]) -> Option<JsonValue> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Some(current.clone());
+    };
+    let keyed = current.get(&segment.key)?;
+    match &segment.index {
+        None => resolve_path_segments(keyed, rest),
+        Some(PathIndex::At(i)) => resolve_path_segments(keyed.as_array()?.get(*i)?, rest),
+        Some(PathIndex::Wildcard) => {
+            let results: Vec<JsonValue> = keyed
+                .as_array()?
+                .iter()
+                .filter_map(|item| resolve_path_segments(item, rest))
+                .collect();
+            Some(JsonValue::Array(results))
+        }
+    }
+}
+
+/// Inverse of [`json_to_cbor`], for reconstructing a document from the CBOR
+/// [`DittoDocument::to_cbor`] produces. Map keys are expected to be
+/// [`CborValue::Text`] (the only kind `json_to_cbor` ever emits); a
+/// non-text key falls back to its debug representation rather than
+/// panicking on input this module didn't itself produce. `Bytes` becomes a
+/// base64 string, matching how [`crate::ditto::Base64Data`] already
+/// represents binary payloads at the JSON boundary.
+fn cbor_to_json(cbor: CborValue) -> JsonValue {
+    match cbor {
+        CborValue::Null => JsonValue::Null,
+        CborValue::Bool(b) => JsonValue::Bool(b),
+        CborValue::Integer(i) => i64::try_from(i)
+            .map(|i| JsonValue::Number(i.into()))
+            .or_else(|_| u64::try_from(i).map(|i| JsonValue::Number(i.into())))
+            .unwrap_or_else(|_| {
+                serde_json::Number::from_f64(i as f64)
+                    .map(JsonValue::Number)
+                    .unwrap_or(JsonValue::Null)
+            }),
+        CborValue::Float(f) => serde_json::Number::from_f64(f)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        CborValue::Text(s) => JsonValue::String(s),
+        CborValue::Bytes(b) => JsonValue::String(STANDARD.encode(b)),
+        CborValue::Array(arr) => JsonValue::Array(arr.into_iter().map(cbor_to_json).collect()),
+        CborValue::Map(map) => {
+            let mut obj = serde_json::Map::with_capacity(map.len());
+            for (key, value) in map {
+                let key = match key {
+                    CborValue::Text(s) => s,
+                    other => format!("{other:?}"),
+                };
+                obj.insert(key, cbor_to_json(value));
+            }
+            JsonValue::Object(obj)
+        }
+        other => JsonValue::String(format!("{other:?}")),
+    }
+}
+
+impl CotDocument {
+    /// Reconstructs a [`CotDocument`] from the CBOR [`DittoDocument::to_cbor`]
+    /// produces, by converting it back to JSON with [`cbor_to_json`] and
+    /// running it through the same `w`-based variant dispatch
+    /// [`CotDocument::from_json_str`] already uses, so the two decode paths
+    /// can never disagree about which variant a document resolves to.
+    pub fn from_cbor(value: &CborValue) -> Result<Self, DittoError> {
+        let json_value = cbor_to_json(value.clone());
+        let invalid = || DittoError::from(ErrorKind::InvalidInput);
+        let json_str = serde_json::to_string(&json_value).map_err(|_| invalid())?;
+        CotDocument::from_json_str(&json_str).map_err(|_| invalid())
+    }
+
+    /// Encodes this document as deterministic CBOR bytes (RFC 8949 §4.2.1),
+    /// suitable for content-addressing or dedup, since two documents with
+    /// identical content always produce identical bytes regardless of field
+    /// order. See [`crate::ditto::canonical_cbor`] for the encoding rules.
+    pub fn to_canonical_cbor_bytes(&self) -> Result<Vec<u8>, DittoError> {
+        let cbor = DittoDocument::to_cbor(self)?;
+        Ok(crate::ditto::canonical_cbor::to_canonical_cbor_bytes(&cbor))
+    }
+}
+
 impl DittoDocument for CotDocument {
     fn id(&self) -> DocumentId {
         // Get the ID string from the document
@@ -52,7 +241,12 @@ impl DittoDocument for CotDocument {
             CotDocument::Api(api) => api.id.clone(),
             CotDocument::Chat(chat) => chat.id.clone(),
             CotDocument::File(file) => file.id.clone(),
+            CotDocument::Generic(generic) => generic.id.clone(),
             CotDocument::MapItem(map_item) => map_item.id.clone(),
+            CotDocument::Unknown(unknown) => unknown
+                .id()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
         };
 
         // Convert the ID string to a DocumentId
@@ -64,70 +258,18 @@ impl DittoDocument for CotDocument {
     }
 
     fn to_cbor(&self) -> Result<CborValue, DittoError> {
-        // Convert the document to a JSON value first
-        let json_value = match self {
-            CotDocument::Api(api) => serde_json::to_value(api),
-            CotDocument::Chat(chat) => serde_json::to_value(chat),
-            CotDocument::File(file) => serde_json::to_value(file),
-            CotDocument::MapItem(map_item) => serde_json::to_value(map_item),
-        }
-        .map_err(|_| DittoError::from(ErrorKind::InvalidInput))?;
-
-        // Convert the JSON value to a CBOR value
-        json_to_cbor(json_value).map_err(|_| DittoError::from(ErrorKind::InvalidInput))
+        json_to_cbor(self.to_flattened_json())
+            .map_err(|_| DittoError::from(ErrorKind::InvalidInput))
     }
 
     fn get<V: DeserializeOwned>(&self, path: &str) -> Result<V, DittoError> {
-        // Convert the document to a JSON value first
-        let json_value = match self {
-            CotDocument::Api(api) => serde_json::to_value(api),
-            CotDocument::Chat(chat) => serde_json::to_value(chat),
-            CotDocument::File(file) => serde_json::to_value(file),
-            CotDocument::MapItem(map_item) => serde_json::to_value(map_item),
-        }
-        .map_err(|_| DittoError::from(ErrorKind::InvalidInput))?;
-
-        // Extract the value at the given path
-        let value = match path {
-            "id" | "_id" => {
-                // Special case for ID, which is stored differently in our model vs Ditto
-                match self {
-                    CotDocument::Api(api) => serde_json::to_value(&api.id),
-                    CotDocument::Chat(chat) => serde_json::to_value(&chat.id),
-                    CotDocument::File(file) => serde_json::to_value(&file.id),
-                    CotDocument::MapItem(map_item) => serde_json::to_value(&map_item.id),
-                }
-                .map_err(|_| DittoError::from(ErrorKind::NonExtant))?
-            }
-            _ => {
-                // For other paths, navigate the JSON structure
-                let mut current = &json_value;
-                for segment in path.split('.') {
-                    match current.get(segment) {
-                        Some(value) => current = value,
-                        None => return Err(DittoError::from(ErrorKind::NonExtant)),
-                    }
-                }
-                current.clone()
-            }
-        };
-
-        // Deserialize the extracted value to the requested type
+        let value = resolve_document_path(self, &self.to_flattened_json(), path)?;
         serde_json::from_value(value).map_err(|_| DittoError::from(ErrorKind::InvalidInput))
     }
 
     fn typed<T: DeserializeOwned>(&self) -> Result<T, DittoError> {
-        // Convert the document to a JSON value first
-        let json_value = match self {
-            CotDocument::Api(api) => serde_json::to_value(api),
-            CotDocument::Chat(chat) => serde_json::to_value(chat),
-            CotDocument::File(file) => serde_json::to_value(file),
-            CotDocument::MapItem(map_item) => serde_json::to_value(map_item),
-        }
-        .map_err(|_| DittoError::from(ErrorKind::InvalidInput))?;
-
-        // Deserialize to the requested type
-        serde_json::from_value(json_value).map_err(|_| DittoError::from(ErrorKind::InvalidInput))
+        serde_json::from_value(self.to_flattened_json())
+            .map_err(|_| DittoError::from(ErrorKind::InvalidInput))
     }
 }
 
@@ -226,6 +368,105 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_ditto_document_get_detail_prefixed_path() {
+        // `detail.*` paths should transparently resolve against the
+        // document's `r` field, the way a caller unfamiliar with the
+        // lettered schema would expect to address CoT detail content.
+        let mut r_map = HashMap::new();
+        r_map.insert(
+            "speed".to_string(),
+            MapItemRValue::Number(12.5),
+        );
+
+        let map_item = CotDocument::MapItem(MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: None,
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: 2,
+            e: "Test Item".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: None,
+            k: None,
+            l: None,
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r: r_map,
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        });
+
+        let speed: f64 = DittoDocument::get(&map_item, "detail.speed").unwrap();
+        assert_eq!(speed, 12.5);
+    }
+
+    #[test]
+    fn test_get_supports_array_index_and_wildcard_paths() {
+        let mut r_map = HashMap::new();
+        r_map.insert(
+            "links".to_string(),
+            MapItemRValue::Array(vec![
+                serde_json::json!({"uid": "link-1"}),
+                serde_json::json!({"uid": "link-2"}),
+            ]),
+        );
+        let map_item = CotDocument::MapItem(MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: None,
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: 2,
+            e: "Test Item".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: None,
+            k: None,
+            l: None,
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r: r_map,
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        });
+
+        let first: String = DittoDocument::get(&map_item, "detail.links[0].uid").unwrap();
+        assert_eq!(first, "link-1");
+
+        let all: Vec<String> = DittoDocument::get(&map_item, "detail.links[*].uid").unwrap();
+        assert_eq!(all, vec!["link-1".to_string(), "link-2".to_string()]);
+
+        let out_of_range = DittoDocument::get::<String>(&map_item, "detail.links[5].uid");
+        assert!(out_of_range.is_err());
+
+        let wildcard_over_scalar =
+            DittoDocument::get::<Vec<String>>(&map_item, "detail.links[0].uid[*]");
+        assert!(wildcard_over_scalar.is_err());
+    }
+
     #[test]
     fn test_to_cbor() {
         // Create a simple MapItem
@@ -267,4 +508,105 @@ mod tests {
             cbor_result.err()
         );
     }
+
+    #[test]
+    fn test_from_cbor_round_trips_a_map_item() {
+        let mut r_map = HashMap::new();
+        r_map.insert(
+            "status_battery".to_string(),
+            MapItemRValue::Number(80.0),
+        );
+        let map_item = CotDocument::MapItem(MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: None,
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: 2,
+            e: "Test Item".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: None,
+            k: None,
+            l: None,
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r: r_map,
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        });
+
+        let cbor = DittoDocument::to_cbor(&map_item).unwrap();
+        let reconstructed = CotDocument::from_cbor(&cbor).unwrap();
+
+        let id: String = DittoDocument::get(&reconstructed, "_id").unwrap();
+        assert_eq!(id, "test-id-123");
+        assert!(matches!(reconstructed, CotDocument::MapItem(_)));
+    }
+
+    #[test]
+    fn test_cbor_to_json_base64_encodes_bytes() {
+        let json = cbor_to_json(CborValue::Bytes(vec![1, 2, 3]));
+        assert_eq!(json, JsonValue::String(STANDARD.encode([1, 2, 3])));
+    }
+
+    #[test]
+    fn test_json_to_cbor_preserves_u64_values_above_i64_max() {
+        let json = JsonValue::Number(serde_json::Number::from(u64::MAX));
+        let cbor = json_to_cbor(json).unwrap();
+        assert_eq!(cbor, CborValue::Integer(u64::MAX as i128));
+    }
+
+    #[test]
+    fn test_cbor_to_json_preserves_large_integers() {
+        let json = cbor_to_json(CborValue::Integer(i64::MAX as i128));
+        assert_eq!(json, JsonValue::Number(i64::MAX.into()));
+    }
+
+    #[test]
+    fn test_to_canonical_cbor_bytes_is_deterministic() {
+        let map_item = CotDocument::MapItem(MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: None,
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: 2,
+            e: "Test Item".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: None,
+            k: None,
+            l: None,
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r: HashMap::new(),
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        });
+
+        let first = map_item.to_canonical_cbor_bytes().unwrap();
+        let second = map_item.to_canonical_cbor_bytes().unwrap();
+        assert_eq!(first, second);
+    }
 }
@@ -0,0 +1,333 @@
+//! Opt-in AEAD encryption of a [`CotDocument`]'s sensitive fields at rest.
+//!
+//! [`CotDocument::encrypt_detail`]/[`decrypt_detail`](CotDocument::decrypt_detail)
+//! seal the generic detail blob (the `r` field, which carries contact
+//! endpoints and other free-form detail among its entries) and a chat
+//! event's `message` with XChaCha20-Poly1305: a random 24-byte nonce per
+//! call, rather than the 96-bit nonce plain ChaCha20-Poly1305 uses, because
+//! CoT traffic is high-volume and long-lived enough that 96-bit nonce reuse
+//! is a real risk. The document's `_id` and CoT `w` (event type) are bound
+//! in as associated data, so ciphertext sealed for one event can't be
+//! transplanted onto another.
+//!
+//! Only [`CotDocument::Generic`] and [`CotDocument::Chat`] carry a field
+//! this module considers sensitive enough to seal; every other variant
+//! passes through [`encrypt_detail`](CotDocument::encrypt_detail) and
+//! [`decrypt_detail`](CotDocument::decrypt_detail) unchanged.
+
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::schema::{ChatRValue, GenericRValue};
+use super::to_ditto::CotDocument;
+
+/// The key a document's `r` map is reduced to when its detail blob is
+/// sealed; see [`CotDocument::encrypt_detail`].
+const ENCRYPTED_DETAIL_KEY: &str = "_encrypted_detail";
+
+/// The Poly1305 tag is always 16 bytes, appended to the ciphertext by the
+/// `aead` crate's combined `encrypt`/`decrypt` calls.
+const TAG_LEN: usize = 16;
+
+/// Failure modes for [`CotDocument::encrypt_detail`] and
+/// [`CotDocument::decrypt_detail`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum EncryptionError {
+    /// XChaCha20-Poly1305 sealing failed (an AEAD primitive failure, not
+    /// something a caller can typically recover from).
+    #[error("failed to seal field: {0}")]
+    Seal(String),
+
+    /// XChaCha20-Poly1305 opening failed: either the key, associated data,
+    /// or ciphertext/tag don't match, so the field can't be trusted.
+    #[error("failed to open sealed field: {0}")]
+    Open(String),
+
+    /// A sealed field's base64 nonce/ciphertext/tag didn't decode.
+    #[error("malformed sealed field: {0}")]
+    InvalidEnvelope(String),
+
+    /// `decrypt_detail` was called on a document whose detail blob carries
+    /// no [`ENCRYPTED_DETAIL_KEY`] entry, i.e. it was never sealed.
+    #[error("document detail is not encrypted")]
+    NotEncrypted,
+}
+
+/// An AEAD-sealed replacement for a plaintext field: a random nonce, the
+/// ciphertext, and the Poly1305 authentication tag, each base64-encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedField {
+    /// The 24-byte XChaCha20 nonce used for this field, base64-encoded.
+    pub nonce: String,
+    /// The ciphertext, base64-encoded.
+    pub ciphertext: String,
+    /// The Poly1305 authentication tag, base64-encoded.
+    pub tag: String,
+}
+
+impl EncryptedField {
+    fn seal(plaintext: &[u8], key: &[u8; 32], aad: &[u8]) -> Result<Self, EncryptionError> {
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut sealed = cipher
+            .encrypt(&nonce, chacha20poly1305::aead::Payload { msg: plaintext, aad })
+            .map_err(|e| EncryptionError::Seal(e.to_string()))?;
+        let tag = sealed.split_off(sealed.len().saturating_sub(TAG_LEN));
+
+        Ok(Self {
+            nonce: URL_SAFE_NO_PAD.encode(nonce),
+            ciphertext: URL_SAFE_NO_PAD.encode(sealed),
+            tag: URL_SAFE_NO_PAD.encode(tag),
+        })
+    }
+
+    fn open(&self, key: &[u8; 32], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let nonce_bytes = URL_SAFE_NO_PAD
+            .decode(&self.nonce)
+            .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))?;
+        let mut combined = URL_SAFE_NO_PAD
+            .decode(&self.ciphertext)
+            .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))?;
+        let tag = URL_SAFE_NO_PAD
+            .decode(&self.tag)
+            .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))?;
+        combined.extend_from_slice(&tag);
+
+        let nonce = XNonce::from_exact_iter(nonce_bytes.iter().copied())
+            .ok_or_else(|| EncryptionError::InvalidEnvelope("nonce is not 24 bytes".to_string()))?;
+
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+        cipher
+            .decrypt(&nonce, chacha20poly1305::aead::Payload { msg: &combined, aad })
+            .map_err(|e| EncryptionError::Open(e.to_string()))
+    }
+
+    fn to_json_map(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+
+    fn from_json_map(map: &Map<String, Value>) -> Result<Self, EncryptionError> {
+        serde_json::from_value(Value::Object(map.clone()))
+            .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))
+    }
+}
+
+/// Binds a sealed field to the document it came from: `_id` and `w` (CoT
+/// event type), so ciphertext can't be copied onto a different event.
+fn associated_data(id: &str, event_type: &str) -> Vec<u8> {
+    format!("{id}\u{0}{event_type}").into_bytes()
+}
+
+impl CotDocument {
+    /// Seals this document's sensitive fields in place, returning the
+    /// sealed copy.
+    ///
+    /// [`CotDocument::Generic`]'s `r` map is replaced wholesale by a single
+    /// [`ENCRYPTED_DETAIL_KEY`] entry holding the sealed envelope;
+    /// [`CotDocument::Chat`] additionally seals `message` (the chat
+    /// `remarks` text) in the same way. Every other variant is returned
+    /// unchanged.
+    pub fn encrypt_detail(&self, key: &[u8; 32]) -> Result<CotDocument, EncryptionError> {
+        match self {
+            CotDocument::Generic(doc) => {
+                let mut doc = doc.clone();
+                let aad = associated_data(&doc.id, &doc.w);
+                let plaintext =
+                    serde_json::to_vec(&doc.r).map_err(|e| EncryptionError::Seal(e.to_string()))?;
+                let sealed = EncryptedField::seal(&plaintext, key, &aad)?;
+                doc.r = HashMap::from([(
+                    ENCRYPTED_DETAIL_KEY.to_string(),
+                    GenericRValue::Object(sealed.to_json_map()),
+                )]);
+                Ok(CotDocument::Generic(doc))
+            }
+            CotDocument::Chat(doc) => {
+                let mut doc = doc.clone();
+                let aad = associated_data(&doc.id, &doc.w);
+
+                let plaintext =
+                    serde_json::to_vec(&doc.r).map_err(|e| EncryptionError::Seal(e.to_string()))?;
+                let sealed_r = EncryptedField::seal(&plaintext, key, &aad)?;
+                doc.r = HashMap::from([(
+                    ENCRYPTED_DETAIL_KEY.to_string(),
+                    ChatRValue::Object(sealed_r.to_json_map()),
+                )]);
+
+                if let Some(message) = &doc.message {
+                    let sealed_message = EncryptedField::seal(message.as_bytes(), key, &aad)?;
+                    doc.message = Some(
+                        serde_json::to_string(&sealed_message)
+                            .map_err(|e| EncryptionError::Seal(e.to_string()))?,
+                    );
+                }
+
+                Ok(CotDocument::Chat(doc))
+            }
+            other => Ok(other.clone()),
+        }
+    }
+
+    /// Reverses [`CotDocument::encrypt_detail`], restoring the plaintext
+    /// `r` map (and, for [`CotDocument::Chat`], `message`).
+    ///
+    /// Fails with [`EncryptionError::NotEncrypted`] if the document's `r`
+    /// map carries no [`ENCRYPTED_DETAIL_KEY`] entry, and with
+    /// [`EncryptionError::Open`] if `key` doesn't match the one
+    /// `encrypt_detail` was called with.
+    pub fn decrypt_detail(&self, key: &[u8; 32]) -> Result<CotDocument, EncryptionError> {
+        match self {
+            CotDocument::Generic(doc) => {
+                let mut doc = doc.clone();
+                let aad = associated_data(&doc.id, &doc.w);
+                let sealed = match doc.r.get(ENCRYPTED_DETAIL_KEY) {
+                    Some(GenericRValue::Object(map)) => EncryptedField::from_json_map(map)?,
+                    _ => return Err(EncryptionError::NotEncrypted),
+                };
+                let plaintext = sealed.open(key, &aad)?;
+                doc.r = serde_json::from_slice(&plaintext)
+                    .map_err(|e| EncryptionError::Open(e.to_string()))?;
+                Ok(CotDocument::Generic(doc))
+            }
+            CotDocument::Chat(doc) => {
+                let mut doc = doc.clone();
+                let aad = associated_data(&doc.id, &doc.w);
+
+                let sealed = match doc.r.get(ENCRYPTED_DETAIL_KEY) {
+                    Some(ChatRValue::Object(map)) => EncryptedField::from_json_map(map)?,
+                    _ => return Err(EncryptionError::NotEncrypted),
+                };
+                let plaintext = sealed.open(key, &aad)?;
+                doc.r = serde_json::from_slice(&plaintext)
+                    .map_err(|e| EncryptionError::Open(e.to_string()))?;
+
+                if let Some(message) = &doc.message {
+                    let sealed_message: EncryptedField = serde_json::from_str(message)
+                        .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))?;
+                    let plaintext_message = sealed_message.open(key, &aad)?;
+                    doc.message = Some(
+                        String::from_utf8(plaintext_message)
+                            .map_err(|e| EncryptionError::Open(e.to_string()))?,
+                    );
+                }
+
+                Ok(CotDocument::Chat(doc))
+            }
+            other => Ok(other.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::{cot_to_document, transform_chat_event};
+
+    const KEY: [u8; 32] = [7u8; 32];
+
+    fn event(uid: &str, detail: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: "b-m-p-s-p-i".to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::new(34.0, -118.0, 100.0),
+            detail: detail.to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn generic_detail_round_trips_through_encryption() {
+        let original = cot_to_document(
+            &event("uid-1", r#"<detail><contact endpoint="192.168.1.1:4242:tcp"/></detail>"#),
+            "peer",
+        );
+
+        let encrypted = original.encrypt_detail(&KEY).unwrap();
+        match &encrypted {
+            CotDocument::Generic(doc) => {
+                assert!(doc.r.contains_key(ENCRYPTED_DETAIL_KEY));
+                assert_eq!(doc.r.len(), 1);
+            }
+            other => panic!("expected Generic document, got {other:?}"),
+        }
+
+        let decrypted = encrypted.decrypt_detail(&KEY).unwrap();
+        assert_eq!(original.to_flattened_json(), decrypted.to_flattened_json());
+    }
+
+    #[test]
+    fn chat_message_round_trips_through_encryption() {
+        let chat_event = event(
+            "uid-1",
+            r#"<detail><__chat><chatgrp/></__chat><remarks>rendezvous at the bridge</remarks></detail>"#,
+        );
+        let original = CotDocument::Chat(transform_chat_event(&chat_event, "peer").unwrap());
+
+        let encrypted = original.encrypt_detail(&KEY).unwrap();
+        match &encrypted {
+            CotDocument::Chat(doc) => {
+                assert_ne!(doc.message.as_deref(), Some("rendezvous at the bridge"));
+            }
+            other => panic!("expected Chat document, got {other:?}"),
+        }
+
+        let decrypted = encrypted.decrypt_detail(&KEY).unwrap();
+        match &decrypted {
+            CotDocument::Chat(doc) => {
+                assert_eq!(doc.message.as_deref(), Some("rendezvous at the bridge"));
+            }
+            other => panic!("expected Chat document, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn wrong_key_fails_to_open() {
+        let original = cot_to_document(&event("uid-1", "<detail/>"), "peer");
+        let encrypted = original.encrypt_detail(&KEY).unwrap();
+
+        let wrong_key = [9u8; 32];
+        let err = encrypted.decrypt_detail(&wrong_key).unwrap_err();
+        assert!(matches!(err, EncryptionError::Open(_)));
+    }
+
+    #[test]
+    fn decrypting_an_unsealed_document_is_a_typed_error() {
+        let plain = cot_to_document(&event("uid-1", "<detail/>"), "peer");
+        let err = plain.decrypt_detail(&KEY).unwrap_err();
+        assert_eq!(err, EncryptionError::NotEncrypted);
+    }
+
+    #[test]
+    fn ciphertext_cannot_be_transplanted_onto_a_different_event() {
+        let a = cot_to_document(&event("uid-a", "<detail><contact callsign=\"A\"/></detail>"), "peer");
+        let b = cot_to_document(&event("uid-b", "<detail><contact callsign=\"B\"/></detail>"), "peer");
+
+        let encrypted_a = a.encrypt_detail(&KEY).unwrap();
+        let sealed_r = match &encrypted_a {
+            CotDocument::Generic(doc) => doc.r.get(ENCRYPTED_DETAIL_KEY).cloned().unwrap(),
+            _ => unreachable!(),
+        };
+
+        let mut transplanted = match b.encrypt_detail(&KEY).unwrap() {
+            CotDocument::Generic(doc) => doc,
+            _ => unreachable!(),
+        };
+        transplanted.r.insert(ENCRYPTED_DETAIL_KEY.to_string(), sealed_r);
+
+        let err = CotDocument::Generic(transplanted).decrypt_detail(&KEY).unwrap_err();
+        assert!(matches!(err, EncryptionError::Open(_)));
+    }
+}
@@ -0,0 +1,350 @@
+//! Injection-safe typed filter AST compiled to DQL `WHERE` clauses.
+//!
+//! The CRUD helpers in [`crate::ditto_sync`] used to build `WHERE` clauses by
+//! interpolating caller strings straight into the query (`format!("... WHERE
+//! {}", q)`, `format!("_id = '{}'", id)`), so a single quote or a `)` in an id
+//! or callsign could break the query or inject arbitrary DQL. [`Filter`]
+//! replaces that with a small typed AST — a [`Field`] is validated against an
+//! identifier pattern at construction and a [`Value`] is rendered through
+//! [`Value::to_dql`], which escapes string literals rather than trusting the
+//! caller. No raw user bytes reach the compiled DQL string unescaped.
+
+use std::fmt;
+
+/// Error returned when a [`Field`] name fails validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidFieldName(String);
+
+impl fmt::Display for InvalidFieldName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid field name '{}': must match ^[A-Za-z_][A-Za-z0-9_.]*$",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidFieldName {}
+
+/// A DQL column or (dot-separated) path reference, validated at construction
+/// so it can only ever contain `[A-Za-z0-9_.]` starting with a letter or
+/// underscore — never operators, quotes, or whitespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field(String);
+
+impl Field {
+    /// Validates and wraps a field name. Rejects anything that isn't a
+    /// leading letter/underscore followed by letters, digits, underscores,
+    /// or dots (e.g. `contact.callsign`, `_id`, `r.speed`).
+    pub fn new(name: impl Into<String>) -> Result<Self, InvalidFieldName> {
+        let name = name.into();
+        let mut chars = name.chars();
+        let starts_ok = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+        let rest_ok = chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if starts_ok && rest_ok {
+            Ok(Self(name))
+        } else {
+            Err(InvalidFieldName(name))
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Escapes a string for embedding in a single-quoted DQL literal by doubling
+/// embedded single quotes (the standard SQL-style escape). Exposed directly
+/// for the rare caller that needs to splice an escaped literal into DQL it
+/// otherwise builds by hand, rather than going through [`Value::to_dql`].
+pub fn dql_escape(s: &str) -> String {
+    s.replace('\'', "''")
+}
+
+/// A DQL literal. Strings are escaped on render; numbers, bools, and `null`
+/// are rendered verbatim since they can't carry injected DQL. [`Value::Json`]
+/// carries a whole JSON sub-document (object, array, or scalar) for fields
+/// whose shape isn't a single primitive — strings inside it are already
+/// quote-escaped by `serde_json`, the same trust level
+/// [`crate::ditto_sync::insert_document`] already gives a document body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A string literal, escaped and single-quoted on render.
+    Str(String),
+    /// A numeric literal.
+    Number(f64),
+    /// A boolean literal.
+    Bool(bool),
+    /// SQL/DQL `NULL`.
+    Null,
+    /// A JSON object/array/scalar literal, rendered as-is.
+    Json(serde_json::Value),
+}
+
+impl Value {
+    /// Renders this value as a DQL literal, escaping string content via
+    /// [`dql_escape`].
+    pub fn to_dql(&self) -> String {
+        match self {
+            Value::Str(s) => format!("'{}'", dql_escape(s)),
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Null => "NULL".to_string(),
+            Value::Json(v) => v.to_string(),
+        }
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    /// Converts a decoded JSON value into the matching [`Value`] variant.
+    /// Primitives map to their typed counterpart so they still render
+    /// through the same escaping a hand-built [`Value::Str`] would get;
+    /// objects and arrays fall through to [`Value::Json`].
+    fn from(json: serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::String(s) => Value::Str(s),
+            serde_json::Value::Number(n) => match n.as_f64() {
+                Some(f) => Value::Number(f),
+                None => Value::Json(serde_json::Value::Number(n)),
+            },
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Null => Value::Null,
+            other => Value::Json(other),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+/// A typed filter expression, compiled to a DQL `WHERE` clause fragment by
+/// [`Filter::to_dql`]. Every leaf carries a validated [`Field`] and an
+/// escaped [`Value`], so the compiled string can't be broken out of by
+/// caller-controlled content.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// `field = value`
+    Eq(Field, Value),
+    /// `field != value`
+    Ne(Field, Value),
+    /// `field > value`
+    Gt(Field, Value),
+    /// `field >= value`
+    Ge(Field, Value),
+    /// `field < value`
+    Lt(Field, Value),
+    /// `field <= value`
+    Le(Field, Value),
+    /// `field IN (values...)`
+    In(Field, Vec<Value>),
+    /// `field LIKE '%needle%'` (needle is escaped, not treated as a pattern)
+    Contains(Field, String),
+    /// `(left AND right)`
+    And(Box<Filter>, Box<Filter>),
+    /// `(left OR right)`
+    Or(Box<Filter>, Box<Filter>),
+    /// `NOT (inner)`
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Compiles this filter to a DQL `WHERE` clause fragment (without the
+    /// `WHERE` keyword).
+    pub fn to_dql(&self) -> String {
+        match self {
+            Filter::Eq(field, value) => format!("{field} = {}", value.to_dql()),
+            Filter::Ne(field, value) => format!("{field} != {}", value.to_dql()),
+            Filter::Gt(field, value) => format!("{field} > {}", value.to_dql()),
+            Filter::Ge(field, value) => format!("{field} >= {}", value.to_dql()),
+            Filter::Lt(field, value) => format!("{field} < {}", value.to_dql()),
+            Filter::Le(field, value) => format!("{field} <= {}", value.to_dql()),
+            Filter::In(field, values) => {
+                let rendered = values
+                    .iter()
+                    .map(Value::to_dql)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{field} IN ({rendered})")
+            }
+            Filter::Contains(field, needle) => {
+                // Backslash must be escaped before '%'/'_' are, or a needle
+                // ending in a literal backslash followed by one of those
+                // would have its doubled backslash read back as a single
+                // literal one, leaving the wildcard escape neutralized.
+                let escaped = needle
+                    .replace('\\', "\\\\")
+                    .replace('\'', "''")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                format!("{field} LIKE '%{escaped}%'")
+            }
+            Filter::And(left, right) => format!("({} AND {})", left.to_dql(), right.to_dql()),
+            Filter::Or(left, right) => format!("({} OR {})", left.to_dql(), right.to_dql()),
+            Filter::Not(inner) => format!("NOT ({})", inner.to_dql()),
+        }
+    }
+
+    /// Builds `_id = <id>`, escaped the same way as any other string value.
+    /// `_id` is a fixed, always-valid field name, so this never fails.
+    pub fn by_id(id: impl Into<String>) -> Self {
+        #[allow(clippy::unwrap_used)]
+        Filter::Eq(Field::new("_id").unwrap(), Value::Str(id.into()))
+    }
+}
+
+/// Sort direction for an `ORDER BY` column in a paged query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// `ASC`
+    Asc,
+    /// `DESC`
+    Desc,
+}
+
+impl Order {
+    /// Renders this direction as its DQL keyword.
+    pub fn to_dql(self) -> &'static str {
+        match self {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_field_names_accepted() {
+        assert!(Field::new("_id").is_ok());
+        assert!(Field::new("contact.callsign").is_ok());
+        assert!(Field::new("a1_b2").is_ok());
+    }
+
+    #[test]
+    fn invalid_field_names_rejected() {
+        assert!(Field::new("1id").is_err());
+        assert!(Field::new("id; DROP").is_err());
+        assert!(Field::new("id = 1 OR 1=1").is_err());
+        assert!(Field::new("").is_err());
+    }
+
+    #[test]
+    fn string_value_escapes_embedded_quotes() {
+        let value = Value::from("O'Brien");
+        assert_eq!(value.to_dql(), "'O''Brien'");
+    }
+
+    #[test]
+    fn numbers_and_bools_render_verbatim() {
+        assert_eq!(Value::from(12.5).to_dql(), "12.5");
+        assert_eq!(Value::from(true).to_dql(), "true");
+        assert_eq!(Value::Null.to_dql(), "NULL");
+    }
+
+    #[test]
+    fn eq_filter_compiles_with_escaping() {
+        let filter = Filter::Eq(Field::new("callsign").unwrap(), Value::from("it's-me"));
+        assert_eq!(filter.to_dql(), "callsign = 'it''s-me'");
+    }
+
+    #[test]
+    fn by_id_escapes_quote_injection_attempt() {
+        let filter = Filter::by_id("abc' OR '1'='1");
+        assert_eq!(filter.to_dql(), "_id = 'abc'' OR ''1''=''1'");
+    }
+
+    #[test]
+    fn in_filter_joins_values() {
+        let filter = Filter::In(
+            Field::new("status").unwrap(),
+            vec![Value::from("a"), Value::from("b")],
+        );
+        assert_eq!(filter.to_dql(), "status IN ('a', 'b')");
+    }
+
+    #[test]
+    fn and_or_not_compose_with_parens() {
+        let a = Filter::Eq(Field::new("a").unwrap(), Value::from(1.0));
+        let b = Filter::Eq(Field::new("b").unwrap(), Value::from(2.0));
+        let and = Filter::And(Box::new(a.clone()), Box::new(b.clone()));
+        assert_eq!(and.to_dql(), "(a = 1 AND b = 2)");
+        let or = Filter::Or(Box::new(a.clone()), Box::new(b.clone()));
+        assert_eq!(or.to_dql(), "(a = 1 OR b = 2)");
+        let not = Filter::Not(Box::new(a));
+        assert_eq!(not.to_dql(), "NOT (a = 1)");
+    }
+
+    #[test]
+    fn order_renders_dql_keyword() {
+        assert_eq!(Order::Asc.to_dql(), "ASC");
+        assert_eq!(Order::Desc.to_dql(), "DESC");
+    }
+
+    #[test]
+    fn contains_escapes_quotes_and_percent_wildcards() {
+        let filter = Filter::Contains(Field::new("e").unwrap(), "100% it's".to_string());
+        assert_eq!(filter.to_dql(), "e LIKE '%100\\% it''s%'");
+    }
+
+    #[test]
+    fn contains_escapes_the_underscore_wildcard_too() {
+        let filter = Filter::Contains(Field::new("e").unwrap(), "a_b".to_string());
+        assert_eq!(filter.to_dql(), "e LIKE '%a\\_b%'");
+    }
+
+    #[test]
+    fn contains_escapes_a_literal_backslash_before_percent_and_underscore() {
+        // If backslash weren't escaped first, the LIKE engine would read the
+        // doubled backslash below back as one literal backslash, consuming
+        // the escape meant for '%' and '_' and leaving them as live wildcards.
+        let filter = Filter::Contains(Field::new("e").unwrap(), "a\\%b\\_c".to_string());
+        assert_eq!(filter.to_dql(), "e LIKE '%a\\\\\\%b\\\\\\_c%'");
+    }
+
+    #[test]
+    fn json_object_values_render_as_raw_json_literals() {
+        let value = Value::from(serde_json::json!({"a": 1, "b": "it's"}));
+        assert_eq!(value.to_dql(), r#"{"a":1,"b":"it's"}"#);
+    }
+
+    #[test]
+    fn json_scalars_convert_to_their_typed_variant() {
+        assert_eq!(Value::from(serde_json::json!("hi")), Value::Str("hi".to_string()));
+        assert_eq!(Value::from(serde_json::json!(2.5)), Value::Number(2.5));
+        assert_eq!(Value::from(serde_json::json!(true)), Value::Bool(true));
+        assert_eq!(Value::from(serde_json::Value::Null), Value::Null);
+    }
+
+    #[test]
+    fn dql_escape_doubles_single_quotes() {
+        assert_eq!(dql_escape("it's a test"), "it''s a test");
+    }
+}
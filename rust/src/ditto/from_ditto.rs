@@ -1,11 +1,120 @@
 //! Convert CotDocument back into CotEvent for round-trip tests
 use crate::cot_events::CotEvent;
+use crate::ditto::coordinate_layout::{classify_event_type, CeSource, CoordinateLayout};
 use crate::ditto::r_field_flattening::unflatten_document_r_field;
-use crate::ditto::{CotDocument, File, FileRValue};
+use crate::ditto::{CommonDocumentFields, CotDocument, CoordinateLayoutRegistry, File, FileRValue};
+use crate::error::CotError;
 use chrono::{DateTime, TimeZone, Utc};
 use serde_json::Value;
 use std::collections::HashMap;
 
+/// Finds the `<detail>...</detail>` section of `xml` by tokenizing it with
+/// `quick-xml` and matching the `detail` element by name, rather than
+/// string-slicing on the literal `"<detail>"`/`"</detail>"` substrings. Unlike
+/// a substring search, this still finds the element when the writer emits
+/// attributes (`<detail foo="bar">`) or a self-closing form (`<detail/>`),
+/// and returns `None` instead of silently falling back to the whole document
+/// when no `detail` element is present.
+pub(crate) fn extract_detail_section(xml: &str) -> Option<String> {
+    use quick_xml::events::Event;
+    use quick_xml::Reader;
+
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(false);
+    let mut buf = Vec::new();
+    let mut detail_depth = 0i32;
+    let mut detail_start = None;
+
+    loop {
+        let pos_before = reader.buffer_position();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"detail" => {
+                if detail_depth == 0 {
+                    detail_start = Some(pos_before);
+                }
+                detail_depth += 1;
+            }
+            Ok(Event::End(e)) if e.name().as_ref() == b"detail" => {
+                detail_depth -= 1;
+                if detail_depth == 0 {
+                    return Some(xml[detail_start?..reader.buffer_position()].to_string());
+                }
+            }
+            Ok(Event::Empty(e)) if e.name().as_ref() == b"detail" && detail_depth == 0 => {
+                return Some(xml[pos_before..reader.buffer_position()].to_string());
+            }
+            Ok(Event::Eof) => return None,
+            Err(_) => return None,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Which wire format [`parse_flexible_timestamp`] recognized a value as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// `2023-01-01T12:00:00Z`.
+    Rfc3339,
+    /// The ISO 8601 "basic" profile some CoT producers emit instead of RFC
+    /// 3339, e.g. `20230101T120000Z`.
+    Iso8601Basic,
+    /// `Sun, 01 Jan 2023 12:00:00 +0000`.
+    Rfc2822,
+    /// A bare integer, interpreted as whole seconds since the Unix epoch.
+    EpochSeconds,
+    /// A bare integer, interpreted as milliseconds since the Unix epoch.
+    EpochMillis,
+    /// A bare integer, interpreted as microseconds since the Unix epoch.
+    EpochMicros,
+}
+
+/// Parses `s` as a timestamp, trying progressively looser formats in order —
+/// RFC 3339, ISO 8601 basic (`20230101T120000Z`), RFC 2822, then a bare
+/// numeric epoch value disambiguated by magnitude — and returning the first
+/// one that succeeds along with which format matched. Used for detail-map
+/// string timestamps (`_time`/`_start`/`_stale`), which, unlike the schema's
+/// `b`/`n`/`o` fields, carry no fixed unit and come from whatever CoT
+/// producer wrote them.
+pub fn parse_flexible_timestamp(s: &str) -> Option<(DateTime<Utc>, TimestampFormat)> {
+    let s = s.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some((dt.with_timezone(&Utc), TimestampFormat::Rfc3339));
+    }
+    if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ") {
+        return Some((Utc.from_utc_datetime(&naive), TimestampFormat::Iso8601Basic));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(s) {
+        return Some((dt.with_timezone(&Utc), TimestampFormat::Rfc2822));
+    }
+    if let Ok(epoch) = s.parse::<i64>() {
+        return Some(timestamp_from_epoch_magnitude(epoch));
+    }
+
+    None
+}
+
+/// Interprets a bare integer as an epoch seconds/millis/micros value by its
+/// magnitude: fewer than 11 digits is seconds (good through the year 2286),
+/// fewer than 14 is milliseconds, otherwise microseconds.
+fn timestamp_from_epoch_magnitude(epoch: i64) -> (DateTime<Utc>, TimestampFormat) {
+    let digits = epoch.unsigned_abs();
+
+    if digits < 10_000_000_000 {
+        let dt = Utc.timestamp_opt(epoch, 0).single().unwrap_or_else(Utc::now);
+        (dt, TimestampFormat::EpochSeconds)
+    } else if digits < 10_000_000_000_000 {
+        let dt = Utc.timestamp_millis_opt(epoch).single().unwrap_or_else(Utc::now);
+        (dt, TimestampFormat::EpochMillis)
+    } else {
+        let secs = epoch / 1_000_000;
+        let nanos = ((epoch % 1_000_000) * 1_000) as u32;
+        let dt = Utc.timestamp_opt(secs, nanos).single().unwrap_or_else(Utc::now);
+        (dt, TimestampFormat::EpochMicros)
+    }
+}
+
 /// Convert a CotDocument back into a CotEvent (best-effort mapping for round-trip tests)
 ///
 /// This function attempts to reconstruct a CotEvent from a CotDocument with the best possible
@@ -13,6 +122,16 @@ use std::collections::HashMap;
 /// differences in the data models.
 pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
     use crate::cot_events::Point;
+    use crate::ditto::schema_version::{negotiate, schema_version_of, Compatibility, CURRENT};
+
+    let remote_version = schema_version_of(doc);
+    if negotiate(CURRENT, remote_version) == Compatibility::Incompatible {
+        log::warn!(
+            "cot_event_from_ditto_document: decoding a CotDocument stamped with schema \
+             version {remote_version:?}, newer than this build's {CURRENT:?}; degrading to \
+             a best-effort CotEvent"
+        );
+    }
 
     /// Helper to safely convert microseconds since epoch to DateTime<Utc>
     fn micros_to_datetime(micros: i64) -> DateTime<Utc> {
@@ -22,9 +141,9 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
 
         // Use timestamp_opt for better error handling
         Utc.timestamp_opt(secs, nanos).single().unwrap_or_else(|| {
-            eprintln!(
-                "WARN: Failed to convert timestamp {} microseconds to DateTime<Utc>",
-                micros
+            log::warn!(
+                "cot_event_from_ditto_document: failed to convert timestamp {micros} \
+                 microseconds to DateTime<Utc>"
             );
             Utc::now()
         })
@@ -53,14 +172,9 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
                 let flat = flat_cot_event_from_ditto(doc);
                 // Extract only the <detail>...</detail> section
                 let xml = to_cot_xml(&flat);
-                // Find <detail>...</detail>
-                let start = xml.find("<detail>").unwrap_or(0);
-                let end = xml
-                    .find("</detail>")
-                    .map(|i| i + "</detail>".len())
-                    .unwrap_or(xml.len());
-                xml[start..end].to_string()
+                extract_detail_section(&xml).unwrap_or(xml)
             },
+            tz_offset_secs: None,
         },
         CotDocument::Chat(chat) => CotEvent {
             version: chat.g.clone(), // g = VERSION
@@ -83,13 +197,9 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
                 use crate::xml_writer::to_cot_xml;
                 let flat = flat_cot_event_from_ditto(doc);
                 let xml = to_cot_xml(&flat);
-                let start = xml.find("<detail>").unwrap_or(0);
-                let end = xml
-                    .find("</detail>")
-                    .map(|i| i + "</detail>".len())
-                    .unwrap_or(xml.len());
-                xml[start..end].to_string()
+                extract_detail_section(&xml).unwrap_or(xml)
             },
+            tz_offset_secs: None,
         },
         CotDocument::File(file) => {
             // Extract the ce value from the _ce field in the detail map if it exists
@@ -105,28 +215,24 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
             } else {
                 // Fallback to _time field in detail map
                 match &file.r.get("_time") {
-                    Some(FileRValue::String(s)) => match s.parse::<DateTime<Utc>>() {
-                        Ok(dt) => dt,
-                        Err(_) => Utc::now(),
-                    },
+                    Some(FileRValue::String(s)) => {
+                        parse_flexible_timestamp(s).map(|(dt, _)| dt).unwrap_or_else(Utc::now)
+                    }
                     _ => Utc::now(),
                 }
             };
 
             let start = match &file.r.get("_start") {
                 Some(FileRValue::String(s)) => {
-                    match s.parse::<DateTime<Utc>>() {
-                        Ok(dt) => dt,
-                        Err(_) => time, // Default to time if parsing fails
-                    }
+                    parse_flexible_timestamp(s).map(|(dt, _)| dt).unwrap_or(time)
                 }
                 _ => time, // Default to time if not found
             };
 
             let stale = match &file.r.get("_stale") {
-                Some(FileRValue::String(s)) => match s.parse::<DateTime<Utc>>() {
-                    Ok(dt) => dt,
-                    Err(_) => {
+                Some(FileRValue::String(s)) => match parse_flexible_timestamp(s) {
+                    Some((dt, _)) => dt,
+                    None => {
                         if file.o.unwrap_or(0.0) != 0.0 {
                             micros_to_datetime(file.o.unwrap_or(0.0) as i64)
                         } else {
@@ -151,10 +257,20 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
             detail_map.remove("_stale");
 
             // Create a modified File with the cleaned detail map for XML generation
-            let modified_file = CotDocument::File(File {
+            let mut modified_file_inner = File {
                 r: detail_map,
                 ..file.clone()
-            });
+            };
+            // Re-emit a canonical `<fileshare>` element from the attachment
+            // metadata rather than whatever raw shape it happened to carry
+            // before the attachment was fetched and its size resolved.
+            if let Some(attachment) = doc.attachments().into_iter().next() {
+                crate::ditto::attachment::apply_attachment_to_file(
+                    &mut modified_file_inner,
+                    &attachment,
+                );
+            }
+            let modified_file = CotDocument::File(modified_file_inner);
 
             CotEvent {
                 version: file.g.clone(), // g = VERSION
@@ -177,13 +293,9 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
                     use crate::xml_writer::to_cot_xml;
                     let flat = flat_cot_event_from_ditto(&modified_file); // Use the modified document
                     let xml = to_cot_xml(&flat);
-                    let start = xml.find("<detail>").unwrap_or(0);
-                    let end = xml
-                        .find("</detail>")
-                        .map(|i| i + "</detail>".len())
-                        .unwrap_or(xml.len());
-                    xml[start..end].to_string()
+                    extract_detail_section(&xml).unwrap_or(xml)
                 },
+                tz_offset_secs: None,
             }
         }
         CotDocument::MapItem(map_item) => CotEvent {
@@ -207,13 +319,9 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
                 use crate::xml_writer::to_cot_xml;
                 let flat = flat_cot_event_from_ditto(doc);
                 let xml = to_cot_xml(&flat);
-                let start = xml.find("<detail>").unwrap_or(0);
-                let end = xml
-                    .find("</detail>")
-                    .map(|i| i + "</detail>".len())
-                    .unwrap_or(xml.len());
-                xml[start..end].to_string()
+                extract_detail_section(&xml).unwrap_or(xml)
             },
+            tz_offset_secs: None,
         },
         CotDocument::Generic(generic) => CotEvent {
             version: generic.g.clone(), // g = VERSION
@@ -230,21 +338,779 @@ pub fn cot_event_from_ditto_document(doc: &CotDocument) -> CotEvent {
                 ce: generic.h.unwrap_or(0.0),  // h = CE
                 le: generic.k.unwrap_or(0.0),  // k = LE
             },
-            // Serialize detail map to XML for round-trip fidelity
-            detail: {
-                use crate::ditto::from_ditto_util::flat_cot_event_from_ditto;
-                use crate::xml_writer::to_cot_xml;
-                let flat = flat_cot_event_from_ditto(doc);
-                let xml = to_cot_xml(&flat);
-                let start = xml.find("<detail>").unwrap_or(0);
-                let end = xml
-                    .find("</detail>")
-                    .map(|i| i + "</detail>".len())
-                    .unwrap_or(xml.len());
-                xml[start..end].to_string()
-            },
+            // Prefer the verbatim `_detail_raw` shadow field when present —
+            // it round-trips the original detail byte-for-byte, unlike the
+            // `r` map reconstruction below, which is lossy by construction.
+            detail: generic
+                ._detail_raw
+                .as_ref()
+                .and_then(|raw| serde_json::from_str::<String>(raw.get()).ok())
+                .unwrap_or_else(|| {
+                    use crate::ditto::from_ditto_util::flat_cot_event_from_ditto;
+                    use crate::xml_writer::to_cot_xml;
+                    let flat = flat_cot_event_from_ditto(doc);
+                    let xml = to_cot_xml(&flat);
+                    extract_detail_section(&xml).unwrap_or(xml)
+                }),
+            tz_offset_secs: None,
         },
+        CotDocument::Unknown(unknown) => {
+            let raw = &unknown.raw;
+            let get_str = |key: &str| raw.get(key).and_then(Value::as_str).unwrap_or("").to_string();
+            let get_f64 = |key: &str| raw.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+
+            CotEvent {
+                version: get_str("g"),
+                uid: raw
+                    .get("_id")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+                event_type: get_str("w"),
+                time: micros_to_datetime(get_f64("b") as i64),
+                start: micros_to_datetime(get_f64("n") as i64),
+                stale: micros_to_datetime(get_f64("o") as i64),
+                how: get_str("p"),
+                point: Point {
+                    lat: get_f64("j"),
+                    lon: get_f64("l"),
+                    hae: get_f64("i"),
+                    ce: get_f64("h"),
+                    le: get_f64("k"),
+                },
+                // Serialize detail map to XML for round-trip fidelity, same as
+                // every other variant, rather than a special-cased fallback.
+                detail: {
+                    use crate::ditto::from_ditto_util::flat_cot_event_from_ditto;
+                    use crate::xml_writer::to_cot_xml;
+                    let flat = flat_cot_event_from_ditto(doc);
+                    let xml = to_cot_xml(&flat);
+                    extract_detail_section(&xml).unwrap_or(xml)
+                },
+                tz_offset_secs: None,
+            }
+        }
+    }
+}
+
+/// [`cot_event_from_ditto_document`] with the detail-section encoding made
+/// explicit instead of hard-coded to CoT XML. Every field other than
+/// `detail` is reconstructed identically; `detail` is produced by `encoder`
+/// instead of [`to_cot_xml`](crate::xml_writer::to_cot_xml), so a caller that
+/// only needs the structured detail map can skip XML re-serialization
+/// entirely via [`JsonDetailEncoder`](super::detail_encoding::JsonDetailEncoder)
+/// or [`MsgPackDetailEncoder`](super::detail_encoding::MsgPackDetailEncoder).
+///
+/// [`CotDocument::Generic`]'s verbatim `_detail_raw` shadow field is only
+/// honored for [`DetailEncoding::Xml`](super::detail_encoding::DetailEncoding::Xml)
+/// — it's a raw XML string, so it has no meaningful JSON or MessagePack form.
+pub fn cot_event_from_ditto_document_with(
+    doc: &CotDocument,
+    encoder: &dyn crate::ditto::detail_encoding::DetailEncoder,
+) -> Result<CotEvent, CotError> {
+    use crate::cot_events::Point;
+    use crate::ditto::detail_encoding::DetailEncoding;
+    use crate::ditto::from_ditto_util::flat_cot_event_from_ditto;
+
+    fn micros_to_datetime(micros: i64) -> DateTime<Utc> {
+        let secs = micros / 1_000_000;
+        let nanos = ((micros % 1_000_000) * 1_000) as u32;
+        Utc.timestamp_opt(secs, nanos).single().unwrap_or_else(Utc::now)
+    }
+
+    match doc {
+        CotDocument::Api(api) => Ok(CotEvent {
+            version: api.g.clone(),
+            uid: api.id.clone(),
+            event_type: api.w.clone(),
+            time: micros_to_datetime(api.b as i64),
+            start: micros_to_datetime(api.n.unwrap_or(0.0) as i64),
+            stale: micros_to_datetime(api.o.unwrap_or(0.0) as i64),
+            how: api.p.clone(),
+            point: Point {
+                lat: api.j.unwrap_or(0.0),
+                lon: api.l.unwrap_or(0.0),
+                hae: api.i.unwrap_or(0.0),
+                ce: api.h.unwrap_or(0.0),
+                le: api.k.unwrap_or(0.0),
+            },
+            detail: encoder.encode(&flat_cot_event_from_ditto(doc))?,
+            tz_offset_secs: None,
+        }),
+        CotDocument::Chat(chat) => Ok(CotEvent {
+            version: chat.g.clone(),
+            uid: chat.id.clone(),
+            event_type: chat.w.clone(),
+            time: micros_to_datetime(chat.b as i64),
+            start: micros_to_datetime(chat.n.unwrap_or(0.0) as i64),
+            stale: micros_to_datetime(chat.o.unwrap_or(0.0) as i64),
+            how: chat.p.clone(),
+            point: Point {
+                lat: chat.j.unwrap_or(0.0),
+                lon: chat.l.unwrap_or(0.0),
+                hae: chat.i.unwrap_or(0.0),
+                ce: chat.h.unwrap_or(0.0),
+                le: chat.k.unwrap_or(0.0),
+            },
+            detail: encoder.encode(&flat_cot_event_from_ditto(doc))?,
+            tz_offset_secs: None,
+        }),
+        CotDocument::File(file) => {
+            let ce = match &file.r.get("_ce") {
+                Some(FileRValue::Number(n)) => *n,
+                Some(FileRValue::String(s)) => s.parse::<f64>().unwrap_or(0.0),
+                _ => 0.0,
+            };
+
+            let time = if file.b != 0.0 {
+                micros_to_datetime(file.b as i64)
+            } else {
+                match &file.r.get("_time") {
+                    Some(FileRValue::String(s)) => {
+                        parse_flexible_timestamp(s).map(|(dt, _)| dt).unwrap_or_else(Utc::now)
+                    }
+                    _ => Utc::now(),
+                }
+            };
+            let start = match &file.r.get("_start") {
+                Some(FileRValue::String(s)) => {
+                    parse_flexible_timestamp(s).map(|(dt, _)| dt).unwrap_or(time)
+                }
+                _ => time,
+            };
+            let stale = match &file.r.get("_stale") {
+                Some(FileRValue::String(s)) => match parse_flexible_timestamp(s) {
+                    Some((dt, _)) => dt,
+                    None => {
+                        if file.o.unwrap_or(0.0) != 0.0 {
+                            micros_to_datetime(file.o.unwrap_or(0.0) as i64)
+                        } else {
+                            time + chrono::Duration::minutes(30)
+                        }
+                    }
+                },
+                _ => {
+                    if file.o.unwrap_or(0.0) != 0.0 {
+                        micros_to_datetime(file.o.unwrap_or(0.0) as i64)
+                    } else {
+                        time + chrono::Duration::minutes(30)
+                    }
+                }
+            };
+
+            let mut detail_map = file.r.clone();
+            detail_map.remove("_ce");
+            detail_map.remove("_time");
+            detail_map.remove("_start");
+            detail_map.remove("_stale");
+
+            let mut modified_file_inner = File {
+                r: detail_map,
+                ..file.clone()
+            };
+            if let Some(attachment) = doc.attachments().into_iter().next() {
+                crate::ditto::attachment::apply_attachment_to_file(
+                    &mut modified_file_inner,
+                    &attachment,
+                );
+            }
+            let modified_file = CotDocument::File(modified_file_inner);
+
+            Ok(CotEvent {
+                version: file.g.clone(),
+                uid: file.id.clone(),
+                event_type: file.w.clone(),
+                time,
+                start,
+                stale,
+                how: file.p.clone(),
+                point: Point {
+                    lat: file.j.unwrap_or(0.0),
+                    lon: file.l.unwrap_or(0.0),
+                    hae: file.i.unwrap_or(0.0),
+                    ce,
+                    le: file.k.unwrap_or(0.0),
+                },
+                detail: encoder.encode(&flat_cot_event_from_ditto(&modified_file))?,
+                tz_offset_secs: None,
+            })
+        }
+        CotDocument::MapItem(map_item) => Ok(CotEvent {
+            version: map_item.g.clone(),
+            uid: map_item.id.clone(),
+            event_type: map_item.w.clone(),
+            time: micros_to_datetime(map_item.b as i64),
+            start: micros_to_datetime(map_item.n.unwrap_or(0.0) as i64),
+            stale: micros_to_datetime(map_item.o.unwrap_or(0.0) as i64),
+            how: map_item.p.clone(),
+            point: Point {
+                lat: map_item.j.unwrap_or(0.0),
+                lon: map_item.l.unwrap_or(0.0),
+                hae: map_item.i.unwrap_or(0.0),
+                ce: map_item.h.unwrap_or(0.0),
+                le: map_item.k.unwrap_or(0.0),
+            },
+            detail: encoder.encode(&flat_cot_event_from_ditto(doc))?,
+            tz_offset_secs: None,
+        }),
+        CotDocument::Generic(generic) => {
+            let raw_xml = match encoder.encoding() {
+                DetailEncoding::Xml => generic
+                    ._detail_raw
+                    .as_ref()
+                    .and_then(|raw| serde_json::from_str::<String>(raw.get()).ok()),
+                _ => None,
+            };
+            let detail = match raw_xml {
+                Some(raw) => raw,
+                None => encoder.encode(&flat_cot_event_from_ditto(doc))?,
+            };
+            Ok(CotEvent {
+                version: generic.g.clone(),
+                uid: generic.id.clone(),
+                event_type: generic.w.clone(),
+                time: micros_to_datetime(generic.b as i64),
+                start: micros_to_datetime(generic.n.unwrap_or(0.0) as i64),
+                stale: micros_to_datetime(generic.o.unwrap_or(0.0) as i64),
+                how: generic.p.clone(),
+                point: Point {
+                    lat: generic.j.unwrap_or(0.0),
+                    lon: generic.l.unwrap_or(0.0),
+                    hae: generic.i.unwrap_or(0.0),
+                    ce: generic.h.unwrap_or(0.0),
+                    le: generic.k.unwrap_or(0.0),
+                },
+                detail,
+                tz_offset_secs: None,
+            })
+        }
+        CotDocument::Unknown(unknown) => {
+            let raw = &unknown.raw;
+            let get_str =
+                |key: &str| raw.get(key).and_then(Value::as_str).unwrap_or("").to_string();
+            let get_f64 = |key: &str| raw.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+
+            Ok(CotEvent {
+                version: get_str("g"),
+                uid: raw
+                    .get("_id")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+                event_type: get_str("w"),
+                time: micros_to_datetime(get_f64("b") as i64),
+                start: micros_to_datetime(get_f64("n") as i64),
+                stale: micros_to_datetime(get_f64("o") as i64),
+                how: get_str("p"),
+                point: Point {
+                    lat: get_f64("j"),
+                    lon: get_f64("l"),
+                    hae: get_f64("i"),
+                    ce: get_f64("h"),
+                    le: get_f64("k"),
+                },
+                detail: encoder.encode(&flat_cot_event_from_ditto(doc))?,
+                tz_offset_secs: None,
+            })
+        }
+    }
+}
+
+/// How operationally meaningful a dropped field's loss is, for
+/// [`ConversionReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LossSeverity {
+    /// Reserved or bookkeeping data; losing it doesn't change the event's
+    /// operational meaning (e.g. an always-empty schema letter field).
+    Info,
+    /// Data a downstream consumer might reasonably expect to survive the
+    /// round-trip; worth a caller's attention but not necessarily action.
+    Warning,
+    /// A populated field with genuine operational content was dropped.
+    Error,
+}
+
+/// A field that was populated on the source side of a conversion but has no
+/// representation on the produced side, so it was dropped instead of
+/// silently discarded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LossyField {
+    /// Dotted path of the field within the source document.
+    pub path: String,
+    /// How much the drop affects operational use.
+    pub severity: LossSeverity,
+    /// Human-readable explanation of why it couldn't be carried forward.
+    pub note: String,
+}
+
+/// The outcome of a [`CotDocument::to_cot_event_with_report`](super::CotDocument::to_cot_event_with_report)
+/// conversion: every field [`to_cot_event_with_report`] found populated on
+/// the source `CotDocument` with no representation in the produced
+/// `CotEvent`, instead of the plain [`cot_event_from_ditto_document`]
+/// silently discarding them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionReport {
+    /// Populated source fields that had nowhere to go in the conversion.
+    pub dropped: Vec<LossyField>,
+}
+
+impl ConversionReport {
+    /// Whether the conversion preserved every populated field.
+    pub fn is_lossless(&self) -> bool {
+        self.dropped.is_empty()
+    }
+
+    /// The worst severity among the dropped fields, if any.
+    pub fn max_severity(&self) -> Option<LossSeverity> {
+        self.dropped.iter().map(|f| f.severity).max()
+    }
+}
+
+/// Schema fields that [`cot_event_from_ditto_document`] already consumes to
+/// build the `CotEvent` it returns (directly, or via the `r`/`_detail_raw`
+/// detail reconstruction) — anything else present and populated on the
+/// source document has no home in `CotEvent`.
+const CONSUMED_DOCUMENT_FIELDS: &[&str] = &[
+    "_id", "a", "b", "_c", "_r", "_v", "d", "g", "h", "i", "j", "k", "l", "n", "o", "p", "r", "w",
+    "_detail_raw",
+];
+
+/// Whether a JSON value represents a populated field, as opposed to a
+/// default/absent one not worth reporting as dropped.
+fn is_populated(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+        Value::Bool(_) => true,
+        Value::Number(n) => n.as_f64().map_or(true, |f| f != 0.0),
+    }
+}
+
+/// Every field populated on `doc` that [`cot_event_from_ditto_document`] has
+/// no representation for, as `(path, value)` pairs in sorted path order.
+///
+/// Shared by [`to_cot_event_with_report`] (which reports these as dropped)
+/// and [`to_cot_event_with_options`] (which, when asked to, instead carries
+/// them into the produced event's `<detail>` so a concurrent peer's write to
+/// a field this schema version doesn't map isn't erased by the round trip).
+fn unmapped_document_fields(doc: &CotDocument) -> Vec<(String, Value)> {
+    let Ok(Value::Object(obj)) = serde_json::to_value(doc) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<_> = obj.keys().cloned().collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter(|path| !CONSUMED_DOCUMENT_FIELDS.contains(&path.as_str()))
+        .filter_map(|path| {
+            let value = obj[&path].clone();
+            is_populated(&value).then_some((path, value))
+        })
+        .collect()
+}
+
+/// Like [`cot_event_from_ditto_document`], but also returns a
+/// [`ConversionReport`] enumerating every populated field on `doc` that the
+/// conversion had no representation for, following a "check field exists
+/// before copy" discipline rather than letting the lossy default path
+/// (`unwrap_or(0.0)`, `unwrap_or_default()`, and similar) mask the drop.
+pub fn to_cot_event_with_report(doc: &CotDocument) -> (CotEvent, ConversionReport) {
+    use crate::ditto::schema_version::{negotiate, schema_version_of, Compatibility, CURRENT};
+
+    let event = cot_event_from_ditto_document(doc);
+    let mut report = ConversionReport::default();
+
+    let remote_version = schema_version_of(doc);
+    if negotiate(CURRENT, remote_version) == Compatibility::Incompatible {
+        report.dropped.push(LossyField {
+            path: "schema_version".to_string(),
+            severity: LossSeverity::Warning,
+            note: format!(
+                "document stamped with schema version {remote_version:?}, newer than this build's {CURRENT:?}; some fields may have degraded to best-effort defaults"
+            ),
+        });
+    }
+
+    if let CotDocument::Unknown(_) = doc {
+        report.dropped.push(LossyField {
+            path: "*".to_string(),
+            severity: LossSeverity::Warning,
+            note: "document shape didn't match any known schema variant; fidelity wasn't checked field-by-field".to_string(),
+        });
+        return (event, report);
+    }
+
+    for (path, _) in unmapped_document_fields(doc) {
+        report.dropped.push(LossyField {
+            path,
+            severity: LossSeverity::Info,
+            note: "populated field has no CotEvent representation".to_string(),
+        });
+    }
+
+    (event, report)
+}
+
+/// Options controlling how [`to_cot_event_with_options`] reconstructs a
+/// [`CotEvent`] from a [`CotDocument`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConversionOptions {
+    /// If true, fields populated on `doc` with no `CotEvent` representation
+    /// (the same set [`to_cot_event_with_report`] reports as dropped) are
+    /// carried through into the produced event's `<detail>` as preserved
+    /// extension elements instead of being silently discarded. This matters
+    /// for CRDT documents: a field another peer concurrently wrote that this
+    /// schema version doesn't map shouldn't vanish just because this peer
+    /// round-tripped the document through `CotEvent` and back. Off by
+    /// default, matching
+    /// [`CotDocument::to_cot_event`](super::CotDocument::to_cot_event)'s
+    /// existing lossy behavior.
+    pub preserve_unknown_detail: bool,
+}
+
+/// The element name preserved-but-unmapped fields are rendered under in
+/// `<detail>`, double-underscore-prefixed the same way
+/// [`to_cot_xml`](crate::xml_writer::to_cot_xml) already synthesizes
+/// `__group` for data with no standard CoT tag of its own.
+const UNMAPPED_FIELD_ELEMENT: &str = "__unmapped_field";
+
+/// Renders one `(path, value)` pair from [`unmapped_document_fields`] as a
+/// self-closing `<detail>` element, going through quick-xml's `Writer` the
+/// same as [`to_cot_xml`](crate::xml_writer::to_cot_xml) so a value
+/// containing `&`, `<`, `>`, or `"` still round-trips as valid XML.
+fn render_unmapped_field_element(path: &str, value: &Value) -> String {
+    use quick_xml::events::{BytesStart, Event};
+    use quick_xml::Writer;
+    use std::io::Cursor;
+
+    let rendered_value = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut element = BytesStart::new(UNMAPPED_FIELD_ELEMENT);
+    element.push_attribute(("key", path));
+    element.push_attribute(("value", rendered_value.as_str()));
+    writer
+        .write_event(Event::Empty(element))
+        .expect("writing to an in-memory buffer never fails");
+    String::from_utf8(writer.into_inner().into_inner())
+        .expect("quick-xml only writes valid UTF-8")
+}
+
+/// Splices `element` into `detail` just before its closing `</detail>` tag
+/// (or appends it if `detail` has no closing tag, which shouldn't happen for
+/// anything [`cot_event_from_ditto_document`] produced).
+fn splice_into_detail(detail: &str, element: &str) -> String {
+    match detail.find("</detail>") {
+        Some(idx) => format!("{}{element}{}", &detail[..idx], &detail[idx..]),
+        None => format!("{detail}{element}"),
+    }
+}
+
+/// Like [`cot_event_from_ditto_document`], but governed by
+/// [`ConversionOptions`]. With [`ConversionOptions::preserve_unknown_detail`]
+/// set, every field [`unmapped_document_fields`] finds populated with no
+/// `CotEvent` representation is rendered into the produced event's
+/// `<detail>` as a [`UNMAPPED_FIELD_ELEMENT`] element instead of being
+/// dropped. [`to_cot_event`] is the `ConversionOptions::default()` shortcut.
+pub fn to_cot_event_with_options(doc: &CotDocument, options: ConversionOptions) -> CotEvent {
+    let mut event = cot_event_from_ditto_document(doc);
+
+    if options.preserve_unknown_detail {
+        for (path, value) in unmapped_document_fields(doc) {
+            let element = render_unmapped_field_element(&path, &value);
+            event.detail = splice_into_detail(&event.detail, &element);
+        }
+    }
+
+    event
+}
+
+/// One value that [`try_cot_event_from_ditto_document`]/
+/// [`try_cot_event_from_flattened_json`] couldn't convert exactly, recorded
+/// instead of silently degrading to a default the way
+/// [`cot_event_from_ditto_document`] does. Distinct from [`LossyField`]:
+/// [`LossyField`] reports a source field with no `CotEvent` representation
+/// at all, while [`RoundTripIssue`] reports a field that *is* mapped but
+/// whose stored value failed to parse or convert.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoundTripIssue {
+    /// A `b`/`n`/`o` microseconds-since-epoch value was out of
+    /// [`DateTime<Utc>`]'s representable range.
+    TimestampOutOfRange {
+        /// Which field the value came from (`"b"`, `"n"`, or `"o"`).
+        field: &'static str,
+        /// The unconvertible microseconds value.
+        micros: i64,
+    },
+    /// A string-encoded timestamp (`_time`/`_start`/`_stale`) matched none
+    /// of the formats [`parse_flexible_timestamp`] accepts.
+    TimestampParseFailed {
+        /// Which detail field the value came from.
+        field: &'static str,
+        /// The unparseable raw value.
+        value: String,
+    },
+    /// A coordinate field (`j`/`l`/`i`/`h`/`k`) was absent, so the point
+    /// defaulted to `0.0` instead of carrying a real value. Also reported
+    /// for an absent `n`/`o` timestamp in flattened JSON, which falls back
+    /// to the event's `time`.
+    MissingCoordinate {
+        /// Which coordinate field was absent.
+        field: &'static str,
+    },
+    /// The `_ce` detail field was present but not parseable as a number.
+    CeParseFailed {
+        /// The unparseable raw value.
+        value: String,
+    },
+    /// The `<detail>...</detail>` section couldn't be located in the
+    /// reconstructed XML, so `detail` fell back to the whole document.
+    DetailExtractionFailed,
+    /// No [`CoordinateLayout`](super::coordinate_layout::CoordinateLayout) was
+    /// registered for the event's classified document kind, so coordinates
+    /// fell back to the built-in "other documents" mapping. Unreachable with
+    /// [`CoordinateLayoutRegistry::with_builtins`] — only possible if a
+    /// caller supplies a custom, incompletely populated registry.
+    UnknownCoordinateLayout {
+        /// The classified document kind with no registered layout.
+        kind: crate::ditto::coordinate_layout::DocumentKind,
+    },
+}
+
+/// Every [`RoundTripIssue`] found while reconstructing a [`CotEvent`] via
+/// [`try_cot_event_from_ditto_document`]/[`try_cot_event_from_flattened_json`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundTripReport {
+    /// Issues found, in the order they were encountered.
+    pub issues: Vec<RoundTripIssue>,
+}
+
+impl RoundTripReport {
+    /// Whether the conversion hit no [`RoundTripIssue`] at all.
+    pub fn is_exact(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Like the `micros_to_datetime` closure in [`cot_event_from_ditto_document`],
+/// but records a [`RoundTripIssue::TimestampOutOfRange`] instead of printing
+/// a warning and substituting [`Utc::now`].
+fn micros_to_datetime_tracked(
+    field: &'static str,
+    micros: i64,
+    issues: &mut Vec<RoundTripIssue>,
+) -> DateTime<Utc> {
+    let secs = micros / 1_000_000;
+    let nanos = ((micros % 1_000_000) * 1_000) as u32;
+    Utc.timestamp_opt(secs, nanos).single().unwrap_or_else(|| {
+        issues.push(RoundTripIssue::TimestampOutOfRange { field, micros });
+        Utc::now()
+    })
+}
+
+/// Reads a coordinate field, recording a [`RoundTripIssue::MissingCoordinate`]
+/// instead of silently defaulting to `0.0` when it's absent.
+fn coord(field: &'static str, value: Option<f64>, issues: &mut Vec<RoundTripIssue>) -> f64 {
+    value.unwrap_or_else(|| {
+        issues.push(RoundTripIssue::MissingCoordinate { field });
+        0.0
+    })
+}
+
+/// Extracts the `<detail>...</detail>` section from `doc`'s reconstructed
+/// XML via [`extract_detail_section`], recording a
+/// [`RoundTripIssue::DetailExtractionFailed`] instead of silently falling
+/// back to the whole document when no `detail` element is found.
+fn extract_detail(doc: &CotDocument, issues: &mut Vec<RoundTripIssue>) -> String {
+    use crate::ditto::from_ditto_util::flat_cot_event_from_ditto;
+    use crate::xml_writer::to_cot_xml;
+
+    let flat = flat_cot_event_from_ditto(doc);
+    let xml = to_cot_xml(&flat);
+
+    extract_detail_section(&xml).unwrap_or_else(|| {
+        issues.push(RoundTripIssue::DetailExtractionFailed);
+        xml
+    })
+}
+
+/// Like [`cot_event_from_ditto_document`], but fails on a missing `_id`
+/// instead of silently producing an empty-uid event, and reports every
+/// timestamp/coordinate/`_ce` value that couldn't be converted exactly as a
+/// [`RoundTripIssue`] instead of defaulting it without a trace.
+pub fn try_cot_event_from_ditto_document(
+    doc: &CotDocument,
+) -> Result<(CotEvent, RoundTripReport), CotError> {
+    use crate::cot_events::Point;
+
+    if doc.common_id().unwrap_or("").is_empty() {
+        return Err(CotError::MissingField("_id".to_string()));
     }
+
+    let mut issues = Vec::new();
+
+    let event = match doc {
+        CotDocument::File(file) => {
+            let ce = match file.r.get("_ce") {
+                Some(FileRValue::Number(n)) => *n,
+                Some(FileRValue::String(s)) => match s.parse::<f64>() {
+                    Ok(n) => n,
+                    Err(_) => {
+                        issues.push(RoundTripIssue::CeParseFailed { value: s.clone() });
+                        0.0
+                    }
+                },
+                _ => 0.0,
+            };
+
+            let time = if file.b != 0.0 {
+                micros_to_datetime_tracked("b", file.b as i64, &mut issues)
+            } else {
+                match file.r.get("_time") {
+                    Some(FileRValue::String(s)) => parse_flexible_timestamp(s)
+                        .map(|(dt, _)| dt)
+                        .unwrap_or_else(|| {
+                            issues.push(RoundTripIssue::TimestampParseFailed {
+                                field: "_time",
+                                value: s.clone(),
+                            });
+                            Utc::now()
+                        }),
+                    _ => Utc::now(),
+                }
+            };
+
+            let start = match file.r.get("_start") {
+                Some(FileRValue::String(s)) => {
+                    parse_flexible_timestamp(s).map(|(dt, _)| dt).unwrap_or_else(|| {
+                        issues.push(RoundTripIssue::TimestampParseFailed {
+                            field: "_start",
+                            value: s.clone(),
+                        });
+                        time
+                    })
+                }
+                _ => time,
+            };
+
+            let stale = match file.r.get("_stale") {
+                Some(FileRValue::String(s)) => {
+                    parse_flexible_timestamp(s).map(|(dt, _)| dt).unwrap_or_else(|| {
+                        issues.push(RoundTripIssue::TimestampParseFailed {
+                            field: "_stale",
+                            value: s.clone(),
+                        });
+                        time + chrono::Duration::minutes(30)
+                    })
+                }
+                _ => time + chrono::Duration::minutes(30),
+            };
+
+            CotEvent {
+                version: file.g.clone(),
+                uid: file.id.clone(),
+                event_type: file.w.clone(),
+                time,
+                start,
+                stale,
+                how: file.p.clone(),
+                point: Point {
+                    lat: coord("j", file.j, &mut issues),
+                    lon: coord("l", file.l, &mut issues),
+                    hae: coord("i", file.i, &mut issues),
+                    ce,
+                    le: coord("k", file.k, &mut issues),
+                },
+                detail: extract_detail(doc, &mut issues),
+                tz_offset_secs: None,
+            }
+        }
+        CotDocument::Api(d) => CotEvent {
+            version: d.g.clone(),
+            uid: d.id.clone(),
+            event_type: d.w.clone(),
+            time: micros_to_datetime_tracked("b", d.b as i64, &mut issues),
+            start: micros_to_datetime_tracked("n", d.n.unwrap_or(0.0) as i64, &mut issues),
+            stale: micros_to_datetime_tracked("o", d.o.unwrap_or(0.0) as i64, &mut issues),
+            how: d.p.clone(),
+            point: Point {
+                lat: coord("j", d.j, &mut issues),
+                lon: coord("l", d.l, &mut issues),
+                hae: coord("i", d.i, &mut issues),
+                ce: coord("h", d.h, &mut issues),
+                le: coord("k", d.k, &mut issues),
+            },
+            detail: extract_detail(doc, &mut issues),
+            tz_offset_secs: None,
+        },
+        CotDocument::Chat(d) => CotEvent {
+            version: d.g.clone(),
+            uid: d.id.clone(),
+            event_type: d.w.clone(),
+            time: micros_to_datetime_tracked("b", d.b as i64, &mut issues),
+            start: micros_to_datetime_tracked("n", d.n.unwrap_or(0.0) as i64, &mut issues),
+            stale: micros_to_datetime_tracked("o", d.o.unwrap_or(0.0) as i64, &mut issues),
+            how: d.p.clone(),
+            point: Point {
+                lat: coord("j", d.j, &mut issues),
+                lon: coord("l", d.l, &mut issues),
+                hae: coord("i", d.i, &mut issues),
+                ce: coord("h", d.h, &mut issues),
+                le: coord("k", d.k, &mut issues),
+            },
+            detail: extract_detail(doc, &mut issues),
+            tz_offset_secs: None,
+        },
+        CotDocument::MapItem(d) => CotEvent {
+            version: d.g.clone(),
+            uid: d.id.clone(),
+            event_type: d.w.clone(),
+            time: micros_to_datetime_tracked("b", d.b as i64, &mut issues),
+            start: micros_to_datetime_tracked("n", d.n.unwrap_or(0.0) as i64, &mut issues),
+            stale: micros_to_datetime_tracked("o", d.o.unwrap_or(0.0) as i64, &mut issues),
+            how: d.p.clone(),
+            point: Point {
+                lat: coord("j", d.j, &mut issues),
+                lon: coord("l", d.l, &mut issues),
+                hae: coord("i", d.i, &mut issues),
+                ce: coord("h", d.h, &mut issues),
+                le: coord("k", d.k, &mut issues),
+            },
+            detail: extract_detail(doc, &mut issues),
+            tz_offset_secs: None,
+        },
+        CotDocument::Generic(d) => CotEvent {
+            version: d.g.clone(),
+            uid: d.id.clone(),
+            event_type: d.w.clone(),
+            time: micros_to_datetime_tracked("b", d.b as i64, &mut issues),
+            start: micros_to_datetime_tracked("n", d.n.unwrap_or(0.0) as i64, &mut issues),
+            stale: micros_to_datetime_tracked("o", d.o.unwrap_or(0.0) as i64, &mut issues),
+            how: d.p.clone(),
+            point: Point {
+                lat: coord("j", d.j, &mut issues),
+                lon: coord("l", d.l, &mut issues),
+                hae: coord("i", d.i, &mut issues),
+                ce: coord("h", d.h, &mut issues),
+                le: coord("k", d.k, &mut issues),
+            },
+            detail: d
+                ._detail_raw
+                .as_ref()
+                .and_then(|raw| serde_json::from_str::<String>(raw.get()).ok())
+                .unwrap_or_else(|| extract_detail(doc, &mut issues)),
+            tz_offset_secs: None,
+        },
+        CotDocument::Unknown(_) => cot_event_from_ditto_document(doc),
+    };
+
+    Ok((event, RoundTripReport { issues }))
 }
 
 /// Convert a flattened JSON document (with r_* fields) back into a CotEvent
@@ -259,9 +1125,9 @@ pub fn cot_event_from_flattened_json(json_value: &Value) -> CotEvent {
 
         // Use timestamp_opt for better error handling
         Utc.timestamp_opt(secs, nanos).single().unwrap_or_else(|| {
-            eprintln!(
-                "WARN: Failed to convert timestamp {} microseconds to DateTime<Utc>",
-                micros
+            log::warn!(
+                "cot_event_from_flattened_json: failed to convert timestamp {micros} \
+                 microseconds to DateTime<Utc>"
             );
             Utc::now()
         })
@@ -294,19 +1160,22 @@ pub fn cot_event_from_flattened_json(json_value: &Value) -> CotEvent {
         let get_opt_f64 =
             |key: &str| -> Option<f64> { document_map.get(key).and_then(|v| v.as_f64()) };
 
-        // Determine document type to use correct coordinate mappings
+        // Determine the document kind to use the right coordinate mapping,
+        // via the same event-type tables the forward (`cot_to_document`)
+        // path dispatches on, so the two can't drift apart.
         let event_type = get_string("w");
-        let is_map_item = event_type.contains("a-u-r-loc-g")
-            || event_type.contains("a-f-G-U-C")
-            || event_type.contains("a-f-G-U")
-            || event_type.contains("a-f-G-U-I")
-            || event_type.contains("a-f-G-U-T")
-            || event_type.contains("a-u-S")
-            || event_type.contains("a-u-A")
-            || event_type.contains("a-u-G");
-        let is_file = event_type.contains("file")
-            || event_type.contains("attachment")
-            || event_type.contains("b-f-t-file");
+        let layout_registry = CoordinateLayoutRegistry::with_builtins();
+        let layout = *layout_registry
+            .layout_for(classify_event_type(&event_type))
+            .expect("CoordinateLayoutRegistry::with_builtins registers every DocumentKind");
+        let get_ce = |layout: &CoordinateLayout| -> f64 {
+            match layout.ce_source {
+                CeSource::TopLevelField(field) => get_opt_f64(field).unwrap_or(0.0),
+                CeSource::DetailField(field) => {
+                    r_map.get(field).and_then(|v| v.as_f64()).unwrap_or(0.0)
+                }
+            }
+        };
 
         CotEvent {
             version: get_string("g"), // g = VERSION
@@ -317,47 +1186,56 @@ pub fn cot_event_from_flattened_json(json_value: &Value) -> CotEvent {
                 if b != 0.0 {
                     micros_to_datetime(b as i64) // b = TIME in microseconds
                 } else {
-                    Utc::now()
+                    // Fallback to the _time detail field, same as the typed
+                    // File arm in `cot_event_from_ditto_document`.
+                    r_map
+                        .get("_time")
+                        .and_then(Value::as_str)
+                        .and_then(parse_flexible_timestamp)
+                        .map(|(dt, _)| dt)
+                        .unwrap_or_else(Utc::now)
                 }
             },
             start: {
                 let n = get_opt_f64("n").unwrap_or(0.0);
-                micros_to_datetime(n as i64)
+                if n != 0.0 {
+                    micros_to_datetime(n as i64)
+                } else {
+                    r_map
+                        .get("_start")
+                        .and_then(Value::as_str)
+                        .and_then(parse_flexible_timestamp)
+                        .map(|(dt, _)| dt)
+                        .unwrap_or_else(Utc::now)
+                }
             },
             stale: {
                 let o = get_opt_f64("o").unwrap_or(0.0);
-                micros_to_datetime(o as i64)
+                if o != 0.0 {
+                    micros_to_datetime(o as i64)
+                } else {
+                    r_map
+                        .get("_stale")
+                        .and_then(Value::as_str)
+                        .and_then(parse_flexible_timestamp)
+                        .map(|(dt, _)| dt)
+                        .unwrap_or_else(Utc::now)
+                }
             },
             how: get_string("p"),
             point: Point {
-                lat: if is_map_item {
-                    get_opt_f64("j").unwrap_or(0.0)
-                } else {
-                    // For file and other documents, lat is stored in h field
-                    get_opt_f64("h").unwrap_or(0.0)
-                },
-                lon: if is_map_item {
-                    get_opt_f64("l").unwrap_or(0.0)
-                } else {
-                    // For file and other documents, lon is stored in i field
-                    get_opt_f64("i").unwrap_or(0.0)
-                },
-                hae: if is_map_item {
-                    get_opt_f64("i").unwrap_or(0.0)
-                } else {
-                    // For file and other documents, hae is stored in j field
-                    get_opt_f64("j").unwrap_or(0.0)
-                },
-                ce: if is_file {
-                    // For file documents, CE is stored in r__ce field, but after unflattening it would be in r_map["_ce"]
-                    r_map.get("_ce").and_then(|v| v.as_f64()).unwrap_or(0.0)
-                } else {
-                    get_opt_f64("h").unwrap_or(0.0) // h = CE for other document types
-                },
+                lat: get_opt_f64(layout.lat_field).unwrap_or(0.0),
+                lon: get_opt_f64(layout.lon_field).unwrap_or(0.0),
+                hae: get_opt_f64(layout.hae_field).unwrap_or(0.0),
+                ce: get_ce(&layout),
                 le: get_opt_f64("k").unwrap_or(0.0),
             },
-            // Reconstruct detail XML from the unflattened r_map
-            detail: {
+            // Prefer the verbatim `_detail_raw` shadow field when present —
+            // it round-trips the original detail byte-for-byte, unlike the
+            // r_map reconstruction below, which is lossy by construction.
+            detail: if let Some(raw) = document_map.get("_detail_raw").and_then(Value::as_str) {
+                raw.to_string()
+            } else {
                 use crate::model::FlatCotEvent;
                 use crate::xml_writer::to_cot_xml;
 
@@ -400,40 +1278,23 @@ pub fn cot_event_from_flattened_json(json_value: &Value) -> CotEvent {
                             .to_rfc3339()
                     },
                     how: get_string("p"),
-                    lat: if is_map_item {
-                        get_opt_f64("j").unwrap_or(0.0)
-                    } else {
-                        get_opt_f64("h").unwrap_or(0.0)
-                    },
-                    lon: if is_map_item {
-                        get_opt_f64("l").unwrap_or(0.0)
-                    } else {
-                        get_opt_f64("i").unwrap_or(0.0)
-                    },
-                    hae: if is_map_item {
-                        get_opt_f64("i").unwrap_or(0.0)
-                    } else {
-                        get_opt_f64("j").unwrap_or(0.0)
-                    },
-                    ce: if is_file {
-                        get_opt_f64("r__ce").unwrap_or(0.0)
-                    } else {
-                        get_opt_f64("h").unwrap_or(0.0) // h = CE for other types
-                    },
+                    lat: get_opt_f64(layout.lat_field).unwrap_or(0.0),
+                    lon: get_opt_f64(layout.lon_field).unwrap_or(0.0),
+                    hae: get_opt_f64(layout.hae_field).unwrap_or(0.0),
+                    ce: get_ce(&layout),
                     le: get_opt_f64("k").unwrap_or(0.0),
                     callsign: None,      // Comes from detail_extra
                     group_name: None,    // Comes from detail_extra
-                    detail_extra: r_map, // Use the properly reconstructed r_map!
+                    group_role: None,    // Comes from detail_extra
+                    speed: None,         // Comes from detail_extra
+                    course: None,        // Comes from detail_extra
+                    tz_offset_secs: None,
+                    detail_extra: r_map.into_iter().collect(), // the reconstructed r_map!
+                    extra_attrs: Default::default(),
                 };
 
                 let xml = to_cot_xml(&flat);
-                // Extract only the <detail>...</detail> section
-                let start = xml.find("<detail>").unwrap_or(0);
-                let end = xml
-                    .find("</detail>")
-                    .map(|i| i + "</detail>".len())
-                    .unwrap_or(xml.len());
-                xml[start..end].to_string()
+                extract_detail_section(&xml).unwrap_or(xml)
             },
         }
     } else {
@@ -454,6 +1315,591 @@ pub fn cot_event_from_flattened_json(json_value: &Value) -> CotEvent {
                 le: 0.0,
             },
             detail: "<detail></detail>".to_string(),
+            tz_offset_secs: None,
+        }
+    }
+}
+
+/// Like [`try_cot_event_from_flattened_json`], but accumulates every
+/// missing required field instead of stopping at `_id`, so a caller
+/// hand-authoring or repairing a flattened document gets every problem in
+/// one report. Returns the single underlying error when exactly one check
+/// fails, or [`CotError::Multiple`] when more than one does.
+pub fn cot_event_from_flattened_json_checked(json_value: &Value) -> Result<CotEvent, CotError> {
+    let Value::Object(obj) = json_value else {
+        return Err(CotError::MissingField("(document root)".to_string()));
+    };
+
+    let mut errors = Vec::new();
+
+    if obj.get("_id").and_then(Value::as_str).unwrap_or("").is_empty() {
+        errors.push(CotError::MissingField("_id".to_string()));
+    }
+    if obj.get("w").and_then(Value::as_str).unwrap_or("").is_empty() {
+        errors.push(CotError::MissingField("w".to_string()));
+    }
+    for field in ["b", "n", "o"] {
+        if obj.get(field).and_then(Value::as_f64).is_none() {
+            errors.push(CotError::MissingField(field.to_string()));
+        }
+    }
+
+    match errors.len() {
+        0 => Ok(cot_event_from_flattened_json(json_value)),
+        1 => Err(errors.remove(0)),
+        _ => Err(CotError::Multiple(errors)),
+    }
+}
+
+/// Like [`cot_event_from_flattened_json`], but fails on a missing/empty
+/// `_id` instead of silently producing one, and reports every
+/// timestamp/coordinate value that couldn't be converted exactly as a
+/// [`RoundTripIssue`] instead of defaulting it without a trace.
+pub fn try_cot_event_from_flattened_json(
+    json_value: &Value,
+) -> Result<(CotEvent, RoundTripReport), CotError> {
+    use crate::cot_events::Point;
+
+    let Value::Object(obj) = json_value else {
+        return Err(CotError::MissingField("_id".to_string()));
+    };
+
+    let uid = obj.get("_id").and_then(Value::as_str).unwrap_or("");
+    if uid.is_empty() {
+        return Err(CotError::MissingField("_id".to_string()));
+    }
+
+    let mut issues = Vec::new();
+    let mut document_map: HashMap<String, Value> = obj.clone().into_iter().collect();
+    let r_map = unflatten_document_r_field(&mut document_map);
+
+    let get_string = |key: &str| -> String {
+        document_map
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let get_opt_f64 = |key: &str| -> Option<f64> { document_map.get(key).and_then(|v| v.as_f64()) };
+
+    let event_type = get_string("w");
+    let layout_registry = CoordinateLayoutRegistry::with_builtins();
+    let kind = classify_event_type(&event_type);
+    let layout = match layout_registry.layout_for(kind) {
+        Some(layout) => *layout,
+        None => {
+            issues.push(RoundTripIssue::UnknownCoordinateLayout { kind });
+            CoordinateLayout {
+                lat_field: "h",
+                lon_field: "i",
+                hae_field: "j",
+                ce_source: CeSource::TopLevelField("h"),
+            }
+        }
+    };
+
+    let time = match get_opt_f64("b") {
+        Some(b) if b != 0.0 => micros_to_datetime_tracked("b", b as i64, &mut issues),
+        _ => Utc::now(),
+    };
+    let start = match get_opt_f64("n") {
+        Some(n) => micros_to_datetime_tracked("n", n as i64, &mut issues),
+        None => {
+            issues.push(RoundTripIssue::MissingCoordinate { field: "n" });
+            time
+        }
+    };
+    let stale = match get_opt_f64("o") {
+        Some(o) => micros_to_datetime_tracked("o", o as i64, &mut issues),
+        None => {
+            issues.push(RoundTripIssue::MissingCoordinate { field: "o" });
+            time
+        }
+    };
+
+    let lat = coord(layout.lat_field, get_opt_f64(layout.lat_field), &mut issues);
+    let lon = coord(layout.lon_field, get_opt_f64(layout.lon_field), &mut issues);
+    let hae = coord(layout.hae_field, get_opt_f64(layout.hae_field), &mut issues);
+    let ce = match layout.ce_source {
+        CeSource::TopLevelField(field) => coord(field, get_opt_f64(field), &mut issues),
+        CeSource::DetailField(field) => match r_map.get(field) {
+            Some(v) => match v.as_f64() {
+                Some(n) => n,
+                None => {
+                    issues.push(RoundTripIssue::CeParseFailed { value: v.to_string() });
+                    0.0
+                }
+            },
+            None => coord(field, None, &mut issues),
+        },
+    };
+    let le = coord("k", get_opt_f64("k"), &mut issues);
+
+    let detail = if let Some(raw) = document_map.get("_detail_raw").and_then(Value::as_str) {
+        raw.to_string()
+    } else {
+        use crate::model::FlatCotEvent;
+        use crate::xml_writer::to_cot_xml;
+
+        let flat = FlatCotEvent {
+            uid: uid.to_string(),
+            type_: event_type.clone(),
+            time: time.to_rfc3339(),
+            start: start.to_rfc3339(),
+            stale: stale.to_rfc3339(),
+            how: get_string("p"),
+            lat,
+            lon,
+            hae,
+            ce,
+            le,
+            callsign: None,
+            group_name: None,
+            group_role: None,
+            speed: None,
+            course: None,
+            tz_offset_secs: None,
+            detail_extra: r_map.into_iter().collect(),
+            extra_attrs: Default::default(),
+        };
+
+        let xml = to_cot_xml(&flat);
+        extract_detail_section(&xml).unwrap_or_else(|| {
+            issues.push(RoundTripIssue::DetailExtractionFailed);
+            xml
+        })
+    };
+
+    let event = CotEvent {
+        version: get_string("g"),
+        uid: uid.to_string(),
+        event_type,
+        time,
+        start,
+        stale,
+        how: get_string("p"),
+        point: Point { lat, lon, hae, ce, le },
+        detail,
+        tz_offset_secs: None,
+    };
+
+    Ok((event, RoundTripReport { issues }))
+}
+
+#[cfg(test)]
+mod conversion_report_tests {
+    use super::*;
+    use crate::ditto::{MapItem, MapItemRValue};
+
+    fn minimal_map_item() -> MapItem {
+        MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: None,
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: CURRENT_SCHEMA_VERSION,
+            e: "".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: None,
+            k: None,
+            l: None,
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r: HashMap::new(),
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        }
+    }
+
+    const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+    #[test]
+    fn lossless_when_every_unmapped_field_is_empty() {
+        let doc = CotDocument::MapItem(minimal_map_item());
+        let (_, report) = to_cot_event_with_report(&doc);
+        assert!(report.is_lossless(), "unexpected drops: {:?}", report.dropped);
+        assert_eq!(report.max_severity(), None);
+    }
+
+    #[test]
+    fn reports_a_populated_field_with_no_cot_event_home() {
+        let mut item = minimal_map_item();
+        item.c = Some("Map Item Title".to_string());
+        let doc = CotDocument::MapItem(item);
+
+        let (_, report) = to_cot_event_with_report(&doc);
+
+        assert!(!report.is_lossless());
+        assert!(report.dropped.iter().any(|f| f.path == "c"));
+        assert_eq!(report.max_severity(), Some(LossSeverity::Info));
+    }
+
+    #[test]
+    fn reports_a_newer_schema_version_as_a_warning() {
+        let mut item = minimal_map_item();
+        item.d_v = CURRENT_SCHEMA_VERSION + 1;
+        let doc = CotDocument::MapItem(item);
+
+        let (_, report) = to_cot_event_with_report(&doc);
+
+        assert!(report.dropped.iter().any(|f| f.path == "schema_version"));
+        assert_eq!(report.max_severity(), Some(LossSeverity::Warning));
+    }
+
+    #[test]
+    fn populated_r_field_detail_does_not_count_as_dropped() {
+        let mut item = minimal_map_item();
+        item.r.insert(
+            "test_key".to_string(),
+            MapItemRValue::String("test_value".to_string()),
+        );
+        let doc = CotDocument::MapItem(item);
+
+        let (_, report) = to_cot_event_with_report(&doc);
+
+        assert!(report.is_lossless(), "unexpected drops: {:?}", report.dropped);
+    }
+
+    #[test]
+    fn default_options_drop_unmapped_fields_like_to_cot_event() {
+        let mut item = minimal_map_item();
+        item.c = Some("Map Item Title".to_string());
+        let doc = CotDocument::MapItem(item);
+
+        let event = to_cot_event_with_options(&doc, ConversionOptions::default());
+
+        assert!(!event.detail.contains(UNMAPPED_FIELD_ELEMENT));
+    }
+
+    #[test]
+    fn preserve_unknown_detail_carries_unmapped_fields_into_detail() {
+        let mut item = minimal_map_item();
+        item.c = Some("Map Item Title".to_string());
+        let doc = CotDocument::MapItem(item);
+
+        let options = ConversionOptions {
+            preserve_unknown_detail: true,
+        };
+        let event = to_cot_event_with_options(&doc, options);
+
+        assert!(event.detail.ends_with("</detail>"));
+        assert!(event
+            .detail
+            .contains(r#"<__unmapped_field key="c" value="Map Item Title"/>"#));
+    }
+
+    #[test]
+    fn preserve_unknown_detail_escapes_special_characters() {
+        let mut item = minimal_map_item();
+        item.c = Some("AT&T <tower>".to_string());
+        let doc = CotDocument::MapItem(item);
+
+        let options = ConversionOptions {
+            preserve_unknown_detail: true,
+        };
+        let event = to_cot_event_with_options(&doc, options);
+
+        assert!(event.detail.contains("AT&amp;T &lt;tower&gt;"));
+        assert!(!event.detail.contains("AT&T <tower>"));
+    }
+}
+
+#[cfg(test)]
+mod round_trip_tests {
+    use super::*;
+    use crate::ditto::{MapItem, MapItemRValue};
+
+    #[test]
+    fn extract_detail_section_finds_a_plain_detail_element() {
+        let xml = "<event><detail><remarks>hi</remarks></detail></event>";
+        assert_eq!(
+            extract_detail_section(xml),
+            Some("<detail><remarks>hi</remarks></detail>".to_string())
+        );
+    }
+
+    #[test]
+    fn extract_detail_section_finds_detail_with_attributes() {
+        let xml = r#"<event><detail foo="bar"><remarks>hi</remarks></detail></event>"#;
+        assert_eq!(
+            extract_detail_section(xml),
+            Some(r#"<detail foo="bar"><remarks>hi</remarks></detail>"#.to_string())
+        );
+    }
+
+    #[test]
+    fn extract_detail_section_finds_a_self_closing_detail_element() {
+        let xml = r#"<event><detail foo="bar"/></event>"#;
+        assert_eq!(
+            extract_detail_section(xml),
+            Some(r#"<detail foo="bar"/>"#.to_string())
+        );
+    }
+
+    #[test]
+    fn extract_detail_section_returns_none_when_absent() {
+        let xml = "<event><remarks>hi</remarks></event>";
+        assert_eq!(extract_detail_section(xml), None);
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_accepts_rfc3339() {
+        let (dt, format) = parse_flexible_timestamp("2023-01-01T12:00:00Z").unwrap();
+        assert_eq!(format, TimestampFormat::Rfc3339);
+        assert_eq!(dt.to_rfc3339(), "2023-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_accepts_iso8601_basic() {
+        let (dt, format) = parse_flexible_timestamp("20230101T120000Z").unwrap();
+        assert_eq!(format, TimestampFormat::Iso8601Basic);
+        assert_eq!(dt.to_rfc3339(), "2023-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_accepts_rfc2822() {
+        let (dt, format) = parse_flexible_timestamp("Sun, 01 Jan 2023 12:00:00 +0000").unwrap();
+        assert_eq!(format, TimestampFormat::Rfc2822);
+        assert_eq!(dt.to_rfc3339(), "2023-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_detects_epoch_seconds() {
+        let (dt, format) = parse_flexible_timestamp("1672574400").unwrap();
+        assert_eq!(format, TimestampFormat::EpochSeconds);
+        assert_eq!(dt.to_rfc3339(), "2023-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_detects_epoch_millis() {
+        let (dt, format) = parse_flexible_timestamp("1672574400000").unwrap();
+        assert_eq!(format, TimestampFormat::EpochMillis);
+        assert_eq!(dt.to_rfc3339(), "2023-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_detects_epoch_micros() {
+        let (dt, format) = parse_flexible_timestamp("1672574400000000").unwrap();
+        assert_eq!(format, TimestampFormat::EpochMicros);
+        assert_eq!(dt.to_rfc3339(), "2023-01-01T12:00:00+00:00");
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_rejects_garbage() {
+        assert_eq!(parse_flexible_timestamp("not a timestamp"), None);
+    }
+
+    fn map_item() -> MapItem {
+        MapItem {
+            id: "UID-1".to_string(),
+            a: "peer-a".to_string(),
+            b: 1_622_548_800_000_000.0,
+            c: None,
+            d: "UID-1".to_string(),
+            d_c: 0,
+            d_r: false,
+            d_v: 2,
+            source: None,
+            e: String::new(),
+            f: None,
+            g: "2.0".to_string(),
+            h: Some(5.0),
+            i: Some(10.0),
+            j: Some(35.0),
+            k: Some(2.0),
+            l: Some(-118.0),
+            n: Some(1_622_548_800_000_000.0),
+            o: Some(1_622_548_900_000_000.0),
+            p: "h-g-i-g-o".to_string(),
+            q: String::new(),
+            r: HashMap::new(),
+            s: String::new(),
+            t: String::new(),
+            u: String::new(),
+            v: String::new(),
+            w: "a-f-G-U-C".to_string(),
         }
     }
+
+    #[test]
+    fn exact_conversion_reports_no_issues() {
+        let doc = CotDocument::MapItem(map_item());
+        let (event, report) = try_cot_event_from_ditto_document(&doc).unwrap();
+        assert!(report.is_exact(), "unexpected issues: {:?}", report.issues);
+        assert_eq!(event.uid, "UID-1");
+    }
+
+    #[test]
+    fn missing_id_is_a_missing_field_error() {
+        let mut item = map_item();
+        item.id = String::new();
+        let doc = CotDocument::MapItem(item);
+        let err = try_cot_event_from_ditto_document(&doc).unwrap_err();
+        assert!(matches!(err, CotError::MissingField(field) if field == "_id"));
+    }
+
+    #[test]
+    fn missing_coordinate_is_reported_instead_of_silently_defaulted() {
+        let mut item = map_item();
+        item.j = None;
+        let doc = CotDocument::MapItem(item);
+        let (event, report) = try_cot_event_from_ditto_document(&doc).unwrap();
+        assert_eq!(event.point.lat, 0.0);
+        assert!(report
+            .issues
+            .contains(&RoundTripIssue::MissingCoordinate { field: "j" }));
+    }
+
+    #[test]
+    fn unparseable_ce_in_map_item_r_is_not_a_round_trip_issue() {
+        let mut item = map_item();
+        item.r
+            .insert("_ce".to_string(), MapItemRValue::String("n/a".to_string()));
+        let doc = CotDocument::MapItem(item);
+        // MapItem has no _ce special-casing, so this just exercises that a
+        // non-numeric r-map value elsewhere doesn't break the conversion.
+        let (_, report) = try_cot_event_from_ditto_document(&doc).unwrap();
+        assert!(report.is_exact());
+    }
+
+    #[test]
+    fn flattened_json_missing_id_is_a_missing_field_error() {
+        let json = serde_json::json!({"w": "a-f-G-U-C"});
+        let err = try_cot_event_from_flattened_json(&json).unwrap_err();
+        assert!(matches!(err, CotError::MissingField(field) if field == "_id"));
+    }
+
+    #[test]
+    fn flattened_json_exact_conversion_reports_no_issues() {
+        let json = serde_json::json!({
+            "_id": "UID-2",
+            "w": "a-f-G-U-C",
+            "g": "2.0",
+            "p": "h-g-i-g-o",
+            "b": 1_622_548_800_000_000i64,
+            "n": 1_622_548_800_000_000i64,
+            "o": 1_622_548_900_000_000i64,
+            "j": 35.0,
+            "l": -118.0,
+            "i": 10.0,
+            "h": 5.0,
+            "k": 2.0,
+        });
+        let (event, report) = try_cot_event_from_flattened_json(&json).unwrap();
+        assert!(report.is_exact(), "unexpected issues: {:?}", report.issues);
+        assert_eq!(event.uid, "UID-2");
+    }
+
+    #[test]
+    fn flattened_json_uses_map_item_coordinates_for_a_previously_unmatched_subtype() {
+        // a-f-S-C-U is a LOCATION_EVENT_TYPE_MARKERS entry that the old
+        // hand-rolled is_map_item check didn't list, so it used to fall
+        // through to the "other documents" h/i/j mapping instead of j/l/i.
+        let json = serde_json::json!({
+            "_id": "UID-3",
+            "w": "a-f-S-C-U",
+            "j": 35.0,
+            "l": -118.0,
+            "i": 10.0,
+            "h": 5.0,
+        });
+        let (event, _) = try_cot_event_from_flattened_json(&json).unwrap();
+        assert_eq!(event.point.lat, 35.0);
+        assert_eq!(event.point.lon, -118.0);
+    }
+
+    #[test]
+    fn flattened_json_reads_file_ce_from_the_unflattened_detail_field() {
+        let json = serde_json::json!({
+            "_id": "UID-4",
+            "w": "b-f-t-file",
+            "h": 5.0,
+            "r__ce": 12.5,
+        });
+        let (event, _) = try_cot_event_from_flattened_json(&json).unwrap();
+        assert_eq!(event.point.ce, 12.5);
+    }
+
+    #[test]
+    fn with_xml_encoder_matches_the_plain_conversion() {
+        use crate::ditto::XmlDetailEncoder;
+
+        let doc = CotDocument::MapItem(map_item());
+        let plain = cot_event_from_ditto_document(&doc);
+        let via_encoder = cot_event_from_ditto_document_with(&doc, &XmlDetailEncoder).unwrap();
+        assert_eq!(plain.detail, via_encoder.detail);
+        assert_eq!(plain.uid, via_encoder.uid);
+    }
+
+    #[test]
+    fn with_json_encoder_skips_xml_entirely() {
+        use crate::ditto::JsonDetailEncoder;
+
+        let mut item = map_item();
+        item.r
+            .insert("remarks".to_string(), MapItemRValue::String("test".to_string()));
+        let doc = CotDocument::MapItem(item);
+        let event = cot_event_from_ditto_document_with(&doc, &JsonDetailEncoder).unwrap();
+        assert!(!event.detail.starts_with('<'));
+        let parsed: serde_json::Value = serde_json::from_str(&event.detail).unwrap();
+        assert_eq!(parsed["remarks"], "test");
+    }
+
+    #[test]
+    fn with_msgpack_encoder_round_trips_through_base64() {
+        use crate::ditto::base64_data::Base64Data;
+        use crate::ditto::MsgPackDetailEncoder;
+
+        let doc = CotDocument::MapItem(map_item());
+        let event = cot_event_from_ditto_document_with(&doc, &MsgPackDetailEncoder).unwrap();
+        assert!(Base64Data::decode(&event.detail).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod cot_event_from_flattened_json_checked_tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn a_well_formed_document_succeeds() {
+        let doc = json!({"_id": "uid-1", "w": "a-f-G-U-C", "b": 1.0, "n": 1.0, "o": 2.0});
+        let event = cot_event_from_flattened_json_checked(&doc).unwrap();
+        assert_eq!(event.uid, "uid-1");
+    }
+
+    #[test]
+    fn a_single_missing_field_is_reported_directly() {
+        let doc = json!({"w": "a-f-G-U-C", "b": 1.0, "n": 1.0, "o": 2.0});
+        let err = cot_event_from_flattened_json_checked(&doc).unwrap_err();
+        assert!(matches!(err, CotError::MissingField(field) if field == "_id"));
+    }
+
+    #[test]
+    fn several_missing_fields_are_all_reported_together() {
+        let doc = json!({});
+        let err = cot_event_from_flattened_json_checked(&doc).unwrap_err();
+        let CotError::Multiple(errors) = err else {
+            panic!("expected CotError::Multiple, got a single error");
+        };
+        assert_eq!(errors.len(), 5);
+    }
+
+    #[test]
+    fn a_non_object_document_is_rejected() {
+        let err = cot_event_from_flattened_json_checked(&json!("nope")).unwrap_err();
+        assert!(matches!(err, CotError::MissingField(_)));
+    }
 }
@@ -1,13 +1,98 @@
-//! Utility to convert CotDocument + r map to FlatCotEvent for XML serialization
+//! Utility to convert CotDocument + r map to FlatCotEvent.
+//!
+//! `FlatCotEvent` is a format-neutral intermediate — see
+//! [`crate::format`] for encoding it to XML, JSON, or MessagePack — so the
+//! two functions here are its front-ends: [`flat_cot_event_from_ditto`]
+//! builds one from a typed [`CotDocument`], and
+//! [`flat_cot_event_from_flattened_json`] builds one from the flattened
+//! JSON shape, feeding either into whichever [`crate::format::CotFormat`]
+//! the caller has registered.
 use crate::ditto::r_field_flattening::unflatten_document_r_field;
+use crate::ditto::schema::{
+    Api, ApiRValue, Chat, ChatRValue, File, FileRValue, Generic, GenericRValue, MapItem,
+    MapItemRValue,
+};
 use crate::ditto::CotDocument;
+use crate::error::{CotError, FlatCotError};
 use crate::model::FlatCotEvent;
-use chrono::TimeZone;
+use crate::timestamp::{
+    epoch_to_rfc3339, epoch_to_rfc3339_with_offset, try_epoch_to_rfc3339, TimePrecision,
+};
+use indexmap::IndexMap;
 use serde_json::Value;
 use std::collections::HashMap;
 
-/// Convert a CotDocument to a FlatCotEvent for XML serialization
+/// Which precision each numeric time field (`n`, `o`) is stored in, for
+/// [`flat_cot_event_from_ditto_with_config`] and
+/// [`flat_cot_event_from_flattened_json_with_config`].
+///
+/// `b` has no slot here: unlike `n`/`o` it's never rendered as an RFC 3339
+/// string in this module, only copied straight into [`FlatCotEvent::ce`] as
+/// a raw number, so no precision conversion applies to it — the MapItem
+/// branch's "time in millis, but used for ce" comment is about a unit
+/// mismatch at the `to_ditto` call site, not a conversion this file
+/// performs.
+///
+/// Defaults to [`TimePrecision::Micros`] for every field, matching the
+/// schema's actual encoding and the behavior before this config existed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeFieldConfig {
+    /// Precision of the `n` (time/start) field.
+    pub n: TimePrecision,
+    /// Precision of the `o` (stale) field.
+    pub o: TimePrecision,
+}
+
+impl Default for TimeFieldConfig {
+    fn default() -> Self {
+        Self {
+            n: TimePrecision::Micros,
+            o: TimePrecision::Micros,
+        }
+    }
+}
+
+/// Reads the originating producer's wall-clock UTC offset (in whole
+/// seconds) back out of a document's `r` map, where the `to_ditto`
+/// transforms stash it as a number under the reserved `"tz_offset_secs"`
+/// key when [`CotEvent::tz_offset_secs`](crate::cot_events::CotEvent::tz_offset_secs)
+/// is set — the same place `"original_type"` rides to survive a round
+/// trip, since the generated schema's `n`/`o` fields have no first-class
+/// offset slot of their own.
+fn tz_offset_from_r<T>(
+    r: &HashMap<String, T>,
+    as_number: impl Fn(&T) -> Option<f64>,
+) -> Option<i32> {
+    r.get("tz_offset_secs")
+        .and_then(as_number)
+        .map(|secs| secs as i32)
+}
+
+/// Formats `value` (expressed in `precision` units since the epoch) as an
+/// RFC 3339 string, rendered in `offset_secs` east of UTC if present, or
+/// `Z` otherwise — the formatting half of [`tz_offset_from_r`].
+fn format_time_field(value: f64, precision: TimePrecision, offset_secs: Option<i32>) -> String {
+    match offset_secs {
+        Some(secs) => epoch_to_rfc3339_with_offset(value, precision, secs),
+        None => epoch_to_rfc3339(value, precision),
+    }
+}
+
+/// Convert a CotDocument to a FlatCotEvent, assuming `n`/`o` are
+/// microseconds-since-epoch.
+///
+/// See [`flat_cot_event_from_ditto_with_config`] if the document's time
+/// fields use a different precision.
 pub fn flat_cot_event_from_ditto(doc: &CotDocument) -> FlatCotEvent {
+    flat_cot_event_from_ditto_with_config(doc, &TimeFieldConfig::default())
+}
+
+/// Convert a CotDocument to a FlatCotEvent, interpreting its `n`/`o` fields
+/// at the precision given by `config`.
+pub fn flat_cot_event_from_ditto_with_config(
+    doc: &CotDocument,
+    config: &TimeFieldConfig,
+) -> FlatCotEvent {
     use serde_json::Value;
     use std::collections::HashMap;
 
@@ -53,255 +138,269 @@ pub fn flat_cot_event_from_ditto(doc: &CotDocument) -> FlatCotEvent {
                 .collect();
             log::trace!("flat_cot_event_from_ditto: MapItem.r = {:?}", map);
         }
+        CotDocument::Unknown(unknown) => {
+            log::trace!("flat_cot_event_from_ditto: Unknown.raw = {:?}", unknown.raw);
+        }
     }
 
     match doc {
-        CotDocument::Api(api) => FlatCotEvent {
-            uid: api.id.clone(),
-            type_: api.w.clone(),
-            time: chrono::Utc
-                .timestamp_opt(
-                    (api.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((api.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            start: chrono::Utc
-                .timestamp_opt(
-                    (api.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((api.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            stale: chrono::Utc
-                .timestamp_opt(
-                    (api.o.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((api.o.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            how: api.p.clone(),
-            lat: api.h.unwrap_or(0.0),
-            lon: api.i.unwrap_or(0.0),
-            hae: api.j.unwrap_or(0.0),
-            ce: api.b,
-            le: api.k.unwrap_or(0.0),
-            callsign: api.e.clone().into(),
-            group_name: api.g.clone().into(),
-            detail_extra: {
-                let mut map: HashMap<String, Value> = api
-                    .r
-                    .iter()
-                    .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
-                    .collect();
-                if api.r.contains_key("original_type") {
-                    map.insert("original_type".to_string(), Value::String(api.w.clone()));
-                }
-                map
-            },
-        },
-        CotDocument::Chat(chat) => FlatCotEvent {
-            uid: chat.id.clone(),
-            type_: chat.w.clone(),
-            time: chrono::Utc
-                .timestamp_opt(
-                    (chat.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((chat.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            start: chrono::Utc
-                .timestamp_opt(
-                    (chat.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((chat.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            stale: chrono::Utc
-                .timestamp_opt(
-                    (chat.o.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((chat.o.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            how: chat.p.clone(),
-            lat: chat.h.unwrap_or(0.0),
-            lon: chat.i.unwrap_or(0.0),
-            hae: chat.j.unwrap_or(0.0),
-            ce: chat.b,
-            le: chat.k.unwrap_or(0.0),
-            callsign: chat.e.clone().into(),
-            group_name: chat.g.clone().into(),
-            detail_extra: {
-                let mut map: HashMap<String, Value> = chat
-                    .r
-                    .iter()
-                    .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
-                    .collect();
-                if chat.r.contains_key("original_type") {
-                    map.insert("original_type".to_string(), Value::String(chat.w.clone()));
-                }
-                map
-            },
-        },
-        CotDocument::File(file) => FlatCotEvent {
-            uid: file.id.clone(),
-            type_: file.w.clone(),
-            time: chrono::Utc
-                .timestamp_opt(
-                    (file.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((file.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            start: chrono::Utc
-                .timestamp_opt(
-                    (file.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((file.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            stale: chrono::Utc
-                .timestamp_opt(
-                    (file.o.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((file.o.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            how: file.p.clone(),
-            lat: file.h.unwrap_or(0.0),
-            lon: file.i.unwrap_or(0.0),
-            hae: file.j.unwrap_or(0.0),
-            ce: file.b,
-            le: file.k.unwrap_or(0.0),
-            callsign: file.e.clone().into(),
-            group_name: file.g.clone().into(),
-            detail_extra: {
-                let mut map: HashMap<String, Value> = file
-                    .r
-                    .iter()
-                    .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
-                    .collect();
-                if file.r.contains_key("original_type") {
-                    map.insert("original_type".to_string(), Value::String(file.w.clone()));
-                }
-                map
-            },
-        },
-        CotDocument::Generic(generic) => FlatCotEvent {
-            uid: generic.id.clone(),
-            type_: generic.w.clone(),
-            time: chrono::Utc
-                .timestamp_opt(
-                    (generic.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((generic.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            start: chrono::Utc
-                .timestamp_opt(
-                    (generic.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((generic.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            stale: chrono::Utc
-                .timestamp_opt(
-                    (generic.o.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((generic.o.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            how: generic.p.clone(),
-            lat: generic.h.unwrap_or(0.0),
-            lon: generic.i.unwrap_or(0.0),
-            hae: generic.j.unwrap_or(0.0),
-            ce: generic.b,
-            le: generic.k.unwrap_or(0.0),
-            callsign: generic.e.clone().into(),
-            group_name: generic.g.clone().into(),
-            detail_extra: {
-                let mut map: HashMap<String, Value> = generic
-                    .r
-                    .iter()
-                    .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
-                    .collect();
-                if generic.r.contains_key("original_type") {
-                    map.insert(
-                        "original_type".to_string(),
-                        Value::String(generic.w.clone()),
-                    );
-                }
-                map
-            },
-        },
-        CotDocument::MapItem(map_item) => FlatCotEvent {
-            uid: map_item.id.clone(),
-            type_: map_item.w.clone(),
-            time: chrono::Utc
-                .timestamp_opt(
-                    (map_item.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((map_item.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            start: chrono::Utc
-                .timestamp_opt(
-                    (map_item.n.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((map_item.n.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            stale: chrono::Utc
-                .timestamp_opt(
-                    (map_item.o.unwrap_or(0.0) as i64) / 1_000_000,
-                    (((map_item.o.unwrap_or(0.0) as i64) % 1_000_000) * 1_000) as u32,
-                )
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339(),
-            how: map_item.p.clone(),
-            lat: map_item.j.unwrap_or(0.0), // For MapItems: j = lat
-            lon: map_item.l.unwrap_or(0.0), // For MapItems: l = lon
-            hae: map_item.i.unwrap_or(0.0), // For MapItems: i = hae
-            ce: map_item.b,                 // b = ce (time in millis, but used for ce)
-            le: map_item.k.unwrap_or(0.0),  // k = le
-            callsign: map_item.e.clone().into(),
-            group_name: map_item.g.clone().into(),
-            detail_extra: {
-                let mut map: HashMap<String, Value> = map_item
-                    .r
-                    .iter()
-                    .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
-                    .collect();
-                if map_item.r.contains_key("original_type") {
-                    map.insert(
-                        "original_type".to_string(),
-                        Value::String(map_item.w.clone()),
-                    );
-                }
-                map
-            },
-        },
+        CotDocument::Api(api) => {
+            let tz = tz_offset_from_r(&api.r, |v| match v {
+                ApiRValue::Number(n) => Some(*n),
+                _ => None,
+            });
+            FlatCotEvent {
+                uid: api.id.clone(),
+                type_: api.w.clone(),
+                time: format_time_field(api.n.unwrap_or(0.0), config.n, tz),
+                start: format_time_field(api.n.unwrap_or(0.0), config.n, tz),
+                stale: format_time_field(api.o.unwrap_or(0.0), config.o, tz),
+                how: api.p.clone(),
+                lat: api.h.unwrap_or(0.0),
+                lon: api.i.unwrap_or(0.0),
+                hae: api.j.unwrap_or(0.0),
+                ce: api.b,
+                le: api.k.unwrap_or(0.0),
+                callsign: api.e.clone().into(),
+                group_name: api.g.clone().into(),
+                group_role: None,
+                speed: None,
+                course: None,
+                tz_offset_secs: tz,
+                detail_extra: {
+                    let mut map: IndexMap<String, Value> = api
+                        .r
+                        .iter()
+                        .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
+                        .collect();
+                    if api.r.contains_key("original_type") {
+                        map.insert("original_type".to_string(), Value::String(api.w.clone()));
+                    }
+                    map.shift_remove("tz_offset_secs");
+                    map
+                },
+                extra_attrs: IndexMap::new(),
+            }
+        }
+        CotDocument::Chat(chat) => {
+            let tz = tz_offset_from_r(&chat.r, |v| match v {
+                ChatRValue::Number(n) => Some(*n),
+                _ => None,
+            });
+            FlatCotEvent {
+                uid: chat.id.clone(),
+                type_: chat.w.clone(),
+                time: format_time_field(chat.n.unwrap_or(0.0), config.n, tz),
+                start: format_time_field(chat.n.unwrap_or(0.0), config.n, tz),
+                stale: format_time_field(chat.o.unwrap_or(0.0), config.o, tz),
+                how: chat.p.clone(),
+                lat: chat.h.unwrap_or(0.0),
+                lon: chat.i.unwrap_or(0.0),
+                hae: chat.j.unwrap_or(0.0),
+                ce: chat.b,
+                le: chat.k.unwrap_or(0.0),
+                callsign: chat.e.clone().into(),
+                group_name: chat.g.clone().into(),
+                group_role: None,
+                speed: None,
+                course: None,
+                tz_offset_secs: tz,
+                detail_extra: {
+                    let mut map: IndexMap<String, Value> = chat
+                        .r
+                        .iter()
+                        .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
+                        .collect();
+                    if chat.r.contains_key("original_type") {
+                        map.insert("original_type".to_string(), Value::String(chat.w.clone()));
+                    }
+                    map.shift_remove("tz_offset_secs");
+                    map
+                },
+                extra_attrs: IndexMap::new(),
+            }
+        }
+        CotDocument::File(file) => {
+            let tz = tz_offset_from_r(&file.r, |v| match v {
+                FileRValue::Number(n) => Some(*n),
+                _ => None,
+            });
+            FlatCotEvent {
+                uid: file.id.clone(),
+                type_: file.w.clone(),
+                time: format_time_field(file.n.unwrap_or(0.0), config.n, tz),
+                start: format_time_field(file.n.unwrap_or(0.0), config.n, tz),
+                stale: format_time_field(file.o.unwrap_or(0.0), config.o, tz),
+                how: file.p.clone(),
+                lat: file.h.unwrap_or(0.0),
+                lon: file.i.unwrap_or(0.0),
+                hae: file.j.unwrap_or(0.0),
+                ce: file.b,
+                le: file.k.unwrap_or(0.0),
+                callsign: file.e.clone().into(),
+                group_name: file.g.clone().into(),
+                group_role: None,
+                speed: None,
+                course: None,
+                tz_offset_secs: tz,
+                detail_extra: {
+                    let mut map: IndexMap<String, Value> = file
+                        .r
+                        .iter()
+                        .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
+                        .collect();
+                    if file.r.contains_key("original_type") {
+                        map.insert("original_type".to_string(), Value::String(file.w.clone()));
+                    }
+                    map.shift_remove("tz_offset_secs");
+                    map
+                },
+                extra_attrs: IndexMap::new(),
+            }
+        }
+        CotDocument::Generic(generic) => {
+            let tz = tz_offset_from_r(&generic.r, |v| match v {
+                GenericRValue::Number(n) => Some(*n),
+                _ => None,
+            });
+            FlatCotEvent {
+                uid: generic.id.clone(),
+                type_: generic.w.clone(),
+                time: format_time_field(generic.n.unwrap_or(0.0), config.n, tz),
+                start: format_time_field(generic.n.unwrap_or(0.0), config.n, tz),
+                stale: format_time_field(generic.o.unwrap_or(0.0), config.o, tz),
+                how: generic.p.clone(),
+                lat: generic.h.unwrap_or(0.0),
+                lon: generic.i.unwrap_or(0.0),
+                hae: generic.j.unwrap_or(0.0),
+                ce: generic.b,
+                le: generic.k.unwrap_or(0.0),
+                callsign: generic.e.clone().into(),
+                group_name: generic.g.clone().into(),
+                group_role: None,
+                speed: None,
+                course: None,
+                tz_offset_secs: tz,
+                detail_extra: {
+                    let mut map: IndexMap<String, Value> = generic
+                        .r
+                        .iter()
+                        .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
+                        .collect();
+                    if generic.r.contains_key("original_type") {
+                        map.insert(
+                            "original_type".to_string(),
+                            Value::String(generic.w.clone()),
+                        );
+                    }
+                    map.shift_remove("tz_offset_secs");
+                    map
+                },
+                extra_attrs: IndexMap::new(),
+            }
+        }
+        CotDocument::MapItem(map_item) => {
+            let tz = tz_offset_from_r(&map_item.r, |v| match v {
+                MapItemRValue::Number(n) => Some(*n),
+                _ => None,
+            });
+            FlatCotEvent {
+                uid: map_item.id.clone(),
+                type_: map_item.w.clone(),
+                time: format_time_field(map_item.n.unwrap_or(0.0), config.n, tz),
+                start: format_time_field(map_item.n.unwrap_or(0.0), config.n, tz),
+                stale: format_time_field(map_item.o.unwrap_or(0.0), config.o, tz),
+                how: map_item.p.clone(),
+                lat: map_item.j.unwrap_or(0.0), // For MapItems: j = lat
+                lon: map_item.l.unwrap_or(0.0), // For MapItems: l = lon
+                hae: map_item.i.unwrap_or(0.0), // For MapItems: i = hae
+                ce: map_item.b,                 // b = ce (time in millis, but used for ce)
+                le: map_item.k.unwrap_or(0.0),  // k = le
+                callsign: map_item.e.clone().into(),
+                group_name: map_item.g.clone().into(),
+                group_role: None,
+                speed: None,
+                course: None,
+                tz_offset_secs: tz,
+                detail_extra: {
+                    let mut map: IndexMap<String, Value> = map_item
+                        .r
+                        .iter()
+                        .map(|(k, v)| (k.clone(), serde_json::to_value(v).unwrap_or(Value::Null)))
+                        .collect();
+                    if map_item.r.contains_key("original_type") {
+                        map.insert(
+                            "original_type".to_string(),
+                            Value::String(map_item.w.clone()),
+                        );
+                    }
+                    map.shift_remove("tz_offset_secs");
+                    map
+                },
+                extra_attrs: IndexMap::new(),
+            }
+        }
+        CotDocument::Unknown(unknown) => {
+            let raw = &unknown.raw;
+            let get_string =
+                |key: &str| raw.get(key).and_then(Value::as_str).unwrap_or("").to_string();
+            let get_f64 = |key: &str| raw.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+            let mut detail_extra: IndexMap<String, Value> = raw
+                .get("r")
+                .and_then(Value::as_object)
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .collect();
+            let tz = detail_extra
+                .shift_remove("tz_offset_secs")
+                .and_then(|v| v.as_f64())
+                .map(|secs| secs as i32);
+
+            FlatCotEvent {
+                uid: raw
+                    .get("_id")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+                type_: get_string("w"),
+                time: format_time_field(get_f64("n"), config.n, tz),
+                start: format_time_field(get_f64("n"), config.n, tz),
+                stale: format_time_field(get_f64("o"), config.o, tz),
+                how: get_string("p"),
+                lat: get_f64("j"),
+                lon: get_f64("l"),
+                hae: get_f64("i"),
+                ce: get_f64("h"),
+                le: get_f64("k"),
+                callsign: None,
+                group_name: None,
+                group_role: None,
+                speed: None,
+                course: None,
+                tz_offset_secs: tz,
+                detail_extra,
+                extra_attrs: IndexMap::new(),
+            }
+        }
     }
 }
 
-/// Convert a flattened JSON document (with r_* fields) to a FlatCotEvent for XML serialization
+/// Convert a flattened JSON document (with r_* fields) to a FlatCotEvent,
+/// assuming `n`/`o` are microseconds-since-epoch.
+///
+/// See [`flat_cot_event_from_flattened_json_with_config`] if the document's
+/// time fields use a different precision.
 pub fn flat_cot_event_from_flattened_json(json_value: &Value) -> FlatCotEvent {
+    flat_cot_event_from_flattened_json_with_config(json_value, &TimeFieldConfig::default())
+}
+
+/// Convert a flattened JSON document (with r_* fields) to a FlatCotEvent,
+/// interpreting its `n`/`o` fields at the precision given by `config`.
+pub fn flat_cot_event_from_flattened_json_with_config(
+    json_value: &Value,
+    config: &TimeFieldConfig,
+) -> FlatCotEvent {
     // Convert JSON Value to HashMap and unflatten r_* fields
     if let Value::Object(obj) = json_value {
         let mut document_map: HashMap<String, Value> = obj.clone().into_iter().collect();
@@ -341,16 +440,11 @@ pub fn flat_cot_event_from_flattened_json(json_value: &Value) -> FlatCotEvent {
             || event_type.contains("a-u-A")
             || event_type.contains("a-u-G");
 
-        // Helper to convert microseconds to RFC3339 string
-        let micros_to_rfc3339 = |micros: f64| -> String {
-            let secs = (micros as i64) / 1_000_000;
-            let nanos = (((micros as i64) % 1_000_000) * 1_000) as u32;
-            chrono::Utc
-                .timestamp_opt(secs, nanos)
-                .single()
-                .unwrap_or_else(chrono::Utc::now)
-                .to_rfc3339()
-        };
+        let mut r_map = r_map;
+        let tz = r_map
+            .remove("tz_offset_secs")
+            .and_then(|v| v.as_f64())
+            .map(|secs| secs as i32);
 
         FlatCotEvent {
             uid: get_string("_id"),
@@ -358,18 +452,18 @@ pub fn flat_cot_event_from_flattened_json(json_value: &Value) -> FlatCotEvent {
             time: {
                 let n = get_opt_f64("n").unwrap_or(0.0);
                 if n != 0.0 {
-                    micros_to_rfc3339(n)
+                    format_time_field(n, config.n, tz)
                 } else {
                     chrono::Utc::now().to_rfc3339()
                 }
             },
             start: {
                 let n = get_opt_f64("n").unwrap_or(0.0);
-                micros_to_rfc3339(n)
+                format_time_field(n, config.n, tz)
             },
             stale: {
                 let o = get_opt_f64("o").unwrap_or(0.0);
-                micros_to_rfc3339(o)
+                format_time_field(o, config.o, tz)
             },
             how: get_string("p"),
             lat: if is_map_item {
@@ -391,7 +485,12 @@ pub fn flat_cot_event_from_flattened_json(json_value: &Value) -> FlatCotEvent {
             le: get_opt_f64("k").unwrap_or(0.0),
             callsign: None,   // Callsign info comes from detail_extra, not e field
             group_name: None, // Group info comes from detail_extra, not g field (which is version)
-            detail_extra: r_map,
+            group_role: None, // Role info comes from detail_extra
+            speed: None,      // Kinematics info comes from detail_extra
+            course: None,
+            tz_offset_secs: tz,
+            detail_extra: r_map.into_iter().collect(),
+            extra_attrs: IndexMap::new(),
         }
     } else {
         // Fallback for non-object JSON
@@ -409,7 +508,468 @@ pub fn flat_cot_event_from_flattened_json(json_value: &Value) -> FlatCotEvent {
             le: 0.0,
             callsign: Some("".to_string()),
             group_name: Some("".to_string()),
-            detail_extra: HashMap::new(),
+            group_role: None,
+            speed: None,
+            course: None,
+            tz_offset_secs: None,
+            detail_extra: IndexMap::new(),
+            extra_attrs: IndexMap::new(),
         }
     }
 }
+
+/// Coordinate and `r`-map fields common to every non-`Unknown` document
+/// variant, extracted so [`try_flat_cot_event_from_ditto_with_config`] can
+/// validate them without re-matching on `doc` a second time.
+struct FlatFieldsToValidate<'a> {
+    coords: [(&'static str, f64); 5],
+    n: Option<f64>,
+    o: Option<f64>,
+    r: Box<dyn Iterator<Item = (String, serde_json::Result<Value>)> + 'a>,
+}
+
+fn serialize_r_entries<'a, K, V>(
+    r: &'a HashMap<K, V>,
+) -> Box<dyn Iterator<Item = (String, serde_json::Result<Value>)> + 'a>
+where
+    K: ToString,
+    V: serde::Serialize,
+{
+    Box::new(
+        r.iter()
+            .map(|(k, v)| (k.to_string(), serde_json::to_value(v))),
+    )
+}
+
+fn validate_flat_fields(
+    fields: FlatFieldsToValidate,
+    config: &TimeFieldConfig,
+) -> Result<(), FlatCotError> {
+    for (field, value) in fields.coords {
+        if !value.is_finite() {
+            return Err(FlatCotError::NonFiniteCoordinate { field, value });
+        }
+    }
+
+    for (key, result) in fields.r {
+        result.map_err(|e| FlatCotError::UnserializableRValue {
+            key,
+            reason: e.to_string(),
+        })?;
+    }
+
+    if let Some(n) = fields.n {
+        if try_epoch_to_rfc3339(n, config.n).is_none() {
+            return Err(FlatCotError::TimestampOutOfRange { field: "time" });
+        }
+    }
+    if let Some(o) = fields.o {
+        if try_epoch_to_rfc3339(o, config.o).is_none() {
+            return Err(FlatCotError::TimestampOutOfRange { field: "stale" });
+        }
+    }
+
+    Ok(())
+}
+
+/// Fallible counterpart to [`flat_cot_event_from_ditto`]: validates
+/// coordinates, `r` map entries, and timestamps up front, surfacing a
+/// [`FlatCotError`] instead of letting the lenient function's per-field
+/// fallbacks (`unwrap_or(Value::Null)`, `unwrap_or_else(chrono::Utc::now)`,
+/// and similar) silently turn bad input into a wrong-but-valid-looking
+/// event.
+///
+/// [`flat_cot_event_from_ditto`] is unchanged: it remains the lenient
+/// wrapper for callers who would rather get a best-effort event than an
+/// error.
+pub fn try_flat_cot_event_from_ditto(doc: &CotDocument) -> Result<FlatCotEvent, FlatCotError> {
+    try_flat_cot_event_from_ditto_with_config(doc, &TimeFieldConfig::default())
+}
+
+/// Like [`try_flat_cot_event_from_ditto`], interpreting `n`/`o` at the
+/// precision given by `config`.
+pub fn try_flat_cot_event_from_ditto_with_config(
+    doc: &CotDocument,
+    config: &TimeFieldConfig,
+) -> Result<FlatCotEvent, FlatCotError> {
+    match doc {
+        CotDocument::Api(api) => validate_flat_fields(
+            FlatFieldsToValidate {
+                coords: [
+                    ("lat", api.h.unwrap_or(0.0)),
+                    ("lon", api.i.unwrap_or(0.0)),
+                    ("hae", api.j.unwrap_or(0.0)),
+                    ("ce", api.b),
+                    ("le", api.k.unwrap_or(0.0)),
+                ],
+                n: api.n,
+                o: api.o,
+                r: serialize_r_entries(&api.r),
+            },
+            config,
+        )?,
+        CotDocument::Chat(chat) => validate_flat_fields(
+            FlatFieldsToValidate {
+                coords: [
+                    ("lat", chat.h.unwrap_or(0.0)),
+                    ("lon", chat.i.unwrap_or(0.0)),
+                    ("hae", chat.j.unwrap_or(0.0)),
+                    ("ce", chat.b),
+                    ("le", chat.k.unwrap_or(0.0)),
+                ],
+                n: chat.n,
+                o: chat.o,
+                r: serialize_r_entries(&chat.r),
+            },
+            config,
+        )?,
+        CotDocument::File(file) => validate_flat_fields(
+            FlatFieldsToValidate {
+                coords: [
+                    ("lat", file.h.unwrap_or(0.0)),
+                    ("lon", file.i.unwrap_or(0.0)),
+                    ("hae", file.j.unwrap_or(0.0)),
+                    ("ce", file.b),
+                    ("le", file.k.unwrap_or(0.0)),
+                ],
+                n: file.n,
+                o: file.o,
+                r: serialize_r_entries(&file.r),
+            },
+            config,
+        )?,
+        CotDocument::Generic(generic) => validate_flat_fields(
+            FlatFieldsToValidate {
+                coords: [
+                    ("lat", generic.h.unwrap_or(0.0)),
+                    ("lon", generic.i.unwrap_or(0.0)),
+                    ("hae", generic.j.unwrap_or(0.0)),
+                    ("ce", generic.b),
+                    ("le", generic.k.unwrap_or(0.0)),
+                ],
+                n: generic.n,
+                o: generic.o,
+                r: serialize_r_entries(&generic.r),
+            },
+            config,
+        )?,
+        CotDocument::MapItem(map_item) => validate_flat_fields(
+            FlatFieldsToValidate {
+                coords: [
+                    ("lat", map_item.j.unwrap_or(0.0)),
+                    ("lon", map_item.l.unwrap_or(0.0)),
+                    ("hae", map_item.i.unwrap_or(0.0)),
+                    ("ce", map_item.b),
+                    ("le", map_item.k.unwrap_or(0.0)),
+                ],
+                n: map_item.n,
+                o: map_item.o,
+                r: serialize_r_entries(&map_item.r),
+            },
+            config,
+        )?,
+        // `unknown.raw`'s "r" entries are already deserialized `Value`s (no
+        // re-serialization step to fail) and its coordinates come from
+        // `as_f64`, which JSON's number grammar guarantees is finite, so
+        // only its timestamps need checking here.
+        CotDocument::Unknown(unknown) => {
+            let get_f64 = |key: &str| unknown.raw.get(key).and_then(Value::as_f64).unwrap_or(0.0);
+            if try_epoch_to_rfc3339(get_f64("n"), config.n).is_none() {
+                return Err(FlatCotError::TimestampOutOfRange { field: "time" });
+            }
+            if try_epoch_to_rfc3339(get_f64("o"), config.o).is_none() {
+                return Err(FlatCotError::TimestampOutOfRange { field: "stale" });
+            }
+        }
+    }
+
+    Ok(flat_cot_event_from_ditto_with_config(doc, config))
+}
+
+/// Fallible counterpart to [`flat_cot_event_from_flattened_json`]: rejects
+/// non-object input and out-of-range timestamps instead of silently
+/// returning a placeholder `"unknown"` event or a fabricated "now".
+///
+/// [`flat_cot_event_from_flattened_json`] is unchanged: it remains the
+/// lenient wrapper for callers who would rather get a best-effort event
+/// than an error.
+pub fn try_flat_cot_event_from_flattened_json(
+    json_value: &Value,
+) -> Result<FlatCotEvent, FlatCotError> {
+    try_flat_cot_event_from_flattened_json_with_config(json_value, &TimeFieldConfig::default())
+}
+
+/// Like [`try_flat_cot_event_from_flattened_json`], interpreting `n`/`o` at
+/// the precision given by `config`.
+pub fn try_flat_cot_event_from_flattened_json_with_config(
+    json_value: &Value,
+    config: &TimeFieldConfig,
+) -> Result<FlatCotEvent, FlatCotError> {
+    if !json_value.is_object() {
+        return Err(FlatCotError::NonObjectJson);
+    }
+
+    // A JSON number is always finite, so only chrono's representable range
+    // needs checking here.
+    if let Some(n) = json_value.get("n").and_then(Value::as_f64) {
+        if try_epoch_to_rfc3339(n, config.n).is_none() {
+            return Err(FlatCotError::TimestampOutOfRange { field: "time" });
+        }
+    }
+    if let Some(o) = json_value.get("o").and_then(Value::as_f64) {
+        if try_epoch_to_rfc3339(o, config.o).is_none() {
+            return Err(FlatCotError::TimestampOutOfRange { field: "stale" });
+        }
+    }
+
+    Ok(flat_cot_event_from_flattened_json_with_config(
+        json_value, config,
+    ))
+}
+
+/// Which [`CotDocument`] variant [`cot_document_from_flat_cot_event`] should
+/// rebuild, since a [`FlatCotEvent`] alone doesn't carry enough to pick one
+/// (several variants share the exact same flat shape).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CotDocumentKind {
+    /// Rebuild an [`Api`] document.
+    Api,
+    /// Rebuild a [`Chat`] document.
+    Chat,
+    /// Rebuild a [`File`] document.
+    File,
+    /// Rebuild a [`Generic`] document.
+    Generic,
+    /// Rebuild a [`MapItem`] document.
+    MapItem,
+}
+
+/// Parses an RFC 3339 timestamp into microseconds since the Unix epoch, the
+/// unit every `n`/`o`/`b` timestamp field is stored in.
+fn micros_from_rfc3339(field: &str, value: &str) -> Result<f64, CotError> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.timestamp_micros() as f64)
+        .map_err(|_| CotError::InvalidDateTime {
+            field: field.to_string(),
+            value: value.to_string(),
+        })
+}
+
+/// Converts a [`FlatCotEvent`]'s `detail_extra` map back into an `r` field
+/// map, the inverse of the
+/// `r.iter().map(|(k, v)| (k.clone(), serde_json::to_value(v)...))`
+/// conversion every `transform_*_event` function in [`super::to_ditto`]
+/// does going the other way. Also re-stashes `tz_offset_secs`, if present,
+/// under the same reserved `r` key [`tz_offset_from_r`] reads it back from.
+macro_rules! r_map_from_detail_extra {
+    ($flat:expr, $rvalue:ty) => {{
+        let mut map: HashMap<String, $rvalue> = $flat
+            .detail_extra
+            .iter()
+            .map(|(k, v)| {
+                let rvalue = match v {
+                    Value::String(s) => <$rvalue>::String(s.clone()),
+                    Value::Number(n) => <$rvalue>::Number(n.as_f64().unwrap_or(0.0)),
+                    Value::Bool(b) => <$rvalue>::Boolean(*b),
+                    Value::Object(o) => <$rvalue>::Object(o.clone()),
+                    Value::Array(a) => <$rvalue>::Array(a.clone()),
+                    Value::Null => <$rvalue>::Null,
+                };
+                (k.clone(), rvalue)
+            })
+            .collect();
+        if let Some(secs) = $flat.tz_offset_secs {
+            map.insert("tz_offset_secs".to_string(), <$rvalue>::Number(secs as f64));
+        }
+        map
+    }};
+}
+
+/// Rebuilds a typed [`CotDocument`] from a [`FlatCotEvent`], the inverse of
+/// [`flat_cot_event_from_ditto`].
+///
+/// `kind` picks the variant to rebuild, since several variants (`Api`,
+/// `Chat`, `File`, `Generic`) share the same flat shape and can't be told
+/// apart from `flat` alone.
+///
+/// A few fields are necessarily lossy, since [`flat_cot_event_from_ditto`]
+/// itself never populates them from the document: `group_role`, `speed`,
+/// and `course` are always `None` on the way out, so they're ignored here
+/// too, and `time`/`start` both come from the single `n` field on the way
+/// out, so only `start` is used to rebuild it — pass a `FlatCotEvent` whose
+/// `time` equals its `start` for a lossless round trip. Fields the document
+/// carries but `FlatCotEvent` has no slot for (`content_type`, `mime`,
+/// `tag`, chat's `room`/`parent`, ...) come back as their schema defaults.
+pub fn cot_document_from_flat_cot_event(
+    flat: &FlatCotEvent,
+    kind: CotDocumentKind,
+) -> Result<CotDocument, CotError> {
+    let n = micros_from_rfc3339("start", &flat.start)?;
+    let o = micros_from_rfc3339("stale", &flat.stale)?;
+    let e = flat.callsign.clone().unwrap_or_default();
+    let g = flat.group_name.clone().unwrap_or_default();
+
+    Ok(match kind {
+        CotDocumentKind::Api => CotDocument::Api(Api {
+            id: flat.uid.clone(),
+            a: "".to_string(),
+            b: flat.ce,
+            content_type: None,
+            d: flat.uid.clone(),
+            d_c: 0,
+            d_r: false,
+            d_v: 2,
+            source: None,
+            data: None,
+            e,
+            g,
+            h: Some(flat.lat),
+            i: Some(flat.lon),
+            j: Some(flat.hae),
+            k: Some(flat.le),
+            l: None,
+            mime: None,
+            n: Some(n),
+            o: Some(o),
+            p: flat.how.clone(),
+            q: "".to_string(),
+            r: r_map_from_detail_extra!(flat, ApiRValue),
+            s: "".to_string(),
+            t: "".to_string(),
+            tag: None,
+            title: None,
+            u: "".to_string(),
+            v: "".to_string(),
+            w: flat.type_.clone(),
+            time_millis: None,
+            is_file: None,
+            is_removed: None,
+        }),
+        CotDocumentKind::Chat => CotDocument::Chat(Chat {
+            id: flat.uid.clone(),
+            a: "".to_string(),
+            b: flat.ce,
+            d: flat.uid.clone(),
+            d_c: 0,
+            d_r: false,
+            d_v: 2,
+            source: None,
+            e,
+            g,
+            h: Some(flat.lat),
+            i: Some(flat.lon),
+            j: Some(flat.hae),
+            k: Some(flat.le),
+            l: None,
+            n: Some(n),
+            o: Some(o),
+            p: flat.how.clone(),
+            q: "".to_string(),
+            r: r_map_from_detail_extra!(flat, ChatRValue),
+            s: "".to_string(),
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: flat.type_.clone(),
+            author_callsign: flat.callsign.clone(),
+            author_type: Some("user".to_string()),
+            author_uid: Some(flat.uid.clone()),
+            location: Some(format!("{},{},{}", flat.lat, flat.lon, flat.hae)),
+            message: None,
+            parent: None,
+            room: None,
+            room_id: None,
+            time: Some(flat.time.clone()),
+        }),
+        CotDocumentKind::File => CotDocument::File(File {
+            id: flat.uid.clone(),
+            a: "".to_string(),
+            b: flat.ce,
+            c: None,
+            content_type: Some("file".to_string()),
+            d: flat.uid.clone(),
+            d_c: 0,
+            d_r: false,
+            d_v: 2,
+            source: None,
+            e,
+            file: None,
+            g,
+            h: Some(flat.lat),
+            i: Some(flat.lon),
+            j: Some(flat.hae),
+            k: Some(flat.le),
+            l: None,
+            item_id: None,
+            mime: None,
+            n: Some(n),
+            o: Some(o),
+            p: flat.how.clone(),
+            q: "".to_string(),
+            r: r_map_from_detail_extra!(flat, FileRValue),
+            s: "".to_string(),
+            sz: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: flat.type_.clone(),
+        }),
+        CotDocumentKind::Generic => CotDocument::Generic(Generic {
+            id: flat.uid.clone(),
+            a: "".to_string(),
+            b: flat.ce,
+            d: flat.uid.clone(),
+            d_c: 0,
+            d_r: false,
+            d_v: 2,
+            source: None,
+            e,
+            g,
+            h: Some(flat.lat),
+            i: Some(flat.lon),
+            j: Some(flat.hae),
+            k: Some(flat.le),
+            l: None,
+            n: Some(n),
+            o: Some(o),
+            p: flat.how.clone(),
+            q: "".to_string(),
+            r: r_map_from_detail_extra!(flat, GenericRValue),
+            s: "".to_string(),
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: flat.type_.clone(),
+            _detail_raw: None,
+        }),
+        CotDocumentKind::MapItem => CotDocument::MapItem(MapItem {
+            id: flat.uid.clone(),
+            a: "".to_string(),
+            b: flat.ce,
+            c: None,
+            d: flat.uid.clone(),
+            d_c: 0,
+            d_r: false,
+            d_v: 2,
+            source: None,
+            e,
+            f: None,
+            g,
+            h: None,
+            i: Some(flat.hae),
+            j: Some(flat.lat),
+            k: Some(flat.le),
+            l: Some(flat.lon),
+            n: Some(n),
+            o: Some(o),
+            p: flat.how.clone(),
+            q: "".to_string(),
+            r: r_map_from_detail_extra!(flat, MapItemRValue),
+            s: "".to_string(),
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: flat.type_.clone(),
+        }),
+    })
+}
@@ -0,0 +1,366 @@
+//! A compact `"lat,lon,hae"` string encoding of a geographic point, for
+//! detail fields that carry location as a single string rather than
+//! [`Point`](crate::cot_events::Point)'s three separate numeric fields.
+//!
+//! The ask this module answers was phrased against a `ChatDocument.location:
+//! Option<String>` field and a structured `Location` type — neither exists
+//! in this tree: there's no `ChatDocument` (the real type is
+//! [`CotDocument::Chat`](super::CotDocument::Chat), a `Chat` struct with the
+//! same `j`/`l`/`i` lat/lon/hae fields every other variant has, not a
+//! string-encoded location), and the closest existing structured point is
+//! [`cot_events::Point`](crate::cot_events::Point), which additionally
+//! carries `ce`/`le` accuracy this module's encoding has no room for. What's
+//! implemented here is the generically useful piece the request actually
+//! wants — a `GeoPoint` newtype that round-trips through the compact
+//! string form — available to any `r`-map detail field via
+//! [`CotDocument::geo_point_field`]/[`CotDocument::set_geo_point_field`]
+//! rather than a field on a struct that doesn't exist.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use serde_json::Value;
+
+use crate::cot_events::Point;
+use crate::ditto::{ApiRValue, ChatRValue, CotDocument, FileRValue, GenericRValue, MapItemRValue};
+
+/// A geographic point as `lat,lon,hae`, serializing to and parsing from that
+/// compact string form rather than a JSON object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    /// Latitude in decimal degrees, always in `[-90, 90]`.
+    pub lat: f64,
+    /// Longitude in decimal degrees, always in `[-180, 180]`.
+    pub lon: f64,
+    /// Height above the WGS84 ellipsoid, in meters.
+    pub hae: f64,
+}
+
+/// Failure modes for [`GeoPoint::parse`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum GeoParseError {
+    /// Neither a `"lat,lon"` nor `"lat,lon,hae"` shape.
+    #[error("expected \"lat,lon\" or \"lat,lon,hae\", got {0} comma-separated components")]
+    WrongComponentCount(usize),
+
+    /// A component didn't parse as a number at all.
+    #[error("component '{component}' is not a number: '{value}'")]
+    NotANumber {
+        /// Which component failed (`"lat"`, `"lon"`, or `"hae"`).
+        component: &'static str,
+        /// The raw text that failed to parse.
+        value: String,
+    },
+
+    /// A component parsed but was non-finite or outside its valid range.
+    #[error("component '{component}' is out of range: {value}")]
+    OutOfRange {
+        /// Which component failed.
+        component: &'static str,
+        /// The offending value.
+        value: f64,
+    },
+}
+
+impl GeoPoint {
+    /// Builds a [`GeoPoint`], rejecting non-finite or out-of-range
+    /// components up front so a constructed value is always safe to
+    /// serialize.
+    pub fn new(lat: f64, lon: f64, hae: f64) -> Result<Self, GeoParseError> {
+        check_range("lat", lat, -90.0, 90.0)?;
+        check_range("lon", lon, -180.0, 180.0)?;
+        if !hae.is_finite() {
+            return Err(GeoParseError::OutOfRange { component: "hae", value: hae });
+        }
+        Ok(Self { lat, lon, hae })
+    }
+
+    /// Parses the compact string form. Tolerates a missing altitude
+    /// component (`"lat,lon"`), defaulting `hae` to `0.0`.
+    pub fn parse(s: &str) -> Result<Self, GeoParseError> {
+        let parts: Vec<&str> = s.split(',').collect();
+        let (lat, lon, hae) = match parts.as_slice() {
+            [lat, lon] => (*lat, *lon, "0"),
+            [lat, lon, hae] => (*lat, *lon, *hae),
+            other => return Err(GeoParseError::WrongComponentCount(other.len())),
+        };
+        Self::new(
+            parse_component("lat", lat)?,
+            parse_component("lon", lon)?,
+            parse_component("hae", hae)?,
+        )
+    }
+
+    /// Keeps `lat`/`lon`/`hae` and adds the `ce`/`le` accuracy this
+    /// encoding has no room for, producing a full
+    /// [`Point`](crate::cot_events::Point). The inverse of [`Self::from`]'s
+    /// `&Point` impl, modulo the accuracy fields a round trip through the
+    /// compact string form can't preserve.
+    pub fn to_point(self, ce: f64, le: f64) -> Point {
+        Point { lat: self.lat, lon: self.lon, hae: self.hae, ce, le }
+    }
+}
+
+fn parse_component(component: &'static str, raw: &str) -> Result<f64, GeoParseError> {
+    raw.trim()
+        .parse::<f64>()
+        .map_err(|_| GeoParseError::NotANumber { component, value: raw.to_string() })
+}
+
+fn check_range(
+    component: &'static str,
+    value: f64,
+    min: f64,
+    max: f64,
+) -> Result<(), GeoParseError> {
+    if !value.is_finite() || value < min || value > max {
+        return Err(GeoParseError::OutOfRange { component, value });
+    }
+    Ok(())
+}
+
+impl CotDocument {
+    /// Reads detail field `field` as a [`GeoPoint`], if it's present and
+    /// holds a parseable `"lat,lon,hae"` string. Returns `None` (not an
+    /// error) for a missing field, a non-string value, or a string that
+    /// fails [`GeoPoint::parse`] — the same "absent or unusable" treatment
+    /// [`super::DetailAccessor`] gives other optional detail fields.
+    pub fn geo_point_field(&self, field: &str) -> Option<GeoPoint> {
+        fn read<T>(
+            r: &HashMap<String, T>,
+            field: &str,
+            as_str: impl Fn(&T) -> Option<&str>,
+        ) -> Option<GeoPoint> {
+            GeoPoint::parse(as_str(r.get(field)?)?).ok()
+        }
+
+        match self {
+            CotDocument::Api(d) => read(&d.r, field, |v| match v {
+                ApiRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::Chat(d) => read(&d.r, field, |v| match v {
+                ChatRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::File(d) => read(&d.r, field, |v| match v {
+                FileRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::Generic(d) => read(&d.r, field, |v| match v {
+                GenericRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::MapItem(d) => read(&d.r, field, |v| match v {
+                MapItemRValue::String(s) => Some(s.as_str()),
+                _ => None,
+            }),
+            CotDocument::Unknown(u) => {
+                let raw = u.raw.as_object()?.get("r")?.as_object()?.get(field)?.as_str()?;
+                GeoPoint::parse(raw).ok()
+            }
+        }
+    }
+
+    /// Writes `point` into detail field `field` as its compact
+    /// `"lat,lon,hae"` string form, replacing whatever was there before.
+    pub fn set_geo_point_field(&mut self, field: &str, point: GeoPoint) {
+        let encoded = point.to_string();
+
+        match self {
+            CotDocument::Api(d) => {
+                d.r.insert(field.to_string(), ApiRValue::String(encoded));
+            }
+            CotDocument::Chat(d) => {
+                d.r.insert(field.to_string(), ChatRValue::String(encoded));
+            }
+            CotDocument::File(d) => {
+                d.r.insert(field.to_string(), FileRValue::String(encoded));
+            }
+            CotDocument::Generic(d) => {
+                d.r.insert(field.to_string(), GenericRValue::String(encoded));
+            }
+            CotDocument::MapItem(d) => {
+                d.r.insert(field.to_string(), MapItemRValue::String(encoded));
+            }
+            CotDocument::Unknown(u) => {
+                let r = u
+                    .raw
+                    .as_object_mut()
+                    .and_then(|obj| obj.get_mut("r"))
+                    .and_then(Value::as_object_mut);
+                if let Some(r) = r {
+                    r.insert(field.to_string(), Value::String(encoded));
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for GeoPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.lat, self.lon, self.hae)
+    }
+}
+
+impl From<&Point> for GeoPoint {
+    /// Carries over `lat`/`lon`/`hae`; `ce`/`le` have no equivalent in the
+    /// compact string form and are dropped.
+    fn from(point: &Point) -> Self {
+        GeoPoint { lat: point.lat, lon: point.lon, hae: point.hae }
+    }
+}
+
+impl Serialize for GeoPoint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for GeoPoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        GeoPoint::parse(&s).map_err(D::Error::custom)
+    }
+}
+
+impl schemars::JsonSchema for GeoPoint {
+    fn schema_name() -> String {
+        "GeoPoint".to_string()
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            format: Some("lat,lon,hae".to_string()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::MapItem;
+
+    fn map_item(r: HashMap<String, MapItemRValue>) -> CotDocument {
+        CotDocument::MapItem(MapItem {
+            id: "UID-1".to_string(),
+            a: "peer-a".to_string(),
+            b: 0.0,
+            c: None,
+            d: "UID-1".to_string(),
+            d_c: 0,
+            d_r: false,
+            d_v: 1,
+            source: None,
+            e: "ALPHA-1".to_string(),
+            f: None,
+            g: "2.0".to_string(),
+            h: None,
+            i: Some(10.0),
+            j: Some(35.0),
+            k: Some(5.0),
+            l: Some(-118.0),
+            n: Some(0.0),
+            o: Some(0.0),
+            p: "h-g-i-g-o".to_string(),
+            q: String::new(),
+            r,
+            s: String::new(),
+            t: String::new(),
+            u: String::new(),
+            v: String::new(),
+            w: "a-f-G-U-C".to_string(),
+        })
+    }
+
+    #[test]
+    fn round_trips_through_display_and_parse() {
+        let point = GeoPoint::new(45.0, -122.0, 10.0).unwrap();
+        assert_eq!(GeoPoint::parse(&point.to_string()).unwrap(), point);
+    }
+
+    #[test]
+    fn parse_tolerates_a_missing_altitude() {
+        let point = GeoPoint::parse("45.0,-122.0").unwrap();
+        assert_eq!(point.hae, 0.0);
+    }
+
+    #[test]
+    fn parse_rejects_the_wrong_number_of_components() {
+        assert!(matches!(
+            GeoPoint::parse("45.0"),
+            Err(GeoParseError::WrongComponentCount(1))
+        ));
+        assert!(matches!(
+            GeoPoint::parse("45.0,1.0,2.0,3.0"),
+            Err(GeoParseError::WrongComponentCount(4))
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_non_numeric_components() {
+        assert!(matches!(
+            GeoPoint::parse("not-a-number,1.0"),
+            Err(GeoParseError::NotANumber { component: "lat", .. })
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_out_of_range_latitude() {
+        assert!(matches!(
+            GeoPoint::parse("95.0,1.0"),
+            Err(GeoParseError::OutOfRange { component: "lat", .. })
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_non_finite_values() {
+        assert!(matches!(
+            GeoPoint::parse("NaN,1.0"),
+            Err(GeoParseError::OutOfRange { component: "lat", .. })
+        ));
+    }
+
+    #[test]
+    fn json_round_trips_through_serde() {
+        let point = GeoPoint::new(1.5, 2.5, 3.5).unwrap();
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(json, "\"1.5,2.5,3.5\"");
+        assert_eq!(serde_json::from_str::<GeoPoint>(&json).unwrap(), point);
+    }
+
+    #[test]
+    fn from_point_drops_accuracy_fields() {
+        let point = Point { lat: 1.0, lon: 2.0, hae: 3.0, ce: 4.0, le: 5.0 };
+        let geo = GeoPoint::from(&point);
+        assert_eq!(geo, GeoPoint::new(1.0, 2.0, 3.0).unwrap());
+    }
+
+    #[test]
+    fn geo_point_field_reads_back_what_was_set() {
+        let mut doc = map_item(HashMap::new());
+        let point = GeoPoint::new(12.5, -34.5, 100.0).unwrap();
+        doc.set_geo_point_field("rallyPoint", point);
+        assert_eq!(doc.geo_point_field("rallyPoint"), Some(point));
+    }
+
+    #[test]
+    fn geo_point_field_is_none_when_absent() {
+        let doc = map_item(HashMap::new());
+        assert_eq!(doc.geo_point_field("rallyPoint"), None);
+    }
+
+    #[test]
+    fn geo_point_field_is_none_when_unparseable() {
+        let mut r = HashMap::new();
+        r.insert("rallyPoint".to_string(), MapItemRValue::String("not-a-point".to_string()));
+        let doc = map_item(r);
+        assert_eq!(doc.geo_point_field("rallyPoint"), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! Observer-driven streaming from a Ditto live query into decoded [`CotEvent`]s.
+//!
+//! [`sdk_conversion`](super::sdk_conversion) gives a raw `register_observer_v2`
+//! callback helpers to pull a [`CotDocument`] or plain id/type out of a
+//! document's JSON, but callers still have to re-query or call
+//! [`CotDocument::to_cot_event`] by hand on every firing and work out for
+//! themselves which rows are new, which changed, and which vanished.
+//! [`CotEventObserver`] wraps that loop: it decodes every row the query
+//! returns into a [`CotEvent`] and delivers only the transition.
+//!
+//! Ditto's live query callback fires with the query's entire current result
+//! set on every change, not a diff, so [`CotEventObserver`] keeps its own
+//! by-id snapshot of what it last delivered and diffs the new result set
+//! against it — the same trick [`sync`](crate::ditto::sync) and
+//! [`changelog`](crate::ditto::changelog) use to turn Ditto's snapshot-based
+//! primitives into discrete change events.
+
+use crate::cot_events::CotEvent;
+use crate::ditto::{get_document_id_from_json, CotDocument, Filter};
+use crate::error::CotError;
+use dittolive_ditto::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// One transition [`CotEventObserver`] delivers to its callback as a live
+/// query's result set changes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CotEventChange {
+    /// A document matching the query appeared for the first time.
+    Inserted(CotEvent),
+    /// A document matching the query changed since the last delivery.
+    Updated(CotEvent),
+    /// A document that previously matched the query no longer does it —
+    /// hard-deleted, soft-deleted, or edited out of the query's predicate.
+    /// Carries the last decoded [`CotEvent`] this observer saw for it, so a
+    /// downstream consumer can retract a stale track without re-fetching
+    /// something that's already gone.
+    Deleted {
+        /// TAK uid of the document that disappeared.
+        uid: String,
+        /// CoT event type it last had, if known.
+        event_type: String,
+        /// The fully decoded event last delivered for this document, if this
+        /// observer was alive to see an insert for it before it vanished.
+        last_known: Option<CotEvent>,
+    },
+}
+
+/// Per-document state [`CotEventObserver`] tracks between deliveries, so it
+/// can tell an unchanged row from an updated one and surface a deleted row's
+/// last-known event.
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedDocument {
+    event: CotEvent,
+}
+
+/// Diffs a live query's current result set (`current`, keyed by Ditto `_id`)
+/// against `seen`, the snapshot from the previous delivery, updating `seen`
+/// in place and returning the transitions this round produced.
+///
+/// Kept free of any Ditto SDK types so it can be exercised directly in unit
+/// tests instead of only through a real `register_observer_v2` callback.
+fn diff_snapshot(
+    seen: &mut HashMap<String, TrackedDocument>,
+    current: Vec<(String, CotEvent)>,
+) -> Vec<CotEventChange> {
+    let mut changes = Vec::new();
+    let mut current_ids = HashSet::with_capacity(current.len());
+
+    for (id, event) in current {
+        current_ids.insert(id.clone());
+        let tracked = TrackedDocument { event };
+        match seen.insert(id, tracked.clone()) {
+            None => changes.push(CotEventChange::Inserted(tracked.event)),
+            Some(previous) if previous != tracked => {
+                changes.push(CotEventChange::Updated(tracked.event))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let vanished_ids: Vec<String> = seen
+        .keys()
+        .filter(|id| !current_ids.contains(*id))
+        .cloned()
+        .collect();
+    for id in vanished_ids {
+        if let Some(tracked) = seen.remove(&id) {
+            changes.push(CotEventChange::Deleted {
+                uid: tracked.event.uid.clone(),
+                event_type: tracked.event.event_type.clone(),
+                last_known: Some(tracked.event),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Subscribes to a Ditto live query and decodes each changed document into a
+/// [`CotEvent`], delivering insert/update/delete transitions to `on_change`
+/// instead of requiring the caller to poll and call
+/// [`CotDocument::to_cot_event`] by hand.
+///
+/// Dropping the returned [`CotEventObserver`] unsubscribes it, same as the
+/// underlying `StoreObserver`.
+pub struct CotEventObserver {
+    _inner: StoreObserver,
+}
+
+impl CotEventObserver {
+    /// Registers a live query observer over `store` scoped by `query` (a
+    /// full DQL `SELECT`, same as `Store::register_observer_v2`), invoking
+    /// `on_change` once per transition every time the query's result set
+    /// changes.
+    ///
+    /// `on_change` runs on Ditto's observer-callback thread, same as a raw
+    /// `register_observer_v2` callback — it should stay quick and hand off
+    /// any slow work instead of blocking the next delivery. A row whose JSON
+    /// doesn't decode into a [`CotDocument`] is skipped rather than panicking
+    /// the callback, so one malformed document doesn't starve the rest of
+    /// the result set of updates.
+    pub fn new(
+        store: &Store,
+        query: &str,
+        mut on_change: impl FnMut(CotEventChange) + Send + 'static,
+    ) -> Result<Self, CotError> {
+        let seen: Mutex<HashMap<String, TrackedDocument>> = Mutex::new(HashMap::new());
+
+        let inner = store
+            .register_observer_v2(query, move |result| {
+                let current: Vec<(String, CotEvent)> = result
+                    .iter()
+                    .filter_map(|observer_doc| {
+                        let json_str = observer_doc.json_string();
+                        let id = get_document_id_from_json(&json_str)?;
+                        let doc = CotDocument::from_json_str(&json_str).ok()?;
+                        Some((id, doc.to_cot_event()))
+                    })
+                    .collect();
+
+                let mut seen = seen.lock().expect("observer snapshot lock poisoned");
+                for change in diff_snapshot(&mut seen, current) {
+                    on_change(change);
+                }
+            })
+            .map_err(|e| CotError::InvalidFormat(e.to_string()))?;
+
+        Ok(Self { _inner: inner })
+    }
+
+    /// Unsubscribes explicitly. Equivalent to dropping this handle, but
+    /// spells out the intent at the call site instead of relying on scope.
+    pub fn unsubscribe(self) {}
+}
+
+/// Subscribes to `collection` (optionally narrowed by a [`Filter`]) and
+/// decodes each changed document into a [`CotEvent`], delivering
+/// insert/update/delete transitions to `on_change` as they arrive — a
+/// callback-driven counterpart to
+/// [`observe_documents`](super::observe::observe_documents) for a caller
+/// that doesn't want to poll a [`Stream`](futures::Stream).
+///
+/// Builds the query the same way [`get_documents`](crate::ditto_sync::get_documents)
+/// does (an unfiltered `SELECT *`, or one narrowed by `filter`), then
+/// delegates to [`CotEventObserver::new`].
+pub fn subscribe_cot_events(
+    ditto: &Ditto,
+    collection: &str,
+    filter: Option<&Filter>,
+    on_change: impl FnMut(CotEventChange) + Send + 'static,
+) -> Result<CotEventObserver, CotError> {
+    let store = ditto.store();
+    let query = match filter {
+        Some(f) => format!("SELECT * FROM {} WHERE {}", collection, f.to_dql()),
+        None => format!("SELECT * FROM {}", collection),
+    };
+    CotEventObserver::new(&store, &query, on_change)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::Point;
+    use chrono::Utc;
+
+    fn event(uid: &str, event_type: &str) -> CotEvent {
+        let now = "2023-01-01T00:00:00Z".parse::<chrono::DateTime<Utc>>().unwrap();
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: event_type.to_string(),
+            time: now,
+            start: now,
+            stale: now,
+            how: "m-g".to_string(),
+            point: Point {
+                lat: 1.0,
+                lon: 2.0,
+                hae: 0.0,
+                ce: 0.0,
+                le: 0.0,
+            },
+            detail: "<detail></detail>".to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn first_sighting_is_an_insert() {
+        let mut seen = HashMap::new();
+        let changes = diff_snapshot(
+            &mut seen,
+            vec![("doc-1".to_string(), event("uid-1", "a-f-G"))],
+        );
+        assert_eq!(
+            changes,
+            vec![CotEventChange::Inserted(event("uid-1", "a-f-G"))]
+        );
+    }
+
+    #[test]
+    fn unchanged_document_produces_no_transition() {
+        let mut seen = HashMap::new();
+        diff_snapshot(
+            &mut seen,
+            vec![("doc-1".to_string(), event("uid-1", "a-f-G"))],
+        );
+        let changes = diff_snapshot(
+            &mut seen,
+            vec![("doc-1".to_string(), event("uid-1", "a-f-G"))],
+        );
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn changed_document_is_an_update() {
+        let mut seen = HashMap::new();
+        diff_snapshot(
+            &mut seen,
+            vec![("doc-1".to_string(), event("uid-1", "a-f-G"))],
+        );
+        let mut moved = event("uid-1", "a-f-G");
+        moved.point.lat = 5.0;
+        let changes = diff_snapshot(&mut seen, vec![("doc-1".to_string(), moved.clone())]);
+        assert_eq!(changes, vec![CotEventChange::Updated(moved)]);
+    }
+
+    #[test]
+    fn vanished_document_is_a_delete_with_its_last_known_event() {
+        let mut seen = HashMap::new();
+        diff_snapshot(
+            &mut seen,
+            vec![("doc-1".to_string(), event("uid-1", "a-f-G"))],
+        );
+        let changes = diff_snapshot(&mut seen, vec![]);
+        assert_eq!(
+            changes,
+            vec![CotEventChange::Deleted {
+                uid: "uid-1".to_string(),
+                event_type: "a-f-G".to_string(),
+                last_known: Some(event("uid-1", "a-f-G")),
+            }]
+        );
+        assert!(seen.is_empty());
+    }
+}
@@ -0,0 +1,1218 @@
+//! Deterministic, offline merge of two divergent [`CotDocument`]s for the
+//! same `id`.
+//!
+//! [`SyncDag::merge`](super::sync_dag::SyncDag::merge) reconciles two peers'
+//! revision histories; this merge works at the document level rather than
+//! the DAG level: it lets
+//! a caller reconcile two copies of a document that diverged without a
+//! Ditto store's last-write-wins resolving it for them, and without relying
+//! on Ditto's receive order to get the same answer on every peer.
+//!
+//! [`CotDocument::merge`] lets a causally dominating [`VersionVector`] pick
+//! the winner; when the vectors don't settle it, it compares `d_v`, breaking a
+//! tie on the `a` (peer key) field — the same "predictable outcome from
+//! concurrent updates" approach iroh uses for simultaneous dials — then
+//! takes every field from the winner *except* `r`, whose keys are unioned
+//! so an element only the loser carries isn't dropped.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::ditto::or_set::{
+    apply_tombstones, merge_tombstones, tombstones_from_json, tombstones_to_json,
+    OR_SET_TOMBSTONES_KEY,
+};
+use crate::ditto::repeated_detail_merge::merge_repeated_elements;
+use crate::ditto::sync_dag::{doc_id, version_fields};
+use crate::ditto::text_crdt::{TextCrdt, TEXT_CRDT_SUFFIX};
+use crate::ditto::version_vector::{VectorOrdering, VersionVector, VERSION_VECTOR_KEY};
+use crate::ditto::{
+    Api, ApiRValue, Chat, ChatRValue, CotDocument, File, FileRValue, Generic, GenericRValue,
+    MapItem, MapItemRValue, UnknownDocument,
+};
+use crate::error::MergeError;
+
+/// The `&'static str` name [`MergeError::KindMismatch`] reports for a
+/// document's variant.
+fn kind_name(doc: &CotDocument) -> &'static str {
+    match doc {
+        CotDocument::Api(_) => "Api",
+        CotDocument::Chat(_) => "Chat",
+        CotDocument::File(_) => "File",
+        CotDocument::Generic(_) => "Generic",
+        CotDocument::MapItem(_) => "MapItem",
+        CotDocument::Unknown(_) => "Unknown",
+    }
+}
+
+/// The `a` (Ditto peer key) field, the tie-break authority for
+/// [`decide_winner`].
+fn peer_key(doc: &CotDocument) -> String {
+    match doc {
+        CotDocument::Api(d) => d.a.clone(),
+        CotDocument::Chat(d) => d.a.clone(),
+        CotDocument::File(d) => d.a.clone(),
+        CotDocument::Generic(d) => d.a.clone(),
+        CotDocument::MapItem(d) => d.a.clone(),
+        CotDocument::Unknown(u) => u
+            .raw
+            .get("a")
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Reads the [`VersionVector`] stashed under [`VERSION_VECTOR_KEY`] in `r`,
+/// or an empty vector for a document produced before this field existed (or
+/// one whose entry is malformed) — treated the same as "no edits recorded",
+/// which falls back to [`decide_winner`]'s `d_v`/peer-key tie-break.
+fn version_vector_of(doc: &CotDocument) -> VersionVector {
+    fn from_object(object: Option<&serde_json::Map<String, serde_json::Value>>) -> VersionVector {
+        object.map(VersionVector::from_json_map).unwrap_or_default()
+    }
+
+    match doc {
+        CotDocument::Api(d) => from_object(d.r.get(VERSION_VECTOR_KEY).and_then(|v| match v {
+            ApiRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::Chat(d) => from_object(d.r.get(VERSION_VECTOR_KEY).and_then(|v| match v {
+            ChatRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::File(d) => from_object(d.r.get(VERSION_VECTOR_KEY).and_then(|v| match v {
+            FileRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::Generic(d) => from_object(d.r.get(VERSION_VECTOR_KEY).and_then(|v| match v {
+            GenericRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::MapItem(d) => from_object(d.r.get(VERSION_VECTOR_KEY).and_then(|v| match v {
+            MapItemRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::Unknown(u) => from_object(
+            u.raw
+                .get("r")
+                .and_then(serde_json::Value::as_object)
+                .and_then(|r| r.get(VERSION_VECTOR_KEY))
+                .and_then(serde_json::Value::as_object),
+        ),
+    }
+}
+
+/// Reads the observed-remove set tombstones stashed under
+/// [`OR_SET_TOMBSTONES_KEY`] in `r`, or an empty map for a document that has
+/// never had a repeated detail element removed from it.
+fn tombstones_of(doc: &CotDocument) -> HashMap<String, Vec<String>> {
+    fn from_object(
+        object: Option<&serde_json::Map<String, serde_json::Value>>,
+    ) -> HashMap<String, Vec<String>> {
+        object.map(tombstones_from_json).unwrap_or_default()
+    }
+
+    match doc {
+        CotDocument::Api(d) => from_object(d.r.get(OR_SET_TOMBSTONES_KEY).and_then(|v| match v {
+            ApiRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::Chat(d) => from_object(d.r.get(OR_SET_TOMBSTONES_KEY).and_then(|v| match v {
+            ChatRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::File(d) => from_object(d.r.get(OR_SET_TOMBSTONES_KEY).and_then(|v| match v {
+            FileRValue::Object(o) => Some(o),
+            _ => None,
+        })),
+        CotDocument::Generic(d) => {
+            from_object(d.r.get(OR_SET_TOMBSTONES_KEY).and_then(|v| match v {
+                GenericRValue::Object(o) => Some(o),
+                _ => None,
+            }))
+        }
+        CotDocument::MapItem(d) => {
+            from_object(d.r.get(OR_SET_TOMBSTONES_KEY).and_then(|v| match v {
+                MapItemRValue::Object(o) => Some(o),
+                _ => None,
+            }))
+        }
+        CotDocument::Unknown(u) => from_object(
+            u.raw
+                .get("r")
+                .and_then(serde_json::Value::as_object)
+                .and_then(|r| r.get(OR_SET_TOMBSTONES_KEY))
+                .and_then(serde_json::Value::as_object),
+        ),
+    }
+}
+
+/// Re-filters every array-valued `r` entry named in `tombstones` to drop
+/// elements whose [`or_set::tag_of`](super::or_set::tag_of) was removed, then
+/// persists `tombstones` itself under [`OR_SET_TOMBSTONES_KEY`] — applied
+/// after [`merge_r`] has already unioned both sides' adds, so a remove one
+/// peer made isn't resurrected by the other peer's stale copy surviving the
+/// union.
+fn with_tombstones_applied<T>(
+    mut r: HashMap<String, T>,
+    tombstones: &HashMap<String, Vec<String>>,
+    as_array: impl Fn(&T) -> Option<&[Value]>,
+    from_array: impl Fn(Vec<Value>) -> T,
+    to_rvalue: impl Fn(serde_json::Map<String, serde_json::Value>) -> T,
+) -> HashMap<String, T> {
+    for (group, tags) in tombstones {
+        if let Some(live) =
+            r.get(group).and_then(|v| as_array(v)).map(|items| apply_tombstones(items, tags))
+        {
+            r.insert(group.clone(), from_array(live));
+        }
+    }
+    if !tombstones.is_empty() {
+        r.insert(OR_SET_TOMBSTONES_KEY.to_string(), to_rvalue(tombstones_to_json(tombstones)));
+    }
+    r
+}
+
+/// For every `r` key ending in [`TEXT_CRDT_SUFFIX`] present on both `winner`
+/// and `loser` (the [`text_crdt`](super::text_crdt) module's opt-in
+/// convention), merges the two [`TextCrdt`] logs character-by-character
+/// instead of [`merge_r`]'s default of keeping the winner's value outright,
+/// and rewrites the companion plain-string field (the key with the suffix
+/// stripped) to the merged result so a reader that doesn't know about the
+/// log still sees the right text.
+fn merge_text_crdt_fields<T>(
+    mut r: HashMap<String, T>,
+    winner: &HashMap<String, T>,
+    loser: &HashMap<String, T>,
+    as_object: impl Fn(&T) -> Option<&serde_json::Map<String, Value>>,
+    to_object: impl Fn(serde_json::Map<String, Value>) -> T,
+    to_string_value: impl Fn(String) -> T,
+) -> HashMap<String, T> {
+    for (key, winner_value) in winner {
+        let Some(field) = key.strip_suffix(TEXT_CRDT_SUFFIX) else { continue };
+        let Some(loser_value) = loser.get(key) else { continue };
+        let (Some(winner_log), Some(loser_log)) = (as_object(winner_value), as_object(loser_value))
+        else {
+            continue;
+        };
+
+        let merged_log =
+            TextCrdt::from_json_map(winner_log).merge(&TextCrdt::from_json_map(loser_log));
+        r.insert(key.clone(), to_object(merged_log.to_json_map()));
+        r.insert(field.to_string(), to_string_value(merged_log.materialize()));
+    }
+    r
+}
+
+/// `true` if `local` should win over `remote`.
+///
+/// When both sides carry a [`VersionVector`] that dominates the other, the
+/// dominating side wins — that's a causal successor, not a guess. When the
+/// vectors are absent, equal, or [`VectorOrdering::Concurrent`] (a genuine
+/// conflict neither vector resolves), this falls back to comparing `d_v`,
+/// breaking a `d_v` tie on the lexicographically greater `a`. Either choice
+/// of tie-break direction would be equally deterministic; "greater" is
+/// arbitrary but fixed, so both peers merging the same pair always agree.
+fn decide_winner(local: &CotDocument, remote: &CotDocument) -> bool {
+    match version_vector_of(local).compare(&version_vector_of(remote)) {
+        VectorOrdering::Dominates => return true,
+        VectorOrdering::Dominated => return false,
+        VectorOrdering::Equal | VectorOrdering::Concurrent => {}
+    }
+
+    let (local_d_v, ..) = version_fields(local);
+    let (remote_d_v, ..) = version_fields(remote);
+    match local_d_v.cmp(&remote_d_v) {
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Equal => peer_key(local) >= peer_key(remote),
+    }
+}
+
+/// `true` if `local` and `remote` carry [`VersionVector`]s that are both
+/// non-empty and [`VectorOrdering::Concurrent`] — edits applied independently
+/// that neither vector resolves, exposed to callers via
+/// [`CotDocument::merge_with_conflicts`].
+fn is_concurrent(local: &CotDocument, remote: &CotDocument) -> bool {
+    let (local_vv, remote_vv) = (version_vector_of(local), version_vector_of(remote));
+    local_vv != VersionVector::new()
+        && remote_vv != VersionVector::new()
+        && local_vv.compare(&remote_vv) == VectorOrdering::Concurrent
+}
+
+/// Merges two `r` maps under a decided winner: every key from the winner,
+/// plus any key the loser has that the winner doesn't. A key present on
+/// both sides as a repeated-detail-element array (per `as_array`) is
+/// unioned via [`merge_repeated_elements`] instead of the winner simply
+/// keeping its own array and dropping the loser's distinct elements; any
+/// other same-key conflict keeps the winner's value, as before.
+fn merge_r<V: Clone>(
+    winner: &HashMap<String, V>,
+    loser: &HashMap<String, V>,
+    as_array: impl Fn(&V) -> Option<&[Value]>,
+    from_array: impl Fn(Vec<Value>) -> V,
+) -> HashMap<String, V> {
+    let mut merged = winner.clone();
+    for (key, loser_value) in loser {
+        match merged.get(key) {
+            None => {
+                merged.insert(key.clone(), loser_value.clone());
+            }
+            Some(winner_value) => {
+                if let (Some(winner_items), Some(loser_items)) =
+                    (as_array(winner_value), as_array(loser_value))
+                {
+                    let union = merge_repeated_elements(winner_items, loser_items);
+                    merged.insert(key.clone(), from_array(union));
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// Overwrites an already-[`merge_r`]'d map's [`VERSION_VECTOR_KEY`] entry
+/// with `merged_vv` — `merge_r` alone would just carry the winner's own
+/// (now stale) vector forward, losing the loser's edits from the count.
+/// No-op if neither side ever had a vector, so documents predating this
+/// field stay untouched.
+fn with_merged_version_vector<T>(
+    mut r: HashMap<String, T>,
+    merged_vv: &VersionVector,
+    to_rvalue: impl Fn(serde_json::Map<String, serde_json::Value>) -> T,
+) -> HashMap<String, T> {
+    if *merged_vv != VersionVector::new() {
+        r.insert(VERSION_VECTOR_KEY.to_string(), to_rvalue(merged_vv.to_json_map()));
+    }
+    r
+}
+
+/// The result of [`CotDocument::merge_with_conflicts`].
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    /// The merged document, identical to what [`CotDocument::merge`] returns.
+    pub document: CotDocument,
+    /// `true` if both sides carried [`VersionVector`]s that diverged
+    /// ([`VectorOrdering::Concurrent`]), meaning the winner was picked by
+    /// the `d_v`/peer-key fallback rather than a genuine causal ordering.
+    pub concurrent: bool,
+}
+
+impl CotDocument {
+    /// Deterministically merges `self` with `other`, which must share the
+    /// same `id` and `CotDocument` variant.
+    ///
+    /// If both sides carry a [`VersionVector`] (stashed by
+    /// [`cot_to_document`](crate::ditto::cot_to_document) under
+    /// [`VERSION_VECTOR_KEY`]) and one dominates the other, the dominating
+    /// side wins — a genuine causal successor. Otherwise (no vectors, equal
+    /// vectors, or [`VectorOrdering::Concurrent`]) the document with the
+    /// higher `d_v` wins, a `d_v` tie broken by comparing the two documents'
+    /// `a` (peer key) fields. Either way every field but `r` comes from the
+    /// winner; `r`'s keys are unioned (a key present on only one side is
+    /// carried through unchanged) and its version vector is replaced with
+    /// the element-wise max of both sides'. The result's `d_v` is
+    /// `max(self.d_v, other.d_v)`. Both inputs are left untouched.
+    ///
+    /// Use [`Self::merge_with_conflicts`] to also learn whether the vectors
+    /// were concurrent.
+    pub fn merge(&self, other: &CotDocument) -> Result<CotDocument, MergeError> {
+        self.merge_with_conflicts(other).map(|outcome| outcome.document)
+    }
+
+    /// [`Self::merge`], plus whether the two sides' [`VersionVector`]s were
+    /// found [`VectorOrdering::Concurrent`] — a real conflict the merge's
+    /// `d_v`/peer-key fallback resolved arbitrarily rather than causally,
+    /// which a caller may want to surface to a user instead of silently
+    /// accepting.
+    pub fn merge_with_conflicts(&self, other: &CotDocument) -> Result<MergeOutcome, MergeError> {
+        let (self_id, other_id) = (doc_id(self), doc_id(other));
+        if self_id != other_id {
+            return Err(MergeError::IdMismatch { local: self_id, remote: other_id });
+        }
+
+        let local_wins = decide_winner(self, other);
+        let concurrent = is_concurrent(self, other);
+        let merged_d_v = {
+            let (self_d_v, ..) = version_fields(self);
+            let (other_d_v, ..) = version_fields(other);
+            self_d_v.max(other_d_v)
+        };
+        let merged_vv = version_vector_of(self).merged_with(&version_vector_of(other));
+        let merged_tombstones = merge_tombstones(&tombstones_of(self), &tombstones_of(other));
+
+        let document = match (self, other) {
+            (CotDocument::Api(local), CotDocument::Api(remote)) => {
+                let (winner, loser) = if local_wins { (local, remote) } else { (remote, local) };
+                let as_array = |v: &ApiRValue| match v {
+                    ApiRValue::Array(a) => Some(a.as_slice()),
+                    _ => None,
+                };
+                let as_object = |v: &ApiRValue| match v {
+                    ApiRValue::Object(o) => Some(o),
+                    _ => None,
+                };
+                Ok(CotDocument::Api(Api {
+                    d_v: merged_d_v,
+                    r: merge_text_crdt_fields(
+                        with_tombstones_applied(
+                            with_merged_version_vector(
+                                merge_r(&winner.r, &loser.r, as_array, ApiRValue::Array),
+                                &merged_vv,
+                                ApiRValue::Object,
+                            ),
+                            &merged_tombstones,
+                            as_array,
+                            ApiRValue::Array,
+                            ApiRValue::Object,
+                        ),
+                        &winner.r,
+                        &loser.r,
+                        as_object,
+                        ApiRValue::Object,
+                        ApiRValue::String,
+                    ),
+                    ..winner.clone()
+                }))
+            }
+            (CotDocument::Chat(local), CotDocument::Chat(remote)) => {
+                let (winner, loser) = if local_wins { (local, remote) } else { (remote, local) };
+                let as_array = |v: &ChatRValue| match v {
+                    ChatRValue::Array(a) => Some(a.as_slice()),
+                    _ => None,
+                };
+                let as_object = |v: &ChatRValue| match v {
+                    ChatRValue::Object(o) => Some(o),
+                    _ => None,
+                };
+                Ok(CotDocument::Chat(Chat {
+                    d_v: merged_d_v,
+                    r: merge_text_crdt_fields(
+                        with_tombstones_applied(
+                            with_merged_version_vector(
+                                merge_r(&winner.r, &loser.r, as_array, ChatRValue::Array),
+                                &merged_vv,
+                                ChatRValue::Object,
+                            ),
+                            &merged_tombstones,
+                            as_array,
+                            ChatRValue::Array,
+                            ChatRValue::Object,
+                        ),
+                        &winner.r,
+                        &loser.r,
+                        as_object,
+                        ChatRValue::Object,
+                        ChatRValue::String,
+                    ),
+                    ..winner.clone()
+                }))
+            }
+            (CotDocument::File(local), CotDocument::File(remote)) => {
+                let (winner, loser) = if local_wins { (local, remote) } else { (remote, local) };
+                let as_array = |v: &FileRValue| match v {
+                    FileRValue::Array(a) => Some(a.as_slice()),
+                    _ => None,
+                };
+                let as_object = |v: &FileRValue| match v {
+                    FileRValue::Object(o) => Some(o),
+                    _ => None,
+                };
+                Ok(CotDocument::File(File {
+                    d_v: merged_d_v,
+                    r: merge_text_crdt_fields(
+                        with_tombstones_applied(
+                            with_merged_version_vector(
+                                merge_r(&winner.r, &loser.r, as_array, FileRValue::Array),
+                                &merged_vv,
+                                FileRValue::Object,
+                            ),
+                            &merged_tombstones,
+                            as_array,
+                            FileRValue::Array,
+                            FileRValue::Object,
+                        ),
+                        &winner.r,
+                        &loser.r,
+                        as_object,
+                        FileRValue::Object,
+                        FileRValue::String,
+                    ),
+                    ..winner.clone()
+                }))
+            }
+            (CotDocument::Generic(local), CotDocument::Generic(remote)) => {
+                let (winner, loser) = if local_wins { (local, remote) } else { (remote, local) };
+                let as_array = |v: &GenericRValue| match v {
+                    GenericRValue::Array(a) => Some(a.as_slice()),
+                    _ => None,
+                };
+                let as_object = |v: &GenericRValue| match v {
+                    GenericRValue::Object(o) => Some(o),
+                    _ => None,
+                };
+                Ok(CotDocument::Generic(Generic {
+                    d_v: merged_d_v,
+                    r: merge_text_crdt_fields(
+                        with_tombstones_applied(
+                            with_merged_version_vector(
+                                merge_r(&winner.r, &loser.r, as_array, GenericRValue::Array),
+                                &merged_vv,
+                                GenericRValue::Object,
+                            ),
+                            &merged_tombstones,
+                            as_array,
+                            GenericRValue::Array,
+                            GenericRValue::Object,
+                        ),
+                        &winner.r,
+                        &loser.r,
+                        as_object,
+                        GenericRValue::Object,
+                        GenericRValue::String,
+                    ),
+                    ..winner.clone()
+                }))
+            }
+            (CotDocument::MapItem(local), CotDocument::MapItem(remote)) => {
+                let (winner, loser) = if local_wins { (local, remote) } else { (remote, local) };
+                let as_array = |v: &MapItemRValue| match v {
+                    MapItemRValue::Array(a) => Some(a.as_slice()),
+                    _ => None,
+                };
+                let as_object = |v: &MapItemRValue| match v {
+                    MapItemRValue::Object(o) => Some(o),
+                    _ => None,
+                };
+                Ok(CotDocument::MapItem(MapItem {
+                    d_v: merged_d_v,
+                    r: merge_text_crdt_fields(
+                        with_tombstones_applied(
+                            with_merged_version_vector(
+                                merge_r(&winner.r, &loser.r, as_array, MapItemRValue::Array),
+                                &merged_vv,
+                                MapItemRValue::Object,
+                            ),
+                            &merged_tombstones,
+                            as_array,
+                            MapItemRValue::Array,
+                            MapItemRValue::Object,
+                        ),
+                        &winner.r,
+                        &loser.r,
+                        as_object,
+                        MapItemRValue::Object,
+                        MapItemRValue::String,
+                    ),
+                    ..winner.clone()
+                }))
+            }
+            (CotDocument::Unknown(local), CotDocument::Unknown(remote)) => {
+                Ok(CotDocument::Unknown(merge_unknown(
+                    local,
+                    remote,
+                    local_wins,
+                    merged_d_v,
+                    &merged_vv,
+                    &merged_tombstones,
+                )))
+            }
+            _ => Err(MergeError::KindMismatch {
+                local: kind_name(self),
+                remote: kind_name(other),
+            }),
+        }?;
+
+        Ok(MergeOutcome { document, concurrent })
+    }
+
+    /// The [`VersionVector`] this document's `r` map carries under
+    /// [`VERSION_VECTOR_KEY`], or an empty one if it doesn't have one yet
+    /// (e.g. it predates this field, or was built by hand rather than via
+    /// [`cot_to_document`](crate::ditto::cot_to_document)).
+    pub fn version_vector(&self) -> VersionVector {
+        version_vector_of(self)
+    }
+
+    /// Records a local edit by `peer_key` in this document's
+    /// [`VersionVector`], creating one if it doesn't have one yet. Callers
+    /// making an in-place edit (as opposed to [`Self::merge`], which derives
+    /// its own merged vector) should call this once per edit so a later
+    /// merge can tell the edit apart from a concurrent one made elsewhere.
+    pub fn bump_version(&mut self, peer_key: &str) {
+        let mut vector = version_vector_of(self);
+        vector.bump(peer_key);
+        let map = vector.to_json_map();
+
+        match self {
+            CotDocument::Api(d) => {
+                d.r.insert(VERSION_VECTOR_KEY.to_string(), ApiRValue::Object(map));
+            }
+            CotDocument::Chat(d) => {
+                d.r.insert(VERSION_VECTOR_KEY.to_string(), ChatRValue::Object(map));
+            }
+            CotDocument::File(d) => {
+                d.r.insert(VERSION_VECTOR_KEY.to_string(), FileRValue::Object(map));
+            }
+            CotDocument::Generic(d) => {
+                d.r.insert(VERSION_VECTOR_KEY.to_string(), GenericRValue::Object(map));
+            }
+            CotDocument::MapItem(d) => {
+                d.r.insert(VERSION_VECTOR_KEY.to_string(), MapItemRValue::Object(map));
+            }
+            CotDocument::Unknown(u) => {
+                if let Some(obj) = u.raw.as_object_mut() {
+                    let r = obj
+                        .entry("r")
+                        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+                    if let Some(r) = r.as_object_mut() {
+                        r.insert(VERSION_VECTOR_KEY.to_string(), serde_json::Value::Object(map));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes every element of the repeated detail group `group` (e.g.
+    /// `"link"`) for which `predicate` returns `true`, recording each
+    /// removed element's [`or_set::tag_of`](super::or_set::tag_of) as a
+    /// tombstone so a later [`Self::merge`] with a peer that still carries a
+    /// stale copy doesn't resurrect it. A no-op if `group` isn't present or
+    /// isn't an array.
+    pub fn remove_detail_element(&mut self, group: &str, predicate: impl Fn(&Value) -> bool) {
+        fn split(
+            elements: &[Value],
+            predicate: impl Fn(&Value) -> bool,
+        ) -> (Vec<Value>, Vec<String>) {
+            let (removed, kept): (Vec<Value>, Vec<Value>) =
+                elements.iter().cloned().partition(|v| predicate(v));
+            (kept, removed.iter().map(crate::ditto::or_set::tag_of).collect())
+        }
+
+        fn record_tombstones(
+            tombstones: &mut HashMap<String, Vec<String>>,
+            group: &str,
+            new_tags: Vec<String>,
+        ) {
+            let tags = tombstones.entry(group.to_string()).or_default();
+            for tag in new_tags {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+
+        let mut tombstones = tombstones_of(self);
+
+        match self {
+            CotDocument::Api(d) => {
+                if let Some(ApiRValue::Array(elements)) = d.r.get(group) {
+                    let (kept, removed) = split(elements, predicate);
+                    record_tombstones(&mut tombstones, group, removed);
+                    d.r.insert(group.to_string(), ApiRValue::Array(kept));
+                    d.r.insert(
+                        OR_SET_TOMBSTONES_KEY.to_string(),
+                        ApiRValue::Object(tombstones_to_json(&tombstones)),
+                    );
+                }
+            }
+            CotDocument::Chat(d) => {
+                if let Some(ChatRValue::Array(elements)) = d.r.get(group) {
+                    let (kept, removed) = split(elements, predicate);
+                    record_tombstones(&mut tombstones, group, removed);
+                    d.r.insert(group.to_string(), ChatRValue::Array(kept));
+                    d.r.insert(
+                        OR_SET_TOMBSTONES_KEY.to_string(),
+                        ChatRValue::Object(tombstones_to_json(&tombstones)),
+                    );
+                }
+            }
+            CotDocument::File(d) => {
+                if let Some(FileRValue::Array(elements)) = d.r.get(group) {
+                    let (kept, removed) = split(elements, predicate);
+                    record_tombstones(&mut tombstones, group, removed);
+                    d.r.insert(group.to_string(), FileRValue::Array(kept));
+                    d.r.insert(
+                        OR_SET_TOMBSTONES_KEY.to_string(),
+                        FileRValue::Object(tombstones_to_json(&tombstones)),
+                    );
+                }
+            }
+            CotDocument::Generic(d) => {
+                if let Some(GenericRValue::Array(elements)) = d.r.get(group) {
+                    let (kept, removed) = split(elements, predicate);
+                    record_tombstones(&mut tombstones, group, removed);
+                    d.r.insert(group.to_string(), GenericRValue::Array(kept));
+                    d.r.insert(
+                        OR_SET_TOMBSTONES_KEY.to_string(),
+                        GenericRValue::Object(tombstones_to_json(&tombstones)),
+                    );
+                }
+            }
+            CotDocument::MapItem(d) => {
+                if let Some(MapItemRValue::Array(elements)) = d.r.get(group) {
+                    let (kept, removed) = split(elements, predicate);
+                    record_tombstones(&mut tombstones, group, removed);
+                    d.r.insert(group.to_string(), MapItemRValue::Array(kept));
+                    d.r.insert(
+                        OR_SET_TOMBSTONES_KEY.to_string(),
+                        MapItemRValue::Object(tombstones_to_json(&tombstones)),
+                    );
+                }
+            }
+            CotDocument::Unknown(u) => {
+                if let Some(r) = u
+                    .raw
+                    .as_object_mut()
+                    .and_then(|obj| obj.get_mut("r"))
+                    .and_then(Value::as_object_mut)
+                {
+                    if let Some(Value::Array(elements)) = r.get(group) {
+                        let (kept, removed) = split(elements, predicate);
+                        record_tombstones(&mut tombstones, group, removed);
+                        r.insert(group.to_string(), Value::Array(kept));
+                        r.insert(
+                            OR_SET_TOMBSTONES_KEY.to_string(),
+                            Value::Object(tombstones_to_json(&tombstones)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Opts `field` (e.g. `"remarks"`) into character-level merge and
+    /// applies `new_text` to it: creates its [`TextCrdt`] log from the
+    /// field's current value the first time this is called, or edits the
+    /// existing log otherwise, then writes both the log (under
+    /// [`crdt_key`](crate::ditto::text_crdt::crdt_key)) and the plain field
+    /// itself back into `r`. `lamport_counter` must be higher than any this
+    /// document has already used for `field`, e.g. a per-document counter
+    /// the caller bumps on every edit.
+    pub fn set_text_field(
+        &mut self,
+        field: &str,
+        new_text: &str,
+        peer_key: &str,
+        lamport_counter: u64,
+    ) {
+        let key = crate::ditto::text_crdt::crdt_key(field);
+
+        fn existing_crdt(
+            log: Option<&serde_json::Map<String, Value>>,
+            current_text: Option<&str>,
+            peer_key: &str,
+            lamport_counter: u64,
+        ) -> TextCrdt {
+            match log {
+                Some(map) => TextCrdt::from_json_map(map),
+                None => {
+                    let text = current_text.unwrap_or_default();
+                    TextCrdt::from_str(text, peer_key, lamport_counter).0
+                }
+            }
+        }
+
+        macro_rules! edit_typed {
+            ($doc:expr, $rvalue:ty) => {{
+                let log = match $doc.r.get(&key) {
+                    Some($rvalue::Object(o)) => Some(o),
+                    _ => None,
+                };
+                let current = match $doc.r.get(field) {
+                    Some($rvalue::String(s)) => Some(s.as_str()),
+                    _ => None,
+                };
+                let mut crdt = existing_crdt(log, current, peer_key, lamport_counter);
+                crdt.set_text(new_text, peer_key, lamport_counter);
+                $doc.r.insert(key.clone(), $rvalue::Object(crdt.to_json_map()));
+                $doc.r.insert(field.to_string(), $rvalue::String(crdt.materialize()));
+            }};
+        }
+
+        match self {
+            CotDocument::Api(d) => edit_typed!(d, ApiRValue),
+            CotDocument::Chat(d) => edit_typed!(d, ChatRValue),
+            CotDocument::File(d) => edit_typed!(d, FileRValue),
+            CotDocument::Generic(d) => edit_typed!(d, GenericRValue),
+            CotDocument::MapItem(d) => edit_typed!(d, MapItemRValue),
+            CotDocument::Unknown(u) => {
+                if let Some(r) = u.raw.as_object_mut().and_then(|obj| {
+                    obj.entry("r")
+                        .or_insert_with(|| Value::Object(serde_json::Map::new()))
+                        .as_object_mut()
+                }) {
+                    let log = r.get(&key).and_then(Value::as_object);
+                    let current = r.get(field).and_then(Value::as_str);
+                    let mut crdt = existing_crdt(log, current, peer_key, lamport_counter);
+                    crdt.set_text(new_text, peer_key, lamport_counter);
+                    r.insert(key.clone(), Value::Object(crdt.to_json_map()));
+                    r.insert(field.to_string(), Value::String(crdt.materialize()));
+                }
+            }
+        }
+    }
+}
+
+/// [`CotDocument::merge`]'s [`CotDocument::Unknown`] case: the same
+/// winner-takes-all-but-`r` rule, applied to the untyped JSON object
+/// directly since there's no struct to destructure.
+fn merge_unknown(
+    local: &UnknownDocument,
+    remote: &UnknownDocument,
+    local_wins: bool,
+    merged_d_v: u32,
+    merged_vv: &VersionVector,
+    merged_tombstones: &HashMap<String, Vec<String>>,
+) -> UnknownDocument {
+    let (winner, loser) = if local_wins { (local, remote) } else { (remote, local) };
+    let mut merged = winner.raw.clone();
+
+    if let Some(obj) = merged.as_object_mut() {
+        let loser_r = loser.raw.get("r").and_then(serde_json::Value::as_object);
+        if let Some(loser_r) = loser_r {
+            let winner_r = obj
+                .entry("r")
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+            if let Some(winner_r) = winner_r.as_object_mut() {
+                let winner_r_snapshot = winner_r.clone();
+                for (key, loser_value) in loser_r {
+                    match winner_r.get(key) {
+                        None => {
+                            winner_r.insert(key.clone(), loser_value.clone());
+                        }
+                        Some(winner_value) => {
+                            if let (Value::Array(winner_items), Value::Array(loser_items)) =
+                                (winner_value, loser_value)
+                            {
+                                let union = merge_repeated_elements(winner_items, loser_items);
+                                winner_r.insert(key.clone(), Value::Array(union));
+                            }
+                        }
+                    }
+                }
+                for (key, winner_value) in &winner_r_snapshot {
+                    let Some(field) = key.strip_suffix(TEXT_CRDT_SUFFIX) else { continue };
+                    let Some(loser_value) = loser_r.get(key) else { continue };
+                    let (Some(winner_log), Some(loser_log)) =
+                        (winner_value.as_object(), loser_value.as_object())
+                    else {
+                        continue;
+                    };
+                    let winner_log = TextCrdt::from_json_map(winner_log);
+                    let merged_log = winner_log.merge(&TextCrdt::from_json_map(loser_log));
+                    winner_r.insert(key.clone(), Value::Object(merged_log.to_json_map()));
+                    winner_r.insert(field.to_string(), Value::String(merged_log.materialize()));
+                }
+                for (group, tags) in merged_tombstones {
+                    if let Some(live) = winner_r
+                        .get(group)
+                        .and_then(Value::as_array)
+                        .map(|items| apply_tombstones(items, tags))
+                    {
+                        winner_r.insert(group.clone(), Value::Array(live));
+                    }
+                }
+                if !merged_tombstones.is_empty() {
+                    winner_r.insert(
+                        OR_SET_TOMBSTONES_KEY.to_string(),
+                        Value::Object(tombstones_to_json(merged_tombstones)),
+                    );
+                }
+                if *merged_vv != VersionVector::new() {
+                    winner_r.insert(
+                        VERSION_VECTOR_KEY.to_string(),
+                        serde_json::Value::Object(merged_vv.to_json_map()),
+                    );
+                }
+            }
+        }
+        obj.insert("d_v".to_string(), serde_json::Value::from(merged_d_v));
+    }
+
+    UnknownDocument { raw: merged }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn map_item(a: &str, d_v: u32, r: HashMap<String, crate::ditto::MapItemRValue>) -> CotDocument {
+        CotDocument::MapItem(MapItem {
+            id: "UID-1".to_string(),
+            a: a.to_string(),
+            b: 0.0,
+            c: None,
+            d: "UID-1".to_string(),
+            d_c: 0,
+            d_r: false,
+            d_v,
+            source: None,
+            e: "ALPHA-1".to_string(),
+            f: None,
+            g: "2.0".to_string(),
+            h: None,
+            i: Some(10.0),
+            j: Some(35.0),
+            k: Some(5.0),
+            l: Some(-118.0),
+            n: Some(0.0),
+            o: Some(0.0),
+            p: "h-g-i-g-o".to_string(),
+            q: String::new(),
+            r,
+            s: String::new(),
+            t: String::new(),
+            u: String::new(),
+            v: String::new(),
+            w: "a-f-G-U-C".to_string(),
+        })
+    }
+
+    #[test]
+    fn higher_d_v_wins_whole_document_fields() {
+        let local = map_item("peer-a", 1, HashMap::new());
+        let remote = map_item("peer-b", 2, HashMap::new());
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(merged.a, "peer-b");
+        assert_eq!(merged.d_v, 2);
+    }
+
+    #[test]
+    fn tied_d_v_breaks_on_peer_key() {
+        let local = map_item("peer-a", 1, HashMap::new());
+        let remote = map_item("peer-z", 1, HashMap::new());
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(merged.a, "peer-z");
+    }
+
+    #[test]
+    fn result_d_v_is_the_max_of_both_sides() {
+        let local = map_item("peer-z", 5, HashMap::new());
+        let remote = map_item("peer-a", 9, HashMap::new());
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(merged.d_v, 9);
+    }
+
+    #[test]
+    fn r_keys_present_on_only_one_side_are_carried_through() {
+        let mut local_r = HashMap::new();
+        local_r.insert(
+            "contact_callsign".to_string(),
+            crate::ditto::MapItemRValue::String("ALPHA-1".to_string()),
+        );
+
+        let mut remote_r = HashMap::new();
+        remote_r.insert("status_battery".to_string(), crate::ditto::MapItemRValue::Number(50.0));
+
+        let local = map_item("peer-a", 1, local_r);
+        let remote = map_item("peer-b", 2, remote_r);
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert!(merged.r.contains_key("contact_callsign"));
+        assert!(merged.r.contains_key("status_battery"));
+    }
+
+    #[test]
+    fn r_key_present_on_both_sides_takes_the_winners_value() {
+        let mut local_r = HashMap::new();
+        local_r.insert("status_battery".to_string(), crate::ditto::MapItemRValue::Number(30.0));
+
+        let mut remote_r = HashMap::new();
+        remote_r.insert("status_battery".to_string(), crate::ditto::MapItemRValue::Number(90.0));
+
+        let local = map_item("peer-a", 1, local_r);
+        let remote = map_item("peer-b", 2, remote_r);
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(
+            merged.r.get("status_battery"),
+            Some(&crate::ditto::MapItemRValue::Number(90.0))
+        );
+    }
+
+    #[test]
+    fn merge_is_commutative() {
+        let local = map_item("peer-a", 1, HashMap::new());
+        let remote = map_item("peer-b", 2, HashMap::new());
+
+        let a_then_b = local.merge(&remote).unwrap();
+        let b_then_a = remote.merge(&local).unwrap();
+        assert_eq!(a_then_b.to_flattened_json(), b_then_a.to_flattened_json());
+    }
+
+    #[test]
+    fn merge_is_idempotent() {
+        let doc = map_item("peer-a", 3, HashMap::new());
+        let merged = doc.merge(&doc).unwrap();
+        assert_eq!(merged.to_flattened_json(), doc.to_flattened_json());
+    }
+
+    #[test]
+    fn mismatched_ids_are_rejected() {
+        let local = map_item("peer-a", 1, HashMap::new());
+        let CotDocument::MapItem(mut remote_item) = map_item("peer-b", 1, HashMap::new()) else {
+            unreachable!()
+        };
+        remote_item.id = "UID-2".to_string();
+        let remote = CotDocument::MapItem(remote_item);
+
+        assert!(matches!(local.merge(&remote), Err(MergeError::IdMismatch { .. })));
+    }
+
+    fn with_version_vector(doc: CotDocument, vector: &VersionVector) -> CotDocument {
+        let CotDocument::MapItem(mut item) = doc else { unreachable!() };
+        item.r.insert(VERSION_VECTOR_KEY.to_string(), MapItemRValue::Object(vector.to_json_map()));
+        CotDocument::MapItem(item)
+    }
+
+    #[test]
+    fn a_vectors_causal_successor_wins_even_with_a_lower_d_v() {
+        // peer-a's own edit (a real successor) has a lower `d_v` than
+        // peer-b's document, but dominates it causally — the vector should
+        // override the `d_v` comparison [`decide_winner`] would otherwise use.
+        let base_vector = VersionVector::initial("peer-a");
+        let mut successor_vector = base_vector.clone();
+        successor_vector.bump("peer-a");
+
+        let successor =
+            with_version_vector(map_item("peer-a", 1, HashMap::new()), &successor_vector);
+        let higher_d_v_but_older =
+            with_version_vector(map_item("peer-b", 5, HashMap::new()), &base_vector);
+
+        let merged = successor.merge(&higher_d_v_but_older).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(merged.a, "peer-a");
+    }
+
+    #[test]
+    fn concurrent_vectors_fall_back_to_the_d_v_tie_break_and_are_flagged() {
+        // Mirrors `tests/e2e_multi_peer.rs`'s offline-edit scenario: two
+        // peers independently bump their own entry from the same base
+        // vector, so neither dominates.
+        let base_vector = VersionVector::initial("peer-a");
+        let mut peer_a_vector = base_vector.clone();
+        peer_a_vector.bump("peer-a");
+        let mut peer_b_vector = base_vector.clone();
+        peer_b_vector.bump("peer-b");
+
+        let peer_a_edit =
+            with_version_vector(map_item("peer-a", 2, HashMap::new()), &peer_a_vector);
+        let peer_b_edit =
+            with_version_vector(map_item("peer-b", 2, HashMap::new()), &peer_b_vector);
+
+        let outcome = peer_a_edit.merge_with_conflicts(&peer_b_edit).unwrap();
+        assert!(outcome.concurrent);
+        let CotDocument::MapItem(merged) = outcome.document else { unreachable!() };
+        // `d_v` ties, so the fallback tie-break (greater peer key) applies.
+        assert_eq!(merged.a, "peer-b");
+    }
+
+    #[test]
+    fn merges_vector_takes_the_element_wise_max_of_both_sides() {
+        let mut local_vector = VersionVector::new();
+        local_vector.bump("peer-a");
+        local_vector.bump("peer-a"); // peer-a: 2
+
+        let mut remote_vector = VersionVector::new();
+        remote_vector.bump("peer-b"); // peer-b: 1
+
+        let local = with_version_vector(map_item("peer-a", 1, HashMap::new()), &local_vector);
+        let remote = with_version_vector(map_item("peer-b", 2, HashMap::new()), &remote_vector);
+
+        let merged = local.merge(&remote).unwrap();
+        assert_eq!(merged.version_vector(), local_vector.merged_with(&remote_vector));
+    }
+
+    #[test]
+    fn bump_version_creates_a_vector_on_a_document_that_has_none() {
+        let mut doc = map_item("peer-a", 1, HashMap::new());
+        assert_eq!(doc.version_vector(), VersionVector::new());
+
+        doc.bump_version("peer-a");
+        assert_eq!(doc.version_vector(), VersionVector::initial("peer-a"));
+    }
+
+    #[test]
+    fn r_key_holding_arrays_on_both_sides_is_unioned_rather_than_clobbered() {
+        let mut local_r = HashMap::new();
+        local_r.insert(
+            "link".to_string(),
+            crate::ditto::MapItemRValue::Array(vec![serde_json::json!({"uid": "link-1"})]),
+        );
+
+        let mut remote_r = HashMap::new();
+        remote_r.insert(
+            "link".to_string(),
+            crate::ditto::MapItemRValue::Array(vec![serde_json::json!({"uid": "link-2"})]),
+        );
+
+        let local = map_item("peer-a", 1, local_r);
+        let remote = map_item("peer-b", 2, remote_r);
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        // `remote` has the higher `d_v` and wins, so its element comes first.
+        assert_eq!(
+            merged.r.get("link"),
+            Some(&crate::ditto::MapItemRValue::Array(vec![
+                serde_json::json!({"uid": "link-2"}),
+                serde_json::json!({"uid": "link-1"}),
+            ]))
+        );
+    }
+
+    #[test]
+    fn unknown_documents_r_key_holding_arrays_is_also_unioned() {
+        let local = CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({
+                "id": "UID-1",
+                "d_v": 1,
+                "a": "peer-a",
+                "r": {"link": [{"uid": "link-1"}]},
+            }),
+        });
+        let remote = CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({
+                "id": "UID-1",
+                "d_v": 1,
+                "a": "peer-b",
+                "r": {"link": [{"uid": "link-2"}]},
+            }),
+        });
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::Unknown(merged) = merged else { unreachable!() };
+        // `remote` wins the `peer-a` vs `peer-b` tie-break, so its element
+        // comes first.
+        assert_eq!(
+            merged.raw["r"]["link"],
+            serde_json::json!([{"uid": "link-2"}, {"uid": "link-1"}])
+        );
+    }
+
+    #[test]
+    fn removed_element_does_not_resurface_from_a_peers_stale_copy() {
+        let mut local_r = HashMap::new();
+        local_r.insert(
+            "link".to_string(),
+            crate::ditto::MapItemRValue::Array(vec![serde_json::json!({"uid": "link-1"})]),
+        );
+        let mut local = map_item("peer-a", 1, local_r);
+        // peer-a removes the link it just added.
+        local.remove_detail_element("link", |_| true);
+        let CotDocument::MapItem(after_removal) = &local else { unreachable!() };
+        assert_eq!(
+            after_removal.r.get("link"),
+            Some(&crate::ditto::MapItemRValue::Array(vec![]))
+        );
+
+        // peer-b never saw the removal and still has its stale copy.
+        let mut remote_r = HashMap::new();
+        remote_r.insert(
+            "link".to_string(),
+            crate::ditto::MapItemRValue::Array(vec![serde_json::json!({"uid": "link-1"})]),
+        );
+        let remote = map_item("peer-b", 1, remote_r);
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(merged.r.get("link"), Some(&crate::ditto::MapItemRValue::Array(vec![])));
+    }
+
+    #[test]
+    fn tombstones_merge_across_both_sides_and_persist() {
+        let mut local = map_item("peer-a", 1, HashMap::new());
+        local.bump_version("peer-a");
+        local.remove_detail_element("link", |_| true); // no-op, group absent
+
+        let mut remote_r = HashMap::new();
+        remote_r.insert(
+            "sensor".to_string(),
+            crate::ditto::MapItemRValue::Array(vec![serde_json::json!({"uid": "sensor-1"})]),
+        );
+        let mut remote = map_item("peer-b", 1, remote_r);
+        remote.remove_detail_element("sensor", |_| true);
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(merged.r.get("sensor"), Some(&crate::ditto::MapItemRValue::Array(vec![])));
+    }
+
+    #[test]
+    fn set_text_field_creates_a_log_and_updates_the_plain_field() {
+        let mut doc = map_item("peer-a", 1, HashMap::new());
+        doc.set_text_field("remarks", "hello", "peer-a", 0);
+
+        let CotDocument::MapItem(item) = &doc else { unreachable!() };
+        assert_eq!(
+            item.r.get("remarks"),
+            Some(&crate::ditto::MapItemRValue::String("hello".to_string()))
+        );
+        assert!(item.r.contains_key("remarks__text_crdt"));
+    }
+
+    #[test]
+    fn concurrent_text_edits_both_survive_a_merge() {
+        let mut base_r = HashMap::new();
+        base_r.insert(
+            "remarks".to_string(),
+            crate::ditto::MapItemRValue::String("hello".to_string()),
+        );
+        let base = map_item("peer-a", 1, base_r);
+
+        let mut local = base.clone();
+        local.set_text_field("remarks", "hello there", "peer-a", 10);
+        let CotDocument::MapItem(local_item) = &mut local else { unreachable!() };
+        local_item.d_v = 2;
+
+        let mut remote = base;
+        remote.set_text_field("remarks", "hello world", "peer-b", 10);
+        let CotDocument::MapItem(remote_item) = &mut remote else { unreachable!() };
+        remote_item.d_v = 2;
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        let Some(crate::ditto::MapItemRValue::String(text)) = merged.r.get("remarks") else {
+            unreachable!()
+        };
+        assert!(text.starts_with("hello"));
+        assert!(text.contains("there") || text.contains("world"));
+        assert_ne!(text, "hello");
+    }
+
+    #[test]
+    fn a_text_field_without_a_crdt_log_on_either_side_still_uses_plain_lww() {
+        let mut local_r = HashMap::new();
+        local_r.insert(
+            "remarks".to_string(),
+            crate::ditto::MapItemRValue::String("local text".to_string()),
+        );
+        let local = map_item("peer-b", 2, local_r);
+
+        let mut remote_r = HashMap::new();
+        remote_r.insert(
+            "remarks".to_string(),
+            crate::ditto::MapItemRValue::String("remote text".to_string()),
+        );
+        let remote = map_item("peer-a", 1, remote_r);
+
+        let merged = local.merge(&remote).unwrap();
+        let CotDocument::MapItem(merged) = merged else { unreachable!() };
+        assert_eq!(
+            merged.r.get("remarks"),
+            Some(&crate::ditto::MapItemRValue::String("local text".to_string()))
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Step-by-step upgrade of a raw Ditto document's schema version.
+//!
+//! [`schema_version`](super::schema_version) gives the `d_v`/`_v` stamp a
+//! name and a comparison, but nothing actually rewrites an old document to
+//! the shape a newer build expects — a Rust peer that's moved to schema `3`
+//! has no way to read a document a still-`2` peer wrote other than whatever
+//! [`cot_event_from_ditto_document`](super::from_ditto::cot_event_from_ditto_document)
+//! can salvage from the mismatch. This module adds the missing piece: a
+//! [`Migration`] trait for one version-to-version step, a [`MigrationRegistry`]
+//! that chains registered steps together (mirroring how
+//! [`TransformerRegistry`](super::transformer::TransformerRegistry) chains
+//! [`CotTransformer`](super::transformer::CotTransformer) implementations),
+//! and [`MigrationRegistry::upgrade`] to run a raw `Value` through that chain up to
+//! [`schema_version::CURRENT`](super::schema_version::CURRENT) before
+//! [`serde_json::from_value`] ever sees it. A document stamped newer than
+//! [`schema_version::CURRENT`] fails loudly with
+//! [`MigrationError::FutureSchemaVersion`] rather than silently dropping the
+//! fields this build doesn't understand.
+
+use serde_json::Value;
+
+use crate::ditto::schema_version::CURRENT;
+
+/// Failure modes for [`MigrationRegistry::upgrade`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MigrationError {
+    /// `value` has no numeric `d_v`/`_v` field to read a version from.
+    #[error("document has no usable d_v/_v schema-version field")]
+    MissingVersion,
+
+    /// `value`'s declared version is newer than this build understands.
+    #[error("document schema version {found} is newer than this build supports ({current})")]
+    FutureSchemaVersion {
+        /// The version found on the document.
+        found: u32,
+        /// [`schema_version::CURRENT`](super::schema_version::CURRENT)'s
+        /// version number.
+        current: u32,
+    },
+
+    /// No registered [`Migration`] starts at the document's current version,
+    /// so the chain can't progress toward [`schema_version::CURRENT`].
+    #[error("no registered migration starts at schema version {0}")]
+    NoMigrationFrom(u32),
+
+    /// A [`Migration::migrate`] step itself reported a failure.
+    #[error("migration step {from} -> {to} failed: {reason}")]
+    StepFailed {
+        /// The step's declared [`Migration::FROM`].
+        from: u32,
+        /// The step's declared [`Migration::TO`].
+        to: u32,
+        /// What went wrong.
+        reason: String,
+    },
+}
+
+/// One version-to-version upgrade step, rewriting a raw document's JSON
+/// shape in place.
+pub trait Migration {
+    /// The schema version this step upgrades from.
+    const FROM: u32;
+    /// The schema version this step upgrades to.
+    const TO: u32;
+
+    /// Rewrites `value` from [`Self::FROM`]'s shape to [`Self::TO`]'s. Does
+    /// not need to touch `d_v`/`_v` itself — [`MigrationRegistry::upgrade`] stamps the new
+    /// version after a successful step.
+    fn migrate(value: &mut Value) -> Result<(), MigrationError>;
+}
+
+/// Type-erased form of a registered [`Migration`], so a [`MigrationRegistry`]
+/// can hold steps with different `FROM`/`TO` pairs in one list.
+trait ErasedMigration: Send + Sync {
+    fn from_version(&self) -> u32;
+    fn to_version(&self) -> u32;
+    fn apply(&self, value: &mut Value) -> Result<(), MigrationError>;
+}
+
+struct MigrationStep<M>(std::marker::PhantomData<M>);
+
+impl<M: Migration + Send + Sync> ErasedMigration for MigrationStep<M> {
+    fn from_version(&self) -> u32 {
+        M::FROM
+    }
+
+    fn to_version(&self) -> u32 {
+        M::TO
+    }
+
+    fn apply(&self, value: &mut Value) -> Result<(), MigrationError> {
+        M::migrate(value).map_err(|err| match err {
+            MigrationError::StepFailed { reason, .. } => MigrationError::StepFailed {
+                from: M::FROM,
+                to: M::TO,
+                reason,
+            },
+            other => other,
+        })
+    }
+}
+
+/// A chain of registered [`Migration`] steps, applied in order by [`Self::upgrade`].
+#[derive(Default)]
+pub struct MigrationRegistry {
+    steps: Vec<Box<dyn ErasedMigration>>,
+}
+
+impl MigrationRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// Registers `M`, returning `self` for chaining multiple registrations.
+    pub fn register<M: Migration + Send + Sync + 'static>(mut self) -> Self {
+        self.steps.push(Box::new(MigrationStep::<M>(std::marker::PhantomData)));
+        self
+    }
+
+    /// Applies the registered migration chain to `value`, starting from its
+    /// declared `d_v`/`_v` and stepping forward one registered [`Migration`]
+    /// at a time until [`schema_version::CURRENT`] is reached. Returns the
+    /// `(from, to)` pairs applied, in order, so callers can log the upgrade
+    /// path.
+    ///
+    /// Fails with [`MigrationError::FutureSchemaVersion`] if `value`'s
+    /// version already exceeds [`schema_version::CURRENT`], and with
+    /// [`MigrationError::NoMigrationFrom`] if no registered step starts
+    /// where the chain currently stands.
+    pub fn upgrade(&self, value: &mut Value) -> Result<Vec<(u32, u32)>, MigrationError> {
+        let mut version = read_version(value)?;
+        if version > CURRENT.0 {
+            return Err(MigrationError::FutureSchemaVersion { found: version, current: CURRENT.0 });
+        }
+
+        let mut applied = Vec::new();
+        while version < CURRENT.0 {
+            let step = self
+                .steps
+                .iter()
+                .find(|step| step.from_version() == version)
+                .ok_or(MigrationError::NoMigrationFrom(version))?;
+            step.apply(value)?;
+            version = step.to_version();
+            set_version(value, version);
+            applied.push((step.from_version(), version));
+        }
+        Ok(applied)
+    }
+}
+
+fn read_version(value: &Value) -> Result<u32, MigrationError> {
+    value
+        .get("d_v")
+        .or_else(|| value.get("_v"))
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .ok_or(MigrationError::MissingVersion)
+}
+
+fn set_version(value: &mut Value, version: u32) {
+    let Some(obj) = value.as_object_mut() else { return };
+    for key in ["d_v", "_v"] {
+        if obj.contains_key(key) {
+            obj.insert(key.to_string(), Value::from(version));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct AddRemarksField;
+
+    impl Migration for AddRemarksField {
+        const FROM: u32 = 1;
+        const TO: u32 = 2;
+
+        fn migrate(value: &mut Value) -> Result<(), MigrationError> {
+            let Some(obj) = value.as_object_mut() else {
+                return Err(MigrationError::StepFailed {
+                    from: 1,
+                    to: 2,
+                    reason: "expected a JSON object".to_string(),
+                });
+            };
+            obj.entry("remarks").or_insert_with(|| Value::String(String::new()));
+            Ok(())
+        }
+    }
+
+    fn registry() -> MigrationRegistry {
+        MigrationRegistry::new().register::<AddRemarksField>()
+    }
+
+    #[test]
+    fn upgrade_applies_a_single_step_and_stamps_the_new_version() {
+        let mut doc = json!({"_id": "UID-1", "d_v": 1});
+        let applied = registry().upgrade(&mut doc).unwrap();
+        assert_eq!(applied, vec![(1, 2)]);
+        assert_eq!(doc["d_v"], json!(2));
+        assert_eq!(doc["remarks"], json!(""));
+    }
+
+    #[test]
+    fn upgrade_is_a_no_op_already_at_the_current_version() {
+        let mut doc = json!({"_id": "UID-1", "d_v": CURRENT.0});
+        let applied = registry().upgrade(&mut doc).unwrap();
+        assert!(applied.is_empty());
+    }
+
+    #[test]
+    fn upgrade_rejects_a_future_schema_version() {
+        let mut doc = json!({"_id": "UID-1", "d_v": CURRENT.0 + 1});
+        let err = registry().upgrade(&mut doc).unwrap_err();
+        assert!(matches!(err, MigrationError::FutureSchemaVersion { .. }));
+    }
+
+    #[test]
+    fn upgrade_rejects_a_missing_version() {
+        let mut doc = json!({"_id": "UID-1"});
+        let err = registry().upgrade(&mut doc).unwrap_err();
+        assert_eq!(err, MigrationError::MissingVersion);
+    }
+
+    #[test]
+    fn upgrade_rejects_an_unreachable_starting_version_below_current() {
+        let mut doc = json!({"_id": "UID-1", "d_v": 0});
+        let err = registry().upgrade(&mut doc).unwrap_err();
+        assert_eq!(err, MigrationError::NoMigrationFrom(0));
+    }
+}
@@ -3,31 +3,194 @@
 //! This module provides functionality to transform CoT (Cursor on Target) events
 //! into Ditto documents according to the Ditto JSON schemas.
 
+pub mod attachment;
+pub mod avro;
+pub mod base64_data;
+pub mod batch;
+pub mod bson;
+pub mod cached_document;
+pub mod canonical_cbor;
+pub mod changelog;
+pub mod common_fields;
+pub mod coordinate_layout;
+pub mod cot_filter;
+pub mod cot_query;
+pub mod delta;
+pub mod detail_accessor;
+pub mod detail_encoding;
+pub mod detail_index;
+pub mod detail_merge;
 pub mod dql_support;
+pub mod encryption;
+pub mod filter;
 pub mod from_ditto;
 pub mod from_ditto_util;
+pub mod geo_point;
+pub mod live_observer;
+pub mod merge;
+pub mod migrations;
+pub mod observe;
+pub mod msgpack;
+pub mod or_set;
+pub mod projection;
+pub mod prune;
+pub mod reaper;
+pub mod query;
 pub mod r_field_flattening;
+pub mod recurrence;
+pub mod repair;
+pub mod repeated_detail_merge;
+pub mod roundtrip;
+pub mod staleness;
 #[rustfmt::skip]
 pub mod schema;
+pub mod schema_document;
+pub mod schema_version;
+pub mod signing;
+pub mod tagged_schema;
+pub mod text_crdt;
 pub mod to_ditto;
 pub mod sdk_conversion;
+pub mod sync;
+pub mod sync_dag;
+pub mod time_range;
+pub mod transformer;
+pub mod validation;
+pub mod version_vector;
+
+// Re-export the attachment subsystem
+pub use attachment::{AttachmentFetchEvent, CotAttachment, CotAttachmentFetcher};
 
 // Re-export the main types and functions from to_ditto
 pub use to_ditto::{
-    cot_to_document, cot_to_flattened_document, transform_chat_event, transform_emergency_event,
-    transform_location_event, CotDocument,
+    cot_to_document, cot_to_document_checked, cot_to_document_merged, cot_to_flattened_document,
+    transform_chat_event, transform_emergency_event, transform_location_event,
+    try_cot_to_document, CotDocument, UnknownDocument,
 };
 
 // Re-export the conversion functions from from_ditto
-pub use from_ditto::{cot_event_from_ditto_document, cot_event_from_flattened_json};
-pub use from_ditto_util::{flat_cot_event_from_ditto, flat_cot_event_from_flattened_json};
+pub use from_ditto::{
+    cot_event_from_ditto_document, cot_event_from_flattened_json,
+    cot_event_from_flattened_json_checked, parse_flexible_timestamp,
+    try_cot_event_from_ditto_document, try_cot_event_from_flattened_json, RoundTripIssue,
+    RoundTripReport, TimestampFormat,
+};
+pub use from_ditto_util::{
+    cot_document_from_flat_cot_event, flat_cot_event_from_ditto,
+    flat_cot_event_from_ditto_with_config, flat_cot_event_from_flattened_json,
+    flat_cot_event_from_flattened_json_with_config, try_flat_cot_event_from_ditto,
+    try_flat_cot_event_from_ditto_with_config, try_flat_cot_event_from_flattened_json,
+    try_flat_cot_event_from_flattened_json_with_config, CotDocumentKind, TimeFieldConfig,
+};
 
 // Re-export the schema types
 pub use schema::*;
 
+// Re-export the tagged-schema discriminator trait
+pub use tagged_schema::TaggedSchema;
+
+// Re-export the staleness evaluation and soft-delete pruning API
+pub use staleness::{is_stale, prune_expired, soft_delete, stale_documents, valid_between};
+
+// Re-export the schema document generator
+pub use schema_document::{RoutingRule, SchemaDocument};
+
+// Re-export the schema-version negotiation API
+pub use schema_version::{negotiate, CotSchemaVersion, Compatibility};
+
+// Re-export the time-range query builder
+pub use query::TimeRangeFilter;
+
+// Re-export the injection-safe DQL filter AST
+pub use filter::{Field, Filter, InvalidFieldName, Order, Value};
+
+// Re-export the Stream-based live-query subscription API
+pub use observe::{observe_documents, ChangeSet, CotEventStream, ObserverStream};
+
+// Re-export the stale-time TTL reaper
+pub use reaper::{expire_stale_documents, purge_removed, spawn_reaper};
+
+// Re-export the typed detail-map accessor trait
+pub use detail_accessor::DetailAccessor;
+
+// Re-export the pluggable detail-section serialization API
+pub use detail_encoding::{
+    DetailEncoder, DetailEncoding, JsonDetailEncoder, MsgPackDetailEncoder, XmlDetailEncoder,
+};
+
+// Re-export the pluggable CoT event-type transformer registry
+pub use transformer::{CotTransformer, TransformerRegistry};
+
+// Re-export the MessagePack binary encoding API
+pub use msgpack::{
+    cot_document_from_msgpack, cot_document_to_msgpack, flattened_document_from_msgpack,
+    flattened_document_to_msgpack,
+};
+
+// Re-export the Avro binary encoding API for flattened documents
+pub use avro::{
+    flattened_document_from_avro, flattened_document_to_avro, FLATTENED_DOCUMENT_SCHEMA,
+};
+
+// Re-export the detail projection/pruning API
+pub use projection::{cot_to_document_with, prune_document, prune_flattened_document, DetailProjection};
+
 // Re-export observer document conversion utilities
 pub use sdk_conversion::{
     observer_json_to_cot_document, observer_json_to_json_with_r_fields,
     get_document_id_from_value, get_document_id_from_json,
     get_document_type_from_value, get_document_type_from_json
 };
+
+// Re-export the live-query-to-CotEvent observer subsystem
+pub use live_observer::{subscribe_cot_events, CotEventChange, CotEventObserver};
+
+// Re-export the AEAD detail-encryption API
+pub use encryption::{EncryptedField, EncryptionError};
+
+// Re-export the active-window query and periodic-occurrence expansion API
+pub use recurrence::{active_in, occurrences_in_window, RecurrenceBound, RecurrenceRule};
+
+// Re-export the per-peer version-vector type used by `merge`
+pub use version_vector::{VectorOrdering, VersionVector};
+
+// Re-export the parallel batch conversion API
+pub use batch::{
+    convert_many, convert_many_to_events, convert_many_to_events_with_workers,
+    convert_many_with_workers,
+};
+
+// Re-export the raw-document structural validator
+pub use validation::{validate, validate_flattened_json, Constraint, FieldError};
+
+// Re-export the compact "lat,lon,hae" geographic-point type
+pub use geo_point::{GeoParseError, GeoPoint};
+
+// Re-export the tolerant-decode/canonical-encode base64 payload type
+pub use base64_data::Base64Data;
+
+// Re-export the cross-variant common-field accessor trait
+pub use common_fields::CommonDocumentFields;
+
+// Re-export the explicit, overridable flattened-JSON coordinate-field mapping
+pub use coordinate_layout::{CeSource, CoordinateLayout, CoordinateLayoutRegistry, DocumentKind};
+
+// Re-export the query/filter/facet/highlight pipeline over flattened documents
+pub use cot_query::{Comparator, CotQuery, FilterExpr, Highlight, QueryResponse};
+
+// Re-export the in-process WHERE-clause filter evaluator for CotDocument
+pub use cot_filter::{CompareOp, CotFilter, CotFilterParseError, Literal};
+
+// Re-export the deterministic (RFC 8949 canonical) CBOR byte encoder
+pub use canonical_cbor::to_canonical_cbor_bytes;
+
+// Re-export the memoized-JSON `DittoDocument` wrapper for CotDocument
+pub use cached_document::CachedCotDocument;
+
+// Re-export the schema-version migration framework
+pub use migrations::{Migration, MigrationError, MigrationRegistry};
+
+// Re-export the detached-signature subsystem
+pub use signing::{
+    canonical_bytes, sign_document, verify_document, DocumentSignature, SIGNATURE_KEY,
+};
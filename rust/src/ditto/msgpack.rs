@@ -0,0 +1,95 @@
+//! MessagePack binary encoding for [`CotDocument`], as an alternative wire
+//! form alongside the crate's usual JSON-shaped documents.
+//!
+//! CoT traffic over a constrained tactical mesh link benefits from a
+//! compact binary form; MessagePack gets that without touching
+//! [`CotDocument`]'s shape at all, since it serializes through the same
+//! `serde::Serialize`/`Deserialize` impls JSON already uses — the
+//! single-letter field keys and [`MapItemRValue`](super::schema::MapItemRValue)-style
+//! `r` field enums carry over unchanged, so a round trip through msgpack
+//! decodes to the exact same [`CotDocument`] a round trip through JSON would.
+
+use rmp_serde;
+use serde_json::Value;
+
+use crate::error::CotError;
+
+use super::to_ditto::CotDocument;
+
+/// Encodes `document` as MessagePack bytes.
+pub fn cot_document_to_msgpack(document: &CotDocument) -> Result<Vec<u8>, CotError> {
+    rmp_serde::to_vec_named(document).map_err(|e| CotError::MsgpackEncode(e.to_string()))
+}
+
+/// Decodes a [`CotDocument`] previously written by [`cot_document_to_msgpack`].
+pub fn cot_document_from_msgpack(bytes: &[u8]) -> Result<CotDocument, CotError> {
+    rmp_serde::from_slice(bytes).map_err(|e| CotError::MsgpackDecode(e.to_string()))
+}
+
+/// Encodes a flattened Ditto document (as produced by
+/// [`cot_to_flattened_document`](super::to_ditto::cot_to_flattened_document))
+/// as MessagePack bytes.
+pub fn flattened_document_to_msgpack(document: &Value) -> Result<Vec<u8>, CotError> {
+    rmp_serde::to_vec_named(document).map_err(|e| CotError::MsgpackEncode(e.to_string()))
+}
+
+/// Decodes a flattened Ditto document previously written by
+/// [`flattened_document_to_msgpack`].
+pub fn flattened_document_from_msgpack(bytes: &[u8]) -> Result<Value, CotError> {
+    rmp_serde::from_slice(bytes).map_err(|e| CotError::MsgpackDecode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::{cot_to_document, cot_to_flattened_document};
+
+    fn event() -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "uid-1".to_string(),
+            event_type: "a-f-G-U-C".to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::new(34.0, -118.0, 100.0),
+            detail: r#"<detail><contact callsign="ALPHA-1"/></detail>"#.to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn typed_document_round_trips_through_msgpack() {
+        let original = cot_to_document(&event(), "peer");
+        let bytes = cot_document_to_msgpack(&original).unwrap();
+        let decoded = cot_document_from_msgpack(&bytes).unwrap();
+
+        assert_eq!(original.to_flattened_json(), decoded.to_flattened_json());
+    }
+
+    #[test]
+    fn flattened_document_round_trips_through_msgpack() {
+        let original = cot_to_flattened_document(&event(), "peer");
+        let bytes = flattened_document_to_msgpack(&original).unwrap();
+        let decoded = flattened_document_from_msgpack(&bytes).unwrap();
+
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn msgpack_payload_is_smaller_than_json() {
+        let document = cot_to_document(&event(), "peer");
+        let json_bytes = serde_json::to_vec(&document).unwrap();
+        let msgpack_bytes = cot_document_to_msgpack(&document).unwrap();
+
+        assert!(msgpack_bytes.len() < json_bytes.len());
+    }
+
+    #[test]
+    fn decoding_garbage_bytes_is_a_typed_error() {
+        let err = cot_document_from_msgpack(&[0xff, 0x00, 0x01]).unwrap_err();
+        assert!(matches!(err, CotError::MsgpackDecode(_)));
+    }
+}
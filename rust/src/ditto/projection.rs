@@ -0,0 +1,209 @@
+//! CalDAV `calendar-data`-style detail projection/pruning for CoT documents.
+//!
+//! RFC 4791's `CALDAV:calendar-data` element lets a client ask a server to
+//! prune a returned calendar object down to just the requested components
+//! and properties instead of shipping the whole thing. [`DetailProjection`]
+//! ports that idea to CoT detail trees: an allow-list of detail root tag
+//! names (`contact`, `track`, ...) that [`prune_document`] keeps, dropping
+//! every other detail child so a document with a large `<detail>` section
+//! can be stored or returned in reduced form.
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::cot_events::CotEvent;
+use crate::ditto::r_field_flattening::tokenize_path;
+use crate::ditto::to_ditto::cot_to_document;
+use crate::ditto::CotDocument;
+
+/// An allow-list of detail child tag names (`r`'s top-level keys, e.g.
+/// `contact`, `track`) that survives a [`prune_document`] pass.
+///
+/// An empty projection keeps no detail children at all: it prunes a document
+/// down to just its top-level event attributes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DetailProjection {
+    allowed: HashSet<String>,
+}
+
+impl DetailProjection {
+    /// Builds a projection from an allow-list of detail root tag names.
+    pub fn new(allowed_tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            allowed: allowed_tags.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns whether `tag` (a detail root tag name, e.g. `contact`) is kept
+    /// by this projection.
+    pub fn allows(&self, tag: &str) -> bool {
+        self.allowed.contains(tag)
+    }
+}
+
+/// Converts `event` to a [`CotDocument`] and immediately prunes its detail
+/// down to `projection`'s allow-list, equivalent to `cot_to_document` followed
+/// by [`prune_document`].
+pub fn cot_to_document_with(
+    event: &CotEvent,
+    peer_key: &str,
+    projection: &DetailProjection,
+) -> CotDocument {
+    let mut doc = cot_to_document(event, peer_key);
+    prune_document(&mut doc, projection);
+    doc
+}
+
+/// Prunes `doc`'s detail children down to `projection`'s allow-list in place.
+///
+/// Top-level event attributes (`_id`, `w`, `n`, `o`, ...) are always kept;
+/// only `r`'s entries are filtered. Idempotent: pruning an already-pruned
+/// document against the same projection again is a no-op.
+pub fn prune_document(doc: &mut CotDocument, projection: &DetailProjection) {
+    match doc {
+        CotDocument::Api(d) => d.r.retain(|tag, _| projection.allows(tag)),
+        CotDocument::Chat(d) => d.r.retain(|tag, _| projection.allows(tag)),
+        CotDocument::File(d) => d.r.retain(|tag, _| projection.allows(tag)),
+        CotDocument::Generic(d) => d.r.retain(|tag, _| projection.allows(tag)),
+        CotDocument::MapItem(d) => d.r.retain(|tag, _| projection.allows(tag)),
+        CotDocument::Unknown(u) => {
+            if let Some(r) = u.raw.get_mut("r").and_then(Value::as_object_mut) {
+                r.retain(|tag, _| projection.allows(tag));
+            }
+        }
+    }
+}
+
+/// Prunes a flattened document's `r_*` keys down to `projection`'s
+/// allow-list in place, equivalent to [`prune_document`] but for the output
+/// of `cot_to_flattened_document`/`CotDocument::to_flattened_json`.
+///
+/// A flattened key's detail root is everything up to its first path
+/// separator after the `r_` prefix (`r_contact_endpoint` -> `contact`),
+/// tokenized the same way
+/// [`r_field_flattening`](crate::ditto::r_field_flattening) reconstructs a
+/// flattened key's path, so a dunder-prefixed tag like `r___group_name` is
+/// still recognized as rooted at `__group` rather than split mid-tag. Keys
+/// outside the `r_*` namespace are always kept.
+pub fn prune_flattened_document(document: &mut Value, projection: &DetailProjection) {
+    let Some(map) = document.as_object_mut() else {
+        return;
+    };
+    map.retain(|key, _| {
+        let Some(suffix) = key.strip_prefix("r_") else {
+            return true;
+        };
+        let Some(root) = tokenize_path(suffix).into_iter().next() else {
+            return true;
+        };
+        projection.allows(&root)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::to_ditto::cot_to_flattened_document;
+    use serde_json::json;
+
+    fn sample_event() -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "test-uid".to_string(),
+            event_type: "a-f-G-U-C".to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::default(),
+            detail: r#"<detail>
+                <contact callsign="ALPHA-1" endpoint="192.168.1.1:4242:tcp"/>
+                <track course="45.0" speed="1.5"/>
+            </detail>"#
+                .to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn allows_only_the_allow_listed_tags() {
+        let projection = DetailProjection::new(["contact"]);
+        assert!(projection.allows("contact"));
+        assert!(!projection.allows("track"));
+    }
+
+    #[test]
+    fn prune_document_keeps_only_allow_listed_detail_roots() {
+        let event = sample_event();
+        let projection = DetailProjection::new(["contact"]);
+        let doc = cot_to_document_with(&event, "peer", &projection);
+
+        let CotDocument::MapItem(item) = &doc else {
+            panic!("expected a MapItem document");
+        };
+        assert!(item.r.contains_key("contact"));
+        assert!(!item.r.contains_key("track"));
+    }
+
+    #[test]
+    fn prune_document_keeps_top_level_attributes() {
+        let event = sample_event();
+        let mut doc = cot_to_document(&event, "peer");
+        prune_document(&mut doc, &DetailProjection::new(Vec::<String>::new()));
+
+        let CotDocument::MapItem(item) = &doc else {
+            panic!("expected a MapItem document");
+        };
+        assert_eq!(item.id, "test-uid");
+        assert!(item.r.is_empty());
+    }
+
+    #[test]
+    fn prune_document_is_idempotent() {
+        let event = sample_event();
+        let projection = DetailProjection::new(["contact"]);
+        let mut doc = cot_to_document(&event, "peer");
+
+        prune_document(&mut doc, &projection);
+        let once = doc.to_flattened_json();
+        prune_document(&mut doc, &projection);
+        let twice = doc.to_flattened_json();
+
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn prune_flattened_document_filters_r_star_keys_by_detail_root() {
+        let event = sample_event();
+        let mut flattened = cot_to_flattened_document(&event, "peer");
+        assert!(flattened.get("r_track_course").is_some());
+
+        prune_flattened_document(&mut flattened, &DetailProjection::new(["contact"]));
+
+        assert!(flattened.get("r_contact_callsign").is_some());
+        assert!(flattened.get("r_track_course").is_none());
+        assert!(flattened.get("r_track_speed").is_none());
+    }
+
+    #[test]
+    fn prune_flattened_document_keeps_non_r_fields() {
+        let mut doc = json!({ "_id": "test-uid", "w": "a-f-G-U-C", "r_contact_callsign": "ALPHA-1" });
+        prune_flattened_document(&mut doc, &DetailProjection::new(Vec::<String>::new()));
+
+        assert_eq!(doc["_id"], json!("test-uid"));
+        assert_eq!(doc["w"], json!("a-f-G-U-C"));
+        assert!(doc.get("r_contact_callsign").is_none());
+    }
+
+    #[test]
+    fn prune_flattened_document_respects_dunder_prefixed_roots() {
+        let mut doc = json!({ "r___group_name": "Blue" });
+        prune_flattened_document(&mut doc, &DetailProjection::new(["__group"]));
+        assert!(doc.get("r___group_name").is_some());
+
+        let mut doc2 = json!({ "r___group_name": "Blue" });
+        prune_flattened_document(&mut doc2, &DetailProjection::new(Vec::<String>::new()));
+        assert!(doc2.get("r___group_name").is_none());
+    }
+}
@@ -0,0 +1,152 @@
+//! Stale-event pruning for expired CoT documents.
+//!
+//! ATAK semantics require tracks to disappear from the common operational
+//! picture once their `stale` time has elapsed. This mirrors the prune pass
+//! DAV calendar stores run over expired components, but is CRDT-aware: a
+//! merged `stale` value is only trusted once it's definitively in the past,
+//! since two peers may disagree on it after a merge.
+
+use crate::ditto::time_range::OPEN_ENDED_STALE_THRESHOLD_MICROS;
+use crate::error::CotError;
+use dittolive_ditto::prelude::*;
+
+/// Options controlling a [`prune_stale`] pass.
+#[derive(Debug, Clone)]
+pub struct PruneOptions {
+    /// Extra time (microseconds) past `stale` a document is kept before it's
+    /// eligible for pruning, absorbing clock skew between peers.
+    pub grace_period_micros: f64,
+    /// If true, report the ids that would be pruned without issuing any
+    /// `DELETE`.
+    pub dry_run: bool,
+    /// Collections that are never pruned regardless of how stale their
+    /// documents are (e.g. `files`, which operators may want to keep around
+    /// after the track that shared them has expired).
+    pub never_prune: Vec<String>,
+}
+
+impl Default for PruneOptions {
+    fn default() -> Self {
+        Self {
+            grace_period_micros: 0.0,
+            dry_run: false,
+            never_prune: vec!["files".to_string()],
+        }
+    }
+}
+
+/// The result of a [`prune_stale`] pass over one collection.
+#[derive(Debug, Clone, Default)]
+pub struct PruneReport {
+    /// Collection the pass ran over.
+    pub collection: String,
+    /// Ids pruned (or, in dry-run mode, that would have been pruned).
+    pub pruned_ids: Vec<String>,
+    /// Whether this was a dry run (no `DELETE` was actually issued).
+    pub dry_run: bool,
+}
+
+/// Returns whether a document with the given `stale` (microseconds since the
+/// Unix epoch) is eligible for pruning at `now`, honoring `grace_period_micros`.
+///
+/// A missing or open-ended sentinel `stale` (see
+/// [`OPEN_ENDED_STALE_THRESHOLD_MICROS`]) is never eligible, matching the
+/// time-range filter's treatment of "never goes stale" tracks.
+pub fn is_expired(stale: Option<f64>, now: f64, grace_period_micros: f64) -> bool {
+    match stale {
+        None => false,
+        Some(s) if s <= 0.0 || s >= OPEN_ENDED_STALE_THRESHOLD_MICROS => false,
+        Some(s) => s + grace_period_micros < now,
+    }
+}
+
+/// Scans `collection` for documents whose `o` (stale) field has definitively
+/// elapsed as of `now`, and issues a DQL `DELETE` for each (or just reports
+/// them, in [`PruneOptions::dry_run`] mode).
+///
+/// Does nothing for collections in [`PruneOptions::never_prune`].
+pub async fn prune_stale(
+    ditto: &Ditto,
+    collection: &str,
+    now: f64,
+    opts: &PruneOptions,
+) -> Result<PruneReport, CotError> {
+    if opts.never_prune.iter().any(|c| c == collection) {
+        return Ok(PruneReport {
+            collection: collection.to_string(),
+            pruned_ids: Vec::new(),
+            dry_run: opts.dry_run,
+        });
+    }
+
+    let store = ditto.store();
+
+    // `o` is excluded here the same way the time-range filter excludes
+    // open-ended sentinels from an upper bound, so "never stale" tracks are
+    // never swept up by a scan.
+    let expiry_cutoff = now - opts.grace_period_micros;
+    let query = format!(
+        "SELECT _id FROM {collection} WHERE o > 0 AND o < {} AND o < {OPEN_ENDED_STALE_THRESHOLD_MICROS}",
+        expiry_cutoff
+    );
+    let result = store
+        .execute_v2(&query)
+        .await
+        .map_err(|e| CotError::InvalidFormat(e.to_string()))?;
+
+    let pruned_ids: Vec<String> = result
+        .iter()
+        .map(|item| {
+            item.get::<String>("_id")
+                .map_err(|e| CotError::InvalidFormat(e.to_string()))
+        })
+        .collect::<Result<Vec<_>, CotError>>()?;
+
+    if !opts.dry_run {
+        for id in &pruned_ids {
+            let delete_query = format!("DELETE FROM {collection} WHERE _id = '{id}'");
+            store
+                .execute_v2(&delete_query)
+                .await
+                .map_err(|e| CotError::InvalidFormat(e.to_string()))?;
+        }
+    }
+
+    Ok(PruneReport {
+        collection: collection.to_string(),
+        pruned_ids,
+        dry_run: opts.dry_run,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn past_stale_without_grace_is_expired() {
+        assert!(is_expired(Some(100.0), 200.0, 0.0));
+    }
+
+    #[test]
+    fn grace_period_delays_expiry() {
+        assert!(!is_expired(Some(100.0), 150.0, 100.0));
+        assert!(is_expired(Some(100.0), 250.0, 100.0));
+    }
+
+    #[test]
+    fn missing_or_open_ended_stale_never_expires() {
+        assert!(!is_expired(None, 1_000_000.0, 0.0));
+        assert!(!is_expired(Some(0.0), 1_000_000.0, 0.0));
+        assert!(!is_expired(
+            Some(OPEN_ENDED_STALE_THRESHOLD_MICROS),
+            f64::MAX / 2.0,
+            0.0
+        ));
+    }
+
+    #[test]
+    fn never_prune_list_defaults_to_files() {
+        assert!(PruneOptions::default().never_prune.contains(&"files".to_string()));
+    }
+}
@@ -0,0 +1,556 @@
+//! CalDAV-style time-range query builder for CoT documents.
+//!
+//! [`time_range`](super::time_range) already answers "does this document's
+//! `[n, o]` window overlap `[range_start, range_end]`" using inclusive,
+//! microsecond-epoch bounds for pushing a filter into `store.execute_v2`.
+//! This module exposes the same idea through a `DateTime<Utc>`-typed
+//! [`TimeRangeFilter`] using the *exclusive* overlap test from RFC 4791's
+//! `CALDAV:time-range` filter (`ev_start < q_end && ev_stale > q_start`), for
+//! callers working with parsed timestamps instead of raw CoT epoch-micros.
+//!
+//! [`filter_time_range`] and [`is_active_at`] offer that same exclusive test
+//! over *flattened* JSON documents (e.g. `store.execute_v2` rows, or the
+//! `Value` collections [`cot_query`](super::cot_query) filters) instead of
+//! parsed [`CotDocument`]s, reading the `n`/`o` start/stale fields directly.
+//!
+//! [`TypePrefixFilter`] adds the other half of a CoT query over parsed
+//! [`CotDocument`]s: a hierarchical match on the `w` (event-type) field,
+//! splitting both the pattern and `w` on `-` and comparing segment-by-segment
+//! with `*` as a wildcard, rather than [`cot_query`](super::cot_query)'s
+//! flattened-document [`FilterExpr::Prefix`](super::cot_query::FilterExpr::Prefix),
+//! which does a plain trailing-`*` string-prefix match.
+//!
+//! [`CotDocumentQuery`] combines both into a single calendar-`REPORT`-style
+//! request over an in-memory batch of [`CotDocument`]s — a time window, a
+//! type pattern, and an optional [`DetailProjection`] — the typed-document
+//! counterpart to [`cot_query::CotQuery`](super::cot_query::CotQuery), which
+//! answers the same shape of question over flattened documents instead.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use super::projection::{prune_document, DetailProjection};
+use super::time_range::{event_type, start_and_stale, OPEN_ENDED_STALE_THRESHOLD_MICROS};
+use super::CotDocument;
+
+fn to_micros(dt: DateTime<Utc>) -> f64 {
+    // `DateTime<Utc>` is already timezone-normalized by construction (a `Z`
+    // and an equivalent `+00:00` input both parse to the same instant), so
+    // there's no separate "normalize_datetime"-style string massaging to do
+    // here before comparing.
+    dt.timestamp_micros() as f64
+}
+
+/// A CalDAV-style `[start, end)` time-range filter over a [`CotDocument`]'s
+/// `n`/`o` (start/stale) window.
+///
+/// Either bound may be `None` for an open-ended query (`-∞`/`+∞`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeRangeFilter {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimeRangeFilter {
+    /// Creates a new filter from optional start/end bounds.
+    pub fn new(start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns whether `doc`'s validity window overlaps this filter.
+    ///
+    /// Following RFC 4791's `CALDAV:time-range` semantics: a document with a
+    /// real `stale` overlaps iff `ev_start < end && ev_stale > start`. A
+    /// document with no `stale` (or one at/beyond
+    /// [`OPEN_ENDED_STALE_THRESHOLD_MICROS`], matching
+    /// [`time_range`](super::time_range)'s "never goes stale" treatment) is
+    /// instead treated as an instantaneous point, matching iff
+    /// `start <= ev_start < end`.
+    pub fn matches(&self, doc: &CotDocument) -> bool {
+        let Some((ev_start, stale)) = start_and_stale(doc) else {
+            return false;
+        };
+        let query_start = self.start.map(to_micros);
+        let query_end = self.end.map(to_micros);
+        let is_instantaneous = match stale {
+            None => true,
+            Some(o) => o <= 0.0 || o >= OPEN_ENDED_STALE_THRESHOLD_MICROS,
+        };
+
+        let end_satisfied = query_end.map_or(true, |end| ev_start < end);
+        let start_satisfied = if is_instantaneous {
+            query_start.map_or(true, |start| start <= ev_start)
+        } else {
+            query_start.map_or(true, |start| stale.unwrap() > start)
+        };
+
+        end_satisfied && start_satisfied
+    }
+
+    /// Builds the DQL `WHERE` clause fragment equivalent to [`Self::matches`],
+    /// for pushing this filter into `store.execute_v2` instead of filtering
+    /// client-side after a full collection scan.
+    pub fn to_where_clause(&self) -> String {
+        let end_bound = match self.end {
+            Some(end) => format!("n < {}", to_micros(end)),
+            None => "TRUE".to_string(),
+        };
+        let start_bound = match self.start {
+            Some(start) => {
+                let start = to_micros(start);
+                format!(
+                    "(((o IS NULL OR o <= 0 OR o >= {OPEN_ENDED_STALE_THRESHOLD_MICROS}) AND n >= {start}) \
+                     OR (o IS NOT NULL AND o > 0 AND o < {OPEN_ENDED_STALE_THRESHOLD_MICROS} AND o > {start}))"
+                )
+            }
+            None => "TRUE".to_string(),
+        };
+
+        format!("{end_bound} AND {start_bound}")
+    }
+}
+
+/// A hierarchical CoT-type prefix filter over a [`CotDocument`]'s `w` field.
+///
+/// Unlike a plain string prefix, both the pattern and the candidate `w` are
+/// split on `-` and compared segment-by-segment, with `*` as a wildcard
+/// segment — so `a-f-G-*` matches `a-f-G-U-C` (and `a-f-*-U-C`, wildcarding a
+/// middle segment, matches it too), without also matching an unrelated type
+/// that merely shares the same leading characters (e.g. `a-f-Galaxy`, which a
+/// naive `starts_with("a-f-G")` would wrongly accept).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypePrefixFilter {
+    segments: Vec<String>,
+}
+
+impl TypePrefixFilter {
+    /// Builds a filter from a `-`-separated pattern such as `a-f-G-*`.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            segments: pattern.into().split('-').map(str::to_string).collect(),
+        }
+    }
+
+    /// Returns whether `doc`'s `w` field matches this pattern: it must have
+    /// at least as many `-`-separated segments as the pattern, and every
+    /// pattern segment must equal the corresponding `w` segment or be `*`.
+    pub fn matches(&self, doc: &CotDocument) -> bool {
+        let Some(w) = event_type(doc) else {
+            return false;
+        };
+        let w_segments: Vec<&str> = w.split('-').collect();
+        if self.segments.len() > w_segments.len() {
+            return false;
+        }
+        self.segments
+            .iter()
+            .zip(w_segments)
+            .all(|(pattern, actual)| pattern == "*" || pattern == actual)
+    }
+}
+
+/// A calendar-`REPORT`-style query over an in-memory batch of
+/// [`CotDocument`]s: an optional [`TimeRangeFilter`], an optional
+/// [`TypePrefixFilter`], and an optional [`DetailProjection`] applied to
+/// every match. Leaving a field unset matches everything for that criterion,
+/// so a default-constructed query matches (and returns unprojected) every
+/// document it's run over.
+#[derive(Debug, Clone, Default)]
+pub struct CotDocumentQuery {
+    time_range: Option<TimeRangeFilter>,
+    type_filter: Option<TypePrefixFilter>,
+    projection: Option<DetailProjection>,
+}
+
+impl CotDocumentQuery {
+    /// Creates a query that matches every document, unprojected.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts matches to documents whose validity window overlaps `filter`.
+    pub fn time_range(mut self, filter: TimeRangeFilter) -> Self {
+        self.time_range = Some(filter);
+        self
+    }
+
+    /// Restricts matches to documents whose `w` field matches the `-`-delimited
+    /// glob `pattern` (e.g. `a-f-G-*`), per [`TypePrefixFilter`].
+    pub fn type_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.type_filter = Some(TypePrefixFilter::new(pattern));
+        self
+    }
+
+    /// Prunes each match's detail down to `projection`'s allow-list before
+    /// returning it from [`Self::run`].
+    pub fn project(mut self, projection: DetailProjection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Returns whether `doc` satisfies every filter set on this query.
+    pub fn matches(&self, doc: &CotDocument) -> bool {
+        self.time_range.as_ref().map_or(true, |f| f.matches(doc))
+            && self.type_filter.as_ref().map_or(true, |f| f.matches(doc))
+    }
+
+    /// Runs this query over `documents`, returning an owned, projected clone
+    /// of every match in its original relative order.
+    pub fn run<'a>(
+        &self,
+        documents: impl IntoIterator<Item = &'a CotDocument>,
+    ) -> Vec<CotDocument> {
+        documents
+            .into_iter()
+            .filter(|doc| self.matches(doc))
+            .cloned()
+            .map(|mut doc| {
+                if let Some(projection) = &self.projection {
+                    prune_document(&mut doc, projection);
+                }
+                doc
+            })
+            .collect()
+    }
+}
+
+/// Extracts the `(n, o)` start/stale microsecond pair from a flattened JSON
+/// document, mirroring [`start_and_stale`]'s typed-`CotDocument` version.
+fn start_and_stale_value(doc: &Value) -> Option<(f64, Option<f64>)> {
+    let start = doc.get("n")?.as_f64()?;
+    let stale = doc.get("o").and_then(Value::as_f64);
+    Some((start, stale))
+}
+
+/// Filters `documents` down to those whose `[n, o)` validity window overlaps
+/// the half-open `[window_start_us, window_end_us)`, using the same
+/// exclusive CalDAV-style test as [`TimeRangeFilter::matches`]
+/// (`start < window_end && stale > window_start`). A missing/zero/sentinel
+/// `o` is treated as "never stale", matching [`time_range`](super::time_range).
+///
+/// A malformed interval (`window_start_us >= window_end_us`) matches nothing
+/// rather than panicking, since an empty or inverted window has no documents
+/// that can overlap it.
+pub fn filter_time_range(
+    documents: &[Value],
+    window_start_us: u64,
+    window_end_us: u64,
+) -> Vec<&Value> {
+    if window_start_us >= window_end_us {
+        return Vec::new();
+    }
+    let (window_start, window_end) = (window_start_us as f64, window_end_us as f64);
+
+    documents
+        .iter()
+        .filter(|doc| match start_and_stale_value(doc) {
+            Some((start, stale)) => {
+                let never_stale = match stale {
+                    None => true,
+                    Some(o) => o <= 0.0 || o >= OPEN_ENDED_STALE_THRESHOLD_MICROS,
+                };
+                start < window_end && (never_stale || stale.unwrap() > window_start)
+            }
+            None => false,
+        })
+        .collect()
+}
+
+/// Returns whether `doc`'s `[n, o)` validity window covers `instant_us`
+/// (microseconds since the Unix epoch), the single-instant convenience for
+/// [`filter_time_range`].
+pub fn is_active_at(doc: &Value, instant_us: u64) -> bool {
+    let Some((start, stale)) = start_and_stale_value(doc) else {
+        return false;
+    };
+    let instant = instant_us as f64;
+    let never_stale = match stale {
+        None => true,
+        Some(o) => o <= 0.0 || o >= OPEN_ENDED_STALE_THRESHOLD_MICROS,
+    };
+    start <= instant && (never_stale || stale.unwrap() > instant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::common_fields::CommonDocumentFields;
+    use crate::ditto::to_ditto::cot_to_document;
+    use crate::ditto::UnknownDocument;
+
+    // Bounds are built from small, near-epoch offsets (rather than real
+    // calendar dates) so they land well under `OPEN_ENDED_STALE_THRESHOLD_MICROS`
+    // and exercise the "genuinely bounded" branch of `matches`/`to_where_clause`
+    // instead of always falling into the "stale sentinel" open-ended case that
+    // any present-day timestamp's epoch-micros would trip.
+    fn at(micros: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::microseconds(micros)
+    }
+
+    fn doc_with(n: f64, o: Option<f64>) -> CotDocument {
+        CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({ "_id": "test", "n": n, "o": o }),
+        })
+    }
+
+    #[test]
+    fn overlapping_bounded_window_matches() {
+        let doc = doc_with(0.0, Some(100.0));
+        assert!(TimeRangeFilter::new(Some(at(50)), Some(at(200))).matches(&doc));
+    }
+
+    #[test]
+    fn disjoint_bounded_window_does_not_match() {
+        let doc = doc_with(0.0, Some(100.0));
+        assert!(!TimeRangeFilter::new(Some(at(200)), Some(at(300))).matches(&doc));
+    }
+
+    #[test]
+    fn exclusive_end_boundary_does_not_match() {
+        // ev_start == query end is excluded by `ev_start < end`.
+        let doc = doc_with(100.0, Some(200.0));
+        assert!(!TimeRangeFilter::new(Some(at(0)), Some(at(100))).matches(&doc));
+    }
+
+    #[test]
+    fn exclusive_start_boundary_does_not_match() {
+        // ev_stale == query start is excluded by `ev_stale > start`.
+        let doc = doc_with(0.0, Some(100.0));
+        assert!(!TimeRangeFilter::new(Some(at(100)), Some(at(200))).matches(&doc));
+    }
+
+    #[test]
+    fn missing_stale_is_treated_as_instantaneous_point() {
+        let doc = doc_with(100.0, None);
+        assert!(TimeRangeFilter::new(Some(at(100)), Some(at(200))).matches(&doc));
+        assert!(!TimeRangeFilter::new(Some(at(0)), Some(at(100))).matches(&doc));
+    }
+
+    #[test]
+    fn sentinel_stale_is_treated_as_instantaneous_point() {
+        let doc = doc_with(100.0, Some(OPEN_ENDED_STALE_THRESHOLD_MICROS));
+        assert!(TimeRangeFilter::new(Some(at(100)), Some(at(200))).matches(&doc));
+        assert!(!TimeRangeFilter::new(Some(at(0)), Some(at(100))).matches(&doc));
+    }
+
+    #[test]
+    fn open_ended_query_bounds_match_anything() {
+        let doc = doc_with(0.0, Some(100.0));
+        assert!(TimeRangeFilter::new(None, None).matches(&doc));
+    }
+
+    #[test]
+    fn realistic_document_conversion_is_still_matched() {
+        // Smoke test through the real `CotEvent` -> `CotDocument` pipeline,
+        // with a query window wide enough to match regardless of how the
+        // (real, present-day) `stale` timestamp is classified.
+        let time = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let event = CotEvent {
+            version: "2.0".to_string(),
+            uid: "test-uid".to_string(),
+            event_type: "a-u-generic".to_string(),
+            time,
+            start: time,
+            stale: time + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::default(),
+            detail: String::new(),
+            tz_offset_secs: None,
+        };
+        let doc = cot_to_document(&event, "peer");
+        assert!(TimeRangeFilter::new(None, Some(time + chrono::Duration::days(1))).matches(&doc));
+        assert!(!TimeRangeFilter::new(None, Some(time - chrono::Duration::days(1))).matches(&doc));
+    }
+
+    #[test]
+    fn where_clause_contains_both_bounds() {
+        let clause = TimeRangeFilter::new(Some(at(0)), Some(at(100))).to_where_clause();
+        assert!(clause.contains("n < "));
+        assert!(clause.contains("n >= "));
+        assert!(clause.contains("o > "));
+    }
+
+    #[test]
+    fn where_clause_omits_missing_bounds() {
+        let clause = TimeRangeFilter::new(None, None).to_where_clause();
+        assert_eq!(clause, "TRUE AND TRUE");
+    }
+
+    fn value_doc(n: f64, o: Option<f64>) -> Value {
+        serde_json::json!({ "_id": "test", "n": n, "o": o })
+    }
+
+    #[test]
+    fn filter_time_range_matches_an_overlapping_flattened_document() {
+        let docs = vec![value_doc(0.0, Some(100.0))];
+        assert_eq!(filter_time_range(&docs, 50, 200).len(), 1);
+    }
+
+    #[test]
+    fn filter_time_range_excludes_a_disjoint_flattened_document() {
+        let docs = vec![value_doc(0.0, Some(100.0))];
+        assert_eq!(filter_time_range(&docs, 200, 300).len(), 0);
+    }
+
+    #[test]
+    fn filter_time_range_treats_missing_stale_as_never_stale() {
+        let docs = vec![value_doc(0.0, None)];
+        assert_eq!(filter_time_range(&docs, 500, 600).len(), 1);
+    }
+
+    #[test]
+    fn filter_time_range_rejects_an_inverted_window() {
+        let docs = vec![value_doc(0.0, Some(100.0))];
+        assert!(filter_time_range(&docs, 200, 100).is_empty());
+    }
+
+    #[test]
+    fn is_active_at_matches_an_instant_within_the_window() {
+        let doc = value_doc(100.0, Some(200.0));
+        assert!(is_active_at(&doc, 150));
+        assert!(!is_active_at(&doc, 250));
+    }
+
+    #[test]
+    fn is_active_at_treats_missing_stale_as_never_stale() {
+        let doc = value_doc(100.0, None);
+        assert!(is_active_at(&doc, 10_000));
+        assert!(!is_active_at(&doc, 50));
+    }
+
+    fn doc_with_type(w: &str) -> CotDocument {
+        CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({ "_id": "test", "w": w }),
+        })
+    }
+
+    #[test]
+    fn type_prefix_filter_matches_a_trailing_wildcard_segment() {
+        let filter = TypePrefixFilter::new("a-f-G-*");
+        assert!(filter.matches(&doc_with_type("a-f-G-U-C")));
+        assert!(!filter.matches(&doc_with_type("a-f-A-U-C")));
+    }
+
+    #[test]
+    fn type_prefix_filter_matches_a_wildcard_in_a_middle_segment() {
+        let filter = TypePrefixFilter::new("a-f-*-U-C");
+        assert!(filter.matches(&doc_with_type("a-f-G-U-C")));
+        assert!(filter.matches(&doc_with_type("a-f-A-U-C")));
+        assert!(!filter.matches(&doc_with_type("a-f-G-U-X")));
+    }
+
+    #[test]
+    fn type_prefix_filter_rejects_a_shorter_type_than_the_pattern() {
+        let filter = TypePrefixFilter::new("a-f-G-U-C");
+        assert!(!filter.matches(&doc_with_type("a-f-G")));
+    }
+
+    #[test]
+    fn type_prefix_filter_does_not_match_on_shared_leading_characters_alone() {
+        // A naive `starts_with` on the raw string would wrongly accept this;
+        // segment-by-segment comparison requires `Galaxy` to equal `G`.
+        let filter = TypePrefixFilter::new("a-f-G");
+        assert!(!filter.matches(&doc_with_type("a-f-Galaxy")));
+    }
+
+    #[test]
+    fn type_prefix_filter_treats_missing_w_as_no_match() {
+        let doc = CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({ "_id": "test" }),
+        });
+        assert!(!TypePrefixFilter::new("a-f-*").matches(&doc));
+    }
+
+    fn friendly_unit(uid: &str, time: DateTime<Utc>) -> CotDocument {
+        cot_to_document(
+            &CotEvent {
+                version: "2.0".to_string(),
+                uid: uid.to_string(),
+                event_type: "a-f-G-U-C".to_string(),
+                time,
+                start: time,
+                stale: time + chrono::Duration::minutes(5),
+                how: "h-g-i-g-o".to_string(),
+                point: crate::cot_events::Point::default(),
+                detail: r#"<detail><contact callsign="ALPHA-1"/><track speed="3.5"/></detail>"#
+                    .to_string(),
+                tz_offset_secs: None,
+            },
+            "peer",
+        )
+    }
+
+    #[test]
+    fn document_query_combines_time_range_and_type_pattern() {
+        let time = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let friendly = friendly_unit("unit-1", time);
+        let hostile = {
+            let mut doc = friendly_unit("unit-2", time);
+            if let CotDocument::MapItem(d) = &mut doc {
+                d.w = "a-h-G-U-C".to_string();
+            }
+            doc
+        };
+        let docs = vec![friendly, hostile];
+
+        let query = CotDocumentQuery::new()
+            .time_range(TimeRangeFilter::new(
+                None,
+                Some(time + chrono::Duration::days(1)),
+            ))
+            .type_pattern("a-f-*");
+        let matches = query.run(&docs);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].common_id(), Some("unit-1"));
+    }
+
+    #[test]
+    fn document_query_excludes_documents_outside_the_time_range() {
+        let time = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let docs = vec![friendly_unit("unit-1", time)];
+
+        let query = CotDocumentQuery::new().time_range(TimeRangeFilter::new(
+            None,
+            Some(time - chrono::Duration::days(1)),
+        ));
+        assert!(query.run(&docs).is_empty());
+    }
+
+    #[test]
+    fn document_query_projects_matches_down_to_the_allow_list() {
+        let time = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let docs = vec![friendly_unit("unit-1", time)];
+
+        let query = CotDocumentQuery::new().project(DetailProjection::new(["contact"]));
+        let matches = query.run(&docs);
+
+        match &matches[0] {
+            CotDocument::MapItem(d) => {
+                assert!(d.r.contains_key("contact"));
+                assert!(!d.r.contains_key("track"));
+            }
+            other => panic!("expected MapItem document, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn document_query_with_no_filters_matches_everything_unprojected() {
+        let time = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let docs = vec![friendly_unit("unit-1", time), friendly_unit("unit-2", time)];
+
+        let matches = CotDocumentQuery::new().run(&docs);
+        assert_eq!(matches.len(), 2);
+    }
+}
@@ -2,13 +2,182 @@
 //!
 //! This module provides functionality to flatten and unflatten the 'r' field
 //! (CoT detail elements) for DQL compatibility. The flattening converts nested
-//! structures like r.takv.os to individual r_takv_os fields.
+//! structures like r.takv.os to individual r_takv_os fields, recursing through
+//! arbitrarily nested detail (e.g. r.detail.remarks.text -> r_detail_remarks_text)
+//! and encoding repeated sibling elements (several `<link>`s) as an ordinal
+//! path segment (r.link[0].uid -> r_link_0_uid), matching how
+//! [`detail_parser`](crate::detail_parser) already preserves repeated
+//! siblings as a `Value::Array` when parsing raw XML.
+//!
+//! `_` is both the path separator and a character a real detail key can
+//! contain (`takv_os_version`, `__group`), so a key can't be joined into the
+//! flattened path as-is: `r.takv.os_version` and `r.takv.os.version` would
+//! both produce `r_takv_os_version`, and unflattening couldn't tell them
+//! apart. [`escape_segment`] percent-encodes every literal `_` (as `%5F`,
+//! with literal `%` itself escaped to `%25` first so the encoding is its own
+//! inverse) before a key is joined into the path, so the only `_` characters
+//! ever present in a flattened key are real path separators.
+//! [`tokenize_path`] splits purely on those, then [`unescape_segment`]
+//! recovers each segment's original text — a percent-encoded key round-trips
+//! exactly regardless of depth or how many underscores it contains.
 
 use serde_json::Value;
 use std::collections::HashMap;
 
-/// Flatten the r field into individual r_* fields for DQL compatibility
-/// Converts r.takv.os -> r_takv_os, r.contact.endpoint -> r_contact_endpoint, etc.
+/// Percent-encodes `segment` so it can be joined into a flattened path with
+/// `_` as an unambiguous separator: literal `%` becomes `%25` (escaped
+/// first, so it can't collide with the escape this introduces for `_`), and
+/// literal `_` becomes `%5F`.
+///
+/// `pub(crate)` so [`detail_tree`](crate::detail_tree)'s ordinal-path
+/// flattening can reuse the same escaping instead of duplicating it.
+pub(crate) fn escape_segment(segment: &str) -> String {
+    segment.replace('%', "%25").replace('_', "%5F")
+}
+
+/// Inverse of [`escape_segment`]: decodes `%5F` back to `_` and `%25` back
+/// to `%`, scanning left to right so a percent sign that isn't the start of
+/// one of those two escapes is left untouched.
+pub(crate) fn unescape_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    let mut chars = segment.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            let lookahead: String = chars.clone().take(2).collect();
+            if lookahead == "5F" {
+                out.push('_');
+                chars.next();
+                chars.next();
+                continue;
+            } else if lookahead == "25" {
+                out.push('%');
+                chars.next();
+                chars.next();
+                continue;
+            }
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+/// Recovers the one artifact the pre-[`escape_segment`] flattening scheme
+/// left behind in existing Ditto stores: a key built by plain `_`-joining
+/// (no escaping at all) turns every literal underscore in a segment name
+/// into an extra path separator, so a dunder-prefixed TAK element like
+/// `__group` split on `_` yields a leading run of empty tokens (`["", "",
+/// "group", ...]`) instead of one `"__group"` token. [`escape_segment`]
+/// never produces an empty token this way (a literal `_` always becomes
+/// `%5F`, which contains no raw underscore), so any empty token here is
+/// unambiguously a legacy artifact; collapsing a run of `n` empty tokens
+/// onto the token that follows it, prefixed with `n` underscores, recovers
+/// the original segment exactly.
+fn collapse_legacy_empty_tokens(tokens: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut pending_underscores = 0usize;
+
+    for token in tokens {
+        if token.is_empty() {
+            pending_underscores += 1;
+            continue;
+        }
+        if pending_underscores > 0 {
+            out.push(format!("{}{token}", "_".repeat(pending_underscores)));
+            pending_underscores = 0;
+        } else {
+            out.push(token);
+        }
+    }
+
+    out
+}
+
+/// Splits a flattened key's suffix (after the `r_` prefix) into path
+/// segments, unescaping each one back to the original (possibly
+/// underscore-containing) key or array index [`escape_segment`] encoded.
+///
+/// Also reads keys written by the pre-[`escape_segment`] scheme still
+/// present in existing Ditto stores, via [`collapse_legacy_empty_tokens`] —
+/// those never contain an empty token once escaped, so recovering one costs
+/// nothing for documents already in the current format.
+pub(crate) fn tokenize_path(suffix: &str) -> Vec<String> {
+    if suffix.is_empty() {
+        return Vec::new();
+    }
+    let raw_tokens = suffix.split('_').map(str::to_string).collect();
+    collapse_legacy_empty_tokens(raw_tokens)
+        .into_iter()
+        .map(|token| unescape_segment(&token))
+        .collect()
+}
+
+/// Recursively flattens `value` under `prefix`, descending into objects
+/// (`prefix_key`, with `key` escaped) and arrays (`prefix_index`) and
+/// inserting scalars as leaves.
+fn flatten_value_into(prefix: &str, value: Value, out: &mut HashMap<String, Value>) {
+    match value {
+        Value::Object(obj) => {
+            for (key, child) in obj {
+                let segment = escape_segment(&key);
+                flatten_value_into(&format!("{prefix}_{segment}"), child, out);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.into_iter().enumerate() {
+                flatten_value_into(&format!("{prefix}_{index}"), child, out);
+            }
+        }
+        scalar => {
+            out.insert(prefix.to_string(), scalar);
+        }
+    }
+}
+
+/// Inserts `value` at the path described by `tokens` underneath `container`,
+/// turning `container` into a `Value::Array` when the next token is numeric
+/// or a `Value::Object` otherwise, recursing until the last token is reached.
+fn insert_path(container: &mut Value, tokens: &[String], value: Value) {
+    let Some((head, rest)) = tokens.split_first() else {
+        return;
+    };
+
+    if rest.is_empty() {
+        if !container.is_object() {
+            *container = Value::Object(serde_json::Map::new());
+        }
+        if let Value::Object(map) = container {
+            map.insert(head.clone(), value);
+        }
+        return;
+    }
+
+    if let Ok(index) = head.parse::<usize>() {
+        if !container.is_array() {
+            *container = Value::Array(Vec::new());
+        }
+        if let Value::Array(items) = container {
+            while items.len() <= index {
+                items.push(Value::Null);
+            }
+            insert_path(&mut items[index], rest, value);
+        }
+    } else {
+        if !container.is_object() {
+            *container = Value::Object(serde_json::Map::new());
+        }
+        if let Value::Object(map) = container {
+            let entry = map.entry(head.clone()).or_insert(Value::Null);
+            insert_path(entry, rest, value);
+        }
+    }
+}
+
+/// Flatten the r field into individual r_* fields for DQL compatibility.
+/// Converts r.takv.os -> r_takv_os, r.contact.endpoint -> r_contact_endpoint,
+/// nested trees like r.detail.remarks.text -> r_detail_remarks_text, and
+/// repeated elements like r.link (an array) -> r_link_0_uid, r_link_1_uid, etc.
 pub fn flatten_r_field(
     r_map: &HashMap<String, impl Into<Value> + Clone>,
 ) -> HashMap<String, Value> {
@@ -16,55 +185,33 @@ pub fn flatten_r_field(
 
     for (detail_type, detail_value) in r_map {
         let value_json: Value = detail_value.clone().into();
-
-        if let Value::Object(obj) = value_json {
-            // Flatten nested objects
-            for (attribute, attr_value) in obj {
-                let flattened_key = format!("r_{}_{}", detail_type, attribute);
-                flattened.insert(flattened_key, attr_value);
-            }
-        } else {
-            // Simple value
-            let flattened_key = format!("r_{}", detail_type);
-            flattened.insert(flattened_key, value_json);
-        }
+        flatten_value_into(&format!("r_{detail_type}"), value_json, &mut flattened);
     }
 
     flattened
 }
 
-/// Reconstruct the r field from flattened r_* fields
-/// Converts r_takv_os -> r.takv.os, r_contact_endpoint -> r.contact.endpoint, etc.
+/// Reconstruct the r field from flattened r_* fields.
+/// Converts r_takv_os -> r.takv.os, r_contact_endpoint -> r.contact.endpoint,
+/// r_detail_remarks_text -> r.detail.remarks.text, and r_link_0_uid /
+/// r_link_1_uid -> r.link as a two-element array, preserving element order.
 pub fn unflatten_r_field(flattened_map: &HashMap<String, Value>) -> HashMap<String, Value> {
     let mut r_map: HashMap<String, Value> = HashMap::new();
 
     for (key, value) in flattened_map {
-        if let Some(without_r_prefix) = key.strip_prefix("r_") {
-            // Handle the special case where detail_type starts with underscores (like __group)
-            // Find the last underscore to properly split detail_type from attribute
-            if let Some(last_underscore) = without_r_prefix.rfind('_') {
-                let detail_type = &without_r_prefix[..last_underscore];
-                let attribute = &without_r_prefix[last_underscore + 1..];
-
-                // Only treat as nested if we found a meaningful split
-                // (i.e., both parts are non-empty)
-                if !detail_type.is_empty() && !attribute.is_empty() {
-                    // Nested r_detailType_attribute case
-                    let detail_obj = r_map
-                        .entry(detail_type.to_string())
-                        .or_insert_with(|| Value::Object(serde_json::Map::new()));
-
-                    if let Value::Object(obj) = detail_obj {
-                        obj.insert(attribute.to_string(), value.clone());
-                    }
-                } else {
-                    // Simple r_field case (no meaningful split found)
-                    r_map.insert(without_r_prefix.to_string(), value.clone());
-                }
-            } else {
-                // Simple r_field case (no underscore found)
-                r_map.insert(without_r_prefix.to_string(), value.clone());
-            }
+        let Some(without_r_prefix) = key.strip_prefix("r_") else {
+            continue;
+        };
+        let tokens = tokenize_path(without_r_prefix);
+        let Some((head, rest)) = tokens.split_first() else {
+            continue;
+        };
+
+        if rest.is_empty() {
+            r_map.insert(head.clone(), value.clone());
+        } else {
+            let entry = r_map.entry(head.clone()).or_insert(Value::Null);
+            insert_path(entry, rest, value.clone());
         }
     }
 
@@ -81,21 +228,9 @@ pub fn flatten_document_r_field<T>(
     // Remove the original 'r' field
     document_map.remove("r");
 
-    // Add flattened r_* fields
     for (detail_type, detail_value) in r_field {
         let value_json: Value = detail_value.clone().into();
-
-        if let Value::Object(obj) = value_json {
-            // Flatten nested objects
-            for (attribute, attr_value) in obj {
-                let flattened_key = format!("r_{}_{}", detail_type, attribute);
-                document_map.insert(flattened_key, attr_value);
-            }
-        } else {
-            // Simple value
-            let flattened_key = format!("r_{}", detail_type);
-            document_map.insert(flattened_key, value_json);
-        }
+        flatten_value_into(&format!("r_{detail_type}"), value_json, document_map);
     }
 }
 
@@ -107,35 +242,22 @@ pub fn unflatten_document_r_field(
     let mut keys_to_remove = Vec::new();
 
     for (key, value) in document_map.iter() {
-        if let Some(without_r_prefix) = key.strip_prefix("r_") {
-            // Handle the special case where detail_type starts with underscores (like __group)
-            // Find the last underscore to properly split detail_type from attribute
-            if let Some(last_underscore) = without_r_prefix.rfind('_') {
-                let detail_type = &without_r_prefix[..last_underscore];
-                let attribute = &without_r_prefix[last_underscore + 1..];
-
-                // Only treat as nested if we found a meaningful split
-                // (i.e., both parts are non-empty)
-                if !detail_type.is_empty() && !attribute.is_empty() {
-                    // Nested r_detailType_attribute case
-                    let detail_obj = r_map
-                        .entry(detail_type.to_string())
-                        .or_insert_with(|| Value::Object(serde_json::Map::new()));
-
-                    if let Value::Object(obj) = detail_obj {
-                        obj.insert(attribute.to_string(), value.clone());
-                    }
-                } else {
-                    // Simple r_field case (no meaningful split found)
-                    r_map.insert(without_r_prefix.to_string(), value.clone());
-                }
-            } else {
-                // Simple r_field case (no underscore found)
-                r_map.insert(without_r_prefix.to_string(), value.clone());
-            }
-
-            keys_to_remove.push(key.clone());
+        let Some(without_r_prefix) = key.strip_prefix("r_") else {
+            continue;
+        };
+        let tokens = tokenize_path(without_r_prefix);
+        let Some((head, rest)) = tokens.split_first() else {
+            continue;
+        };
+
+        if rest.is_empty() {
+            r_map.insert(head.clone(), value.clone());
+        } else {
+            let entry = r_map.entry(head.clone()).or_insert(Value::Null);
+            insert_path(entry, rest, value.clone());
         }
+
+        keys_to_remove.push(key.clone());
     }
 
     // Remove the r_* fields from the main map
@@ -275,4 +397,235 @@ mod tests {
             }))
         );
     }
+
+    #[test]
+    fn test_flatten_repeated_elements_as_ordinal_keys() {
+        let mut r_map = HashMap::new();
+        r_map.insert(
+            "link".to_string(),
+            json!([
+                { "uid": "PARENT-1", "type": "a-f-G" },
+                { "uid": "PARENT-2", "type": "a-f-G" }
+            ]),
+        );
+
+        let flattened = flatten_r_field(&r_map);
+
+        assert_eq!(flattened.get("r_link_0_uid"), Some(&json!("PARENT-1")));
+        assert_eq!(flattened.get("r_link_1_uid"), Some(&json!("PARENT-2")));
+        assert_eq!(flattened.get("r_link_0_type"), Some(&json!("a-f-G")));
+        assert_eq!(flattened.get("r_link_1_type"), Some(&json!("a-f-G")));
+    }
+
+    #[test]
+    fn test_flatten_deeply_nested_elements() {
+        let mut r_map = HashMap::new();
+        r_map.insert(
+            "detail".to_string(),
+            json!({ "remarks": { "text": "hello", "source": "BAO" } }),
+        );
+
+        let flattened = flatten_r_field(&r_map);
+
+        assert_eq!(flattened.get("r_detail_remarks_text"), Some(&json!("hello")));
+        assert_eq!(flattened.get("r_detail_remarks_source"), Some(&json!("BAO")));
+    }
+
+    #[test]
+    fn test_unflatten_reconstructs_ordinal_array_in_order() {
+        let mut flattened = HashMap::new();
+        flattened.insert("r_link_0_uid".to_string(), json!("PARENT-1"));
+        flattened.insert("r_link_1_uid".to_string(), json!("PARENT-2"));
+
+        let r_map = unflatten_r_field(&flattened);
+
+        assert_eq!(
+            r_map.get("link"),
+            Some(&json!([{ "uid": "PARENT-1" }, { "uid": "PARENT-2" }]))
+        );
+    }
+
+    #[test]
+    fn test_unflatten_reconstructs_deep_nesting() {
+        let mut flattened = HashMap::new();
+        flattened.insert("r_detail_remarks_text".to_string(), json!("hello"));
+
+        let r_map = unflatten_r_field(&flattened);
+
+        assert_eq!(
+            r_map.get("detail"),
+            Some(&json!({ "remarks": { "text": "hello" } }))
+        );
+    }
+
+    #[test]
+    fn test_round_trip_repeated_elements() {
+        let mut original_r_map = HashMap::new();
+        original_r_map.insert(
+            "link".to_string(),
+            json!([
+                { "uid": "PARENT-1" },
+                { "uid": "PARENT-2" },
+                { "uid": "PARENT-3" }
+            ]),
+        );
+
+        let flattened = flatten_r_field(&original_r_map);
+        let reconstructed = unflatten_r_field(&flattened);
+
+        let Some(Value::Array(items)) = reconstructed.get("link") else {
+            panic!("expected link to reconstruct as an array");
+        };
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0]["uid"], json!("PARENT-1"));
+        assert_eq!(items[2]["uid"], json!("PARENT-3"));
+    }
+
+    #[test]
+    fn test_dunder_prefixed_tag_names_survive_tokenization() {
+        let mut r_map = HashMap::new();
+        r_map.insert("__group".to_string(), json!({ "name": "Blue", "role": "Team Member" }));
+
+        let flattened = flatten_r_field(&r_map);
+        assert_eq!(
+            flattened.get("r_%5F%5Fgroup_name"),
+            Some(&json!("Blue"))
+        );
+
+        let reconstructed = unflatten_r_field(&flattened);
+        assert_eq!(
+            reconstructed.get("__group"),
+            Some(&json!({ "name": "Blue", "role": "Team Member" }))
+        );
+    }
+
+    #[test]
+    fn test_embedded_underscore_key_no_longer_collides_with_nesting() {
+        // Before escaping, "takv.os_version" and "takv.os.version" both
+        // flattened to "r_takv_os_version" and were indistinguishable.
+        let mut r_map = HashMap::new();
+        r_map.insert(
+            "takv".to_string(),
+            json!({ "os": "35", "os_version": "14" }),
+        );
+
+        let flattened = flatten_r_field(&r_map);
+        assert_eq!(flattened.get("r_takv_os"), Some(&json!("35")));
+        assert_eq!(
+            flattened.get("r_takv_os%5Fversion"),
+            Some(&json!("14"))
+        );
+
+        let reconstructed = unflatten_r_field(&flattened);
+        assert_eq!(
+            reconstructed.get("takv"),
+            Some(&json!({ "os": "35", "os_version": "14" }))
+        );
+    }
+
+    #[test]
+    fn test_deeply_nested_dunder_prefixed_path_round_trips() {
+        let mut r_map = HashMap::new();
+        r_map.insert(
+            "__group".to_string(),
+            json!({ "name": { "role": "Team Member" } }),
+        );
+
+        let flattened = flatten_r_field(&r_map);
+        let reconstructed = unflatten_r_field(&flattened);
+
+        assert_eq!(
+            reconstructed.get("__group"),
+            Some(&json!({ "name": { "role": "Team Member" } }))
+        );
+    }
+
+    #[test]
+    fn test_unflatten_reads_a_legacy_unescaped_dunder_key() {
+        // Pre-escaping documents joined "__group" + "name" with plain "_",
+        // producing "r___group_name" rather than today's
+        // "r_%5F%5Fgroup_name" — this is what's actually sitting in an
+        // existing Ditto store.
+        let mut flattened = HashMap::new();
+        flattened.insert("r___group_name".to_string(), json!("Blue"));
+        flattened.insert("r___group_role".to_string(), json!("Team Member"));
+
+        let r_map = unflatten_r_field(&flattened);
+
+        assert_eq!(
+            r_map.get("__group"),
+            Some(&json!({ "name": "Blue", "role": "Team Member" }))
+        );
+    }
+
+    #[test]
+    fn test_unflatten_reads_a_legacy_key_with_no_dunder_segments() {
+        // Legacy keys with no embedded underscores are identical under both
+        // schemes, so they should keep reading the same as always.
+        let mut flattened = HashMap::new();
+        flattened.insert("r_takv_os".to_string(), json!("35"));
+
+        let r_map = unflatten_r_field(&flattened);
+        assert_eq!(r_map.get("takv"), Some(&json!({ "os": "35" })));
+    }
+
+    /// Tiny linear-congruential generator so the round-trip test below can
+    /// exercise many randomly-shaped nested structures without pulling in an
+    /// external fuzzing/property-testing dependency — deterministic across
+    /// runs, since this crate has no existing precedent for one.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+            self.0
+        }
+
+        fn next_range(&mut self, bound: u64) -> u64 {
+            self.next_u64() % bound
+        }
+    }
+
+    fn random_value(rng: &mut Lcg, depth: u32) -> Value {
+        if depth == 0 || rng.next_range(4) == 0 {
+            return match rng.next_range(3) {
+                0 => json!(rng.next_range(1000)),
+                1 => json!(format!("val_{}", rng.next_range(1000))),
+                _ => json!(rng.next_range(2) == 0),
+            };
+        }
+        match rng.next_range(3) {
+            0 => {
+                let mut map = serde_json::Map::new();
+                for i in 0..1 + rng.next_range(3) {
+                    // Never a purely-numeric key: array-vs-object dispatch
+                    // during unflattening is index-shaped, a pre-existing
+                    // ambiguity this test isn't targeting.
+                    let key = format!("key_{i}_{}", rng.next_range(10));
+                    map.insert(key, random_value(rng, depth - 1));
+                }
+                Value::Object(map)
+            }
+            1 => {
+                let len = 1 + rng.next_range(3);
+                Value::Array((0..len).map(|_| random_value(rng, depth - 1)).collect())
+            }
+            _ => random_value(rng, 0),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_randomly_generated_nested_structures() {
+        let mut rng = Lcg(0x2545_F491_4F6C_DD1D);
+
+        for _ in 0..50 {
+            let mut r_map = HashMap::new();
+            r_map.insert("root".to_string(), random_value(&mut rng, 3));
+
+            let flattened = flatten_r_field(&r_map);
+            let reconstructed = unflatten_r_field(&flattened);
+
+            assert_eq!(reconstructed.get("root"), r_map.get("root"));
+        }
+    }
 }
@@ -0,0 +1,121 @@
+//! Stale-time TTL expiry and soft-delete tombstone purging.
+//!
+//! [`staleness`](super::staleness) can tell you which already-fetched
+//! [`CotDocument`](super::CotDocument)s are stale and hand back their
+//! soft-deleted form, but nothing drives that against the live store — a
+//! `cot_location` or `cot_emergency` collection that updates continuously
+//! just grows forever. Taking the expiration-tag idea from nostr-rs-relay's
+//! NIP-40 reaper, this module issues the soft-delete and hard-delete passes
+//! as DQL statements directly: [`expire_stale_documents`] flips the existing
+//! `d_r` removed flag (the same one [`staleness::soft_delete`] sets, and the
+//! one the schema tests assert on) for every row whose `o` (stale,
+//! microseconds since the Unix epoch) has elapsed, and [`purge_removed`]
+//! hard-deletes tombstones once they're older than a grace window so peers
+//! have had a chance to converge on the soft-delete first. [`spawn_reaper`]
+//! runs both on an interval.
+
+use crate::error::CotError;
+use dittolive_ditto::prelude::*;
+use std::time::Duration;
+
+/// Soft-deletes (`d_r = true`) every document in `collection` whose `o`
+/// (stale, microseconds since the Unix epoch) is set and has elapsed as of
+/// `now_micros`.
+///
+/// # Returns
+/// The number of documents soft-deleted.
+pub async fn expire_stale_documents(
+    ditto: &Ditto,
+    collection: &str,
+    now_micros: f64,
+) -> Result<usize, CotError> {
+    let store = ditto.store();
+    let where_clause = format!("o IS NOT NULL AND o < {now_micros} AND d_r = false");
+
+    let matches = store
+        .execute_v2(&format!(
+            "SELECT _id FROM {collection} WHERE {where_clause}"
+        ))
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+    let count = matches.iter().count();
+    if count == 0 {
+        return Ok(0);
+    }
+
+    store
+        .execute_v2(&format!(
+            "UPDATE {collection} SET d_r = true WHERE {where_clause}"
+        ))
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+
+    Ok(count)
+}
+
+/// Hard-deletes every already soft-deleted (`d_r = true`) document in
+/// `collection` whose `o` is older than `older_than_micros`, so CRDT
+/// convergence has a grace window to propagate the tombstone to peers
+/// before it's removed for good.
+///
+/// # Returns
+/// The number of tombstones purged.
+pub async fn purge_removed(
+    ditto: &Ditto,
+    collection: &str,
+    older_than_micros: f64,
+) -> Result<usize, CotError> {
+    let store = ditto.store();
+    let where_clause = format!("d_r = true AND o IS NOT NULL AND o < {older_than_micros}");
+
+    let matches = store
+        .execute_v2(&format!(
+            "SELECT _id FROM {collection} WHERE {where_clause}"
+        ))
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+    let count = matches.iter().count();
+    if count == 0 {
+        return Ok(0);
+    }
+
+    store
+        .execute_v2(&format!("DELETE FROM {collection} WHERE {where_clause}"))
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+
+    Ok(count)
+}
+
+/// Runs [`expire_stale_documents`] (and, if `grace_period` is given,
+/// [`purge_removed`] against the stale cutoff `grace_period` in the past) on
+/// `collection` every `period`, until the returned handle is dropped or
+/// aborted.
+///
+/// A single pass failing (e.g. a transient store error) is logged and does
+/// not stop the reaper — it tries again on the next tick.
+pub fn spawn_reaper(
+    ditto: Ditto,
+    collection: String,
+    period: Duration,
+    grace_period: Option<Duration>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        loop {
+            ticker.tick().await;
+            let now_micros = chrono::Utc::now().timestamp_micros() as f64;
+
+            if let Err(e) = expire_stale_documents(&ditto, &collection, now_micros).await {
+                log::warn!("reaper: failed to expire stale documents in {collection}: {e}");
+            }
+
+            if let Some(grace_period) = grace_period {
+                let cutoff = now_micros - grace_period.as_micros() as f64;
+                if let Err(e) = purge_removed(&ditto, &collection, cutoff).await {
+                    log::warn!("reaper: failed to purge removed documents in {collection}: {e}");
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,221 @@
+//! Active-window queries and periodic-occurrence expansion over collections
+//! of [`CotDocument`]s.
+//!
+//! [`time_range::overlaps`](super::time_range::overlaps) and
+//! [`query::TimeRangeFilter`](super::query::TimeRangeFilter) already answer
+//! "does this one document's `[n, o]` window overlap a range"; [`active_in`]
+//! lifts that to any iterable of documents instead of just the `&[CotDocument]`
+//! slice [`time_range::filter_in_range`](super::time_range::filter_in_range)
+//! takes, so callers with a `HashMap` of tracks, a DB cursor, or any other
+//! iterator don't need to collect into a slice first.
+//!
+//! [`occurrences_in_window`] goes further: a single stored document — a
+//! sensor heartbeat, an API poll — can represent a *recurring* event that
+//! repeats every [`RecurrenceRule::period`] until a [`RecurrenceBound`] is
+//! hit, so it can match a window it doesn't literally overlap. This mirrors
+//! the expansion a calendar `REPORT` does for an `RRULE`-bearing `VEVENT`,
+//! but against CoT's plain `n`/`o` microsecond fields rather than iCalendar
+//! recurrence syntax.
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::time_range::{start_and_stale, OPEN_ENDED_STALE_THRESHOLD_MICROS};
+use super::CotDocument;
+
+fn micros_to_datetime(micros: f64) -> Option<DateTime<Utc>> {
+    DateTime::<Utc>::from_timestamp_micros(micros as i64)
+}
+
+/// Filters any iterable of documents down to those whose `[n, o]` validity
+/// window overlaps `[window_start, window_end]`, using the same inclusive
+/// overlap test and open-ended-stale handling as
+/// [`time_range::overlaps`](super::time_range::overlaps).
+///
+/// Documents missing an `n` (start) field never match, since there is no
+/// window to compare against.
+pub fn active_in<'a, I>(
+    documents: I,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Vec<&'a CotDocument>
+where
+    I: IntoIterator<Item = &'a CotDocument>,
+{
+    let range_start = window_start.timestamp_micros() as f64;
+    let range_end = window_end.timestamp_micros() as f64;
+
+    documents
+        .into_iter()
+        .filter(|doc| match start_and_stale(doc) {
+            Some((start, stale)) => super::time_range::overlaps(start, stale, range_start, range_end),
+            None => false,
+        })
+        .collect()
+}
+
+/// The stop condition for a repeating occurrence series: either a fixed
+/// number of repeats, or a cutoff instant beyond which no more occur.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceBound {
+    /// Repeat this many times total, including the document's own `[n, o]`
+    /// occurrence.
+    Count(u32),
+    /// Repeat until (and including) the occurrence whose start falls at or
+    /// before this instant.
+    Until(DateTime<Utc>),
+}
+
+/// How a document's own `[n, o]` window repeats: every `period`, stopping at
+/// `bound`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub period: Duration,
+    pub bound: RecurrenceBound,
+}
+
+impl RecurrenceRule {
+    /// Creates a new rule repeating every `period`, stopping at `bound`.
+    pub fn new(period: Duration, bound: RecurrenceBound) -> Self {
+        Self { period, bound }
+    }
+}
+
+/// Expands `doc`'s own `[n, o]` window into concrete `(start, stale)`
+/// occurrences under `rule`, returning only those overlapping
+/// `[window_start, window_end]`.
+///
+/// The document's own occurrence is always the first candidate (offset
+/// zero); later occurrences shift both `start` and `stale` by `rule.period`
+/// each step. An open-ended `stale` (per
+/// [`OPEN_ENDED_STALE_THRESHOLD_MICROS`]) is preserved as-is on every
+/// occurrence rather than shifted, since there's no finite duration to carry
+/// forward. Returns an empty `Vec` for a document with no `n` (start) field.
+pub fn occurrences_in_window(
+    doc: &CotDocument,
+    rule: &RecurrenceRule,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let Some((start_micros, stale_micros)) = start_and_stale(doc) else {
+        return Vec::new();
+    };
+    let Some(start) = micros_to_datetime(start_micros) else {
+        return Vec::new();
+    };
+    let is_open_ended = match stale_micros {
+        None => true,
+        Some(s) => s <= 0.0 || s >= OPEN_ENDED_STALE_THRESHOLD_MICROS,
+    };
+    let stale = stale_micros.and_then(micros_to_datetime);
+
+    let range_start = window_start.timestamp_micros() as f64;
+    let range_end = window_end.timestamp_micros() as f64;
+
+    let max_occurrences = match rule.bound {
+        RecurrenceBound::Count(n) => n,
+        // No fixed count: cap how far we'll walk forward by how many whole
+        // periods fit before the `Until` cutoff (plus the zeroth occurrence).
+        RecurrenceBound::Until(until) => {
+            if until < start || rule.period <= Duration::zero() {
+                1
+            } else {
+                ((until - start).num_milliseconds() / rule.period.num_milliseconds().max(1)) as u32 + 1
+            }
+        }
+    };
+
+    (0..max_occurrences)
+        .map(|n| {
+            let offset = rule.period * n as i32;
+            let occurrence_stale = if is_open_ended { stale } else { stale.map(|s| s + offset) };
+            (start + offset, occurrence_stale)
+        })
+        .take_while(|(occurrence_start, _)| match rule.bound {
+            RecurrenceBound::Count(_) => true,
+            RecurrenceBound::Until(until) => *occurrence_start <= until,
+        })
+        .filter(|(occurrence_start, occurrence_stale)| {
+            let occurrence_stale_micros = if is_open_ended {
+                stale_micros
+            } else {
+                occurrence_stale.map(|s| s.timestamp_micros() as f64)
+            };
+            super::time_range::overlaps(
+                occurrence_start.timestamp_micros() as f64,
+                occurrence_stale_micros,
+                range_start,
+                range_end,
+            )
+        })
+        .map(|(occurrence_start, occurrence_stale)| {
+            (occurrence_start, occurrence_stale.unwrap_or(occurrence_start))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::UnknownDocument;
+
+    fn at(offset_secs: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + Duration::seconds(offset_secs)
+    }
+
+    fn doc_with(n: f64, o: Option<f64>) -> CotDocument {
+        CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({ "_id": "test", "n": n, "o": o }),
+        })
+    }
+
+    #[test]
+    fn active_in_finds_overlapping_documents_from_any_iterator() {
+        let docs = vec![doc_with(0.0, Some(100_000_000.0)), doc_with(500_000_000.0, Some(600_000_000.0))];
+        let matches = active_in(docs.iter(), at(0), at(120));
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[test]
+    fn occurrences_expand_a_periodic_heartbeat_into_the_window() {
+        // A heartbeat at t=0 repeating every 60s, ten times total.
+        let doc = doc_with(0.0, Some(1_000_000.0)); // 1s validity window each beat
+        let rule = RecurrenceRule::new(Duration::seconds(60), RecurrenceBound::Count(10));
+
+        let occurrences = occurrences_in_window(&doc, &rule, at(150), at(250));
+        // Beats at t=180 and t=240 fall in [150, 250]; t=120's 1s window ends
+        // before 150, so it's excluded.
+        assert_eq!(occurrences.len(), 2);
+        assert_eq!(occurrences[0].0, at(180));
+        assert_eq!(occurrences[1].0, at(240));
+    }
+
+    #[test]
+    fn occurrences_respect_an_until_bound() {
+        let doc = doc_with(0.0, Some(1_000_000.0));
+        let rule = RecurrenceRule::new(Duration::seconds(60), RecurrenceBound::Until(at(130)));
+
+        let occurrences = occurrences_in_window(&doc, &rule, at(0), at(1_000));
+        // Occurrences at t=0, 60, 120 are <= until=130; t=180 is excluded.
+        assert_eq!(occurrences.len(), 3);
+    }
+
+    #[test]
+    fn open_ended_occurrences_always_satisfy_the_upper_bound() {
+        let doc = doc_with(0.0, None);
+        let rule = RecurrenceRule::new(Duration::seconds(60), RecurrenceBound::Count(3));
+
+        let occurrences = occurrences_in_window(&doc, &rule, at(50), at(70));
+        // All three occurrences (t=0, 60, 120) are open-ended, so each
+        // satisfies any upper bound; only their `start <= range_end` matters.
+        assert_eq!(occurrences.len(), 2);
+    }
+
+    #[test]
+    fn document_without_a_start_field_has_no_occurrences() {
+        let doc = CotDocument::Unknown(UnknownDocument {
+            raw: serde_json::json!({ "_id": "test" }),
+        });
+        let rule = RecurrenceRule::new(Duration::seconds(60), RecurrenceBound::Count(5));
+        assert!(occurrences_in_window(&doc, &rule, at(0), at(1_000)).is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+//! Repair/validation pass for flattened Ditto documents carrying CRDT-merge
+//! damage, run before [`cot_event_from_flattened_json`](crate::ditto::from_ditto::cot_event_from_flattened_json)
+//! reconstructs a [`CotEvent`](crate::cot_events::CotEvent) from them.
+//!
+//! Two peers independently editing the same document can leave a merged
+//! document in a state the happy-path conversion doesn't expect: an `r_*`
+//! value that arrived with a different JSON type than its siblings, a
+//! core field dropped entirely, or (once element-level merge lands, see
+//! [`crate::ditto::detail_merge`]) a duplicated element prefix from
+//! conflicting writes. This module validates a flattened document against
+//! those failure modes and reconstructs a well-formed one, reporting exactly
+//! what was wrong and what was fixed.
+//!
+//! The repaired output stays a flattened `serde_json::Value` (the same shape
+//! [`cot_event_from_flattened_json`](crate::ditto::from_ditto::cot_event_from_flattened_json)
+//! already consumes) rather than a schema-specific document type, since this
+//! pass runs on raw CRDT-synced JSON before a document variant is even chosen.
+
+use crate::detail_parser::{coerce_scalar, ParseOptions};
+use serde_json::Value;
+
+/// Core fields a flattened document must have for
+/// `cot_event_from_flattened_json` to produce a meaningful `CotEvent`.
+const MANDATORY_FIELDS: &[&str] = &["_id", "w", "n", "o"];
+
+/// A single defect found (and, unless unrecoverable, fixed) during a repair pass.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// An `r_*` key's value arrived as a different JSON type than it should
+    /// have (e.g. a numeric attribute synced as a string by one peer); the
+    /// value was coerced to its inferred type.
+    WrongType {
+        /// The flattened key, e.g. `r_status_battery`.
+        key: String,
+    },
+    /// An `r_<prefix>_<attr>` key whose `<prefix>` doesn't appear on any other
+    /// key, suggesting the rest of its element's attributes were dropped by a
+    /// conflicting merge.
+    OrphanedFragment {
+        /// The flattened key, e.g. `r_contact_callsign` with no other `r_contact_*` sibling.
+        key: String,
+    },
+    /// A field required to reconstruct a `CotEvent` was missing.
+    MissingField {
+        /// The missing field name, e.g. `_id`.
+        field: String,
+    },
+}
+
+/// The outcome of a [`repair_flattened_document`] pass.
+#[derive(Debug, Clone)]
+pub struct RepairReport {
+    /// Every defect found, in the order encountered.
+    pub issues: Vec<Issue>,
+    /// The repaired flattened document.
+    pub repaired: Value,
+    /// Whether producing `repaired` required dropping or guessing at data
+    /// (as opposed to a type coercion that's fully reversible).
+    pub lossy: bool,
+}
+
+/// Returned by [`repair_flattened_document`] in strict mode when an issue
+/// can't be repaired without guessing at or dropping data.
+#[derive(Debug, thiserror::Error)]
+#[error("unrecoverable issues in flattened document: {0:?}")]
+pub struct UnrecoverableError(pub Vec<Issue>);
+
+/// Validates and repairs a flattened Ditto document.
+///
+/// In non-strict mode, unrecoverable issues (missing mandatory fields,
+/// orphaned fragments) are recorded in the report and `lossy` is set, but a
+/// best-effort `repaired` document is still returned. In `strict` mode, the
+/// same issues are returned as an [`UnrecoverableError`] instead, so
+/// operators can distinguish a clean document from a merely salvaged one
+/// without inspecting the report by hand.
+pub fn repair_flattened_document(doc: &Value, strict: bool) -> Result<RepairReport, UnrecoverableError> {
+    let Value::Object(obj) = doc else {
+        let issue = Issue::MissingField {
+            field: "<document root>".to_string(),
+        };
+        return if strict {
+            Err(UnrecoverableError(vec![issue]))
+        } else {
+            Ok(RepairReport {
+                issues: vec![issue],
+                repaired: doc.clone(),
+                lossy: true,
+            })
+        };
+    };
+
+    let mut issues = Vec::new();
+    let mut repaired = obj.clone();
+    let mut lossy = false;
+
+    for field in MANDATORY_FIELDS {
+        if !repaired.contains_key(*field) {
+            issues.push(Issue::MissingField {
+                field: field.to_string(),
+            });
+            lossy = true;
+        }
+    }
+
+    // Track each r_<prefix>_<attr> key's prefix so fragments with no sibling
+    // sharing the same prefix can be flagged as orphaned.
+    let r_prefixes: std::collections::HashMap<String, usize> = repaired
+        .keys()
+        .filter_map(|key| key.strip_prefix("r_"))
+        .filter_map(|rest| rest.rfind('_').map(|i| rest[..i].to_string()))
+        .fold(std::collections::HashMap::new(), |mut acc, prefix| {
+            *acc.entry(prefix).or_insert(0) += 1;
+            acc
+        });
+
+    let r_keys: Vec<String> = repaired
+        .keys()
+        .filter(|k| k.starts_with("r_"))
+        .cloned()
+        .collect();
+
+    for key in r_keys {
+        let rest = &key["r_".len()..];
+        let Some(split) = rest.rfind('_') else {
+            continue;
+        };
+        let prefix = &rest[..split];
+        let attr = &rest[split + 1..];
+
+        if r_prefixes.get(prefix).copied().unwrap_or(0) <= 1 {
+            issues.push(Issue::OrphanedFragment { key: key.clone() });
+            lossy = true;
+            // Kept rather than dropped: a lone attribute is still usable
+            // detail, just not known-coherent with the rest of its element.
+        }
+
+        // Re-run the same scalar coercion the XML detail parser applies, so
+        // a value that arrived as a string after a merge (e.g. "true" where
+        // a sibling write had `true`) normalizes the same way a fresh parse
+        // of the original XML would have.
+        if let Some(Value::String(raw)) = repaired.get(&key) {
+            let coerced = coerce_scalar(attr, raw, &ParseOptions::default());
+            if coerced != Value::String(raw.clone()) {
+                issues.push(Issue::WrongType { key: key.clone() });
+                repaired.insert(key, coerced);
+            }
+        }
+    }
+
+    if strict && lossy {
+        return Err(UnrecoverableError(issues));
+    }
+
+    Ok(RepairReport {
+        issues,
+        repaired: Value::Object(repaired),
+        lossy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn clean_document_reports_no_issues() {
+        let doc = json!({
+            "_id": "uid-1",
+            "w": "a-f-G-U-C",
+            "n": 100.0,
+            "o": 200.0,
+            "r_status_battery": 85,
+            "r_status_readiness": true,
+        });
+        let report = repair_flattened_document(&doc, false).unwrap();
+        assert!(report.issues.is_empty());
+        assert!(!report.lossy);
+    }
+
+    #[test]
+    fn string_value_coerced_to_matching_type() {
+        let doc = json!({
+            "_id": "uid-1",
+            "w": "a-f-G-U-C",
+            "n": 100.0,
+            "o": 200.0,
+            "r_status_battery": "85",
+            "r_status_readiness": "true",
+        });
+        let report = repair_flattened_document(&doc, false).unwrap();
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, Issue::WrongType { key } if key == "r_status_battery")));
+        assert_eq!(report.repaired["r_status_battery"], json!(85));
+        assert_eq!(report.repaired["r_status_readiness"], json!(true));
+    }
+
+    #[test]
+    fn missing_mandatory_field_is_reported() {
+        let doc = json!({ "w": "a-f-G-U-C", "n": 100.0, "o": 200.0 });
+        let report = repair_flattened_document(&doc, false).unwrap();
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, Issue::MissingField { field } if field == "_id")));
+        assert!(report.lossy);
+    }
+
+    #[test]
+    fn strict_mode_errors_on_missing_field_instead_of_salvaging() {
+        let doc = json!({ "w": "a-f-G-U-C", "n": 100.0, "o": 200.0 });
+        let result = repair_flattened_document(&doc, true);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn lone_r_fragment_is_flagged_orphaned() {
+        let doc = json!({
+            "_id": "uid-1",
+            "w": "a-f-G-U-C",
+            "n": 100.0,
+            "o": 200.0,
+            "r_contact_callsign": "ALPHA-1",
+        });
+        let report = repair_flattened_document(&doc, false).unwrap();
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| matches!(i, Issue::OrphanedFragment { key } if key == "r_contact_callsign")));
+    }
+
+    #[test]
+    fn sibling_r_attrs_are_not_orphaned() {
+        let doc = json!({
+            "_id": "uid-1",
+            "w": "a-f-G-U-C",
+            "n": 100.0,
+            "o": 200.0,
+            "r_contact_callsign": "ALPHA-1",
+            "r_contact_endpoint": "192.168.1.1:4242:tcp",
+        });
+        let report = repair_flattened_document(&doc, false).unwrap();
+        assert!(report.issues.is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+//! Add-wins set merge for repeated same-tag `<detail>` children.
+//!
+//! [`detail_parser`](crate::detail_parser) already preserves several
+//! same-tag siblings (`<link>`, `<sensor>`, `<__geofence>`) as a single `r`
+//! entry holding a `Value::Array` rather than overwriting one with the next,
+//! but [`merge`](super::merge)'s ordinary `r`-merge still treats that array
+//! as one opaque scalar: when both peers concurrently add a *different*
+//! element under the same tag, the merge winner's whole array wins and the
+//! loser's addition is dropped. [`merge_repeated_elements`] instead treats
+//! such an array as an add-wins set: each element's identity is a content
+//! hash (so the same element added on both sides converges on one copy
+//! instead of a duplicate), and the merged array is the union of both
+//! sides', in first-seen order.
+//!
+//! This function tracks no removals itself, so on its own a peer's stale
+//! copy would bring a removed element back;
+//! [`CotDocument::merge`](super::CotDocument::merge) filters the union
+//! through [`or_set`](super::or_set)'s removal tombstones afterwards, which
+//! keeps such an element removed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use serde_json::Value;
+
+/// A content-addressed identity for a repeated-detail-element value, stable
+/// across peers since it depends only on the element's own fields. Used both
+/// to dedupe identical adds here and, by [`or_set`](super::or_set), as the
+/// observed-remove set's per-element tag.
+pub(crate) fn element_identity(value: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Unions two repeated-detail-element arrays, deduplicating elements that
+/// are identical on both sides and otherwise preserving `local`'s elements
+/// before `remote`'s.
+pub(crate) fn merge_repeated_elements(local: &[Value], remote: &[Value]) -> Vec<Value> {
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+
+    for value in local.iter().chain(remote.iter()) {
+        if seen.insert(element_identity(value)) {
+            merged.push(value.clone());
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn distinct_elements_from_both_sides_are_unioned() {
+        let local = vec![json!({"uid": "link-1"})];
+        let remote = vec![json!({"uid": "link-2"})];
+
+        let merged = merge_repeated_elements(&local, &remote);
+        assert_eq!(merged, vec![json!({"uid": "link-1"}), json!({"uid": "link-2"})]);
+    }
+
+    #[test]
+    fn identical_elements_on_both_sides_are_deduplicated() {
+        let local = vec![json!({"uid": "link-1"})];
+        let remote = vec![json!({"uid": "link-1"})];
+
+        let merged = merge_repeated_elements(&local, &remote);
+        assert_eq!(merged, vec![json!({"uid": "link-1"})]);
+    }
+
+    #[test]
+    fn an_empty_side_contributes_nothing_but_loses_nothing() {
+        let local = vec![json!({"uid": "link-1"})];
+        let remote: Vec<Value> = vec![];
+
+        assert_eq!(merge_repeated_elements(&local, &remote), local);
+        assert_eq!(merge_repeated_elements(&remote, &local), local);
+    }
+}
@@ -0,0 +1,205 @@
+//! Lossless round-trip validation for the flattening <-> CoT pipeline.
+//!
+//! Existing tests assert detail fidelity with brittle `detail.contains(...)`
+//! substring checks, which only catch a value going missing entirely and say
+//! nothing about *which* attribute was dropped or mangled. [`validate_roundtrip`]
+//! and [`validate_flattened_roundtrip`] instead push a document through
+//! [`cot_event_from_flattened_json`](crate::ditto::from_ditto::cot_event_from_flattened_json)
+//! and back through [`cot_to_flattened_document`](crate::ditto::to_ditto::cot_to_flattened_document),
+//! then perform a structural diff between the original and regenerated flattened
+//! JSON so callers see exactly which paths (typically `r_*` detail keys) failed
+//! to survive the trip, not just a pass/fail boolean.
+
+use crate::cot_events::CotEvent;
+use crate::ditto::from_ditto::cot_event_from_flattened_json;
+use crate::ditto::to_ditto::cot_to_flattened_document;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Peer key used when flattening a bare `CotEvent` that isn't already
+/// associated with a Ditto peer; validation doesn't care which peer "wrote"
+/// the document, only whether its content survives the trip.
+const VALIDATION_PEER_KEY: &str = "roundtrip-validator";
+
+/// A single path that diverged between the original and regenerated document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    /// Dot/bracket-separated path into the flattened document, e.g.
+    /// `r_precisionlocation_geopointsrc`.
+    pub path: String,
+    /// The value at `path` in the original document, or `None` if the path
+    /// only exists in the regenerated document.
+    pub before: Option<Value>,
+    /// The value at `path` in the regenerated document, or `None` if the
+    /// path only exists in the original document.
+    pub after: Option<Value>,
+}
+
+/// The outcome of a round-trip validation pass.
+#[derive(Debug, Clone)]
+pub struct RoundtripReport {
+    /// `true` when no paths diverged.
+    pub is_lossless: bool,
+    /// Every diverging path, in sorted key order.
+    pub diffs: Vec<FieldDiff>,
+}
+
+/// Validates that a `CotEvent`, once flattened, reconstructed, and reflattened,
+/// produces the same flattened document it started with.
+pub fn validate_roundtrip(event: &CotEvent) -> RoundtripReport {
+    let original = cot_to_flattened_document(event, VALIDATION_PEER_KEY);
+    validate_flattened_roundtrip(&original)
+}
+
+/// Validates that a flattened document survives
+/// [`cot_event_from_flattened_json`] followed by [`cot_to_flattened_document`]
+/// unchanged, modulo canonicalization (key order, numeric-string formatting).
+pub fn validate_flattened_roundtrip(original: &Value) -> RoundtripReport {
+    let peer_key = original
+        .get("a")
+        .and_then(Value::as_str)
+        .unwrap_or(VALIDATION_PEER_KEY);
+
+    let reconstructed_event = cot_event_from_flattened_json(original);
+    let regenerated = cot_to_flattened_document(&reconstructed_event, peer_key);
+
+    let mut diffs = Vec::new();
+    diff_values("", original, &regenerated, &mut diffs);
+
+    RoundtripReport {
+        is_lossless: diffs.is_empty(),
+        diffs,
+    }
+}
+
+/// Recursively diffs `before` against `after`, normalizing scalars so
+/// `"0.0"`, `0.0`, and `0` all compare equal, and reporting every path that
+/// still diverges after normalization.
+fn diff_values(path: &str, before: &Value, after: &Value, diffs: &mut Vec<FieldDiff>) {
+    match (before, after) {
+        (Value::Object(b), Value::Object(a)) => {
+            let mut keys: BTreeSet<&String> = BTreeSet::new();
+            keys.extend(b.keys());
+            keys.extend(a.keys());
+            for key in keys {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match (b.get(key), a.get(key)) {
+                    (Some(bv), Some(av)) => diff_values(&child_path, bv, av, diffs),
+                    (Some(bv), None) => diffs.push(FieldDiff {
+                        path: child_path,
+                        before: Some(bv.clone()),
+                        after: None,
+                    }),
+                    (None, Some(av)) => diffs.push(FieldDiff {
+                        path: child_path,
+                        before: None,
+                        after: Some(av.clone()),
+                    }),
+                    (None, None) => unreachable!("key came from one of the two maps"),
+                }
+            }
+        }
+        (Value::Array(b), Value::Array(a)) => {
+            for i in 0..b.len().max(a.len()) {
+                let child_path = format!("{path}[{i}]");
+                match (b.get(i), a.get(i)) {
+                    (Some(bv), Some(av)) => diff_values(&child_path, bv, av, diffs),
+                    (Some(bv), None) => diffs.push(FieldDiff {
+                        path: child_path,
+                        before: Some(bv.clone()),
+                        after: None,
+                    }),
+                    (None, Some(av)) => diffs.push(FieldDiff {
+                        path: child_path,
+                        before: None,
+                        after: Some(av.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => {
+            if normalize_scalar(before) != normalize_scalar(after) {
+                diffs.push(FieldDiff {
+                    path: path.to_string(),
+                    before: Some(before.clone()),
+                    after: Some(after.clone()),
+                });
+            }
+        }
+    }
+}
+
+/// Normalizes a scalar `Value` for comparison: numeric strings (`"0.0"`) and
+/// numbers (`0.0`, `0`) all collapse to the same canonical string so a
+/// representation change alone doesn't register as a divergence.
+fn normalize_scalar(value: &Value) -> Value {
+    let as_f64 = match value {
+        Value::String(s) => s.parse::<f64>().ok(),
+        Value::Number(n) => n.as_f64(),
+        _ => None,
+    };
+
+    match as_f64 {
+        Some(f) if f.is_finite() => {
+            if f.fract() == 0.0 && f.abs() < 1e15 {
+                Value::String(format!("{}", f as i64))
+            } else {
+                Value::String(format!("{f}"))
+            }
+        }
+        _ => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn identical_documents_report_no_diffs() {
+        let doc = json!({ "_id": "uid-1", "b": 100.0, "r_status_battery": 85 });
+        let report = validate_flattened_roundtrip(&doc);
+        // The document isn't a full schema document, so reconstruction is
+        // best-effort; what matters here is that identical inputs yield an
+        // empty diff set when compared against themselves.
+        let mut diffs = Vec::new();
+        diff_values("", &doc, &doc, &mut diffs);
+        assert!(diffs.is_empty());
+        let _ = report; // exercised for the public entry point above
+    }
+
+    #[test]
+    fn numeric_string_and_number_normalize_equal() {
+        assert_eq!(normalize_scalar(&json!("0.0")), normalize_scalar(&json!(0)));
+        assert_eq!(normalize_scalar(&json!("85")), normalize_scalar(&json!(85.0)));
+    }
+
+    #[test]
+    fn dropped_attribute_is_reported_with_before_and_after() {
+        let before = json!({ "r_precisionlocation_geopointsrc": "GPS" });
+        let after = json!({});
+        let mut diffs = Vec::new();
+        diff_values("", &before, &after, &mut diffs);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, "r_precisionlocation_geopointsrc");
+        assert_eq!(diffs[0].before, Some(json!("GPS")));
+        assert_eq!(diffs[0].after, None);
+    }
+
+    #[test]
+    fn changed_attribute_is_reported_with_both_values() {
+        let before = json!({ "r_status_battery": 85 });
+        let after = json!({ "r_status_battery": 40 });
+        let mut diffs = Vec::new();
+        diff_values("", &before, &after, &mut diffs);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].before, Some(json!(85)));
+        assert_eq!(diffs[0].after, Some(json!(40)));
+    }
+}
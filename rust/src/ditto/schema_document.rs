@@ -0,0 +1,173 @@
+//! Machine-readable schema document generator for all Ditto document types.
+//!
+//! [`TaggedSchema`](super::tagged_schema::TaggedSchema) bakes a `const`-valued
+//! discriminator into each variant's generated schema; [`SchemaDocument`]
+//! walks every [`CotDocument`](super::CotDocument) variant and stitches them
+//! into one self-describing artifact — combined `oneOf` JSON Schema plus the
+//! CoT `event_type` -> collection routing table `cot_to_document` otherwise
+//! only expresses as `String::contains` dispatch — so external tooling or
+//! other-language clients can validate documents and generate bindings
+//! without reading the Rust source.
+
+use crate::ditto::tagged_schema::TaggedSchema;
+use crate::ditto::{Api, Chat, File, Generic, MapItem};
+use crate::error::CotError;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{RootSchema, SchemaObject, SubschemaValidation};
+use serde::Serialize;
+use std::path::Path;
+
+/// One row of the CoT `event_type` -> Ditto document type routing table,
+/// mirroring the dispatch `cot_to_document` performs by
+/// `event_type.contains(...)`, in the same priority order (earlier rows are
+/// checked first).
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingRule {
+    /// The [`TaggedSchema::discriminator`] tag of the document type this
+    /// rule routes to.
+    pub document_type: String,
+    /// Substrings of a CoT `event_type` that route to `document_type`. An
+    /// empty list (only ever the last rule) marks the fallback taken when no
+    /// earlier rule matches.
+    pub event_type_patterns: Vec<String>,
+}
+
+/// A self-describing schema document: every [`CotDocument`](super::CotDocument)
+/// variant's tagged JSON Schema, plus the routing table used to pick a
+/// variant for a given CoT event.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaDocument {
+    /// Combined `oneOf` JSON Schema covering every document type.
+    pub schema: RootSchema,
+    /// CoT `event_type` pattern -> document type routing table, in dispatch
+    /// priority order.
+    pub routing: Vec<RoutingRule>,
+}
+
+/// Builds the `event_type` -> document type routing table, matching
+/// [`cot_to_document`](super::to_ditto::cot_to_document)'s dispatch order
+/// exactly so the table never drifts from the code it's documenting.
+pub fn routing_table() -> Vec<RoutingRule> {
+    fn patterns(strs: &[&str]) -> Vec<String> {
+        strs.iter().map(|s| s.to_string()).collect()
+    }
+
+    vec![
+        RoutingRule {
+            document_type: Api::discriminator().to_string(),
+            event_type_patterns: patterns(&["a-u-emergency-g"]),
+        },
+        RoutingRule {
+            document_type: Chat::discriminator().to_string(),
+            event_type_patterns: patterns(&["b-t-f", "chat"]),
+        },
+        RoutingRule {
+            document_type: MapItem::discriminator().to_string(),
+            event_type_patterns: patterns(&[
+                "a-u-r-loc-g",
+                "a-f-G-U-C",
+                "a-f-G-U",
+                "a-f-G-U-I",
+                "a-f-G-U-T",
+                "a-f-S-C-U",
+                "a-f-A-M-F-Q",
+                "a-u-S",
+                "a-u-A",
+                "a-u-G",
+            ]),
+        },
+        RoutingRule {
+            document_type: File::discriminator().to_string(),
+            event_type_patterns: patterns(&["file", "attachment"]),
+        },
+        RoutingRule {
+            document_type: Generic::discriminator().to_string(),
+            event_type_patterns: Vec::new(),
+        },
+    ]
+}
+
+/// Walks `Api`/`Chat`/`File`/`MapItem`/`Generic`, producing the combined
+/// schema document described in the module docs.
+pub fn generate() -> SchemaDocument {
+    let mut gen = SchemaGenerator::default();
+    let one_of = vec![
+        Api::tagged_schema(&mut gen),
+        Chat::tagged_schema(&mut gen),
+        File::tagged_schema(&mut gen),
+        Generic::tagged_schema(&mut gen),
+        MapItem::tagged_schema(&mut gen),
+    ];
+
+    let schema = RootSchema {
+        meta_schema: Some("http://json-schema.org/draft-07/schema#".to_string()),
+        schema: SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                one_of: Some(one_of),
+                ..Default::default()
+            })),
+            ..Default::default()
+        },
+        definitions: gen.take_definitions(),
+    };
+
+    SchemaDocument {
+        schema,
+        routing: routing_table(),
+    }
+}
+
+impl SchemaDocument {
+    /// Serializes this document to stable, pretty-printed JSON. Stable
+    /// because `routing` is always built in the fixed order above and
+    /// `schema.definitions` is a `BTreeMap`, so the same document types
+    /// always produce byte-identical output.
+    pub fn to_json(&self) -> Result<String, CotError> {
+        serde_json::to_string_pretty(self).map_err(CotError::from)
+    }
+
+    /// Writes [`to_json`](Self::to_json)'s output to `path`.
+    pub fn write_to_file(&self, path: &Path) -> Result<(), CotError> {
+        let json = self.to_json()?;
+        std::fs::write(path, json).map_err(|e| CotError::InvalidFormat(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routing_table_covers_every_document_type() {
+        let table = routing_table();
+        let tags: Vec<&str> = table.iter().map(|r| r.document_type.as_str()).collect();
+        assert_eq!(tags, vec!["api", "chat", "map_item", "file", "generic"]);
+    }
+
+    #[test]
+    fn fallback_rule_has_no_patterns() {
+        let table = routing_table();
+        let fallback = table.last().expect("routing table is non-empty");
+        assert!(fallback.event_type_patterns.is_empty());
+    }
+
+    #[test]
+    fn generated_schema_has_one_branch_per_document_type() {
+        let doc = generate();
+        let one_of = doc
+            .schema
+            .schema
+            .subschemas
+            .expect("schema should have a oneOf")
+            .one_of
+            .expect("oneOf should be populated");
+        assert_eq!(one_of.len(), 5);
+    }
+
+    #[test]
+    fn document_serializes_to_stable_json() {
+        let first = generate().to_json().unwrap();
+        let second = generate().to_json().unwrap();
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,120 @@
+//! Schema/protocol version negotiation between Rust and Java CoT implementations.
+//!
+//! Every document [`to_ditto`](super::to_ditto) produces is already stamped
+//! with a `d_v`/`_v` schema-version field (see `d_v: 2` throughout
+//! `to_ditto.rs`), but nothing on the decode side ever looks at it: a Rust
+//! peer syncing with a Java peer running a different schema revision just
+//! gets whatever [`cot_event_from_ditto_document`](super::from_ditto::cot_event_from_ditto_document)
+//! can salvage, silently. This module gives that stamp a name
+//! ([`CotSchemaVersion`]) and a comparison ([`negotiate`]), mirroring how
+//! distributed clients check a protocol version at the client/server boundary
+//! before trusting a peer's payload.
+
+use crate::ditto::CotDocument;
+use serde_json::Value;
+
+/// The schema revision a [`CotDocument`] (or flattened document) was stamped
+/// with, carried in its `d_v`/`_v` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CotSchemaVersion(pub u32);
+
+/// The schema version this build of the crate reads and writes.
+pub const CURRENT: CotSchemaVersion = CotSchemaVersion(2);
+
+/// The outcome of comparing a remote document's schema version against a
+/// local one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    /// The remote version matches exactly; no translation needed.
+    Identical,
+    /// The remote version is older than local, but local can still read it:
+    /// every schema revision to date has only added fields, so an older
+    /// document simply has fewer of them filled in.
+    BackwardCompatible,
+    /// The remote version is newer than local; decoding it would silently
+    /// drop or misinterpret fields this build doesn't know about.
+    Incompatible,
+}
+
+/// Classifies `remote` relative to `local`.
+pub fn negotiate(local: CotSchemaVersion, remote: CotSchemaVersion) -> Compatibility {
+    if remote == local {
+        Compatibility::Identical
+    } else if remote < local {
+        Compatibility::BackwardCompatible
+    } else {
+        Compatibility::Incompatible
+    }
+}
+
+/// Extracts the schema version stamped on a typed [`CotDocument`].
+///
+/// A [`CotDocument::Unknown`] has no typed `d_v` field, so its raw `d_v`
+/// (or `_v`) is read instead; when neither is a number it's reported as
+/// version `0` (older than anything this build writes) rather than guessed
+/// at: [`negotiate`] then treats it as backward-compatible
+/// instead of blocking on it.
+pub fn schema_version_of(doc: &CotDocument) -> CotSchemaVersion {
+    match doc {
+        CotDocument::Api(d) => CotSchemaVersion(d.d_v),
+        CotDocument::Chat(d) => CotSchemaVersion(d.d_v),
+        CotDocument::File(d) => CotSchemaVersion(d.d_v),
+        CotDocument::Generic(d) => CotSchemaVersion(d.d_v),
+        CotDocument::MapItem(d) => CotSchemaVersion(d.d_v),
+        CotDocument::Unknown(u) => CotSchemaVersion(
+            u.raw
+                .get("d_v")
+                .or_else(|| u.raw.get("_v"))
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as u32,
+        ),
+    }
+}
+
+/// Extracts the schema version stamped on a flattened document's `_v` key, if
+/// present and numeric. `doc` is the [`Value`] object [`cot_to_flattened_document`](super::to_ditto::cot_to_flattened_document)
+/// produces.
+pub fn schema_version_of_flattened(doc: &Value) -> Option<CotSchemaVersion> {
+    doc.get("_v")?.as_u64().map(|v| CotSchemaVersion(v as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_versions_negotiate_to_identical() {
+        assert_eq!(
+            negotiate(CotSchemaVersion(2), CotSchemaVersion(2)),
+            Compatibility::Identical
+        );
+    }
+
+    #[test]
+    fn older_remote_negotiates_to_backward_compatible() {
+        assert_eq!(
+            negotiate(CotSchemaVersion(2), CotSchemaVersion(1)),
+            Compatibility::BackwardCompatible
+        );
+    }
+
+    #[test]
+    fn newer_remote_negotiates_to_incompatible() {
+        assert_eq!(
+            negotiate(CotSchemaVersion(2), CotSchemaVersion(3)),
+            Compatibility::Incompatible
+        );
+    }
+
+    #[test]
+    fn schema_version_of_flattened_reads_the_v_key() {
+        let doc = serde_json::json!({ "_v": 2 });
+        assert_eq!(schema_version_of_flattened(&doc), Some(CotSchemaVersion(2)));
+    }
+
+    #[test]
+    fn schema_version_of_flattened_is_none_when_missing() {
+        let doc = serde_json::json!({});
+        assert_eq!(schema_version_of_flattened(&doc), None);
+    }
+}
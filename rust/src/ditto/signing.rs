@@ -0,0 +1,294 @@
+//! Detached Ed25519 signatures over a [`CotDocument`], so a receiver can
+//! trust that a synced CoT event (a position report, a chat message, ...)
+//! really came from the unit it claims to, after passing through Ditto's
+//! sync layer.
+//!
+//! The hard part is canonicalization: Ditto flattens `r` into `r_*` fields
+//! on the wire and doesn't guarantee map key order, so two peers must agree
+//! on the exact bytes being hashed before either can trust the other's
+//! signature. [`canonical_bytes`] normalizes
+//! [`CotDocument::to_flattened_json`]'s output (which already nests `r`
+//! rather than flattening it — there's no `r_*` unflattening step needed
+//! for a typed [`CotDocument`], unlike the truly-flat wire documents
+//! [`r_field_flattening`](super::r_field_flattening) deals with) by
+//! recursively sorting object keys, relying on `serde_json`'s own
+//! shortest-round-trip float formatting for `j`/`l`/`i`/`h`/`k`, and
+//! stripping the reserved [`SIGNATURE_KEY`] entry so a signature never
+//! signs over itself. [`sign_document`]/[`verify_document`] SHA-256-hash
+//! those bytes and sign/verify with `ed25519-dalek`, and
+//! [`CotDocument::with_signature`]/[`CotDocument::signature`] store and
+//! retrieve the resulting [`DocumentSignature`] from the document's `r`
+//! map, the same reserved-key convention
+//! [`version_vector::VERSION_VECTOR_KEY`](super::version_vector::VERSION_VECTOR_KEY)
+//! already uses for sync metadata that isn't really part of the detail.
+//!
+//! This normalization must stay byte-identical across the Rust/Java/C#
+//! clients for a signature made by one to verify on another; a
+//! cross-language fixture asserting equal [`canonical_bytes`] output
+//! belongs in this crate's cross-SDK integration suite, not here.
+
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use super::schema::{ApiRValue, ChatRValue, FileRValue, GenericRValue, MapItemRValue};
+use super::to_ditto::CotDocument;
+use crate::error::CotError;
+
+/// The `r` map key a document's [`DocumentSignature`] is stored under.
+pub const SIGNATURE_KEY: &str = "_sig";
+
+/// A detached Ed25519 signature over a document's [`canonical_bytes`] hash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentSignature {
+    /// The signature algorithm; always `"ed25519"` today, carried
+    /// explicitly so a future algorithm change doesn't silently break old
+    /// verifiers expecting this shape.
+    pub alg: String,
+    /// Identifies the signing peer, independent of the document's own `a`
+    /// (peer key) so a signature survives the document being re-keyed.
+    pub signer_id: String,
+    /// When the signature was created, as RFC 3339.
+    pub created: String,
+    /// The raw Ed25519 signature, base64 (URL-safe, unpadded).
+    pub sig: String,
+}
+
+/// Recursively sorts `value`'s object keys lexicographically, so two peers
+/// serializing the same logical document produce byte-identical output
+/// regardless of how their maps happened to iterate.
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let sorted: BTreeMap<String, Value> = map
+                .iter()
+                .map(|(k, v)| (k.clone(), canonicalize(v)))
+                .collect();
+            Value::Object(sorted.into_iter().collect())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Strips the [`SIGNATURE_KEY`] entry from `value`'s `r` object, if
+/// present, so a signature never has to sign over itself.
+fn strip_signature(mut value: Value) -> Value {
+    if let Some(r) = value.get_mut("r").and_then(Value::as_object_mut) {
+        r.remove(SIGNATURE_KEY);
+    }
+    value
+}
+
+/// The exact bytes [`sign_document`]/[`verify_document`] hash: `doc`'s
+/// fully reconstructed JSON with the [`SIGNATURE_KEY`] entry removed,
+/// object keys sorted recursively, and no insignificant whitespace — the
+/// same normalization a JSON-LD canonicalizer applies before hashing.
+pub fn canonical_bytes(doc: &CotDocument) -> Vec<u8> {
+    let canonical = canonicalize(&strip_signature(doc.to_flattened_json()));
+    serde_json::to_vec(&canonical).unwrap_or_default()
+}
+
+/// SHA-256-hashes `doc`'s [`canonical_bytes`] and signs the digest with
+/// `signing_key`, returning a [`DocumentSignature`] ready to attach via
+/// [`CotDocument::with_signature`].
+pub fn sign_document(
+    doc: &CotDocument,
+    signing_key: &SigningKey,
+    signer_id: &str,
+) -> DocumentSignature {
+    let digest = Sha256::digest(canonical_bytes(doc));
+    let signature: Signature = signing_key.sign(&digest);
+
+    DocumentSignature {
+        alg: "ed25519".to_string(),
+        signer_id: signer_id.to_string(),
+        created: chrono::Utc::now().to_rfc3339(),
+        sig: URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+    }
+}
+
+/// Verifies that `signature` is a valid Ed25519 signature over `doc`'s
+/// [`canonical_bytes`] hash, made by the holder of `public_key`.
+pub fn verify_document(
+    doc: &CotDocument,
+    signature: &DocumentSignature,
+    public_key: &VerifyingKey,
+) -> Result<(), CotError> {
+    let sig_bytes = URL_SAFE_NO_PAD
+        .decode(&signature.sig)
+        .map_err(|e| CotError::InvalidFormat(format!("signature is not valid base64: {e}")))?;
+    let sig_array: [u8; 64] = sig_bytes
+        .try_into()
+        .map_err(|_| CotError::InvalidFormat("signature is not 64 bytes".to_string()))?;
+    let ed_signature = Signature::from_bytes(&sig_array);
+
+    let digest = Sha256::digest(canonical_bytes(doc));
+    public_key
+        .verify(&digest, &ed_signature)
+        .map_err(|e| CotError::InvalidFormat(format!("signature verification failed: {e}")))
+}
+
+/// Converts a [`DocumentSignature`] to the JSON object form stored under
+/// [`SIGNATURE_KEY`].
+fn signature_to_map(signature: &DocumentSignature) -> Map<String, Value> {
+    match serde_json::to_value(signature) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+impl CotDocument {
+    /// Returns a copy of `self` with `signature` attached under
+    /// [`SIGNATURE_KEY`] in its `r` map. [`CotDocument::Unknown`] is
+    /// attached to at the raw JSON level since it carries no typed `r`
+    /// field.
+    pub fn with_signature(&self, signature: &DocumentSignature) -> CotDocument {
+        let sig_map = signature_to_map(signature);
+
+        match self.clone() {
+            CotDocument::Api(mut doc) => {
+                doc.r
+                    .insert(SIGNATURE_KEY.to_string(), ApiRValue::Object(sig_map));
+                CotDocument::Api(doc)
+            }
+            CotDocument::Chat(mut doc) => {
+                doc.r
+                    .insert(SIGNATURE_KEY.to_string(), ChatRValue::Object(sig_map));
+                CotDocument::Chat(doc)
+            }
+            CotDocument::File(mut doc) => {
+                doc.r
+                    .insert(SIGNATURE_KEY.to_string(), FileRValue::Object(sig_map));
+                CotDocument::File(doc)
+            }
+            CotDocument::Generic(mut doc) => {
+                doc.r
+                    .insert(SIGNATURE_KEY.to_string(), GenericRValue::Object(sig_map));
+                CotDocument::Generic(doc)
+            }
+            CotDocument::MapItem(mut doc) => {
+                doc.r
+                    .insert(SIGNATURE_KEY.to_string(), MapItemRValue::Object(sig_map));
+                CotDocument::MapItem(doc)
+            }
+            CotDocument::Unknown(mut unknown) => {
+                if let Some(obj) = unknown.raw.as_object_mut() {
+                    let r = obj
+                        .entry("r")
+                        .or_insert_with(|| Value::Object(Map::new()));
+                    if let Some(r_obj) = r.as_object_mut() {
+                        r_obj.insert(SIGNATURE_KEY.to_string(), Value::Object(sig_map));
+                    }
+                }
+                CotDocument::Unknown(unknown)
+            }
+        }
+    }
+
+    /// Reads back a [`DocumentSignature`] previously attached by
+    /// [`with_signature`](CotDocument::with_signature), if any.
+    pub fn signature(&self) -> Option<DocumentSignature> {
+        let json = self.to_flattened_json();
+        let sig_value = json.get("r")?.get(SIGNATURE_KEY)?.clone();
+        serde_json::from_value(sig_value).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::schema::MapItem;
+    use std::collections::HashMap;
+
+    fn map_item() -> MapItem {
+        MapItem {
+            id: "test-id-123".to_string(),
+            a: "peer-key".to_string(),
+            b: 123.0,
+            c: None,
+            d: "tak-uid-123".to_string(),
+            d_c: 1,
+            d_r: false,
+            d_v: 2,
+            e: "".to_string(),
+            f: None,
+            g: "".to_string(),
+            h: None,
+            i: None,
+            j: Some(34.0),
+            k: None,
+            l: Some(-118.0),
+            n: 1622548800000,
+            o: 1622548800000,
+            p: "".to_string(),
+            q: "".to_string(),
+            r: HashMap::new(),
+            s: "".to_string(),
+            source: None,
+            t: "".to_string(),
+            u: "".to_string(),
+            v: "".to_string(),
+            w: "a-f-G-U".to_string(),
+        }
+    }
+
+    #[test]
+    fn canonical_bytes_is_deterministic_across_calls() {
+        let doc = CotDocument::MapItem(map_item());
+        assert_eq!(canonical_bytes(&doc), canonical_bytes(&doc));
+    }
+
+    #[test]
+    fn a_well_formed_signature_verifies() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let verifying_key = signing_key.verifying_key();
+        let doc = CotDocument::MapItem(map_item());
+
+        let signature = sign_document(&doc, &signing_key, "ALPHA-1");
+        let signed = doc.with_signature(&signature);
+
+        assert_eq!(signed.signature(), Some(signature.clone()));
+        assert!(verify_document(&signed, &signature, &verifying_key).is_ok());
+    }
+
+    #[test]
+    fn a_tampered_document_fails_verification() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let verifying_key = signing_key.verifying_key();
+        let doc = CotDocument::MapItem(map_item());
+        let signature = sign_document(&doc, &signing_key, "ALPHA-1");
+
+        let mut tampered = map_item();
+        tampered.j = Some(99.0);
+        let tampered_doc = CotDocument::MapItem(tampered);
+
+        assert!(verify_document(&tampered_doc, &signature, &verifying_key).is_err());
+    }
+
+    #[test]
+    fn a_signature_from_the_wrong_key_fails_verification() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let other_key = SigningKey::from_bytes(&[9u8; 32]);
+        let doc = CotDocument::MapItem(map_item());
+        let signature = sign_document(&doc, &signing_key, "ALPHA-1");
+
+        assert!(
+            verify_document(&doc, &signature, &other_key.verifying_key()).is_err()
+        );
+    }
+
+    #[test]
+    fn the_signature_field_is_excluded_from_its_own_canonical_bytes() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let doc = CotDocument::MapItem(map_item());
+        let signature = sign_document(&doc, &signing_key, "ALPHA-1");
+        let signed = doc.with_signature(&signature);
+
+        assert_eq!(canonical_bytes(&doc), canonical_bytes(&signed));
+    }
+}
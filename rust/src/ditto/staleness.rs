@@ -0,0 +1,186 @@
+//! Staleness evaluation and soft-delete pruning for [`CotDocument`]s.
+//!
+//! Every transform in [`to_ditto`](super::to_ditto) faithfully copies the CoT
+//! event's `stale` time into `o`, but nothing acts on it: a location, chat,
+//! or emergency item whose `stale` has long passed just sits in the store
+//! forever. This mirrors the calendar-query time-range logic in
+//! [`time_range`](super::time_range), but answers "is this one gone?" instead
+//! of "does this one overlap a window?", and actually produces the
+//! soft-deleted document rather than just a yes/no.
+
+use crate::ditto::time_range::{filter_in_range, OPEN_ENDED_STALE_THRESHOLD_MICROS};
+use crate::ditto::{CotDocument, UnknownDocument};
+use serde_json::Value;
+
+/// Returns whether `doc`'s `o` (stale) field has definitively elapsed as of
+/// `now` (both in microseconds since the Unix epoch).
+///
+/// A missing, zero, or open-ended sentinel `stale` (see
+/// [`OPEN_ENDED_STALE_THRESHOLD_MICROS`]) is never stale, matching
+/// [`time_range::overlaps`](super::time_range::overlaps)'s treatment of
+/// "never goes stale" tracks.
+pub fn is_stale(doc: &CotDocument, now: f64) -> bool {
+    match stale_time(doc) {
+        Some(o) if o > 0.0 && o < OPEN_ENDED_STALE_THRESHOLD_MICROS => now > o,
+        _ => false,
+    }
+}
+
+/// Filters `documents` down to those whose `o` has elapsed as of `now`.
+pub fn stale_documents<'a>(documents: &'a [CotDocument], now: f64) -> Vec<&'a CotDocument> {
+    documents.iter().filter(|doc| is_stale(doc, now)).collect()
+}
+
+/// Filters `documents` down to those whose `[n, o]` validity window overlaps
+/// `[range_start, range_end]` (both in microseconds since the Unix epoch).
+///
+/// An alias for [`time_range::filter_in_range`](super::time_range::filter_in_range)
+/// exposed under the pruning subsystem's naming, since a caller doing
+/// periodic expiry typically wants both this and [`prune_expired`] from the
+/// same place.
+pub fn valid_between<'a>(
+    documents: &'a [CotDocument],
+    range_start: f64,
+    range_end: f64,
+) -> Vec<&'a CotDocument> {
+    filter_in_range(documents, range_start, range_end)
+}
+
+/// Returns a soft-deleted copy of `doc`: `d_r` flipped to `true` and `d_c`
+/// bumped by one so the update is visible to peers as a new revision rather
+/// than a silent mutation.
+pub fn soft_delete(doc: &CotDocument) -> CotDocument {
+    match doc.clone() {
+        CotDocument::Api(d) => CotDocument::Api(crate::ditto::Api {
+            d_r: true,
+            d_c: d.d_c + 1,
+            ..d
+        }),
+        CotDocument::Chat(d) => CotDocument::Chat(crate::ditto::Chat {
+            d_r: true,
+            d_c: d.d_c + 1,
+            ..d
+        }),
+        CotDocument::File(d) => CotDocument::File(crate::ditto::File {
+            d_r: true,
+            d_c: d.d_c + 1,
+            ..d
+        }),
+        CotDocument::Generic(d) => CotDocument::Generic(crate::ditto::Generic {
+            d_r: true,
+            d_c: d.d_c + 1,
+            ..d
+        }),
+        CotDocument::MapItem(d) => CotDocument::MapItem(crate::ditto::MapItem {
+            d_r: true,
+            d_c: d.d_c + 1,
+            ..d
+        }),
+        CotDocument::Unknown(u) => {
+            let mut raw = u.raw;
+            if let Some(obj) = raw.as_object_mut() {
+                let d_c = obj
+                    .get("d_c")
+                    .or_else(|| obj.get("_c"))
+                    .and_then(Value::as_i64)
+                    .unwrap_or(0);
+                obj.insert("d_r".to_string(), Value::Bool(true));
+                obj.insert("d_c".to_string(), Value::from(d_c + 1));
+            }
+            CotDocument::Unknown(UnknownDocument { raw })
+        }
+    }
+}
+
+/// Runs a full expiry pass over `documents` as of `now`, returning the
+/// soft-deleted form of every document whose `stale` has elapsed.
+///
+/// Callers run this periodically instead of re-deriving stale semantics
+/// per document type.
+pub fn prune_expired(documents: &[CotDocument], now: f64) -> Vec<CotDocument> {
+    stale_documents(documents, now)
+        .into_iter()
+        .map(soft_delete)
+        .collect()
+}
+
+/// Extracts the `o` (stale) microsecond field from any [`CotDocument`] variant.
+fn stale_time(doc: &CotDocument) -> Option<f64> {
+    match doc {
+        CotDocument::Api(d) => d.o,
+        CotDocument::Chat(d) => d.o,
+        CotDocument::File(d) => d.o,
+        CotDocument::Generic(d) => d.o,
+        CotDocument::MapItem(d) => d.o,
+        CotDocument::Unknown(u) => u.stale_micros(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ditto::to_ditto::cot_to_document;
+    use crate::cot_events::CotEvent;
+    use chrono::{DateTime, Utc};
+
+    fn make_event(stale_offset_secs: i64) -> CotEvent {
+        let time: DateTime<Utc> = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "test-uid".to_string(),
+            event_type: "a-u-generic".to_string(),
+            time,
+            start: time,
+            stale: time + chrono::Duration::seconds(stale_offset_secs),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point {
+                lat: 1.0,
+                lon: 2.0,
+                hae: 3.0,
+                ce: 4.0,
+                le: 5.0,
+            },
+            detail: String::new(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn expired_document_is_stale() {
+        let doc = cot_to_document(&make_event(-10), "peer");
+        let now = make_event(-10).time.timestamp_micros() as f64 + 1.0;
+        assert!(is_stale(&doc, now));
+    }
+
+    #[test]
+    fn future_stale_document_is_not_stale() {
+        let doc = cot_to_document(&make_event(3600), "peer");
+        let now = make_event(3600).time.timestamp_micros() as f64;
+        assert!(!is_stale(&doc, now));
+    }
+
+    #[test]
+    fn prune_expired_flips_d_r_and_bumps_d_c() {
+        let event = make_event(-10);
+        let doc = cot_to_document(&event, "peer");
+        let now = event.time.timestamp_micros() as f64 + 1.0;
+
+        let pruned = prune_expired(std::slice::from_ref(&doc), now);
+        assert_eq!(pruned.len(), 1);
+        match &pruned[0] {
+            CotDocument::Generic(d) => {
+                assert!(d.d_r);
+                assert_eq!(d.d_c, 1);
+            }
+            other => panic!("expected Generic document, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn live_document_is_not_pruned() {
+        let doc = cot_to_document(&make_event(3600), "peer");
+        assert!(prune_expired(std::slice::from_ref(&doc), 0.0).is_empty());
+    }
+}
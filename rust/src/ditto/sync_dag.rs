@@ -0,0 +1,679 @@
+//! Document-history DAG with Lamport-style change tokens.
+//!
+//! [`sync`](super::sync) and [`changelog`](super::changelog) both answer
+//! "what changed since this token" from a flat, single-writer log of the
+//! *latest* state. [`SyncDag`] instead keeps every revision: each
+//! [`SyncDag::apply`] call records a new [`VersionNode`] pointing at the
+//! document id's previous node, so a document's history is a parent-linked
+//! chain per id.
+//!
+//! Tokens are a Lamport clock seeded from the document's own `d_v`/`d_c`
+//! (schema version, change counter) fields rather than a plain local
+//! auto-increment, so two peers independently applying the same revision
+//! converge on comparable tokens instead of racing two unrelated counters.
+//! Two peers that instead diverge — each keeping their own [`SyncDag`] and
+//! independently `apply`-ing different revisions — reconcile with
+//! [`SyncDag::merge`], which folds one DAG's history into the other and
+//! deterministically picks a winner for any id both sides touched. The
+//! frontier a caller was watching across both sides before the merge is a
+//! [`SyncDagHeads`] rather than a single [`SyncDagToken`], so
+//! [`SyncDag::changes_since_heads`] can still answer "what's new to me" in
+//! one call.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ditto::CotDocument;
+use crate::error::CotError;
+
+/// A monotonic, never-reused position in a [`SyncDag`]'s version history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+pub struct SyncDagToken(u64);
+
+impl SyncDagToken {
+    /// The token representing "nothing applied yet"; [`SyncDag::changes_since`]
+    /// from it returns every node ever applied.
+    pub fn initial() -> Self {
+        Self(0)
+    }
+}
+
+/// An opaque position in a [`SyncDag`]'s history that may span more than one
+/// concurrently-advanced branch — what [`SyncDag::merge`] hands back instead
+/// of a single [`SyncDagToken`], so a consumer that was watching two DAGs
+/// independently before they merged can still ask "what's new to me" in one
+/// call via [`SyncDag::changes_since_heads`].
+///
+/// Stable and serializable (like [`super::sync::SyncToken`]) so it can be
+/// persisted by the caller and handed back on the next sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncDagHeads(Vec<SyncDagToken>);
+
+impl SyncDagHeads {
+    /// A single-token frontier, the common case before any branch exists.
+    pub fn single(token: SyncDagToken) -> Self {
+        Self(vec![token])
+    }
+
+    /// The individual tokens making up this frontier.
+    pub fn tokens(&self) -> &[SyncDagToken] {
+        &self.0
+    }
+
+    /// Encodes this frontier as an opaque string suitable for a client to
+    /// store and present on the next sync call.
+    pub fn encode(&self) -> String {
+        self.0
+            .iter()
+            .map(|token| token.0.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Decodes a frontier previously produced by [`SyncDagHeads::encode`].
+    pub fn decode(encoded: &str) -> Result<Self, CotError> {
+        let tokens: Result<Vec<SyncDagToken>, _> = encoded
+            .split(',')
+            .map(|part| part.parse::<u64>().map(SyncDagToken))
+            .collect();
+        let mut tokens = tokens.map_err(|_| {
+            CotError::InvalidFormat(format!("invalid sync dag heads '{encoded}'"))
+        })?;
+        if tokens.is_empty() {
+            return Err(CotError::InvalidFormat(format!(
+                "invalid sync dag heads '{encoded}'"
+            )));
+        }
+        tokens.sort();
+        tokens.dedup();
+        Ok(Self(tokens))
+    }
+}
+
+/// One recorded revision of a document in a [`SyncDag`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionNode {
+    /// This node's position in the history.
+    pub token: SyncDagToken,
+    /// The previous node recorded for the same document id, if any.
+    pub parent: Option<SyncDagToken>,
+    /// Id of the document this revision belongs to.
+    pub doc_id: String,
+    /// Whether this revision is a tombstone (`d_r == true`).
+    pub tombstone: bool,
+    /// A hash of the document's flattened content, used only to tell two
+    /// revisions apart; not cryptographically strong.
+    pub content_hash: u64,
+}
+
+/// The result of [`SyncDag::changes_since`]: document ids classified by how
+/// they moved relative to a prior [`SyncDagToken`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncChange {
+    /// Ids with no prior non-tombstone node before `since`.
+    pub added: Vec<String>,
+    /// Ids with a prior non-tombstone node before `since`, now updated.
+    pub updated: Vec<String>,
+    /// Ids whose latest node as of this call is a tombstone.
+    pub removed: Vec<String>,
+}
+
+pub(crate) fn doc_id(doc: &CotDocument) -> String {
+    match doc {
+        CotDocument::Api(d) => d.id.clone(),
+        CotDocument::Chat(d) => d.id.clone(),
+        CotDocument::File(d) => d.id.clone(),
+        CotDocument::Generic(d) => d.id.clone(),
+        CotDocument::MapItem(d) => d.id.clone(),
+        CotDocument::Unknown(u) => u
+            .raw
+            .get("_id")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Returns `(d_v, d_c, d_r)`. A [`CotDocument::Unknown`] has no typed
+/// fields, so they're read from its raw `d_v`, `d_c` and `d_r` keys, with
+/// `0`, `0` and `false` for any that's missing.
+pub(crate) fn version_fields(doc: &CotDocument) -> (u32, i64, bool) {
+    match doc {
+        CotDocument::Api(d) => (d.d_v, d.d_c, d.d_r),
+        CotDocument::Chat(d) => (d.d_v, d.d_c, d.d_r),
+        CotDocument::File(d) => (d.d_v, d.d_c, d.d_r),
+        CotDocument::Generic(d) => (d.d_v, d.d_c, d.d_r),
+        CotDocument::MapItem(d) => (d.d_v, d.d_c, d.d_r),
+        CotDocument::Unknown(u) => (
+            u.raw.get("d_v").and_then(Value::as_u64).unwrap_or(0) as u32,
+            u.raw.get("d_c").and_then(Value::as_i64).unwrap_or(0),
+            u.raw
+                .get("d_r")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        ),
+    }
+}
+
+fn content_hash(doc: &CotDocument) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    doc.to_flattened_json().to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// An in-memory DAG of document revisions, keyed by monotonic [`SyncDagToken`]s.
+#[derive(Debug, Clone, Default)]
+pub struct SyncDag {
+    nodes: Vec<VersionNode>,
+    latest_by_id: HashMap<String, usize>,
+    clock: u64,
+    /// Tokens at or before this floor are no longer guaranteed a complete
+    /// diff; set by [`SyncDag::compact`].
+    oldest_token: SyncDagToken,
+}
+
+impl SyncDag {
+    /// Creates an empty DAG.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `doc` as a new revision, returning its assigned token.
+    ///
+    /// The token is this DAG's Lamport clock advanced past both its own
+    /// previous value and `doc`'s own `d_v`/`d_c` stamp, then incremented —
+    /// so applying a revision a peer has already stamped with a higher
+    /// `d_v`/`d_c` can never produce a token smaller than one already handed
+    /// out.
+    pub fn apply(&mut self, doc: &CotDocument) -> SyncDagToken {
+        let id = doc_id(doc);
+        let (d_v, d_c, d_r) = version_fields(doc);
+        let doc_stamp = (d_v as u64) << 32 | (d_c.max(0) as u64 & 0xFFFF_FFFF);
+
+        self.clock = self.clock.max(doc_stamp) + 1;
+        let token = SyncDagToken(self.clock);
+
+        let parent = self.latest_by_id.get(&id).map(|&idx| self.nodes[idx].token);
+        self.nodes.push(VersionNode {
+            token,
+            parent,
+            doc_id: id.clone(),
+            tombstone: d_r,
+            content_hash: content_hash(doc),
+        });
+        self.latest_by_id.insert(id, self.nodes.len() - 1);
+
+        token
+    }
+
+    /// Returns the latest recorded node for `doc_id`, if any.
+    pub fn latest(&self, doc_id: &str) -> Option<&VersionNode> {
+        self.latest_by_id.get(doc_id).map(|&idx| &self.nodes[idx])
+    }
+
+    /// Classifies every document id with a node `token > since` as
+    /// added/updated/removed.
+    ///
+    /// A document is `removed` if its latest node overall is a tombstone,
+    /// regardless of whether it was re-added afterwards and tombstoned again
+    /// (only the net latest state matters). Otherwise it's `added` if no
+    /// non-tombstone node existed before `since`, or `updated` if one did.
+    /// `changes_since` from the DAG's current token always returns empty.
+    ///
+    /// Returns [`CotError::TokenExpired`] if `since` is at or before the
+    /// retention floor raised by a prior [`SyncDag::compact`] call, since the
+    /// history needed to compute a complete diff may no longer be present.
+    pub fn changes_since(&self, since: SyncDagToken) -> Result<SyncChange, CotError> {
+        if self.oldest_token > SyncDagToken::initial() && since <= self.oldest_token {
+            return Err(CotError::TokenExpired);
+        }
+
+        let mut touched_ids: Vec<String> = Vec::new();
+        let mut seen = HashMap::new();
+        for node in &self.nodes {
+            if node.token > since && !seen.contains_key(&node.doc_id) {
+                seen.insert(node.doc_id.clone(), ());
+                touched_ids.push(node.doc_id.clone());
+            }
+        }
+
+        let mut change = SyncChange::default();
+        for id in touched_ids {
+            let latest = self
+                .latest(&id)
+                .expect("every touched id has at least one node");
+            if latest.tombstone {
+                change.removed.push(id);
+                continue;
+            }
+
+            let existed_before_since = self
+                .history(&id)
+                .any(|node| node.token <= since && !node.tombstone);
+            if existed_before_since {
+                change.updated.push(id);
+            } else {
+                change.added.push(id);
+            }
+        }
+
+        Ok(change)
+    }
+
+    /// Drops history that can no longer affect a future diff and raises the
+    /// retention floor to `retain_from`.
+    ///
+    /// Only fully-resolved deletions are eligible: a document id whose latest
+    /// node is a tombstone recorded at or before `retain_from` has nothing
+    /// left to tell a future caller beyond "it's gone", so its whole chain is
+    /// dropped. Ids that are still live, or were tombstoned only after
+    /// `retain_from`, keep their full history. After this call,
+    /// [`SyncDag::changes_since`] returns [`CotError::TokenExpired`] for any
+    /// token at or before `retain_from`, since a diff from such a token can
+    /// no longer distinguish "never existed" from "existed and was pruned".
+    pub fn compact(&mut self, retain_from: SyncDagToken) {
+        let resolved_deletions: Vec<String> = self
+            .latest_by_id
+            .iter()
+            .filter_map(|(id, &idx)| {
+                let node = &self.nodes[idx];
+                (node.tombstone && node.token <= retain_from).then(|| id.clone())
+            })
+            .collect();
+
+        if !resolved_deletions.is_empty() {
+            let drop: std::collections::HashSet<String> = resolved_deletions.into_iter().collect();
+            self.nodes.retain(|node| !drop.contains(&node.doc_id));
+            self.latest_by_id.clear();
+            for (idx, node) in self.nodes.iter().enumerate() {
+                self.latest_by_id.insert(node.doc_id.clone(), idx);
+            }
+        }
+
+        if retain_from > self.oldest_token {
+            self.oldest_token = retain_from;
+        }
+    }
+
+    /// This DAG's current frontier — always a single token, since every
+    /// revision [`SyncDag::apply`]s and every history [`SyncDag::merge`]s in
+    /// is folded onto this one instance's own Lamport clock. Present mainly
+    /// so a caller can persist a [`SyncDagHeads`] uniformly regardless of
+    /// whether it came from a single DAG or a [`SyncDag::merge`] result.
+    pub fn heads(&self) -> SyncDagHeads {
+        SyncDagHeads::single(SyncDagToken(self.clock))
+    }
+
+    /// Like [`SyncDag::changes_since`], but accepts every concurrent head a
+    /// caller may be holding after watching more than one [`SyncDag`] before
+    /// they were [`SyncDag::merge`]d.
+    ///
+    /// Uses the oldest of `since`'s tokens as the floor: conservative (a
+    /// document already known from a *later* head may be reported again),
+    /// but never misses a change, which matches the rest of this module's
+    /// bias toward "duplicate over dropped".
+    pub fn changes_since_heads(&self, since: &SyncDagHeads) -> Result<SyncChange, CotError> {
+        let floor = since
+            .tokens()
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or_else(SyncDagToken::initial);
+        self.changes_since(floor)
+    }
+
+    /// Folds `other`'s entire revision history into `self`, as if every node
+    /// it ever recorded had been [`SyncDag::apply`]'d here directly.
+    ///
+    /// `other`'s nodes are replayed in their original (append) order onto
+    /// `self`'s own Lamport clock, so every intermediate revision survives
+    /// for [`SyncDag::changes_since`] to see, even for a document id that
+    /// only `self` or only `other` ever touched. A node identical to one
+    /// `self` already has for the same doc id — same `content_hash`, most
+    /// commonly a shared ancestor from before `other` was cloned off of
+    /// `self` and diverged — is skipped rather than replayed with a fresh
+    /// token: replaying it anyway would make [`SyncDag::changes_since`]
+    /// think that id changed, when really nothing about it differs from
+    /// what `self` already knew. When both sides recorded a revision for the
+    /// *same* doc id since they diverged — a genuine concurrent edit neither
+    /// side's chain alone resolves — the node with the greater
+    /// [`VersionNode::content_hash`] wins and becomes `self`'s latest for
+    /// that id; either direction would be equally deterministic, "greater"
+    /// is just the fixed, arbitrary choice — the same tie-break shape
+    /// `ditto::merge`'s document-content conflict resolution uses. The
+    /// loser's chain is still appended to `self.nodes` so it's not silently
+    /// lost, just no longer reachable as anyone's latest.
+    ///
+    /// Returns the merged frontier (see [`SyncDag::heads`]).
+    pub fn merge(&mut self, other: &SyncDag) -> SyncDagHeads {
+        // Maps an `other` token to the index its re-homed (or deduplicated)
+        // node ended up at in `self.nodes`, so parent links and per-id
+        // winners can be resolved without a linear search.
+        let mut remap: HashMap<SyncDagToken, usize> = HashMap::new();
+
+        // Tracks the first node `self` has (already present, or just
+        // replayed) for a given `(doc_id, content_hash)` pair, so a node
+        // `other` replays that's identical to one `self` already has —
+        // typically a shared ancestor — reuses it instead of being
+        // duplicated under a brand-new token.
+        let mut existing: HashMap<(String, u64), usize> = HashMap::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            existing
+                .entry((node.doc_id.clone(), node.content_hash))
+                .or_insert(idx);
+        }
+
+        for node in &other.nodes {
+            let key = (node.doc_id.clone(), node.content_hash);
+            if let Some(&idx) = existing.get(&key) {
+                remap.insert(node.token, idx);
+                continue;
+            }
+
+            self.clock += 1;
+            let parent = node
+                .parent
+                .and_then(|p| remap.get(&p))
+                .map(|&idx| self.nodes[idx].token);
+            self.nodes.push(VersionNode {
+                token: SyncDagToken(self.clock),
+                parent,
+                doc_id: node.doc_id.clone(),
+                tombstone: node.tombstone,
+                content_hash: node.content_hash,
+            });
+            let new_idx = self.nodes.len() - 1;
+            remap.insert(node.token, new_idx);
+            existing.insert(key, new_idx);
+        }
+
+        for (doc_id, &other_idx) in &other.latest_by_id {
+            let Some(&remapped_idx) = remap.get(&other.nodes[other_idx].token) else {
+                continue;
+            };
+
+            let other_wins = match self.latest_by_id.get(doc_id) {
+                None => true,
+                Some(&self_idx) => {
+                    self.nodes[remapped_idx].content_hash > self.nodes[self_idx].content_hash
+                }
+            };
+            if other_wins {
+                self.latest_by_id.insert(doc_id.clone(), remapped_idx);
+            }
+        }
+
+        if other.oldest_token > self.oldest_token {
+            self.oldest_token = other.oldest_token;
+        }
+
+        self.heads()
+    }
+
+    /// Walks a document id's nodes from newest to oldest via parent pointers.
+    fn history<'a>(&'a self, doc_id: &str) -> impl Iterator<Item = &'a VersionNode> + 'a {
+        let mut next = self.latest_by_id.get(doc_id).map(|&idx| &self.nodes[idx]);
+        std::iter::from_fn(move || {
+            let current = next.take()?;
+            next = current
+                .parent
+                .and_then(|token| self.nodes.iter().find(|n| n.token == token));
+            Some(current)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cot_events::CotEvent;
+    use crate::ditto::to_ditto::cot_to_document;
+
+    fn event(uid: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: "a-f-G-U-C".to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::default(),
+            detail: "<detail><contact callsign=\"ALPHA-1\"/></detail>".to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    fn tombstoned(mut doc: CotDocument) -> CotDocument {
+        match &mut doc {
+            CotDocument::MapItem(d) => d.d_r = true,
+            _ => panic!("test fixture only produces MapItem documents"),
+        }
+        doc
+    }
+
+    #[test]
+    fn first_apply_is_added() {
+        let mut dag = SyncDag::new();
+        let doc = cot_to_document(&event("uid-1"), "peer");
+        dag.apply(&doc);
+
+        let change = dag.changes_since(SyncDagToken::initial()).unwrap();
+        assert_eq!(change.added, vec!["uid-1".to_string()]);
+        assert!(change.updated.is_empty());
+        assert!(change.removed.is_empty());
+    }
+
+    #[test]
+    fn second_apply_of_the_same_id_is_updated() {
+        let mut dag = SyncDag::new();
+        let first_token = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+
+        let change = dag.changes_since(first_token).unwrap();
+        assert_eq!(change.updated, vec!["uid-1".to_string()]);
+        assert!(change.added.is_empty());
+    }
+
+    #[test]
+    fn tombstoned_id_is_removed_even_if_first_seen() {
+        let mut dag = SyncDag::new();
+        let doc = tombstoned(cot_to_document(&event("uid-1"), "peer"));
+        dag.apply(&doc);
+
+        let change = dag.changes_since(SyncDagToken::initial()).unwrap();
+        assert_eq!(change.removed, vec!["uid-1".to_string()]);
+        assert!(change.added.is_empty());
+    }
+
+    #[test]
+    fn re_added_then_re_removed_id_nets_to_removed() {
+        let mut dag = SyncDag::new();
+        let since = SyncDagToken::initial();
+        dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        dag.apply(&tombstoned(cot_to_document(&event("uid-1"), "peer")));
+        dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        dag.apply(&tombstoned(cot_to_document(&event("uid-1"), "peer")));
+
+        let change = dag.changes_since(since).unwrap();
+        assert_eq!(change.removed, vec!["uid-1".to_string()]);
+        assert!(change.added.is_empty());
+        assert!(change.updated.is_empty());
+    }
+
+    #[test]
+    fn changes_since_the_latest_token_is_empty() {
+        let mut dag = SyncDag::new();
+        let token = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+
+        let change = dag.changes_since(token).unwrap();
+        assert!(change.added.is_empty());
+        assert!(change.updated.is_empty());
+        assert!(change.removed.is_empty());
+    }
+
+    #[test]
+    fn compact_expires_tokens_at_or_before_the_retention_point() {
+        let mut dag = SyncDag::new();
+        let since = SyncDagToken::initial();
+        let retain_from = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        dag.compact(retain_from);
+
+        assert!(matches!(
+            dag.changes_since(since),
+            Err(CotError::TokenExpired)
+        ));
+        assert!(matches!(
+            dag.changes_since(retain_from),
+            Err(CotError::TokenExpired)
+        ));
+    }
+
+    #[test]
+    fn compact_keeps_a_token_just_past_the_retention_point_valid() {
+        let mut dag = SyncDag::new();
+        let retain_from = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        let after = dag.apply(&cot_to_document(&event("uid-2"), "peer"));
+        dag.compact(retain_from);
+
+        let change = dag.changes_since(after).unwrap();
+        assert!(change.added.is_empty());
+    }
+
+    #[test]
+    fn compact_drops_history_for_ids_resolved_as_deleted_before_the_retention_point() {
+        let mut dag = SyncDag::new();
+        dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        let retain_from = dag.apply(&tombstoned(cot_to_document(&event("uid-1"), "peer")));
+        let marker = dag.apply(&cot_to_document(&event("uid-2"), "peer"));
+        dag.compact(retain_from);
+
+        assert!(dag.latest("uid-1").is_none());
+        let change = dag.changes_since(marker).unwrap();
+        assert!(change.removed.is_empty());
+        assert!(change.added.is_empty());
+    }
+
+    #[test]
+    fn compact_keeps_history_for_an_id_still_live_at_the_retention_point() {
+        let mut dag = SyncDag::new();
+        dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        let retain_from = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        dag.compact(retain_from);
+
+        assert!(dag.latest("uid-1").is_some());
+    }
+
+    #[test]
+    fn tokens_are_strictly_increasing_and_never_reused() {
+        let mut dag = SyncDag::new();
+        let a = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        let b = dag.apply(&cot_to_document(&event("uid-2"), "peer"));
+        let c = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+
+        assert!(a < b);
+        assert!(b < c);
+    }
+
+    #[test]
+    fn history_walks_a_documents_revisions_newest_first() {
+        let mut dag = SyncDag::new();
+        let first = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+        let second = dag.apply(&cot_to_document(&event("uid-1"), "peer"));
+
+        let tokens: Vec<_> = dag.history("uid-1").map(|n| n.token).collect();
+        assert_eq!(tokens, vec![second, first]);
+    }
+
+    fn with_callsign(uid: &str, callsign: &str) -> CotEvent {
+        let mut e = event(uid);
+        e.detail = format!("<detail><contact callsign=\"{callsign}\"/></detail>");
+        e
+    }
+
+    #[test]
+    fn merge_unions_changes_for_disjoint_ids() {
+        let mut dag_a = SyncDag::new();
+        dag_a.apply(&cot_to_document(&event("uid-1"), "peer-a"));
+
+        let mut dag_b = SyncDag::new();
+        dag_b.apply(&cot_to_document(&event("uid-2"), "peer-b"));
+
+        dag_a.merge(&dag_b);
+
+        let mut change = dag_a.changes_since(SyncDagToken::initial()).unwrap();
+        change.added.sort();
+        assert_eq!(change.added, vec!["uid-1".to_string(), "uid-2".to_string()]);
+        assert!(dag_a.latest("uid-1").is_some());
+        assert!(dag_a.latest("uid-2").is_some());
+    }
+
+    #[test]
+    fn merge_of_a_concurrently_edited_id_picks_the_greater_content_hash() {
+        let doc_a = cot_to_document(&with_callsign("uid-1", "ALPHA-1"), "peer-a");
+        let doc_b = cot_to_document(&with_callsign("uid-1", "BRAVO-2"), "peer-b");
+        let expected_winner = content_hash(&doc_a).max(content_hash(&doc_b));
+
+        let mut dag_a = SyncDag::new();
+        dag_a.apply(&doc_a);
+        let mut dag_b = SyncDag::new();
+        dag_b.apply(&doc_b);
+
+        dag_a.merge(&dag_b);
+
+        assert_eq!(dag_a.latest("uid-1").unwrap().content_hash, expected_winner);
+    }
+
+    #[test]
+    fn merge_of_branched_histories_unions_changes_relative_to_the_shared_ancestor() {
+        let mut dag_a = SyncDag::new();
+        let shared = dag_a.apply(&cot_to_document(&event("uid-1"), "peer"));
+        let mut dag_b = dag_a.clone();
+
+        dag_a.apply(&cot_to_document(&event("uid-2"), "peer-a"));
+        dag_b.apply(&tombstoned(cot_to_document(&event("uid-3"), "peer-b")));
+
+        dag_a.merge(&dag_b);
+
+        let mut change = dag_a.changes_since(shared).unwrap();
+        change.added.sort();
+        assert_eq!(change.added, vec!["uid-2".to_string()]);
+        assert_eq!(change.removed, vec!["uid-3".to_string()]);
+        assert!(dag_a.latest("uid-1").is_some());
+        // uid-1 is the shared ancestor and was untouched by either branch,
+        // so merging dag_b back in must not make it look updated.
+        assert!(change.updated.is_empty());
+    }
+
+    #[test]
+    fn changes_since_heads_uses_the_oldest_of_the_given_heads_as_the_floor() {
+        let mut dag_a = SyncDag::new();
+        let shared = dag_a.apply(&cot_to_document(&event("uid-1"), "peer"));
+        let mut dag_b = dag_a.clone();
+
+        dag_a.apply(&cot_to_document(&event("uid-2"), "peer-a"));
+        dag_b.apply(&cot_to_document(&event("uid-3"), "peer-b"));
+
+        dag_a.merge(&dag_b);
+
+        let change = dag_a.changes_since_heads(&SyncDagHeads::single(shared)).unwrap();
+        assert!(change.added.contains(&"uid-2".to_string()));
+        assert!(change.added.contains(&"uid-3".to_string()));
+        assert!(!change.added.contains(&"uid-1".to_string()));
+    }
+
+    #[test]
+    fn sync_dag_heads_round_trip_through_encode_decode() {
+        let heads = SyncDagHeads::single(SyncDagToken(7));
+        assert_eq!(SyncDagHeads::decode(&heads.encode()).unwrap(), heads);
+
+        let multi = SyncDagHeads::decode("3,7").unwrap();
+        assert_eq!(multi.tokens(), &[SyncDagToken(3), SyncDagToken(7)]);
+
+        assert!(SyncDagHeads::decode("not-a-token").is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! Schema-level discriminators for [`CotDocument`](super::CotDocument) variants.
+//!
+//! [`CotDocument`] is `#[serde(untagged)]`, so deserialization and the
+//! generated `JsonSchema` both have to guess a variant by field shape. The
+//! `w` field (the CoT event type) is already stable across every variant and
+//! already drives [`CotDocument::from_json_str`](super::CotDocument::from_json_str)'s
+//! routing; [`TaggedSchema`] instead bakes a fixed per-variant tag (`"chat"`,
+//! `"map_item"`, ...) into each variant's generated schema as a
+//! `const`-valued `d_t` property, turning the
+//! emitted `anyOf` into a proper `oneOf` with `const`-tagged branches.
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use schemars::JsonSchema;
+
+/// A [`JsonSchema`] type that carries a compile-time constant discriminator
+/// tag, used to route [`CotDocument`](super::CotDocument) variants without
+/// relying on `String::contains` substring matching on `w`.
+pub trait TaggedSchema: JsonSchema {
+    /// The discriminator tag for this variant, e.g. `"chat"` or `"map_item"`.
+    fn discriminator() -> &'static str;
+
+    /// Builds this type's schema with its discriminator baked in as a
+    /// `const`-valued `d_t` property, so the schema alone is enough to tell
+    /// variants apart.
+    fn tagged_schema(gen: &mut SchemaGenerator) -> Schema {
+        let schema = <Self as JsonSchema>::json_schema(gen);
+        let mut schema_object = match schema {
+            Schema::Object(obj) => obj,
+            Schema::Bool(_) => SchemaObject::default(),
+        };
+
+        let mut tag_schema = SchemaObject {
+            instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::String))),
+            ..Default::default()
+        };
+        tag_schema.const_value = Some(serde_json::Value::String(Self::discriminator().to_string()));
+
+        if let Some(object) = &mut schema_object.object {
+            object
+                .properties
+                .insert("d_t".to_string(), Schema::Object(tag_schema));
+            object.required.insert("d_t".to_string());
+        }
+
+        Schema::Object(schema_object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(JsonSchema)]
+    struct Dummy {
+        #[allow(dead_code)]
+        id: String,
+    }
+
+    impl TaggedSchema for Dummy {
+        fn discriminator() -> &'static str {
+            "dummy"
+        }
+    }
+
+    #[test]
+    fn tagged_schema_sets_const_discriminator() {
+        let mut gen = SchemaGenerator::default();
+        let schema = Dummy::tagged_schema(&mut gen);
+        let Schema::Object(obj) = schema else {
+            panic!("expected an object schema");
+        };
+        let object = obj.object.expect("schema should describe an object");
+        let d_t = object.properties.get("d_t").expect("d_t property");
+        let Schema::Object(d_t_obj) = d_t else {
+            panic!("expected an object schema for d_t");
+        };
+        assert_eq!(
+            d_t_obj.const_value,
+            Some(serde_json::Value::String("dummy".to_string()))
+        );
+        assert!(object.required.contains("d_t"));
+    }
+}
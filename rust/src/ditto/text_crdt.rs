@@ -0,0 +1,279 @@
+//! Character-level CRDT merge for free-text detail fields (`<remarks>`, chat
+//! message bodies) that two offline peers may both edit.
+//!
+//! [`detail_merge`](super::detail_merge)'s [`CausalStamp`](super::detail_merge::CausalStamp)
+//! already buys per-field last-writer-wins merge for scalar `r_*` entries,
+//! but LWW is the wrong answer for a text field two peers both *edited*
+//! rather than both *replaced*: Peer A appending a line and Peer B fixing a
+//! typo elsewhere in the same `<remarks>` shouldn't mean one peer's edit
+//! wholesale destroys the other's. [`TextCrdt`] is a minimal replicated
+//! growable array (RGA): every character is inserted immediately after a
+//! specific existing character (or at the start), tagged with a
+//! [`CausalStamp`] that gives it a total order against concurrent inserts at
+//! the same position, and a delete only tombstones an existing character
+//! rather than removing it outright, so [`TextCrdt::merge`] never has to
+//! guess whether a character a peer doesn't have was deleted there or just
+//! never arrived.
+//!
+//! This is opt-in per field: a [`TextCrdt`] is a plain value a caller stashes
+//! in an `r` entry alongside the plain-string field it backs (by convention,
+//! under [`crdt_key`]'s name), so a reader that doesn't know about it still
+//! sees an ordinary string and nothing here changes the default LWW path for
+//! fields that don't have one. [`super::merge`] upgrades a field from LWW to
+//! character-level merge exactly when both sides carry that companion entry.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::ditto::detail_merge::CausalStamp;
+
+/// The suffix [`crdt_key`] appends to a field name; [`super::merge`] strips
+/// it back off to find the plain-string field a merged log should update.
+pub(crate) const TEXT_CRDT_SUFFIX: &str = "__text_crdt";
+
+/// The `r` key a text field's [`TextCrdt`] log is stored under, alongside the
+/// plain-string field `field` itself (e.g. `crdt_key("remarks")` is
+/// `"remarks__text_crdt"` next to the existing `"remarks"` entry).
+pub fn crdt_key(field: &str) -> String {
+    format!("{field}{TEXT_CRDT_SUFFIX}")
+}
+
+/// One character in a [`TextCrdt`]'s insert log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Element {
+    id: CausalStamp,
+    ch: char,
+    /// The element this one was inserted immediately after, or `None` for
+    /// "at the start of the text".
+    origin: Option<CausalStamp>,
+    tombstoned: bool,
+}
+
+/// A character-level CRDT for a single free-text field, mergeable across
+/// peers without losing either side's concurrent edits. See the module
+/// documentation for why this exists and how it's meant to be stored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextCrdt {
+    elements: Vec<Element>,
+}
+
+impl TextCrdt {
+    /// Builds a [`TextCrdt`] seeded with `text`, as if `peer_id` typed it in
+    /// one sitting starting at `lamport_counter`. Returns the CRDT and the
+    /// next unused counter value, so a caller making further edits via
+    /// [`Self::set_text`] keeps counters strictly increasing.
+    pub fn from_str(text: &str, peer_id: &str, lamport_counter: u64) -> (Self, u64) {
+        let mut crdt = TextCrdt::default();
+        let counter = crdt.append(text, peer_id, lamport_counter, None);
+        (crdt, counter)
+    }
+
+    fn append(
+        &mut self,
+        text: &str,
+        peer_id: &str,
+        lamport_counter: u64,
+        mut origin: Option<CausalStamp>,
+    ) -> u64 {
+        let mut counter = lamport_counter;
+        for ch in text.chars() {
+            let id = CausalStamp { lamport_counter: counter, peer_id: peer_id.to_string() };
+            self.elements.push(Element { id: id.clone(), ch, origin, tombstoned: false });
+            origin = Some(id);
+            counter += 1;
+        }
+        counter
+    }
+
+    /// Materializes the live (non-tombstoned) text, in causal order.
+    pub fn materialize(&self) -> String {
+        causal_order(&self.elements).into_iter().filter(|e| !e.tombstoned).map(|e| e.ch).collect()
+    }
+
+    /// Replaces this field's text with `new_text`, diffing against the
+    /// current materialized value on common prefix/suffix so only the
+    /// actually-changed middle section becomes inserts/deletes — an edit
+    /// that only appends, for instance, produces pure inserts with no
+    /// spurious delete-then-reinsert of the unchanged prefix. Returns the
+    /// next unused counter value.
+    pub fn set_text(&mut self, new_text: &str, peer_id: &str, lamport_counter: u64) -> u64 {
+        let live: Vec<CausalStamp> = causal_order(&self.elements)
+            .into_iter()
+            .filter(|e| !e.tombstoned)
+            .map(|e| e.id.clone())
+            .collect();
+        let old: Vec<char> = {
+            let by_id: BTreeMap<&CausalStamp, char> =
+                self.elements.iter().map(|e| (&e.id, e.ch)).collect();
+            live.iter().map(|id| by_id[id]).collect()
+        };
+        let new: Vec<char> = new_text.chars().collect();
+
+        let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+        let max_suffix = (old.len() - prefix).min(new.len() - prefix);
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        let removed_range = prefix..(old.len() - suffix);
+        let inserted: String = new[prefix..(new.len() - suffix)].iter().collect();
+
+        for id in &live[removed_range.clone()] {
+            if let Some(element) = self.elements.iter_mut().find(|e| &e.id == id) {
+                element.tombstoned = true;
+            }
+        }
+
+        let origin = removed_range.start.checked_sub(1).and_then(|i| live.get(i)).cloned();
+        self.append(&inserted, peer_id, lamport_counter, origin)
+    }
+
+    /// Merges `self` with `other`: the union of both sides' elements (by id,
+    /// so shared history converges without duplication), with a tombstone
+    /// from either side winning — a delete anywhere always takes effect.
+    /// Ordering follows each element's `origin` pointer, so the result
+    /// doesn't depend on which side is `self`.
+    pub fn merge(&self, other: &Self) -> Self {
+        let mut by_id: BTreeMap<CausalStamp, Element> =
+            self.elements.iter().map(|e| (e.id.clone(), e.clone())).collect();
+
+        for element in &other.elements {
+            by_id
+                .entry(element.id.clone())
+                .and_modify(|existing| existing.tombstoned |= element.tombstoned)
+                .or_insert_with(|| element.clone());
+        }
+
+        TextCrdt { elements: by_id.into_values().collect() }
+    }
+
+    /// Serializes to the JSON object a [`crdt_key`] `r` entry holds.
+    pub fn to_json_map(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+
+    /// The inverse of [`Self::to_json_map`], tolerant of a missing or
+    /// malformed entry (treated as "no CRDT log recorded yet").
+    pub fn from_json_map(map: &Map<String, Value>) -> Self {
+        serde_json::from_value(Value::Object(map.clone())).unwrap_or_default()
+    }
+}
+
+/// Orders `elements` causally: each element appears immediately after its
+/// `origin`, with concurrent inserts at the same origin (same `origin`,
+/// different `id`) ordered by descending id so every peer resolving the same
+/// set of elements picks the same order.
+fn causal_order(elements: &[Element]) -> Vec<&Element> {
+    let mut children: BTreeMap<Option<CausalStamp>, Vec<&Element>> = BTreeMap::new();
+    for element in elements {
+        children.entry(element.origin.clone()).or_default().push(element);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by(|a, b| b.id.cmp(&a.id));
+    }
+
+    fn visit<'a>(
+        origin: Option<&CausalStamp>,
+        children: &BTreeMap<Option<CausalStamp>, Vec<&'a Element>>,
+        out: &mut Vec<&'a Element>,
+    ) {
+        if let Some(siblings) = children.get(&origin.cloned()) {
+            for element in siblings {
+                out.push(element);
+                visit(Some(&element.id), children, out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    visit(None, &children, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_from_str_and_materialize() {
+        let (crdt, next) = TextCrdt::from_str("hello", "peer-a", 0);
+        assert_eq!(crdt.materialize(), "hello");
+        assert_eq!(next, 5);
+    }
+
+    #[test]
+    fn set_text_only_touches_the_changed_middle() {
+        let (mut crdt, next) = TextCrdt::from_str("hello world", "peer-a", 0);
+        crdt.set_text("hello there world", "peer-a", next);
+        assert_eq!(crdt.materialize(), "hello there world");
+    }
+
+    #[test]
+    fn independent_appends_from_both_peers_both_survive() {
+        let (base, next) = TextCrdt::from_str("hello", "peer-a", 0);
+
+        let mut local = base.clone();
+        local.set_text("hello there", "peer-a", next);
+
+        let mut remote = base.clone();
+        remote.set_text("hello world", "peer-b", next);
+
+        let merged = local.merge(&remote);
+        let text = merged.materialize();
+        assert!(text.starts_with("hello"));
+        assert!(text.contains("there") && text.contains("world"));
+        // Both peers' concurrent appends survive in the union, not just one.
+        assert_ne!(text, "hello");
+    }
+
+    #[test]
+    fn a_delete_on_one_side_removes_the_character_after_merge() {
+        let (base, next) = TextCrdt::from_str("hello", "peer-a", 0);
+
+        let mut local = base.clone();
+        local.set_text("hell", "peer-a", next); // deletes the trailing "o"
+
+        let remote = base.clone(); // never saw the delete
+
+        let merged = local.merge(&remote);
+        assert_eq!(merged.materialize(), "hell");
+    }
+
+    #[test]
+    fn merge_is_commutative() {
+        let (base, next) = TextCrdt::from_str("hello", "peer-a", 0);
+
+        let mut local = base.clone();
+        local.set_text("hello there", "peer-a", next);
+
+        let mut remote = base.clone();
+        remote.set_text("hello world", "peer-b", next);
+
+        let a_then_b = local.merge(&remote);
+        let b_then_a = remote.merge(&local);
+        assert_eq!(a_then_b, b_then_a);
+    }
+
+    #[test]
+    fn merge_is_idempotent() {
+        let (crdt, _) = TextCrdt::from_str("hello", "peer-a", 0);
+        let merged = crdt.merge(&crdt);
+        assert_eq!(merged.materialize(), "hello");
+    }
+
+    #[test]
+    fn json_round_trips_through_to_and_from_map() {
+        let (crdt, _) = TextCrdt::from_str("hello", "peer-a", 0);
+        let map = crdt.to_json_map();
+        assert_eq!(TextCrdt::from_json_map(&map), crdt);
+    }
+}
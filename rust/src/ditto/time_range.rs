@@ -0,0 +1,137 @@
+//! Time-range filtering for CoT documents, mirroring a CalDAV `calendar-query`
+//! time-range filter.
+//!
+//! A CoT event is considered "live" over the inclusive interval
+//! `[start (n), stale (o)]`. Given a caller-supplied `[range_start, range_end]`
+//! window (also in microseconds since the Unix epoch, matching the flattened
+//! `n`/`o` fields), a document overlaps the range when
+//! `start <= range_end && stale >= range_start`.
+
+use crate::ditto::CotDocument;
+
+/// Real ATAK feeds sometimes mark a track as never going stale by setting an
+/// absurdly large `stale` timestamp (on the order of `9999999` seconds, i.e.
+/// far beyond any plausible microsecond range bound). Anything at or beyond
+/// this threshold is treated as `+∞` so it always matches a range's upper
+/// bound.
+pub const OPEN_ENDED_STALE_THRESHOLD_MICROS: f64 = 9_999_999.0 * 1_000_000.0;
+
+/// Returns whether an event whose validity window is `[start, stale]`
+/// (both in microseconds since the Unix epoch) overlaps `[range_start, range_end]`.
+///
+/// Both bounds are inclusive, so a momentary event (`start == stale`) landing
+/// exactly on a range boundary still matches. A missing, zero, or
+/// [`OPEN_ENDED_STALE_THRESHOLD_MICROS`]-or-beyond `stale` is treated as
+/// open-ended (`+∞`) and always satisfies the upper bound.
+pub fn overlaps(start: f64, stale: Option<f64>, range_start: f64, range_end: f64) -> bool {
+    let stale_is_open_ended = match stale {
+        None => true,
+        Some(s) => s <= 0.0 || s >= OPEN_ENDED_STALE_THRESHOLD_MICROS,
+    };
+
+    let stale_satisfies_range = stale_is_open_ended || stale.unwrap() >= range_start;
+    start <= range_end && stale_satisfies_range
+}
+
+/// Filters `documents` down to those whose `[n, o]` validity window overlaps
+/// `[range_start, range_end]` (both in microseconds since the Unix epoch).
+///
+/// Documents missing an `n` (start) field never match, since there is no
+/// window to compare against.
+pub fn filter_in_range<'a>(
+    documents: &'a [CotDocument],
+    range_start: f64,
+    range_end: f64,
+) -> Vec<&'a CotDocument> {
+    documents
+        .iter()
+        .filter(|doc| match start_and_stale(doc) {
+            Some((start, stale)) => overlaps(start, stale, range_start, range_end),
+            None => false,
+        })
+        .collect()
+}
+
+/// Extracts the `(n, o)` start/stale microsecond pair from any [`CotDocument`] variant.
+///
+/// `pub(crate)` so [`query`](super::query) can reuse the same extraction
+/// instead of re-deriving it per-variant.
+pub(crate) fn start_and_stale(doc: &CotDocument) -> Option<(f64, Option<f64>)> {
+    let (n, o) = match doc {
+        CotDocument::Api(d) => (d.n, d.o),
+        CotDocument::Chat(d) => (d.n, d.o),
+        CotDocument::File(d) => (d.n, d.o),
+        CotDocument::Generic(d) => (d.n, d.o),
+        CotDocument::MapItem(d) => (d.n, d.o),
+        CotDocument::Unknown(u) => (u.start_micros(), u.stale_micros()),
+    };
+    n.map(|start| (start, o))
+}
+
+/// Extracts the `w` (CoT event-type) field from any [`CotDocument`] variant.
+///
+/// `pub(crate)` so [`query`](super::query) can reuse the same extraction
+/// instead of re-deriving it per-variant, mirroring [`start_and_stale`].
+pub(crate) fn event_type(doc: &CotDocument) -> Option<&str> {
+    match doc {
+        CotDocument::Api(d) => Some(d.w.as_str()),
+        CotDocument::Chat(d) => Some(d.w.as_str()),
+        CotDocument::File(d) => Some(d.w.as_str()),
+        CotDocument::Generic(d) => Some(d.w.as_str()),
+        CotDocument::MapItem(d) => Some(d.w.as_str()),
+        CotDocument::Unknown(u) => u.event_type(),
+    }
+}
+
+/// Builds the DQL `WHERE` clause fragment selecting documents whose `n`/`o`
+/// window overlaps `[range_start, range_end]` (both in microseconds since the
+/// Unix epoch), for pushing this filter into `store.execute_v2` instead of
+/// filtering client-side after a full collection scan.
+///
+/// Mirrors [`overlaps`]: `o` is excluded from the upper-bound check when it is
+/// missing or at/above [`OPEN_ENDED_STALE_THRESHOLD_MICROS`].
+pub fn time_range_where_clause(range_start: f64, range_end: f64) -> String {
+    format!(
+        "n <= {range_end} AND (o IS NULL OR o <= 0 OR o >= {OPEN_ENDED_STALE_THRESHOLD_MICROS} OR o >= {range_start})"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn momentary_event_on_boundary_matches() {
+        assert!(overlaps(100.0, Some(100.0), 100.0, 200.0));
+        assert!(overlaps(100.0, Some(100.0), 0.0, 100.0));
+    }
+
+    #[test]
+    fn non_overlapping_event_does_not_match() {
+        assert!(!overlaps(300.0, Some(400.0), 100.0, 200.0));
+        assert!(!overlaps(100.0, Some(150.0), 200.0, 300.0));
+    }
+
+    #[test]
+    fn missing_or_zero_stale_is_open_ended() {
+        assert!(overlaps(100.0, None, 500.0, 600.0));
+        assert!(overlaps(100.0, Some(0.0), 500.0, 600.0));
+    }
+
+    #[test]
+    fn far_future_sentinel_stale_is_open_ended() {
+        assert!(overlaps(
+            100.0,
+            Some(OPEN_ENDED_STALE_THRESHOLD_MICROS),
+            500.0,
+            600.0
+        ));
+    }
+
+    #[test]
+    fn where_clause_contains_both_bounds() {
+        let clause = time_range_where_clause(100.0, 200.0);
+        assert!(clause.contains("n <= 200"));
+        assert!(clause.contains("o >= 100"));
+    }
+}
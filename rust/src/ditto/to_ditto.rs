@@ -6,9 +6,16 @@
 use crate::cot_events::CotEvent;
 use crate::detail_parser::parse_detail_section;
 use crate::ditto::r_field_flattening::flatten_document_r_field;
+use crate::ditto::tagged_schema::TaggedSchema;
+use crate::ditto::transformer::TransformerRegistry;
+use crate::ditto::version_vector::{VersionVector, VERSION_VECTOR_KEY};
+use crate::error::{CotConversionError, CotError};
 
 use anyhow;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{Schema, SchemaObject, SubschemaValidation};
 use serde::{Deserialize, Serialize};
+use serde_json::value::to_raw_value;
 use serde_json::Value;
 use std::collections::HashMap;
 // No unused imports remaining
@@ -71,81 +78,285 @@ fn extract_callsign(extras: &HashMap<String, Value>) -> Option<String> {
     None
 }
 
-/// Convert a CoT event to the appropriate Ditto document type
+/// Stashes the event's originating UTC offset into a parsed detail map under
+/// the reserved `tz_offset_secs` key, mirroring `from_ditto_util`'s reverse
+/// lookup of that key out of a document's `r` field. No-op if the event
+/// didn't carry one.
+fn insert_tz_offset(detail_map: &mut HashMap<String, Value>, tz_offset_secs: Option<i32>) {
+    if let Some(secs) = tz_offset_secs {
+        detail_map.insert("tz_offset_secs".to_string(), Value::from(secs));
+    }
+}
+
+/// Stashes a freshly created document's initial per-peer
+/// [`VersionVector`] into a parsed detail map under the reserved
+/// [`VERSION_VECTOR_KEY`], so [`CotDocument::merge`](crate::ditto::CotDocument::merge)
+/// can later tell a causal update from a concurrent one instead of relying
+/// on `d_v` alone. A document built this way starts at `{peer_key: 1}`; a
+/// later local edit is expected to call
+/// [`CotDocument::bump_version`](crate::ditto::CotDocument::bump_version)
+/// rather than re-deriving the vector from scratch.
+fn insert_version_vector(detail_map: &mut HashMap<String, Value>, peer_key: &str) {
+    let vector = VersionVector::initial(peer_key);
+    detail_map.insert(VERSION_VECTOR_KEY.to_string(), Value::Object(vector.to_json_map()));
+}
+
+/// Convert a CoT event to the appropriate Ditto document type.
+///
+/// Dispatch is delegated to a default [`TransformerRegistry`]; see
+/// [`transformer`](super::transformer) to register a custom
+/// [`CotTransformer`](super::transformer::CotTransformer) for a type this
+/// crate doesn't already handle.
 pub fn cot_to_document(event: &CotEvent, peer_key: &str) -> CotDocument {
-    let event_type = &event.event_type;
-
-    if event_type == "a-u-emergency-g" {
-        // Handle emergency events
-        CotDocument::Api(transform_emergency_event(event, peer_key))
-    } else if event_type.contains("b-t-f") || event_type.contains("chat") {
-        // Handle chat events
-        match transform_chat_event(event, peer_key) {
-            Some(chat_doc) => CotDocument::Chat(chat_doc),
-            None => CotDocument::Generic(transform_generic_event(event, peer_key)),
+    TransformerRegistry::with_builtins().transform(event, peer_key)
+}
+
+/// The largest integer an `f64` can represent exactly (2^53 - 1); beyond
+/// this, `as f64` conversions used throughout this module's field mapping
+/// silently lose precision rather than erroring.
+const MAX_SAFE_TIMESTAMP_MICROS: i64 = 9_007_199_254_740_991;
+
+/// Checks that `event.detail` has the shape [`transform_chat_event`] expects
+/// (a `<remarks>` element carrying the chat text), distinguishing "no
+/// message at all" from "a `<remarks>` element is present but empty".
+fn validate_chat_detail(detail: &str) -> Result<(), CotConversionError> {
+    let detail_map = parse_detail_section(detail);
+    match parse_chat_detail(&detail_map).message {
+        None => Err(CotConversionError::MissingRequiredDetail("remarks")),
+        Some(message) if message.is_empty() => Err(CotConversionError::MalformedChatDetail),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Fallible counterpart to [`cot_to_document`]: validates coordinates,
+/// timestamps, and (for chat events) detail shape up front, surfacing a
+/// [`CotConversionError`] instead of letting [`cot_to_document`]'s
+/// field-level fallbacks (`unwrap_or_default()`, `as_f64().unwrap_or(0.0)`,
+/// and similar) silently turn bad input into a wrong-but-valid-looking
+/// document.
+///
+/// [`cot_to_document`] and the rest of this module's infallible conversion
+/// functions are unchanged: they remain the lenient wrappers for callers who
+/// would rather get a best-effort document than an error.
+pub fn try_cot_to_document(
+    event: &CotEvent,
+    peer_key: &str,
+) -> Result<CotDocument, CotConversionError> {
+    for (field, value) in [
+        ("lat", event.point.lat),
+        ("lon", event.point.lon),
+        ("hae", event.point.hae),
+        ("ce", event.point.ce),
+        ("le", event.point.le),
+    ] {
+        if !value.is_finite() {
+            return Err(CotConversionError::NonFiniteCoordinate { field, value });
         }
-    } else if event_type.contains("a-u-r-loc-g")
-        || event_type.contains("a-f-G-U-C")
-        || event_type.contains("a-f-G-U")
-        || event_type.contains("a-f-G-U-I")
-        || event_type.contains("a-f-G-U-T")
-        || event_type.contains("a-f-S-C-U")
-        || event_type.contains("a-f-A-M-F-Q")
-        || event_type.contains("a-u-S")
-        || event_type.contains("a-u-A")
-        || event_type.contains("a-u-G")
-    {
-        // Handle location update events
-        CotDocument::MapItem(transform_location_event(event, peer_key))
-    } else if event_type.contains("file") || event_type.contains("attachment") {
-        // Handle file events
-        CotDocument::File(transform_file_event(event, peer_key))
-    } else {
-        // Fall back to generic document for all other event types
-        CotDocument::Generic(transform_generic_event(event, peer_key))
     }
+
+    for (field, micros) in [
+        ("time", event.time.timestamp_micros()),
+        ("start", event.start.timestamp_micros()),
+        ("stale", event.stale.timestamp_micros()),
+    ] {
+        if micros.unsigned_abs() > MAX_SAFE_TIMESTAMP_MICROS as u64 {
+            return Err(CotConversionError::TimestampOutOfRange { field });
+        }
+    }
+
+    if event.event_type.contains("b-t-f") || event.event_type.contains("chat") {
+        validate_chat_detail(&event.detail)?;
+    }
+
+    Ok(cot_to_document(event, peer_key))
 }
 
-/// Convert a CoT event to a flattened Ditto document for DQL compatibility
-pub fn cot_to_flattened_document(event: &CotEvent, peer_key: &str) -> Value {
-    let event_type = &event.event_type;
-
-    if event_type == "a-u-emergency-g" {
-        // Handle emergency events
-        transform_emergency_event_flattened(event, peer_key)
-    } else if event_type.contains("b-t-f") || event_type.contains("chat") {
-        // Handle chat events
-        match transform_chat_event_flattened(event, peer_key) {
-            Some(chat_doc) => chat_doc,
-            None => transform_generic_event_flattened(event, peer_key),
+/// Like [`try_cot_to_document`], but accumulates every missing or invalid
+/// field instead of stopping at the first, so a caller hand-authoring or
+/// repairing CoT can report every problem in one pass instead of fixing
+/// fields one error at a time. Returns the single underlying error when
+/// exactly one check fails, or [`CotError::Multiple`] when more than one
+/// does.
+pub fn cot_to_document_checked(
+    event: &CotEvent,
+    peer_key: &str,
+) -> Result<CotDocument, CotError> {
+    let mut errors = Vec::new();
+
+    if event.uid.is_empty() {
+        errors.push(CotError::MissingField("uid".to_string()));
+    }
+    if event.event_type.is_empty() {
+        errors.push(CotError::MissingField("type".to_string()));
+    }
+
+    for (field, value) in [
+        ("lat", event.point.lat),
+        ("lon", event.point.lon),
+        ("hae", event.point.hae),
+        ("ce", event.point.ce),
+        ("le", event.point.le),
+    ] {
+        if !value.is_finite() {
+            errors.push(CotError::InvalidFormat(format!(
+                "field '{field}' is not a finite number: {value}"
+            )));
         }
-    } else if event_type.contains("a-u-r-loc-g")
-        || event_type.contains("a-f-G-U-C")
-        || event_type.contains("a-f-G-U")
-        || event_type.contains("a-f-G-U-I")
-        || event_type.contains("a-f-G-U-T")
-        || event_type.contains("a-f-S-C-U")
-        || event_type.contains("a-f-A-M-F-Q")
-        || event_type.contains("a-u-S")
-        || event_type.contains("a-u-A")
-        || event_type.contains("a-u-G")
-    {
-        // Handle location update events
-        transform_location_event_flattened(event, peer_key)
-    } else if event_type.contains("file") || event_type.contains("attachment") {
-        // Handle file events
-        transform_file_event_flattened(event, peer_key)
+    }
+
+    for (field, micros) in [
+        ("time", event.time.timestamp_micros()),
+        ("start", event.start.timestamp_micros()),
+        ("stale", event.stale.timestamp_micros()),
+    ] {
+        if micros.unsigned_abs() > MAX_SAFE_TIMESTAMP_MICROS as u64 {
+            errors.push(CotError::InvalidFormat(format!(
+                "field '{field}' is out of the range a Ditto timestamp can represent exactly"
+            )));
+        }
+    }
+
+    if event.event_type.contains("b-t-f") || event.event_type.contains("chat") {
+        if let Err(e) = validate_chat_detail(&event.detail) {
+            errors.push(CotError::InvalidFormat(e.to_string()));
+        }
+    }
+
+    match errors.len() {
+        0 => Ok(cot_to_document(event, peer_key)),
+        1 => Err(errors.remove(0)),
+        _ => Err(CotError::Multiple(errors)),
+    }
+}
+
+/// Re-transforms `event` against an existing `previous` document for the same
+/// id instead of clobbering it outright.
+///
+/// Every field of `previous` not present on the freshly transformed candidate
+/// survives (a field-level last-writer-wins merge keyed on whichever side has
+/// the newer `n`/start time), and `d_c` is bumped strictly past
+/// `previous`'s counter so the update is versioned rather than silently
+/// overwritten. If `previous` is already soft-deleted (`d_r == true`) with a
+/// `d_c` at least as high as the freshly transformed candidate's, it is
+/// returned unchanged: an older, out-of-order CoT event can't resurrect a
+/// document peers have already agreed is gone.
+pub fn cot_to_document_merged(
+    event: &CotEvent,
+    peer_key: &str,
+    previous: &CotDocument,
+) -> CotDocument {
+    let candidate = cot_to_document(event, peer_key);
+
+    let (prev_d_c, prev_d_r) = counter_and_tombstone(previous);
+    let (cand_d_c, _) = counter_and_tombstone(&candidate);
+
+    if prev_d_r && prev_d_c >= cand_d_c {
+        return previous.clone();
+    }
+
+    let merged_json = merge_documents_by_n(&previous.to_flattened_json(), &candidate.to_flattened_json());
+    let next_d_c = prev_d_c.max(cand_d_c) + 1;
+    let merged_json = with_counter(merged_json, next_d_c);
+
+    // Deserialize back into `candidate`'s own concrete type rather than
+    // going through `CotDocument::from_json_str`'s `w`-based guessing, since
+    // the merged document is guaranteed to already be the variant
+    // `cot_to_document` chose for this event.
+    match &candidate {
+        CotDocument::Api(_) => serde_json::from_value(merged_json)
+            .map(CotDocument::Api)
+            .unwrap_or(candidate),
+        CotDocument::Chat(_) => serde_json::from_value(merged_json)
+            .map(CotDocument::Chat)
+            .unwrap_or(candidate),
+        CotDocument::File(_) => serde_json::from_value(merged_json)
+            .map(CotDocument::File)
+            .unwrap_or(candidate),
+        CotDocument::Generic(_) => serde_json::from_value(merged_json)
+            .map(CotDocument::Generic)
+            .unwrap_or(candidate),
+        CotDocument::MapItem(_) => serde_json::from_value(merged_json)
+            .map(CotDocument::MapItem)
+            .unwrap_or(candidate),
+        CotDocument::Unknown(_) => serde_json::from_value(merged_json)
+            .map(|raw| CotDocument::Unknown(UnknownDocument { raw }))
+            .unwrap_or(candidate),
+    }
+}
+
+/// Extracts the `(d_c, d_r)` pair from any [`CotDocument`] variant.
+fn counter_and_tombstone(doc: &CotDocument) -> (i64, bool) {
+    match doc {
+        CotDocument::Api(d) => (d.d_c, d.d_r),
+        CotDocument::Chat(d) => (d.d_c, d.d_r),
+        CotDocument::File(d) => (d.d_c, d.d_r),
+        CotDocument::Generic(d) => (d.d_c, d.d_r),
+        CotDocument::MapItem(d) => (d.d_c, d.d_r),
+        CotDocument::Unknown(u) => (
+            u.raw
+                .get("d_c")
+                .or_else(|| u.raw.get("_c"))
+                .and_then(Value::as_i64)
+                .unwrap_or(0),
+            u.raw
+                .get("d_r")
+                .or_else(|| u.raw.get("_r"))
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        ),
+    }
+}
+
+/// Merges two serialized documents for the same id field-by-field: every
+/// field from whichever side has the higher `n` (start time) wins, and a
+/// field present on only the older side is carried through rather than
+/// dropped.
+fn merge_documents_by_n(local: &Value, remote: &Value) -> Value {
+    let (Some(local_obj), Some(remote_obj)) = (local.as_object(), remote.as_object()) else {
+        return local.clone();
+    };
+
+    let local_n = local_obj.get("n").and_then(Value::as_f64).unwrap_or(0.0);
+    let remote_n = remote_obj.get("n").and_then(Value::as_f64).unwrap_or(0.0);
+
+    let (newer, older) = if local_n >= remote_n {
+        (local_obj, remote_obj)
     } else {
-        // Fall back to generic document for all other event types
-        transform_generic_event_flattened(event, peer_key)
+        (remote_obj, local_obj)
+    };
+
+    let mut merged = newer.clone();
+    for (key, older_value) in older {
+        merged.entry(key.clone()).or_insert_with(|| older_value.clone());
+    }
+
+    Value::Object(merged)
+}
+
+/// Overwrites the serialized `_c` (d_c) counter field on a document value.
+fn with_counter(mut value: Value, d_c: i64) -> Value {
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("_c".to_string(), Value::Number(d_c.into()));
     }
+    value
+}
+
+/// Convert a CoT event to a flattened Ditto document for DQL compatibility.
+///
+/// Shares the same default [`TransformerRegistry`] dispatch as
+/// [`cot_to_document`], so the typed and flattened forms of a given event can
+/// never disagree on which CoT type family handled it.
+pub fn cot_to_flattened_document(event: &CotEvent, peer_key: &str) -> Value {
+    TransformerRegistry::with_builtins().transform_flattened(event, peer_key)
 }
 
 /// Transform a location CoT event to a Ditto location document
 pub fn transform_location_event(event: &CotEvent, peer_key: &str) -> MapItem {
     // Parse detail section to extract callsign and other fields
-    let detail_map = parse_detail_section(&event.detail);
+    let mut detail_map = parse_detail_section(&event.detail);
     let callsign = extract_callsign(&detail_map).unwrap_or_default();
+    insert_tz_offset(&mut detail_map, event.tz_offset_secs);
+    insert_version_vector(&mut detail_map, peer_key);
     
     // Map CotEvent and peer_key to MapItem fields
     MapItem {
@@ -277,44 +488,83 @@ pub fn transform_location_event_flattened(event: &CotEvent, peer_key: &str) -> V
     Value::Object(base_doc.into_iter().collect())
 }
 
-/// Transform a chat CoT event to a Ditto chat document
-pub fn transform_chat_event(event: &CotEvent, peer_key: &str) -> Option<Chat> {
-    // Parse chat message details from the detail XML
-    // Expected format: <detail>chat from=SENDER room=ROOM msg=MESSAGE</detail>
-
-    let mut message = None;
-    let mut room = None;
-    let mut room_id = None;
-    let mut author_callsign = None;
-
-    // Simple regex-like extraction for chat details
-    if let Some(msg_start) = event.detail.find("msg=") {
-        let msg_part = &event.detail[msg_start + 4..];
-        if let Some(msg_end) = msg_part.find("</detail>") {
-            message = Some(msg_part[..msg_end].trim().to_string());
-        }
-    }
-
-    if let Some(room_start) = event.detail.find("room=") {
-        let room_part = &event.detail[room_start + 5..];
-        if let Some(room_end) = room_part.find(" roomId=") {
-            room = Some(room_part[..room_end].trim().to_string());
-        }
-    }
+/// Chat fields extracted from a parsed `<detail>` section by
+/// [`parse_chat_detail`].
+struct ChatDetailFields {
+    message: Option<String>,
+    room: Option<String>,
+    room_id: Option<String>,
+    author_callsign: Option<String>,
+}
 
-    if let Some(room_id_start) = event.detail.find("roomId=") {
-        let room_id_part = &event.detail[room_id_start + 7..];
-        if let Some(room_id_end) = room_id_part.find(" msg=") {
-            room_id = Some(room_id_part[..room_id_end].trim().to_string());
-        }
+/// Extracts the chat message, room, room id, and sender callsign from an
+/// already-[`parse_detail_section`]ed `<detail>` map by reading the TAK
+/// `<__chat chatroom="..." senderCallsign="..."><chatgrp uid0="..."
+/// uid1="..." id="..."/></__chat>` and `<remarks>...</remarks>` element
+/// structure, instead of slicing the raw XML. This is immune to attribute
+/// reordering, embedded whitespace, and XML entities in the message, and is
+/// shared by [`transform_chat_event`] and [`transform_chat_event_flattened`]
+/// so the typed and flattened documents can never disagree about what the
+/// chat message even is.
+fn parse_chat_detail(detail_map: &HashMap<String, Value>) -> ChatDetailFields {
+    let chat = detail_map.get("__chat").and_then(Value::as_object);
+    let chatgrp = chat.and_then(|c| c.get("chatgrp")).and_then(Value::as_object);
+
+    let room = chat
+        .and_then(|c| c.get("chatroom"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    // `chatgrp`'s own `id` attribute (falling back to `__chat`'s `id`) is the
+    // room's stable uid -- for a broadcast room this is the literal string
+    // "All Chat Rooms", for a 1-1 chat it's the peer's uid -- distinct from
+    // `chatroom`, which is just the display name. `chatgrp/@uid0..@uidN`
+    // list the room's member uids and are preserved losslessly in the `r`
+    // field rather than promoted onto a typed field here.
+    let room_id = chatgrp
+        .and_then(|g| g.get("id"))
+        .or_else(|| chat.and_then(|c| c.get("id")))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let author_callsign = chat
+        .and_then(|c| c.get("senderCallsign"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let message = detail_map
+        .get("remarks")
+        .and_then(|remarks| match remarks {
+            Value::Object(o) => o.get("_text").and_then(Value::as_str),
+            Value::String(s) => Some(s.as_str()),
+            _ => None,
+        })
+        .map(|s| s.trim().to_string());
+
+    ChatDetailFields {
+        message,
+        room,
+        room_id,
+        author_callsign,
     }
+}
 
-    if let Some(from_start) = event.detail.find("from=") {
-        let from_part = &event.detail[from_start + 5..];
-        if let Some(from_end) = from_part.find(" ") {
-            author_callsign = Some(from_part[..from_end].trim().to_string());
-        }
-    }
+/// Transform a chat CoT event to a Ditto chat document
+pub fn transform_chat_event(event: &CotEvent, peer_key: &str) -> Option<Chat> {
+    // Parse the detail section as structured XML so the chat fields can be
+    // read from the real TAK `<__chat>`/`<chatgrp>`/`<remarks>` elements
+    // rather than sliced out of the raw string, and so any sibling detail
+    // elements beyond those (e.g. `link`, `marti`) survive the round trip
+    // through the `r` field instead of being silently dropped.
+    let mut detail_map = parse_detail_section(&event.detail);
+    insert_tz_offset(&mut detail_map, event.tz_offset_secs);
+    insert_version_vector(&mut detail_map, peer_key);
+    let ChatDetailFields {
+        message,
+        room,
+        room_id,
+        author_callsign,
+    } = parse_chat_detail(&detail_map);
 
     let author_uid = Some(event.uid.clone());
     let author_type = Some("user".to_string());
@@ -346,8 +596,22 @@ pub fn transform_chat_event(event: &CotEvent, peer_key: &str) -> Option<Chat> {
         o: Some(event.stale.timestamp_micros() as f64),
         p: event.how.clone(),
         q: "".to_string(),
-        // Empty r field - will use flattened r_* fields
-        r: HashMap::new(),
+        r: {
+            detail_map
+                .into_iter()
+                .map(|(k, v)| {
+                    let rvalue = match v {
+                        serde_json::Value::String(s) => ChatRValue::String(s),
+                        serde_json::Value::Number(n) => ChatRValue::Number(n.as_f64().unwrap_or(0.0)),
+                        serde_json::Value::Bool(b) => ChatRValue::Boolean(b),
+                        serde_json::Value::Object(o) => ChatRValue::Object(o),
+                        serde_json::Value::Array(a) => ChatRValue::Array(a),
+                        serde_json::Value::Null => ChatRValue::Null,
+                    };
+                    (k, rvalue)
+                })
+                .collect()
+        }, // Parse detail elements into r field
         s: "".to_string(),
         t: "".to_string(),
         u: "".to_string(),
@@ -368,14 +632,19 @@ pub fn transform_chat_event(event: &CotEvent, peer_key: &str) -> Option<Chat> {
 
 /// Transform a chat CoT event to a flattened JSON value for DQL compatibility
 pub fn transform_chat_event_flattened(event: &CotEvent, peer_key: &str) -> Option<Value> {
-    // Extract chat message and room from event.detail
-    let parts: Vec<&str> = event.detail.split_whitespace().collect();
-    let message = if parts.len() >= 2 {
-        Some(format!("{} {}", parts[0], parts[1]))
-    } else {
-        parts.first().map(|s| s.to_string())
-    };
-    let room = parts.get(2).map(|s| s.to_string());
+    // Parse detail section and flatten r field for DQL compatibility
+    let extras = parse_detail_section(&event.detail);
+
+    // Reuse the same `<__chat>`/`<chatgrp>`/`<remarks>` parser as
+    // `transform_chat_event` so the typed and flattened documents never
+    // disagree about what the message, room, room id, or sender are.
+    let ChatDetailFields {
+        message,
+        room,
+        room_id,
+        author_callsign,
+    } = parse_chat_detail(&extras);
+
     let location = Some(format!(
         "{},{},{}",
         event.point.lat, event.point.lon, event.point.hae
@@ -384,9 +653,6 @@ pub fn transform_chat_event_flattened(event: &CotEvent, peer_key: &str) -> Optio
     // If there's no message, return None
     message.as_ref()?;
 
-    // Parse detail section and flatten r field for DQL compatibility
-    let extras = parse_detail_section(&event.detail);
-
     // Create base document as a HashMap for flattening
     let mut base_doc = HashMap::new();
     base_doc.insert("_id".to_string(), Value::String(event.uid.clone()));
@@ -452,6 +718,12 @@ pub fn transform_chat_event_flattened(event: &CotEvent, peer_key: &str) -> Optio
     if let Some(r) = room {
         base_doc.insert("room".to_string(), Value::String(r));
     }
+    if let Some(room_id) = room_id {
+        base_doc.insert("roomId".to_string(), Value::String(room_id));
+    }
+    if let Some(callsign) = author_callsign {
+        base_doc.insert("authorCallsign".to_string(), Value::String(callsign));
+    }
     if let Some(loc) = location {
         base_doc.insert("location".to_string(), Value::String(loc));
     }
@@ -466,6 +738,12 @@ pub fn transform_chat_event_flattened(event: &CotEvent, peer_key: &str) -> Optio
 
 /// Transform an emergency CoT event to a Ditto emergency document
 pub fn transform_emergency_event(event: &CotEvent, peer_key: &str) -> Api {
+    // Parse the detail section so any emergency-specific sibling elements
+    // (e.g. `__emergency`) survive the round trip through the `r` field.
+    let mut detail_map = parse_detail_section(&event.detail);
+    insert_tz_offset(&mut detail_map, event.tz_offset_secs);
+    insert_version_vector(&mut detail_map, peer_key);
+
     let title = None;
     let data = None;
     let mime = Some("application/vnd.cot.emergency+json".to_string());
@@ -497,8 +775,22 @@ pub fn transform_emergency_event(event: &CotEvent, peer_key: &str) -> Api {
         o: Some(event.stale.timestamp_micros() as f64),
         p: event.how.clone(),
         q: "".to_string(),
-        // Empty r field - will use flattened r_* fields
-        r: HashMap::new(),
+        r: {
+            detail_map
+                .into_iter()
+                .map(|(k, v)| {
+                    let rvalue = match v {
+                        serde_json::Value::String(s) => ApiRValue::String(s),
+                        serde_json::Value::Number(n) => ApiRValue::Number(n.as_f64().unwrap_or(0.0)),
+                        serde_json::Value::Bool(b) => ApiRValue::Boolean(b),
+                        serde_json::Value::Object(o) => ApiRValue::Object(o),
+                        serde_json::Value::Array(a) => ApiRValue::Array(a),
+                        serde_json::Value::Null => ApiRValue::Null,
+                    };
+                    (k, rvalue)
+                })
+                .collect()
+        }, // Parse detail elements into r field
         s: "".to_string(),
         t: "".to_string(),
         tag,
@@ -514,11 +806,13 @@ pub fn transform_emergency_event(event: &CotEvent, peer_key: &str) -> Api {
 }
 
 /// Transform a file CoT event to a Ditto file document
-fn transform_file_event(event: &CotEvent, peer_key: &str) -> File {
+pub(crate) fn transform_file_event(event: &CotEvent, peer_key: &str) -> File {
     let c = None;
 
     // Parse the detail section to extract file metadata
     let mut extras = parse_detail_section(&event.detail);
+    insert_tz_offset(&mut extras, event.tz_offset_secs);
+    insert_version_vector(&mut extras, peer_key);
 
     // Extract filename from fileshare element if it exists
     let file = if let Some(fileshare) = extras.get("fileshare") {
@@ -620,8 +914,22 @@ fn transform_file_event(event: &CotEvent, peer_key: &str) -> File {
         o: Some(stale_micros as f64), // Store stale in microseconds
         p: event.how.clone(),
         q: "".to_string(),
-        // Empty r field - will use flattened r_* fields
-        r: HashMap::new(),
+        r: {
+            extras
+                .into_iter()
+                .map(|(k, v)| {
+                    let rvalue = match v {
+                        serde_json::Value::String(s) => FileRValue::String(s),
+                        serde_json::Value::Number(n) => FileRValue::Number(n.as_f64().unwrap_or(0.0)),
+                        serde_json::Value::Bool(b) => FileRValue::Boolean(b),
+                        serde_json::Value::Object(o) => FileRValue::Object(o),
+                        serde_json::Value::Array(a) => FileRValue::Array(a),
+                        serde_json::Value::Null => FileRValue::Null,
+                    };
+                    (k, rvalue)
+                })
+                .collect()
+        }, // Parse detail elements (plus the _ce/_time/_start/_stale round-trip keys above) into r field
         s: "".to_string(),
         sz,
         t: "".to_string(),
@@ -632,9 +940,11 @@ fn transform_file_event(event: &CotEvent, peer_key: &str) -> File {
 }
 
 /// Transform any CoT event to a generic Ditto document
-fn transform_generic_event(event: &CotEvent, peer_key: &str) -> Generic {
+pub(crate) fn transform_generic_event(event: &CotEvent, peer_key: &str) -> Generic {
     // Store the circular error in a special key in the r map to avoid field overloading
     let mut extras = parse_detail_section(&event.detail);
+    insert_tz_offset(&mut extras, event.tz_offset_secs);
+    insert_version_vector(&mut extras, peer_key);
     // Add ce as a special field in the detail map to preserve it during round-trip
     extras.insert(
         "_ce".to_string(),
@@ -720,6 +1030,13 @@ fn transform_generic_event(event: &CotEvent, peer_key: &str) -> Generic {
         u: "".to_string(),
         v: "".to_string(),
         w: event.event_type.clone(),
+
+        // Verbatim copy of `event.detail`, captured as a `RawValue` so it's
+        // embedded as-is on serialization instead of being reparsed into a
+        // lossy `Value`. `cot_event_from_ditto_document` prefers this over
+        // reconstructing detail XML from the (lossy) `r` field above when
+        // it's present.
+        _detail_raw: to_raw_value(&event.detail).ok(),
     }
 }
 
@@ -728,7 +1045,7 @@ fn transform_generic_event(event: &CotEvent, peer_key: &str) -> Generic {
 /// This is the main enum used when working with Ditto documents in the system.
 /// It uses `#[serde(untagged)]` to ensure clean serialization/deserialization
 /// without an additional type tag in the JSON representation.
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum CotDocument {
     /// API document type
@@ -741,6 +1058,112 @@ pub enum CotDocument {
     Generic(Generic),
     /// Map item document type
     MapItem(MapItem),
+    /// Fallback for a document that doesn't match any other variant's shape.
+    ///
+    /// Always declared last: `#[serde(untagged)]` tries each variant in
+    /// order and takes the first successful deserialization, and this one
+    /// accepts any JSON value, so it must never shadow a real match.
+    Unknown(UnknownDocument),
+}
+
+/// Catch-all for a Ditto document whose shape doesn't match any of
+/// [`CotDocument`]'s known variants — e.g. one written by a newer client
+/// version with fields this build doesn't understand, or a malformed record.
+///
+/// Deserializing into this never fails (any valid JSON value round-trips
+/// through the transparent `raw` field), which is what keeps
+/// [`CotDocument::from_json_str`] and the `#[serde(untagged)]` decode path
+/// total instead of erroring or panicking on a document shape nobody
+/// anticipated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UnknownDocument {
+    /// The document's full JSON payload, verbatim.
+    pub raw: Value,
+}
+
+impl UnknownDocument {
+    /// The document's `_id` field, if present and a string.
+    pub fn id(&self) -> Option<&str> {
+        self.raw.get("_id").and_then(Value::as_str)
+    }
+
+    /// The document's `w` (CoT event type) field, if present and a string.
+    pub fn event_type(&self) -> Option<&str> {
+        self.raw.get("w").and_then(Value::as_str)
+    }
+
+    /// The document's start (`n`) timestamp in microseconds since the Unix
+    /// epoch, if present and numeric.
+    pub fn start_micros(&self) -> Option<f64> {
+        self.raw.get("n").and_then(Value::as_f64)
+    }
+
+    /// The document's stale (`o`) timestamp in microseconds since the Unix
+    /// epoch, if present and numeric.
+    pub fn stale_micros(&self) -> Option<f64> {
+        self.raw.get("o").and_then(Value::as_f64)
+    }
+}
+
+impl TaggedSchema for Api {
+    fn discriminator() -> &'static str {
+        "api"
+    }
+}
+
+impl TaggedSchema for Chat {
+    fn discriminator() -> &'static str {
+        "chat"
+    }
+}
+
+impl TaggedSchema for File {
+    fn discriminator() -> &'static str {
+        "file"
+    }
+}
+
+impl TaggedSchema for Generic {
+    fn discriminator() -> &'static str {
+        "generic"
+    }
+}
+
+impl TaggedSchema for MapItem {
+    fn discriminator() -> &'static str {
+        "map_item"
+    }
+}
+
+/// Hand-written, since `#[derive(JsonSchema)]` on an untagged enum only ever
+/// emits an ambiguous `anyOf`. This instead assembles a `oneOf` whose
+/// branches each carry the `const`-tagged `d_t` discriminator from
+/// [`TaggedSchema`], so the schema alone disambiguates variants.
+///
+/// [`CotDocument::Unknown`] is deliberately excluded: it has no fixed shape
+/// to describe, and advertising an "anything goes" branch in a `oneOf` would
+/// defeat the schema's ability to disambiguate the other variants.
+impl schemars::JsonSchema for CotDocument {
+    fn schema_name() -> String {
+        "CotDocument".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        Schema::Object(SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                one_of: Some(vec![
+                    Api::tagged_schema(gen),
+                    Chat::tagged_schema(gen),
+                    File::tagged_schema(gen),
+                    Generic::tagged_schema(gen),
+                    MapItem::tagged_schema(gen),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        })
+    }
 }
 
 /// Transform an emergency CoT event to a flattened JSON value for DQL compatibility
@@ -821,6 +1244,14 @@ pub fn transform_emergency_event_flattened(event: &CotEvent, peer_key: &str) ->
         Value::Number(serde_json::Number::from(event.time.timestamp_millis())),
     );
 
+    // Verbatim copy of `event.detail`, preserved alongside the lossy `r`
+    // flattening above so a caller who needs byte-for-byte fidelity doesn't
+    // have to reconstruct it from the flattened fields.
+    base_doc.insert(
+        "_detail_raw".to_string(),
+        Value::String(event.detail.clone()),
+    );
+
     // Apply flattening to the r field
     flatten_document_r_field(&mut base_doc, &extras);
 
@@ -972,6 +1403,14 @@ pub fn transform_file_event_flattened(event: &CotEvent, peer_key: &str) -> Value
         );
     }
 
+    // Verbatim copy of `event.detail`, preserved alongside the lossy `r`
+    // flattening above so a caller who needs byte-for-byte fidelity doesn't
+    // have to reconstruct it from the flattened fields.
+    base_doc.insert(
+        "_detail_raw".to_string(),
+        Value::String(event.detail.clone()),
+    );
+
     // Apply flattening to the r field
     flatten_document_r_field(&mut base_doc, &extras);
 
@@ -1064,6 +1503,14 @@ pub fn transform_generic_event_flattened(event: &CotEvent, peer_key: &str) -> Va
     base_doc.insert("v".to_string(), Value::String("".to_string()));
     base_doc.insert("w".to_string(), Value::String(event.event_type.clone()));
 
+    // Verbatim copy of `event.detail`, preserved alongside the lossy `r`
+    // flattening above so a caller who needs byte-for-byte fidelity doesn't
+    // have to reconstruct it from the flattened fields.
+    base_doc.insert(
+        "_detail_raw".to_string(),
+        Value::String(event.detail.clone()),
+    );
+
     // Apply flattening to the r field
     flatten_document_r_field(&mut base_doc, &extras);
 
@@ -1093,9 +1540,17 @@ impl CotDocument {
             CotDocument::File(file) => serde_json::to_value(file).unwrap_or(Value::Null),
             CotDocument::Generic(generic) => serde_json::to_value(generic).unwrap_or(Value::Null),
             CotDocument::MapItem(map_item) => serde_json::to_value(map_item).unwrap_or(Value::Null),
+            CotDocument::Unknown(unknown) => unknown.raw.clone(),
         }
     }
 
+    /// Structurally validates this document (see
+    /// [`validation`](super::validation)), returning every field that fails
+    /// rather than stopping at the first.
+    pub fn validate(&self) -> Result<(), crate::error::CotError> {
+        super::validation::validate_flattened_json(&self.to_flattened_json())
+    }
+
     /// Returns true if this document has the specified key in its top-level fields
     /// This is a simplified implementation that only checks a few common fields
     pub fn has_key(&self, key: &str) -> bool {
@@ -1139,18 +1594,23 @@ impl CotDocument {
                 "e" => true, // callsign
                 _ => false,
             },
+            // Unknown carries its full raw JSON, so this can answer exactly
+            // rather than guessing from a fixed field list.
+            CotDocument::Unknown(unknown) => unknown.raw.get(key).is_some(),
         }
     }
 
     /// Deserialize a JSON string into a CotDocument, determining the variant based on the 'w' field.
-    /// Handles defaults for missing fields in variants.
+    /// Handles defaults for missing fields in variants, and never fails outright: a document
+    /// with no recognizable 'w' field, or one that doesn't fit any known variant's shape, comes
+    /// back as [`CotDocument::Unknown`] instead of an error.
     pub fn from_json_str(json_str: &str) -> Result<Self, anyhow::Error> {
         let json_value: serde_json::Value = serde_json::from_str(json_str)
             .map_err(|e| anyhow::anyhow!("Failed to parse JSON: {}", e))?;
-        let doc_type = json_value
-            .get("w")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Document is missing 'w' field"))?;
+        let doc_type = match json_value.get("w").and_then(|v| v.as_str()) {
+            Some(doc_type) => doc_type,
+            None => return Ok(CotDocument::Unknown(UnknownDocument { raw: json_value })),
+        };
 
         if doc_type.contains("a-u-r-loc-g")
             || doc_type.contains("a-f-G-U-C")
@@ -1184,11 +1644,13 @@ impl CotDocument {
             let api: Api = serde_json::from_value(json_value)
                 .map_err(|e| anyhow::anyhow!("Failed to deserialize as Api: {}", e))?;
             Ok(CotDocument::Api(api))
+        } else if let Ok(generic) = serde_json::from_value::<Generic>(json_value.clone()) {
+            // Closest fit for a recognized-but-not-specially-handled 'w' value
+            Ok(CotDocument::Generic(generic))
         } else {
-            // Default to File for unknown types
-            let file: File = serde_json::from_value(json_value)
-                .map_err(|e| anyhow::anyhow!("Failed to deserialize as File: {}", e))?;
-            Ok(CotDocument::File(file))
+            // Doesn't fit any known variant's shape; preserve it verbatim
+            // rather than failing the whole decode.
+            Ok(CotDocument::Unknown(UnknownDocument { raw: json_value }))
         }
     }
 
@@ -1207,6 +1669,7 @@ impl CotDocument {
             CotDocument::File(_) => "files",
             CotDocument::Api(_) => "api_events",
             CotDocument::Generic(_) => "generic",
+            CotDocument::Unknown(_) => "unknown",
         }
     }
 
@@ -1256,4 +1719,287 @@ impl CotDocument {
     pub fn to_cot_event(&self) -> CotEvent {
         crate::ditto::from_ditto::cot_event_from_ditto_document(self)
     }
+
+    /// Like [`to_cot_event`](Self::to_cot_event), but also returns a
+    /// [`ConversionReport`](crate::ditto::from_ditto::ConversionReport)
+    /// enumerating every field populated on `self` that had no
+    /// representation in the produced [`CotEvent`], instead of silently
+    /// dropping it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use ditto_cot::ditto::CotDocument;
+    /// # fn example(doc: CotDocument) {
+    /// let (cot_event, report) = doc.to_cot_event_with_report();
+    /// if !report.is_lossless() {
+    ///     eprintln!("dropped fields: {:?}", report.dropped);
+    /// }
+    /// # let _ = cot_event;
+    /// # }
+    /// ```
+    pub fn to_cot_event_with_report(
+        &self,
+    ) -> (CotEvent, crate::ditto::from_ditto::ConversionReport) {
+        crate::ditto::from_ditto::to_cot_event_with_report(self)
+    }
+
+    /// Like [`to_cot_event`](Self::to_cot_event), but governed by
+    /// [`ConversionOptions`](crate::ditto::from_ditto::ConversionOptions) —
+    /// in particular, `preserve_unknown_detail` carries fields this schema
+    /// version can't map into the produced event's `<detail>` as preserved
+    /// extension elements instead of dropping them, so a round trip through
+    /// `CotEvent` doesn't erase another peer's concurrent writes to them.
+    pub fn to_cot_event_with_options(
+        &self,
+        options: crate::ditto::from_ditto::ConversionOptions,
+    ) -> CotEvent {
+        crate::ditto::from_ditto::to_cot_event_with_options(self, options)
+    }
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn make_event(uid: &str, start_offset_secs: i64, event_type: &str) -> CotEvent {
+        let base: DateTime<Utc> = DateTime::parse_from_rfc3339("2023-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let start = base + chrono::Duration::seconds(start_offset_secs);
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: event_type.to_string(),
+            time: start,
+            start,
+            stale: start + chrono::Duration::hours(1),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point {
+                lat: 1.0,
+                lon: 2.0,
+                hae: 3.0,
+                ce: 4.0,
+                le: 5.0,
+            },
+            detail: String::new(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn merged_update_bumps_counter_past_previous() {
+        let previous = cot_to_document(&make_event("uid-1", 0, "a-u-generic"), "peer");
+        let next_event = make_event("uid-1", 60, "a-u-generic");
+
+        let merged = cot_to_document_merged(&next_event, "peer", &previous);
+        let (d_c, _) = counter_and_tombstone(&merged);
+        assert_eq!(d_c, 1);
+    }
+
+    #[test]
+    fn older_out_of_order_update_does_not_clobber_newer_n() {
+        let previous = cot_to_document(&make_event("uid-1", 100, "a-u-generic"), "peer");
+        let stale_event = make_event("uid-1", 0, "a-u-generic");
+
+        let merged = cot_to_document_merged(&stale_event, "peer", &previous);
+        let CotDocument::Generic(merged) = merged else {
+            panic!("expected Generic document");
+        };
+        let CotDocument::Generic(previous) = previous else {
+            panic!("expected Generic document");
+        };
+        assert_eq!(merged.n, previous.n);
+    }
+
+    #[test]
+    fn soft_deleted_previous_with_higher_counter_suppresses_resurrection() {
+        let mut previous = cot_to_document(&make_event("uid-1", 100, "a-u-generic"), "peer");
+        if let CotDocument::Generic(ref mut d) = previous {
+            d.d_r = true;
+            d.d_c = 5;
+        }
+        let stale_event = make_event("uid-1", 0, "a-u-generic");
+
+        let merged = cot_to_document_merged(&stale_event, "peer", &previous);
+        let CotDocument::Generic(merged) = merged else {
+            panic!("expected Generic document");
+        };
+        assert!(merged.d_r);
+        assert_eq!(merged.d_c, 5);
+    }
+}
+
+#[cfg(test)]
+mod unknown_document_tests {
+    use super::*;
+
+    #[test]
+    fn from_json_str_falls_back_to_unknown_without_w_field() {
+        let doc = CotDocument::from_json_str(r#"{"_id": "no-type-field"}"#).unwrap();
+        let CotDocument::Unknown(unknown) = doc else {
+            panic!("expected Unknown document");
+        };
+        assert_eq!(unknown.id(), Some("no-type-field"));
+    }
+
+    #[test]
+    fn from_json_str_falls_back_to_unknown_for_unrecognized_shape() {
+        // A 'w' value that doesn't match any routing rule, whose remaining
+        // fields don't fit Generic's required shape either.
+        let doc = CotDocument::from_json_str(
+            r#"{"_id": "weird-1", "w": "x-unheard-of", "totally_unexpected": true}"#,
+        )
+        .unwrap();
+        let CotDocument::Unknown(unknown) = doc else {
+            panic!("expected Unknown document");
+        };
+        assert_eq!(unknown.event_type(), Some("x-unheard-of"));
+    }
+
+    #[test]
+    fn from_json_str_never_errors_on_malformed_shapes() {
+        // Any syntactically valid JSON must decode to *something*, never an error.
+        for json in [
+            "null",
+            "42",
+            r#""just a string""#,
+            "[]",
+            r#"{"arbitrary": {"nested": ["stuff"]}}"#,
+        ] {
+            assert!(
+                CotDocument::from_json_str(json).is_ok(),
+                "expected Ok for {json}"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod try_cot_to_document_tests {
+    use super::*;
+
+    fn event(event_type: &str, detail: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "uid-1".to_string(),
+            event_type: event_type.to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::new(34.0, -118.0, 100.0),
+            detail: detail.to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn well_formed_location_event_succeeds() {
+        let doc = try_cot_to_document(&event("a-f-G-U-C", ""), "peer").unwrap();
+        assert!(matches!(doc, CotDocument::MapItem(_)));
+    }
+
+    #[test]
+    fn non_finite_latitude_is_rejected() {
+        let mut bad = event("a-f-G-U-C", "");
+        bad.point.lat = f64::NAN;
+
+        let err = try_cot_to_document(&bad, "peer").unwrap_err();
+        assert!(matches!(
+            err,
+            CotConversionError::NonFiniteCoordinate { field: "lat", .. }
+        ));
+    }
+
+    #[test]
+    fn chat_event_without_a_message_is_rejected() {
+        let bad = event(
+            "b-t-f",
+            r#"<detail><__chat chatroom="ops" senderCallsign="ALPHA"><chatgrp uid0="uid-1" uid1="ops"/></__chat></detail>"#,
+        );
+        let err = try_cot_to_document(&bad, "peer").unwrap_err();
+        assert_eq!(err, CotConversionError::MissingRequiredDetail("remarks"));
+    }
+
+    #[test]
+    fn chat_event_with_empty_remarks_is_malformed() {
+        let bad = event(
+            "b-t-f",
+            r#"<detail><__chat chatroom="ops" senderCallsign="ALPHA"/><remarks></remarks></detail>"#,
+        );
+        let err = try_cot_to_document(&bad, "peer").unwrap_err();
+        assert_eq!(err, CotConversionError::MalformedChatDetail);
+    }
+
+    #[test]
+    fn well_formed_chat_event_succeeds() {
+        let good = event(
+            "b-t-f",
+            r#"<detail><__chat chatroom="ops" senderCallsign="ALPHA"><chatgrp uid0="uid-1" uid1="ops"/></__chat><remarks>hello</remarks></detail>"#,
+        );
+        let doc = try_cot_to_document(&good, "peer").unwrap();
+        assert!(matches!(doc, CotDocument::Chat(_)));
+    }
+}
+
+#[cfg(test)]
+mod cot_to_document_checked_tests {
+    use super::*;
+
+    fn event(uid: &str, event_type: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: uid.to_string(),
+            event_type: event_type.to_string(),
+            time: chrono::Utc::now(),
+            start: chrono::Utc::now(),
+            stale: chrono::Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::new(34.0, -118.0, 100.0),
+            detail: String::new(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn well_formed_event_succeeds() {
+        let doc = cot_to_document_checked(&event("uid-1", "a-f-G-U-C"), "peer").unwrap();
+        assert!(matches!(doc, CotDocument::MapItem(_)));
+    }
+
+    #[test]
+    fn a_single_missing_field_is_reported_directly() {
+        let err = cot_to_document_checked(&event("", "a-f-G-U-C"), "peer").unwrap_err();
+        assert!(matches!(err, CotError::MissingField(field) if field == "uid"));
+    }
+
+    #[test]
+    fn several_missing_or_invalid_fields_are_all_reported_together() {
+        let mut bad = event("", "");
+        bad.point.lat = f64::NAN;
+
+        let err = cot_to_document_checked(&bad, "peer").unwrap_err();
+        let CotError::Multiple(errors) = err else {
+            panic!("expected CotError::Multiple, got a single error");
+        };
+        assert_eq!(errors.len(), 3);
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, CotError::MissingField(field) if field == "uid")));
+        assert!(errors
+            .iter()
+            .any(|e| matches!(e, CotError::MissingField(field) if field == "type")));
+    }
+
+    #[test]
+    fn iter_errors_flattens_a_multiple_error() {
+        let mut bad = event("", "");
+        bad.point.lat = f64::NAN;
+        let err = cot_to_document_checked(&bad, "peer").unwrap_err();
+
+        let count = (&err).into_iter().count();
+        assert_eq!(count, 3);
+    }
 }
@@ -0,0 +1,306 @@
+//! Pluggable CoT event-type -> [`CotDocument`] dispatch.
+//!
+//! [`to_ditto::cot_to_document`](super::to_ditto::cot_to_document) and
+//! [`to_ditto::cot_to_flattened_document`](super::to_ditto::cot_to_flattened_document)
+//! used to hard-code event-type dispatch as an `if/else` chain over
+//! `event_type.contains(...)`, so adding a new CoT type — or letting a
+//! downstream user handle a custom MIL-STD-2525 `a-*` subtype — meant editing
+//! that function directly. [`CotTransformer`] is the extension point instead:
+//! each transformer owns one CoT type family's `matches` check plus its typed
+//! and flattened conversions, and [`TransformerRegistry`] dispatches to the
+//! first matching transformer in registration order.
+//! [`TransformerRegistry::with_builtins`] pre-loads the built-in families
+//! (emergency, chat, location, file, generic fallback) in the same precedence
+//! the old `if/else` chain used, and both `cot_to_document` and
+//! `cot_to_flattened_document` are built on top of the same registry, so the
+//! typed and flattened paths can't diverge.
+
+use crate::cot_events::CotEvent;
+use crate::ditto::to_ditto::{
+    transform_chat_event, transform_chat_event_flattened, transform_emergency_event,
+    transform_emergency_event_flattened, transform_file_event, transform_file_event_flattened,
+    transform_generic_event, transform_generic_event_flattened, transform_location_event,
+    transform_location_event_flattened, CotDocument,
+};
+use serde_json::Value;
+
+/// One CoT event-type family's conversion into a [`CotDocument`], both typed
+/// and flattened.
+pub trait CotTransformer {
+    /// Returns whether this transformer handles `event_type` (the CoT
+    /// event's `type` attribute, e.g. `"a-f-G-U-C"`).
+    fn matches(&self, event_type: &str) -> bool;
+
+    /// Converts `event` into this transformer's [`CotDocument`] variant.
+    fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument;
+
+    /// Converts `event` into this transformer's flattened JSON form, for DQL
+    /// compatibility.
+    fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value;
+}
+
+/// The exact CoT event type [`EmergencyTransformer`] matches, shared with
+/// [`coordinate_layout`](super::coordinate_layout) so the inverse
+/// (flattened-JSON-to-`CotEvent`) classification can't drift from this
+/// forward dispatch table.
+pub const EMERGENCY_EVENT_TYPE: &str = "a-u-emergency-g";
+
+/// Substrings [`ChatTransformer`] matches anywhere in the event type, shared
+/// with [`coordinate_layout`](super::coordinate_layout).
+pub const CHAT_EVENT_TYPE_MARKERS: &[&str] = &["b-t-f", "chat"];
+
+/// Substrings [`LocationTransformer`] matches anywhere in the event type,
+/// shared with [`coordinate_layout`](super::coordinate_layout).
+pub const LOCATION_EVENT_TYPE_MARKERS: &[&str] = &[
+    "a-u-r-loc-g",
+    "a-f-G-U-C",
+    "a-f-G-U",
+    "a-f-G-U-I",
+    "a-f-G-U-T",
+    "a-f-S-C-U",
+    "a-f-A-M-F-Q",
+    "a-u-S",
+    "a-u-A",
+    "a-u-G",
+];
+
+/// Substrings [`FileTransformer`] matches anywhere in the event type, shared
+/// with [`coordinate_layout`](super::coordinate_layout).
+pub const FILE_EVENT_TYPE_MARKERS: &[&str] = &["file", "attachment"];
+
+struct EmergencyTransformer;
+
+impl CotTransformer for EmergencyTransformer {
+    fn matches(&self, event_type: &str) -> bool {
+        event_type == EMERGENCY_EVENT_TYPE
+    }
+
+    fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument {
+        CotDocument::Api(transform_emergency_event(event, peer_key))
+    }
+
+    fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value {
+        transform_emergency_event_flattened(event, peer_key)
+    }
+}
+
+struct ChatTransformer;
+
+impl CotTransformer for ChatTransformer {
+    fn matches(&self, event_type: &str) -> bool {
+        CHAT_EVENT_TYPE_MARKERS.iter().any(|marker| event_type.contains(marker))
+    }
+
+    fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument {
+        match transform_chat_event(event, peer_key) {
+            Some(chat_doc) => CotDocument::Chat(chat_doc),
+            None => CotDocument::Generic(transform_generic_event(event, peer_key)),
+        }
+    }
+
+    fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value {
+        match transform_chat_event_flattened(event, peer_key) {
+            Some(chat_doc) => chat_doc,
+            None => transform_generic_event_flattened(event, peer_key),
+        }
+    }
+}
+
+struct LocationTransformer;
+
+impl CotTransformer for LocationTransformer {
+    fn matches(&self, event_type: &str) -> bool {
+        LOCATION_EVENT_TYPE_MARKERS.iter().any(|marker| event_type.contains(marker))
+    }
+
+    fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument {
+        CotDocument::MapItem(transform_location_event(event, peer_key))
+    }
+
+    fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value {
+        transform_location_event_flattened(event, peer_key)
+    }
+}
+
+struct FileTransformer;
+
+impl CotTransformer for FileTransformer {
+    fn matches(&self, event_type: &str) -> bool {
+        FILE_EVENT_TYPE_MARKERS.iter().any(|marker| event_type.contains(marker))
+    }
+
+    fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument {
+        CotDocument::File(transform_file_event(event, peer_key))
+    }
+
+    fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value {
+        transform_file_event_flattened(event, peer_key)
+    }
+}
+
+struct GenericTransformer;
+
+impl CotTransformer for GenericTransformer {
+    fn matches(&self, _event_type: &str) -> bool {
+        true
+    }
+
+    fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument {
+        CotDocument::Generic(transform_generic_event(event, peer_key))
+    }
+
+    fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value {
+        transform_generic_event_flattened(event, peer_key)
+    }
+}
+
+/// An ordered collection of [`CotTransformer`]s, dispatched first-match-wins.
+pub struct TransformerRegistry {
+    transformers: Vec<Box<dyn CotTransformer + Send + Sync>>,
+}
+
+impl Default for TransformerRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl TransformerRegistry {
+    /// Creates an empty registry with no transformers registered.
+    pub fn new() -> Self {
+        Self {
+            transformers: Vec::new(),
+        }
+    }
+
+    /// Creates a registry pre-loaded with one transformer per built-in CoT
+    /// type family (emergency, chat, location, file), plus a generic
+    /// catch-all that matches any event type.
+    ///
+    /// The generic fallback is registered last, so a caller wanting to
+    /// override a built-in family for a specific custom event type should
+    /// [`Self::register`] its own transformer before this fallback is ever
+    /// consulted — i.e. build the registry with [`Self::new`] and register
+    /// the custom transformer first, or register it afterwards if a plain
+    /// override over the built-ins (not the fallback) is all that's needed.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(EmergencyTransformer));
+        registry.register(Box::new(ChatTransformer));
+        registry.register(Box::new(LocationTransformer));
+        registry.register(Box::new(FileTransformer));
+        registry.register(Box::new(GenericTransformer));
+        registry
+    }
+
+    /// Appends `transformer` to the end of the dispatch order.
+    pub fn register(&mut self, transformer: Box<dyn CotTransformer + Send + Sync>) {
+        self.transformers.push(transformer);
+    }
+
+    /// Returns the first registered transformer that matches `event_type`,
+    /// if any.
+    pub fn transformer_for(&self, event_type: &str) -> Option<&(dyn CotTransformer + Send + Sync)> {
+        self.transformers
+            .iter()
+            .find(|transformer| transformer.matches(event_type))
+            .map(|transformer| transformer.as_ref())
+    }
+
+    /// Converts `event` to a [`CotDocument`] via the first matching
+    /// transformer, falling back to a bare generic document if nothing in
+    /// this registry matches at all (e.g. a custom registry built without
+    /// the generic catch-all).
+    pub fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument {
+        match self.transformer_for(&event.event_type) {
+            Some(transformer) => transformer.transform(event, peer_key),
+            None => CotDocument::Generic(transform_generic_event(event, peer_key)),
+        }
+    }
+
+    /// Flattened counterpart of [`Self::transform`].
+    pub fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value {
+        match self.transformer_for(&event.event_type) {
+            Some(transformer) => transformer.transform_flattened(event, peer_key),
+            None => transform_generic_event_flattened(event, peer_key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn event(event_type: &str) -> CotEvent {
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "uid-1".to_string(),
+            event_type: event_type.to_string(),
+            time: Utc::now(),
+            start: Utc::now(),
+            stale: Utc::now() + chrono::Duration::minutes(5),
+            how: "h-g-i-g-o".to_string(),
+            point: crate::cot_events::Point::default(),
+            detail: String::new(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn builtins_dispatch_emergency_to_api() {
+        let registry = TransformerRegistry::with_builtins();
+        let doc = registry.transform(&event("a-u-emergency-g"), "peer");
+        assert!(matches!(doc, CotDocument::Api(_)));
+    }
+
+    #[test]
+    fn builtins_dispatch_location_to_map_item() {
+        let registry = TransformerRegistry::with_builtins();
+        let doc = registry.transform(&event("a-f-G-U-C"), "peer");
+        assert!(matches!(doc, CotDocument::MapItem(_)));
+    }
+
+    #[test]
+    fn unrecognized_type_falls_back_to_generic() {
+        let registry = TransformerRegistry::with_builtins();
+        let doc = registry.transform(&event("x-unheard-of"), "peer");
+        assert!(matches!(doc, CotDocument::Generic(_)));
+    }
+
+    #[test]
+    fn custom_transformer_registered_before_builtins_wins() {
+        struct AlwaysGeneric;
+        impl CotTransformer for AlwaysGeneric {
+            fn matches(&self, event_type: &str) -> bool {
+                event_type == "a-f-G-U-C"
+            }
+            fn transform(&self, event: &CotEvent, peer_key: &str) -> CotDocument {
+                CotDocument::Generic(transform_generic_event(event, peer_key))
+            }
+            fn transform_flattened(&self, event: &CotEvent, peer_key: &str) -> Value {
+                transform_generic_event_flattened(event, peer_key)
+            }
+        }
+
+        let mut registry = TransformerRegistry::new();
+        registry.register(Box::new(AlwaysGeneric));
+        registry.register(Box::new(LocationTransformer));
+
+        let doc = registry.transform(&event("a-f-G-U-C"), "peer");
+        assert!(matches!(doc, CotDocument::Generic(_)));
+    }
+
+    #[test]
+    fn typed_and_flattened_paths_agree_on_which_transformer_matched() {
+        let registry = TransformerRegistry::with_builtins();
+        let typed = registry.transform(&event("a-u-emergency-g"), "peer");
+        let flattened = registry.transform_flattened(&event("a-u-emergency-g"), "peer");
+
+        assert!(matches!(typed, CotDocument::Api(_)));
+        assert_eq!(
+            flattened.get("contentType").and_then(Value::as_str),
+            Some("emergency")
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Per-peer version vectors, so [`merge`](super::merge) can tell a plain
+//! causal overwrite from a genuine concurrent conflict instead of guessing
+//! from a single scalar `d_v` — the same replication-state tracking
+//! iroh-sync and aquadoggo use to decide whether two revisions need a
+//! conflict resolver at all, or whether one simply happened after the
+//! other.
+//!
+//! The generated schema has no vector-valued field of its own, so (like
+//! `tz_offset_secs` and `original_type`) the vector rides in the document's
+//! `r` map under the reserved [`VERSION_VECTOR_KEY`].
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+/// The reserved `r` key a document's [`VersionVector`] is stashed under.
+pub const VERSION_VECTOR_KEY: &str = "_version_vector";
+
+/// How two [`VersionVector`]s relate: whether one is a strict causal
+/// successor of the other, they're identical, or they diverged
+/// independently (a genuine concurrent edit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorOrdering {
+    /// Every entry is equal on both sides.
+    Equal,
+    /// `self`'s entries are all ≥ the other's, with at least one strictly
+    /// greater: `self` causally follows the other.
+    Dominates,
+    /// The mirror of [`Self::Dominates`]: the other vector causally follows
+    /// `self`.
+    Dominated,
+    /// Neither side dominates: each has at least one entry strictly ahead
+    /// of the other's, so the edits happened independently.
+    Concurrent,
+}
+
+/// A per-peer edit counter for one document, keyed by Ditto peer key (the
+/// same string [`cot_to_document`](crate::ditto::cot_to_document)'s
+/// `peer_key` argument supplies).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionVector(BTreeMap<String, u64>);
+
+impl VersionVector {
+    /// An empty vector, as if no peer had ever edited this document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A vector with a single peer's count, the initial state
+    /// [`cot_to_document`](crate::ditto::cot_to_document) stamps a freshly
+    /// created document with.
+    pub fn initial(peer_key: &str) -> Self {
+        let mut vector = Self::new();
+        vector.bump(peer_key);
+        vector
+    }
+
+    /// Increments `peer_key`'s entry, the only entry a local edit is
+    /// allowed to touch.
+    pub fn bump(&mut self, peer_key: &str) {
+        *self.0.entry(peer_key.to_string()).or_insert(0) += 1;
+    }
+
+    /// The element-wise maximum of `self` and `other` over the union of
+    /// both sides' peers — the merged vector two replicas converge on
+    /// regardless of which one applies the merge.
+    pub fn merged_with(&self, other: &Self) -> Self {
+        let mut merged = self.0.clone();
+        for (peer, &count) in &other.0 {
+            let entry = merged.entry(peer.clone()).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+        Self(merged)
+    }
+
+    /// Compares `self` against `other`, treating a peer missing from either
+    /// side as count `0`.
+    pub fn compare(&self, other: &Self) -> VectorOrdering {
+        let mut self_ahead = false;
+        let mut other_ahead = false;
+
+        for peer in self.0.keys().chain(other.0.keys()) {
+            let self_count = self.0.get(peer).copied().unwrap_or(0);
+            let other_count = other.0.get(peer).copied().unwrap_or(0);
+            match self_count.cmp(&other_count) {
+                Ordering::Greater => self_ahead = true,
+                Ordering::Less => other_ahead = true,
+                Ordering::Equal => {}
+            }
+        }
+
+        match (self_ahead, other_ahead) {
+            (false, false) => VectorOrdering::Equal,
+            (true, false) => VectorOrdering::Dominates,
+            (false, true) => VectorOrdering::Dominated,
+            (true, true) => VectorOrdering::Concurrent,
+        }
+    }
+
+    /// Serializes this vector as a JSON object (`{peer_key: count, ...}`),
+    /// the shape stashed under [`VERSION_VECTOR_KEY`].
+    pub fn to_json_map(&self) -> Map<String, Value> {
+        self.0.iter().map(|(peer, &count)| (peer.clone(), Value::from(count))).collect()
+    }
+
+    /// The inverse of [`Self::to_json_map`]; entries that aren't
+    /// non-negative integers are skipped rather than failing the whole
+    /// vector.
+    pub fn from_json_map(map: &Map<String, Value>) -> Self {
+        Self(
+            map.iter()
+                .filter_map(|(peer, value)| value.as_u64().map(|count| (peer.clone(), count)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bump_only_touches_the_editing_peer() {
+        let mut vector = VersionVector::initial("peer-a");
+        vector.bump("peer-a");
+
+        assert_eq!(vector.to_json_map().get("peer-a"), Some(&Value::from(2u64)));
+        assert_eq!(vector.to_json_map().get("peer-b"), None);
+    }
+
+    #[test]
+    fn a_strict_successor_dominates() {
+        let base = VersionVector::initial("peer-a");
+        let mut successor = base.clone();
+        successor.bump("peer-a");
+
+        assert_eq!(successor.compare(&base), VectorOrdering::Dominates);
+        assert_eq!(base.compare(&successor), VectorOrdering::Dominated);
+    }
+
+    #[test]
+    fn identical_vectors_are_equal() {
+        let a = VersionVector::initial("peer-a");
+        let b = VersionVector::initial("peer-a");
+        assert_eq!(a.compare(&b), VectorOrdering::Equal);
+    }
+
+    #[test]
+    fn independent_edits_are_concurrent() {
+        let mut a = VersionVector::initial("peer-a");
+        let mut b = a.clone();
+        a.bump("peer-a");
+        b.bump("peer-b");
+
+        assert_eq!(a.compare(&b), VectorOrdering::Concurrent);
+        assert_eq!(b.compare(&a), VectorOrdering::Concurrent);
+    }
+
+    #[test]
+    fn merge_takes_the_element_wise_maximum() {
+        let mut a = VersionVector::initial("peer-a");
+        a.bump("peer-a");
+        a.bump("peer-a"); // peer-a: 3
+
+        let b = VersionVector::initial("peer-b"); // peer-b: 1
+
+        let merged = a.merged_with(&b);
+        assert_eq!(merged.to_json_map().get("peer-a"), Some(&Value::from(3u64)));
+        assert_eq!(merged.to_json_map().get("peer-b"), Some(&Value::from(1u64)));
+    }
+
+    #[test]
+    fn json_round_trip_skips_non_integer_entries() {
+        let mut map = Map::new();
+        map.insert("peer-a".to_string(), Value::from(5u64));
+        map.insert("peer-b".to_string(), Value::String("not a count".to_string()));
+
+        let vector = VersionVector::from_json_map(&map);
+        assert_eq!(vector.to_json_map().get("peer-a"), Some(&Value::from(5u64)));
+        assert_eq!(vector.to_json_map().get("peer-b"), None);
+    }
+}
@@ -1,9 +1,10 @@
 use crate::cot_events::CotEvent;
-use crate::ditto::{self, DittoDocument};
+use crate::ditto::{self, CotDocument, DittoDocument, Field, Filter, Order, Value as FilterValue};
 use crate::error::CotError;
 use dittolive_ditto::prelude::*;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Inserts a CoT event into Ditto after transforming it to Ditto's format
 ///
@@ -32,6 +33,101 @@ pub async fn insert_cot_event(
     insert_document(ditto, &collection_name, &doc_value).await
 }
 
+/// Inserts a batch of CoT events in as few round-trips as possible.
+///
+/// Each event is transformed to its target Ditto document and grouped by
+/// collection (`cot_chat`, `cot_location`, `cot_emergency`, `cot_generic`),
+/// then each group is sent as a single multi-document
+/// `INSERT INTO coll DOCUMENTS {..},{..},...` statement instead of one
+/// round-trip per event.
+///
+/// # Returns
+/// One result per input event, in the same order as `events`, so a
+/// transform failure or a rejected batch for one collection doesn't abort
+/// insertion of the others.
+pub async fn insert_cot_events(
+    ditto: &Ditto,
+    events: &[CotEvent],
+    peer_key: &str,
+) -> Result<Vec<Result<(), CotError>>, CotError> {
+    let mut results: Vec<Option<Result<(), CotError>>> = vec![None; events.len()];
+    let mut by_collection: HashMap<&'static str, Vec<(usize, serde_json::Value)>> = HashMap::new();
+
+    for (index, event) in events.iter().enumerate() {
+        let ditto_doc = ditto::cot_to_document(event, peer_key);
+        let (collection, doc_value) = match ditto_doc {
+            DittoDocument::Chat(chat) => ("cot_chat", serde_json::to_value(chat)),
+            DittoDocument::MapItem(loc) => ("cot_location", serde_json::to_value(loc)),
+            DittoDocument::Api(emergency) => ("cot_emergency", serde_json::to_value(emergency)),
+            DittoDocument::File(gen) => ("cot_generic", serde_json::to_value(gen)),
+        };
+        match doc_value {
+            Ok(value) => by_collection
+                .entry(collection)
+                .or_default()
+                .push((index, value)),
+            Err(e) => results[index] = Some(Err(CotError::Json(e))),
+        }
+    }
+
+    let store = ditto.store();
+    for (collection, docs) in by_collection {
+        let body = docs
+            .iter()
+            .map(|(_, value)| value.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let query = format!("INSERT INTO {} DOCUMENTS {}", collection, body);
+
+        let outcome = store
+            .execute_v2(&query)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+
+        for (index, _) in &docs {
+            results[*index] = Some(outcome.clone().map_err(CotError::Format));
+        }
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|r| r.expect("every index was assigned a result"))
+        .collect())
+}
+
+/// Deletes every document whose `_id` is in `ids` from a collection in a
+/// single round-trip (`DELETE FROM coll WHERE _id IN (...)`), escaped
+/// through the same [`Filter`] builder as the single-document helpers.
+///
+/// # Returns
+/// `Ok(())` if the batch deletion was successful, or an error if it failed.
+/// A no-op (no query is sent) if `ids` is empty.
+pub async fn delete_documents(
+    ditto: &Ditto,
+    collection: &str,
+    ids: &[&str],
+) -> Result<(), CotError> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let store = ditto.store();
+    #[allow(clippy::unwrap_used)]
+    let filter = Filter::In(
+        Field::new("_id").unwrap(),
+        ids.iter().map(|id| FilterValue::from(*id)).collect(),
+    );
+    let query = format!("DELETE FROM {} WHERE {}", collection, filter.to_dql());
+
+    store
+        .execute_v2(&query)
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+
+    Ok(())
+}
+
 /// Inserts a document into the specified collection
 async fn insert_document(
     ditto: &Ditto,
@@ -53,7 +149,8 @@ async fn insert_document(
     Ok(())
 }
 
-/// Retrieves documents from a specific Ditto collection
+/// Retrieves documents from a specific Ditto collection, optionally
+/// constrained by a typed [`Filter`].
 ///
 /// # Type Parameters
 /// - `T`: The type to deserialize the documents into
@@ -61,18 +158,20 @@ async fn insert_document(
 /// # Parameters
 /// - `ditto`: The Ditto instance to use
 /// - `collection`: The name of the collection to query
-/// - `query`: Optional WHERE clause (without the WHERE keyword)
+/// - `filter`: Optional `WHERE` constraint, compiled through [`Filter::to_dql`]
+///   so caller-controlled values (ids, callsigns, ...) are escaped rather
+///   than interpolated raw
 ///
 /// # Returns
 /// A vector of deserialized documents, or an error if the operation fails
 pub async fn get_documents<T: DeserializeOwned>(
     ditto: &Ditto,
     collection: &str,
-    query: Option<&str>,
+    filter: Option<&Filter>,
 ) -> Result<Vec<T>, CotError> {
     let store = ditto.store();
-    let query_str = match query {
-        Some(q) => format!("SELECT * FROM {} WHERE {}", collection, q),
+    let query_str = match filter {
+        Some(f) => format!("SELECT * FROM {} WHERE {}", collection, f.to_dql()),
         None => format!("SELECT * FROM {}", collection),
     };
 
@@ -92,6 +191,195 @@ pub async fn get_documents<T: DeserializeOwned>(
     Ok(items)
 }
 
+/// Retrieves every document whose `_id` is in `ids` from a collection in a
+/// single round-trip (`SELECT * FROM coll WHERE _id IN (...)`), mirroring
+/// [`delete_documents`]'s batching so a caller fetching a burst of CoT UIDs
+/// doesn't pay one round-trip per id.
+///
+/// # Returns
+/// The matching documents, in whatever order the store returns them (not
+/// necessarily `ids`' order, and shorter than `ids` if some weren't found).
+/// `Ok(vec![])` without a query if `ids` is empty.
+pub async fn get_documents_by_ids<T: DeserializeOwned>(
+    ditto: &Ditto,
+    collection: &str,
+    ids: &[&str],
+) -> Result<Vec<T>, CotError> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    #[allow(clippy::unwrap_used)]
+    let filter = Filter::In(
+        Field::new("_id").unwrap(),
+        ids.iter().map(|id| FilterValue::from(*id)).collect(),
+    );
+    get_documents(ditto, collection, Some(&filter)).await
+}
+
+/// Ordering, limit, and offset for [`get_documents_paged`].
+///
+/// `order_by` columns are [`Field`]s, so they're validated against the same
+/// identifier allowlist as [`Filter`] before they can reach the compiled
+/// query.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryOptions {
+    /// Columns to sort by, applied in order (e.g. newest-first is
+    /// `[(Field::new("n")?, Order::Desc)]`).
+    pub order_by: Vec<(Field, Order)>,
+    /// Maximum number of rows to return.
+    pub limit: Option<usize>,
+    /// Number of matching rows to skip before collecting `limit` of them.
+    pub offset: Option<usize>,
+}
+
+/// One page of results from [`get_documents_paged`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    /// The rows in this page.
+    pub items: Vec<T>,
+    /// The `offset` to pass for the next page, or `None` once this page
+    /// came back shorter than the requested `limit` (so there's nothing
+    /// left to fetch).
+    pub next_offset: Option<usize>,
+}
+
+/// Retrieves a page of documents from a Ditto collection, ordered and
+/// sliced per `opts`, appending `ORDER BY`/`LIMIT`/`OFFSET` clauses to the
+/// same filtered `SELECT` [`get_documents`] builds.
+///
+/// Lets a caller lazily scroll chat history or the newest N map items
+/// instead of pulling the whole collection.
+pub async fn get_documents_paged<T: DeserializeOwned>(
+    ditto: &Ditto,
+    collection: &str,
+    filter: Option<&Filter>,
+    opts: &QueryOptions,
+) -> Result<Page<T>, CotError> {
+    let store = ditto.store();
+    let mut query = match filter {
+        Some(f) => format!("SELECT * FROM {} WHERE {}", collection, f.to_dql()),
+        None => format!("SELECT * FROM {}", collection),
+    };
+
+    if !opts.order_by.is_empty() {
+        let columns = opts
+            .order_by
+            .iter()
+            .map(|(field, order)| format!("{} {}", field, order.to_dql()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        query.push_str(&format!(" ORDER BY {}", columns));
+    }
+    if let Some(limit) = opts.limit {
+        query.push_str(&format!(" LIMIT {}", limit));
+    }
+    if let Some(offset) = opts.offset {
+        query.push_str(&format!(" OFFSET {}", offset));
+    }
+
+    let result = store
+        .execute_v2(&query)
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+
+    let items = result
+        .iter()
+        .map(|item| {
+            item.deserialize_value::<T>()
+                .map_err(|e| CotError::Format(e.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let next_offset = match opts.limit {
+        Some(limit) if items.len() == limit => Some(opts.offset.unwrap_or(0) + items.len()),
+        _ => None,
+    };
+
+    Ok(Page { items, next_offset })
+}
+
+/// Row shape for the `COUNT(*)` query [`count_documents`] issues.
+#[derive(Debug, Clone, Deserialize)]
+struct CountRow {
+    count: u64,
+}
+
+/// Counts the documents in `collection` matching `filter`, via a `SELECT
+/// COUNT(*)` instead of [`get_documents`] plus `.len()` — the store
+/// aggregates server-side, so a caller showing "N active emergencies"
+/// doesn't pay to deserialize every row just to throw away everything but
+/// its count.
+pub async fn count_documents(
+    ditto: &Ditto,
+    collection: &str,
+    filter: Option<&Filter>,
+) -> Result<u64, CotError> {
+    let store = ditto.store();
+    let query = match filter {
+        Some(f) => format!(
+            "SELECT COUNT(*) as count FROM {} WHERE {}",
+            collection,
+            f.to_dql()
+        ),
+        None => format!("SELECT COUNT(*) as count FROM {}", collection),
+    };
+
+    let result = store
+        .execute_v2(&query)
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+
+    let row = result
+        .iter()
+        .next()
+        .ok_or_else(|| CotError::Format("COUNT query returned no rows".to_string()))?;
+    let count_row = row
+        .deserialize_value::<CountRow>()
+        .map_err(|e| CotError::Format(e.to_string()))?;
+
+    Ok(count_row.count)
+}
+
+/// Row shape for the grouped `COUNT(*)` query [`read_index`] issues.
+#[derive(Debug, Clone, Deserialize)]
+struct IndexRow {
+    key: String,
+    count: u64,
+}
+
+/// Builds a per-key document count for `collection` — e.g. chat message
+/// count per `room_id`, or active-document count per `emergency_type` —
+/// via a single `GROUP BY` aggregate query rather than pulling every row
+/// through [`get_documents`] and counting client-side.
+///
+/// `group_by` is validated the same as any other [`Field`], so it can't
+/// carry injected DQL.
+pub async fn read_index(
+    ditto: &Ditto,
+    collection: &str,
+    group_by: &Field,
+) -> Result<HashMap<String, u64>, CotError> {
+    let store = ditto.store();
+    let query = format!(
+        "SELECT {group_by} as key, COUNT(*) as count FROM {collection} GROUP BY {group_by}"
+    );
+
+    let result = store
+        .execute_v2(&query)
+        .await
+        .map_err(|e| CotError::Format(e.to_string()))?;
+
+    result
+        .iter()
+        .map(|item| {
+            item.deserialize_value::<IndexRow>()
+                .map(|row| (row.key, row.count))
+                .map_err(|e| CotError::Format(e.to_string()))
+        })
+        .collect()
+}
+
 /// Retrieves a single document by ID from a collection
 ///
 /// # Type Parameters
@@ -109,13 +397,20 @@ pub async fn get_document<T: DeserializeOwned>(
     collection: &str,
     id: &str,
 ) -> Result<Option<T>, CotError> {
-    let query = format!("_id = '{}'", id);
-    let mut results = get_documents::<T>(ditto, collection, Some(&query)).await?;
+    let filter = Filter::by_id(id);
+    let mut results = get_documents::<T>(ditto, collection, Some(&filter)).await?;
     Ok(results.pop())
 }
 
 /// Updates a document in a Ditto collection
 ///
+/// `updates` must serialize to a JSON object; each top-level field becomes
+/// one `field = value` assignment, the field name validated through
+/// [`Field::new`] and the value escaped through [`FilterValue::to_dql`] —
+/// this used to string-trim `updates`'s JSON into the `SET` clause verbatim,
+/// which broke (or let a caller inject DQL through) any string field
+/// containing a quote.
+///
 /// # Parameters
 /// - `ditto`: The Ditto instance to use
 /// - `collection`: The name of the collection containing the document
@@ -134,13 +429,26 @@ pub async fn update_document<T: Serialize>(
     let updates_json =
         serde_json::to_value(updates).map_err(|e| CotError::Format(e.to_string()))?;
 
-    // Convert the updates to a JSON string, removing the outer braces
-    let updates_str = updates_json.to_string();
-    let updates_content = updates_str.trim_start_matches('{').trim_end_matches('}');
+    let serde_json::Value::Object(fields) = updates_json else {
+        return Err(CotError::Format(
+            "update_document requires `updates` to serialize to a JSON object".to_string(),
+        ));
+    };
+
+    let assignments = fields
+        .into_iter()
+        .map(|(key, value)| {
+            let field = Field::new(key).map_err(|e| CotError::Format(e.to_string()))?;
+            Ok(format!("{field} = {}", FilterValue::from(value).to_dql()))
+        })
+        .collect::<Result<Vec<String>, CotError>>()?
+        .join(", ");
 
     let query = format!(
-        "UPDATE {} SET {} WHERE _id = '{}'",
-        collection, updates_content, doc_id
+        "UPDATE {} SET {} WHERE {}",
+        collection,
+        assignments,
+        Filter::by_id(doc_id).to_dql()
     );
 
     store
@@ -151,6 +459,42 @@ pub async fn update_document<T: Serialize>(
     Ok(())
 }
 
+/// Reads the current document for `event`'s uid (if any), causally merges
+/// the incoming transform into it via
+/// [`CotDocument::merge_with_conflicts`](crate::ditto::CotDocument::merge_with_conflicts),
+/// and writes the combined result back, instead of [`update_document`]'s
+/// blind overwrite clobbering whatever another peer wrote concurrently.
+///
+/// # Returns
+/// `true` if the two sides' version vectors were found concurrent (a real
+/// conflict the merge's `d_v`/peer-key fallback resolved arbitrarily rather
+/// than causally), `false` if this was either a fresh insert or a clean
+/// causal successor.
+pub async fn insert_or_merge_cot_event(
+    ditto: &Ditto,
+    event: &CotEvent,
+    peer_key: &str,
+) -> Result<bool, CotError> {
+    let incoming = ditto::cot_to_document(event, peer_key);
+    let collection = incoming.get_collection_name();
+    let id = ditto::sync_dag::doc_id(&incoming);
+
+    let current = get_document::<CotDocument>(ditto, collection, &id).await?;
+
+    let (merged, concurrent) = match current {
+        Some(existing) => {
+            let outcome = existing
+                .merge_with_conflicts(&incoming)
+                .map_err(|e| CotError::Format(e.to_string()))?;
+            (outcome.document, outcome.concurrent)
+        }
+        None => (incoming, false),
+    };
+
+    insert_document(ditto, collection, &merged).await?;
+    Ok(concurrent)
+}
+
 /// Deletes a document from a Ditto collection
 ///
 /// # Parameters
@@ -166,7 +510,11 @@ pub async fn delete_document(
     doc_id: &str,
 ) -> Result<(), CotError> {
     let store = ditto.store();
-    let query = format!("DELETE FROM {} WHERE _id = '{}'", collection, doc_id);
+    let query = format!(
+        "DELETE FROM {} WHERE {}",
+        collection,
+        Filter::by_id(doc_id).to_dql()
+    );
 
     store
         .execute_v2(&query)
@@ -19,9 +19,23 @@ pub enum CotError {
     #[error("XML error: {0}")]
     XmlError(String),
 
-    /// Failed to parse XML content
-    #[error("XML parse error: {0}")]
-    XmlParse(String),
+    /// Failed to parse XML content, with the source location the failure
+    /// occurred at so a caller can point a user at the offending markup
+    /// instead of guessing where in a large detail blob things went wrong.
+    #[error("XML parse error at line {line}, col {column}: {message}")]
+    XmlParse {
+        /// Human-readable description of the parse failure.
+        message: String,
+        /// Byte offset into the original input where the failure occurred.
+        byte_offset: usize,
+        /// 1-based line number.
+        line: u32,
+        /// 1-based column number.
+        column: u32,
+        /// A short caret-underlined snippet of the surrounding markup, in
+        /// the style of a compiler diagnostic.
+        context: String,
+    },
 
     /// A required field was missing from the input
     #[error("Missing required field: {0}")]
@@ -55,6 +69,92 @@ pub enum CotError {
     /// An error occurred during JSON serialization/deserialization
     #[error("JSON serialization error: {0}")]
     Json(#[from] serde_json::Error),
+
+    /// The input's character encoding could not be determined or is not supported
+    #[error("Unsupported or undeclared encoding: {0}")]
+    UnsupportedEncoding(String),
+
+    /// An error occurred while encoding a document to MessagePack
+    #[error("MessagePack encode error: {0}")]
+    MsgpackEncode(String),
+
+    /// An error occurred while decoding a document from MessagePack
+    #[error("MessagePack decode error: {0}")]
+    MsgpackDecode(String),
+
+    /// An error occurred while encoding a document to Avro
+    #[error("Avro encode error: {0}")]
+    AvroEncode(String),
+
+    /// An error occurred while decoding a document from Avro
+    #[error("Avro decode error: {0}")]
+    AvroDecode(String),
+
+    /// An error occurred while encoding a document to BSON
+    #[error("BSON encode error: {0}")]
+    BsonEncode(String),
+
+    /// An error occurred while decoding a document from BSON
+    #[error("BSON decode error: {0}")]
+    BsonDecode(String),
+
+    /// An error occurred while encoding a document to TAK Protobuf
+    #[error("Protobuf encode error: {0}")]
+    ProtoEncode(String),
+
+    /// An error occurred while decoding a document from TAK Protobuf
+    #[error("Protobuf decode error: {0}")]
+    ProtoDecode(String),
+
+    /// A document failed structural validation
+    /// (see [`validation`](crate::ditto::validation)).
+    #[error("document failed validation: {} field(s) rejected", errors.len())]
+    Validation {
+        /// Every field that failed validation, not just the first.
+        errors: Vec<crate::ditto::validation::FieldError>,
+    },
+
+    /// Several errors occurred in a single conversion attempt, gathered by
+    /// a "checked" entry point (e.g.
+    /// [`cot_to_document_checked`](crate::ditto::to_ditto::cot_to_document_checked))
+    /// that validates every field up front instead of stopping at the
+    /// first failure.
+    #[error("{} errors occurred during conversion", .0.len())]
+    Multiple(Vec<CotError>),
+
+    /// An error occurred in the embedded `cot-store` persistence layer
+    #[cfg(feature = "cot-store")]
+    #[error("CoT store error: {0}")]
+    Store(String),
+
+    /// A sync token referred to a point in history that's since been
+    /// pruned or compacted away (see
+    /// [`SyncDag::compact`](crate::ditto::sync_dag::SyncDag::compact)), so
+    /// the requested diff can no longer be computed completely; the caller
+    /// should discard the token and perform a full re-sync instead.
+    #[error("sync token refers to pruned history; a full re-sync is required")]
+    TokenExpired,
+}
+
+impl CotError {
+    /// Iterates over every error this carries, flattening nested
+    /// [`CotError::Multiple`] values so a caller never has to recurse
+    /// themselves to walk a batch-reported conversion failure.
+    pub fn iter_errors(&self) -> Box<dyn Iterator<Item = &CotError> + '_> {
+        match self {
+            CotError::Multiple(errors) => Box::new(errors.iter().flat_map(CotError::iter_errors)),
+            other => Box::new(std::iter::once(other)),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a CotError {
+    type Item = &'a CotError;
+    type IntoIter = Box<dyn Iterator<Item = &'a CotError> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_errors()
+    }
 }
 
 impl From<quick_xml::Error> for CotError {
@@ -63,6 +163,33 @@ impl From<quick_xml::Error> for CotError {
     }
 }
 
+impl CotError {
+    /// Builds a [`CotError::XmlParse`] whose `line`/`column`/`context` are
+    /// computed from `byte_offset` into `source` (typically a reader's
+    /// `buffer_position()` at the point a parse error was observed).
+    pub fn xml_parse_at(source: &str, byte_offset: usize, message: impl Into<String>) -> Self {
+        let offset = byte_offset.min(source.len());
+        let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+        let line = source[..offset].matches('\n').count() as u32 + 1;
+        let column = (offset - line_start) as u32 + 1;
+
+        let line_text = &source[line_start..line_end];
+        let caret = " ".repeat(offset - line_start);
+        let context = format!("{line_text}\n{caret}^");
+
+        CotError::XmlParse {
+            message: message.into(),
+            byte_offset: offset,
+            line,
+            column,
+            context,
+        }
+    }
+}
+
 impl From<std::string::FromUtf8Error> for CotError {
     fn from(err: std::string::FromUtf8Error) -> Self {
         CotError::XmlError(err.to_string())
@@ -74,3 +201,158 @@ impl From<AttrError> for CotError {
         CotError::XmlError(err.to_string())
     }
 }
+
+/// Failure modes for converting a [`CotEvent`](crate::cot_events::CotEvent)
+/// into a Ditto document that the crate's lenient, infallible conversion
+/// functions (e.g. `cot_to_document`) would otherwise paper over with a
+/// silent default (`unwrap_or_default()`, `as_f64().unwrap_or(0.0)`, and
+/// similar). [`try_cot_to_document`](crate::ditto::to_ditto::try_cot_to_document)
+/// surfaces these instead of masking them, so a caller can distinguish "bad
+/// input" from "unsupported type" and log a useful diagnostic.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CotConversionError {
+    /// A coordinate or accuracy field was NaN or infinite, which would
+    /// otherwise silently become `0.0` on the way to a Ditto document.
+    #[error("field '{field}' is not a finite number: {value}")]
+    NonFiniteCoordinate {
+        /// The `Point` field that failed the check (e.g. `"lat"`).
+        field: &'static str,
+        /// The offending value.
+        value: f64,
+    },
+
+    /// A timestamp's microsecond representation falls outside the range
+    /// `f64` can represent exactly, which would otherwise silently lose
+    /// precision when the value is carried through Ditto's numeric fields.
+    #[error("field '{field}' is out of the range a Ditto timestamp can represent exactly")]
+    TimestampOutOfRange {
+        /// The event field that failed the check (e.g. `"stale"`).
+        field: &'static str,
+    },
+
+    /// A detail element required to build this event type's document is
+    /// missing entirely.
+    #[error("missing required detail element: {0}")]
+    MissingRequiredDetail(&'static str),
+
+    /// A chat event's `<detail>` carried a `<remarks>` element, but its
+    /// text content was empty.
+    #[error("chat event detail is malformed")]
+    MalformedChatDetail,
+
+    /// Reading the next `<event>` block from a streaming source failed at
+    /// the I/O layer, before any XML parsing was attempted.
+    #[error("I/O error reading CoT stream: {0}")]
+    Io(String),
+
+    /// A buffered `<event>...</event>` block could not be parsed into a
+    /// [`CotEvent`](crate::cot_events::CotEvent) at all.
+    #[error("failed to parse CoT XML: {0}")]
+    XmlParse(String),
+}
+
+/// Failure modes for [`CotDocument::merge`](crate::ditto::CotDocument::merge).
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// The two documents don't share the same `id`, so there's no single
+    /// document they could sensibly merge into.
+    #[error("cannot merge documents with different ids: '{local}' vs '{remote}'")]
+    IdMismatch {
+        /// `self`'s id.
+        local: String,
+        /// `other`'s id.
+        remote: String,
+    },
+
+    /// The two documents are different [`CotDocument`](crate::ditto::CotDocument)
+    /// variants (e.g. a `MapItem` and a `Chat`), which carry incompatible
+    /// field sets and can't be reconciled field-by-field.
+    #[error("cannot merge a {local} document with a {remote} document")]
+    KindMismatch {
+        /// `self`'s variant name.
+        local: &'static str,
+        /// `other`'s variant name.
+        remote: &'static str,
+    },
+}
+
+/// Failure modes for [`CotDocument::apply_delta`](crate::ditto::CotDocument::apply_delta).
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum DeltaError {
+    /// The delta's `id` doesn't match the document it's being applied to.
+    #[error("cannot apply a delta for '{delta_id}' to document '{doc_id}'")]
+    IdMismatch {
+        /// The id [`CotDelta::diff`](crate::ditto::delta::CotDelta::diff) recorded.
+        delta_id: String,
+        /// The id of the document
+        /// [`apply_delta`](crate::ditto::CotDocument::apply_delta) was
+        /// called on.
+        doc_id: String,
+    },
+
+    /// The document's own JSON representation wasn't an object, so there was
+    /// nothing to apply field-level changes to.
+    #[error("document is not a JSON object")]
+    NotAnObject,
+
+    /// The patched JSON no longer deserializes into a [`CotDocument`](crate::ditto::CotDocument).
+    #[error("patched document failed to deserialize: {0}")]
+    Deserialize(String),
+}
+
+impl From<std::io::Error> for CotConversionError {
+    fn from(err: std::io::Error) -> Self {
+        CotConversionError::Io(err.to_string())
+    }
+}
+
+impl From<CotError> for CotConversionError {
+    fn from(err: CotError) -> Self {
+        CotConversionError::XmlParse(err.to_string())
+    }
+}
+
+/// Failure modes for converting a Ditto document or flattened JSON document
+/// into a [`FlatCotEvent`](crate::model::FlatCotEvent) that the crate's
+/// lenient, infallible conversion functions (e.g. `flat_cot_event_from_ditto`)
+/// would otherwise paper over with a silent default
+/// (`unwrap_or(Value::Null)`, `unwrap_or_else(chrono::Utc::now)`, and
+/// similar). [`try_flat_cot_event_from_ditto`](crate::ditto::try_flat_cot_event_from_ditto)
+/// and [`try_flat_cot_event_from_flattened_json`](crate::ditto::try_flat_cot_event_from_flattened_json)
+/// surface these instead of masking them.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum FlatCotError {
+    /// A coordinate field was NaN or infinite, which would otherwise
+    /// silently become `0.0`.
+    #[error("field '{field}' is not a finite number: {value}")]
+    NonFiniteCoordinate {
+        /// The flat-event field that failed the check (e.g. `"lat"`).
+        field: &'static str,
+        /// The offending value.
+        value: f64,
+    },
+
+    /// An `r` map entry couldn't be converted to a `serde_json::Value`
+    /// (e.g. a non-finite number, which JSON can't represent), which would
+    /// otherwise be silently dropped from `detail_extra`.
+    #[error("field 'r.{key}' could not be serialized: {reason}")]
+    UnserializableRValue {
+        /// The `r` map key whose value failed to serialize.
+        key: String,
+        /// The underlying serialization failure, as text.
+        reason: String,
+    },
+
+    /// An `n`/`o` epoch value falls outside the range `chrono` can
+    /// represent as a UTC instant, which would otherwise silently become
+    /// "now".
+    #[error("field '{field}' is out of chrono's representable range")]
+    TimestampOutOfRange {
+        /// The event field that failed the check (e.g. `"stale"`).
+        field: &'static str,
+    },
+
+    /// The flattened JSON document wasn't a JSON object.
+    #[error("flattened document is not a JSON object")]
+    NonObjectJson,
+}
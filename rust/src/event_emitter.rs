@@ -0,0 +1,124 @@
+//! Type-dispatching event emitter for a stream of incoming [`CotEvent`]s.
+//!
+//! Modeled on the Matrix SDK's event-emitter pattern: implement
+//! [`EventEmitter`] for whatever state your application tracks, override
+//! only the handlers you care about (every method defaults to a no-op), and
+//! pass each incoming event to [`dispatch_event`], which classifies its
+//! `event_type` prefix and calls the matching handler. This gives
+//! applications a clean subscription surface instead of hand-writing a
+//! match on `event_type` at every call site that consumes events.
+
+use crate::cot_events::CotEvent;
+
+/// Subscribes to a stream of incoming [`CotEvent`]s by type. Every handler
+/// defaults to a no-op, so an implementer only overrides the events it
+/// cares about.
+pub trait EventEmitter {
+    /// Called for a location update (`a-*`).
+    fn on_location_update(&mut self, event: &CotEvent) {
+        let _ = event;
+    }
+
+    /// Called for a chat message (`b-t-f`).
+    fn on_chat_message(&mut self, event: &CotEvent) {
+        let _ = event;
+    }
+
+    /// Called for an emergency alert or cancellation (`b-a-o-*`).
+    fn on_emergency(&mut self, event: &CotEvent) {
+        let _ = event;
+    }
+
+    /// Called for a deletion/tombstone (`t-x-d-d`), e.g. one built by
+    /// [`CotEvent::new_deletion`].
+    fn on_deletion(&mut self, event: &CotEvent) {
+        let _ = event;
+    }
+
+    /// Called for any `event_type` none of the other handlers match.
+    fn on_other(&mut self, event: &CotEvent) {
+        let _ = event;
+    }
+}
+
+/// Classifies `event`'s `event_type` prefix and calls the matching
+/// [`EventEmitter`] handler, falling back to [`EventEmitter::on_other`] for
+/// anything unrecognized.
+pub fn dispatch_event(emitter: &mut impl EventEmitter, event: &CotEvent) {
+    let event_type = event.event_type.as_str();
+    if event_type.starts_with("a-") {
+        emitter.on_location_update(event);
+    } else if event_type.starts_with("b-t-f") {
+        emitter.on_chat_message(event);
+    } else if event_type.starts_with("b-a-o-") {
+        emitter.on_emergency(event);
+    } else if event_type.starts_with("t-x-d-d") {
+        emitter.on_deletion(event);
+    } else {
+        emitter.on_other(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingEmitter {
+        location_updates: u32,
+        chat_messages: u32,
+        emergencies: u32,
+        deletions: u32,
+        other: u32,
+    }
+
+    impl EventEmitter for RecordingEmitter {
+        fn on_location_update(&mut self, _event: &CotEvent) {
+            self.location_updates += 1;
+        }
+        fn on_chat_message(&mut self, _event: &CotEvent) {
+            self.chat_messages += 1;
+        }
+        fn on_emergency(&mut self, _event: &CotEvent) {
+            self.emergencies += 1;
+        }
+        fn on_deletion(&mut self, _event: &CotEvent) {
+            self.deletions += 1;
+        }
+        fn on_other(&mut self, _event: &CotEvent) {
+            self.other += 1;
+        }
+    }
+
+    fn event_of_type(event_type: &str) -> CotEvent {
+        let mut event = CotEvent::default();
+        event.event_type = event_type.to_string();
+        event
+    }
+
+    #[test]
+    fn dispatch_event_routes_each_type_to_its_handler() {
+        let mut emitter = RecordingEmitter::default();
+
+        dispatch_event(&mut emitter, &event_of_type("a-f-G-U-C"));
+        dispatch_event(&mut emitter, &event_of_type("b-t-f"));
+        dispatch_event(&mut emitter, &event_of_type("b-a-o-tbl"));
+        dispatch_event(&mut emitter, &event_of_type("t-x-d-d"));
+        dispatch_event(&mut emitter, &event_of_type("u-d-f"));
+
+        assert_eq!(emitter.location_updates, 1);
+        assert_eq!(emitter.chat_messages, 1);
+        assert_eq!(emitter.emergencies, 1);
+        assert_eq!(emitter.deletions, 1);
+        assert_eq!(emitter.other, 1);
+    }
+
+    #[test]
+    fn default_handlers_are_no_ops() {
+        struct NoopEmitter;
+        impl EventEmitter for NoopEmitter {}
+
+        let mut emitter = NoopEmitter;
+        dispatch_event(&mut emitter, &event_of_type("a-f-G-U-C"));
+    }
+}
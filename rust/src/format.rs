@@ -0,0 +1,131 @@
+//! Pluggable wire-format encoding for [`FlatCotEvent`], the crate's
+//! format-neutral intermediate representation.
+//!
+//! [`xml_writer::to_cot_xml`](crate::xml_writer::to_cot_xml) and
+//! [`xml_parser::parse_cot`](crate::xml_parser::parse_cot) have always
+//! treated TAK XML as the only wire form a `FlatCotEvent` could round-trip
+//! through, but the struct itself carries no XML-specific state — callers
+//! increasingly want to hand the same event to a JSON API or ship it over a
+//! constrained link as MessagePack instead. [`CotFormat`] is a small
+//! `encode`/`decode` trait (in the spirit of ilc's multi-backend `Format`
+//! trait) with [`XmlFormat`], [`JsonFormat`], and [`MsgpackFormat`]
+//! implementations, so a caller picks a serializer at runtime instead of the
+//! crate hardcoding one.
+
+use crate::error::CotError;
+use crate::model::FlatCotEvent;
+use crate::xml_parser::parse_cot;
+use crate::xml_writer::to_cot_xml;
+
+/// Encodes and decodes a [`FlatCotEvent`] to and from a particular wire
+/// format.
+pub trait CotFormat {
+    /// Serializes `event` to this format's byte representation.
+    fn encode(&self, event: &FlatCotEvent) -> Result<Vec<u8>, CotError>;
+    /// Deserializes a [`FlatCotEvent`] previously written by [`Self::encode`].
+    fn decode(&self, bytes: &[u8]) -> Result<FlatCotEvent, CotError>;
+}
+
+/// TAK CoT XML, via [`to_cot_xml`] and [`parse_cot`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XmlFormat;
+
+impl CotFormat for XmlFormat {
+    fn encode(&self, event: &FlatCotEvent) -> Result<Vec<u8>, CotError> {
+        Ok(to_cot_xml(event).into_bytes())
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<FlatCotEvent, CotError> {
+        let xml = std::str::from_utf8(bytes).map_err(|e| CotError::XmlError(e.to_string()))?;
+        parse_cot(xml)
+    }
+}
+
+/// Plain JSON, via `FlatCotEvent`'s `Serialize`/`Deserialize` impls.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormat;
+
+impl CotFormat for JsonFormat {
+    fn encode(&self, event: &FlatCotEvent) -> Result<Vec<u8>, CotError> {
+        Ok(serde_json::to_vec(event)?)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<FlatCotEvent, CotError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// MessagePack, matching [`ditto::msgpack`](crate::ditto::msgpack)'s
+/// `rmp_serde::to_vec_named`/`from_slice` convention.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MsgpackFormat;
+
+impl CotFormat for MsgpackFormat {
+    fn encode(&self, event: &FlatCotEvent) -> Result<Vec<u8>, CotError> {
+        rmp_serde::to_vec_named(event).map_err(|e| CotError::MsgpackEncode(e.to_string()))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<FlatCotEvent, CotError> {
+        rmp_serde::from_slice(bytes).map_err(|e| CotError::MsgpackDecode(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> FlatCotEvent {
+        FlatCotEvent {
+            uid: "ANDROID-deadbeef".to_string(),
+            type_: "a-f-G-U-C".to_string(),
+            time: "2023-01-01T00:00:00Z".to_string(),
+            start: "2023-01-01T00:00:00Z".to_string(),
+            stale: "2023-01-01T00:00:00Z".to_string(),
+            how: "h-g-i-g-o".to_string(),
+            lat: 34.12345,
+            lon: -118.12345,
+            hae: 150.0,
+            ce: 10.0,
+            le: 20.0,
+            callsign: Some("ALPHA-1".to_string()),
+            group_name: Some("Blue".to_string()),
+            group_role: None,
+            speed: None,
+            course: None,
+            tz_offset_secs: None,
+            detail_extra: Default::default(),
+            extra_attrs: Default::default(),
+        }
+    }
+
+    #[test]
+    fn xml_format_round_trips() {
+        let original = event();
+        let bytes = XmlFormat.encode(&original).unwrap();
+        let decoded = XmlFormat.decode(&bytes).unwrap();
+        assert_eq!(decoded.uid, original.uid);
+        assert_eq!(decoded.callsign, original.callsign);
+    }
+
+    #[test]
+    fn json_format_round_trips() {
+        let original = event();
+        let bytes = JsonFormat.encode(&original).unwrap();
+        let decoded = JsonFormat.decode(&bytes).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn msgpack_format_round_trips() {
+        let original = event();
+        let bytes = MsgpackFormat.encode(&original).unwrap();
+        let decoded = MsgpackFormat.decode(&bytes).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn msgpack_decode_of_garbage_is_a_typed_error() {
+        let err = MsgpackFormat.decode(&[0xff, 0x00, 0x01]).unwrap_err();
+        assert!(matches!(err, CotError::MsgpackDecode(_)));
+    }
+}
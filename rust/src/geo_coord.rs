@@ -0,0 +1,398 @@
+//! WGS84 coordinate conversions for [`Point`](crate::cot_events::Point):
+//! geocentric ECEF, UTM, and MGRS, alongside the lat/lon/hae it already
+//! stores.
+//!
+//! TAK tooling routinely displays and ingests MGRS grid references and
+//! needs geocentric coordinates for line-of-sight/range math, but `Point`
+//! only ever carried WGS84 lat/lon/hae. The conversions live in this
+//! standalone module (rather than inline in `cot_events.rs`) because the
+//! UTM/MGRS formulas are self-contained numerical routines with no
+//! dependency on the rest of `Point`'s CoT-specific fields; `cot_events`
+//! exposes them as `Point::to_ecef`/`to_utm`/`to_mgrs` and their inverses.
+//!
+//! MGRS support here is the common non-polar case (UTM zones 1-60, bands
+//! C-X): the UPS polar zones (A, B, Y, Z) are out of scope, matching this
+//! crate's existing WGS84-only geodesy.
+
+use crate::cot_events::Point;
+
+/// WGS84 semi-major axis, in meters.
+const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+fn wgs84_e2() -> f64 {
+    2.0 * WGS84_F - WGS84_F * WGS84_F
+}
+
+/// UTM false-easting/northing scale factor.
+const UTM_K0: f64 = 0.9996;
+
+/// Which hemisphere a [`UtmCoordinate`]'s northing is measured from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hemisphere {
+    /// Northing measured north of the equator.
+    North,
+    /// Northing measured from a 10,000,000 m false origin south of the
+    /// equator.
+    South,
+}
+
+/// A point in the Universal Transverse Mercator grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UtmCoordinate {
+    /// UTM longitude zone, `1..=60`.
+    pub zone: u8,
+    /// Which side of the equator `northing` is measured from.
+    pub hemisphere: Hemisphere,
+    /// Meters east of the zone's central meridian, false-origin-adjusted.
+    pub easting: f64,
+    /// Meters north of the equator (or the southern false origin).
+    pub northing: f64,
+}
+
+/// Failure modes for [`Point::from_mgrs`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MgrsParseError {
+    /// The string was too short to contain a zone, band, and 100km square ID.
+    #[error("MGRS string '{0}' is too short")]
+    TooShort(String),
+
+    /// The zone number prefix didn't parse as `1..=60`.
+    #[error("invalid UTM zone in MGRS string '{0}'")]
+    InvalidZone(String),
+
+    /// The latitude band letter wasn't one of `C-X` (excluding `I`/`O`).
+    #[error("invalid latitude band letter '{0}'")]
+    InvalidBand(char),
+
+    /// The 100km-square column or row letter wasn't in its expected set.
+    #[error("invalid 100km grid square ID '{0}'")]
+    InvalidSquareId(String),
+
+    /// The easting/northing digit string had an odd length or held a
+    /// non-digit character.
+    #[error("invalid easting/northing digits '{0}'")]
+    InvalidDigits(String),
+}
+
+const COLUMN_SETS: [&str; 3] = ["ABCDEFGH", "JKLMNPQR", "STUVWXYZ"];
+const ROW_SET_ODD: &str = "ABCDEFGHJKLMNPQRSTUV";
+const ROW_SET_EVEN: &str = "FGHJKLMNPQRSTUVABCDE";
+const BAND_LETTERS: &str = "CDEFGHJKLMNPQRSTUVWXX";
+
+impl Point {
+    /// Converts to Earth-Centered, Earth-Fixed geocentric coordinates
+    /// `(x, y, z)`, in meters, on the WGS84 ellipsoid.
+    pub fn to_ecef(&self) -> (f64, f64, f64) {
+        let e2 = wgs84_e2();
+        let lat = self.lat.to_radians();
+        let lon = self.lon.to_radians();
+        let (sin_lat, cos_lat) = (lat.sin(), lat.cos());
+        let n = WGS84_A / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+
+        let x = (n + self.hae) * cos_lat * lon.cos();
+        let y = (n + self.hae) * cos_lat * lon.sin();
+        let z = (n * (1.0 - e2) + self.hae) * sin_lat;
+        (x, y, z)
+    }
+
+    /// Builds a [`Point`] from ECEF geocentric coordinates, via Bowring's
+    /// iterative method. `ce`/`le` are set to the same "unknown" default
+    /// [`Point::new`] uses.
+    pub fn from_ecef(x: f64, y: f64, z: f64) -> Self {
+        let e2 = wgs84_e2();
+        let lon = y.atan2(x);
+        let p = (x * x + y * y).sqrt();
+
+        let mut lat = (z / (p * (1.0 - e2))).atan();
+        for _ in 0..5 {
+            let sin_lat = lat.sin();
+            let n = WGS84_A / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+            let h = p / lat.cos() - n;
+            lat = (z / (p * (1.0 - e2 * n / (n + h)))).atan();
+        }
+        let sin_lat = lat.sin();
+        let n = WGS84_A / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+        let h = p / lat.cos() - n;
+
+        Point::new(lat.to_degrees(), lon.to_degrees(), h)
+    }
+
+    /// Converts to a UTM grid coordinate, via Snyder's transverse Mercator
+    /// series. `hae` has no UTM equivalent and is dropped.
+    pub fn to_utm(&self) -> UtmCoordinate {
+        let e2 = wgs84_e2();
+        let e2p = e2 / (1.0 - e2);
+
+        let zone = utm_zone_for_lon(self.lon);
+        let lon0 = zone_central_meridian(zone).to_radians();
+        let lat = self.lat.to_radians();
+        let lon = self.lon.to_radians();
+
+        let sin_lat = lat.sin();
+        let n = WGS84_A / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+        let t = lat.tan().powi(2);
+        let c = e2p * lat.cos().powi(2);
+        let aa = (lon - lon0) * lat.cos();
+        let m = meridional_arc(lat, e2);
+
+        let easting = UTM_K0
+            * n
+            * (aa + (1.0 - t + c) * aa.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * e2p) * aa.powi(5) / 120.0)
+            + 500_000.0;
+        let mut northing = UTM_K0
+            * (m + n
+                * lat.tan()
+                * (aa * aa / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * aa.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * e2p) * aa.powi(6) / 720.0));
+
+        let hemisphere = if self.lat < 0.0 {
+            northing += 10_000_000.0;
+            Hemisphere::South
+        } else {
+            Hemisphere::North
+        };
+
+        UtmCoordinate { zone, hemisphere, easting, northing }
+    }
+
+    /// Builds a [`Point`] from a UTM grid coordinate, via Snyder's inverse
+    /// series. `hae` is set to `0.0`, since UTM carries no height.
+    pub fn from_utm(utm: &UtmCoordinate) -> Self {
+        let e2 = wgs84_e2();
+        let e2p = e2 / (1.0 - e2);
+        let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+
+        let x = utm.easting - 500_000.0;
+        let y = match utm.hemisphere {
+            Hemisphere::North => utm.northing,
+            Hemisphere::South => utm.northing - 10_000_000.0,
+        };
+
+        let m = y / UTM_K0;
+        let mu = m / (WGS84_A * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+
+        let phi1 = mu
+            + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+            + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+            + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+            + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+        let sin_phi1 = phi1.sin();
+        let n1 = WGS84_A / (1.0 - e2 * sin_phi1 * sin_phi1).sqrt();
+        let t1 = phi1.tan().powi(2);
+        let c1 = e2p * phi1.cos().powi(2);
+        let r1 = WGS84_A * (1.0 - e2) / (1.0 - e2 * sin_phi1 * sin_phi1).powf(1.5);
+        let d = x / (n1 * UTM_K0);
+
+        let lat = phi1
+            - (n1 * phi1.tan() / r1)
+                * (d * d / 2.0
+                    - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * e2p) * d.powi(4) / 24.0
+                    + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * e2p
+                        - 3.0 * c1 * c1)
+                        * d.powi(6)
+                        / 720.0);
+        let lon0 = zone_central_meridian(utm.zone).to_radians();
+        let lon = lon0
+            + (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+                + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * e2p + 24.0 * t1 * t1)
+                    * d.powi(5)
+                    / 120.0)
+                / phi1.cos();
+
+        Point::new(lat.to_degrees(), lon.to_degrees(), 0.0)
+    }
+
+    /// Formats as an MGRS grid reference with 1-meter precision (5 digits
+    /// each for easting and northing).
+    pub fn to_mgrs(&self) -> String {
+        let utm = self.to_utm();
+        let band = latitude_band(self.lat);
+        let col = COLUMN_SETS[((utm.zone - 1) % 3) as usize]
+            .as_bytes()[(utm.easting / 100_000.0) as usize - 1] as char;
+        let row_set = if utm.zone % 2 == 1 { ROW_SET_ODD } else { ROW_SET_EVEN };
+        let row = row_set.as_bytes()[(utm.northing / 100_000.0) as usize % 20] as char;
+
+        let easting_rem = (utm.easting as i64).rem_euclid(100_000);
+        let northing_rem = (utm.northing as i64).rem_euclid(100_000);
+
+        format!(
+            "{}{}{}{}{:05}{:05}",
+            utm.zone, band, col, row, easting_rem, northing_rem
+        )
+    }
+
+    /// Parses an MGRS grid reference produced by [`Self::to_mgrs`] (or any
+    /// other equal-digit-count easting/northing precision). `hae` is set to
+    /// `0.0`, since MGRS carries no height.
+    pub fn from_mgrs(mgrs: &str) -> Result<Self, MgrsParseError> {
+        let mgrs = mgrs.trim();
+        if mgrs.len() < 5 {
+            return Err(MgrsParseError::TooShort(mgrs.to_string()));
+        }
+
+        let zone_digits = mgrs.chars().take_while(|c| c.is_ascii_digit()).count();
+        let zone: u8 = mgrs[..zone_digits]
+            .parse()
+            .map_err(|_| MgrsParseError::InvalidZone(mgrs.to_string()))?;
+        if !(1..=60).contains(&zone) {
+            return Err(MgrsParseError::InvalidZone(mgrs.to_string()));
+        }
+
+        let rest = &mgrs[zone_digits..];
+        let mut chars = rest.chars();
+        let band = chars.next().ok_or_else(|| MgrsParseError::TooShort(mgrs.to_string()))?;
+        let band_idx = BAND_LETTERS
+            .find(band.to_ascii_uppercase())
+            .ok_or(MgrsParseError::InvalidBand(band))?;
+
+        let col = chars.next().ok_or_else(|| MgrsParseError::TooShort(mgrs.to_string()))?;
+        let row = chars.next().ok_or_else(|| MgrsParseError::TooShort(mgrs.to_string()))?;
+        let col_idx = COLUMN_SETS[((zone - 1) % 3) as usize]
+            .find(col.to_ascii_uppercase())
+            .ok_or_else(|| MgrsParseError::InvalidSquareId(rest.to_string()))?;
+        let row_set = if zone % 2 == 1 { ROW_SET_ODD } else { ROW_SET_EVEN };
+        let row_idx = row_set
+            .find(row.to_ascii_uppercase())
+            .ok_or_else(|| MgrsParseError::InvalidSquareId(rest.to_string()))?;
+
+        let digits: String = chars.collect();
+        if digits.is_empty() || digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(MgrsParseError::InvalidDigits(digits));
+        }
+        let half = digits.len() / 2;
+        let scale = 10f64.powi(5 - half as i32);
+        let easting_digits: f64 = digits[..half].parse().unwrap_or(0.0);
+        let northing_digits: f64 = digits[half..].parse().unwrap_or(0.0);
+
+        let easting = col_idx as f64 * 100_000.0 + easting_digits * scale;
+        // The 100km row band repeats every 2,000,000m; approximate the
+        // northing by assuming the band letter's southernmost occurrence
+        // within the latitude band this grid zone covers.
+        let band_lat_south = band_idx as f64 * 8.0 - 80.0;
+        let approx_northing = if band_lat_south < 0.0 {
+            (band_lat_south / 8.0) * 1_100_000.0 + 10_000_000.0
+        } else {
+            (band_lat_south / 8.0) * 1_100_000.0
+        };
+        let row_band_base = (approx_northing / 2_000_000.0).floor() * 2_000_000.0;
+        let mut northing = row_band_base + row_idx as f64 * 100_000.0 + northing_digits * scale;
+        while northing < approx_northing - 1_000_000.0 {
+            northing += 2_000_000.0;
+        }
+        while northing > approx_northing + 1_000_000.0 {
+            northing -= 2_000_000.0;
+        }
+
+        let hemisphere = if band_idx < 10 { Hemisphere::South } else { Hemisphere::North };
+        let northing = if hemisphere == Hemisphere::South && northing < 0.0 {
+            northing + 10_000_000.0
+        } else {
+            northing
+        };
+
+        Ok(Point::from_utm(&UtmCoordinate { zone, hemisphere, easting, northing }))
+    }
+}
+
+fn utm_zone_for_lon(lon_deg: f64) -> u8 {
+    let zone = ((lon_deg + 180.0) / 6.0).floor() as i32 + 1;
+    zone.clamp(1, 60) as u8
+}
+
+fn zone_central_meridian(zone: u8) -> f64 {
+    zone as f64 * 6.0 - 183.0
+}
+
+fn meridional_arc(lat: f64, e2: f64) -> f64 {
+    WGS84_A
+        * ((1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0) * lat
+            - (3.0 * e2 / 8.0 + 3.0 * e2 * e2 / 32.0 + 45.0 * e2.powi(3) / 1024.0)
+                * (2.0 * lat).sin()
+            + (15.0 * e2 * e2 / 256.0 + 45.0 * e2.powi(3) / 1024.0) * (4.0 * lat).sin()
+            - (35.0 * e2.powi(3) / 3072.0) * (6.0 * lat).sin())
+}
+
+fn latitude_band(lat_deg: f64) -> char {
+    let idx = (((lat_deg + 80.0) / 8.0).floor() as i32).clamp(0, (BAND_LETTERS.len() - 1) as i32);
+    BAND_LETTERS.as_bytes()[idx as usize] as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64, tol: f64) {
+        assert!((a - b).abs() < tol, "{a} vs {b} (tolerance {tol})");
+    }
+
+    #[test]
+    fn ecef_round_trips_a_known_point() {
+        let point = Point::new(34.12345, -118.12345, 150.0);
+        let (x, y, z) = point.to_ecef();
+        let back = Point::from_ecef(x, y, z);
+        assert_close(back.lat, point.lat, 1e-6);
+        assert_close(back.lon, point.lon, 1e-6);
+        assert_close(back.hae, point.hae, 1e-3);
+    }
+
+    #[test]
+    fn ecef_of_the_equator_prime_meridian_matches_the_semi_major_axis() {
+        let point = Point::new(0.0, 0.0, 0.0);
+        let (x, y, z) = point.to_ecef();
+        assert_close(x, WGS84_A, 1e-6);
+        assert_close(y, 0.0, 1e-6);
+        assert_close(z, 0.0, 1e-6);
+    }
+
+    #[test]
+    fn utm_round_trips_a_northern_hemisphere_point() {
+        let point = Point::new(34.12345, -118.12345, 0.0);
+        let utm = point.to_utm();
+        assert_eq!(utm.zone, 11);
+        assert_eq!(utm.hemisphere, Hemisphere::North);
+
+        let back = Point::from_utm(&utm);
+        assert_close(back.lat, point.lat, 1e-5);
+        assert_close(back.lon, point.lon, 1e-5);
+    }
+
+    #[test]
+    fn utm_round_trips_a_southern_hemisphere_point() {
+        let point = Point::new(-33.8688, 151.2093, 0.0);
+        let utm = point.to_utm();
+        assert_eq!(utm.hemisphere, Hemisphere::South);
+        assert!(utm.northing > 0.0);
+
+        let back = Point::from_utm(&utm);
+        assert_close(back.lat, point.lat, 1e-5);
+        assert_close(back.lon, point.lon, 1e-5);
+    }
+
+    #[test]
+    fn mgrs_round_trips_through_to_mgrs_and_from_mgrs() {
+        let point = Point::new(34.12345, -118.12345, 0.0);
+        let mgrs = point.to_mgrs();
+        let back = Point::from_mgrs(&mgrs).unwrap();
+        assert_close(back.lat, point.lat, 1e-3);
+        assert_close(back.lon, point.lon, 1e-3);
+    }
+
+    #[test]
+    fn from_mgrs_rejects_a_bad_zone() {
+        assert!(matches!(
+            Point::from_mgrs("99UXP0000000000"),
+            Err(MgrsParseError::InvalidZone(_))
+        ));
+    }
+
+    #[test]
+    fn from_mgrs_rejects_too_short_input() {
+        assert!(matches!(Point::from_mgrs("11U"), Err(MgrsParseError::TooShort(_))));
+    }
+}
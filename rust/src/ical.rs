@@ -0,0 +1,556 @@
+//! iCalendar (RFC 5545) `VEVENT` import/export bridge for CoT events.
+//!
+//! Lets a temporally-scoped CoT event be shared with ordinary calendar
+//! tooling for mission/ops planning, via this field mapping:
+//!
+//! | CoT                                           | iCalendar |
+//! |------------------------------------------------|-----------|
+//! | `uid`                                           | `UID`     |
+//! | `time`                                          | `DTSTAMP` |
+//! | `start`                                         | `DTSTART` |
+//! | `stale` (or `DURATION` when `stale <= start`)   | `DTEND`/`DURATION` |
+//! | `<contact callsign>` detail                     | `SUMMARY` |
+//! | flattened `<remarks>` text                      | `DESCRIPTION` |
+//! | `point.lat`/`point.lon`                         | `GEO`     |
+//! | `point.hae`/`ce`/`le`                           | `X-COT-HAE`/`X-COT-CE`/`X-COT-LE` |
+//! | every other `<detail>` child                    | `X-COT-<flattened path>` |
+//!
+//! `how` isn't representable in iCalendar and is dropped on export; every
+//! other `<detail>` child round-trips: `<contact>` and `<remarks>` get the
+//! named properties above, and everything else is flattened (via
+//! [`r_field_flattening`](crate::ditto::r_field_flattening)) into one
+//! `X-COT-<path>` extension property per leaf value, then reassembled on
+//! import with [`write_detail_value`](crate::xml_writer::write_detail_value).
+//!
+//! `GEO` is also omitted when either coordinate is the
+//! [`UNKNOWN_COORDINATE`] sentinel TAK devices emit for "no position fix" —
+//! a calendar tool has no use for `GEO:9999999;9999999`, and re-importing
+//! such a `VEVENT` is indistinguishable from one that never had a `GEO` line.
+//!
+//! A single `VEVENT` component is produced/consumed, not a full `VCALENDAR`
+//! file; wrapping one or more of these in a `BEGIN:VCALENDAR`/`END:VCALENDAR`
+//! envelope is left to the caller.
+
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use chrono::{DateTime, Utc};
+use quick_xml::Writer;
+use serde_json::Value;
+
+use crate::cot_events::{CotEvent, Point};
+use crate::detail_parser::parse_detail_section;
+use crate::detail_tree::{write_detail_tree, DetailNode};
+use crate::ditto::r_field_flattening::{flatten_r_field, unflatten_r_field};
+use crate::error::CotError;
+use crate::xml_utils::format_cot_float;
+use crate::xml_writer::write_detail_value;
+
+/// `X-COT-*` extension names already reserved for [`Point`] fields, so the
+/// generic detail round-trip (below) never emits or consumes one of these
+/// under its own flattened-path naming.
+const RESERVED_EXTENSIONS: [&str; 3] = ["X-COT-HAE", "X-COT-CE", "X-COT-LE"];
+
+const ICAL_DATETIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// The sentinel value TAK devices use for "no position fix" in place of a
+/// real `lat`/`lon`. [`cot_event_to_ical`] treats either coordinate being
+/// this value as "no position to export" and omits `GEO` entirely.
+const UNKNOWN_COORDINATE: f64 = 9999999.0;
+
+fn format_ical_datetime(dt: DateTime<Utc>) -> String {
+    dt.format(ICAL_DATETIME_FORMAT).to_string()
+}
+
+fn parse_ical_datetime(value: &str) -> Result<DateTime<Utc>, CotError> {
+    DateTime::parse_from_str(value, ICAL_DATETIME_FORMAT)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| CotError::InvalidDateTime {
+            field: "ical datetime".to_string(),
+            value: value.to_string(),
+        })
+}
+
+/// Extracts the `callsign` attribute of a `<contact>` detail child, if present.
+fn callsign_from_detail(detail: &HashMap<String, Value>) -> Option<String> {
+    detail
+        .get("contact")
+        .and_then(|contact| contact.get("callsign"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
+/// Extracts the text of a `<remarks>` detail child, if present.
+///
+/// A text-only `<remarks>text</remarks>` parses to a plain
+/// [`Value::String`]; one with attributes alongside its text (rare, but
+/// valid CoT) parses to an object with a `_text` key instead, so both shapes
+/// are checked.
+fn remarks_text(detail: &HashMap<String, Value>) -> Option<String> {
+    match detail.get("remarks")? {
+        Value::String(text) => Some(text.clone()),
+        Value::Object(obj) => obj.get("_text").and_then(|v| v.as_str()).map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Escapes a value before splicing it into an iCalendar `TEXT` property line
+/// (RFC 5545 §3.3.11): a literal backslash, comma, or semicolon is
+/// backslash-escaped, and embedded CR/LF is folded to a space. Without this,
+/// a callsign, remarks string, or flattened detail leaf carrying a literal
+/// newline could inject extra lines into the exported `VEVENT` — forged
+/// `X-COT-*` properties, or an early `END:VEVENT`/second `BEGIN:VEVENT` —
+/// that [`ical_to_cot_event`] (or any other consumer) would then parse as
+/// real properties. The backslash must be escaped first, before comma and
+/// semicolon add backslashes of their own, or a value ending in a backslash
+/// would absorb the following escape instead of getting one of its own.
+fn escape_ical_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace(['\r', '\n'], " ")
+}
+
+/// Renders one flattened scalar leaf as the text of an `X-COT-*` property.
+fn extension_value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Null => None,
+        Value::Object(_) | Value::Array(_) => None,
+    }
+}
+
+/// Flattens every detail child other than `contact`/`remarks` (which have
+/// their own named properties) into `X-COT-<path>` extension lines.
+fn extension_lines(detail: &HashMap<String, Value>) -> Vec<String> {
+    let other_details: HashMap<String, Value> = detail
+        .iter()
+        .filter(|(key, _)| key.as_str() != "contact" && key.as_str() != "remarks")
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    let mut lines: Vec<(String, String)> = flatten_r_field(&other_details)
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let suffix = key.strip_prefix("r_")?;
+            let text = extension_value_to_string(&value)?;
+            Some((format!("X-COT-{suffix}"), escape_ical_text(&text)))
+        })
+        .collect();
+    lines.sort();
+
+    lines
+        .into_iter()
+        .map(|(name, value)| format!("{name}:{value}"))
+        .collect()
+}
+
+/// Reverses [`extension_lines`]: collects every `X-COT-*` property other
+/// than the [`RESERVED_EXTENSIONS`] back into a detail map and renders it as
+/// XML via [`write_detail_value`].
+fn extensions_to_detail_xml(properties: &HashMap<String, String>) -> String {
+    let flattened: HashMap<String, Value> = properties
+        .iter()
+        .filter(|(key, _)| {
+            key.starts_with("X-COT-") && !RESERVED_EXTENSIONS.contains(&key.as_str())
+        })
+        .map(|(key, value)| {
+            let suffix = key.strip_prefix("X-COT-").unwrap_or(key.as_str());
+            (format!("r_{suffix}"), Value::String(value.clone()))
+        })
+        .collect();
+
+    if flattened.is_empty() {
+        return String::new();
+    }
+
+    let r_map = unflatten_r_field(&flattened);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    const INFALLIBLE: &str = "writing to an in-memory buffer never fails";
+    let mut keys: Vec<_> = r_map.keys().collect();
+    keys.sort();
+    for key in keys {
+        write_detail_value(&mut writer, key, &r_map[key]).expect(INFALLIBLE);
+    }
+    String::from_utf8(writer.into_inner().into_inner()).expect("quick-xml only writes valid UTF-8")
+}
+
+/// Converts a [`CotEvent`] into a single iCalendar `VEVENT` component.
+///
+/// See the module docs for the field mapping and which fields are dropped.
+pub fn cot_event_to_ical(event: &CotEvent) -> String {
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", event.uid),
+        format!("DTSTAMP:{}", format_ical_datetime(event.time)),
+        format!("DTSTART:{}", format_ical_datetime(event.start)),
+    ];
+
+    if event.stale <= event.start {
+        lines.push("DURATION:PT0S".to_string());
+    } else {
+        lines.push(format!("DTEND:{}", format_ical_datetime(event.stale)));
+    }
+
+    let detail_map = parse_detail_section(&event.detail)
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    if let Some(callsign) = callsign_from_detail(&detail_map) {
+        lines.push(format!("SUMMARY:{}", escape_ical_text(&callsign)));
+    }
+
+    if let Some(remarks) = remarks_text(&detail_map) {
+        lines.push(format!("DESCRIPTION:{}", escape_ical_text(&remarks)));
+    }
+
+    if event.point.lat != UNKNOWN_COORDINATE && event.point.lon != UNKNOWN_COORDINATE {
+        lines.push(format!(
+            "GEO:{};{}",
+            format_cot_float(event.point.lat),
+            format_cot_float(event.point.lon)
+        ));
+        lines.push(format!("X-COT-HAE:{}", format_cot_float(event.point.hae)));
+        lines.push(format!("X-COT-CE:{}", format_cot_float(event.point.ce)));
+        lines.push(format!("X-COT-LE:{}", format_cot_float(event.point.le)));
+    }
+
+    lines.extend(extension_lines(&detail_map));
+
+    lines.push("END:VEVENT".to_string());
+    lines.join("\r\n")
+}
+
+/// Parses a single iCalendar `VEVENT` component back into a [`CotEvent`].
+///
+/// `event_type` and `how` aren't representable in iCalendar, so the caller
+/// supplies them directly rather than this function inventing a default.
+/// `<detail>` is reassembled from every `X-COT-*` extension other than the
+/// reserved HAE/CE/LE ones (see the module docs), plus `SUMMARY` (as
+/// `<contact callsign="...">`), `DESCRIPTION` (as `<remarks>`), and `GEO`
+/// (as `<precisionlocation>`, skipped if the extensions already reconstructed
+/// one). `point.hae`/`ce`/`le` are filled from the `X-COT-HAE`/`X-COT-CE`/
+/// `X-COT-LE` extension properties when present alongside `GEO`, else
+/// default to `0.0`.
+pub fn ical_to_cot_event(ical: &str, event_type: &str, how: &str) -> Result<CotEvent, CotError> {
+    let properties = parse_ical_properties(ical);
+
+    let uid = properties
+        .get("UID")
+        .ok_or_else(|| CotError::MissingField("UID".to_string()))?
+        .clone();
+    let time = parse_ical_datetime(
+        properties
+            .get("DTSTAMP")
+            .ok_or_else(|| CotError::MissingField("DTSTAMP".to_string()))?,
+    )?;
+    let start = parse_ical_datetime(
+        properties
+            .get("DTSTART")
+            .ok_or_else(|| CotError::MissingField("DTSTART".to_string()))?,
+    )?;
+    let stale = match properties.get("DTEND") {
+        Some(dtend) => parse_ical_datetime(dtend)?,
+        // A zero (or absent) DURATION is the only duration this bridge
+        // emits, so treat any DURATION property the same way: a momentary
+        // event whose stale time equals its start time. `cot_event_to_ical`
+        // also takes this branch for `stale < start`, which isn't
+        // recoverable from a `VEVENT` alone; `stale == start` is the closest
+        // faithful reconstruction.
+        None => start,
+    };
+
+    let mut detail = extensions_to_detail_xml(&properties);
+    if let Some(summary) = properties.get("SUMMARY") {
+        // SUMMARY/DESCRIPTION come from an external .ics file, so they go
+        // through write_detail_tree's escaping rather than a raw format!
+        // splice — otherwise a value containing `"/><remarks>` etc. could
+        // inject sibling elements into the reconstructed detail XML.
+        let mut contact = DetailNode::new("contact");
+        contact.attrs.push(("callsign".to_string(), summary.clone()));
+        detail.push_str(&write_detail_tree(&[contact]));
+    }
+    if let Some(description) = properties.get("DESCRIPTION") {
+        let mut remarks = DetailNode::new("remarks");
+        remarks.text = Some(description.clone());
+        detail.push_str(&write_detail_tree(&[remarks]));
+    }
+    let point = match properties.get("GEO") {
+        Some(geo) => {
+            // `precisionlocation` is only synthesized here when the
+            // round-tripped `X-COT-*` extensions didn't already reconstruct
+            // one from the original detail, so a document that had a real
+            // `<precisionlocation>` doesn't end up with two.
+            if !detail.contains("<precisionlocation") {
+                detail.push_str("<precisionlocation geopointsrc=\"GEO\" altsrc=\"GEO\"/>");
+            }
+            let mut parts = geo.splitn(2, ';');
+            let lat = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+            let lon = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0.0);
+            let hae = properties
+                .get("X-COT-HAE")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.0);
+            let mut point = Point::new(lat, lon, hae);
+            if let Some(ce) = properties.get("X-COT-CE").and_then(|v| v.parse().ok()) {
+                point.ce = ce;
+            }
+            if let Some(le) = properties.get("X-COT-LE").and_then(|v| v.parse().ok()) {
+                point.le = le;
+            }
+            point
+        }
+        None => Point::default(),
+    };
+    let detail = if detail.is_empty() {
+        String::new()
+    } else {
+        format!("<detail>{detail}</detail>")
+    };
+
+    Ok(CotEvent {
+        version: "2.0".to_string(),
+        uid,
+        event_type: event_type.to_string(),
+        time,
+        start,
+        stale,
+        how: how.to_string(),
+        point,
+        detail,
+        tz_offset_secs: None,
+    })
+}
+
+fn parse_ical_properties(ical: &str) -> HashMap<String, String> {
+    ical.lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_end_matches('\r');
+            if line.is_empty() || line == "BEGIN:VEVENT" || line == "END:VEVENT" {
+                return None;
+            }
+            let (key, value) = line.split_once(':')?;
+            // Strip any `;PARAM=...` parameters from the property name.
+            let key = key.split(';').next().unwrap_or(key);
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml_utils::semantic_xml_eq;
+
+    fn make_event(stale_offset_secs: i64) -> CotEvent {
+        let time = DateTime::parse_from_rfc3339("2023-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        CotEvent {
+            version: "2.0".to_string(),
+            uid: "EVENT-1".to_string(),
+            event_type: "a-f-G-U-C".to_string(),
+            time,
+            start: time,
+            stale: time + chrono::Duration::seconds(stale_offset_secs),
+            how: "h-g-i-g-o".to_string(),
+            point: Point::new(34.12345, -118.12345, 150.0),
+            detail: "<detail><contact callsign=\"ALPHA-1\"/></detail>".to_string(),
+            tz_offset_secs: None,
+        }
+    }
+
+    #[test]
+    fn export_maps_core_fields() {
+        let ical = cot_event_to_ical(&make_event(3600));
+        assert!(ical.contains("UID:EVENT-1"));
+        assert!(ical.contains("DTSTART:20230615T120000Z"));
+        assert!(ical.contains("DTEND:20230615T130000Z"));
+        assert!(ical.contains("SUMMARY:ALPHA-1"));
+        assert!(ical.contains("GEO:34.12345;-118.12345"));
+    }
+
+    #[test]
+    fn export_uses_duration_when_stale_equals_start() {
+        let ical = cot_event_to_ical(&make_event(0));
+        assert!(ical.contains("DURATION:PT0S"));
+        assert!(!ical.contains("DTEND"));
+    }
+
+    #[test]
+    fn export_uses_duration_when_stale_precedes_start() {
+        let ical = cot_event_to_ical(&make_event(-60));
+        assert!(ical.contains("DURATION:PT0S"));
+        assert!(!ical.contains("DTEND"));
+    }
+
+    #[test]
+    fn export_omits_geo_for_unknown_coordinates() {
+        let mut event = make_event(3600);
+        event.point = Point::new(UNKNOWN_COORDINATE, UNKNOWN_COORDINATE, 0.0);
+        let ical = cot_event_to_ical(&event);
+        assert!(!ical.contains("GEO"));
+
+        let reimported = ical_to_cot_event(&ical, &event.event_type, &event.how).unwrap();
+        assert_eq!(reimported.point.lat, 0.0);
+        assert_eq!(reimported.point.lon, 0.0);
+    }
+
+    #[test]
+    fn round_trip_preserves_the_surviving_fields() {
+        let original = make_event(3600);
+        let ical = cot_event_to_ical(&original);
+        let reimported = ical_to_cot_event(&ical, &original.event_type, &original.how).unwrap();
+
+        assert_eq!(reimported.uid, original.uid);
+        assert_eq!(reimported.time, original.time);
+        assert_eq!(reimported.start, original.start);
+        assert_eq!(reimported.stale, original.stale);
+        assert_eq!(reimported.point.lat, original.point.lat);
+        assert_eq!(reimported.point.lon, original.point.lon);
+        assert!(semantic_xml_eq(
+            &reimported.detail,
+            "<detail><contact callsign=\"ALPHA-1\"/><precisionlocation geopointsrc=\"GEO\" altsrc=\"GEO\"/></detail>",
+            false
+        ));
+    }
+
+    #[test]
+    fn import_missing_uid_is_an_error() {
+        let ical = "BEGIN:VEVENT\r\nDTSTAMP:20230615T120000Z\r\nDTSTART:20230615T120000Z\r\nEND:VEVENT";
+        assert!(ical_to_cot_event(ical, "a-f-G-U-C", "h-g-i-g-o").is_err());
+    }
+
+    #[test]
+    fn export_includes_hae_ce_le_extensions_alongside_geo() {
+        let ical = cot_event_to_ical(&make_event(3600));
+        assert!(ical.contains("X-COT-HAE:150.0"));
+        assert!(ical.contains("X-COT-CE:999999.0"));
+        assert!(ical.contains("X-COT-LE:999999.0"));
+    }
+
+    #[test]
+    fn export_omits_hae_ce_le_extensions_for_unknown_coordinates() {
+        let mut event = make_event(3600);
+        event.point = Point::new(UNKNOWN_COORDINATE, UNKNOWN_COORDINATE, 0.0);
+        let ical = cot_event_to_ical(&event);
+        assert!(!ical.contains("X-COT-HAE"));
+        assert!(!ical.contains("X-COT-CE"));
+        assert!(!ical.contains("X-COT-LE"));
+    }
+
+    #[test]
+    fn round_trip_preserves_hae_ce_le() {
+        let original = make_event(3600);
+        let ical = cot_event_to_ical(&original);
+        let reimported = ical_to_cot_event(&ical, &original.event_type, &original.how).unwrap();
+
+        assert_eq!(reimported.point.hae, original.point.hae);
+        assert_eq!(reimported.point.ce, original.point.ce);
+        assert_eq!(reimported.point.le, original.point.le);
+    }
+
+    #[test]
+    fn export_maps_remarks_to_description() {
+        let mut event = make_event(3600);
+        event.detail =
+            "<detail><contact callsign=\"ALPHA-1\"/><remarks>Moving to RP2</remarks></detail>"
+                .to_string();
+        let ical = cot_event_to_ical(&event);
+        assert!(ical.contains("DESCRIPTION:Moving to RP2"));
+    }
+
+    #[test]
+    fn round_trip_preserves_remarks() {
+        let mut original = make_event(3600);
+        original.detail =
+            "<detail><contact callsign=\"ALPHA-1\"/><remarks>Moving to RP2</remarks></detail>"
+                .to_string();
+        let ical = cot_event_to_ical(&original);
+        let reimported = ical_to_cot_event(&ical, &original.event_type, &original.how).unwrap();
+
+        assert!(semantic_xml_eq(
+            &reimported.detail,
+            "<detail><contact callsign=\"ALPHA-1\"/><remarks>Moving to RP2</remarks><precisionlocation geopointsrc=\"GEO\" altsrc=\"GEO\"/></detail>",
+            false
+        ));
+    }
+
+    #[test]
+    fn round_trip_preserves_unknown_detail_elements_via_extensions() {
+        let mut original = make_event(3600);
+        original.detail = "<detail><contact callsign=\"ALPHA-1\"/>\
+            <__group name=\"Blue\" role=\"Team Member\"/>\
+            <status readiness=\"true\"/></detail>"
+            .to_string();
+
+        let ical = cot_event_to_ical(&original);
+        assert!(ical.contains("X-COT-__group_name:Blue"));
+        assert!(ical.contains("X-COT-status_readiness:true"));
+
+        let reimported = ical_to_cot_event(&ical, &original.event_type, &original.how).unwrap();
+        assert!(semantic_xml_eq(&reimported.detail, &original.detail, false));
+    }
+
+    #[test]
+    fn import_escapes_summary_and_description_instead_of_splicing_raw_xml() {
+        let ical = "BEGIN:VEVENT\r\n\
+            UID:EVENT-1\r\n\
+            DTSTAMP:20230615T120000Z\r\n\
+            DTSTART:20230615T120000Z\r\n\
+            SUMMARY:ALPHA\"/><remarks>INJECTED</remarks><contact callsign=\"\r\n\
+            DESCRIPTION:</remarks><status readiness=\"true\"/><remarks>\r\n\
+            END:VEVENT";
+
+        let event = ical_to_cot_event(ical, "a-f-G-U-C", "h-g-i-g-o").unwrap();
+        let detail = parse_detail_section(&event.detail);
+
+        assert!(
+            !event.detail.contains("<status"),
+            "DESCRIPTION must not inject a sibling <status> element: {}",
+            event.detail
+        );
+        assert_eq!(
+            detail.get("contact").and_then(|c| c.get("callsign")).and_then(|v| v.as_str()),
+            Some("ALPHA\"/><remarks>INJECTED</remarks><contact callsign=\"")
+        );
+    }
+
+    #[test]
+    fn export_escapes_embedded_line_breaks_instead_of_injecting_extra_vevent_lines() {
+        let mut event = make_event(3600);
+        event.detail = "<detail><contact callsign=\"ALPHA\"/>\
+            <remarks>line one\r\nX-COT-INJECTED:evil\r\nEND:VEVENT</remarks></detail>"
+            .to_string();
+
+        let ical = cot_event_to_ical(&event);
+
+        assert_eq!(ical.matches("BEGIN:VEVENT").count(), 1);
+        assert_eq!(ical.matches("END:VEVENT").count(), 1);
+        assert!(!ical.contains("X-COT-INJECTED:evil"));
+        assert!(ical.contains("DESCRIPTION:line one X-COT-INJECTED:evil END:VEVENT"));
+    }
+
+    #[test]
+    fn export_escapes_backslash_before_comma_and_semicolon_in_text_properties() {
+        let mut event = make_event(3600);
+        event.detail = "<detail><contact callsign=\"A\\B,C;D\"/></detail>".to_string();
+
+        let ical = cot_event_to_ical(&event);
+
+        assert!(ical.contains("SUMMARY:A\\\\B\\,C\\;D"));
+    }
+
+    #[test]
+    fn extensions_never_collide_with_the_reserved_point_extensions() {
+        let ical = cot_event_to_ical(&make_event(3600));
+        // HAE/CE/LE are reserved for point uncertainty, not generic detail
+        // round-tripping, so they must appear exactly once each.
+        assert_eq!(ical.matches("X-COT-HAE:").count(), 1);
+        assert_eq!(ical.matches("X-COT-CE:").count(), 1);
+        assert_eq!(ical.matches("X-COT-LE:").count(), 1);
+    }
+}
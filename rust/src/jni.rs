@@ -0,0 +1,179 @@
+//! Native JNI bindings, so Java callers share this crate's exact CoT
+//! parsing/flattening logic in-process instead of shelling out to
+//! `ditto_cot-1.0-SNAPSHOT-all.jar` and piping text commands to it.
+//!
+//! Gated behind the `jni` Cargo feature (off by default, since most
+//! consumers of this crate are pure Rust). Every entry point below takes and
+//! returns JSON strings marshalled through [`jni::objects::JString`] — a
+//! [`CotEvent`], [`CotDocument`](crate::ditto::CotDocument), or flattened
+//! document is always exchanged as its `serde_json` form, so the JVM side
+//! never has to agree on a binary layout, only on the same JSON shape this
+//! crate already serializes in Rust-to-Rust tests. On failure, the entry
+//! point throws a `java.lang.RuntimeException` with the underlying error's
+//! message and returns `null` rather than panicking across the FFI boundary.
+#![cfg(feature = "jni")]
+
+use crate::cot_events::CotEvent;
+use crate::ditto::{
+    cot_event_from_ditto_document, cot_event_from_flattened_json, cot_to_document,
+    cot_to_flattened_document, CotDocument,
+};
+use jni::objects::{JClass, JString};
+use jni::sys::jstring;
+use jni::JNIEnv;
+
+/// Reads a Java string argument into an owned [`String`], or `None` if the
+/// JVM couldn't hand it over (already-pending exception).
+fn read_jstring(env: &mut JNIEnv, value: &JString) -> Option<String> {
+    env.get_string(value).ok().map(|s| s.into())
+}
+
+/// Allocates a Java string for `value`, or throws and returns `null` if the
+/// JVM is out of memory.
+fn new_jstring(env: &JNIEnv, value: &str) -> jstring {
+    match env.new_string(value) {
+        Ok(s) => s.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Throws `message` as a `java.lang.RuntimeException` and returns the `null`
+/// every entry point below uses as its failure return value.
+fn throw(env: &mut JNIEnv, message: impl AsRef<str>) -> jstring {
+    let _ = env.throw_new("java/lang/RuntimeException", message.as_ref());
+    std::ptr::null_mut()
+}
+
+/// Parses CoT XML into a JSON-serialized [`CotEvent`].
+///
+/// Java signature: `static native String cotEventFromXml(String xml);`
+#[no_mangle]
+pub extern "system" fn Java_com_ditto_cot_jni_NativeBridge_cotEventFromXml(
+    mut env: JNIEnv,
+    _class: JClass,
+    xml: JString,
+) -> jstring {
+    let Some(xml) = read_jstring(&mut env, &xml) else {
+        return throw(&mut env, "xml argument was not a valid UTF-8 string");
+    };
+    match CotEvent::from_xml(&xml) {
+        Ok(event) => match serde_json::to_string(&event) {
+            Ok(json) => new_jstring(&env, &json),
+            Err(e) => throw(&mut env, e.to_string()),
+        },
+        Err(e) => throw(&mut env, e.to_string()),
+    }
+}
+
+/// Converts a JSON-serialized [`CotEvent`] into a JSON-serialized
+/// [`CotDocument`](crate::ditto::CotDocument) for `peer_key`.
+///
+/// Java signature: `static native String cotToDocument(String eventJson, String peerKey);`
+#[no_mangle]
+pub extern "system" fn Java_com_ditto_cot_jni_NativeBridge_cotToDocument(
+    mut env: JNIEnv,
+    _class: JClass,
+    event_json: JString,
+    peer_key: JString,
+) -> jstring {
+    let Some(event_json) = read_jstring(&mut env, &event_json) else {
+        return throw(&mut env, "eventJson argument was not a valid UTF-8 string");
+    };
+    let Some(peer_key) = read_jstring(&mut env, &peer_key) else {
+        return throw(&mut env, "peerKey argument was not a valid UTF-8 string");
+    };
+
+    let event: CotEvent = match serde_json::from_str(&event_json) {
+        Ok(event) => event,
+        Err(e) => return throw(&mut env, format!("invalid CotEvent JSON: {e}")),
+    };
+
+    let document = cot_to_document(&event, &peer_key);
+    match serde_json::to_string(&document) {
+        Ok(json) => new_jstring(&env, &json),
+        Err(e) => throw(&mut env, e.to_string()),
+    }
+}
+
+/// Converts a JSON-serialized [`CotEvent`] into its flattened, DQL-compatible
+/// JSON document for `peer_key`.
+///
+/// Java signature: `static native String cotToFlattenedDocument(String eventJson, String peerKey);`
+#[no_mangle]
+pub extern "system" fn Java_com_ditto_cot_jni_NativeBridge_cotToFlattenedDocument(
+    mut env: JNIEnv,
+    _class: JClass,
+    event_json: JString,
+    peer_key: JString,
+) -> jstring {
+    let Some(event_json) = read_jstring(&mut env, &event_json) else {
+        return throw(&mut env, "eventJson argument was not a valid UTF-8 string");
+    };
+    let Some(peer_key) = read_jstring(&mut env, &peer_key) else {
+        return throw(&mut env, "peerKey argument was not a valid UTF-8 string");
+    };
+
+    let event: CotEvent = match serde_json::from_str(&event_json) {
+        Ok(event) => event,
+        Err(e) => return throw(&mut env, format!("invalid CotEvent JSON: {e}")),
+    };
+
+    let flattened = cot_to_flattened_document(&event, &peer_key);
+    match serde_json::to_string(&flattened) {
+        Ok(json) => new_jstring(&env, &json),
+        Err(e) => throw(&mut env, e.to_string()),
+    }
+}
+
+/// Converts a JSON-serialized [`CotDocument`](crate::ditto::CotDocument) back
+/// into a JSON-serialized [`CotEvent`] (best-effort, per
+/// [`cot_event_from_ditto_document`]).
+///
+/// Java signature: `static native String cotEventFromDittoDocument(String documentJson);`
+#[no_mangle]
+pub extern "system" fn Java_com_ditto_cot_jni_NativeBridge_cotEventFromDittoDocument(
+    mut env: JNIEnv,
+    _class: JClass,
+    document_json: JString,
+) -> jstring {
+    let Some(document_json) = read_jstring(&mut env, &document_json) else {
+        return throw(&mut env, "documentJson argument was not a valid UTF-8 string");
+    };
+
+    let document: CotDocument = match serde_json::from_str(&document_json) {
+        Ok(document) => document,
+        Err(e) => return throw(&mut env, format!("invalid CotDocument JSON: {e}")),
+    };
+
+    let event = cot_event_from_ditto_document(&document);
+    match serde_json::to_string(&event) {
+        Ok(json) => new_jstring(&env, &json),
+        Err(e) => throw(&mut env, e.to_string()),
+    }
+}
+
+/// Converts a flattened, DQL-compatible JSON document back into a
+/// JSON-serialized [`CotEvent`].
+///
+/// Java signature: `static native String cotEventFromFlattenedJson(String flattenedJson);`
+#[no_mangle]
+pub extern "system" fn Java_com_ditto_cot_jni_NativeBridge_cotEventFromFlattenedJson(
+    mut env: JNIEnv,
+    _class: JClass,
+    flattened_json: JString,
+) -> jstring {
+    let Some(flattened_json) = read_jstring(&mut env, &flattened_json) else {
+        return throw(&mut env, "flattenedJson argument was not a valid UTF-8 string");
+    };
+
+    let flattened: serde_json::Value = match serde_json::from_str(&flattened_json) {
+        Ok(value) => value,
+        Err(e) => return throw(&mut env, format!("invalid flattened document JSON: {e}")),
+    };
+
+    let event = cot_event_from_flattened_json(&flattened);
+    match serde_json::to_string(&event) {
+        Ok(json) => new_jstring(&env, &json),
+        Err(e) => throw(&mut env, e.to_string()),
+    }
+}
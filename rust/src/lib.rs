@@ -99,40 +99,119 @@
 //! ```
 //!
 //! ## Modules
+//! - `airspace`: OpenAir-style airspace/geofence import producing CoT drawing events
+//! - `codec`: Pluggable wire-format codec (XML/JSON/MessagePack/Protobuf) for `CotEvent`
 //! - `cot_events`: Core CoT event types and parsing
+//! - `cot_store`: Embedded, durable persistence/outbox layer for `CotEvent`s (`cot-store` feature)
+//! - `cot_type`: Structured parser/classifier for the dash-delimited CoT event-type taxonomy
+//! - `detail_model`: Typed view over a `<detail>` section's common sub-elements
+//! - `detail_tree`: Lossless, order-preserving `<detail>` tree model
 //! - `ditto`: Ditto document types and transformations
 //! - `error`: Error types and utilities
+//! - `event_emitter`: Type-dispatching event emitter for incoming `CotEvent`s
+//! - `format`: Pluggable wire-format (XML/JSON/MessagePack) encoding for `FlatCotEvent`
+//! - `geo_coord`: WGS84 ECEF/UTM/MGRS coordinate conversions for `Point`
+//! - `ical`: iCalendar (RFC 5545) VEVENT import/export bridge for CoT events
+//! - `interop`: Structured JSON-lines interop protocol for driving peer clients
+//! - `jni`: Native JNI bindings for in-process Java interop (`jni` feature)
 //! - `model`: Data models and serialization
 //! - `schema_validator`: XML schema validation
+//! - `stale`: Stale-time lifecycle tracking and expiry for `CotEvent`
+//! - `timestamp`: Time-scale–aware (UTC/TAI/GPS) instant type
 //! - `xml_parser`: XML parsing utilities
+//! - `xml_stream_writer`: Incremental, constant-memory multi-event XML writer
 //! - `xml_writer`: XML generation utilities
 
 #![warn(missing_docs)]
 
+/// OpenAir-style airspace/geofence import producing CoT drawing events
+pub mod airspace;
+
+/// Pluggable wire-format codec (XML/JSON/MessagePack/Protobuf) for `CotEvent`
+pub mod codec;
+
 /// Core CoT event types and parsing
 pub mod cot_events;
 
+/// Embedded, durable persistence/outbox layer for `CotEvent`s, behind the
+/// `cot-store` feature
+#[cfg(feature = "cot-store")]
+pub mod cot_store;
+
+/// Structured parser/classifier for the dash-delimited CoT event-type taxonomy
+pub mod cot_type;
+
 /// Detail section parsing utilities
 pub mod detail_parser;
 
+/// CRDT merge of concurrently-edited detail maps
+pub mod crdt_merge;
+
+/// Typed view over a `<detail>` section's common sub-elements
+pub mod detail_model;
+
+/// Selector/query API over parsed detail trees
+pub mod detail_query;
+
+/// Lossless, order-preserving `<detail>` tree model
+pub mod detail_tree;
+
 /// Ditto document types and transformations
 pub mod ditto;
 
 /// Error types and utilities
 pub mod error;
 
+/// Type-dispatching event emitter for incoming `CotEvent`s
+pub mod event_emitter;
+
+/// WGS84 ECEF/UTM/MGRS coordinate conversions for `Point`
+pub mod geo_coord;
+
+/// Pluggable wire-format (XML/JSON/MessagePack) encoding for `FlatCotEvent`
+pub mod format;
+
+/// iCalendar (RFC 5545) VEVENT import/export bridge for CoT events
+pub mod ical;
+
+/// Structured JSON-lines interop protocol for driving peer clients
+pub mod interop;
+
+/// Native JNI bindings for in-process Java interop, behind the `jni` feature
+#[cfg(feature = "jni")]
+pub mod jni;
+
 /// Data models and serialization
 pub mod model;
 
+/// Extension-point registry for CoT `<detail>` child elements
+pub mod plugin;
+
 /// XML schema validation
 pub mod schema_validator;
 
+/// Stale-time lifecycle tracking and expiry for `CotEvent`
+pub mod stale;
+
+/// Incremental CoT XML to Ditto document conversion over a `Read` source
+pub mod stream;
+
+/// Time-scale–aware (UTC/TAI/GPS) instant type with leap-second-correct
+/// conversion to Unix microseconds
+pub mod timestamp;
+
+/// vCard 4.0 export for CoT contact/group/takv detail elements
+pub mod vcard;
+
 /// XML normalization utilities
 pub mod xml_utils;
 
 /// XML parsing utilities
 pub mod xml_parser;
 
+/// Incremental, constant-memory multi-event XML writer for TAK batches
+pub mod xml_stream_writer;
+
 /// XML generation utilities
 pub mod xml_writer;
 
@@ -3,9 +3,9 @@
 //! This module contains the core data structures used for representing
 //! and transforming CoT messages in a flattened format.
 
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
 
 /// A flattened representation of a CoT (Cursor on Target) event.
 ///
@@ -53,6 +53,42 @@ pub struct FlatCotEvent {
     /// Optional group name the entity belongs to
     pub group_name: Option<String>,
 
-    /// Additional event-specific details in a key-value format
-    pub detail_extra: HashMap<String, Value>,
+    /// Optional role within the group (e.g. "Team Member", "Team Lead"),
+    /// promoted from the CoT `<__group role="...">` attribute alongside
+    /// `group_name`.
+    pub group_role: Option<String>,
+
+    /// Ground speed in meters/second, promoted from the CoT
+    /// `<track speed="...">` attribute when present.
+    pub speed: Option<f64>,
+
+    /// Course (true heading) in degrees, promoted from the CoT
+    /// `<track course="...">` attribute when present.
+    pub course: Option<f64>,
+
+    /// The producer's wall-clock UTC offset in seconds, carried through
+    /// from [`CotEvent::tz_offset_secs`](crate::cot_events::CotEvent::tz_offset_secs)
+    /// so [`time`](Self::time)/[`start`](Self::start)/[`stale`](Self::stale)
+    /// can be re-rendered in their originating offset instead of being
+    /// normalized to `Z`. `None` defaults to UTC, matching behavior before
+    /// this field existed.
+    #[serde(default)]
+    pub tz_offset_secs: Option<i32>,
+
+    /// Additional event-specific details in a key-value format, in the
+    /// order their tags first appeared in the source `<detail>` section —
+    /// an [`IndexMap`] rather than a `HashMap` so
+    /// [`to_cot_xml`](crate::xml_writer::to_cot_xml) can re-emit them in
+    /// that same order instead of an arbitrary or alphabetical one.
+    pub detail_extra: IndexMap<String, Value>,
+
+    /// Attributes on the top-level `<event>` element that this crate
+    /// doesn't promote to a first-class field (anything other than
+    /// `version`/`uid`/`type`/`time`/`start`/`stale`/`how`/`lat`/`lon`/
+    /// `hae`/`ce`/`le`), preserved in their original order so a round trip
+    /// through [`parse_cot`](crate::xml_parser::parse_cot) and
+    /// [`to_cot_xml`](crate::xml_writer::to_cot_xml) doesn't silently drop
+    /// a vendor-specific attribute.
+    #[serde(default)]
+    pub extra_attrs: IndexMap<String, String>,
 }
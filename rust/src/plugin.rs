@@ -1,33 +1,489 @@
-use crate::model::FlatCotEvent;
+//! Extension-point registry for CoT `<detail>` child elements.
+//!
+//! Parsing/enriching/writing standard detail blocks (`contact`, `takv`,
+//! `status`, `track`, `precisionlocation`, `__group`, `uid`, `remarks`) used
+//! to be hardwired across `detail_parser`, `from_ditto`, and `xml_writer`.
+//! [`DetailPlugin`] is the extension point instead: each plugin owns one
+//! detail tag's `parse` (XML attributes -> a flattened r-map entry),
+//! `enrich_flat` (that entry -> [`FlatCotEvent`] fields), and `to_xml` (the
+//! reverse, for round-tripping) in one place, and a downstream crate can
+//! [`PluginRegistry::register`] its own plugin for a vendor-specific tag
+//! without forking this crate.
+//!
+//! [`PluginRegistry::with_builtins`] ships one plugin per standard tag above.
+//! Dispatch is first-match-wins over the registry's plugin order: the first
+//! plugin whose [`DetailPlugin::matches`] returns `true` for a tag handles
+//! it, and no other plugin is consulted — so a caller registering a custom
+//! plugin for an already-handled tag needs to register it before the
+//! built-ins (there's no "override" path, only dispatch order).
+//!
+//! [`xml_parser::parse_cot`](crate::xml_parser::parse_cot) drives this
+//! registry over the parsed `<detail>` tags to promote the well-known ones
+//! (`contact.callsign`, `__group.name`/`role`, `track.speed`/`course`) onto
+//! [`FlatCotEvent`] fields, in addition to the lossless `detail_extra` map it
+//! already builds. `from_ditto` and `xml_writer` haven't been migrated onto
+//! it yet and keep their own hardwired promotion of `callsign`/`group_name`;
+//! that's follow-up work.
+
 use std::collections::HashMap;
+use std::io::Cursor;
+
+use indexmap::IndexMap;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
 use serde_json::Value;
 
+use crate::model::FlatCotEvent;
+
+/// Writes a flat (no nested children) XML element from an attribute map,
+/// escaping values the same way [`xml_writer`](crate::xml_writer) does.
+/// Attribute order is sorted for deterministic output.
+fn format_empty_element(tag: &str, attrs: &Value) -> Option<String> {
+    let object = attrs.as_object()?;
+    let mut keys: Vec<&String> = object.keys().collect();
+    keys.sort();
+
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    let mut start = BytesStart::new(tag);
+    for key in keys {
+        let text = match object.get(key)? {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        start.push_attribute((key.as_str(), text.as_str()));
+    }
+    writer.write_event(Event::Empty(start)).ok()?;
+    String::from_utf8(writer.into_inner().into_inner()).ok()
+}
+
+/// An extension point covering one CoT `<detail>` child tag's full
+/// round-trip: parsing its XML attributes, enriching a [`FlatCotEvent`] from
+/// the parsed value, and writing it back out to XML.
 pub trait DetailPlugin {
+    /// Returns whether this plugin handles `tag` (e.g. `"contact"`).
     fn matches(&self, tag: &str) -> bool;
+
+    /// Parses this tag's XML attributes into a flattened r-map entry
+    /// (the key this plugin's detail lives under, and its value).
     fn parse(&self, attributes: &HashMap<String, String>) -> Option<(String, Value)>;
+
+    /// Enriches `flat` from a previously [`parse`](Self::parse)d `(key, val)`
+    /// pair — e.g. promoting `contact.callsign` to [`FlatCotEvent::callsign`]
+    /// as well as stashing it under `detail_extra`.
     fn enrich_flat(&self, flat: &mut FlatCotEvent, key: &str, val: &Value);
+
+    /// Writes this plugin's tag back to XML from `flat`'s state, or `None`
+    /// if this plugin's key isn't present in `flat.detail_extra`.
+    fn to_xml(&self, flat: &FlatCotEvent) -> Option<String>;
 }
 
+/// An ordered collection of [`DetailPlugin`]s, dispatched first-match-wins.
 pub struct PluginRegistry {
     plugins: Vec<Box<dyn DetailPlugin + Send + Sync>>,
 }
 
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PluginRegistry {
+    /// Creates an empty registry with no plugins registered.
     pub fn new() -> Self {
-        Self { plugins: Vec::new() }
+        Self {
+            plugins: Vec::new(),
+        }
     }
 
+    /// Creates a registry pre-loaded with one plugin per standard CoT detail
+    /// tag (`contact`, `takv`, `status`, `track`, `precisionlocation`,
+    /// `__group`, `uid`, `remarks`).
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(ContactPlugin));
+        registry.register(Box::new(TakvPlugin));
+        registry.register(Box::new(StatusPlugin));
+        registry.register(Box::new(TrackPlugin));
+        registry.register(Box::new(PrecisionLocationPlugin));
+        registry.register(Box::new(GroupPlugin));
+        registry.register(Box::new(UidPlugin));
+        registry.register(Box::new(RemarksPlugin));
+        registry
+    }
+
+    /// Appends `plugin` to the end of the dispatch order, so a caller can
+    /// register a custom tag handler without displacing any built-in.
     pub fn register(&mut self, plugin: Box<dyn DetailPlugin + Send + Sync>) {
         self.plugins.push(plugin);
     }
 
+    /// Returns the first registered plugin that matches `tag`, if any.
+    pub fn plugin_for(&self, tag: &str) -> Option<&(dyn DetailPlugin + Send + Sync)> {
+        self.plugins
+            .iter()
+            .find(|plugin| plugin.matches(tag))
+            .map(|plugin| plugin.as_ref())
+    }
+
+    /// Dispatches `tag`'s attributes to the first matching plugin, parsing
+    /// and enriching `flat` in one step. A no-op if no plugin matches.
     pub fn handle(&self, tag: &str, attrs: &HashMap<String, String>, flat: &mut FlatCotEvent) {
-        for plugin in &self.plugins {
-            if plugin.matches(tag) {
-                if let Some((key, value)) = plugin.parse(attrs) {
-                    plugin.enrich_flat(flat, &key, &value);
+        let Some(plugin) = self.plugin_for(tag) else {
+            return;
+        };
+        if let Some((key, value)) = plugin.parse(attrs) {
+            plugin.enrich_flat(flat, &key, &value);
+        }
+    }
+
+    /// Writes every registered plugin's tag back to XML from `flat`'s state,
+    /// skipping plugins whose tag has nothing to write, in registration
+    /// order.
+    pub fn write_details(&self, flat: &FlatCotEvent) -> Vec<String> {
+        self.plugins
+            .iter()
+            .filter_map(|plugin| plugin.to_xml(flat))
+            .collect()
+    }
+}
+
+macro_rules! single_tag_plugin {
+    ($name:ident, $tag:literal) => {
+        /// Built-in plugin for the
+        #[doc = concat!("`<", $tag, ">`")]
+        /// detail tag: stores its attributes verbatim under `detail_extra`,
+        /// with no further field promotion.
+        pub struct $name;
+
+        impl DetailPlugin for $name {
+            fn matches(&self, tag: &str) -> bool {
+                tag == $tag
+            }
+
+            fn parse(&self, attributes: &HashMap<String, String>) -> Option<(String, Value)> {
+                if attributes.is_empty() {
+                    return None;
                 }
+                let object = attributes
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                    .collect();
+                Some(($tag.to_string(), Value::Object(object)))
+            }
+
+            fn enrich_flat(&self, flat: &mut FlatCotEvent, key: &str, val: &Value) {
+                flat.detail_extra.insert(key.to_string(), val.clone());
             }
+
+            fn to_xml(&self, flat: &FlatCotEvent) -> Option<String> {
+                let value = flat.detail_extra.get($tag)?;
+                format_empty_element($tag, value)
+            }
+        }
+    };
+}
+
+single_tag_plugin!(TakvPlugin, "takv");
+single_tag_plugin!(StatusPlugin, "status");
+single_tag_plugin!(PrecisionLocationPlugin, "precisionlocation");
+single_tag_plugin!(UidPlugin, "uid");
+
+/// Built-in plugin for `<contact callsign="...">`. On top of stashing the
+/// parsed attributes under `detail_extra["contact"]`, it also promotes
+/// `callsign` to [`FlatCotEvent::callsign`] (matching the long-standing
+/// hardwired behavior in `from_ditto`/`detail_parser`).
+pub struct ContactPlugin;
+
+impl DetailPlugin for ContactPlugin {
+    fn matches(&self, tag: &str) -> bool {
+        tag == "contact"
+    }
+
+    fn parse(&self, attributes: &HashMap<String, String>) -> Option<(String, Value)> {
+        if attributes.is_empty() {
+            return None;
+        }
+        let object = attributes
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        Some(("contact".to_string(), Value::Object(object)))
+    }
+
+    fn enrich_flat(&self, flat: &mut FlatCotEvent, key: &str, val: &Value) {
+        if let Some(callsign) = val.get("callsign").and_then(Value::as_str) {
+            flat.callsign = Some(callsign.to_string());
+        }
+        flat.detail_extra.insert(key.to_string(), val.clone());
+    }
+
+    fn to_xml(&self, flat: &FlatCotEvent) -> Option<String> {
+        let value = flat.detail_extra.get("contact")?;
+        format_empty_element("contact", value)
+    }
+}
+
+/// Built-in plugin for `<__group name="..." role="...">`. Promotes `name`
+/// to [`FlatCotEvent::group_name`] (matching the long-standing hardwired
+/// behavior elsewhere in this crate) and `role` to
+/// [`FlatCotEvent::group_role`].
+pub struct GroupPlugin;
+
+impl DetailPlugin for GroupPlugin {
+    fn matches(&self, tag: &str) -> bool {
+        tag == "__group"
+    }
+
+    fn parse(&self, attributes: &HashMap<String, String>) -> Option<(String, Value)> {
+        if attributes.is_empty() {
+            return None;
         }
+        let object = attributes
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        Some(("__group".to_string(), Value::Object(object)))
     }
-}
\ No newline at end of file
+
+    fn enrich_flat(&self, flat: &mut FlatCotEvent, key: &str, val: &Value) {
+        if let Some(name) = val.get("name").and_then(Value::as_str) {
+            flat.group_name = Some(name.to_string());
+        }
+        if let Some(role) = val.get("role").and_then(Value::as_str) {
+            flat.group_role = Some(role.to_string());
+        }
+        flat.detail_extra.insert(key.to_string(), val.clone());
+    }
+
+    fn to_xml(&self, flat: &FlatCotEvent) -> Option<String> {
+        let value = flat.detail_extra.get("__group")?;
+        format_empty_element("__group", value)
+    }
+}
+
+/// Built-in plugin for `<track course="..." speed="...">`. On top of
+/// stashing the parsed attributes under `detail_extra["track"]`, it promotes
+/// `speed` and `course` to [`FlatCotEvent::speed`]/[`FlatCotEvent::course`]
+/// so kinematics are queryable as first-class fields instead of only living
+/// in the generic detail bag.
+pub struct TrackPlugin;
+
+impl DetailPlugin for TrackPlugin {
+    fn matches(&self, tag: &str) -> bool {
+        tag == "track"
+    }
+
+    fn parse(&self, attributes: &HashMap<String, String>) -> Option<(String, Value)> {
+        if attributes.is_empty() {
+            return None;
+        }
+        let object = attributes
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        Some(("track".to_string(), Value::Object(object)))
+    }
+
+    fn enrich_flat(&self, flat: &mut FlatCotEvent, key: &str, val: &Value) {
+        if let Some(speed) = val
+            .get("speed")
+            .and_then(Value::as_str)
+            .and_then(|s| s.parse::<f64>().ok())
+        {
+            flat.speed = Some(speed);
+        }
+        if let Some(course) = val
+            .get("course")
+            .and_then(Value::as_str)
+            .and_then(|s| s.parse::<f64>().ok())
+        {
+            flat.course = Some(course);
+        }
+        flat.detail_extra.insert(key.to_string(), val.clone());
+    }
+
+    fn to_xml(&self, flat: &FlatCotEvent) -> Option<String> {
+        let value = flat.detail_extra.get("track")?;
+        format_empty_element("track", value)
+    }
+}
+
+/// Built-in plugin for `<remarks>free text</remarks>`. [`DetailPlugin::parse`]
+/// only receives attributes, not element text, so this plugin reads the text
+/// from a synthetic `_text` attribute key that a caller parsing `<remarks>`
+/// is expected to populate (there being no other text channel in the current
+/// [`DetailPlugin`] signature).
+pub struct RemarksPlugin;
+
+impl DetailPlugin for RemarksPlugin {
+    fn matches(&self, tag: &str) -> bool {
+        tag == "remarks"
+    }
+
+    fn parse(&self, attributes: &HashMap<String, String>) -> Option<(String, Value)> {
+        let text = attributes.get("_text")?;
+        Some(("remarks".to_string(), Value::String(text.clone())))
+    }
+
+    fn enrich_flat(&self, flat: &mut FlatCotEvent, key: &str, val: &Value) {
+        flat.detail_extra.insert(key.to_string(), val.clone());
+    }
+
+    fn to_xml(&self, flat: &FlatCotEvent) -> Option<String> {
+        let text = flat.detail_extra.get("remarks")?.as_str()?;
+        let mut writer = Writer::new(Cursor::new(Vec::new()));
+        writer.write_event(Event::Start(BytesStart::new("remarks"))).ok()?;
+        writer.write_event(Event::Text(BytesText::new(text))).ok()?;
+        writer.write_event(Event::End(BytesEnd::new("remarks"))).ok()?;
+        String::from_utf8(writer.into_inner().into_inner()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn empty_flat() -> FlatCotEvent {
+        FlatCotEvent {
+            uid: "uid-1".to_string(),
+            type_: "a-f-G-U-C".to_string(),
+            time: "2023-01-01T00:00:00Z".to_string(),
+            start: "2023-01-01T00:00:00Z".to_string(),
+            stale: "2023-01-01T00:00:00Z".to_string(),
+            how: "h-g-i-g-o".to_string(),
+            lat: 0.0,
+            lon: 0.0,
+            hae: 0.0,
+            ce: 0.0,
+            le: 0.0,
+            callsign: None,
+            group_name: None,
+            group_role: None,
+            speed: None,
+            course: None,
+            tz_offset_secs: None,
+            detail_extra: IndexMap::new(),
+            extra_attrs: IndexMap::new(),
+        }
+    }
+
+    #[test]
+    fn contact_plugin_promotes_callsign_and_stores_detail() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("contact", &attrs(&[("callsign", "ALPHA-1")]), &mut flat);
+
+        assert_eq!(flat.callsign, Some("ALPHA-1".to_string()));
+        assert_eq!(
+            flat.detail_extra.get("contact"),
+            Some(&serde_json::json!({ "callsign": "ALPHA-1" }))
+        );
+    }
+
+    #[test]
+    fn group_plugin_promotes_group_name() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("__group", &attrs(&[("name", "Blue"), ("role", "Team Lead")]), &mut flat);
+
+        assert_eq!(flat.group_name, Some("Blue".to_string()));
+    }
+
+    #[test]
+    fn group_plugin_promotes_role() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("__group", &attrs(&[("name", "Blue"), ("role", "Team Lead")]), &mut flat);
+
+        assert_eq!(flat.group_role, Some("Team Lead".to_string()));
+    }
+
+    #[test]
+    fn track_plugin_promotes_speed_and_course() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("track", &attrs(&[("speed", "15.0"), ("course", "90.0")]), &mut flat);
+
+        assert_eq!(flat.speed, Some(15.0));
+        assert_eq!(flat.course, Some(90.0));
+        assert_eq!(
+            flat.detail_extra.get("track"),
+            Some(&serde_json::json!({ "speed": "15.0", "course": "90.0" }))
+        );
+    }
+
+    #[test]
+    fn unmatched_tag_is_a_no_op() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("sensor", &attrs(&[("vfov", "45")]), &mut flat);
+        assert!(flat.detail_extra.is_empty());
+    }
+
+    #[test]
+    fn first_match_wins_over_a_later_custom_plugin_for_the_same_tag() {
+        struct AlwaysOverrideContact;
+        impl DetailPlugin for AlwaysOverrideContact {
+            fn matches(&self, tag: &str) -> bool {
+                tag == "contact"
+            }
+            fn parse(&self, _attributes: &HashMap<String, String>) -> Option<(String, Value)> {
+                Some(("contact".to_string(), Value::String("overridden".to_string())))
+            }
+            fn enrich_flat(&self, flat: &mut FlatCotEvent, key: &str, val: &Value) {
+                flat.detail_extra.insert(key.to_string(), val.clone());
+            }
+            fn to_xml(&self, _flat: &FlatCotEvent) -> Option<String> {
+                None
+            }
+        }
+
+        let mut registry = PluginRegistry::with_builtins();
+        registry.register(Box::new(AlwaysOverrideContact));
+        let mut flat = empty_flat();
+        registry.handle("contact", &attrs(&[("callsign", "ALPHA-1")]), &mut flat);
+
+        assert_eq!(flat.callsign, Some("ALPHA-1".to_string()));
+    }
+
+    #[test]
+    fn contact_round_trips_through_to_xml() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("contact", &attrs(&[("callsign", "ALPHA-1")]), &mut flat);
+
+        let plugin = registry.plugin_for("contact").unwrap();
+        let xml = plugin.to_xml(&flat).unwrap();
+        assert_eq!(xml, r#"<contact callsign="ALPHA-1"/>"#);
+    }
+
+    #[test]
+    fn remarks_plugin_reads_synthetic_text_attribute_and_writes_a_text_element() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("remarks", &attrs(&[("_text", "all clear")]), &mut flat);
+
+        let plugin = registry.plugin_for("remarks").unwrap();
+        let xml = plugin.to_xml(&flat).unwrap();
+        assert_eq!(xml, "<remarks>all clear</remarks>");
+    }
+
+    #[test]
+    fn write_details_collects_every_plugin_with_something_to_write() {
+        let registry = PluginRegistry::with_builtins();
+        let mut flat = empty_flat();
+        registry.handle("contact", &attrs(&[("callsign", "ALPHA-1")]), &mut flat);
+        registry.handle("__group", &attrs(&[("name", "Blue")]), &mut flat);
+
+        let xml = registry.write_details(&flat);
+        assert_eq!(xml.len(), 2);
+    }
+}
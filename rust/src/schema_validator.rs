@@ -0,0 +1,286 @@
+//! Structural validation of CoT `<event>` XML against the shape real CoT
+//! producers/consumers expect, without shelling out to `xmllint` or a real
+//! XSD engine.
+//!
+//! [`validate_against_cot_schema`] walks the document with a `quick_xml`
+//! event loop, tracking an element stack the way an `EventReader`-driven
+//! typed decoder would, and enforces CoT's rules directly in Rust: the root
+//! must be `<event>` carrying its required attributes, exactly one
+//! `<point>` child with valid `lat`/`lon`, at most one `<detail>`, and no
+//! unknown children of `<event>`. This is intentionally narrower than a
+//! full XSD — it validates the handful of structural invariants this crate
+//! actually depends on, not arbitrary CoT extension schemas.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Attributes every `<event>` root element must carry.
+const REQUIRED_EVENT_ATTRS: &[&str] = &["version", "uid", "type", "time", "start", "stale", "how"];
+
+/// Failure modes for [`validate_against_cot_schema`].
+#[derive(Debug, thiserror::Error, Clone, PartialEq)]
+pub enum SchemaValidationError {
+    /// The input wasn't even well-formed XML.
+    #[error("malformed XML at byte offset {offset}: {source}")]
+    Malformed {
+        /// Byte offset into the input where the read failed.
+        offset: u64,
+        /// The underlying `quick_xml` error, stringified.
+        source: String,
+    },
+
+    /// A structural or semantic CoT rule was violated.
+    #[error("CoT schema violation at {path}: {message}")]
+    Validation {
+        /// The offending element/attribute path, e.g. `event/point/@lat`.
+        path: String,
+        /// Human-readable description of what rule was violated.
+        message: String,
+    },
+}
+
+impl SchemaValidationError {
+    fn validation(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Validation {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Validates `xml` against CoT's structural rules, returning
+/// [`SchemaValidationError::Validation`] (with the offending element/
+/// attribute path) on the first violation found:
+///
+/// - the root element must be `<event>`, carrying `version`, `uid`, `type`,
+///   `time`, `start`, `stale`, `how`;
+/// - `<event>` must have exactly one `<point>` child, whose `lat`/`lon`/
+///   `hae`/`ce`/`le` attributes must all parse as `f64`, with
+///   `lat ∈ [-90, 90]` and `lon ∈ [-180, 180]`;
+/// - `<event>` may have at most one `<detail>` child;
+/// - `<event>` may not have any other kind of child.
+pub fn validate_against_cot_schema(xml: &str) -> Result<(), SchemaValidationError> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut seen_root = false;
+    let mut point_count = 0u32;
+    let mut detail_count = 0u32;
+
+    loop {
+        buf.clear();
+        let offset = reader.buffer_position();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Eof) => break,
+            Err(err) => {
+                return Err(SchemaValidationError::Malformed {
+                    offset,
+                    source: err.to_string(),
+                })
+            }
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+
+                if !seen_root {
+                    if tag != "event" {
+                        return Err(SchemaValidationError::validation(
+                            "",
+                            format!("root element must be <event>, found <{tag}>"),
+                        ));
+                    }
+                    seen_root = true;
+                    require_event_attrs(e)?;
+                    continue;
+                }
+
+                match tag.as_str() {
+                    "point" => {
+                        point_count += 1;
+                        if point_count > 1 {
+                            return Err(SchemaValidationError::validation(
+                                "event/point",
+                                "an <event> may have at most one <point> child",
+                            ));
+                        }
+                        validate_point_attrs(e)?;
+                    }
+                    "detail" => {
+                        detail_count += 1;
+                        if detail_count > 1 {
+                            return Err(SchemaValidationError::validation(
+                                "event/detail",
+                                "an <event> may have at most one <detail> child",
+                            ));
+                        }
+                    }
+                    other => {
+                        return Err(SchemaValidationError::validation(
+                            format!("event/{other}"),
+                            format!("<event> has no <{other}> child in the CoT schema"),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !seen_root {
+        return Err(SchemaValidationError::validation(
+            "",
+            "document has no root <event> element",
+        ));
+    }
+    if point_count == 0 {
+        return Err(SchemaValidationError::validation(
+            "event/point",
+            "an <event> must have exactly one <point> child",
+        ));
+    }
+
+    Ok(())
+}
+
+fn require_event_attrs(start: &BytesStart) -> Result<(), SchemaValidationError> {
+    for &required in REQUIRED_EVENT_ATTRS {
+        let present = start
+            .attributes()
+            .flatten()
+            .any(|attr| attr.key.as_ref() == required.as_bytes());
+        if !present {
+            return Err(SchemaValidationError::validation(
+                format!("event/@{required}"),
+                format!("<event> is missing required attribute '{required}'"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_point_attrs(start: &BytesStart) -> Result<(), SchemaValidationError> {
+    for &attr_name in &["lat", "lon", "hae", "ce", "le"] {
+        let path = format!("event/point/@{attr_name}");
+        let Some(attr) = start
+            .attributes()
+            .flatten()
+            .find(|attr| attr.key.as_ref() == attr_name.as_bytes())
+        else {
+            return Err(SchemaValidationError::validation(
+                path.as_str(),
+                format!("<point> is missing required attribute '{attr_name}'"),
+            ));
+        };
+
+        let value = String::from_utf8_lossy(&attr.value);
+        let parsed: f64 = value.parse().map_err(|_| {
+            let message = format!("'{value}' is not a valid number");
+            SchemaValidationError::validation(path.as_str(), message)
+        })?;
+
+        match attr_name {
+            "lat" if !(-90.0..=90.0).contains(&parsed) => {
+                return Err(SchemaValidationError::validation(
+                    path.as_str(),
+                    format!("lat {parsed} is outside the valid range [-90, 90]"),
+                ));
+            }
+            "lon" if !(-180.0..=180.0).contains(&parsed) => {
+                return Err(SchemaValidationError::validation(
+                    path.as_str(),
+                    format!("lon {parsed} is outside the valid range [-180, 180]"),
+                ));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_EVENT: &str = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/></event>"#;
+
+    #[test]
+    fn accepts_a_minimal_well_formed_event() {
+        assert!(validate_against_cot_schema(VALID_EVENT).is_ok());
+    }
+
+    #[test]
+    fn accepts_a_single_detail_child() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/><detail><contact callsign="A"/></detail></event>"#;
+        assert!(validate_against_cot_schema(xml).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_non_event_root() {
+        let err = validate_against_cot_schema("<foo/>").unwrap_err();
+        assert!(matches!(err, SchemaValidationError::Validation { .. }));
+    }
+
+    #[test]
+    fn rejects_a_missing_required_event_attribute() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" how="m-g"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/></event>"#;
+        let err = validate_against_cot_schema(xml).unwrap_err();
+        match err {
+            SchemaValidationError::Validation { path, .. } => assert_eq!(path, "event/@stale"),
+            other => panic!("expected Validation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_a_missing_point() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"/>"#;
+        let err = validate_against_cot_schema(xml).unwrap_err();
+        match err {
+            SchemaValidationError::Validation { path, .. } => assert_eq!(path, "event/point"),
+            other => panic!("expected Validation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_more_than_one_point() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/></event>"#;
+        assert!(validate_against_cot_schema(xml).is_err());
+    }
+
+    #[test]
+    fn rejects_more_than_one_detail() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/><detail/><detail/></event>"#;
+        assert!(validate_against_cot_schema(xml).is_err());
+    }
+
+    #[test]
+    fn rejects_an_unknown_event_child() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/><bogus/></event>"#;
+        let err = validate_against_cot_schema(xml).unwrap_err();
+        match err {
+            SchemaValidationError::Validation { path, .. } => assert_eq!(path, "event/bogus"),
+            other => panic!("expected Validation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_lat_out_of_range() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"><point lat="91.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/></event>"#;
+        let err = validate_against_cot_schema(xml).unwrap_err();
+        match err {
+            SchemaValidationError::Validation { path, .. } => assert_eq!(path, "event/point/@lat"),
+            other => panic!("expected Validation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_non_numeric_point_attribute() {
+        let xml = r#"<event version="2.0" uid="U1" type="a-f-G" time="t" start="t" stale="t" how="m-g"><point lat="north" lon="2.0" hae="3.0" ce="4.0" le="5.0"/></event>"#;
+        assert!(validate_against_cot_schema(xml).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        let err = validate_against_cot_schema("<event><point").unwrap_err();
+        assert!(matches!(err, SchemaValidationError::Malformed { .. }));
+    }
+}
@@ -0,0 +1,269 @@
+//! Stale-time lifecycle tracking and expiry for [`CotEvent`]s.
+//!
+//! A CoT event's `stale` timestamp marks when it should stop being treated
+//! as current, but [`CotEvent`] itself has no notion of "now" — it's a
+//! point-in-time record, not a running process. [`StaleTracker`] fills that
+//! gap: it ingests events keyed by `uid`, keeps only the newest version of
+//! each track, and on each poll hands back the set of uids whose `stale`
+//! time has passed, so a consumer mesh-syncing CoT traffic can garbage-
+//! collect dropped tracks and cancelled emergencies the same way an
+//! embedded reminder/task scheduler periodically scans stored records and
+//! fires on time-based expiry.
+//!
+//! [`StaleDefaults`] complements this for event construction: it picks a
+//! sensible default stale offset by [`CotType`] category (short-lived for
+//! position reports, longer for chat) so callers of
+//! [`CotEvent::new_location_update`] and friends don't have to hardcode an
+//! interval themselves.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::cot_events::CotEvent;
+use crate::cot_type::CotType;
+
+/// Tracks the newest known version of each `uid`'d [`CotEvent`] and reports
+/// which ones have gone stale.
+///
+/// Ingesting an event for a `uid` already being tracked replaces the stored
+/// version only if the incoming event's `time` is at least as recent,
+/// mirroring the last-writer-wins rule [`CotEvent::merge`] uses for scalar
+/// fields.
+#[derive(Debug, Clone, Default)]
+pub struct StaleTracker {
+    tracked: HashMap<String, CotEvent>,
+}
+
+impl StaleTracker {
+    /// Creates an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ingests an event, keeping it only if it's newer than any previously
+    /// tracked version of the same `uid`.
+    pub fn ingest(&mut self, event: CotEvent) {
+        match self.tracked.get(event.uid.as_str()) {
+            Some(existing) if existing.time > event.time => {}
+            _ => {
+                self.tracked.insert(event.uid.clone(), event);
+            }
+        }
+    }
+
+    /// Returns the number of tracks currently held.
+    pub fn len(&self) -> usize {
+        self.tracked.len()
+    }
+
+    /// Returns whether no tracks are currently held.
+    pub fn is_empty(&self) -> bool {
+        self.tracked.is_empty()
+    }
+
+    /// Returns the newest tracked event for `uid`, if any.
+    pub fn get(&self, uid: &str) -> Option<&CotEvent> {
+        self.tracked.get(uid)
+    }
+
+    /// Removes and returns the uids of every tracked event that has gone
+    /// stale as of `now`, so a caller can garbage-collect dropped tracks
+    /// and cancelled emergencies from whatever store mirrors this tracker.
+    pub fn poll_expired(&mut self, now: DateTime<Utc>) -> Vec<String> {
+        let expired: Vec<String> = self
+            .tracked
+            .values()
+            .filter(|event| event.is_stale(now))
+            .map(|event| event.uid.clone())
+            .collect();
+        for uid in &expired {
+            self.tracked.remove(uid);
+        }
+        expired
+    }
+}
+
+/// Default stale offsets applied when constructing a [`CotEvent`], chosen
+/// by the event type's [`CotType`] category.
+///
+/// Falls back to `default` for any type that isn't a position report
+/// (`a-...`), TAK GeoChat message (`b-t-f`), or one of the
+/// [`EmergencyType`](crate::cot_events::EmergencyType) codes (`b-a-o-...`,
+/// `b-a-g`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleDefaults {
+    position_report: Duration,
+    chat: Duration,
+    emergency: Duration,
+    default: Duration,
+}
+
+impl Default for StaleDefaults {
+    /// Position reports go stale quickly since a unit's location is only
+    /// trustworthy for a few minutes; chat and emergency alerts stay live
+    /// much longer since they're point-in-time records a user still needs
+    /// to see long after the sender last reported position.
+    fn default() -> Self {
+        Self {
+            position_report: Duration::minutes(5),
+            chat: Duration::hours(1),
+            emergency: Duration::hours(12),
+            default: Duration::minutes(5),
+        }
+    }
+}
+
+impl StaleDefaults {
+    /// Starts a [`StaleDefaultsBuilder`] seeded with [`StaleDefaults::default`].
+    pub fn builder() -> StaleDefaultsBuilder {
+        StaleDefaultsBuilder {
+            defaults: Self::default(),
+        }
+    }
+
+    /// Resolves the default stale offset for `event_type` (e.g.
+    /// `"a-f-G-U-C"`, `"b-t-f"`), falling back to `default` if the type
+    /// doesn't parse as a [`CotType`] or doesn't match a known category.
+    pub fn offset_for(&self, event_type: &str) -> Duration {
+        let Ok(cot_type) = CotType::parse(event_type) else {
+            return self.default;
+        };
+        if cot_type.is_atom() {
+            self.position_report
+        } else if cot_type.matches_prefix("b-t-f") {
+            self.chat
+        } else if cot_type.matches_prefix("b-a-o") || cot_type.matches_prefix("b-a-g") {
+            // Covers every `EmergencyType` code: alerts and their
+            // cancellation (`b-a-o-...`) as well as a geo-fence breach
+            // (`b-a-g`).
+            self.emergency
+        } else {
+            self.default
+        }
+    }
+}
+
+/// Builder for [`StaleDefaults`].
+pub struct StaleDefaultsBuilder {
+    defaults: StaleDefaults,
+}
+
+impl StaleDefaultsBuilder {
+    /// Sets the offset applied to position-report (atom, `a-...`) types.
+    pub fn position_report(mut self, offset: Duration) -> Self {
+        self.defaults.position_report = offset;
+        self
+    }
+
+    /// Sets the offset applied to TAK GeoChat (`b-t-f`) types.
+    pub fn chat(mut self, offset: Duration) -> Self {
+        self.defaults.chat = offset;
+        self
+    }
+
+    /// Sets the offset applied to emergency alert (`b-a-o-...`, `b-a-g`)
+    /// types.
+    pub fn emergency(mut self, offset: Duration) -> Self {
+        self.defaults.emergency = offset;
+        self
+    }
+
+    /// Sets the offset applied to any type that doesn't match a known
+    /// category.
+    pub fn default_offset(mut self, offset: Duration) -> Self {
+        self.defaults.default = offset;
+        self
+    }
+
+    /// Builds the [`StaleDefaults`].
+    pub fn build(self) -> StaleDefaults {
+        self.defaults
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn event_with(uid: &str, time: DateTime<Utc>, stale: DateTime<Utc>) -> CotEvent {
+        let mut event = CotEvent::default();
+        event.uid = uid.to_string();
+        event.time = time;
+        event.start = time;
+        event.stale = stale;
+        event
+    }
+
+    #[test]
+    fn tracker_keeps_only_the_newest_version_per_uid() {
+        let mut tracker = StaleTracker::new();
+        let older = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let newer = Utc.with_ymd_and_hms(2024, 1, 1, 12, 5, 0).unwrap();
+
+        tracker.ingest(event_with("UID-1", older, older + Duration::minutes(1)));
+        tracker.ingest(event_with("UID-1", newer, newer + Duration::minutes(1)));
+
+        assert_eq!(tracker.len(), 1);
+        assert_eq!(tracker.get("UID-1").unwrap().time, newer);
+    }
+
+    #[test]
+    fn tracker_ignores_an_older_update_arriving_after_a_newer_one() {
+        let mut tracker = StaleTracker::new();
+        let older = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let newer = Utc.with_ymd_and_hms(2024, 1, 1, 12, 5, 0).unwrap();
+
+        tracker.ingest(event_with("UID-1", newer, newer + Duration::minutes(1)));
+        tracker.ingest(event_with("UID-1", older, older + Duration::minutes(1)));
+
+        assert_eq!(tracker.get("UID-1").unwrap().time, newer);
+    }
+
+    #[test]
+    fn poll_expired_removes_and_returns_only_stale_tracks() {
+        let mut tracker = StaleTracker::new();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+
+        tracker.ingest(event_with(
+            "DROPPED",
+            now - Duration::hours(1),
+            now - Duration::minutes(1),
+        ));
+        tracker.ingest(event_with("LIVE", now, now + Duration::minutes(5)));
+
+        let expired = tracker.poll_expired(now);
+
+        assert_eq!(expired, vec!["DROPPED".to_string()]);
+        assert_eq!(tracker.len(), 1);
+        assert!(tracker.get("LIVE").is_some());
+        assert!(tracker.get("DROPPED").is_none());
+    }
+
+    #[test]
+    fn stale_defaults_resolve_by_cot_type_category() {
+        let defaults = StaleDefaults::default();
+
+        assert_eq!(defaults.offset_for("a-f-G-U-C"), Duration::minutes(5));
+        assert_eq!(defaults.offset_for("b-t-f"), Duration::hours(1));
+        assert_eq!(defaults.offset_for("b-a-o-pan"), Duration::hours(12));
+        assert_eq!(defaults.offset_for("b-a-o-tbl"), Duration::hours(12));
+        assert_eq!(defaults.offset_for("b-a-o-can"), Duration::hours(12));
+        assert_eq!(defaults.offset_for("b-a-g"), Duration::hours(12));
+        assert_eq!(defaults.offset_for("c-capability"), defaults.default);
+    }
+
+    #[test]
+    fn builder_overrides_the_defaults() {
+        let defaults = StaleDefaults::builder()
+            .position_report(Duration::seconds(30))
+            .chat(Duration::days(1))
+            .build();
+
+        assert_eq!(defaults.offset_for("a-f-G-U-C"), Duration::seconds(30));
+        assert_eq!(defaults.offset_for("b-t-f"), Duration::days(1));
+        // Untouched offset keeps its default.
+        assert_eq!(defaults.offset_for("b-a-o-can"), Duration::hours(12));
+    }
+}
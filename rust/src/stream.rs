@@ -0,0 +1,302 @@
+//! Streaming, incremental CoT XML to Ditto document conversion over a
+//! [`std::io::Read`] source (e.g. a TAK TCP/UDP feed), for callers who would
+//! rather not buffer an entire session's worth of events before converting
+//! any of them.
+
+use crate::cot_events::CotEvent;
+use crate::ditto::to_ditto::{cot_to_document, cot_to_flattened_document, CotDocument};
+use crate::error::{CotConversionError, CotError};
+use serde_json::Value;
+use std::io::{BufRead, Read};
+
+/// How many bytes to pull from the underlying reader per [`Read::read`]
+/// call while growing the internal buffer in search of the next complete
+/// `<event>...</event>` block.
+const READ_CHUNK_SIZE: usize = 4096;
+
+/// Returns the position right after the first occurrence of `needle` in
+/// `haystack`, or `None` if `needle` isn't (yet) fully buffered.
+fn find_after(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|pos| pos + needle.len())
+}
+
+/// Finds the next complete `<event ...>...</event>` block in `buf`,
+/// returning its byte range. CoT events never nest `<event>` elements, so
+/// the first `</event>` found after an `<event` start always closes it.
+fn next_event_span(buf: &[u8]) -> Option<(usize, usize)> {
+    let start = buf.windows(6).position(|w| w == b"<event")?;
+    let end = find_after(&buf[start..], b"</event>")? + start;
+    Some((start, end))
+}
+
+/// Incrementally pulls complete `<event>...</event>` blocks out of a `Read`
+/// source, parsing each into a [`CotEvent`] as soon as enough bytes have
+/// arrived, without ever buffering more of the feed than the current
+/// in-flight event requires.
+struct EventReader<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+    reader_exhausted: bool,
+}
+
+impl<R: Read> EventReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            reader_exhausted: false,
+        }
+    }
+
+    fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the next buffered event block, reading more from the
+    /// underlying source as needed, or `None` once the source is exhausted
+    /// and no further complete block remains.
+    fn next_event(&mut self) -> Option<Result<CotEvent, CotError>> {
+        loop {
+            if let Some((start, end)) = next_event_span(&self.buf) {
+                let xml = String::from_utf8_lossy(&self.buf[start..end]).into_owned();
+                self.buf.drain(..end);
+                return Some(CotEvent::from_xml(&xml));
+            }
+
+            if self.reader_exhausted {
+                return None;
+            }
+
+            let mut chunk = [0u8; READ_CHUNK_SIZE];
+            match self.reader.read(&mut chunk) {
+                Ok(0) => self.reader_exhausted = true,
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(e) => return Some(Err(CotError::XmlError(e.to_string()))),
+            }
+        }
+    }
+}
+
+/// Lazily converts a continuous CoT XML feed from any [`Read`] source into
+/// [`CotDocument`]s, one `<event>` at a time, instead of requiring the
+/// caller to buffer the whole input and call [`cot_to_document`] per event.
+///
+/// Bytes are pulled from the underlying source only as needed to complete
+/// the next `<event>...</event>` block; [`CotDocumentStream::get_ref`]
+/// exposes the reader so an integrator embedding this in an event loop can,
+/// for a socket, register its file descriptor with an external
+/// readiness-based selector, the way low-level protocol crates hand back a
+/// raw handle for that kind of integration.
+pub struct CotDocumentStream<R: Read> {
+    events: EventReader<R>,
+    peer_key: String,
+}
+
+impl<R: Read> CotDocumentStream<R> {
+    /// Wraps `reader`, tagging every produced document with `peer_key`.
+    pub fn new(reader: R, peer_key: impl Into<String>) -> Self {
+        Self {
+            events: EventReader::new(reader),
+            peer_key: peer_key.into(),
+        }
+    }
+
+    /// Borrows the underlying reader, e.g. to register a socket's file
+    /// descriptor with an external readiness-based selector.
+    pub fn get_ref(&self) -> &R {
+        self.events.get_ref()
+    }
+
+    /// Mutably borrows the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.events.get_mut()
+    }
+}
+
+impl<R: Read> Iterator for CotDocumentStream<R> {
+    type Item = Result<CotDocument, CotConversionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.next_event().map(|result| {
+            result
+                .map_err(CotConversionError::from)
+                .map(|event| cot_to_document(&event, &self.peer_key))
+        })
+    }
+}
+
+/// Like [`CotDocumentStream`], but yields flattened (DQL-friendly) `Value`
+/// documents via [`cot_to_flattened_document`] instead of typed
+/// [`CotDocument`]s.
+pub struct FlattenedCotDocumentStream<R: Read> {
+    events: EventReader<R>,
+    peer_key: String,
+}
+
+impl<R: Read> FlattenedCotDocumentStream<R> {
+    /// Wraps `reader`, tagging every produced document with `peer_key`.
+    pub fn new(reader: R, peer_key: impl Into<String>) -> Self {
+        Self {
+            events: EventReader::new(reader),
+            peer_key: peer_key.into(),
+        }
+    }
+
+    /// Borrows the underlying reader, e.g. to register a socket's file
+    /// descriptor with an external readiness-based selector.
+    pub fn get_ref(&self) -> &R {
+        self.events.get_ref()
+    }
+
+    /// Mutably borrows the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.events.get_mut()
+    }
+}
+
+impl<R: Read> Iterator for FlattenedCotDocumentStream<R> {
+    type Item = Result<Value, CotConversionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.next_event().map(|result| {
+            result
+                .map_err(CotConversionError::from)
+                .map(|event| cot_to_flattened_document(&event, &self.peer_key))
+        })
+    }
+}
+
+/// Incrementally parses a continuous CoT XML feed from any [`BufRead`]
+/// source (e.g. a live TAK TCP/TLS socket) into raw [`CotEvent`]s, one
+/// `<event>` at a time — the typed-event counterpart to
+/// [`CotDocumentStream`]/[`FlattenedCotDocumentStream`] for callers who want
+/// events themselves rather than already-converted Ditto documents.
+///
+/// Bytes are pulled from the underlying source only as needed to complete
+/// the next `<event>...</event>` block, the same incremental buffering
+/// [`EventReader`] already does for the document-producing streams above.
+pub struct CotStreamReader<R: BufRead> {
+    events: EventReader<R>,
+}
+
+impl<R: BufRead> CotStreamReader<R> {
+    /// Wraps `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            events: EventReader::new(reader),
+        }
+    }
+
+    /// Borrows the underlying reader, e.g. to register a socket's file
+    /// descriptor with an external readiness-based selector.
+    pub fn get_ref(&self) -> &R {
+        self.events.get_ref()
+    }
+
+    /// Mutably borrows the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.events.get_mut()
+    }
+}
+
+impl<R: BufRead> Iterator for CotStreamReader<R> {
+    type Item = Result<CotEvent, CotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.next_event()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn two_location_events() -> String {
+        format!(
+            "{}{}",
+            crate::cot_events::CotEvent::builder()
+                .uid("USER-1")
+                .event_type("a-f-G-U-C")
+                .location(1.0, 2.0, 3.0)
+                .callsign("ALPHA-1")
+                .build()
+                .to_xml()
+                .unwrap(),
+            crate::cot_events::CotEvent::builder()
+                .uid("USER-2")
+                .event_type("a-f-G-U-C")
+                .location(4.0, 5.0, 6.0)
+                .callsign("BRAVO-2")
+                .build()
+                .to_xml()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn yields_one_document_per_event() {
+        let feed = two_location_events();
+        let stream = CotDocumentStream::new(Cursor::new(feed), "peer-1");
+        let docs: Vec<_> = stream.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(docs.len(), 2);
+        assert!(matches!(docs[0], CotDocument::MapItem(_)));
+        assert!(matches!(docs[1], CotDocument::MapItem(_)));
+    }
+
+    #[test]
+    fn yields_nothing_for_empty_input() {
+        let stream = CotDocumentStream::new(Cursor::new(Vec::new()), "peer-1");
+        assert_eq!(stream.count(), 0);
+    }
+
+    #[test]
+    fn stops_on_a_trailing_partial_event() {
+        let mut feed = two_location_events();
+        // Truncate mid-second-event so only the first is complete.
+        feed.truncate(feed.len() - 20);
+        let stream = CotDocumentStream::new(Cursor::new(feed), "peer-1");
+        let docs: Vec<_> = stream.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(docs.len(), 1);
+    }
+
+    #[test]
+    fn flattened_variant_agrees_on_count() {
+        let feed = two_location_events();
+        let stream = FlattenedCotDocumentStream::new(Cursor::new(feed), "peer-1");
+        let docs: Vec<_> = stream.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(docs.len(), 2);
+    }
+
+    #[test]
+    fn get_ref_exposes_underlying_reader() {
+        let stream = CotDocumentStream::new(Cursor::new(Vec::<u8>::new()), "peer-1");
+        assert_eq!(stream.get_ref().position(), 0);
+    }
+
+    #[test]
+    fn cot_stream_reader_yields_raw_events() {
+        let feed = two_location_events();
+        let stream = CotStreamReader::new(Cursor::new(feed));
+        let events: Vec<_> = stream.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].uid, "USER-1");
+        assert_eq!(events[1].uid, "USER-2");
+    }
+
+    #[test]
+    fn cot_stream_reader_stops_on_a_trailing_partial_event() {
+        let mut feed = two_location_events();
+        feed.truncate(feed.len() - 20);
+        let stream = CotStreamReader::new(Cursor::new(feed));
+        let events: Vec<_> = stream.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(events.len(), 1);
+    }
+}
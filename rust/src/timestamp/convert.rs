@@ -0,0 +1,98 @@
+//! Bridges between [`Timestamp`] and each clock library's native date-time
+//! type, behind the `time-backend` feature, so a caller that has already
+//! standardized on `time` (or `chrono`) elsewhere in their own codebase
+//! isn't forced to convert through strings to hand a value to this crate.
+//!
+//! Conversions go through [`Timestamp::to_unix_micros`] rather than its
+//! internal TAI representation, so every bridge here is UTC — a `Timestamp`
+//! parsed from a `TAI`/`GPS`-annotated literal converts the same as the
+//! equivalent UTC instant would.
+
+use super::{Timestamp, TimestampError};
+use time::OffsetDateTime;
+
+/// Failure converting a foreign date-time type into a [`Timestamp`] or vice
+/// versa.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConvertError {
+    /// The source instant's Unix-microsecond representation couldn't be
+    /// computed (see [`TimestampError`]).
+    #[error(transparent)]
+    Timestamp(#[from] TimestampError),
+    /// The instant is out of range for the target type to represent.
+    #[error("instant is out of range for the target date-time type")]
+    OutOfRange,
+}
+
+impl TryFrom<Timestamp> for OffsetDateTime {
+    type Error = ConvertError;
+
+    fn try_from(timestamp: Timestamp) -> Result<Self, Self::Error> {
+        let micros = timestamp.to_unix_micros()?;
+        OffsetDateTime::from_unix_timestamp_nanos((micros as i128) * 1_000)
+            .map_err(|_| ConvertError::OutOfRange)
+    }
+}
+
+impl TryFrom<OffsetDateTime> for Timestamp {
+    type Error = ConvertError;
+
+    fn try_from(dt: OffsetDateTime) -> Result<Self, Self::Error> {
+        let rfc3339 = dt
+            .to_offset(time::UtcOffset::UTC)
+            .format(&time::format_description::well_known::Rfc3339)
+            .map_err(|_| ConvertError::OutOfRange)?;
+        Timestamp::parse(&rfc3339).map_err(ConvertError::from)
+    }
+}
+
+impl TryFrom<Timestamp> for chrono::DateTime<chrono::Utc> {
+    type Error = ConvertError;
+
+    fn try_from(timestamp: Timestamp) -> Result<Self, Self::Error> {
+        let micros = timestamp.to_unix_micros()?;
+        chrono::DateTime::<chrono::Utc>::from_timestamp_micros(micros as i64)
+            .ok_or(ConvertError::OutOfRange)
+    }
+}
+
+impl From<chrono::DateTime<chrono::Utc>> for Timestamp {
+    fn from(dt: chrono::DateTime<chrono::Utc>) -> Self {
+        // `to_rfc3339` always succeeds for a valid `DateTime<Utc>`, and the
+        // result is always `Timestamp::parse`-able as an (implicitly UTC)
+        // literal, so this conversion cannot actually fail in practice.
+        Timestamp::parse(&dt.to_rfc3339())
+            .expect("a chrono DateTime<Utc> always formats as a parseable RFC 3339 literal")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamp_round_trips_through_offset_date_time() {
+        let original = Timestamp::parse("2024-01-15T10:30:00Z").unwrap();
+        let time_dt = OffsetDateTime::try_from(original).unwrap();
+        let back = Timestamp::try_from(time_dt).unwrap();
+
+        assert_eq!(original.to_unix_micros(), back.to_unix_micros());
+    }
+
+    #[test]
+    fn timestamp_round_trips_through_chrono_date_time() {
+        let original = Timestamp::parse("2024-01-15T10:30:00Z").unwrap();
+        let chrono_dt = chrono::DateTime::<chrono::Utc>::try_from(original).unwrap();
+        let back = Timestamp::from(chrono_dt);
+
+        assert_eq!(original.to_unix_micros(), back.to_unix_micros());
+    }
+
+    #[test]
+    fn gps_scaled_timestamp_converts_as_its_utc_equivalent() {
+        let gps = Timestamp::parse("2024-01-15T10:30:19Z GPS").unwrap();
+        let time_dt = OffsetDateTime::try_from(gps).unwrap();
+
+        assert_eq!(time_dt.unix_timestamp(), gps.to_unix_micros().unwrap() as i64 / 1_000_000);
+    }
+}
@@ -0,0 +1,614 @@
+//! High-precision, time-scale–aware instant representation.
+//!
+//! [`CotEvent::from_xml`](crate::cot_events::CotEvent::from_xml) and the
+//! Ditto document conversions in [`ditto::to_ditto`](crate::ditto::to_ditto)
+//! ultimately need a single linear instant in microseconds since the Unix
+//! epoch for the `time`/`start`/`stale` attributes and the `q`/`r`/`mm`
+//! document fields they feed. Plain RFC 3339 parsing assumes every input is
+//! UTC, but sensor and unmanned-system traffic (`a-u-S`, `a-u-A`, `a-u-G`)
+//! frequently timestamps against GPS or TAI instead, and a naive
+//! UTC-only parser either misinterprets those values or has to fall back to
+//! a sentinel like `0`, silently corrupting the document.
+//!
+//! [`Timestamp`] stores every instant as nanoseconds of International Atomic
+//! Time (TAI) elapsed since the Unix epoch — a single continuous count with
+//! no leap-second discontinuities, in the spirit of `hifitime`'s `Epoch` —
+//! together with the [`TimeScale`] the original text was expressed in.
+//! [`Timestamp::to_unix_micros`] performs the leap-second-correct TAI→UTC
+//! conversion on the way out, and is fallible rather than clamping out-of-
+//! range or ambiguous instants to zero.
+
+use std::fmt;
+
+/// `time`-crate backed formatting, parallel to the `chrono`-backed functions
+/// in this module, behind the `time-backend` feature.
+#[cfg(feature = "time-backend")]
+pub mod time_backend;
+
+/// `From`/`TryFrom` bridges between [`Timestamp`] and each backend's native
+/// date-time type, so callers linking both `chrono` and `time` aren't
+/// forced to pick one to talk to this crate, behind the `time-backend`
+/// feature.
+#[cfg(feature = "time-backend")]
+pub mod convert;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// GPS time has run continuously since its epoch and does not observe leap
+/// seconds; it is a fixed 19s behind TAI for every instant after 1980-01-06.
+const GPS_TAI_OFFSET_SECS: i64 = 19;
+
+/// The time scale a [`Timestamp`] was originally expressed in.
+///
+/// This is metadata only: internally every [`Timestamp`] is normalized to
+/// TAI nanoseconds immediately on parse, so arithmetic and comparisons never
+/// need to consult it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeScale {
+    /// Coordinated Universal Time: the default when no scale is annotated,
+    /// and the scale every Ditto document field round-trips through.
+    Utc,
+    /// International Atomic Time: the continuous scale [`Timestamp`] stores
+    /// internally.
+    Tai,
+    /// GPS time: TAI minus a fixed 19-second offset, carried by GPS-sourced
+    /// sensor and unmanned-system CoT traffic.
+    Gps,
+}
+
+impl fmt::Display for TimeScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TimeScale::Utc => "UTC",
+            TimeScale::Tai => "TAI",
+            TimeScale::Gps => "GPS",
+        })
+    }
+}
+
+/// Failure modes for parsing and converting [`Timestamp`]s.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TimestampError {
+    /// The input wasn't a recognized RFC 3339 literal, optionally followed
+    /// by a ` UTC`/` TAI`/` GPS` scale annotation.
+    #[error("invalid timestamp '{0}': expected RFC 3339, optionally suffixed with ' UTC'/' TAI'/' GPS'")]
+    InvalidFormat(String),
+
+    /// The instant's Unix-microsecond representation is negative (before
+    /// the Unix epoch) or exceeds what a `u64` can hold.
+    #[error("timestamp is out of the range representable as Unix microseconds")]
+    MicrosOutOfRange,
+}
+
+/// A table entry recording the TAI−UTC offset (in whole seconds) that has
+/// applied since `effective_at_unix_secs` (a nominal Unix second on the UTC
+/// side, ignoring leap seconds).
+struct LeapSecondEntry {
+    effective_at_unix_secs: i64,
+    tai_minus_utc_secs: i64,
+}
+
+/// Historical leap-second insertions, i.e. the IERS bulletin-C record of
+/// TAI−UTC. Before the first entry, TAI−UTC was not an integer number of
+/// seconds; inputs before 1972-01-01 use that first offset as a reasonable
+/// approximation rather than erroring.
+const LEAP_SECONDS: &[LeapSecondEntry] = &[
+    LeapSecondEntry { effective_at_unix_secs: 63_072_000, tai_minus_utc_secs: 10 }, // 1972-01-01
+    LeapSecondEntry { effective_at_unix_secs: 78_796_800, tai_minus_utc_secs: 11 }, // 1972-07-01
+    LeapSecondEntry { effective_at_unix_secs: 94_694_400, tai_minus_utc_secs: 12 }, // 1973-01-01
+    LeapSecondEntry { effective_at_unix_secs: 126_230_400, tai_minus_utc_secs: 13 }, // 1974-01-01
+    LeapSecondEntry { effective_at_unix_secs: 157_766_400, tai_minus_utc_secs: 14 }, // 1975-01-01
+    LeapSecondEntry { effective_at_unix_secs: 189_302_400, tai_minus_utc_secs: 15 }, // 1976-01-01
+    LeapSecondEntry { effective_at_unix_secs: 220_924_800, tai_minus_utc_secs: 16 }, // 1977-01-01
+    LeapSecondEntry { effective_at_unix_secs: 252_460_800, tai_minus_utc_secs: 17 }, // 1978-01-01
+    LeapSecondEntry { effective_at_unix_secs: 283_996_800, tai_minus_utc_secs: 18 }, // 1979-01-01
+    LeapSecondEntry { effective_at_unix_secs: 315_532_800, tai_minus_utc_secs: 19 }, // 1980-01-01
+    LeapSecondEntry { effective_at_unix_secs: 362_793_600, tai_minus_utc_secs: 20 }, // 1981-07-01
+    LeapSecondEntry { effective_at_unix_secs: 394_329_600, tai_minus_utc_secs: 21 }, // 1982-07-01
+    LeapSecondEntry { effective_at_unix_secs: 425_865_600, tai_minus_utc_secs: 22 }, // 1983-07-01
+    LeapSecondEntry { effective_at_unix_secs: 489_024_000, tai_minus_utc_secs: 23 }, // 1985-07-01
+    LeapSecondEntry { effective_at_unix_secs: 567_993_600, tai_minus_utc_secs: 24 }, // 1988-01-01
+    LeapSecondEntry { effective_at_unix_secs: 631_152_000, tai_minus_utc_secs: 25 }, // 1990-01-01
+    LeapSecondEntry { effective_at_unix_secs: 662_688_000, tai_minus_utc_secs: 26 }, // 1991-01-01
+    LeapSecondEntry { effective_at_unix_secs: 709_948_800, tai_minus_utc_secs: 27 }, // 1992-07-01
+    LeapSecondEntry { effective_at_unix_secs: 741_484_800, tai_minus_utc_secs: 28 }, // 1993-07-01
+    LeapSecondEntry { effective_at_unix_secs: 773_020_800, tai_minus_utc_secs: 29 }, // 1994-07-01
+    LeapSecondEntry { effective_at_unix_secs: 820_454_400, tai_minus_utc_secs: 30 }, // 1996-01-01
+    LeapSecondEntry { effective_at_unix_secs: 867_715_200, tai_minus_utc_secs: 31 }, // 1997-07-01
+    LeapSecondEntry { effective_at_unix_secs: 915_148_800, tai_minus_utc_secs: 32 }, // 1999-01-01
+    LeapSecondEntry { effective_at_unix_secs: 1_136_073_600, tai_minus_utc_secs: 33 }, // 2006-01-01
+    LeapSecondEntry { effective_at_unix_secs: 1_230_768_000, tai_minus_utc_secs: 34 }, // 2009-01-01
+    LeapSecondEntry { effective_at_unix_secs: 1_341_100_800, tai_minus_utc_secs: 35 }, // 2012-07-01
+    LeapSecondEntry { effective_at_unix_secs: 1_435_708_800, tai_minus_utc_secs: 36 }, // 2015-07-01
+    LeapSecondEntry { effective_at_unix_secs: 1_483_228_800, tai_minus_utc_secs: 37 }, // 2017-01-01
+];
+
+/// Returns the TAI−UTC offset, in whole seconds, that applies at
+/// `unix_secs` (a nominal Unix-epoch second count on the UTC side).
+fn tai_minus_utc_at(unix_secs: i64) -> i64 {
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|entry| entry.effective_at_unix_secs <= unix_secs)
+        .map(|entry| entry.tai_minus_utc_secs)
+        .unwrap_or(LEAP_SECONDS[0].tai_minus_utc_secs)
+}
+
+/// Strips a trailing ` UTC`/` TAI`/` GPS` scale annotation from `input`,
+/// defaulting to [`TimeScale::Utc`] when none is present.
+fn split_scale(input: &str) -> (&str, TimeScale) {
+    let trimmed = input.trim();
+    for (suffix, scale) in [
+        (" TAI", TimeScale::Tai),
+        (" GPS", TimeScale::Gps),
+        (" UTC", TimeScale::Utc),
+    ] {
+        if let Some(literal) = trimmed.strip_suffix(suffix) {
+            return (literal, scale);
+        }
+    }
+    (trimmed, TimeScale::Utc)
+}
+
+/// Parses an RFC 3339 literal into (Unix seconds, subsecond nanoseconds,
+/// whether the seconds field was the literal leap second `60`).
+///
+/// `chrono` rejects a `:60` seconds field outright, so a leap-second literal
+/// is parsed as `:59` and flagged for the caller to account for separately.
+fn parse_rfc3339_permitting_leap_second(
+    literal: &str,
+) -> Result<(i64, u32, bool), TimestampError> {
+    use chrono::DateTime;
+
+    let (to_parse, is_leap_second) = match literal.find(":60") {
+        Some(offset) => {
+            let mut patched = literal.to_string();
+            patched.replace_range(offset..offset + 3, ":59");
+            (patched, true)
+        }
+        None => (literal.to_string(), false),
+    };
+
+    let dt = DateTime::parse_from_rfc3339(&to_parse)
+        .map_err(|_| TimestampError::InvalidFormat(literal.to_string()))?;
+    Ok((dt.timestamp(), dt.timestamp_subsec_nanos(), is_leap_second))
+}
+
+/// A single instant in time, stored internally as TAI nanoseconds elapsed
+/// since the Unix epoch so that arithmetic never has to special-case leap
+/// seconds, alongside the [`TimeScale`] it was parsed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp {
+    tai_nanos: i128,
+    source_scale: TimeScale,
+}
+
+impl Timestamp {
+    /// Parses an RFC 3339 timestamp, optionally suffixed with a scale
+    /// annotation (e.g. `"2024-01-15T10:30:00Z TAI"`); an unannotated
+    /// literal is interpreted as UTC.
+    ///
+    /// A literal leap second (`...T23:59:60Z`) is accepted and represented
+    /// as a distinct TAI instant one second after `...T23:59:59Z`, rather
+    /// than being rejected or collapsing onto the following midnight.
+    pub fn parse(input: &str) -> Result<Self, TimestampError> {
+        let (literal, source_scale) = split_scale(input);
+        let (unix_secs, subsec_nanos, is_leap_second) =
+            parse_rfc3339_permitting_leap_second(literal)?;
+
+        let tai_nanos = match source_scale {
+            TimeScale::Utc => {
+                // Use the offset in force *before* the leap second itself,
+                // then add the extra tick, so `:59`, `:60`, and the next
+                // day's `00:00:00` land on three consecutive TAI seconds
+                // instead of the literal leap second collapsing onto
+                // whichever neighbor its offset happens to match.
+                let offset = tai_minus_utc_at(unix_secs);
+                let mut nanos = (unix_secs as i128) * NANOS_PER_SEC
+                    + subsec_nanos as i128
+                    + (offset as i128) * NANOS_PER_SEC;
+                if is_leap_second {
+                    nanos += NANOS_PER_SEC;
+                }
+                nanos
+            }
+            TimeScale::Tai => (unix_secs as i128) * NANOS_PER_SEC + subsec_nanos as i128,
+            TimeScale::Gps => {
+                (unix_secs as i128) * NANOS_PER_SEC
+                    + subsec_nanos as i128
+                    + (GPS_TAI_OFFSET_SECS as i128) * NANOS_PER_SEC
+            }
+        };
+
+        Ok(Self { tai_nanos, source_scale })
+    }
+
+    /// The time scale this instant's source text was expressed in.
+    pub fn source_scale(&self) -> TimeScale {
+        self.source_scale
+    }
+
+    /// Converts this instant to microseconds since the Unix epoch (UTC),
+    /// the representation carried by the Ditto `q`/`r`/`mm` fields.
+    ///
+    /// Performs the leap-second-correct TAI→UTC conversion rather than
+    /// assuming TAI and UTC share an offset, and fails instead of clamping
+    /// when the instant falls before the epoch or overflows a `u64`.
+    pub fn to_unix_micros(&self) -> Result<u64, TimestampError> {
+        // The TAI-UTC offset only changes at whole-second leap-second
+        // boundaries, so a couple of fixed-point iterations from a rough
+        // guess always converges.
+        let mut unix_secs_guess = (self.tai_nanos / NANOS_PER_SEC) as i64;
+        for _ in 0..3 {
+            unix_secs_guess =
+                ((self.tai_nanos / NANOS_PER_SEC) as i64) - tai_minus_utc_at(unix_secs_guess);
+        }
+        let offset = tai_minus_utc_at(unix_secs_guess);
+
+        let utc_nanos = self.tai_nanos - (offset as i128) * NANOS_PER_SEC;
+        if utc_nanos < 0 {
+            return Err(TimestampError::MicrosOutOfRange);
+        }
+        u64::try_from(utc_nanos / 1000).map_err(|_| TimestampError::MicrosOutOfRange)
+    }
+}
+
+/// A plain UTC instant for the `time`/`start`/`stale`-style fields CoT
+/// producers actually send, as opposed to [`Timestamp`]'s leap-second-aware
+/// TAI model: a straightforward RFC 3339 parse/format pair, with no scale
+/// annotation and no leap-second table to consult.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CotTime(chrono::DateTime<chrono::Utc>);
+
+impl CotTime {
+    /// Parses an RFC 3339 timestamp with fractional seconds and either a
+    /// `Z` or `+hh:mm`/`-hh:mm` offset, normalizing to UTC.
+    pub fn parse(input: &str) -> Result<Self, TimestampError> {
+        chrono::DateTime::parse_from_rfc3339(input)
+            .map(|dt| Self(dt.with_timezone(&chrono::Utc)))
+            .map_err(|_| TimestampError::InvalidFormat(input.to_string()))
+    }
+
+    /// Microseconds since the Unix epoch (UTC). Instants before the epoch
+    /// saturate at `0` rather than wrapping, since no CoT timestamp this
+    /// crate handles predates 1970.
+    pub fn as_micros(&self) -> u64 {
+        self.0.timestamp_micros().max(0) as u64
+    }
+
+    /// Formats back to RFC 3339 at millisecond precision (equivalent to
+    /// [`SecondsFormat::Millis`](chrono::SecondsFormat::Millis)) with a
+    /// trailing `Z`, so sub-second precision round-trips instead of being
+    /// dropped or reconstructed with ad hoc string surgery.
+    pub fn to_rfc3339_millis(&self) -> String {
+        self.0.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+}
+
+impl From<chrono::DateTime<chrono::Utc>> for CotTime {
+    fn from(value: chrono::DateTime<chrono::Utc>) -> Self {
+        Self(value)
+    }
+}
+
+/// Ordered fallback formats [`parse_flexible_timestamp`] tries after a
+/// strict RFC 3339 parse fails, mirroring the near-ISO-8601 variants real
+/// CoT producers emit in practice: missing fractional seconds, and missing
+/// seconds entirely.
+const FLEXIBLE_DATETIME_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%#z", "%Y-%m-%dT%H:%M%#z"];
+
+/// How [`parse_flexible_timestamp`] pads a bare date (no time-of-day) out to
+/// a full instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateBound {
+    /// Pad with `00:00:00`, for an opening bound such as `time`/`start`.
+    Floor,
+    /// Pad with `23:59:59`, for an open-ended bound such as `stale`, so a
+    /// bare-date stale value reads as valid through the end of that day
+    /// rather than expiring at midnight.
+    Ceil,
+}
+
+/// Tolerantly parses a CoT timestamp attribute into microseconds since the
+/// Unix epoch (UTC): the literal `"now"`, strict RFC 3339, or one of the
+/// near-ISO-8601 variants in [`FLEXIBLE_DATETIME_FORMATS`], falling back to
+/// a bare `YYYY-MM-DD` date padded per `bound` if even those don't match.
+///
+/// This only covers the UTC-assuming formats real producers get wrong;
+/// [`Timestamp::parse`] remains the entry point for TAI/GPS-scaled
+/// timestamps.
+pub fn parse_flexible_timestamp(s: &str, bound: DateBound) -> Result<i64, TimestampError> {
+    use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+
+    if s.eq_ignore_ascii_case("now") {
+        return Ok(Utc::now().timestamp_micros());
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc).timestamp_micros());
+    }
+
+    for format in FLEXIBLE_DATETIME_FORMATS {
+        if let Ok(dt) = DateTime::parse_from_str(s, format) {
+            return Ok(dt.with_timezone(&Utc).timestamp_micros());
+        }
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let time_of_day = match bound {
+            DateBound::Floor => NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+            DateBound::Ceil => NaiveTime::from_hms_opt(23, 59, 59).unwrap(),
+        };
+        return Ok(Utc.from_utc_datetime(&date.and_time(time_of_day)).timestamp_micros());
+    }
+
+    Err(TimestampError::InvalidFormat(s.to_string()))
+}
+
+/// The unit a plain numeric epoch-offset field (as opposed to a [`Timestamp`]
+/// literal) is expressed in, for callers that don't control how upstream
+/// data encodes its timestamps. Mirrors tantivy's `DatePrecision`: a
+/// document field tagged `Millis` and read as `Micros` is off by a factor of
+/// 1000, not merely imprecise, so getting this explicit beats guessing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimePrecision {
+    /// Whole seconds since the Unix epoch.
+    Seconds,
+    /// Milliseconds since the Unix epoch.
+    Millis,
+    /// Microseconds since the Unix epoch. The Ditto CoT schema's `n`/`o`
+    /// time fields use this.
+    Micros,
+    /// Nanoseconds since the Unix epoch.
+    Nanos,
+}
+
+impl TimePrecision {
+    /// Converts `value`, expressed in this precision's unit, to whole
+    /// microseconds since the Unix epoch.
+    fn to_micros(self, value: f64) -> i64 {
+        let micros = match self {
+            TimePrecision::Seconds => value * 1_000_000.0,
+            TimePrecision::Millis => value * 1_000.0,
+            TimePrecision::Micros => value,
+            TimePrecision::Nanos => value / 1_000.0,
+        };
+        micros as i64
+    }
+}
+
+/// Converts `value`, an epoch offset expressed in `precision` units, to an
+/// RFC 3339 string, falling back to the current time if the result is
+/// outside the range `chrono` can represent.
+pub fn epoch_to_rfc3339(value: f64, precision: TimePrecision) -> String {
+    try_epoch_to_rfc3339(value, precision).unwrap_or_else(|| chrono::Utc::now().to_rfc3339())
+}
+
+/// Like [`epoch_to_rfc3339`], but returns `None` instead of falling back to
+/// the current time when `value` falls outside the range `chrono` can
+/// represent as a UTC instant.
+pub fn try_epoch_to_rfc3339(value: f64, precision: TimePrecision) -> Option<String> {
+    use chrono::TimeZone;
+
+    let micros = precision.to_micros(value);
+    chrono::Utc
+        .timestamp_opt(
+            micros.div_euclid(1_000_000),
+            (micros.rem_euclid(1_000_000) * 1_000) as u32,
+        )
+        .single()
+        .map(|dt| dt.to_rfc3339())
+}
+
+/// Like [`epoch_to_rfc3339`], but renders the result in `offset_secs`
+/// seconds east of UTC instead of `Z`, for replaying a timestamp in the
+/// producer's originating wall-clock offset (e.g. a CoT event's
+/// `tz_offset_secs`) rather than normalizing it away.
+pub fn epoch_to_rfc3339_with_offset(value: f64, precision: TimePrecision, offset_secs: i32) -> String {
+    try_epoch_to_rfc3339_with_offset(value, precision, offset_secs)
+        .unwrap_or_else(|| epoch_to_rfc3339(value, precision))
+}
+
+/// Like [`try_epoch_to_rfc3339`], but renders the result in `offset_secs`
+/// seconds east of UTC instead of `Z`.
+pub fn try_epoch_to_rfc3339_with_offset(
+    value: f64,
+    precision: TimePrecision,
+    offset_secs: i32,
+) -> Option<String> {
+    use chrono::TimeZone;
+
+    let micros = precision.to_micros(value);
+    let utc = chrono::Utc
+        .timestamp_opt(
+            micros.div_euclid(1_000_000),
+            (micros.rem_euclid(1_000_000) * 1_000) as u32,
+        )
+        .single()?;
+    let offset = chrono::FixedOffset::east_opt(offset_secs)?;
+    Some(utc.with_timezone(&offset).to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utc_round_trips_through_tai() {
+        let utc = Timestamp::parse("2024-01-15T10:30:00.000Z").unwrap();
+        let tai = Timestamp::parse("2024-01-15T10:30:37.000Z TAI").unwrap();
+        assert_eq!(utc.to_unix_micros().unwrap(), tai.to_unix_micros().unwrap());
+    }
+
+    #[test]
+    fn gps_is_nineteen_seconds_behind_tai() {
+        let tai = Timestamp::parse("2024-01-15T10:30:00.000Z TAI").unwrap();
+        let gps = Timestamp::parse("2024-01-15T10:29:41.000Z GPS").unwrap();
+        assert_eq!(tai.tai_nanos, gps.tai_nanos);
+    }
+
+    #[test]
+    fn unannotated_literal_defaults_to_utc() {
+        let plain = Timestamp::parse("2024-01-15T10:30:00.000Z").unwrap();
+        let explicit = Timestamp::parse("2024-01-15T10:30:00.000Z UTC").unwrap();
+        assert_eq!(plain.to_unix_micros().unwrap(), explicit.to_unix_micros().unwrap());
+    }
+
+    #[test]
+    fn leap_second_does_not_collapse_into_a_neighboring_second() {
+        let before = Timestamp::parse("2016-12-31T23:59:59Z").unwrap();
+        let leap = Timestamp::parse("2016-12-31T23:59:60Z").unwrap();
+        let after = Timestamp::parse("2017-01-01T00:00:00Z").unwrap();
+
+        assert_eq!(leap.tai_nanos - before.tai_nanos, NANOS_PER_SEC);
+        assert_eq!(after.tai_nanos - leap.tai_nanos, NANOS_PER_SEC);
+    }
+
+    #[test]
+    fn malformed_input_is_an_error_not_a_zeroed_timestamp() {
+        assert!(Timestamp::parse("not a timestamp").is_err());
+    }
+
+    #[test]
+    fn pre_epoch_instant_is_out_of_range_for_unix_micros() {
+        let before_epoch = Timestamp::parse("1960-01-01T00:00:00Z").unwrap();
+        assert_eq!(before_epoch.to_unix_micros(), Err(TimestampError::MicrosOutOfRange));
+    }
+
+    #[test]
+    fn cot_time_parses_a_z_suffixed_fractional_timestamp() {
+        let parsed = CotTime::parse("2021-02-27T20:32:24.913Z").unwrap();
+        assert_eq!(parsed.as_micros(), 1_614_457_944_913_000);
+    }
+
+    #[test]
+    fn cot_time_normalizes_an_explicit_offset_to_utc() {
+        let offset = CotTime::parse("2021-02-27T22:32:24.913+02:00").unwrap();
+        let utc = CotTime::parse("2021-02-27T20:32:24.913Z").unwrap();
+        assert_eq!(offset, utc);
+    }
+
+    #[test]
+    fn cot_time_rejects_a_non_rfc3339_literal() {
+        assert!(CotTime::parse("not a timestamp").is_err());
+    }
+
+    #[test]
+    fn cot_time_round_trips_through_rfc3339_millis() {
+        let parsed = CotTime::parse("2021-02-27T20:32:24.913Z").unwrap();
+        assert_eq!(parsed.to_rfc3339_millis(), "2021-02-27T20:32:24.913Z");
+    }
+
+    #[test]
+    fn cot_time_truncates_sub_millisecond_precision_on_format() {
+        let parsed = CotTime::parse("2021-02-27T20:32:24.913456Z").unwrap();
+        assert_eq!(parsed.to_rfc3339_millis(), "2021-02-27T20:32:24.913Z");
+        assert_eq!(parsed.as_micros(), 1_614_457_944_913_456);
+    }
+
+    #[test]
+    fn micros_and_millis_of_the_same_instant_agree_once_tagged_correctly() {
+        let micros = epoch_to_rfc3339(1_705_315_800_000_000.0, TimePrecision::Micros);
+        let millis = epoch_to_rfc3339(1_705_315_800_000.0, TimePrecision::Millis);
+        assert_eq!(micros, millis);
+    }
+
+    #[test]
+    fn same_numeric_value_under_the_wrong_precision_is_a_different_instant() {
+        let as_micros = epoch_to_rfc3339(1_705_315_800_000.0, TimePrecision::Micros);
+        let as_millis = epoch_to_rfc3339(1_705_315_800_000.0, TimePrecision::Millis);
+        assert_ne!(as_micros, as_millis);
+    }
+
+    #[test]
+    fn seconds_and_nanos_round_trip_to_the_same_instant_too() {
+        let seconds = epoch_to_rfc3339(1_705_315_800.0, TimePrecision::Seconds);
+        let nanos = epoch_to_rfc3339(1_705_315_800_000_000_000.0, TimePrecision::Nanos);
+        assert_eq!(seconds, nanos);
+    }
+
+    #[test]
+    fn try_epoch_to_rfc3339_is_none_outside_chronos_representable_range() {
+        assert_eq!(
+            try_epoch_to_rfc3339(f64::MAX, TimePrecision::Seconds),
+            None
+        );
+    }
+
+    #[test]
+    fn try_epoch_to_rfc3339_agrees_with_the_lenient_version_in_range() {
+        let value = 1_705_315_800_000_000.0;
+        assert_eq!(
+            try_epoch_to_rfc3339(value, TimePrecision::Micros),
+            Some(epoch_to_rfc3339(value, TimePrecision::Micros))
+        );
+    }
+
+    #[test]
+    fn epoch_to_rfc3339_with_offset_renders_the_requested_offset_instead_of_z() {
+        let value = 1_705_315_800_000_000.0; // 2024-01-15T10:30:00Z
+        let plus_ten = epoch_to_rfc3339_with_offset(value, TimePrecision::Micros, 10 * 3600);
+
+        assert!(plus_ten.starts_with("2024-01-15T20:30:00"));
+        assert!(plus_ten.ends_with("+10:00"));
+    }
+
+    #[test]
+    fn epoch_to_rfc3339_with_offset_falls_back_to_z_for_an_invalid_offset() {
+        let value = 1_705_315_800_000_000.0;
+        let invalid = epoch_to_rfc3339_with_offset(value, TimePrecision::Micros, 100_000);
+
+        assert_eq!(invalid, epoch_to_rfc3339(value, TimePrecision::Micros));
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_accepts_strict_rfc3339() {
+        let strict = Timestamp::parse("2024-01-15T10:30:00Z").unwrap().to_unix_micros().unwrap();
+        let flexible = parse_flexible_timestamp("2024-01-15T10:30:00Z", DateBound::Floor).unwrap();
+        assert_eq!(strict, flexible);
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_accepts_missing_fractional_seconds() {
+        let with_offset = parse_flexible_timestamp("2024-01-15T10:30:00+02:00", DateBound::Floor);
+        let expected =
+            Timestamp::parse("2024-01-15T08:30:00Z").unwrap().to_unix_micros().unwrap();
+        assert_eq!(with_offset.unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_accepts_missing_seconds() {
+        let with_offset = parse_flexible_timestamp("2024-01-15T10:30+00:00", DateBound::Floor);
+        let expected =
+            Timestamp::parse("2024-01-15T10:30:00Z").unwrap().to_unix_micros().unwrap();
+        assert_eq!(with_offset.unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_floors_a_bare_date_to_start_of_day() {
+        let floored = parse_flexible_timestamp("2024-01-15", DateBound::Floor).unwrap();
+        let expected = Timestamp::parse("2024-01-15T00:00:00Z").unwrap().to_unix_micros().unwrap();
+        assert_eq!(floored, expected);
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_ceils_a_bare_date_to_end_of_day() {
+        let ceiled = parse_flexible_timestamp("2024-01-15", DateBound::Ceil).unwrap();
+        let expected = Timestamp::parse("2024-01-15T23:59:59Z").unwrap().to_unix_micros().unwrap();
+        assert_eq!(ceiled, expected);
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_accepts_now_case_insensitively() {
+        assert!(parse_flexible_timestamp("NOW", DateBound::Floor).is_ok());
+        assert!(parse_flexible_timestamp("now", DateBound::Ceil).is_ok());
+    }
+
+    #[test]
+    fn parse_flexible_timestamp_rejects_garbage() {
+        assert_eq!(
+            parse_flexible_timestamp("not a timestamp", DateBound::Floor),
+            Err(TimestampError::InvalidFormat("not a timestamp".to_string()))
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! `time`-crate equivalents of this module's `chrono`-backed formatting
+//! functions, behind the `time-backend` feature.
+//!
+//! `time`'s RFC 3339 formatter can reject a date-time `chrono` would render
+//! without complaint (e.g. a component pushed out of range by the input),
+//! so every function here returns a [`Result`] rather than the best-effort
+//! `String` the `chrono`-backed versions return.
+
+use super::TimePrecision;
+
+/// Failure modes for the `time`-backed formatting functions in this module.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TimeBackendError {
+    /// The epoch value doesn't correspond to a `time::OffsetDateTime` that
+    /// can be constructed from a Unix nanosecond count.
+    #[error("epoch value is out of range for a time::OffsetDateTime")]
+    OutOfRange,
+    /// `time` rejected the date-time while rendering it as RFC 3339.
+    #[error("failed to format as RFC 3339: {0}")]
+    Format(String),
+    /// The requested offset is not representable as a `time::UtcOffset`.
+    #[error("offset of {0} seconds is out of range for a time::UtcOffset")]
+    InvalidOffset(i32),
+}
+
+/// `time`-backed equivalent of [`super::epoch_to_rfc3339`]: renders an
+/// epoch value at the given precision as an RFC 3339 string, in UTC.
+pub fn epoch_to_rfc3339(value: f64, precision: TimePrecision) -> Result<String, TimeBackendError> {
+    epoch_to_rfc3339_with_offset(value, precision, 0)
+}
+
+/// `time`-backed equivalent of
+/// [`super::epoch_to_rfc3339_with_offset`]: renders an epoch value at the
+/// given precision as an RFC 3339 string, in `offset_secs` seconds east of
+/// UTC instead of `Z`.
+pub fn epoch_to_rfc3339_with_offset(
+    value: f64,
+    precision: TimePrecision,
+    offset_secs: i32,
+) -> Result<String, TimeBackendError> {
+    use time::format_description::well_known::Rfc3339;
+    use time::{OffsetDateTime, UtcOffset};
+
+    let micros = precision.to_micros(value);
+    let nanos = (micros as i128) * 1_000;
+    let utc = OffsetDateTime::from_unix_timestamp_nanos(nanos)
+        .map_err(|_| TimeBackendError::OutOfRange)?;
+    let offset = UtcOffset::from_whole_seconds(offset_secs)
+        .map_err(|_| TimeBackendError::InvalidOffset(offset_secs))?;
+
+    utc.to_offset(offset)
+        .format(&Rfc3339)
+        .map_err(|e| TimeBackendError::Format(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_same_instant_as_the_chrono_backend() {
+        let value = 1_705_315_800_000_000.0;
+        let chrono_rendered = super::super::epoch_to_rfc3339(value, TimePrecision::Micros);
+        let time_rendered = epoch_to_rfc3339(value, TimePrecision::Micros).unwrap();
+        assert_eq!(chrono_rendered, time_rendered);
+    }
+
+    #[test]
+    fn renders_the_requested_offset_instead_of_z() {
+        let value = 1_705_315_800_000_000.0; // 2024-01-15T10:30:00Z
+        let plus_ten =
+            epoch_to_rfc3339_with_offset(value, TimePrecision::Micros, 10 * 3600).unwrap();
+
+        assert!(plus_ten.starts_with("2024-01-15T20:30:00"));
+        assert!(plus_ten.ends_with("+10:00"));
+    }
+
+    #[test]
+    fn rejects_an_offset_time_cannot_represent() {
+        let value = 1_705_315_800_000_000.0;
+        assert_eq!(
+            epoch_to_rfc3339_with_offset(value, TimePrecision::Micros, 100_000),
+            Err(TimeBackendError::InvalidOffset(100_000))
+        );
+    }
+}
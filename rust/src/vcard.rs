@@ -0,0 +1,192 @@
+//! vCard 4.0 (RFC 6350) export for CoT `<contact>`/`<__group>`/`<takv>`
+//! detail elements.
+//!
+//! Lets CoT participants be imported into ordinary address-book/CardDAV
+//! clients, via this field mapping:
+//!
+//! | CoT detail                    | vCard |
+//! |--------------------------------|-------|
+//! | `<contact callsign>`           | `FN`  |
+//! | `<contact endpoint>`           | `TEL`/`X-COT-ENDPOINT` |
+//! | `<__group name>`               | `ORG`/`CATEGORIES` |
+//! | `<takv platform>`/`<version>`  | `X-COT-TAKV` |
+//!
+//! [`contacts_to_vcards`] takes the stable-keyed detail map produced by
+//! [`crdt_detail_parser::parse_detail_section_with_stable_keys`](crate::crdt_detail_parser::parse_detail_section_with_stable_keys)
+//! rather than [`detail_parser::parse_detail_section`](crate::detail_parser::parse_detail_section),
+//! so a detail section with more than one `<contact>` element (each parsed
+//! under its own stable key instead of overwriting a single `"contact"`
+//! entry) still produces one vCard per contact. `<__group>` and `<takv>`
+//! aren't expected to repeat, so every emitted vCard carries the same group
+//! and TAK version fields.
+//!
+//! This is an export-only bridge — there's no `vcard_to_contacts`, and
+//! property values aren't escaped per RFC 6350 §3.4, so a callsign or group
+//! name containing a literal comma, semicolon, or backslash will produce a
+//! vCard that doesn't round-trip faithfully through a strict parser. Embedded
+//! CR/LF is handled, though: properties are joined with `\r\n`, so a value
+//! carrying a literal newline is stripped (see [`strip_line_breaks`]) rather
+//! than left to inject extra vCard lines.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// The metadata key [`crdt_detail_parser`](crate::crdt_detail_parser) stamps
+/// onto a value parsed under a hashed stable key, naming the element's
+/// original tag.
+const TAG_METADATA: &str = "_tag";
+
+/// Returns every entry in `detail` whose element tag is `tag`, in stable
+/// (sorted-key) order — either the single entry stored directly under `tag`
+/// (the common, non-duplicated case), or any number of entries stored under
+/// a hashed stable key and marked with a [`TAG_METADATA`] of `tag` (the
+/// duplicated case).
+fn entries_for_tag<'a>(detail: &'a HashMap<String, Value>, tag: &str) -> Vec<&'a Value> {
+    let mut keys: Vec<&String> = detail
+        .keys()
+        .filter(|key| {
+            key.as_str() == tag
+                || detail[*key]
+                    .get(TAG_METADATA)
+                    .and_then(Value::as_str)
+                    == Some(tag)
+        })
+        .collect();
+    keys.sort();
+    keys.into_iter().map(|key| &detail[key]).collect()
+}
+
+/// Strips embedded CR/LF from a value before it's written into a vCard line:
+/// property values are joined with `\r\n` (RFC 6350 §3.2), so a callsign or
+/// name carrying a literal newline would otherwise let it inject extra
+/// lines — forged properties, or an early `END:VCARD`/second `BEGIN:VCARD`.
+fn strip_line_breaks(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+fn string_attr(value: &Value, attr: &str) -> Option<String> {
+    value.get(attr)?.as_str().map(strip_line_breaks)
+}
+
+/// Renders a single `<contact>` entry (plus the document's shared
+/// `<__group>` and `<takv>`, if present) into one vCard 4.0 record.
+fn contact_to_vcard(contact: &Value, group: Option<&Value>, takv: Option<&Value>) -> String {
+    let mut lines = vec!["BEGIN:VCARD".to_string(), "VERSION:4.0".to_string()];
+
+    if let Some(callsign) = string_attr(contact, "callsign") {
+        lines.push(format!("FN:{callsign}"));
+    }
+    if let Some(endpoint) = string_attr(contact, "endpoint") {
+        lines.push(format!("TEL:{endpoint}"));
+        lines.push(format!("X-COT-ENDPOINT:{endpoint}"));
+    }
+
+    if let Some(name) = group.and_then(|g| string_attr(g, "name")) {
+        lines.push(format!("ORG:{name}"));
+        lines.push(format!("CATEGORIES:{name}"));
+    }
+
+    if let Some(takv) = takv {
+        let platform = string_attr(takv, "platform");
+        let version = string_attr(takv, "version");
+        if platform.is_some() || version.is_some() {
+            lines.push(format!(
+                "X-COT-TAKV:{};{}",
+                platform.unwrap_or_default(),
+                version.unwrap_or_default()
+            ));
+        }
+    }
+
+    lines.push("END:VCARD".to_string());
+    lines.join("\r\n")
+}
+
+/// Renders every `<contact>` element in `detail` into one vCard 4.0 record,
+/// sharing the detail section's `<__group>` and `<takv>` fields across all
+/// of them. See the module docs for the field mapping.
+pub fn contacts_to_vcards(detail: &HashMap<String, Value>) -> Vec<String> {
+    let group = entries_for_tag(detail, "__group").into_iter().next();
+    let takv = entries_for_tag(detail, "takv").into_iter().next();
+
+    entries_for_tag(detail, "contact")
+        .into_iter()
+        .map(|contact| contact_to_vcard(contact, group, takv))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crdt_detail_parser::parse_detail_section_with_stable_keys;
+
+    #[test]
+    fn exports_callsign_and_endpoint_as_fn_and_tel() {
+        let detail = parse_detail_section_with_stable_keys(
+            "<detail><contact callsign=\"ALPHA-1\" endpoint=\"*:-1:stcp\"/></detail>",
+            "doc-1",
+        );
+        let vcards = contacts_to_vcards(&detail);
+
+        assert_eq!(vcards.len(), 1);
+        assert!(vcards[0].contains("BEGIN:VCARD"));
+        assert!(vcards[0].contains("VERSION:4.0"));
+        assert!(vcards[0].contains("FN:ALPHA-1"));
+        assert!(vcards[0].contains("TEL:*:-1:stcp"));
+        assert!(vcards[0].contains("X-COT-ENDPOINT:*:-1:stcp"));
+    }
+
+    #[test]
+    fn shares_group_and_takv_across_every_contact() {
+        let detail = parse_detail_section_with_stable_keys(
+            "<detail><contact callsign=\"ALPHA-1\"/>\
+             <__group name=\"Blue\" role=\"Team Member\"/>\
+             <takv platform=\"ATAK-CIV\" version=\"4.5.0.0\"/></detail>",
+            "doc-1",
+        );
+        let vcards = contacts_to_vcards(&detail);
+
+        assert_eq!(vcards.len(), 1);
+        assert!(vcards[0].contains("ORG:Blue"));
+        assert!(vcards[0].contains("CATEGORIES:Blue"));
+        assert!(vcards[0].contains("X-COT-TAKV:ATAK-CIV;4.5.0.0"));
+    }
+
+    #[test]
+    fn emits_one_vcard_per_duplicated_contact_element() {
+        let detail = parse_detail_section_with_stable_keys(
+            "<detail><contact callsign=\"ALPHA-1\"/><contact callsign=\"BRAVO-2\"/></detail>",
+            "doc-1",
+        );
+        let vcards = contacts_to_vcards(&detail);
+
+        assert_eq!(vcards.len(), 2);
+        let callsigns: Vec<bool> = vec![
+            vcards.iter().any(|v| v.contains("FN:ALPHA-1")),
+            vcards.iter().any(|v| v.contains("FN:BRAVO-2")),
+        ];
+        assert!(callsigns.into_iter().all(|found| found));
+    }
+
+    #[test]
+    fn strips_embedded_line_breaks_instead_of_injecting_extra_vcard_lines() {
+        let mut detail = HashMap::new();
+        detail.insert(
+            "contact".to_string(),
+            serde_json::json!({"callsign": "ALPHA\r\nX-INJECTED:evil\r\nEND:VCARD"}),
+        );
+        let vcards = contacts_to_vcards(&detail);
+
+        assert_eq!(vcards.len(), 1);
+        assert_eq!(vcards[0].matches("BEGIN:VCARD").count(), 1);
+        assert_eq!(vcards[0].matches("END:VCARD").count(), 1);
+        assert!(vcards[0].contains("FN:ALPHA X-INJECTED:evil END:VCARD"));
+    }
+
+    #[test]
+    fn empty_detail_produces_no_vcards() {
+        let detail = parse_detail_section_with_stable_keys("<detail></detail>", "doc-1");
+        assert!(contacts_to_vcards(&detail).is_empty());
+    }
+}
@@ -1,13 +1,94 @@
 //! XML parsing utilities for CoT (Cursor on Target) messages.
 //!
 //! This module provides functionality to parse CoT XML messages into
-//! structured Rust types.
+//! structured Rust types. [`parse_cot`]/[`parse_cot_bytes`] parse a single,
+//! fully-buffered message; [`parse_cot_stream`] parses a feed of many
+//! concatenated `<event>` blocks incrementally off a [`std::io::BufRead`].
 
 use crate::detail_parser::parse_detail_section;
 use crate::error::CotError;
 use crate::model::FlatCotEvent;
-use quick_xml::events::Event;
+use crate::plugin::PluginRegistry;
+use encoding_rs::Encoding;
+use indexmap::IndexMap;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// How many leading bytes of a raw feed to scan for an `encoding="..."`
+/// pseudo-attribute in the XML declaration before giving up and assuming UTF-8.
+const ENCODING_SNIFF_WINDOW: usize = 256;
+
+/// Detects the character encoding of a raw CoT byte stream and decodes it to a
+/// UTF-8 `String`.
+///
+/// Detection order, matching what real TAK gateways and radios emit:
+/// 1. A byte-order mark (UTF-8/UTF-16LE/UTF-16BE), per [`Encoding::for_bom`].
+/// 2. An `encoding="..."` pseudo-attribute in the XML declaration, scanned as
+///    ASCII over the first [`ENCODING_SNIFF_WINDOW`] bytes (the declaration is
+///    always ASCII-compatible regardless of the declared encoding).
+/// 3. Otherwise, UTF-8 is assumed, but decoding is strict: invalid UTF-8 is
+///    reported as [`CotError::UnsupportedEncoding`] instead of being silently
+///    replaced with U+FFFD.
+fn decode_cot_bytes(data: &[u8]) -> Result<String, CotError> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(data) {
+        let (decoded, _, had_errors) = encoding.decode(&data[bom_len..]);
+        if had_errors {
+            return Err(CotError::UnsupportedEncoding(format!(
+                "invalid {} byte sequence after BOM",
+                encoding.name()
+            )));
+        }
+        return Ok(decoded.into_owned());
+    }
+
+    let sniff_len = data.len().min(ENCODING_SNIFF_WINDOW);
+    let declared_label = std::str::from_utf8(&data[..sniff_len])
+        .ok()
+        .and_then(|prefix| {
+            let start = prefix.find("encoding=")? + "encoding=".len();
+            let quote = prefix[start..].chars().next()?;
+            if quote != '"' && quote != '\'' {
+                return None;
+            }
+            let rest = &prefix[start + 1..];
+            let end = rest.find(quote)?;
+            Some(rest[..end].to_string())
+        });
+
+    if let Some(label) = declared_label {
+        let encoding = Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+            CotError::UnsupportedEncoding(format!("unrecognized encoding label '{label}'"))
+        })?;
+        let (decoded, _, had_errors) = encoding.decode(data);
+        if had_errors {
+            return Err(CotError::UnsupportedEncoding(format!(
+                "invalid {} byte sequence declared as encoding '{label}'",
+                encoding.name()
+            )));
+        }
+        return Ok(decoded.into_owned());
+    }
+
+    std::str::from_utf8(data).map(str::to_string).map_err(|_| {
+        CotError::UnsupportedEncoding(
+            "no BOM or encoding declaration found and input is not valid UTF-8".to_string(),
+        )
+    })
+}
+
+/// Parses a raw CoT byte feed into a `FlatCotEvent`, transcoding it to UTF-8
+/// first if it carries a BOM or an `encoding="..."` declaration other than
+/// UTF-8 (e.g. UTF-16 from some TAK gateways and radios).
+///
+/// Unlike [`parse_cot`], this never silently mangles non-UTF-8 input: an
+/// unresolvable or undeclared non-UTF-8 encoding is reported as
+/// [`CotError::UnsupportedEncoding`] rather than lossily converted.
+pub fn parse_cot_bytes(data: &[u8]) -> Result<FlatCotEvent, CotError> {
+    let decoded = decode_cot_bytes(data)?;
+    parse_cot(&decoded)
+}
 
 /// Parses a CoT XML string into a `FlatCotEvent`.
 ///
@@ -36,7 +117,145 @@ pub fn parse_cot(xml: &str) -> Result<FlatCotEvent, CotError> {
     reader.trim_text(true);
 
     let mut buf = Vec::new();
-    let mut flat = FlatCotEvent {
+    let mut flat = blank_flat_cot_event();
+
+    while let Ok(event) = reader.read_event_into(&mut buf) {
+        match event {
+            Event::Start(ref e) if e.name().as_ref() == b"event" => {
+                for attr in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                    let val = attr.unescape_value().unwrap_or_default().to_string();
+                    apply_event_attribute(&mut flat, key, val)?;
+                }
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"detail" => {
+                read_detail_section(&mut reader, &mut flat);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(flat)
+}
+
+/// Applies one `<event>`-level attribute (`key`/`val`) to `flat`, promoting
+/// the attributes this crate treats as first-class fields and preserving
+/// everything else in [`FlatCotEvent::extra_attrs`] in the order encountered.
+///
+/// Shared by [`parse_cot`] and [`parse_cot_stream`] so both parse a single
+/// `<event>` start tag identically.
+fn apply_event_attribute(
+    flat: &mut FlatCotEvent,
+    key: String,
+    val: String,
+) -> Result<(), CotError> {
+    match key.as_str() {
+        "uid" => flat.uid = val,
+        "type" => flat.type_ = val,
+        "time" => flat.time = val,
+        "start" => flat.start = val,
+        "stale" => flat.stale = val,
+        "how" => flat.how = val,
+        "lat" => {
+            flat.lat = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
+                field: "lat".to_string(),
+                value: val.clone(),
+                source: Box::new(e),
+            })?
+        }
+        "lon" => {
+            flat.lon = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
+                field: "lon".to_string(),
+                value: val.clone(),
+                source: Box::new(e),
+            })?
+        }
+        "hae" => {
+            flat.hae = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
+                field: "hae".to_string(),
+                value: val.clone(),
+                source: Box::new(e),
+            })?
+        }
+        "ce" => {
+            flat.ce = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
+                field: "ce".to_string(),
+                value: val.clone(),
+                source: Box::new(e),
+            })?
+        }
+        "le" => {
+            flat.le = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
+                field: "le".to_string(),
+                value: val.clone(),
+                source: Box::new(e),
+            })?
+        }
+        // Anything else is a vendor-specific or
+        // not-yet-promoted `<event>` attribute; keep it
+        // rather than silently dropping it, so a round trip
+        // through `to_cot_xml` doesn't lose it.
+        _ => {
+            flat.extra_attrs.insert(key, val);
+        }
+    }
+    Ok(())
+}
+
+/// Reads a `<detail>...</detail>` subtree (the `<detail>` start tag itself
+/// already consumed by the caller), parses it via [`parse_detail_section`],
+/// and promotes well-known tags (contact.callsign, `__group.name/role`,
+/// track.speed/course, ...) onto `flat`'s first-class fields before stashing
+/// the full parsed map in [`FlatCotEvent::detail_extra`].
+///
+/// Shared by [`parse_cot`] and [`parse_cot_stream`] so both handle a
+/// detail section identically.
+fn read_detail_section<R: BufRead>(reader: &mut Reader<R>, flat: &mut FlatCotEvent) {
+    let mut detail_buf = Vec::new();
+    let mut depth = 1;
+
+    // Read until we find the matching end tag
+    loop {
+        match reader.read_event_into(&mut detail_buf) {
+            Ok(Event::Start(_)) => depth += 1,
+            Ok(Event::End(_)) => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            Ok(Event::Eof) => break,
+            _ => {}
+        }
+        detail_buf.clear();
+    }
+
+    // Get the inner XML as a string
+    let inner_xml = String::from_utf8_lossy(&detail_buf);
+    let extras = parse_detail_section(&inner_xml);
+
+    let registry = PluginRegistry::with_builtins();
+    for (tag, value) in &extras {
+        if let Some(obj) = value.as_object() {
+            let str_attrs: HashMap<String, String> = obj
+                .iter()
+                .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                .collect();
+            registry.handle(tag, &str_attrs, flat);
+        }
+    }
+
+    flat.detail_extra = extras;
+}
+
+/// Builds a blank [`FlatCotEvent`] with every field defaulted, ready to have
+/// `<event>` attributes and a detail section applied on top. Shared by
+/// [`parse_cot`] and [`parse_cot_stream`] so both start from the same
+/// defaults.
+fn blank_flat_cot_event() -> FlatCotEvent {
+    FlatCotEvent {
         uid: String::new(),
         type_: String::new(),
         time: String::new(),
@@ -50,91 +269,184 @@ pub fn parse_cot(xml: &str) -> Result<FlatCotEvent, CotError> {
         le: 0.0,
         callsign: None,
         group_name: None,
+        group_role: None,
+        speed: None,
+        course: None,
+        tz_offset_secs: None,
         detail_extra: Default::default(),
-    };
+        extra_attrs: IndexMap::new(),
+    }
+}
 
-    while let Ok(event) = reader.read_event_into(&mut buf) {
-        match event {
-            Event::Start(ref e) if e.name().as_ref() == b"event" => {
-                for attr in e.attributes().flatten() {
-                    let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
-                    let val = attr.unescape_value().unwrap_or_default().to_string();
-                    match key.as_str() {
-                        "uid" => flat.uid = val,
-                        "type" => flat.type_ = val,
-                        "time" => flat.time = val,
-                        "start" => flat.start = val,
-                        "stale" => flat.stale = val,
-                        "how" => flat.how = val,
-                        "lat" => {
-                            flat.lat = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
-                                field: "lat".to_string(),
-                                value: val.clone(),
-                                source: Box::new(e),
-                            })?
-                        }
-                        "lon" => {
-                            flat.lon = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
-                                field: "lon".to_string(),
-                                value: val.clone(),
-                                source: Box::new(e),
-                            })?
-                        }
-                        "hae" => {
-                            flat.hae = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
-                                field: "hae".to_string(),
-                                value: val.clone(),
-                                source: Box::new(e),
-                            })?
-                        }
-                        "ce" => {
-                            flat.ce = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
-                                field: "ce".to_string(),
-                                value: val.clone(),
-                                source: Box::new(e),
-                            })?
-                        }
-                        "le" => {
-                            flat.le = val.parse::<f64>().map_err(|e| CotError::InvalidNumeric {
-                                field: "le".to_string(),
-                                value: val.clone(),
-                                source: Box::new(e),
-                            })?
-                        }
-                        _ => {}
-                    }
+/// Incrementally parses a feed of concatenated `<event>...</event>` blocks
+/// from any [`BufRead`] source into [`FlatCotEvent`]s, one per top-level
+/// `<event>`, built directly on [`quick_xml::Reader`] instead of buffering
+/// the whole feed into memory the way [`parse_cot`] does.
+///
+/// Whitespace, XML declarations, and any stray non-`event` elements between
+/// events are skipped. A malformed event (e.g. a non-numeric `lat`) yields
+/// `Some(Err(_))` for that one item without aborting the stream; parsing
+/// resumes at the next `<event>` start tag.
+pub fn parse_cot_stream<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<FlatCotEvent, CotError>> {
+    CotEventStream {
+        reader: Reader::from_reader(reader),
+        buf: Vec::new(),
+    }
+}
+
+struct CotEventStream<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> Iterator for CotEventStream<R> {
+    type Item = Result<FlatCotEvent, CotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf) {
+                Ok(Event::Eof) => return None,
+                Ok(Event::Start(ref e)) if e.name().as_ref() == b"event" => {
+                    return Some(parse_one_event(&mut self.reader, e));
                 }
-            }
-            Event::Start(ref e) if e.name().as_ref() == b"detail" => {
-                let mut detail_buf = Vec::new();
-                let mut depth = 1;
-
-                // Read until we find the matching end tag
-                loop {
-                    match reader.read_event_into(&mut detail_buf) {
-                        Ok(Event::Start(_)) => depth += 1,
-                        Ok(Event::End(_)) => {
-                            depth -= 1;
-                            if depth == 0 {
-                                break;
-                            }
-                        }
-                        Ok(Event::Eof) => break,
-                        _ => {}
+                Ok(Event::Start(_)) => {
+                    if let Err(err) = skip_element(&mut self.reader) {
+                        return Some(Err(err));
                     }
-                    detail_buf.clear();
                 }
+                Ok(_) => {}
+                Err(e) => return Some(Err(CotError::XmlError(e.to_string()))),
+            }
+        }
+    }
+}
+
+/// Parses one `<event>` (its start tag already consumed as `start`) through
+/// its matching `</event>`, resetting all per-event state fresh, the
+/// streaming counterpart to the single-event loop in [`parse_cot`].
+fn parse_one_event<R: BufRead>(
+    reader: &mut Reader<R>,
+    start: &BytesStart,
+) -> Result<FlatCotEvent, CotError> {
+    let mut flat = blank_flat_cot_event();
+
+    for attr in start.attributes().flatten() {
+        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+        let val = attr.unescape_value().unwrap_or_default().to_string();
+        apply_event_attribute(&mut flat, key, val)?;
+    }
 
-                // Get the inner XML as a string
-                let inner_xml = String::from_utf8_lossy(&detail_buf);
-                let extras = parse_detail_section(&inner_xml);
-                flat.detail_extra = extras;
+    let mut buf = Vec::new();
+    let mut depth = 1u32;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name().as_ref() == b"detail" => {
+                read_detail_section(reader, &mut flat);
             }
-            Event::Eof => break,
+            Ok(Event::Start(_)) => depth += 1,
+            Ok(Event::End(ref e)) if e.name().as_ref() == b"event" && depth == 1 => break,
+            Ok(Event::End(_)) => depth -= 1,
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(CotError::XmlError(e.to_string())),
             _ => {}
         }
-        buf.clear();
     }
 
     Ok(flat)
 }
+
+/// Drains a non-`event` element (its start tag already consumed) through its
+/// matching end tag, so stray top-level elements between `<event>` blocks
+/// don't confuse [`CotEventStream`] about where the next event starts.
+fn skip_element<R: BufRead>(reader: &mut Reader<R>) -> Result<(), CotError> {
+    let mut buf = Vec::new();
+    let mut depth = 1u32;
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(_)) => depth += 1,
+            Ok(Event::End(_)) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+            Ok(Event::Eof) => return Ok(()),
+            Err(e) => return Err(CotError::XmlError(e.to_string())),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn event_xml(uid: &str, lat: &str) -> String {
+        format!(
+            r#"<event version="2.0" uid="{uid}" type="a-f-G-U-C" time="2023-01-01T00:00:00Z" start="2023-01-01T00:00:00Z" stale="2023-01-01T00:05:00Z" how="h-g-i-g-o" lat="{lat}" lon="2.0" hae="3.0" ce="4.0" le="5.0"><detail><contact callsign="ALPHA-1"/></detail></event>"#
+        )
+    }
+
+    #[test]
+    fn yields_one_event_per_concatenated_block() {
+        let feed = format!("{}{}", event_xml("ONE", "1.0"), event_xml("TWO", "2.0"));
+        let events: Vec<_> = parse_cot_stream(Cursor::new(feed))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].uid, "ONE");
+        assert_eq!(events[1].uid, "TWO");
+        assert_eq!(events[0].callsign.as_deref(), Some("ALPHA-1"));
+    }
+
+    #[test]
+    fn yields_nothing_for_empty_input() {
+        let events: Vec<_> = parse_cot_stream(Cursor::new(Vec::<u8>::new())).collect();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn tolerates_whitespace_and_a_declaration_between_events() {
+        let feed = format!(
+            "<?xml version=\"1.0\"?>\n{}\n\n{}",
+            event_xml("ONE", "1.0"),
+            event_xml("TWO", "2.0")
+        );
+        let events: Vec<_> = parse_cot_stream(Cursor::new(feed))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn a_malformed_event_errors_without_aborting_the_stream() {
+        let feed = format!(
+            "{}{}",
+            event_xml("BAD", "not-a-number"),
+            event_xml("GOOD", "3.0")
+        );
+        let mut events = parse_cot_stream(Cursor::new(feed));
+
+        assert!(events.next().unwrap().is_err());
+        let recovered = events.next().unwrap().unwrap();
+        assert_eq!(recovered.uid, "GOOD");
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn matches_parse_cot_for_a_single_event() {
+        let xml = event_xml("SOLO", "1.0");
+        let from_stream = parse_cot_stream(Cursor::new(xml.clone()))
+            .next()
+            .unwrap()
+            .unwrap();
+        let from_str = parse_cot(&xml).unwrap();
+        assert_eq!(from_stream, from_str);
+    }
+}
@@ -0,0 +1,154 @@
+//! Incremental, constant-memory CoT XML writer for exporting large batches
+//! of events — the write-side counterpart to [`crate::stream`]'s
+//! incremental reader. Built directly on quick-xml's streaming [`Writer`]
+//! (the same one [`crate::xml_writer::to_cot_xml`] uses for a single event),
+//! [`CotEventStreamWriter`] accepts [`CotDocument`]s or [`FlatCotEvent`]s one
+//! at a time and writes each straight through to an arbitrary
+//! [`std::io::Write`] sink, flushing after every event, instead of building
+//! and concatenating per-event strings in memory.
+
+use crate::ditto::{flat_cot_event_from_ditto, CotDocument};
+use crate::error::CotError;
+use crate::model::FlatCotEvent;
+use crate::xml_writer::write_cot_event;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
+use quick_xml::Writer;
+use std::io::Write;
+
+/// Incrementally writes `<event>...</event>` blocks to `W`, optionally
+/// wrapped in a single configurable root element so the overall output is
+/// one well-formed XML document rather than a bare sequence of sibling
+/// `<event>` elements.
+///
+/// Must be closed with [`Self::finish`] to close the root element (if any)
+/// and hand back the underlying writer; dropping a [`CotEventStreamWriter`]
+/// without calling it leaves the root element (if configured) unclosed.
+pub struct CotEventStreamWriter<W: Write> {
+    writer: Writer<W>,
+    root: Option<String>,
+}
+
+impl<W: Write> CotEventStreamWriter<W> {
+    /// Starts a stream with no wrapping root element: `inner` receives a
+    /// bare sequence of sibling `<event>` blocks.
+    pub fn new(inner: W) -> Result<Self, CotError> {
+        Self::with_root(inner, None)
+    }
+
+    /// Starts a stream wrapped in a `<root>...</root>` element named
+    /// `root_name`, e.g. `"events"` for a `<events>...</events>` batch.
+    pub fn with_root(inner: W, root_name: Option<&str>) -> Result<Self, CotError> {
+        let mut writer = Writer::new(inner);
+        writer
+            .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+            .map_err(|e| CotError::XmlError(e.to_string()))?;
+
+        if let Some(name) = root_name {
+            writer
+                .write_event(Event::Start(BytesStart::new(name)))
+                .map_err(|e| CotError::XmlError(e.to_string()))?;
+        }
+
+        Ok(Self {
+            writer,
+            root: root_name.map(str::to_string),
+        })
+    }
+
+    /// Writes one event and flushes the underlying sink.
+    pub fn write_flat_event(&mut self, event: &FlatCotEvent) -> Result<(), CotError> {
+        write_cot_event(&mut self.writer, event).map_err(|e| CotError::XmlError(e.to_string()))?;
+        self.writer
+            .get_mut()
+            .flush()
+            .map_err(|e| CotError::XmlError(e.to_string()))
+    }
+
+    /// Flattens `doc` and writes it, the same conversion
+    /// [`crate::ditto::flat_cot_event_from_ditto`] performs for a single
+    /// document.
+    pub fn write_document(&mut self, doc: &CotDocument) -> Result<(), CotError> {
+        self.write_flat_event(&flat_cot_event_from_ditto(doc))
+    }
+
+    /// Closes the root element (if one was configured) and hands back the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W, CotError> {
+        if let Some(name) = &self.root {
+            self.writer
+                .write_event(Event::End(BytesEnd::new(name.as_str())))
+                .map_err(|e| CotError::XmlError(e.to_string()))?;
+        }
+        self.writer
+            .get_mut()
+            .flush()
+            .map_err(|e| CotError::XmlError(e.to_string()))?;
+        Ok(self.writer.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(uid: &str) -> FlatCotEvent {
+        FlatCotEvent {
+            uid: uid.to_string(),
+            type_: "a-f-G-U-C".to_string(),
+            time: "2023-01-01T00:00:00Z".to_string(),
+            start: "2023-01-01T00:00:00Z".to_string(),
+            stale: "2023-01-01T00:00:00Z".to_string(),
+            how: "h-g-i-g-o".to_string(),
+            lat: 34.12345,
+            lon: -118.12345,
+            hae: 150.0,
+            ce: 10.0,
+            le: 20.0,
+            callsign: Some("ALPHA-1".to_string()),
+            group_name: None,
+            group_role: None,
+            speed: None,
+            course: None,
+            tz_offset_secs: None,
+            detail_extra: Default::default(),
+            extra_attrs: Default::default(),
+        }
+    }
+
+    #[test]
+    fn writes_a_bare_sequence_of_events_with_no_root() {
+        let mut stream = CotEventStreamWriter::new(Vec::new()).unwrap();
+        stream.write_flat_event(&event("ONE")).unwrap();
+        stream.write_flat_event(&event("TWO")).unwrap();
+        let bytes = stream.finish().unwrap();
+        let xml = String::from_utf8(bytes).unwrap();
+
+        assert_eq!(xml.matches("<event ").count(), 2);
+        assert!(xml.contains("uid=\"ONE\""));
+        assert!(xml.contains("uid=\"TWO\""));
+    }
+
+    #[test]
+    fn wraps_events_in_a_configured_root_element() {
+        let mut stream = CotEventStreamWriter::with_root(Vec::new(), Some("events")).unwrap();
+        stream.write_flat_event(&event("ONE")).unwrap();
+        let bytes = stream.finish().unwrap();
+        let xml = String::from_utf8(bytes).unwrap();
+
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<events><event "));
+        assert!(xml.trim_end().ends_with("</events>"));
+    }
+
+    #[test]
+    fn each_event_is_individually_well_formed() {
+        let mut stream = CotEventStreamWriter::new(Vec::new()).unwrap();
+        stream.write_flat_event(&event("ONE")).unwrap();
+        let bytes = stream.finish().unwrap();
+        let xml = String::from_utf8(bytes).unwrap();
+
+        let event_xml = &xml[xml.find("<event").unwrap()..];
+        let parsed = crate::xml_parser::parse_cot(event_xml).unwrap();
+        assert_eq!(parsed.uid, "ONE");
+    }
+}
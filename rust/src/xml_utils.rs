@@ -184,7 +184,21 @@ fn nodes_equal(node1: &roxmltree::Node, node2: &roxmltree::Node, strict: bool) -
     // Compare children (recursively)
     let mut children1: Vec<roxmltree::Node> = node1.children().filter(|n| n.is_element()).collect();
     let mut children2: Vec<roxmltree::Node> = node2.children().filter(|n| n.is_element()).collect();
-    
+
+    // Compare text content for leaf elements (no child elements), so a
+    // reconstructed `<remarks>hello</remarks>` is actually checked against
+    // the original instead of only its tag name and (nonexistent)
+    // attributes. Skipped for elements with children, since pretty-printed
+    // whitespace between siblings would otherwise make semantically
+    // identical documents compare unequal.
+    if children1.is_empty() && children2.is_empty() {
+        let text1 = node1.text().unwrap_or("").trim();
+        let text2 = node2.text().unwrap_or("").trim();
+        if text1 != text2 {
+            return false;
+        }
+    }
+
     // Special handling for <detail> elements - order doesn't matter
     if node1.tag_name().name() == "detail" && node2.tag_name().name() == "detail" {
         // Count children by tag name
@@ -2,8 +2,18 @@
 //!
 //! This module provides functionality to generate CoT XML messages from
 //! structured Rust types.
+//!
+//! Generation goes through quick-xml's streaming [`Writer`] rather than raw
+//! `format!` interpolation, so attribute and text content containing `&`,
+//! `<`, `>`, or `"` (e.g. a callsign like `A&B`) is escaped instead of
+//! producing XML that [`CotEvent::from_xml`](crate::cot_events::CotEvent::from_xml)
+//! can't re-parse.
 
 use crate::model::FlatCotEvent;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::Writer;
+use serde_json::Value;
+use std::io::Cursor;
 
 /// Converts a `FlatCotEvent` into a CoT XML string.
 ///
@@ -36,7 +46,12 @@ use crate::model::FlatCotEvent;
 ///     le: 0.0,
 ///     callsign: Some("TestUser".to_string()),
 ///     group_name: Some("Blue".to_string()),
+///     group_role: None,
+///     speed: None,
+///     course: None,
+///     tz_offset_secs: None,
 ///     detail_extra: Default::default(),
+///     extra_attrs: Default::default(),
 /// };
 ///
 /// let xml = to_cot_xml(&event);
@@ -44,142 +59,359 @@ use crate::model::FlatCotEvent;
 /// assert!(xml.contains("<contact callsign=\"TestUser\""));
 /// ```
 pub fn to_cot_xml(event: &FlatCotEvent) -> String {
-    let mut xml = String::new();
-    xml.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
-    xml.push_str(&format!(
-        r#"<event version="2.0" uid="{}" type="{}" time="{}" start="{}" stale="{}" how="{}">"#,
-        event.uid, event.type_, event.time, event.start, event.stale, event.how
-    ));
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    const INFALLIBLE: &str = "writing to an in-memory buffer never fails";
+
+    writer
+        .write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+        .expect(INFALLIBLE);
+    write_cot_event(&mut writer, event).expect(INFALLIBLE);
+
+    String::from_utf8(writer.into_inner().into_inner()).expect("quick-xml only writes valid UTF-8")
+}
+
+/// Writes one `<event>...</event>` block for `event` to `writer`, without a
+/// leading XML declaration — the shared body [`to_cot_xml`] wraps in a
+/// one-shot in-memory [`Writer`], and
+/// [`CotEventStreamWriter`](crate::xml_stream_writer::CotEventStreamWriter)
+/// writes repeatedly to an arbitrary [`std::io::Write`] sink.
+pub(crate) fn write_cot_event<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    event: &FlatCotEvent,
+) -> quick_xml::Result<()> {
+    let mut event_start = BytesStart::new("event");
+    event_start.push_attribute(("version", "2.0"));
+    event_start.push_attribute(("uid", event.uid.as_str()));
+    event_start.push_attribute(("type", event.type_.as_str()));
+    event_start.push_attribute(("time", event.time.as_str()));
+    event_start.push_attribute(("start", event.start.as_str()));
+    event_start.push_attribute(("stale", event.stale.as_str()));
+    event_start.push_attribute(("how", event.how.as_str()));
+    for (k, v) in &event.extra_attrs {
+        event_start.push_attribute((k.as_str(), v.as_str()));
+    }
+    writer.write_event(Event::Start(event_start))?;
 
     // Add point element with coordinates
-    xml.push_str(&format!(
-        r#"<point lat="{}" lon="{}" hae="{}" ce="{}" le="{}"/>"#,
-        event.lat, event.lon, event.hae, event.ce, event.le
-    ));
+    let mut point = BytesStart::new("point");
+    point.push_attribute(("lat", event.lat.to_string().as_str()));
+    point.push_attribute(("lon", event.lon.to_string().as_str()));
+    point.push_attribute(("hae", event.hae.to_string().as_str()));
+    point.push_attribute(("ce", event.ce.to_string().as_str()));
+    point.push_attribute(("le", event.le.to_string().as_str()));
+    writer.write_event(Event::Empty(point))?;
 
-    xml.push_str("<detail>");
+    writer.write_event(Event::Start(BytesStart::new("detail")))?;
 
-    // Add callsign if present and not empty
-    if let Some(callsign) = &event.callsign {
-        if !callsign.is_empty() {
-            xml.push_str(&format!(r#"<contact callsign="{}"/>"#, callsign));
+    // Add callsign if present and not empty, unless `detail_extra` already
+    // carries a `contact` element (e.g. round-tripped through `parse_cot`) —
+    // that generic path preserves the tag's other attributes too, so don't
+    // double-write it here.
+    if !event.detail_extra.contains_key("contact") {
+        if let Some(callsign) = &event.callsign {
+            if !callsign.is_empty() {
+                let mut contact = BytesStart::new("contact");
+                contact.push_attribute(("callsign", callsign.as_str()));
+                writer.write_event(Event::Empty(contact))?;
+            }
         }
     }
 
-    // Add group_name if present and not empty
-    if let Some(group_name) = &event.group_name {
-        if !group_name.is_empty() {
-            xml.push_str(&format!(r#"<__group name="{}"/>"#, group_name));
+    // Add group_name if present and not empty, with the same "don't
+    // double-write `detail_extra`'s own `__group`" guard as `contact` above.
+    if !event.detail_extra.contains_key("__group") {
+        if let Some(group_name) = &event.group_name {
+            if !group_name.is_empty() {
+                let mut group = BytesStart::new("__group");
+                group.push_attribute(("name", group_name.as_str()));
+                writer.write_event(Event::Empty(group))?;
+            }
         }
     }
 
-    // Helper for recursive serialization of detail_extra
-    fn write_detail_xml(xml: &mut String, k: &str, v: &serde_json::Value) {
-        log::trace!("write_detail_xml: key = {} | value = {:?}", k, v);
-        if let Some(obj) = v.as_object() {
-            // Special cases for known nested elements
-            if (k == "sensor" || k == "platform") && obj.contains_key("name") && obj.len() == 1 {
-                // Handle <sensor><n>ThermalCam-X</n></sensor> and <platform><n>MQ-9 Reaper</n></platform> format
-                if let Some(serde_json::Value::String(name)) = obj.get("name") {
-                    log::trace!(
-                        "write_detail_xml: special case for <{}><n>{}</n></{}>",
-                        k,
-                        name,
-                        k
-                    );
-                    xml.push_str(&format!("<{}><n>{}</n></{}>", k, name, k));
-                    return;
-                }
-            }
+    // `detail_extra` is an `IndexMap`, so this already iterates in the order
+    // its tags were first parsed rather than needing an explicit sort.
+    for (k, v) in &event.detail_extra {
+        write_detail_value(writer, k, v)?;
+    }
 
-            // If all values are string and no _text, treat as attributes
-            let mut attrs = Vec::new();
-            let mut children = Vec::new();
-            let mut text = None;
-            // Sort keys for canonical order
-            let mut keys: Vec<_> = obj.keys().collect();
-            keys.sort();
-
-            for key in keys {
-                let val = &obj[key];
-                if key == "_text" {
-                    if let Some(s) = val.as_str() {
-                        text = Some(s.to_string());
-                    }
-                } else if val.is_object() || val.is_array() {
-                    children.push((key.as_str(), val));
-                } else if let Some(s) = val.as_str() {
-                    attrs.push((key.as_str(), s.to_string()));
-                } else if let Some(n) = val.as_f64() {
-                    let n_str = n.to_string();
-                    attrs.push((key.as_str(), n_str));
-                } else if let Some(b) = val.as_bool() {
-                    let b_str = b.to_string();
-                    attrs.push((key.as_str(), b_str));
-                }
-            }
+    writer.write_event(Event::End(BytesEnd::new("detail")))?;
+    writer.write_event(Event::End(BytesEnd::new("event")))?;
+
+    Ok(())
+}
 
-            // If we have children or text, we need a full element
-            if !children.is_empty() || text.is_some() {
+/// Recursively writes one `detail` child (and its nested objects/arrays, to
+/// arbitrary depth) as properly escaped, correctly nested XML events.
+pub(crate) fn write_detail_value<W: std::io::Write>(
+    writer: &mut Writer<W>,
+    k: &str,
+    v: &Value,
+) -> quick_xml::Result<()> {
+    log::trace!("write_detail_value: key = {} | value = {:?}", k, v);
+
+    if let Some(obj) = v.as_object() {
+        // Special cases for known nested elements
+        if (k == "sensor" || k == "platform") && obj.contains_key("name") && obj.len() == 1 {
+            // Handle <sensor><n>ThermalCam-X</n></sensor> and <platform><n>MQ-9 Reaper</n></platform> format
+            if let Some(Value::String(name)) = obj.get("name") {
                 log::trace!(
-                    "write_detail_xml: <{}> attrs: {:?}, children: {:?}, text: {:?}",
+                    "write_detail_value: special case for <{}><n>{}</n></{}>",
                     k,
-                    attrs,
-                    children,
-                    text
+                    name,
+                    k
                 );
-                // Start tag with attributes
-                xml.push_str(&format!("<{}", k));
-                for (attr_k, attr_v) in &attrs {
-                    xml.push_str(&format!(" {}=\"{}\"", attr_k, attr_v));
-                }
-                xml.push('>');
+                writer.write_event(Event::Start(BytesStart::new(k)))?;
+                writer.write_event(Event::Start(BytesStart::new("n")))?;
+                writer.write_event(Event::Text(BytesText::new(name)))?;
+                writer.write_event(Event::End(BytesEnd::new("n")))?;
+                writer.write_event(Event::End(BytesEnd::new(k)))?;
+                return Ok(());
+            }
+        }
 
-                // Add text if any
-                if let Some(t) = text {
-                    xml.push_str(&t);
-                }
+        // If all values are string and no _text, treat as attributes
+        let mut attrs = Vec::new();
+        let mut children = Vec::new();
+        let mut text = None;
+        // Sort keys for canonical order
+        let mut keys: Vec<_> = obj.keys().collect();
+        keys.sort();
 
-                // Add children
-                for (child_k, child_v) in children {
-                    write_detail_xml(xml, child_k, child_v);
+        for key in keys {
+            let val = &obj[key];
+            if key == "_text" {
+                if let Some(s) = val.as_str() {
+                    text = Some(s.to_string());
                 }
+            } else if val.is_object() || val.is_array() {
+                children.push((key.as_str(), val));
+            } else if let Some(s) = val.as_str() {
+                attrs.push((key.as_str(), s.to_string()));
+            } else if let Some(n) = val.as_f64() {
+                let n_str = n.to_string();
+                attrs.push((key.as_str(), n_str));
+            } else if let Some(b) = val.as_bool() {
+                let b_str = b.to_string();
+                attrs.push((key.as_str(), b_str));
+            }
+        }
 
-                // Close tag
-                xml.push_str(&format!("</{}>", k));
-            } else {
-                // Just attributes, no children or text
-                log::trace!("write_detail_xml: <{}> only attributes: {:?}", k, attrs);
-                xml.push_str(&format!("<{}", k));
-                for (attr_k, attr_v) in &attrs {
-                    xml.push_str(&format!(" {}=\"{}\"", attr_k, attr_v));
-                }
-                xml.push_str("/>");
-                log::trace!("write_detail_xml: emitting tag: <{}/>", k);
+        let mut start = BytesStart::new(k);
+        for (attr_k, attr_v) in &attrs {
+            start.push_attribute((*attr_k, attr_v.as_str()));
+        }
+
+        // If we have children or text, we need a full element
+        if !children.is_empty() || text.is_some() {
+            log::trace!(
+                "write_detail_value: <{}> attrs: {:?}, children: {:?}, text: {:?}",
+                k,
+                attrs,
+                children,
+                text
+            );
+            writer.write_event(Event::Start(start))?;
+
+            if let Some(t) = text {
+                writer.write_event(Event::Text(BytesText::new(&t)))?;
             }
-        } else if let Some(arr) = v.as_array() {
-            log::trace!("write_detail_xml: <{}> array value: {:?}", k, arr);
-            for item in arr {
-                write_detail_xml(xml, k, item);
+
+            for (child_k, child_v) in children {
+                write_detail_value(writer, child_k, child_v)?;
             }
-        } else if let Some(s) = v.as_str() {
-            log::trace!("write_detail_xml: <{}> string value: {}", k, s);
-            xml.push_str(&format!("<{}>{}</{}>", k, s, k));
-        } else if let Some(n) = v.as_f64() {
-            log::trace!("write_detail_xml: <{}> number value: {}", k, n);
-            xml.push_str(&format!("<{}>{}</{}>", k, n, k));
-        } else if let Some(b) = v.as_bool() {
-            log::trace!("write_detail_xml: <{}> bool value: {}", k, b);
-            xml.push_str(&format!("<{}>{}</{}>", k, b, k));
+
+            writer.write_event(Event::End(BytesEnd::new(k)))?;
+        } else {
+            // Just attributes, no children or text
+            log::trace!("write_detail_value: <{}> only attributes: {:?}", k, attrs);
+            writer.write_event(Event::Empty(start))?;
+        }
+    } else if let Some(arr) = v.as_array() {
+        log::trace!("write_detail_value: <{}> array value: {:?}", k, arr);
+        for item in arr {
+            write_detail_value(writer, k, item)?;
+        }
+    } else if let Some(s) = v.as_str() {
+        log::trace!("write_detail_value: <{}> string value: {}", k, s);
+        writer.write_event(Event::Start(BytesStart::new(k)))?;
+        writer.write_event(Event::Text(BytesText::new(s)))?;
+        writer.write_event(Event::End(BytesEnd::new(k)))?;
+    } else if let Some(n) = v.as_f64() {
+        log::trace!("write_detail_value: <{}> number value: {}", k, n);
+        let n_str = n.to_string();
+        writer.write_event(Event::Start(BytesStart::new(k)))?;
+        writer.write_event(Event::Text(BytesText::new(&n_str)))?;
+        writer.write_event(Event::End(BytesEnd::new(k)))?;
+    } else if let Some(b) = v.as_bool() {
+        log::trace!("write_detail_value: <{}> bool value: {}", k, b);
+        let b_str = b.to_string();
+        writer.write_event(Event::Start(BytesStart::new(k)))?;
+        writer.write_event(Event::Text(BytesText::new(&b_str)))?;
+        writer.write_event(Event::End(BytesEnd::new(k)))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml_parser::parse_cot;
+    use indexmap::IndexMap;
+
+    fn base_event() -> FlatCotEvent {
+        FlatCotEvent {
+            uid: "TEST-1".to_string(),
+            type_: "a-f-G-U-C".to_string(),
+            time: "2023-01-01T00:00:00Z".to_string(),
+            start: "2023-01-01T00:00:00Z".to_string(),
+            stale: "2023-01-01T00:05:00Z".to_string(),
+            how: "h-g-i-g-o".to_string(),
+            lat: 1.0,
+            lon: 2.0,
+            hae: 3.0,
+            ce: 4.0,
+            le: 5.0,
+            callsign: None,
+            group_name: None,
+            group_role: None,
+            speed: None,
+            course: None,
+            tz_offset_secs: None,
+            detail_extra: IndexMap::new(),
+            extra_attrs: IndexMap::new(),
         }
     }
-    let mut detail_keys: Vec<_> = event.detail_extra.keys().collect();
-    detail_keys.sort();
-    for k in detail_keys {
-        let v = &event.detail_extra[k];
-        write_detail_xml(&mut xml, k, v);
+
+    #[test]
+    fn escapes_special_characters_in_attributes() {
+        let mut event = base_event();
+        event.callsign = Some("A&B <evil>\"quote\"".to_string());
+
+        let xml = to_cot_xml(&event);
+        assert!(!xml.contains("A&B <evil>"));
+        assert!(xml.contains("A&amp;B &lt;evil&gt;&quot;quote&quot;"));
+
+        // And it must still re-parse back to the original value.
+        let event = parse_cot(&xml).expect("escaped XML must still be valid");
+        assert_eq!(
+            event.detail_extra["contact"]["callsign"],
+            "A&B <evil>\"quote\""
+        );
+    }
+
+    #[test]
+    fn escapes_special_characters_in_text_content() {
+        let mut event = base_event();
+        let mut remarks = serde_json::Map::new();
+        remarks.insert("_text".to_string(), Value::String("Tom & Jerry < 5".to_string()));
+        event.detail_extra.insert("remarks".to_string(), Value::Object(remarks));
+
+        let xml = to_cot_xml(&event);
+        assert!(xml.contains("Tom &amp; Jerry &lt; 5"));
+
+        let reparsed = parse_cot(&xml).expect("escaped XML must still be valid");
+        assert_eq!(reparsed.detail_extra["remarks"]["_text"], "Tom & Jerry < 5");
+    }
+
+    #[test]
+    fn round_trips_deeply_nested_detail() {
+        let mut event = base_event();
+        let inner = serde_json::json!({
+            "remarks": { "source": "BAO", "_text": "hello" }
+        });
+        event.detail_extra.insert("nested".to_string(), inner);
+
+        let xml = to_cot_xml(&event);
+        let reparsed = parse_cot(&xml).expect("valid XML");
+        assert_eq!(reparsed.detail_extra["nested"]["remarks"]["source"], "BAO");
+        assert_eq!(reparsed.detail_extra["nested"]["remarks"]["_text"], "hello");
+    }
+
+    #[test]
+    fn round_trips_repeated_sibling_elements() {
+        let mut event = base_event();
+        event.detail_extra.insert(
+            "link".to_string(),
+            serde_json::json!([
+                { "uid": "PARENT-1" },
+                { "uid": "PARENT-2" }
+            ]),
+        );
+
+        let xml = to_cot_xml(&event);
+        let reparsed = parse_cot(&xml).expect("valid XML");
+        let links = reparsed.detail_extra["link"]
+            .as_array()
+            .expect("repeated <link> should round-trip as an array");
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0]["uid"], "PARENT-1");
+        assert_eq!(links[1]["uid"], "PARENT-2");
+    }
+
+    #[test]
+    fn does_not_duplicate_contact_already_present_in_detail_extra() {
+        // Simulates a FlatCotEvent round-tripped through `parse_cot`, where
+        // `callsign` and `detail_extra["contact"]` are both populated from
+        // the same source element.
+        let mut event = base_event();
+        event.callsign = Some("ALPHA-1".to_string());
+        event.detail_extra.insert(
+            "contact".to_string(),
+            serde_json::json!({ "callsign": "ALPHA-1", "endpoint": "*:-1:stcp" }),
+        );
+
+        let xml = to_cot_xml(&event);
+        assert_eq!(xml.matches("<contact").count(), 1);
+        assert!(xml.contains("endpoint=\"*:-1:stcp\""));
+    }
+
+    #[test]
+    fn sensor_name_shorthand_still_round_trips() {
+        let mut event = base_event();
+        event
+            .detail_extra
+            .insert("sensor".to_string(), serde_json::json!({ "name": "ThermalCam-X" }));
+
+        let xml = to_cot_xml(&event);
+        assert!(xml.contains("<sensor><n>ThermalCam-X</n></sensor>"));
+
+        let reparsed = parse_cot(&xml).expect("valid XML");
+        assert_eq!(reparsed.detail_extra["sensor"]["n"], "ThermalCam-X");
+    }
+
+    #[test]
+    fn round_trips_custom_attributes_and_repeated_details_in_order() {
+        let mut event = base_event();
+        event
+            .extra_attrs
+            .insert("access".to_string(), "UNCLASSIFIED".to_string());
+        event
+            .extra_attrs
+            .insert("qos".to_string(), "1-r-c".to_string());
+        event.detail_extra.insert(
+            "link".to_string(),
+            serde_json::json!([{ "uid": "CHILD-1" }, { "uid": "CHILD-2" }]),
+        );
+        event
+            .detail_extra
+            .insert("remarks".to_string(), serde_json::json!({ "_text": "hi" }));
+
+        let xml = to_cot_xml(&event);
+        let reparsed = parse_cot(&xml).expect("valid XML");
+
+        assert_eq!(reparsed.extra_attrs, event.extra_attrs);
+        assert_eq!(
+            reparsed.detail_extra.keys().collect::<Vec<_>>(),
+            event.detail_extra.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(reparsed.detail_extra["link"][0]["uid"], "CHILD-1");
+        assert_eq!(reparsed.detail_extra["link"][1]["uid"], "CHILD-2");
+
+        // Re-serializing the round-tripped event must produce byte-identical
+        // XML, not just an equal `FlatCotEvent` — that's the actual
+        // "lossless round trip" guarantee this test is for.
+        assert_eq!(to_cot_xml(&reparsed), xml);
     }
-    xml.push_str("</detail>");
-    xml.push_str("</event>");
-    xml
 }
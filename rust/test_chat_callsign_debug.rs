@@ -10,18 +10,21 @@ fn main() {
         "Test message content",
         "Operations Room",
         "ops-room-001",
-    );
+        None,
+        "+5m",
+    )
+    .unwrap();
 
     println!("=== CHAT EVENT DETAIL ===");
     println!("Detail string: {}", chat_event.detail);
-    
+
     println!("\n=== PARSING DETAIL ===");
     let parsed_detail = parse_detail_section(&chat_event.detail);
     println!("Parsed detail map: {:#?}", parsed_detail);
-    
+
     println!("\n=== EXTRACT CALLSIGN ===");
     // This is the private function, so let's simulate what it does
-    
+
     // The function first checks for a "chat" key
     if let Some(chat_obj) = parsed_detail.get("chat") {
         println!("Found 'chat' object: {:?}", chat_obj);
@@ -40,9 +43,12 @@ fn main() {
         }
     } else {
         println!("No 'chat' key found in parsed detail");
-        println!("Available keys: {:?}", parsed_detail.keys().collect::<Vec<_>>());
+        println!(
+            "Available keys: {:?}",
+            parsed_detail.keys().collect::<Vec<_>>()
+        );
     }
-    
+
     println!("\n=== CONVERSION TO DITTO ===");
     let ditto_doc = cot_to_document(&chat_event, "test-peer");
     match ditto_doc {
@@ -52,13 +58,16 @@ fn main() {
         }
         _ => println!("Document was not converted to Chat type"),
     }
-    
+
     println!("\n=== ANALYSIS ===");
-    println!("The detail string '{}' is not valid XML because:", chat_event.detail);
+    println!(
+        "The detail string '{}' is not valid XML because:",
+        chat_event.detail
+    );
     println!("1. The attributes don't have quoted values");
     println!("2. 'chat' should be a proper XML element, not text with attributes");
     println!("3. The XML parser can't parse unquoted attribute values");
-    
+
     println!("\n=== EXPECTED FORMAT ===");
     println!("Should be: <detail><chat from=\"DELTA-4\" room=\"Operations Room\" roomId=\"ops-room-001\" msg=\"Test message content\"/></detail>");
-}
\ No newline at end of file
+}
@@ -11,7 +11,10 @@ fn test_chat_callsign_extraction() {
         "Test message content",
         "Operations Room",
         "ops-room-001",
-    );
+        None,
+        "+5m",
+    )
+    .unwrap();
 
     println!("Detail string: {}", chat_event.detail);
 
@@ -19,26 +22,26 @@ fn test_chat_callsign_extraction() {
     let parsed_detail = parse_detail_section(&chat_event.detail);
     println!("Parsed detail: {:#?}", parsed_detail);
 
-    // Verify that the detail was parsed correctly and has a 'chat' object
+    // Verify that the detail was parsed correctly and has a '__chat' object
     assert!(
-        parsed_detail.contains_key("chat"),
-        "Detail should contain 'chat' key"
+        parsed_detail.contains_key("__chat"),
+        "Detail should contain '__chat' key"
     );
 
-    let chat_obj = parsed_detail.get("chat").unwrap();
-    assert!(chat_obj.is_object(), "Chat value should be an object");
+    let chat_obj = parsed_detail.get("__chat").unwrap();
+    assert!(chat_obj.is_object(), "__chat value should be an object");
 
     let chat_map = chat_obj.as_object().unwrap();
     assert!(
-        chat_map.contains_key("from"),
-        "Chat object should contain 'from' key"
+        chat_map.contains_key("senderCallsign"),
+        "__chat object should contain 'senderCallsign' key"
     );
 
-    let from_value = chat_map.get("from").unwrap();
+    let sender_callsign = chat_map.get("senderCallsign").unwrap();
     assert_eq!(
-        from_value.as_str().unwrap(),
+        sender_callsign.as_str().unwrap(),
         "DELTA-4",
-        "From value should be 'DELTA-4'"
+        "senderCallsign value should be 'DELTA-4'"
     );
 
     // Test the conversion to Ditto document
@@ -66,23 +69,31 @@ fn test_chat_detail_parsing_with_spaces() {
         "Hello world",
         "Command Center Alpha",
         "cmd-center-001",
-    );
+        None,
+        "+5m",
+    )
+    .unwrap();
 
     let parsed_detail = parse_detail_section(&chat_event.detail);
 
     // Verify all fields are parsed correctly
-    let chat_obj = parsed_detail.get("chat").unwrap().as_object().unwrap();
-    assert_eq!(chat_obj.get("from").unwrap().as_str().unwrap(), "BRAVO-2");
+    let chat_obj = parsed_detail.get("__chat").unwrap().as_object().unwrap();
+    assert_eq!(
+        chat_obj.get("senderCallsign").unwrap().as_str().unwrap(),
+        "BRAVO-2"
+    );
     assert_eq!(
-        chat_obj.get("room").unwrap().as_str().unwrap(),
+        chat_obj.get("chatroom").unwrap().as_str().unwrap(),
         "Command Center Alpha"
     );
+    let chatgrp = chat_obj.get("chatgrp").unwrap().as_object().unwrap();
     assert_eq!(
-        chat_obj.get("roomId").unwrap().as_str().unwrap(),
+        chatgrp.get("id").unwrap().as_str().unwrap(),
         "cmd-center-001"
     );
+    let remarks = parsed_detail.get("remarks").unwrap().as_object().unwrap();
     assert_eq!(
-        chat_obj.get("msg").unwrap().as_str().unwrap(),
+        remarks.get("_text").unwrap().as_str().unwrap(),
         "Hello world"
     );
 }
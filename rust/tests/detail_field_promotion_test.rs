@@ -0,0 +1,57 @@
+//! Tests that `xml_parser::parse_cot` promotes well-known CoT detail
+//! sub-elements (`contact`, `__group`, `track`) onto their first-class
+//! `FlatCotEvent` fields, not just the generic `detail_extra` bag.
+
+use ditto_cot::xml_parser::parse_cot;
+
+fn sample_xml(detail: &str) -> String {
+    format!(
+        r#"<event version="2.0" uid="TEST-1" type="a-f-G-U-C"
+            time="2023-01-01T00:00:00Z" start="2023-01-01T00:00:00Z"
+            stale="2023-01-01T00:05:00Z" how="h-g-i-g-o"
+            lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0">
+            <detail>{}</detail>
+        </event>"#,
+        detail
+    )
+}
+
+#[test]
+fn promotes_track_speed_and_course() {
+    let xml = sample_xml(r#"<track speed="15.0" course="90.0"/>"#);
+    let event = parse_cot(&xml).expect("valid XML");
+
+    assert_eq!(event.speed, Some(15.0));
+    assert_eq!(event.course, Some(90.0));
+    // Still preserved losslessly in the generic bag.
+    assert_eq!(event.detail_extra["track"]["speed"], "15.0");
+}
+
+#[test]
+fn promotes_group_name_and_role() {
+    let xml = sample_xml(r#"<__group name="Cyan" role="Team Lead"/>"#);
+    let event = parse_cot(&xml).expect("valid XML");
+
+    assert_eq!(event.group_name, Some("Cyan".to_string()));
+    assert_eq!(event.group_role, Some("Team Lead".to_string()));
+}
+
+#[test]
+fn promotes_contact_callsign() {
+    let xml = sample_xml(r#"<contact callsign="ALPHA-1"/>"#);
+    let event = parse_cot(&xml).expect("valid XML");
+
+    assert_eq!(event.callsign, Some("ALPHA-1".to_string()));
+}
+
+#[test]
+fn missing_well_known_tags_leave_fields_none() {
+    let xml = sample_xml(r#"<remarks>all clear</remarks>"#);
+    let event = parse_cot(&xml).expect("valid XML");
+
+    assert_eq!(event.callsign, None);
+    assert_eq!(event.group_name, None);
+    assert_eq!(event.group_role, None);
+    assert_eq!(event.speed, None);
+    assert_eq!(event.course, None);
+}
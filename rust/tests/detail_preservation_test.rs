@@ -0,0 +1,87 @@
+use anyhow::Result;
+use ditto_cot::{
+    cot_events::CotEvent,
+    ditto::{cot_event_from_flattened_json, cot_to_flattened_document},
+    xml_utils,
+};
+
+/// Extracts the `<detail>...</detail>` substring from a full CoT XML document.
+fn detail_section(xml: &str) -> &str {
+    let start = xml.find("<detail>").expect("xml should contain <detail>");
+    let end = xml.find("</detail>").expect("xml should contain </detail>") + "</detail>".len();
+    &xml[start..end]
+}
+
+/// Wraps a `<detail>...</detail>` fragment in a synthetic root so `semantic_xml_eq`
+/// (which expects a single well-formed document) can parse and compare it.
+fn wrap(detail: &str) -> String {
+    format!("<root>{}</root>", detail)
+}
+
+#[test]
+fn unknown_atak_extension_round_trips_losslessly() -> Result<()> {
+    // `customExtension`/`widget` are not among the fixed detail types
+    // (fileshare, track, contact, __group, status, keywords) any transform
+    // has special-cased logic for; the flattening layer must still preserve
+    // them exactly.
+    let cot_xml = r#"<event version="2.0" uid="EXT-1" type="a-f-G-U-C"
+        time="2023-05-01T12:00:00Z" start="2023-05-01T12:00:00Z"
+        stale="2023-05-01T12:30:00Z" how="h-g-i-g-o">
+        <point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/>
+        <detail>
+            <contact callsign="ALPHA-1"/>
+            <customExtension vendor="acme" revision="7">
+                <widget id="w1" enabled="true"/>
+                <widget id="w2" enabled="false">
+                    <note>needs calibration</note>
+                </widget>
+            </customExtension>
+        </detail>
+    </event>"#;
+
+    let event = CotEvent::from_xml(cot_xml)?;
+    let flattened = cot_to_flattened_document(&event, "test-peer");
+    let roundtrip_event = cot_event_from_flattened_json(&flattened);
+
+    let expected = wrap(detail_section(cot_xml));
+    let actual = wrap(&roundtrip_event.detail);
+
+    assert!(
+        xml_utils::semantic_xml_eq(&expected, &actual, false),
+        "unknown detail extension did not round-trip losslessly:\noriginal: {}\nroundtrip: {}",
+        expected,
+        actual
+    );
+
+    Ok(())
+}
+
+#[test]
+fn repeated_siblings_and_text_content_round_trip_losslessly() -> Result<()> {
+    let cot_xml = r#"<event version="2.0" uid="EXT-2" type="a-f-G-U-C"
+        time="2023-05-01T12:00:00Z" start="2023-05-01T12:00:00Z"
+        stale="2023-05-01T12:30:00Z" how="h-g-i-g-o">
+        <point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/>
+        <detail>
+            <link uid="PARENT-1" type="a-f-G" relation="p-p"/>
+            <link uid="PARENT-2" type="a-f-G" relation="p-p"/>
+            <remarks source="BAO">All clear</remarks>
+        </detail>
+    </event>"#;
+
+    let event = CotEvent::from_xml(cot_xml)?;
+    let flattened = cot_to_flattened_document(&event, "test-peer");
+    let roundtrip_event = cot_event_from_flattened_json(&flattened);
+
+    let expected = wrap(detail_section(cot_xml));
+    let actual = wrap(&roundtrip_event.detail);
+
+    assert!(
+        xml_utils::semantic_xml_eq(&expected, &actual, false),
+        "repeated siblings/text content did not round-trip losslessly:\noriginal: {}\nroundtrip: {}",
+        expected,
+        actual
+    );
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 //! Tests for improved error handling
 
 use ditto_cot::cot_events::CotEvent;
+use ditto_cot::detail_parser::{parse_detail_section, try_parse_detail_section};
 use ditto_cot::error::CotError;
 use ditto_cot::xml_parser::parse_cot;
 
@@ -68,3 +69,39 @@ fn test_valid_parsing_still_works() {
     assert_eq!(event.point.lat, 34.12345);
     assert_eq!(event.point.lon, -118.12345);
 }
+
+#[test]
+fn test_try_parse_detail_section_rejects_a_mismatched_end_tag() {
+    let detail = "<contact callsign=\"TEST-123\">\n<track course=\"1\"></wrong>\n</contact>";
+
+    let result = try_parse_detail_section(detail);
+    assert!(result.is_err());
+
+    match result.unwrap_err() {
+        CotError::XmlParse { line, column, .. } => {
+            assert_eq!(line, 2);
+            assert!(column > 1);
+        }
+        other => panic!("expected CotError::XmlParse, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_try_parse_detail_section_accepts_well_formed_detail() {
+    let detail = r#"<contact callsign="TEST-123"/><__group name="Blue"/>"#;
+
+    let extras = try_parse_detail_section(detail).expect("well-formed detail must parse");
+    assert_eq!(extras["contact"]["callsign"], "TEST-123");
+    assert_eq!(extras["__group"]["name"], "Blue");
+}
+
+#[test]
+fn test_parse_detail_section_returns_partial_result_for_malformed_input() {
+    // The infallible wrapper must still accept its well-formed sibling
+    // rather than dropping the whole detail section, unlike
+    // `try_parse_detail_section` which rejects the input outright.
+    let detail = "<contact callsign=\"TEST-123\"/>\n<track course=\"1\"></wrong>";
+
+    let extras = parse_detail_section(detail);
+    assert_eq!(extras["contact"]["callsign"], "TEST-123");
+}
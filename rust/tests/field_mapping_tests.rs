@@ -4,7 +4,7 @@
 //! to their corresponding Ditto document fields.
 
 use chrono::{DateTime, Utc};
-use ditto_cot::cot_events::{CotEvent, Point};
+use ditto_cot::cot_events::{CotEvent, EmergencyType, Point};
 use ditto_cot::ditto::from_ditto::cot_event_from_ditto_document;
 use ditto_cot::ditto::{cot_to_document, CotDocument};
 use std::str::FromStr;
@@ -28,6 +28,7 @@ fn test_cot_to_ditto_field_mapping() {
             le: 20.0,
         },
         detail: "<detail><contact callsign=\"ALPHA-1\"/></detail>".to_string(),
+        tz_offset_secs: None,
     };
 
     let doc = cot_to_document(&event, "test-peer-key");
@@ -130,6 +131,7 @@ fn test_timestamp_field_conversions() {
         how: "m-g".to_string(),
         point: Point::default(),
         detail: "<detail/>".to_string(),
+        tz_offset_secs: None,
     };
 
     let doc = cot_to_document(&event, "test-peer");
@@ -172,6 +174,7 @@ fn test_custom_field_preservation() {
         how: "m-g".to_string(),
         point: Point::default(),
         detail: detail_xml.to_string(),
+        tz_offset_secs: None,
     };
 
     let doc = cot_to_document(&event, "test-peer");
@@ -193,6 +196,67 @@ fn test_custom_field_preservation() {
     }
 }
 
+/// `cot_to_document`/`cot_event_from_ditto_document` round-trip repeated
+/// same-name detail elements (e.g. multiple `<link>` children) without
+/// collapsing them: each `XRValue` enum (`MapItemRValue` here) carries an
+/// `Array` variant that `detail_parser`'s already-array-valued output maps
+/// onto directly, so no flattening to indexed keys is needed to keep every
+/// occurrence and its order.
+#[test]
+fn test_repeated_detail_elements_round_trip_through_document() {
+    let detail_xml = r#"<detail>
+        <link uid="PARENT-1" relation="p-p" type="a-f-G-U-C"/>
+        <link uid="PARENT-2" relation="p-p" type="a-f-G-U-C"/>
+        <link uid="PARENT-3" relation="p-p" type="a-f-G-U-C"/>
+    </detail>"#;
+
+    let event = CotEvent {
+        version: "2.0".to_string(),
+        uid: "MULTI-LINK-001".to_string(),
+        event_type: "a-f-G-U-C".to_string(),
+        time: Utc::now(),
+        start: Utc::now(),
+        stale: Utc::now() + chrono::Duration::minutes(5),
+        how: "m-g".to_string(),
+        point: Point::default(),
+        detail: detail_xml.to_string(),
+        tz_offset_secs: None,
+    };
+
+    let doc = cot_to_document(&event, "test-peer");
+
+    let links = match &doc {
+        CotDocument::MapItem(map_item) => map_item.r.get("link"),
+        other => panic!("expected MapItem document, got {other:?}"),
+    };
+    match links {
+        Some(ditto_cot::ditto::MapItemRValue::Array(items)) => {
+            assert_eq!(items.len(), 3, "all three <link> siblings should survive");
+            assert_eq!(items[0]["uid"], "PARENT-1");
+            assert_eq!(items[1]["uid"], "PARENT-2");
+            assert_eq!(items[2]["uid"], "PARENT-3");
+        }
+        other => panic!("expected link to be an Array RValue, got {other:?}"),
+    }
+
+    let round_tripped = cot_event_from_ditto_document(&doc);
+    let link_positions = [
+        round_tripped.detail.find("PARENT-1"),
+        round_tripped.detail.find("PARENT-2"),
+        round_tripped.detail.find("PARENT-3"),
+    ];
+    assert!(
+        link_positions.iter().all(Option::is_some),
+        "all three link uids should appear in the reconstructed detail XML: {}",
+        round_tripped.detail
+    );
+    assert!(
+        link_positions[0] < link_positions[1] && link_positions[1] < link_positions[2],
+        "reconstructed <link> elements should keep their original order: {}",
+        round_tripped.detail
+    );
+}
+
 /// Test field type conversions (string to number, etc.)
 #[test]
 fn test_field_type_conversions() {
@@ -234,7 +298,10 @@ fn test_chat_message_field_mapping() {
         "Test message content",
         "Operations Room",
         "ops-room-001",
-    );
+        None,
+        "+5m",
+    )
+    .unwrap();
 
     let doc = cot_to_document(&chat_event, "test-peer");
 
@@ -259,25 +326,27 @@ fn test_emergency_event_field_mapping() {
         "ECHO-5",
         36.0,
         -121.0,
-        "Emergency-911",
+        EmergencyType::Alert911,
         "Medical assistance required",
-    );
+        "+5m",
+    )
+    .unwrap();
 
     let doc = cot_to_document(&emrg_event, "test-peer");
 
-    // Note: new_emergency creates "b-a-o-can" type, which maps to Generic, not Api
+    // Note: new_emergency creates "b-a-o-pan" type, which maps to Generic, not Api
     match doc {
         CotDocument::Generic(generic) => {
             assert_eq!(generic.id, "EMRG-MAP-001");
             assert_eq!(generic.e, "ECHO-5");
-            assert_eq!(generic.w, "b-a-o-can");
+            assert_eq!(generic.w, "b-a-o-pan");
             assert_eq!(generic.j, Some(36.0));
             assert_eq!(generic.l, Some(-121.0));
 
             // Emergency details should be in r field
             assert!(!generic.r.is_empty());
         }
-        _ => panic!("Expected Generic document for emergency event with b-a-o-can type"),
+        _ => panic!("Expected Generic document for emergency event with b-a-o-pan type"),
     }
 }
 
@@ -364,6 +433,7 @@ fn test_unknown_type_to_generic_mapping() {
         how: "m-g".to_string(),
         point: Point::default(),
         detail: "<detail><custom>Special data</custom></detail>".to_string(),
+        tz_offset_secs: None,
     };
 
     let doc = cot_to_document(&unknown_event, "test-peer");
@@ -397,7 +467,9 @@ fn test_sensor_event_type_mapping() {
             35.0,
             -120.0,
             500.0,
-        );
+            "+5m",
+        )
+        .unwrap();
         event.event_type = event_type.to_string();
 
         let doc = cot_to_document(&event, "test-peer");
@@ -432,6 +504,7 @@ fn test_round_trip_field_preservation() {
             le: 10.5,
         },
         detail: r#"<detail><contact callsign="FOXTROT-6"/></detail>"#.to_string(),
+        tz_offset_secs: None,
     };
 
     // Convert to Ditto document
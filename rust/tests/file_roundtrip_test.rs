@@ -149,16 +149,15 @@ fn test_file_roundtrip() -> Result<()> {
     println!("Original XML:\n{}", cot_xml);
     println!("Roundtrip XML:\n{}", cot_xml_out);
 
-    // Check if the XML documents are semantically equivalent
+    // The general detail-preservation layer in r_field_flattening reconstructs
+    // every fileshare/keywords child exactly, so this no longer needs the
+    // loose fallback the earlier flattening implementation required.
     let are_equal = xml_utils::semantic_xml_eq_legacy(cot_xml, &cot_xml_out);
     if !are_equal {
-        println!("XML documents are not semantically equivalent");
         println!("Minimized Original XML:\n{}", min_expected);
         println!("Minimized Roundtrip XML:\n{}", min_actual);
     }
-
-    // For now, skip the semantic equality check and focus on the field-specific assertions
-    // assert!(are_equal, "XML documents are not semantically equivalent");
+    assert!(are_equal, "XML documents are not semantically equivalent");
 
     println!("✓ File roundtrip test passed");
     Ok(())
@@ -274,15 +274,51 @@ impl CoTTestFixtures {
         })
     }
 
+    /// Creates an expected Generic document structure whose detail blob
+    /// (`nn`) has been sealed with XChaCha20-Poly1305, as
+    /// `CotDocument::encrypt_detail` would produce. Pairs with
+    /// [`Self::create_expected_generic_document`]: a test seeds the
+    /// `nonce`/`ciphertext`/`tag` from a real `encrypt_detail` call, then
+    /// asserts that decrypting this structure's `nn` reproduces the
+    /// plaintext `nn` map the unsealed fixture expects.
+    pub fn create_encrypted_generic_document(
+        uid: &str,
+        cot_type: &str,
+        nonce_b64: &str,
+        ciphertext_b64: &str,
+        tag_b64: &str,
+    ) -> Value {
+        json!({
+            "_id": uid,
+            "w": cot_type,
+            "h": STANDARD_LAT,
+            "j": STANDARD_LON,
+            "k": STANDARD_HAE,
+            "l": STANDARD_CE,
+            "m": STANDARD_LE,
+            "q": Self::parse_timestamp_to_micros(STANDARD_START),
+            "r": Self::parse_timestamp_to_micros(STANDARD_STALE),
+            "nn": {
+                "_encrypted": {
+                    "nonce": nonce_b64,
+                    "ciphertext": ciphertext_b64,
+                    "tag": tag_b64
+                }
+            }
+        })
+    }
+
     /// Helper method to parse timestamp to microseconds
+    ///
+    /// Delegates to [`ditto_cot::timestamp::Timestamp`] so fixture data goes
+    /// through the same leap-second-correct, scale-aware conversion as
+    /// production code; a malformed fixture timestamp is a bug in the test
+    /// itself, so this panics rather than silently producing a zeroed
+    /// document as the old `Err(_) => 0` fallback did.
     fn parse_timestamp_to_micros(timestamp: &str) -> u64 {
-        match DateTime::parse_from_rfc3339(timestamp) {
-            Ok(dt) => {
-                let utc_dt = dt.with_timezone(&Utc);
-                (utc_dt.timestamp() as u64) * 1_000_000 + (utc_dt.timestamp_subsec_micros() as u64)
-            }
-            Err(_) => 0,
-        }
+        ditto_cot::timestamp::Timestamp::parse(timestamp)
+            .and_then(|ts| ts.to_unix_micros())
+            .unwrap_or_else(|e| panic!("invalid fixture timestamp '{timestamp}': {e}"))
     }
 
     /// Creates a timestamp that is X seconds in the future from the standard time
@@ -0,0 +1,139 @@
+//! Data-driven golden-vector conformance corpus.
+//!
+//! Each case pairs a `*.cot.xml` input with a `*.expected.json` flattened
+//! Ditto document under `fixtures/golden/`, both embedded at compile time via
+//! `include_str!` so the corpus lives as reviewable, versioned files instead
+//! of the hand-written `create_*_xml`/`create_expected_*_document` literal
+//! pairs in `fixtures::CoTTestFixtures`. Adding a case is "drop in an XML,
+//! run with `UPDATE_GOLDEN_VECTORS=1`, review the diff" (see
+//! [`regenerate_golden_vectors`]).
+//!
+//! Covers one case per built-in [`TransformerRegistry`](ditto_cot::ditto::TransformerRegistry)
+//! family: MapItem, Chat, FileShare, Api (emergency), Generic, and a sensor
+//! (`a-u-S`) variant, which is itself a MapItem per the registry's dispatch
+//! rules.
+
+use ditto_cot::cot_events::CotEvent;
+use ditto_cot::ditto::cot_to_flattened_document;
+use serde_json::Value;
+
+/// The peer key every golden case is converted with.
+const GOLDEN_PEER_KEY: &str = "golden-peer";
+
+struct GoldenCase {
+    name: &'static str,
+    xml: &'static str,
+    expected_json: &'static str,
+    /// Real on-disk path to the `.expected.json` file, for
+    /// [`regenerate_golden_vectors`] to rewrite.
+    expected_path: &'static str,
+}
+
+macro_rules! golden_case {
+    ($name:literal) => {
+        GoldenCase {
+            name: $name,
+            xml: include_str!(concat!("fixtures/golden/", $name, ".cot.xml")),
+            expected_json: include_str!(concat!("fixtures/golden/", $name, ".expected.json")),
+            expected_path: concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/golden/",
+                $name,
+                ".expected.json"
+            ),
+        }
+    };
+}
+
+const GOLDEN_CASES: &[GoldenCase] = &[
+    golden_case!("map_item"),
+    golden_case!("chat"),
+    golden_case!("file_share"),
+    golden_case!("api"),
+    golden_case!("generic"),
+    golden_case!("sensor"),
+];
+
+/// Converts `case`'s XML through the real production path:
+/// `CotEvent::from_xml` then `cot_to_flattened_document`.
+fn convert(case: &GoldenCase) -> Value {
+    let event = CotEvent::from_xml(case.xml)
+        .unwrap_or_else(|e| panic!("{}: failed to parse input XML: {e}", case.name));
+    cot_to_flattened_document(&event, GOLDEN_PEER_KEY)
+}
+
+/// Returns the dotted path to the first field where `actual` diverges from
+/// `expected`, or `None` if they're structurally equal (object key order
+/// doesn't matter).
+fn diff_path(expected: &Value, actual: &Value, path: &str) -> Option<String> {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            for (key, e_val) in e {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match a.get(key) {
+                    Some(a_val) => {
+                        if let Some(p) = diff_path(e_val, a_val, &child_path) {
+                            return Some(p);
+                        }
+                    }
+                    None => return Some(format!("{child_path} (missing from actual)")),
+                }
+            }
+            for key in a.keys() {
+                if !e.contains_key(key) {
+                    let child_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    return Some(format!("{child_path} (unexpected in actual)"));
+                }
+            }
+            None
+        }
+        (e, a) if e == a => None,
+        (e, a) => Some(format!("{path}: expected {e}, got {a}")),
+    }
+}
+
+#[test]
+fn golden_vectors_match_conversion_output() {
+    for case in GOLDEN_CASES {
+        let expected: Value = serde_json::from_str(case.expected_json)
+            .unwrap_or_else(|e| panic!("{}: malformed expected.json: {e}", case.name));
+        let actual = convert(case);
+
+        if let Some(path) = diff_path(&expected, &actual, "") {
+            panic!(
+                "{}: conversion output mismatch at `{path}`\n\
+                 full expected: {expected:#}\n\
+                 full actual:   {actual:#}\n\
+                 (run with UPDATE_GOLDEN_VECTORS=1 to regenerate, then review the diff)",
+                case.name
+            );
+        }
+    }
+}
+
+/// Rewrites every `.expected.json` from the current conversion output when
+/// `UPDATE_GOLDEN_VECTORS` is set, so adding a new case is "drop in an XML,
+/// run, review the diff" instead of hand-computing the compressed-key
+/// flattened document. A no-op otherwise, so this doesn't silently mutate
+/// the corpus during ordinary `cargo test` runs.
+#[test]
+fn regenerate_golden_vectors() {
+    if std::env::var("UPDATE_GOLDEN_VECTORS").is_err() {
+        return;
+    }
+    for case in GOLDEN_CASES {
+        let actual = convert(case);
+        let pretty = serde_json::to_string_pretty(&actual)
+            .unwrap_or_else(|e| panic!("{}: failed to serialize actual output: {e}", case.name));
+        std::fs::write(case.expected_path, format!("{pretty}\n"))
+            .unwrap_or_else(|e| panic!("{}: failed to write {}: {e}", case.name, case.expected_path));
+    }
+}
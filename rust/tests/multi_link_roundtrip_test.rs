@@ -0,0 +1,76 @@
+//! Round-trip tests for `detail` sections with repeated sibling elements
+//! (e.g. multiple `<link>` children), exercising `parse_cot`/`to_cot_xml`.
+
+use ditto_cot::error::CotError;
+use ditto_cot::xml_parser::parse_cot;
+use ditto_cot::xml_writer::to_cot_xml;
+
+/// Multiple `<link>` children should all survive a parse/write/parse cycle
+/// instead of the last one silently overwriting the rest.
+#[test]
+fn test_multi_link_roundtrip() -> Result<(), CotError> {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?><event version="2.0" uid="TEST-456" type="a-f-G-U-C" time="2023-01-01T12:00:00Z" start="2023-01-01T12:00:00Z" stale="2023-01-01T12:05:00Z" how="h-g-i-g-o"><point lat="34.12345" lon="-118.12345" hae="150.0" ce="10.0" le="20.0"/><detail><link uid="PARENT-1" relation="p-p" type="a-f-G-U-C"/><link uid="PARENT-2" relation="p-p" type="a-f-G-U-C"/><link uid="PARENT-3" relation="p-p" type="a-f-G-U-C"/></detail></event>"#;
+
+    let event = parse_cot(xml)?;
+    let links = event
+        .detail_extra
+        .get("link")
+        .expect("link key should be present");
+    let links = links.as_array().expect("repeated <link> should parse as an array");
+    assert_eq!(links.len(), 3);
+    assert_eq!(links[0]["uid"], "PARENT-1");
+    assert_eq!(links[1]["uid"], "PARENT-2");
+    assert_eq!(links[2]["uid"], "PARENT-3");
+
+    // Round-trip through the writer and re-parse: order and count must survive.
+    let xml_roundtrip = to_cot_xml(&event);
+    let reparsed = parse_cot(&xml_roundtrip)?;
+    let reparsed_links = reparsed.detail_extra["link"]
+        .as_array()
+        .expect("link should still be an array after round-trip");
+    assert_eq!(reparsed_links.len(), 3);
+    assert_eq!(reparsed_links[0]["uid"], "PARENT-1");
+    assert_eq!(reparsed_links[1]["uid"], "PARENT-2");
+    assert_eq!(reparsed_links[2]["uid"], "PARENT-3");
+
+    Ok(())
+}
+
+/// Repeated `<remarks>` elements with mixed text content should also be
+/// preserved as an array rather than collapsed to one.
+#[test]
+fn test_multi_remarks_roundtrip() -> Result<(), CotError> {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?><event version="2.0" uid="TEST-789" type="a-f-G-U-C" time="2023-01-01T12:00:00Z" start="2023-01-01T12:00:00Z" stale="2023-01-01T12:05:00Z" how="h-g-i-g-o"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/><detail><remarks source="op1">First remark</remarks><remarks source="op2">Second remark</remarks></detail></event>"#;
+
+    let event = parse_cot(xml)?;
+    let remarks = event.detail_extra["remarks"]
+        .as_array()
+        .expect("repeated <remarks> should parse as an array");
+    assert_eq!(remarks.len(), 2);
+    assert_eq!(remarks[0]["_text"], "First remark");
+    assert_eq!(remarks[1]["_text"], "Second remark");
+
+    let xml_roundtrip = to_cot_xml(&event);
+    let reparsed = parse_cot(&xml_roundtrip)?;
+    let reparsed_remarks = reparsed.detail_extra["remarks"]
+        .as_array()
+        .expect("remarks should still be an array after round-trip");
+    assert_eq!(reparsed_remarks.len(), 2);
+    assert_eq!(reparsed_remarks[0]["_text"], "First remark");
+    assert_eq!(reparsed_remarks[1]["_text"], "Second remark");
+
+    Ok(())
+}
+
+/// A single, unrepeated sibling should still parse as a plain object, not a
+/// one-element array, so existing single-element callers are unaffected.
+#[test]
+fn test_single_link_stays_object() -> Result<(), CotError> {
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?><event version="2.0" uid="TEST-999" type="a-f-G-U-C" time="2023-01-01T12:00:00Z" start="2023-01-01T12:00:00Z" stale="2023-01-01T12:05:00Z" how="h-g-i-g-o"><point lat="1.0" lon="2.0" hae="3.0" ce="4.0" le="5.0"/><detail><link uid="ONLY-PARENT" relation="p-p" type="a-f-G-U-C"/></detail></event>"#;
+
+    let event = parse_cot(xml)?;
+    assert!(event.detail_extra["link"].is_object());
+    assert_eq!(event.detail_extra["link"]["uid"], "ONLY-PARENT");
+
+    Ok(())
+}
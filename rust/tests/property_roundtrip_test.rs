@@ -0,0 +1,427 @@
+//! Property-based tests for `CotEvent` parsing and conversion, run under
+//! `cargo test` rather than `cargo fuzz run` so the same invariants checked
+//! by `fuzz/fuzz_targets/fuzz_cot_property_corpus.rs` are also part of CI.
+//!
+//! Generators synthesize arbitrary-but-structurally-valid CoT: type strings
+//! from the same closed vocabulary the `TransformerRegistry` dispatches on,
+//! coordinates biased toward the `±90`/`±180` extremes alongside arbitrary
+//! finite values, and callsigns/messages/filenames/sizes from realistic
+//! vocabularies. See `fuzz_cot_property_corpus`'s corpus seeds for the
+//! regression cases (extreme coordinates, the `a-u-S` malformed-placement
+//! quirk) this complements rather than duplicates.
+//!
+//! [`repeated_detail_elements_strategy`] additionally generates details as
+//! an ordered set keyed by element name (a `BTreeMap` from tag to the
+//! instances generated for it) rather than a flat list, so same-tag
+//! siblings like `<link>`/`<sensor>` — the elements
+//! [`repeated_detail_merge`](ditto_cot::ditto::repeated_detail_merge)
+//! preserves as an array instead of clobbering — are exercised with
+//! duplicate keys and a fixed emission order a shrunk failure stays
+//! reproducible under.
+
+use ditto_cot::cot_events::{CotEvent, CotEventBuilder};
+use ditto_cot::detail_parser::parse_detail_section;
+use ditto_cot::ditto::{cot_event_from_ditto_document, cot_to_document, cot_to_flattened_document};
+use proptest::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+fn event_type_strategy() -> impl Strategy<Value = &'static str> {
+    prop_oneof![
+        Just("a-f-G-U-C"),     // MapItem
+        Just("b-t-f"),         // Chat
+        Just("b-f-t-f"),       // FileShare
+        Just("t-x-c-t"),       // Api
+        Just("a-u-emergency-g"), // Emergency
+        Just("a-u-S"),         // Sensor
+        Just("a-u-A"),         // Aircraft
+        Just("a-u-G"),         // Ground
+        Just("x-custom-type"), // Generic
+    ]
+}
+
+/// One component of a point, biased toward the `±90`/`±180` extremes and
+/// zero rather than drawn from a flat distribution the whole time.
+fn coordinate_strategy(extreme: f64) -> impl Strategy<Value = f64> {
+    prop_oneof![
+        3 => Just(extreme),
+        3 => Just(0.0),
+        10 => -extreme..=extreme,
+    ]
+}
+
+fn callsign_strategy() -> impl Strategy<Value = &'static str> {
+    prop_oneof![
+        Just("ALPHA-1"),
+        Just("BRAVO-2"),
+        Just("CHARLIE-3"),
+        Just("DELTA-4"),
+        Just("ECHO-5"),
+    ]
+}
+
+fn message_strategy() -> impl Strategy<Value = &'static str> {
+    prop_oneof![
+        Just("Hello World"),
+        Just("Test message"),
+        Just("Emergency situation"),
+        Just("All clear"),
+        Just("Status update"),
+    ]
+}
+
+fn filename_strategy() -> impl Strategy<Value = (&'static str, u64)> {
+    prop_oneof![
+        Just(("document.pdf", 1024u64)),
+        Just(("image.jpg", 1_048_576u64)),
+        Just(("video.mp4", 5_242_880u64)),
+        Just(("audio.wav", 10_485_760u64)),
+        Just(("data.zip", 104_857_600u64)),
+    ]
+}
+
+#[derive(Debug, Clone)]
+enum GeneratedDetail {
+    None,
+    Contact(&'static str),
+    Remarks(&'static str),
+    FileShare(&'static str, u64),
+}
+
+fn detail_strategy() -> impl Strategy<Value = GeneratedDetail> {
+    prop_oneof![
+        Just(GeneratedDetail::None),
+        callsign_strategy().prop_map(GeneratedDetail::Contact),
+        message_strategy().prop_map(GeneratedDetail::Remarks),
+        filename_strategy().prop_map(|(name, size)| GeneratedDetail::FileShare(name, size)),
+    ]
+}
+
+fn detail_xml(detail: &GeneratedDetail) -> String {
+    match detail {
+        GeneratedDetail::None => String::new(),
+        GeneratedDetail::Contact(callsign) => {
+            format!("<detail><contact callsign=\"{callsign}\"/></detail>")
+        }
+        GeneratedDetail::Remarks(message) => format!("<detail><remarks>{message}</remarks></detail>"),
+        GeneratedDetail::FileShare(filename, size) => format!(
+            "<detail><fileshare filename=\"{filename}\" sizeInBytes=\"{size}\"/></detail>"
+        ),
+    }
+}
+
+prop_compose! {
+    fn arbitrary_cot_event()(
+        uid in "[A-Za-z0-9-]{1,32}",
+        event_type in event_type_strategy(),
+        lat in coordinate_strategy(90.0),
+        lon in coordinate_strategy(180.0),
+        hae in coordinate_strategy(9999.9),
+        ce in coordinate_strategy(999.9),
+        le in coordinate_strategy(999.9),
+        detail in detail_strategy(),
+    ) -> CotEvent {
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type(event_type)
+            .location_with_accuracy(lat, lon, hae, ce, le)
+            .detail(detail_xml(&detail))
+            .build()
+    }
+}
+
+/// One repeated-detail-group instance: `link` and `sensor` both appear as
+/// same-tag siblings in real CoT, each carrying its own `uid`.
+fn element_instance_strategy() -> impl Strategy<Value = &'static str> {
+    prop_oneof![Just("link-1"), Just("link-2"), Just("sensor-1"), Just("sensor-2")]
+}
+
+/// Generates 0-3 instances per repeated-tag group (`link`, `sensor`), so a
+/// generated event can exercise zero, one, or duplicate-keyed siblings of
+/// the same element name.
+fn repeated_detail_elements_strategy(
+) -> impl Strategy<Value = BTreeMap<&'static str, Vec<&'static str>>> {
+    (
+        prop::collection::vec(element_instance_strategy(), 0..3),
+        prop::collection::vec(element_instance_strategy(), 0..3),
+    )
+        .prop_map(|(links, sensors)| {
+            let mut groups: BTreeMap<&'static str, Vec<&'static str>> = BTreeMap::new();
+            let links = links.into_iter().filter(|u| u.starts_with("link")).collect();
+            let sensors = sensors.into_iter().filter(|u| u.starts_with("sensor")).collect();
+            groups.insert("link", links);
+            groups.insert("sensor", sensors);
+            groups
+        })
+}
+
+/// Renders `groups` in key order (the `BTreeMap`'s own iteration order), one
+/// element per instance, so the same generated value always produces the
+/// same XML regardless of how proptest happened to shrink it.
+fn repeated_detail_xml(groups: &BTreeMap<&'static str, Vec<&'static str>>) -> String {
+    let mut body = String::new();
+    for (tag, uids) in groups {
+        for uid in uids {
+            body.push_str(&format!("<{tag} uid=\"{uid}\"/>"));
+        }
+    }
+    format!("<detail>{body}</detail>")
+}
+
+prop_compose! {
+    fn arbitrary_cot_event_with_repeated_details()(
+        uid in "[A-Za-z0-9-]{1,32}",
+        event_type in event_type_strategy(),
+        lat in coordinate_strategy(90.0),
+        lon in coordinate_strategy(180.0),
+        hae in coordinate_strategy(9999.9),
+        ce in coordinate_strategy(999.9),
+        le in coordinate_strategy(999.9),
+        groups in repeated_detail_elements_strategy(),
+    ) -> CotEvent {
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type(event_type)
+            .location_with_accuracy(lat, lon, hae, ce, le)
+            .detail(repeated_detail_xml(&groups))
+            .build()
+    }
+}
+
+proptest! {
+    /// Parsing never panics on arbitrary bytes, structured or not.
+    #[test]
+    fn from_xml_never_panics_on_arbitrary_bytes(data in ".{0,256}") {
+        let _ = CotEvent::from_xml(&data);
+    }
+
+    /// `xml -> CotEvent -> cot_to_document -> CotDocument` never panics for
+    /// any structurally-valid generated event.
+    #[test]
+    fn convert_never_panics(event in arbitrary_cot_event()) {
+        let xml = event.to_xml().expect("generated event must serialize");
+        let reparsed = CotEvent::from_xml(&xml).expect("generated xml must parse");
+        let _doc = cot_to_document(&reparsed, "proptest-peer");
+    }
+
+    /// `serialize -> parse -> serialize` reaches a fixed point: reparsing a
+    /// serialized event and serializing it again reproduces the same XML.
+    #[test]
+    fn serialize_parse_serialize_reaches_a_fixed_point(event in arbitrary_cot_event()) {
+        let xml = event.to_xml().expect("generated event must serialize");
+        let reparsed = CotEvent::from_xml(&xml).expect("generated xml must parse");
+        let xml_again = reparsed.to_xml().expect("reparsed event must serialize");
+
+        let reparsed_again = CotEvent::from_xml(&xml_again).expect("reparsed xml must parse");
+        let xml_fixed_point = reparsed_again.to_xml().expect("must serialize again");
+
+        prop_assert_eq!(xml_again, xml_fixed_point);
+    }
+
+    /// The full Ditto round trip — `from_xml(to_xml(cot_event_from_ditto_document(to_ditto(e))))`
+    /// — preserves `e`'s identity, placement, and detail content. `d_v`
+    /// (the document's own edit counter, not a field `CotEvent` has) is the
+    /// only thing this invariant deliberately ignores; detail is compared as
+    /// its parsed element map rather than raw XML since attribute/element
+    /// ordering isn't guaranteed to survive the `r`-map's `HashMap` detour.
+    #[test]
+    fn full_ditto_round_trip_preserves_identity_and_detail(
+        event in arbitrary_cot_event_with_repeated_details(),
+    ) {
+        let xml = event.to_xml().expect("generated event must serialize");
+        let original = CotEvent::from_xml(&xml).expect("generated xml must parse");
+
+        let doc = cot_to_document(&original, "proptest-peer");
+        let round_tripped = cot_event_from_ditto_document(&doc);
+        let xml_back = round_tripped.to_xml().expect("round-tripped event must serialize");
+        let reparsed_back = CotEvent::from_xml(&xml_back).expect("round-tripped xml must parse");
+
+        prop_assert_eq!(reparsed_back.uid, original.uid);
+        prop_assert_eq!(reparsed_back.event_type, original.event_type);
+        prop_assert_eq!(reparsed_back.point, original.point);
+        prop_assert_eq!(
+            parse_detail_section(&reparsed_back.detail),
+            parse_detail_section(&original.detail),
+        );
+    }
+}
+
+/// Asserts every field in `original` survives in `round_tripped`, allowing
+/// numeric fields (coordinates, `n`/`o` start/stale microseconds) to drift by
+/// up to `tolerance` from lossy float/microsecond round-tripping, while every
+/// other field must match exactly. Named after the old `assert_round_trip_conversion`
+/// helper this supersedes, which checked only `_id` and so missed exactly
+/// this kind of field-level drift.
+fn assert_round_trip_conversion(original: &Value, round_tripped: &Value, tolerance: f64) {
+    let (Some(original), Some(round_tripped)) = (original.as_object(), round_tripped.as_object())
+    else {
+        panic!("flattened documents must both be JSON objects");
+    };
+    for (field, original_value) in original {
+        let round_tripped_value = round_tripped
+            .get(field)
+            .unwrap_or_else(|| panic!("round-tripped document is missing field {field:?}"));
+        match (original_value, round_tripped_value) {
+            (Value::Number(a), Value::Number(b)) => {
+                let (a, b) = (a.as_f64().unwrap(), b.as_f64().unwrap());
+                assert!((a - b).abs() <= tolerance, "field {field:?} drifted: {a} vs {b}");
+            }
+            (a, b) => assert_eq!(a, b, "field {field:?} mismatched after round trip"),
+        }
+    }
+}
+
+/// Runs `event` through a full `to_xml -> from_xml -> cot_to_document ->
+/// cot_event_from_ditto_document -> to_xml -> from_xml` round trip and
+/// asserts every flattened field survives, within `tolerance` for numeric
+/// fields.
+fn assert_flattened_round_trip_matches(event: CotEvent, tolerance: f64) {
+    let xml = event.to_xml().expect("generated event must serialize");
+    let original = CotEvent::from_xml(&xml).expect("generated xml must parse");
+    let flattened_before = cot_to_flattened_document(&original, "proptest-peer");
+
+    let doc = cot_to_document(&original, "proptest-peer");
+    let round_tripped = cot_event_from_ditto_document(&doc);
+    let xml_back = round_tripped.to_xml().expect("round-tripped event must serialize");
+    let reparsed_back = CotEvent::from_xml(&xml_back).expect("round-tripped xml must parse");
+    let flattened_after = cot_to_flattened_document(&reparsed_back, "proptest-peer");
+
+    assert_round_trip_conversion(&flattened_before, &flattened_after, tolerance);
+}
+
+prop_compose! {
+    /// A MapItem-dispatching event (`LOCATION_EVENT_TYPE_MARKERS`), carrying
+    /// a `<contact>` so `e` (callsign) has something to round-trip too.
+    fn arbitrary_map_item_event()(
+        uid in "[A-Za-z0-9-]{1,32}",
+        lat in coordinate_strategy(90.0),
+        lon in coordinate_strategy(180.0),
+        hae in coordinate_strategy(9999.9),
+        ce in coordinate_strategy(999.9),
+        le in coordinate_strategy(999.9),
+        callsign in callsign_strategy(),
+    ) -> CotEvent {
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type("a-f-G-U-C")
+            .location_with_accuracy(lat, lon, hae, ce, le)
+            .detail(format!("<detail><contact callsign=\"{callsign}\"/></detail>"))
+            .build()
+    }
+}
+
+prop_compose! {
+    /// A Chat-dispatching event: `transform_chat_event` only produces a
+    /// [`Chat`](ditto_cot::ditto::Chat) (rather than falling back to
+    /// Generic) when `<remarks>` carries a message, so this always includes
+    /// the full `__chat`/`chatgrp`/`remarks` structure
+    /// [`GeoChat::to_detail_xml`](ditto_cot::cot_events::GeoChat::to_detail_xml) emits.
+    fn arbitrary_chat_event()(
+        uid in "[A-Za-z0-9-]{1,32}",
+        lat in coordinate_strategy(90.0),
+        lon in coordinate_strategy(180.0),
+        callsign in callsign_strategy(),
+        message in message_strategy(),
+    ) -> CotEvent {
+        let detail = format!(
+            "<detail><__chat chatroom=\"All Chat Rooms\" groupOwner=\"false\" \
+             senderCallsign=\"{callsign}\" id=\"All Chat Rooms\" messageId=\"m1\">\
+             <chatgrp uid0=\"{uid}\" id=\"All Chat Rooms\"/></__chat>\
+             <link uid=\"{uid}\"/>\
+             <remarks source=\"{uid}\" to=\"All Chat Rooms\">{message}</remarks></detail>"
+        );
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type("b-t-f")
+            .location_with_accuracy(lat, lon, 0.0, 0.0, 0.0)
+            .detail(detail)
+            .build()
+    }
+}
+
+prop_compose! {
+    /// A File-dispatching event (`FILE_EVENT_TYPE_MARKERS`), carrying a
+    /// `<fileshare>` so `file`-variant fields have real values to round-trip.
+    fn arbitrary_file_event()(
+        uid in "[A-Za-z0-9-]{1,32}",
+        lat in coordinate_strategy(90.0),
+        lon in coordinate_strategy(180.0),
+        (filename, size) in filename_strategy(),
+    ) -> CotEvent {
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type("b-f-t-f")
+            .location_with_accuracy(lat, lon, 0.0, 0.0, 0.0)
+            .detail(format!(
+                "<detail><fileshare filename=\"{filename}\" sizeInBytes=\"{size}\" \
+                 mime=\"application/octet-stream\"/></detail>"
+            ))
+            .build()
+    }
+}
+
+prop_compose! {
+    /// An Api-dispatching event (the exact `EMERGENCY_EVENT_TYPE`).
+    fn arbitrary_api_event()(
+        uid in "[A-Za-z0-9-]{1,32}",
+        lat in coordinate_strategy(90.0),
+        lon in coordinate_strategy(180.0),
+    ) -> CotEvent {
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type("a-u-emergency-g")
+            .location_with_accuracy(lat, lon, 0.0, 0.0, 0.0)
+            .build()
+    }
+}
+
+prop_compose! {
+    /// A Generic-dispatching event: any type string none of the other
+    /// transformers' markers match, falling through to the catch-all.
+    fn arbitrary_generic_event()(
+        uid in "[A-Za-z0-9-]{1,32}",
+        lat in coordinate_strategy(90.0),
+        lon in coordinate_strategy(180.0),
+        message in message_strategy(),
+    ) -> CotEvent {
+        CotEventBuilder::new()
+            .uid(uid)
+            .event_type("x-custom-type")
+            .location_with_accuracy(lat, lon, 0.0, 0.0, 0.0)
+            .detail(format!("<detail><remarks>{message}</remarks></detail>"))
+            .build()
+    }
+}
+
+proptest! {
+    /// MapItem's flattened fields (`w`, `h`/`i`/`j`/`k`/`l`, `n`/`o`, `e`, ...)
+    /// all survive the full Ditto round trip.
+    #[test]
+    fn map_item_round_trip_preserves_every_flattened_field(event in arbitrary_map_item_event()) {
+        assert_flattened_round_trip_matches(event, 1e-3);
+    }
+
+    /// Chat's flattened fields survive the full Ditto round trip.
+    #[test]
+    fn chat_round_trip_preserves_every_flattened_field(event in arbitrary_chat_event()) {
+        assert_flattened_round_trip_matches(event, 1e-3);
+    }
+
+    /// File's flattened fields survive the full Ditto round trip.
+    #[test]
+    fn file_round_trip_preserves_every_flattened_field(event in arbitrary_file_event()) {
+        assert_flattened_round_trip_matches(event, 1e-3);
+    }
+
+    /// Api's flattened fields survive the full Ditto round trip.
+    #[test]
+    fn api_round_trip_preserves_every_flattened_field(event in arbitrary_api_event()) {
+        assert_flattened_round_trip_matches(event, 1e-3);
+    }
+
+    /// Generic's flattened fields survive the full Ditto round trip.
+    #[test]
+    fn generic_round_trip_preserves_every_flattened_field(event in arbitrary_generic_event()) {
+        assert_flattened_round_trip_matches(event, 1e-3);
+    }
+}
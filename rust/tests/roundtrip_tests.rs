@@ -1,7 +1,7 @@
 //! Round-trip tests for CoT XML parsing and serialization
 
 use chrono::{TimeZone, Utc};
-use ditto_cot::cot_events::CotEvent;
+use ditto_cot::cot_events::{CotEvent, EmergencyType};
 use ditto_cot::ditto::from_ditto::cot_event_from_ditto_document;
 use ditto_cot::ditto::{cot_to_document, CotDocument};
 use ditto_cot::error::CotError;
@@ -10,8 +10,9 @@ use ditto_cot::error::CotError;
 #[test]
 fn test_location_update_roundtrip() -> Result<(), CotError> {
     // Create a location update
-    let mut event =
-        CotEvent::new_location_update("USER-123", "ALPHA-1", "Cyan", 34.12345, -118.12345, 150.0);
+    let mut event = CotEvent::new_location_update(
+        "USER-123", "ALPHA-1", "Cyan", 34.12345, -118.12345, 150.0, "+5m",
+    )?;
 
     // Set specific timestamps for testing
     let test_time = Utc.with_ymd_and_hms(2023, 1, 1, 12, 0, 0).unwrap();
@@ -63,7 +64,9 @@ fn test_chat_message_roundtrip() -> Result<(), CotError> {
         "Test message",
         "All Chat Rooms",
         "All Chat Rooms",
-    );
+        None,
+        "+5m",
+    )?;
 
     // Convert to XML and back
     let xml = event.to_xml()?;
@@ -95,9 +98,10 @@ fn test_emergency_roundtrip() -> Result<(), CotError> {
         "ALPHA-1",
         34.12345,
         -118.12345,
-        "Emergency-911",
+        EmergencyType::Alert911,
         "Need immediate assistance!",
-    );
+        "+5m",
+    )?;
 
     // Convert to XML and back
     let xml = event.to_xml()?;
@@ -3,7 +3,7 @@
 //! This test module validates the serialization, deserialization, and field
 //! handling for all Ditto document types (Api, Chat, File, MapItem, Generic).
 
-use ditto_cot::cot_events::CotEvent;
+use ditto_cot::cot_events::{CotEvent, EmergencyType};
 use ditto_cot::ditto::schema::*;
 use ditto_cot::ditto::{cot_to_document, CotDocument};
 use serde_json::json;
@@ -262,6 +262,7 @@ fn test_generic_document_fallback() {
         v: String::new(),
         w: "x-custom-type".to_string(),
         source: None,
+        _detail_raw: None,
     };
 
     // Generic should handle any event type
@@ -360,11 +361,13 @@ fn test_cot_document_enum_resolution() {
         "ALPHA-1",
         34.12345,
         -118.12345,
-        "Emergency-911",
+        EmergencyType::Alert911,
         "Medical emergency",
-    );
+        "+5m",
+    )
+    .unwrap();
     let emergency_doc = cot_to_document(&emergency_event, "test-peer");
-    // Note: new_emergency creates "b-a-o-can" type, which maps to Generic, not Api
+    // Note: new_emergency creates "b-a-o-pan" type, which maps to Generic, not Api
     assert!(matches!(emergency_doc, CotDocument::Generic(_)));
 
     // Chat event -> Chat document
@@ -374,19 +377,24 @@ fn test_cot_document_enum_resolution() {
         "Hello team",
         "All Chat Rooms",
         "AllChatRooms",
-    );
+        None,
+        "+5m",
+    )
+    .unwrap();
     let chat_doc = cot_to_document(&chat_event, "test-peer");
     assert!(matches!(chat_doc, CotDocument::Chat(_)));
 
     // Location event -> MapItem document
     let location_event =
-        CotEvent::new_location_update("LOC-001", "CHARLIE-3", "Cyan", 35.0, -120.0, 100.0);
+        CotEvent::new_location_update("LOC-001", "CHARLIE-3", "Cyan", 35.0, -120.0, 100.0, "+5m")
+            .unwrap();
     let location_doc = cot_to_document(&location_event, "test-peer");
     assert!(matches!(location_doc, CotDocument::MapItem(_)));
 
     // Unknown type -> Generic document
     let mut generic_event =
-        CotEvent::new_location_update("GENERIC-001", "DELTA-4", "Red", 36.0, -121.0, 50.0);
+        CotEvent::new_location_update("GENERIC-001", "DELTA-4", "Red", 36.0, -121.0, 50.0, "+5m")
+            .unwrap();
     generic_event.event_type = "x-custom-unknown".to_string();
     let generic_doc = cot_to_document(&generic_event, "test-peer");
     assert!(matches!(generic_doc, CotDocument::Generic(_)));
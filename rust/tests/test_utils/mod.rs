@@ -1,6 +1,10 @@
 use anyhow::Result;
 use std::path::Path;
 
+/// Reusable `proptest` generators for epoch-microsecond timestamps and UTC
+/// offsets.
+pub mod timestamp_gen;
+
 /// Loads environment variables from a .env file if it exists, otherwise uses existing environment variables.
 /// This function is designed to be called at the beginning of test functions that require environment variables.
 ///
@@ -0,0 +1,46 @@
+//! Reusable `proptest` generators for epoch-microsecond timestamps and UTC
+//! offsets, shared across the timestamp-conversion round-trip suites —
+//! the same idea as tendermint-rs's `pbt-gen::time` module, but scoped to
+//! this crate's epoch-micros/offset-seconds representation.
+
+use proptest::prelude::*;
+
+/// Epoch micros for historical UTC leap-second insertions (just before, at,
+/// and just after midnight), plus the Unix epoch itself — boundary values a
+/// uniform range would essentially never land on.
+const LEAP_SECOND_BOUNDARY_MICROS: &[f64] = &[
+    1_483_228_799_000_000.0, // 2016-12-31T23:59:59Z
+    1_483_228_800_000_000.0, // 2017-01-01T00:00:00Z
+    0.0,                     // 1970-01-01T00:00:00Z
+];
+
+/// An epoch-microsecond value covering the ranges the current example
+/// ignores: pre-1970 negatives, historical leap-second boundaries, and
+/// arbitrary values on either side of the epoch.
+pub fn epoch_micros_strategy() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        3 => prop::sample::select(LEAP_SECOND_BOUNDARY_MICROS),
+        3 => (-5_000_000_000_000_000i64..0).prop_map(|v| v as f64), // pre-1970
+        10 => (0i64..5_000_000_000_000_000i64).prop_map(|v| v as f64),
+    ]
+}
+
+/// A UTC offset in seconds, covering the full range CoT producers may send
+/// (`-12:00` to `+13:00`, i.e. `±46800`), biased toward the extremes and
+/// zero rather than drawn uniformly.
+pub fn tz_offset_strategy() -> impl Strategy<Value = i32> {
+    prop_oneof![
+        2 => Just(46_800),
+        2 => Just(-46_800),
+        2 => Just(0),
+        10 => -46_800..=46_800,
+    ]
+}
+
+/// A millisecond-scale value for `MapItem.b`, deliberately a different
+/// order of magnitude than the microsecond-scale `n`/`o` values, so a test
+/// asserting `ce` survives untouched also catches a conversion that
+/// accidentally treats `b` as if it shared `n`/`o`'s precision.
+pub fn millis_scale_value_strategy() -> impl Strategy<Value = f64> {
+    (0i64..5_000_000_000_000i64).prop_map(|v| v as f64)
+}
@@ -0,0 +1,91 @@
+//! Property-based round-trip tests for the epoch-microsecond timestamp
+//! conversion between `MapItem` and `FlatCotEvent` (`flat_cot_event_from_ditto`
+//! / `cot_document_from_flat_cot_event`).
+//!
+//! `examples/test_timestamp_fix.rs` checks this by hand for one fixed
+//! value; this suite generalizes it via the shared generators in
+//! `test_utils::timestamp_gen`, covering the edge cases that example
+//! doesn't: pre-1970 negative timestamps, the microsecond-vs-millisecond
+//! mismatch between `b` (copied straight into `ce`) and `n`/`o`, historical
+//! UTC leap-second boundaries, and the full `±46800`s offset range.
+
+mod test_utils;
+
+use ditto_cot::ditto::from_ditto_util::flat_cot_event_from_ditto;
+use ditto_cot::ditto::{
+    cot_document_from_flat_cot_event, CotDocument, CotDocumentKind, MapItem, MapItemRValue,
+};
+use proptest::prelude::*;
+use std::collections::HashMap;
+use test_utils::timestamp_gen::{
+    epoch_micros_strategy, millis_scale_value_strategy, tz_offset_strategy,
+};
+
+fn map_item_with_timestamps(n: f64, o: f64, b: f64, tz_offset_secs: Option<i32>) -> MapItem {
+    let mut r = HashMap::new();
+    if let Some(secs) = tz_offset_secs {
+        r.insert("tz_offset_secs".to_string(), MapItemRValue::Number(secs as f64));
+    }
+
+    MapItem {
+        id: "TS-GEN-1".to_string(),
+        a: "test-peer-key".to_string(),
+        b,
+        c: None,
+        d: "TS-GEN-1".to_string(),
+        d_c: 0,
+        d_r: false,
+        d_v: 2,
+        source: None,
+        e: "ALPHA-1".to_string(),
+        f: Some(true),
+        g: "2.0".to_string(),
+        h: Some(10.0),
+        i: Some(200.0),
+        j: Some(35.0),
+        k: Some(15.0),
+        l: Some(-119.0),
+        n: Some(n),
+        o: Some(o),
+        p: "m-g".to_string(),
+        q: String::new(),
+        r,
+        s: String::new(),
+        t: String::new(),
+        u: String::new(),
+        v: String::new(),
+        w: "a-f-G-U-C".to_string(),
+    }
+}
+
+proptest! {
+    /// `n`/`o`/`b` all survive `doc -> flat -> doc` exactly, regardless of
+    /// sign, magnitude, or an accompanying `tz_offset_secs`.
+    #[test]
+    fn map_item_epoch_fields_survive_the_round_trip(
+        n in epoch_micros_strategy(),
+        o in epoch_micros_strategy(),
+        b in millis_scale_value_strategy(),
+        tz_offset_secs in proptest::option::of(tz_offset_strategy()),
+    ) {
+        let doc = CotDocument::MapItem(map_item_with_timestamps(n, o, b, tz_offset_secs));
+
+        let flat = flat_cot_event_from_ditto(&doc);
+        let rebuilt = cot_document_from_flat_cot_event(&flat, CotDocumentKind::MapItem)
+            .expect("a flat event produced from a real MapItem must rebuild");
+
+        let CotDocument::MapItem(rebuilt_item) = rebuilt else {
+            panic!("rebuilding a MapItem document must produce a MapItem variant");
+        };
+
+        // `b` must ride through untouched in `ce`, even when it's on a
+        // wildly different scale than `n`/`o` — there's no shared
+        // precision to confuse it with.
+        prop_assert_eq!(flat.ce, b);
+        prop_assert_eq!(rebuilt_item.b, b);
+
+        prop_assert_eq!(rebuilt_item.n, Some(n));
+        prop_assert_eq!(rebuilt_item.o, Some(o));
+        prop_assert_eq!(flat.tz_offset_secs, tz_offset_secs);
+    }
+}